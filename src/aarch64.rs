@@ -0,0 +1,192 @@
+use anyhow::Result;
+
+use crate::attributes::{read_string, read_uleb128};
+use crate::consts::{
+    DT_AARCH64_BTI_PLT, DT_AARCH64_MEMTAG_GLOBALS, DT_AARCH64_MEMTAG_GLOBALSSZ,
+    DT_AARCH64_MEMTAG_HEAP, DT_AARCH64_MEMTAG_MODE, DT_AARCH64_MEMTAG_STACK, DT_AARCH64_PAC_PLT,
+    NT_ANDROID_TYPE_MEMTAG, NT_MEMTAG_HEAP, NT_MEMTAG_LEVEL_MASK, NT_MEMTAG_STACK,
+    SHT_AARCH64_ATTRIBUTES,
+};
+use crate::elf::ElfFile;
+use crate::emachine::EMachine;
+use crate::notes::{self, GNU_PROPERTY_AARCH64_FEATURE_1_AND};
+
+const GNU_PROPERTY_AARCH64_FEATURE_1_BTI: u32 = 1;
+const GNU_PROPERTY_AARCH64_FEATURE_1_PAC: u32 = 2;
+const GNU_PROPERTY_AARCH64_FEATURE_1_GCS: u32 = 4;
+
+/// The attributes of the AArch64 build attributes subsections the report
+/// is about, as `(subsection, tag, value)`.
+fn build_attributes(elf_file: &ElfFile) -> Result<Vec<(String, u64, u64)>> {
+    let mut attributes = Vec::new();
+    for section in elf_file.section_headers()? {
+        if section.sh_type != SHT_AARCH64_ATTRIBUTES {
+            continue;
+        }
+        let data = elf_file.section_data(&section)?;
+        if data.first() != Some(&b'A') {
+            continue;
+        }
+
+        // Unlike the generic format, each subsection names itself and says
+        // whether its values are numbers or strings.
+        let mut rest = &data[1..];
+        while rest.len() >= 4 {
+            let length = u32::from_ne_bytes(rest[..4].try_into().unwrap()) as usize;
+            if length < 4 || length > rest.len() {
+                break;
+            }
+            let subsection = &rest[4..length];
+            rest = &rest[length..];
+
+            let mut offset = 0;
+            let Some(name) = read_string(subsection, &mut offset) else {
+                break;
+            };
+            let (Some(_optional), Some(&numeric)) =
+                (subsection.get(offset), subsection.get(offset + 1))
+            else {
+                break;
+            };
+            offset += 2;
+            while offset < subsection.len() {
+                let Some(tag) = read_uleb128(subsection, &mut offset) else {
+                    break;
+                };
+                if numeric == 0 {
+                    let Some(value) = read_uleb128(subsection, &mut offset) else {
+                        break;
+                    };
+                    attributes.push((name.to_string(), tag, value));
+                } else if read_string(subsection, &mut offset).is_none() {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(attributes)
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+/// Summarises the AArch64 hardening features the file was built with:
+/// branch target identification and pointer authentication of return
+/// addresses, from the GNU property note and the build attributes, the
+/// PLT variants that preserve them, and the memory tagging the dynamic
+/// section or the Android note asks for.
+pub fn print_aarch64_report(elf_file: &ElfFile) -> Result<()> {
+    println!("AArch64 hardening:");
+    if elf_file.machine() != EMachine::Aarch64 {
+        println!("  Not an AArch64 file ({}).", elf_file.machine());
+        return Ok(());
+    }
+
+    let features = notes::gnu_property(elf_file, GNU_PROPERTY_AARCH64_FEATURE_1_AND)?;
+    let feature = |bit: u32| match features {
+        Some(bits) => yes_no(bits & bit != 0),
+        None => "no (no GNU property note)",
+    };
+    println!(
+        "  BTI:                 {}",
+        feature(GNU_PROPERTY_AARCH64_FEATURE_1_BTI)
+    );
+    println!(
+        "  PAC-RET:             {}",
+        feature(GNU_PROPERTY_AARCH64_FEATURE_1_PAC)
+    );
+    println!(
+        "  GCS:                 {}",
+        feature(GNU_PROPERTY_AARCH64_FEATURE_1_GCS)
+    );
+
+    let attributes = build_attributes(elf_file)?;
+    let attribute = |subsection: &str, tag: u64| {
+        attributes
+            .iter()
+            .find(|(s, t, _)| s == subsection && *t == tag)
+            .map(|&(_, _, value)| value)
+    };
+    if attributes.is_empty() {
+        println!("  Build attributes:    none");
+    } else {
+        let flag =
+            |tag| attribute("aeabi_feature_and_bits", tag).map_or("unset", |v| yes_no(v != 0));
+        println!(
+            "  Build attributes:    BTI {}, PAC {}, GCS {}",
+            flag(0),
+            flag(1),
+            flag(2)
+        );
+        match (
+            attribute("aeabi_pauthabi", 1),
+            attribute("aeabi_pauthabi", 2),
+        ) {
+            (None, None) => {}
+            (platform, schema) => println!(
+                "  PAuth ABI:           platform {:#x}, schema {}",
+                platform.unwrap_or(0),
+                schema.unwrap_or(0)
+            ),
+        }
+    }
+
+    let dynamic = elf_file.dynamic_entries()?;
+    let value = |tag| dynamic.iter().find(|e| e.d_tag == tag).map(|e| e.d_val);
+    if !dynamic.is_empty() {
+        println!(
+            "  BTI PLT:             {}",
+            yes_no(value(DT_AARCH64_BTI_PLT).is_some())
+        );
+        println!(
+            "  PAC PLT:             {}",
+            yes_no(value(DT_AARCH64_PAC_PLT).is_some())
+        );
+    }
+
+    let android = notes::notes(elf_file)?
+        .into_iter()
+        .find(|n| n.name == "Android" && n.n_type == NT_ANDROID_TYPE_MEMTAG && n.desc.len() >= 4)
+        .map(|n| u32::from_ne_bytes(n.desc[..4].try_into().unwrap()));
+    if let Some(mode) = value(DT_AARCH64_MEMTAG_MODE) {
+        let mut memtag = format!(
+            "{}, heap {}, stack {}",
+            if mode == 0 {
+                "synchronous"
+            } else {
+                "asynchronous"
+            },
+            yes_no(value(DT_AARCH64_MEMTAG_HEAP).is_some_and(|v| v != 0)),
+            yes_no(value(DT_AARCH64_MEMTAG_STACK).is_some_and(|v| v != 0))
+        );
+        if let Some(globals) = value(DT_AARCH64_MEMTAG_GLOBALS) {
+            memtag += &format!(
+                ", globals at {:#x} ({} bytes)",
+                globals,
+                value(DT_AARCH64_MEMTAG_GLOBALSSZ).unwrap_or(0)
+            );
+        }
+        println!(
+            "  Memory tagging:      {} (from the dynamic section)",
+            memtag
+        );
+    } else if let Some(bits) = android {
+        let level = match bits & NT_MEMTAG_LEVEL_MASK {
+            0 => "none",
+            1 => "asynchronous",
+            2 => "synchronous",
+            _ => "unknown level",
+        };
+        println!(
+            "  Memory tagging:      {}, heap {}, stack {} (from the Android note)",
+            level,
+            yes_no(bits & NT_MEMTAG_HEAP != 0),
+            yes_no(bits & NT_MEMTAG_STACK != 0)
+        );
+    } else {
+        println!("  Memory tagging:      not requested");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,458 @@
+//! The public ABI of a shared library, as far as the loader sees it: the
+//! symbols it exports, their versions, and the sizes of its data, which
+//! programs copy-relocate. Function sizes and all addresses change with
+//! every build without changing the ABI, so they are left out, and
+//! everything is sorted so that dumps of two releases diff cleanly.
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Value, json};
+use std::path::Path;
+
+use crate::dynamic::DynamicTag;
+use crate::elf::ElfFile;
+use crate::section::{SHN_ABS, SHN_UNDEF, SectionType};
+use crate::strtab::SymbolTable;
+use crate::symbol::{SymbolBinding, SymbolType, SymbolVisibility};
+use crate::version::{self, VERSYM_HIDDEN, VersionTables};
+
+/// Bumped when the layout of the dump changes.
+const FORMAT: u64 = 1;
+
+/// An exported symbol. `size` is only kept for data, whose size is part of
+/// the ABI.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AbiSymbol {
+    pub name: String,
+    pub version: Option<String>,
+    /// False for the non-default versions kept for old binaries
+    /// (`name@VERSION` rather than `name@@VERSION`).
+    pub default: bool,
+    pub kind: String,
+    pub binding: String,
+    pub visibility: String,
+    pub size: Option<u64>,
+}
+
+/// A version the library defines, with the versions it inherits from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AbiVersion {
+    pub name: String,
+    pub parents: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Abi {
+    pub soname: Option<String>,
+    pub machine: String,
+    pub class: String,
+    pub needed: Vec<String>,
+    pub versions: Vec<AbiVersion>,
+    pub symbols: Vec<AbiSymbol>,
+}
+
+fn kind_name(kind: SymbolType) -> String {
+    match kind {
+        SymbolType::FUNC => "function".to_string(),
+        SymbolType::GNU_IFUNC => "ifunc".to_string(),
+        SymbolType::OBJECT => "object".to_string(),
+        SymbolType::TLS => "tls".to_string(),
+        SymbolType::COMMON => "common".to_string(),
+        SymbolType::NOTYPE => "notype".to_string(),
+        other => other.to_string().to_ascii_lowercase(),
+    }
+}
+
+impl Abi {
+    /// Reads the ABI from the dynamic symbol table and version sections.
+    pub fn extract(elf_file: &ElfFile) -> Result<Abi> {
+        let sections = elf_file.section_headers_or_dynamic()?;
+        let mut abi = Abi {
+            soname: elf_file
+                .dynamic_strings(DynamicTag::SONAME)?
+                .first()
+                .map(|s| s.to_string()),
+            machine: elf_file.machine().to_string(),
+            class: if elf_file.is_64() { "ELF64" } else { "ELF32" }.to_string(),
+            needed: elf_file
+                .dynamic_strings(DynamicTag::NEEDED)?
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            ..Abi::default()
+        };
+        abi.needed.sort();
+
+        for definition in version::definitions(elf_file, &sections)? {
+            if definition.flags & version::VER_FLG_BASE != 0 {
+                continue;
+            }
+            let mut names = definition.names.into_iter();
+            if let Some(name) = names.next() {
+                abi.versions.push(AbiVersion {
+                    name,
+                    parents: names.collect(),
+                });
+            }
+        }
+        abi.versions.sort();
+
+        let Some(dynsym) = sections.iter().find(|s| s.sh_type == SectionType::DYNSYM) else {
+            return Ok(abi);
+        };
+        let symbols = SymbolTable::new(elf_file, &sections, dynsym)?;
+        let versions = VersionTables::new(elf_file, &sections)?;
+        for (index, symbol) in symbols.iter().enumerate() {
+            let name = symbols.name(index)?;
+            let exported = matches!(
+                symbol.binding(),
+                SymbolBinding::GLOBAL | SymbolBinding::WEAK | SymbolBinding::GNU_UNIQUE
+            ) && matches!(
+                symbol.visibility(),
+                SymbolVisibility::DEFAULT | SymbolVisibility::PROTECTED
+            );
+            if !exported || name.is_empty() || symbol.st_shndx == SHN_UNDEF {
+                continue;
+            }
+            let version = versions.version(index).filter(|v| v.file.is_none());
+            // Each version definition has an absolute symbol of its name.
+            if symbol.st_shndx == SHN_ABS && version.is_some_and(|v| v.name == name) {
+                continue;
+            }
+            let kind = symbol.symbol_type();
+            let data = matches!(
+                kind,
+                SymbolType::OBJECT | SymbolType::TLS | SymbolType::COMMON
+            );
+            abi.symbols.push(AbiSymbol {
+                name: name.to_string(),
+                version: version.map(|v| v.name.clone()),
+                default: versions
+                    .versym(index)
+                    .is_none_or(|versym| versym & VERSYM_HIDDEN == 0),
+                kind: kind_name(kind),
+                binding: symbol.binding().to_string(),
+                visibility: symbol.visibility().to_string(),
+                size: data.then_some(symbol.st_size),
+            });
+        }
+        abi.symbols.sort();
+        Ok(abi)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let symbols: Vec<Value> = self
+            .symbols
+            .iter()
+            .map(|s| {
+                let mut symbol = json!({
+                    "name": s.name,
+                    "version": s.version,
+                    "default_version": s.default,
+                    "kind": s.kind,
+                    "binding": s.binding,
+                    "visibility": s.visibility,
+                });
+                if let Some(size) = s.size {
+                    symbol["size"] = json!(size);
+                }
+                symbol
+            })
+            .collect();
+        let versions: Vec<Value> = self
+            .versions
+            .iter()
+            .map(|v| json!({ "name": v.name, "parents": v.parents }))
+            .collect();
+        json!({
+            "format": FORMAT,
+            "soname": self.soname,
+            "machine": self.machine,
+            "class": self.class,
+            "needed": self.needed,
+            "versions": versions,
+            "symbols": symbols,
+        })
+    }
+}
+
+/// Prints the ABI of the file as JSON, one key per line, in a stable order.
+pub fn print_abi_dump(elf_file: &ElfFile) -> Result<()> {
+    let abi = Abi::extract(elf_file)?;
+    println!("{}", serde_json::to_string_pretty(&abi.to_json())?);
+    Ok(())
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn strings(value: &Value, key: &str) -> Vec<String> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str().map(str::to_string))
+        .collect()
+}
+
+impl Abi {
+    /// Reads back a dump printed by `--abi-dump`.
+    pub fn from_json(dump: &Value) -> Result<Abi> {
+        match dump.get("format").and_then(Value::as_u64) {
+            Some(FORMAT) => {}
+            Some(format) => bail!("ABI dump format {} is not supported", format),
+            None => bail!("Not an ABI dump from --abi-dump"),
+        }
+        let items = |key: &str| {
+            dump.get(key)
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default()
+        };
+        Ok(Abi {
+            soname: string(dump, "soname"),
+            machine: string(dump, "machine").unwrap_or_default(),
+            class: string(dump, "class").unwrap_or_default(),
+            needed: strings(dump, "needed"),
+            versions: items("versions")
+                .iter()
+                .map(|v| AbiVersion {
+                    name: string(v, "name").unwrap_or_default(),
+                    parents: strings(v, "parents"),
+                })
+                .collect(),
+            symbols: items("symbols")
+                .iter()
+                .map(|s| AbiSymbol {
+                    name: string(s, "name").unwrap_or_default(),
+                    version: string(s, "version"),
+                    default: s
+                        .get("default_version")
+                        .and_then(Value::as_bool)
+                        .unwrap_or(true),
+                    kind: string(s, "kind").unwrap_or_default(),
+                    binding: string(s, "binding").unwrap_or_default(),
+                    visibility: string(s, "visibility").unwrap_or_default(),
+                    size: s.get("size").and_then(Value::as_u64),
+                })
+                .collect(),
+        })
+    }
+
+    /// The ABI of a shared library, or of a dump saved from one.
+    pub fn load(path: &Path) -> Result<Abi> {
+        let data =
+            std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        if data.starts_with(b"\x7fELF") {
+            return Abi::extract(&ElfFile::open(path)?);
+        }
+        let dump: Value = serde_json::from_slice(&data).with_context(|| {
+            format!("{} is neither an ELF file nor an ABI dump", path.display())
+        })?;
+        Abi::from_json(&dump).with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
+
+impl AbiSymbol {
+    fn display(&self) -> String {
+        match (&self.version, self.default) {
+            (Some(version), true) => format!("{}@@{}", self.name, version),
+            (Some(version), false) => format!("{}@{}", self.name, version),
+            (None, _) => self.name.clone(),
+        }
+    }
+}
+
+/// The changes between two ABIs, split by whether programs built against
+/// the old one keep working with the new one.
+#[derive(Default)]
+struct Changes {
+    incompatible: Vec<String>,
+    compatible: Vec<String>,
+}
+
+fn compare_symbol(old: &AbiSymbol, new: &AbiSymbol, changes: &mut Changes) {
+    let name = old.display();
+    let data = |kind: &str| matches!(kind, "object" | "tls" | "common");
+    if old.kind != new.kind {
+        let callable = |kind: &str| matches!(kind, "function" | "ifunc");
+        let message = format!("{}: {} became {}", name, old.kind, new.kind);
+        if callable(&old.kind) && callable(&new.kind) {
+            changes.compatible.push(message);
+        } else {
+            changes.incompatible.push(message);
+        }
+    }
+    // Programs hold copies of the library's data, made as large as it was
+    // when they were linked.
+    if let (Some(old_size), Some(new_size)) = (old.size, new.size)
+        && old_size != new_size
+    {
+        changes.incompatible.push(format!(
+            "{}: size changed from {} to {} bytes",
+            name, old_size, new_size
+        ));
+    }
+    if old.binding != new.binding {
+        changes.compatible.push(format!(
+            "{}: binding changed from {} to {}",
+            name, old.binding, new.binding
+        ));
+    }
+    if old.visibility != new.visibility {
+        let message = format!(
+            "{}: visibility changed from {} to {}",
+            name, old.visibility, new.visibility
+        );
+        // Copy relocations cannot refer to protected data.
+        if data(&new.kind) && new.visibility == "PROTECTED" {
+            changes.incompatible.push(message);
+        } else {
+            changes.compatible.push(message);
+        }
+    }
+    if old.default && !new.default {
+        changes.compatible.push(format!(
+            "{}: no longer the default version, so new programs cannot link to it",
+            name
+        ));
+    }
+}
+
+fn compare(old: &Abi, new: &Abi) -> Changes {
+    let mut changes = Changes::default();
+    if old.machine != new.machine || old.class != new.class {
+        changes.incompatible.push(format!(
+            "Built for {} {} instead of {} {}",
+            new.class, new.machine, old.class, old.machine
+        ));
+    }
+    if old.soname != new.soname {
+        changes.incompatible.push(format!(
+            "SONAME changed from {} to {}: programs look for the old name",
+            old.soname.as_deref().unwrap_or("none"),
+            new.soname.as_deref().unwrap_or("none")
+        ));
+    }
+    for version in &old.versions {
+        if !new.versions.iter().any(|v| v.name == version.name) {
+            changes.incompatible.push(format!(
+                "Version {} was removed: programs that need it fail to load",
+                version.name
+            ));
+        }
+    }
+    for version in &new.versions {
+        if !old.versions.iter().any(|v| v.name == version.name) {
+            changes
+                .compatible
+                .push(format!("Version {} was added", version.name));
+        }
+    }
+
+    let find = |abi: &'_ Abi, symbol: &AbiSymbol| {
+        abi.symbols
+            .iter()
+            .find(|s| s.name == symbol.name && s.version == symbol.version)
+            .cloned()
+    };
+    for symbol in &old.symbols {
+        if let Some(new_symbol) = find(new, symbol) {
+            compare_symbol(symbol, &new_symbol, &mut changes);
+            continue;
+        }
+        let default = new
+            .symbols
+            .iter()
+            .find(|s| s.name == symbol.name && s.default);
+        match (&symbol.version, default) {
+            // References made without a version bind to the default one.
+            (None, Some(default)) => {
+                changes.compatible.push(format!(
+                    "{}: now versioned as {}",
+                    symbol.name,
+                    default.display()
+                ));
+                compare_symbol(symbol, default, &mut changes);
+            }
+            (Some(_), Some(default)) => changes.incompatible.push(format!(
+                "{} was removed ({} remains, but programs bound to the old version fail to load)",
+                symbol.display(),
+                default.display()
+            )),
+            _ => changes.incompatible.push(format!(
+                "{} {} was removed",
+                symbol.kind,
+                symbol.display()
+            )),
+        }
+    }
+    for symbol in &new.symbols {
+        // A name that only gained or lost its version is not new.
+        let known = find(old, symbol).is_some()
+            || old.symbols.iter().any(|s| {
+                s.name == symbol.name
+                    && (symbol.version.is_none() || (symbol.default && s.version.is_none()))
+            });
+        if !known {
+            changes
+                .compatible
+                .push(format!("{} {} was added", symbol.kind, symbol.display()));
+        }
+    }
+
+    for needed in &new.needed {
+        if !old.needed.contains(needed) {
+            changes.compatible.push(format!("Now needs {}", needed));
+        }
+    }
+    for needed in &old.needed {
+        if !new.needed.contains(needed) {
+            changes
+                .compatible
+                .push(format!("No longer needs {}", needed));
+        }
+    }
+    changes
+}
+
+/// Compares the ABIs of two releases of a shared library, each given as
+/// the library or as a saved `--abi-dump`. Returns whether programs built
+/// against the old one still work with the new one.
+pub fn print_abi_comparison(old_path: &Path, new_path: &Path) -> Result<bool> {
+    let old = Abi::load(old_path)?;
+    let new = Abi::load(new_path)?;
+    let changes = compare(&old, &new);
+
+    println!(
+        "ABI comparison of {} (old) and {} (new):",
+        old_path.display(),
+        new_path.display()
+    );
+    for (title, list) in [
+        ("Incompatible changes", &changes.incompatible),
+        ("Compatible changes", &changes.compatible),
+    ] {
+        if list.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{} ({}):", title, list.len());
+        for change in list {
+            println!("  {}", change);
+        }
+    }
+
+    println!();
+    if !changes.incompatible.is_empty() {
+        println!(
+            "Verdict: incompatible: programs built against the old library may fail to load or misbehave with the new one"
+        );
+    } else if !changes.compatible.is_empty() {
+        println!("Verdict: compatible: programs built against the old library keep working");
+    } else {
+        println!("Verdict: no ABI changes");
+    }
+    Ok(changes.incompatible.is_empty())
+}
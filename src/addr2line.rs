@@ -0,0 +1,194 @@
+use anyhow::{Context, Result};
+
+use crate::dwarf::{self, LineTable, Reader};
+use crate::elf::ElfFile;
+
+/// A function in the chain of inlined calls at an address, with the place
+/// its caller called it from when it was inlined.
+#[derive(Clone)]
+struct Frame {
+    name: String,
+    call: Option<(String, u32)>,
+}
+
+/// Parses an address the way addr2line does: hexadecimal, with or without
+/// the `0x`.
+pub fn parse_address(value: &str) -> Result<u64> {
+    let digits = value.trim();
+    let digits = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+        .unwrap_or(digits);
+    u64::from_str_radix(digits, 16).with_context(|| format!("Invalid address {:?}", value))
+}
+
+/// The name of a function DIE, looking through the abstract instance or
+/// declaration it completes when it has none of its own.
+fn die_name<'a>(
+    dwarf: &gimli::Dwarf<Reader<'a>>,
+    unit: &gimli::Unit<Reader<'a>>,
+    entry: &gimli::DebuggingInformationEntry<Reader<'a>>,
+) -> Result<Option<String>> {
+    let mut entry = entry.clone();
+    // Bounded, in case of malformed references that go round in circles.
+    for _ in 0..8 {
+        if let Some(name) = entry.attr_value(gimli::DW_AT_name) {
+            return Ok(Some(
+                dwarf
+                    .attr_string(unit, name)?
+                    .to_string_lossy()
+                    .into_owned(),
+            ));
+        }
+        let origin = [gimli::DW_AT_abstract_origin, gimli::DW_AT_specification]
+            .into_iter()
+            .find_map(|attr| match entry.attr_value(attr) {
+                Some(gimli::AttributeValue::UnitRef(offset)) => Some(offset),
+                _ => None,
+            });
+        match origin {
+            Some(offset) => entry = unit.entry(offset)?,
+            None => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+/// Whether a DIE's address ranges contain `address`.
+fn contains<'a>(
+    dwarf: &gimli::Dwarf<Reader<'a>>,
+    unit: &gimli::Unit<Reader<'a>>,
+    entry: &gimli::DebuggingInformationEntry<Reader<'a>>,
+    address: u64,
+) -> Result<bool> {
+    let mut ranges = dwarf.die_ranges(unit, entry)?;
+    while let Some(range) = ranges.next()? {
+        if (range.begin..range.end).contains(&address) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Where an inlined call was made from, from `DW_AT_call_file` and
+/// `DW_AT_call_line`.
+fn call_site<'a>(
+    dwarf: &gimli::Dwarf<Reader<'a>>,
+    unit: &gimli::Unit<Reader<'a>>,
+    entry: &gimli::DebuggingInformationEntry<Reader<'a>>,
+    deterministic: bool,
+) -> Result<Option<(String, u32)>> {
+    let Some(line) = entry
+        .attr_value(gimli::DW_AT_call_line)
+        .and_then(|v| v.udata_value())
+    else {
+        return Ok(None);
+    };
+    let comp_dir = unit
+        .comp_dir
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let file = match (entry.attr_value(gimli::DW_AT_call_file), &unit.line_program) {
+        (Some(gimli::AttributeValue::FileIndex(index)), Some(program)) => {
+            match program.header().file(index) {
+                Some(file) => dwarf::file_path(
+                    dwarf,
+                    unit,
+                    &comp_dir,
+                    program.header(),
+                    file,
+                    deterministic,
+                )?,
+                None => "??".to_string(),
+            }
+        }
+        _ => "??".to_string(),
+    };
+    Ok(Some((file, line as u32)))
+}
+
+/// The function containing `address` followed by the functions inlined
+/// into it, down to the one the address is in, from the subprogram and
+/// `DW_TAG_inlined_subroutine` DIEs whose ranges contain it.
+fn inline_chain(
+    dwarf: &gimli::Dwarf<Reader>,
+    address: u64,
+    deterministic: bool,
+) -> Result<Vec<Frame>> {
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+        let mut ranges = dwarf.unit_ranges(&unit)?;
+        let mut in_unit = false;
+        while let Some(range) = ranges.next()? {
+            in_unit |= (range.begin..range.end).contains(&address);
+        }
+        if !in_unit {
+            continue;
+        }
+
+        // DIEs come depth first, so the chain is cut back to the depth of
+        // each one before it is considered.
+        let mut chain: Vec<(isize, Frame)> = Vec::new();
+        let mut deepest = Vec::new();
+        let mut entries = unit.entries();
+        while let Some(entry) = entries.next_dfs()? {
+            let tag = entry.tag();
+            if tag != gimli::DW_TAG_subprogram && tag != gimli::DW_TAG_inlined_subroutine {
+                continue;
+            }
+            chain.retain(|(depth, _)| *depth < entry.depth());
+            if !contains(dwarf, &unit, entry, address)? {
+                continue;
+            }
+            let frame = Frame {
+                name: die_name(dwarf, &unit, entry)?.unwrap_or_else(|| "??".to_string()),
+                call: call_site(dwarf, &unit, entry, deterministic)?,
+            };
+            chain.push((entry.depth(), frame));
+            deepest = chain.iter().map(|(_, f)| f.clone()).collect();
+        }
+        if !deepest.is_empty() {
+            return Ok(deepest);
+        }
+    }
+    Ok(Vec::new())
+}
+
+/// Prints the function and source location of each address like
+/// `addr2line -f -i -p` does: the function the address is in, then each
+/// function it was inlined into, with the line of the inlined call. With
+/// `deterministic`, the source paths leave out where the code was built.
+pub fn print_addr2line(elf_file: &ElfFile, addresses: &[u64], deterministic: bool) -> Result<()> {
+    let Some(dwarf) = dwarf::load(elf_file)? else {
+        println!("There is no DWARF debug info in this file.");
+        return Ok(());
+    };
+    let lines = LineTable::new(&dwarf, deterministic)?;
+
+    for &address in addresses {
+        let chain = inline_chain(&dwarf, address, deterministic)?;
+        let location = lines.location(address).map_or_else(
+            || "??:0".to_string(),
+            |(file, line)| format!("{}:{}", file, line),
+        );
+        let address = if elf_file.is_64() {
+            format!("{:#018x}", address)
+        } else {
+            format!("{:#010x}", address)
+        };
+        let innermost = chain.last().map_or("??", |f| f.name.as_str());
+        println!("{}: {} at {}", address, innermost, location);
+
+        // Each caller is shown at the call its callee was inlined for.
+        for pair in chain.windows(2).rev() {
+            let (file, line) = pair[1]
+                .call
+                .clone()
+                .unwrap_or_else(|| ("??".to_string(), 0));
+            println!(" (inlined by) {} at {}:{}", pair[0].name, file, line);
+        }
+    }
+
+    Ok(())
+}
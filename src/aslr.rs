@@ -0,0 +1,192 @@
+use anyhow::Result;
+
+use crate::dynamic::DynamicTag;
+use crate::elf::{ElfFile, ElfType};
+use crate::relocation::{self, Relocation};
+use crate::section::SectionType;
+use crate::segment::{ProgramHeader, SegmentType};
+
+/// How many text relocations to show before only counting them.
+const EXAMPLES: usize = 5;
+
+/// A dynamic relocation that patches a segment the loader maps read-only.
+struct TextRelocation {
+    offset: u64,
+    r_type: Option<u32>,
+}
+
+fn text_relocations(elf_file: &ElfFile, loads: &[&ProgramHeader]) -> Result<Vec<TextRelocation>> {
+    let read_only = |offset: u64| {
+        loads
+            .iter()
+            .any(|s| !s.is_writable() && offset >= s.p_vaddr && offset - s.p_vaddr < s.p_memsz)
+    };
+
+    let mut found = Vec::new();
+    for section in &elf_file.section_headers()? {
+        if !section.is_alloc() {
+            continue;
+        }
+        if ElfFile::is_relocation_section(section) {
+            let relocations: Vec<Relocation> = elf_file.decoded_relocations(section)?;
+            found.extend(
+                relocations
+                    .iter()
+                    .filter(|r| read_only(r.r_offset))
+                    .map(|r| TextRelocation {
+                        offset: r.r_offset,
+                        r_type: Some(r.r_type),
+                    }),
+            );
+        } else if matches!(
+            section.sh_type,
+            SectionType::RELR | SectionType::ANDROID_RELR
+        ) {
+            found.extend(
+                elf_file
+                    .relr_addresses(section)?
+                    .into_iter()
+                    .filter(|&offset| read_only(offset))
+                    .map(|offset| TextRelocation {
+                        offset,
+                        r_type: None,
+                    }),
+            );
+        }
+    }
+    Ok(found)
+}
+
+/// Summarizes how much of the image address space layout randomization can
+/// move: whether the file is position-independent, whether it patches its
+/// read-only segments at load time, and whether its segments ask for fixed
+/// addresses.
+pub fn print_aslr(elf_file: &ElfFile) -> Result<()> {
+    println!("ASLR compatibility:");
+    let elf_type = elf_file.elf_type();
+    if elf_type != ElfType::EXEC && elf_type != ElfType::DYN {
+        println!(
+            "  Not applicable: {} files are not loaded as a whole.",
+            elf_file.type_description()
+        );
+        return Ok(());
+    }
+
+    let segments = elf_file.program_headers()?;
+    let mut loads: Vec<&ProgramHeader> = segments
+        .iter()
+        .filter(|s| s.p_type == SegmentType::LOAD)
+        .collect();
+    loads.sort_by_key(|s| s.p_vaddr);
+    let Some(first) = loads.first() else {
+        println!("  Not applicable: there are no loadable segments.");
+        return Ok(());
+    };
+    let end = loads
+        .iter()
+        .map(|s| s.p_vaddr.saturating_add(s.p_memsz))
+        .max()
+        .unwrap_or(first.p_vaddr);
+
+    let fixed = elf_type == ElfType::EXEC;
+    if fixed {
+        println!(
+            "  Type:              {}: not position-independent, so it runs at the addresses it was linked at",
+            elf_file.type_description()
+        );
+    } else {
+        println!(
+            "  Type:              {}: position-independent, loaded at a base the loader picks",
+            elf_file.type_description()
+        );
+    }
+
+    if fixed {
+        println!(
+            "  Fixed addresses:   {} PT_LOAD segments at {:#x}-{:#x}; only the stack, heap, vDSO and shared libraries move",
+            loads.len(),
+            first.p_vaddr,
+            end
+        );
+    } else if first.p_vaddr != 0 {
+        println!(
+            "  Fixed addresses:   none, but the first PT_LOAD asks for a base of {:#x} (prelinked, or linked with -Ttext-segment)",
+            first.p_vaddr
+        );
+    } else {
+        println!("  Fixed addresses:   none (the first PT_LOAD is at 0)");
+    }
+    let prelinked = elf_file
+        .dynamic_entries()?
+        .iter()
+        .any(|e| e.d_tag == DynamicTag::GNU_PRELINKED);
+    if prelinked {
+        println!(
+            "  Prelinked:         yes (DT_GNU_PRELINKED): its libraries were assigned fixed bases by prelink"
+        );
+    }
+
+    let text = text_relocations(elf_file, &loads)?;
+    let sections = elf_file.section_headers()?;
+    if text.is_empty() {
+        println!(
+            "  Text relocations:  none: every dynamic relocation patches a writable (or RELRO) segment"
+        );
+    } else {
+        let absolute = text
+            .iter()
+            .filter(|r| {
+                r.r_type
+                    .is_some_and(|t| !relocation::is_relative(elf_file, t))
+            })
+            .count();
+        println!(
+            "  Text relocations:  {} dynamic relocations patch read-only segments ({} absolute), which must be made writable and cannot be shared",
+            text.len(),
+            absolute
+        );
+        for reloc in text.iter().take(EXAMPLES) {
+            let section = sections
+                .iter()
+                .find(|s| s.contains_addr(reloc.offset))
+                .and_then(|s| elf_file.section_name(&sections, s).ok())
+                .unwrap_or("?");
+            let r_type = reloc.r_type.map_or_else(
+                || "RELR".to_string(),
+                |t| relocation::type_display(elf_file, t),
+            );
+            println!(
+                "                     {} at {:#x} in {}",
+                r_type, reloc.offset, section
+            );
+        }
+        if text.len() > EXAMPLES {
+            println!("                     and {} more", text.len() - EXAMPLES);
+        }
+    }
+
+    // Loaders align the base to the largest p_align, so alignments above
+    // the page size leave fewer bases to choose from.
+    let align = loads.iter().map(|s| s.p_align).max().unwrap_or(0);
+    if align > 0x1000 && align.is_power_of_two() {
+        println!(
+            "  Load alignment:    {:#x}: {} bits less base randomization than with 4 KiB pages",
+            align,
+            align.trailing_zeros() - 12
+        );
+    } else {
+        println!("  Load alignment:    {:#x}", align);
+    }
+
+    let verdict = if fixed {
+        "partial: the executable's code and data are at fixed addresses; rebuild with -fPIE -pie"
+    } else if !text.is_empty() {
+        "degraded: randomized, but text relocations leave code pages writable while it loads; rebuild the objects with -fPIC"
+    } else if prelinked {
+        "degraded: randomized unless the loader honors the prelinked bases"
+    } else {
+        "full: the whole image can be loaded at a random base"
+    };
+    println!("  Verdict:           {}", verdict);
+    Ok(())
+}
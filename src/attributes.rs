@@ -0,0 +1,347 @@
+use anyhow::Result;
+
+use crate::elf::ElfFile;
+use crate::emachine::EMachine;
+use crate::section::SectionType;
+
+const TAG_FILE: u8 = 1;
+const TAG_SECTION: u8 = 2;
+const TAG_SYMBOL: u8 = 3;
+
+const TAG_COMPATIBILITY: u64 = 32;
+
+/// One vendor's part of a build attributes section. `subsections` holds
+/// the scope tag of each subsection (file, section or symbol) with the
+/// bytes that follow its length.
+#[derive(Debug)]
+pub struct VendorAttributes<'a> {
+    pub vendor: &'a str,
+    pub subsections: Vec<(u8, &'a [u8])>,
+}
+
+/// Splits a build attributes section, in the format shared by
+/// `.gnu.attributes` and the processor-specific `.ARM.attributes` and
+/// `.riscv.attributes`, into its vendor sections. What cannot be parsed
+/// ends the list with a description of the problem.
+pub fn parse_attributes(data: &[u8]) -> (Vec<VendorAttributes<'_>>, Option<String>) {
+    let mut vendors = Vec::new();
+    match data.first() {
+        Some(b'A') => {}
+        Some(&version) => {
+            return (
+                vendors,
+                Some(format!(
+                    "Unknown attributes version '{}'({}) - expecting 'A'",
+                    version as char, version
+                )),
+            );
+        }
+        None => return (vendors, None),
+    }
+
+    let mut rest = &data[1..];
+    while rest.len() >= 4 {
+        let mut length = u32::from_ne_bytes(rest[..4].try_into().unwrap()) as usize;
+        if length > rest.len() {
+            return (vendors, Some("Tag section ends prematurely".to_string()));
+        }
+        if length < 4 {
+            return (
+                vendors,
+                Some(format!("Bad section length ({} < 4)", length)),
+            );
+        }
+        let (section, next) = rest.split_at(length);
+        rest = next;
+        length -= 4;
+
+        let body = &section[4..];
+        let Some(name_len) = body.iter().position(|&b| b == 0).filter(|n| n + 1 < length) else {
+            return (vendors, Some("Corrupt attribute section name".to_string()));
+        };
+        let vendor = std::str::from_utf8(&body[..name_len]).unwrap_or("<corrupt>");
+        let mut subsections = Vec::new();
+        let mut body = &body[name_len + 1..];
+        while !body.is_empty() {
+            if body.len() < 5 {
+                return (
+                    vendors,
+                    Some("Attribute subsection ends prematurely".to_string()),
+                );
+            }
+            let tag = body[0];
+            let size = u32::from_ne_bytes(body[1..5].try_into().unwrap()) as usize;
+            if size < 6 {
+                return (
+                    vendors,
+                    Some(format!("Bad subsection length ({} < 6)", size)),
+                );
+            }
+            let size = size.min(body.len());
+            subsections.push((tag, &body[5..size]));
+            body = &body[size..];
+        }
+        vendors.push(VendorAttributes {
+            vendor,
+            subsections,
+        });
+    }
+
+    (vendors, None)
+}
+
+pub fn read_uleb128(data: &[u8], offset: &mut usize) -> Option<u64> {
+    let mut result = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*offset)?;
+        *offset += 1;
+        if shift < 64 {
+            result |= ((byte & 0x7f) as u64) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+    }
+}
+
+pub fn read_string<'a>(data: &'a [u8], offset: &mut usize) -> Option<&'a str> {
+    let bytes = data.get(*offset..)?;
+    let len = bytes.iter().position(|&b| b == 0)?;
+    *offset += len + 1;
+    std::str::from_utf8(&bytes[..len]).ok()
+}
+
+/// Names the value of a GNU attribute that only means something to one
+/// processor. Returns the tag name and the value description. Values out of
+/// the range a field defines are shown in hex before the decoding of the
+/// bits that are defined, as readelf does.
+fn processor_attribute(machine: EMachine, tag: u64, value: u64) -> Option<(&'static str, String)> {
+    let flagged = |limit: u64, text: &str| {
+        if value > limit {
+            format!("({:#x}), {}", value, text)
+        } else {
+            text.to_string()
+        }
+    };
+
+    match (machine, tag) {
+        (EMachine::Ppc | EMachine::Ppc64, 4) => {
+            let float = match value & 3 {
+                0 => "unspecified hard/soft float",
+                1 => "hard float",
+                2 => "soft float",
+                _ => "single-precision hard float",
+            };
+            let long_double = match value & 0xc {
+                0 => "unspecified long double",
+                4 => "128-bit IBM long double",
+                8 => "64-bit long double",
+                _ => "128-bit IEEE long double",
+            };
+            Some((
+                "Tag_GNU_Power_ABI_FP",
+                flagged(15, &format!("{}, {}", float, long_double)),
+            ))
+        }
+        (EMachine::Ppc | EMachine::Ppc64, 8) => {
+            let vector = match value & 3 {
+                0 => "unspecified",
+                1 => "generic",
+                2 => "AltiVec",
+                _ => "SPE",
+            };
+            Some(("Tag_GNU_Power_ABI_Vector", flagged(3, vector)))
+        }
+        (EMachine::Ppc | EMachine::Ppc64, 12) => {
+            let convention = match value & 3 {
+                0 => "unspecified",
+                1 => "r3/r4",
+                2 => "memory",
+                _ => "???",
+            };
+            Some(("Tag_GNU_Power_ABI_Struct_Return", flagged(2, convention)))
+        }
+        (EMachine::Mips | EMachine::MipsRs3Le, 4) => {
+            let abi = match value {
+                0 => "Hard or soft float".to_string(),
+                1 => "Hard float (double precision)".to_string(),
+                2 => "Hard float (single precision)".to_string(),
+                3 => "Soft float".to_string(),
+                4 => "Hard float (MIPS32r2 64-bit FPU 12 callee-saved)".to_string(),
+                5 => "Hard float (32-bit CPU, Any FPU)".to_string(),
+                6 => "Hard float (32-bit CPU, 64-bit FPU)".to_string(),
+                7 => "Hard float compat (32-bit CPU, 64-bit FPU)".to_string(),
+                8 => "NaN 2008 compatibility".to_string(),
+                _ => format!("??? ({})", value),
+            };
+            Some(("Tag_GNU_MIPS_ABI_FP", abi))
+        }
+        (EMachine::Mips | EMachine::MipsRs3Le, 8) => {
+            let msa = match value {
+                0 => "Any MSA or not".to_string(),
+                1 => "128-bit MSA".to_string(),
+                _ => format!("??? ({})", value),
+            };
+            Some(("Tag_GNU_MIPS_ABI_MSA", msa))
+        }
+        (EMachine::S390, 8) => {
+            let vector = match value {
+                0 => "any".to_string(),
+                1 => "software".to_string(),
+                2 => "hardware".to_string(),
+                _ => format!("??? ({})", value),
+            };
+            Some(("Tag_GNU_S390_ABI_Vector", vector))
+        }
+        (EMachine::M68K, 4) => {
+            let abi = match value {
+                0 => "Hard or soft float".to_string(),
+                1 => "Hard float".to_string(),
+                2 => "Soft float".to_string(),
+                _ => format!("??? ({})", value),
+            };
+            Some(("Tag_GNU_M68K_ABI_FP", abi))
+        }
+        _ => None,
+    }
+}
+
+/// Describes the attributes of one subsection of the "gnu" vendor
+/// section. Tags with bit 1 clear belong to the processor; the others are
+/// generic, and follow the rule that odd tags have a string value and even
+/// tags a number.
+fn gnu_attributes(machine: EMachine, data: &[u8]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let Some(tag) = read_uleb128(data, &mut offset) else {
+            lines.push("  <corrupt tag>".to_string());
+            break;
+        };
+
+        if tag == TAG_COMPATIBILITY {
+            let flag = read_uleb128(data, &mut offset);
+            let vendor = read_string(data, &mut offset);
+            match (flag, vendor) {
+                (Some(flag), Some(vendor)) => lines.push(format!(
+                    "  Tag_compatibility: flag = {}, vendor = {}",
+                    flag, vendor
+                )),
+                _ => {
+                    lines.push("  Tag_compatibility: <corrupt>".to_string());
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if tag & 1 != 0 {
+            let Some(value) = read_string(data, &mut offset) else {
+                lines.push(format!("  Tag_unknown_{}: <corrupt string tag>", tag));
+                break;
+            };
+            lines.push(format!("  Tag_unknown_{}: \"{}\"", tag, value));
+            continue;
+        }
+
+        let Some(value) = read_uleb128(data, &mut offset) else {
+            lines.push(format!("  Tag_unknown_{}: <corrupt>", tag));
+            break;
+        };
+        match processor_attribute(machine, tag, value).filter(|_| tag & 2 == 0) {
+            Some((name, text)) => lines.push(format!("  {}: {}", name, text)),
+            None => lines.push(format!("  Tag_unknown_{}: {} ({:#x})", tag, value, value)),
+        }
+    }
+    lines
+}
+
+/// Dumps bytes the way readelf shows attributes it cannot decode.
+fn raw_attributes(data: &[u8]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (i, chunk) in data.chunks(16).enumerate() {
+        let mut line = format!("  0x{:08x} ", i * 16);
+        for j in 0..16 {
+            match chunk.get(j) {
+                Some(b) => line += &format!("{:02x}", b),
+                None => line += "  ",
+            }
+            if j % 4 == 3 {
+                line.push(' ');
+            }
+        }
+        line.extend(chunk.iter().map(|&b| {
+            if (b' '..0x7f).contains(&b) {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        lines.push(line);
+    }
+    lines.push(String::new());
+    lines
+}
+
+/// Prints the `.gnu.attributes` sections in the format of `readelf -A`.
+/// The attributes of the "gnu" vendor are decoded, including the ones
+/// PowerPC, MIPS, s390 and m68k define for their floating point and
+/// vector ABIs; other vendors' attributes are dumped as bytes.
+pub fn print_attributes(elf_file: &ElfFile) -> Result<()> {
+    let machine = elf_file.machine();
+    for section in elf_file.section_headers()? {
+        if section.sh_type != SectionType::GNU_ATTRIBUTES {
+            continue;
+        }
+
+        let (vendors, error) = parse_attributes(elf_file.section_data(&section)?);
+        for vendor in &vendors {
+            println!("Attribute Section: {}", vendor.vendor);
+            for &(tag, data) in &vendor.subsections {
+                let mut data = data;
+                match tag {
+                    TAG_FILE => println!("File Attributes"),
+                    TAG_SECTION | TAG_SYMBOL => {
+                        // The subsection starts with the indexes it
+                        // applies to, ended by a zero.
+                        let mut offset = 0;
+                        let mut indexes = String::new();
+                        while let Some(index) = read_uleb128(data, &mut offset)
+                            && index != 0
+                        {
+                            indexes += &format!(" {}", index);
+                        }
+                        data = &data[offset.min(data.len())..];
+                        let scope = if tag == TAG_SECTION {
+                            "Section"
+                        } else {
+                            "Symbol"
+                        };
+                        println!("{} Attributes:{}", scope, indexes);
+                    }
+                    _ => println!("Unknown tag: {}", tag),
+                }
+
+                let lines = if vendor.vendor == "gnu" {
+                    gnu_attributes(machine, data)
+                } else if data.is_empty() {
+                    Vec::new()
+                } else {
+                    let mut lines = vec!["  Unknown attribute:".to_string()];
+                    lines.extend(raw_attributes(data));
+                    lines
+                };
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
+        }
+        if let Some(error) = error {
+            eprintln!("readelf-rs: Error: {}", error);
+        }
+    }
+
+    Ok(())
+}
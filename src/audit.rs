@@ -0,0 +1,569 @@
+//! Audits of every ELF file in a container image or root filesystem, as
+//! one JSON report: what each file is built for, how it is linked, how it
+//! is hardened and which libraries it needs.
+//!
+//! Images are read from `docker save` archives and OCI image layouts in a
+//! tarball, applying their layers in order with whiteouts, and each
+//! platform of a multi-arch index is audited on its own. Any other tarball,
+//! or a directory, is taken to be a root filesystem.
+
+use anyhow::{Context, Result, bail};
+use memmap2::Mmap;
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use crate::dynamic::{DF_1_NOW, DF_BIND_NOW, DynamicTag};
+use crate::elf::{ElfFile, ElfType};
+use crate::linkage;
+use crate::section::SectionType;
+use crate::segment::SegmentType;
+use crate::validate;
+
+const TAR_BLOCK: usize = 512;
+
+/// A member of a tar archive, with the headers that only carry its name
+/// (GNU long names and pax records) already applied.
+struct Entry<'a> {
+    path: String,
+    kind: u8,
+    link: String,
+    data: &'a [u8],
+}
+
+/// What is left of a C string field.
+fn field(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// A numeric tar header field: octal, or base-256 when the top bit of the
+/// first byte is set.
+fn number(bytes: &[u8]) -> Option<u64> {
+    if bytes[0] & 0x80 != 0 {
+        return Some(
+            bytes[1..]
+                .iter()
+                .fold(0u64, |value, &b| value << 8 | b as u64),
+        );
+    }
+    let text = field(bytes);
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// A path in an archive as an absolute path in the filesystem it holds.
+fn normalize(path: &str) -> String {
+    let parts: Vec<&str> = path
+        .split('/')
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect();
+    format!("/{}", parts.join("/"))
+}
+
+/// The `path` record of a pax extended header.
+fn pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .filter_map(|record| record.split_once(' ').map(|(_, r)| r))
+        .find_map(|record| record.strip_prefix("path=").map(str::to_string))
+}
+
+fn tar_entries(data: &[u8]) -> Result<Vec<Entry<'_>>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    let mut long_name = None;
+    let mut long_link = None;
+    while offset + TAR_BLOCK <= data.len() {
+        let header = &data[offset..offset + TAR_BLOCK];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let size = number(&header[124..136])
+            .with_context(|| format!("Bad size in the tar header at offset {}", offset))?;
+        let start = offset + TAR_BLOCK;
+        let end = usize::try_from(size)
+            .ok()
+            .and_then(|size| start.checked_add(size))
+            .filter(|&end| end <= data.len())
+            .with_context(|| format!("The tar member at offset {} is truncated", offset))?;
+        let body = &data[start..end];
+
+        let mut name = field(&header[..100]);
+        if &header[257..262] == b"ustar" {
+            let prefix = field(&header[345..500]);
+            if !prefix.is_empty() {
+                name = format!("{}/{}", prefix, name);
+            }
+        }
+        match header[156] {
+            b'L' => long_name = Some(field(body)),
+            b'K' => long_link = Some(field(body)),
+            b'x' => long_name = pax_path(body).or(long_name),
+            b'g' => {}
+            kind => entries.push(Entry {
+                path: long_name.take().unwrap_or(name),
+                kind,
+                link: long_link.take().unwrap_or_else(|| field(&header[157..257])),
+                data: body,
+            }),
+        }
+        offset = start + body.len().div_ceil(TAR_BLOCK) * TAR_BLOCK;
+    }
+    Ok(entries)
+}
+
+fn is_regular(kind: u8) -> bool {
+    kind == b'0' || kind == 0 || kind == b'7'
+}
+
+/// The files of a root filesystem, by absolute path, with the names of
+/// everything else in it so that symlinked libraries count as present.
+#[derive(Default)]
+struct Tree<'a> {
+    files: BTreeMap<String, &'a [u8]>,
+    names: BTreeSet<String>,
+}
+
+impl<'a> Tree<'a> {
+    fn remove_under(&mut self, dir: &str) {
+        let prefix = format!("{}/", dir.trim_end_matches('/'));
+        self.files
+            .retain(|path, _| path != dir && !path.starts_with(&prefix));
+        self.names
+            .retain(|path| path != dir && !path.starts_with(&prefix));
+    }
+
+    /// Applies a layer on top of the tree, with the whiteouts of OCI and
+    /// Docker layers deleting what the layers below had.
+    fn apply(&mut self, entries: &[Entry<'a>]) {
+        for entry in entries {
+            let path = normalize(&entry.path);
+            let (dir, base) = path.rsplit_once('/').unwrap_or(("", &path));
+            if base == ".wh..wh..opq" {
+                let dir = if dir.is_empty() { "/" } else { dir };
+                self.remove_under(dir);
+                self.names.insert(dir.to_string());
+                continue;
+            }
+            if let Some(hidden) = base.strip_prefix(".wh.") {
+                self.remove_under(&format!("{}/{}", dir, hidden));
+                continue;
+            }
+            self.files.remove(&path);
+            if is_regular(entry.kind) {
+                self.files.insert(path.clone(), entry.data);
+            } else if entry.kind == b'1'
+                && let Some(data) = self.files.get(&normalize(&entry.link)).copied()
+            {
+                self.files.insert(path.clone(), data);
+            }
+            self.names.insert(path);
+        }
+    }
+}
+
+/// An image of the archive, with where its layers are and what it says it
+/// runs on.
+struct Image {
+    platform: Option<String>,
+    layers: Vec<String>,
+}
+
+fn member<'a>(entries: &'a [Entry], path: &str) -> Option<&'a Entry<'a>> {
+    let path = normalize(path);
+    entries
+        .iter()
+        .find(|e| is_regular(e.kind) && normalize(&e.path) == path)
+}
+
+fn member_json(entries: &[Entry], path: &str) -> Option<Value> {
+    serde_json::from_slice(member(entries, path)?.data).ok()
+}
+
+fn platform(value: &Value) -> Option<String> {
+    let os = value.get("os")?.as_str()?;
+    let architecture = value.get("architecture")?.as_str()?;
+    Some(match value.get("variant").and_then(Value::as_str) {
+        Some(variant) => format!("{}/{}/{}", os, architecture, variant),
+        None => format!("{}/{}", os, architecture),
+    })
+}
+
+fn blob_path(digest: &str) -> String {
+    format!("blobs/{}", digest.replacen(':', "/", 1))
+}
+
+/// The images of an OCI index, going into nested indexes. Manifests whose
+/// blobs the archive does not have are kept, so that they are reported.
+fn oci_images(entries: &[Entry], index: &Value, images: &mut Vec<Image>) {
+    let manifests = index.get("manifests").and_then(Value::as_array);
+    for descriptor in manifests.into_iter().flatten() {
+        let Some(digest) = descriptor.get("digest").and_then(Value::as_str) else {
+            continue;
+        };
+        let Some(manifest) = member_json(entries, &blob_path(digest)) else {
+            images.push(Image {
+                platform: descriptor.get("platform").and_then(platform),
+                layers: Vec::new(),
+            });
+            continue;
+        };
+        if manifest.get("manifests").is_some() {
+            oci_images(entries, &manifest, images);
+            continue;
+        }
+        let config = manifest
+            .pointer("/config/digest")
+            .and_then(Value::as_str)
+            .and_then(|digest| member_json(entries, &blob_path(digest)));
+        let layers = manifest.get("layers").and_then(Value::as_array);
+        images.push(Image {
+            platform: descriptor
+                .get("platform")
+                .and_then(platform)
+                .or_else(|| config.as_ref().and_then(platform)),
+            layers: layers
+                .into_iter()
+                .flatten()
+                .filter_map(|l| l.get("digest").and_then(Value::as_str))
+                .map(blob_path)
+                .collect(),
+        });
+    }
+}
+
+/// The images of a `docker save` archive, from its manifest.json.
+fn docker_images(entries: &[Entry], manifest: &Value) -> Vec<Image> {
+    let images = manifest.as_array().into_iter().flatten();
+    images
+        .map(|image| {
+            let config = image
+                .get("Config")
+                .and_then(Value::as_str)
+                .and_then(|path| member_json(entries, path));
+            let layers = image.get("Layers").and_then(Value::as_array);
+            Image {
+                platform: config.as_ref().and_then(platform),
+                layers: layers
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Why a layer could not be read, from the magic numbers of the formats
+/// layers are compressed with.
+fn unreadable_layer(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        Some("it is gzip-compressed, which is not supported")
+    } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Some("it is zstd-compressed, which is not supported")
+    } else if data.len() < 263 || &data[257..262] != b"ustar" && !data.iter().all(|&b| b == 0) {
+        Some("it is not a tar archive")
+    } else {
+        None
+    }
+}
+
+/// Whether an ELF file makes its GOT read-only after relocation fully,
+/// partly or not at all.
+fn relro(elf_file: &ElfFile) -> Result<&'static str> {
+    let segments = elf_file.program_headers()?;
+    if !segments.iter().any(|s| s.p_type == SegmentType::GNU_RELRO) {
+        return Ok("none");
+    }
+    let bind_now = elf_file.dynamic_entries()?.iter().any(|e| {
+        e.d_tag == DynamicTag::BIND_NOW
+            || (e.d_tag == DynamicTag::FLAGS && e.d_val & DF_BIND_NOW != 0)
+            || (e.d_tag == DynamicTag::FLAGS_1 && e.d_val & DF_1_NOW != 0)
+    });
+    Ok(if bind_now { "full" } else { "partial" })
+}
+
+/// Whether the file calls the stack protector's failure handler and the
+/// `_FORTIFY_SOURCE` checked functions, going by its symbols.
+fn protections(elf_file: &ElfFile) -> Result<(bool, bool)> {
+    let sections = elf_file.section_headers()?;
+    let (mut canary, mut fortify) = (false, false);
+    for section in &sections {
+        if section.sh_type != SectionType::SYMTAB && section.sh_type != SectionType::DYNSYM {
+            continue;
+        }
+        for symbol in elf_file.symbols(section)? {
+            let name = elf_file
+                .symbol_name(&sections, section, &symbol)
+                .unwrap_or("");
+            if name.starts_with("__stack_chk_") {
+                canary = true;
+            } else if name.starts_with("__") && name.ends_with("_chk") {
+                fortify = true;
+            }
+        }
+    }
+    Ok((canary, fortify))
+}
+
+fn audit_file(elf_file: &ElfFile, path: &str) -> Result<Value> {
+    let interpreter = elf_file.interpreter()?;
+    let segments = elf_file.program_headers()?;
+    let pie = match elf_file.elf_type() {
+        ElfType::EXEC => Some(false),
+        ElfType::DYN if elf_file.is_pie()? => Some(true),
+        _ => None,
+    };
+    let nx = segments
+        .iter()
+        .find(|s| s.p_type == SegmentType::GNU_STACK)
+        .map(|s| !s.is_executable());
+    let (stack_protector, fortify) = protections(elf_file)?;
+    let findings: Vec<&str> = validate::security(elf_file)?
+        .iter()
+        .map(|f| f.rule)
+        .collect();
+    let libc = linkage::libc_flavor(elf_file, interpreter)?;
+
+    Ok(json!({
+        "path": path,
+        "class": if elf_file.is_64() { "ELF64" } else { "ELF32" },
+        "machine": elf_file.machine().to_string(),
+        "elf_type": elf_file.type_description(),
+        "linkage": linkage::linkage_kind(elf_file, interpreter)?,
+        "interpreter": interpreter,
+        "libc": libc.map(|(flavor, _)| flavor),
+        "needed": elf_file.dynamic_strings(DynamicTag::NEEDED)?,
+        "runpath": elf_file.dynamic_strings(DynamicTag::RUNPATH)?,
+        "rpath": elf_file.dynamic_strings(DynamicTag::RPATH)?,
+        "hardening": {
+            "pie": pie,
+            "relro": relro(elf_file)?,
+            "nx": nx.unwrap_or(false),
+            "stack_protector": stack_protector,
+            "fortify": fortify,
+        },
+        "findings": findings,
+    }))
+}
+
+fn audit_data(data: &[u8], path: &str) -> Value {
+    ElfFile::parse(data)
+        .and_then(|elf_file| audit_file(&elf_file, path))
+        .unwrap_or_else(|e| json!({ "path": path, "error": format!("{:#}", e) }))
+}
+
+fn increment(map: &mut Map<String, Value>, key: &str) {
+    let count = map.get(key).and_then(Value::as_u64).unwrap_or(0);
+    map.insert(key.to_string(), json!(count + 1));
+}
+
+/// Totals over the files of an image, with the needed libraries no file or
+/// link in the image is named after.
+fn summary(files: &[Value], names: &BTreeSet<String>) -> Value {
+    let mut machines = Map::new();
+    let mut linkages = Map::new();
+    let mut needed = Map::new();
+    let mut counts = BTreeMap::from([
+        ("errors", 0),
+        ("executables_without_pie", 0),
+        ("without_relro", 0),
+        ("partial_relro", 0),
+        ("executable_stack", 0),
+        ("without_stack_protector", 0),
+        ("text_relocations", 0),
+    ]);
+    let present: BTreeSet<&str> = names.iter().filter_map(|n| n.rsplit('/').next()).collect();
+    let mut missing = BTreeSet::new();
+
+    for file in files {
+        if file.get("error").is_some() {
+            *counts.get_mut("errors").unwrap() += 1;
+            continue;
+        }
+        let text = |key: &str| file.get(key).and_then(Value::as_str).unwrap_or("");
+        let hardening = |key: &str| file.pointer(&format!("/hardening/{}", key));
+        increment(&mut machines, text("machine"));
+        increment(&mut linkages, text("linkage"));
+        if hardening("pie") == Some(&json!(false)) {
+            *counts.get_mut("executables_without_pie").unwrap() += 1;
+        }
+        match hardening("relro").and_then(Value::as_str) {
+            Some("none") => *counts.get_mut("without_relro").unwrap() += 1,
+            Some("partial") => *counts.get_mut("partial_relro").unwrap() += 1,
+            _ => {}
+        }
+        if hardening("nx") == Some(&json!(false)) {
+            *counts.get_mut("executable_stack").unwrap() += 1;
+        }
+        if hardening("stack_protector") == Some(&json!(false)) {
+            *counts.get_mut("without_stack_protector").unwrap() += 1;
+        }
+        let findings = file.get("findings").and_then(Value::as_array);
+        if findings.is_some_and(|f| f.contains(&json!("textrel"))) {
+            *counts.get_mut("text_relocations").unwrap() += 1;
+        }
+        let libraries = file.get("needed").and_then(Value::as_array);
+        for library in libraries.into_iter().flatten().filter_map(Value::as_str) {
+            increment(&mut needed, library);
+            if !present.contains(library) {
+                missing.insert(library.to_string());
+            }
+        }
+    }
+
+    let mut summary = Map::new();
+    summary.insert("files".to_string(), json!(files.len()));
+    for (key, count) in counts {
+        summary.insert(key.to_string(), json!(count));
+    }
+    summary.insert("machines".to_string(), Value::Object(machines));
+    summary.insert("linkage".to_string(), Value::Object(linkages));
+    summary.insert("needed".to_string(), Value::Object(needed));
+    summary.insert("missing_needed".to_string(), json!(missing));
+    Value::Object(summary)
+}
+
+fn audit_tree(tree: &Tree) -> (Vec<Value>, Value) {
+    let files: Vec<Value> = tree
+        .files
+        .iter()
+        .filter(|(_, data)| data.starts_with(b"\x7fELF"))
+        .map(|(path, data)| audit_data(data, path))
+        .collect();
+    let summary = summary(&files, &tree.names);
+    (files, summary)
+}
+
+fn audit_directory(root: &Path) -> Result<Value> {
+    fn walk(dir: &Path, root: &Path, files: &mut Vec<Value>, names: &mut BTreeSet<String>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut paths: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        paths.sort();
+        for path in paths {
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                continue;
+            };
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let name = normalize(&relative.to_string_lossy());
+            names.insert(name.clone());
+            if metadata.is_dir() {
+                walk(&path, root, files, names);
+                continue;
+            }
+            if !metadata.is_file() {
+                continue;
+            }
+            let mut magic = [0u8; 4];
+            let is_elf = File::open(&path)
+                .and_then(|mut file| file.read_exact(&mut magic))
+                .is_ok()
+                && magic == *b"\x7fELF";
+            if is_elf {
+                files.push(
+                    ElfFile::open(&path)
+                        .and_then(|elf_file| audit_file(&elf_file, &name))
+                        .unwrap_or_else(|e| json!({ "path": name, "error": format!("{:#}", e) })),
+                );
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    let mut names = BTreeSet::new();
+    walk(root, root, &mut files, &mut names);
+    let summary = summary(&files, &names);
+    Ok(json!({
+        "source": root.display().to_string(),
+        "format": "directory",
+        "images": [{ "platform": null, "files": files, "summary": summary }],
+    }))
+}
+
+fn audit_archive(path: &Path) -> Result<Value> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let data = unsafe { Mmap::map(&file) }
+        .with_context(|| format!("Failed to memory map {}", path.display()))?;
+    if let Some(reason) = unreadable_layer(&data) {
+        bail!("Cannot read {}: {}", path.display(), reason);
+    }
+    let entries = tar_entries(&data)?;
+
+    let (format, images) = if let Some(manifest) = member_json(&entries, "manifest.json") {
+        ("docker-archive", docker_images(&entries, &manifest))
+    } else if let Some(index) = member_json(&entries, "index.json")
+        && member(&entries, "oci-layout").is_some()
+    {
+        let mut images = Vec::new();
+        oci_images(&entries, &index, &mut images);
+        ("oci-layout", images)
+    } else {
+        let mut tree = Tree::default();
+        tree.apply(&entries);
+        let (files, summary) = audit_tree(&tree);
+        return Ok(json!({
+            "source": path.display().to_string(),
+            "format": "rootfs-tar",
+            "images": [{ "platform": null, "files": files, "summary": summary }],
+        }));
+    };
+
+    let mut reports = Vec::new();
+    for image in images {
+        let mut tree = Tree::default();
+        let mut skipped = Vec::new();
+        if image.layers.is_empty() {
+            skipped.push(json!({ "layer": null, "reason": "the archive does not have this image's manifest" }));
+        }
+        for layer in &image.layers {
+            let Some(entry) = member(&entries, layer) else {
+                skipped.push(json!({ "layer": layer, "reason": "not in the archive" }));
+                continue;
+            };
+            if let Some(reason) = unreadable_layer(entry.data) {
+                skipped.push(json!({ "layer": layer, "reason": reason }));
+                continue;
+            }
+            match tar_entries(entry.data) {
+                Ok(layer_entries) => tree.apply(&layer_entries),
+                Err(e) => skipped.push(json!({ "layer": layer, "reason": format!("{:#}", e) })),
+            }
+        }
+        let (files, summary) = audit_tree(&tree);
+        reports.push(json!({
+            "platform": image.platform,
+            "layers": image.layers,
+            "skipped_layers": skipped,
+            "files": files,
+            "summary": summary,
+        }));
+    }
+
+    Ok(json!({
+        "source": path.display().to_string(),
+        "format": format,
+        "images": reports,
+    }))
+}
+
+/// Prints the JSON audit of the container image or root filesystem at
+/// `path`: the class, machine, linkage, hardening and needed libraries of
+/// each ELF file, and totals for every image.
+pub fn print_image_audit(path: &Path) -> Result<()> {
+    let report = if path.is_dir() {
+        audit_directory(path)?
+    } else {
+        audit_archive(path)?
+    };
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
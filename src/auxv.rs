@@ -0,0 +1,362 @@
+//! The auxiliary vector the kernel hands a process at startup, decoded
+//! into named AT_* entries, from a core dump's NT_AUXV note or from
+//! `/proc/<pid>/auxv` for a live process.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+
+use crate::core::{self, Memory};
+use crate::elf::{ElfFile, ElfType};
+use crate::emachine::EMachine;
+
+const AT_PHDR: u64 = 3;
+const AT_BASE: u64 = 7;
+const AT_ENTRY: u64 = 9;
+const AT_PLATFORM: u64 = 15;
+const AT_HWCAP: u64 = 16;
+const AT_SECURE: u64 = 23;
+const AT_BASE_PLATFORM: u64 = 24;
+const AT_RANDOM: u64 = 25;
+const AT_HWCAP2: u64 = 26;
+const AT_EXECFN: u64 = 31;
+const AT_SYSINFO: u64 = 32;
+const AT_SYSINFO_EHDR: u64 = 33;
+
+fn type_name(key: u64) -> Option<&'static str> {
+    Some(match key {
+        1 => "AT_IGNORE",
+        2 => "AT_EXECFD",
+        AT_PHDR => "AT_PHDR",
+        4 => "AT_PHENT",
+        5 => "AT_PHNUM",
+        6 => "AT_PAGESZ",
+        AT_BASE => "AT_BASE",
+        8 => "AT_FLAGS",
+        AT_ENTRY => "AT_ENTRY",
+        10 => "AT_NOTELF",
+        11 => "AT_UID",
+        12 => "AT_EUID",
+        13 => "AT_GID",
+        14 => "AT_EGID",
+        AT_PLATFORM => "AT_PLATFORM",
+        AT_HWCAP => "AT_HWCAP",
+        17 => "AT_CLKTCK",
+        AT_SECURE => "AT_SECURE",
+        AT_BASE_PLATFORM => "AT_BASE_PLATFORM",
+        AT_RANDOM => "AT_RANDOM",
+        AT_HWCAP2 => "AT_HWCAP2",
+        27 => "AT_RSEQ_FEATURE_SIZE",
+        28 => "AT_RSEQ_ALIGN",
+        29 => "AT_HWCAP3",
+        30 => "AT_HWCAP4",
+        AT_EXECFN => "AT_EXECFN",
+        AT_SYSINFO => "AT_SYSINFO",
+        AT_SYSINFO_EHDR => "AT_SYSINFO_EHDR",
+        34 => "AT_L1I_CACHESHAPE",
+        35 => "AT_L1D_CACHESHAPE",
+        36 => "AT_L2_CACHESHAPE",
+        37 => "AT_L3_CACHESHAPE",
+        40 => "AT_L1I_CACHESIZE",
+        41 => "AT_L1I_CACHEGEOMETRY",
+        42 => "AT_L1D_CACHESIZE",
+        43 => "AT_L1D_CACHEGEOMETRY",
+        44 => "AT_L2_CACHESIZE",
+        45 => "AT_L2_CACHEGEOMETRY",
+        46 => "AT_L3_CACHESIZE",
+        47 => "AT_L3_CACHEGEOMETRY",
+        51 => "AT_MINSIGSTKSZ",
+        _ => return None,
+    })
+}
+
+/// The CPUID leaf 1 EDX feature flags x86 kernels report in AT_HWCAP, named
+/// as in /proc/cpuinfo.
+const X86_HWCAP: &[&str] = &[
+    "fpu", "vme", "de", "pse", "tsc", "msr", "pae", "mce", "cx8", "apic", "", "sep", "mtrr", "pge",
+    "mca", "cmov", "pat", "pse36", "pn", "clflush", "", "dts", "acpi", "mmx", "fxsr", "sse",
+    "sse2", "ss", "ht", "tm", "ia64", "pbe",
+];
+const X86_HWCAP2: &[&str] = &["ring3mwait", "fsgsbase"];
+
+const AARCH64_HWCAP: &[&str] = &[
+    "fp", "asimd", "evtstrm", "aes", "pmull", "sha1", "sha2", "crc32", "atomics", "fphp",
+    "asimdhp", "cpuid", "asimdrdm", "jscvt", "fcma", "lrcpc", "dcpop", "sha3", "sm3", "sm4",
+    "asimddp", "sha512", "sve", "asimdfhm", "dit", "uscat", "ilrcpc", "flagm", "ssbs", "sb",
+    "paca", "pacg", "gcs",
+];
+const AARCH64_HWCAP2: &[&str] = &[
+    "dcpodp",
+    "sve2",
+    "sveaes",
+    "svepmull",
+    "svebitperm",
+    "svesha3",
+    "svesm4",
+    "flagm2",
+    "frint",
+    "svei8mm",
+    "svef32mm",
+    "svef64mm",
+    "svebf16",
+    "i8mm",
+    "bf16",
+    "dgh",
+    "rng",
+    "bti",
+    "mte",
+    "ecv",
+    "afp",
+    "rpres",
+    "mte3",
+    "sme",
+    "smei16i64",
+    "smef64f64",
+    "smei8i32",
+    "smef16f32",
+    "smeb16f32",
+    "smef32f32",
+    "smefa64",
+    "wfxt",
+    "ebf16",
+    "sveebf16",
+    "cssc",
+    "rprfm",
+    "sve2p1",
+    "sme2",
+    "sme2p1",
+    "smei16i32",
+    "smebi32i32",
+    "smeb16b16",
+    "smef16f16",
+    "mops",
+    "hbc",
+];
+
+/// The PPC_FEATURE_* bits, named as glibc's LD_SHOW_AUXV does, from bit 0.
+const PPC_HWCAP: &[&str] = &[
+    "ppcle",
+    "true_le",
+    "",
+    "",
+    "",
+    "",
+    "archpmu",
+    "vsx",
+    "arch_2_06",
+    "power6x",
+    "dfp",
+    "pa6t",
+    "arch_2_05",
+    "ic_snoop",
+    "smt",
+    "booke",
+    "cellbe",
+    "power5+",
+    "power5",
+    "power4",
+    "notb",
+    "efpdouble",
+    "efpsingle",
+    "spe",
+    "ucache",
+    "4xxmac",
+    "mmu",
+    "fpu",
+    "altivec",
+    "ppc601",
+    "ppc64",
+    "ppc32",
+];
+const PPC_HWCAP2: &[&str] = &[
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "mma",
+    "arch_3_1",
+    "htm-no-suspend",
+    "scv",
+    "darn",
+    "ieee128",
+    "arch_3_00",
+    "htm-nosc",
+    "vcrypto",
+    "tar",
+    "isel",
+    "ebb",
+    "dscr",
+    "htm",
+    "arch_2_07",
+];
+
+/// The names of the bits set in a hardware capability mask.
+fn capability_names(value: u64, names: &[&str]) -> String {
+    (0..64)
+        .filter(|bit| value & (1 << bit) != 0)
+        .map(|bit| match names.get(bit) {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => format!("bit{}", bit),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// What the hardware capability bits of a machine stand for, by AT_HWCAP
+/// and AT_HWCAP2.
+fn capabilities(machine: EMachine, key: u64, value: u64) -> Option<String> {
+    let names = match (machine, key) {
+        (EMachine::X8664 | EMachine::I386, AT_HWCAP) => X86_HWCAP,
+        (EMachine::X8664 | EMachine::I386, AT_HWCAP2) => X86_HWCAP2,
+        (EMachine::Aarch64, AT_HWCAP) => AARCH64_HWCAP,
+        (EMachine::Aarch64, AT_HWCAP2) => AARCH64_HWCAP2,
+        (EMachine::Ppc64, AT_HWCAP) => PPC_HWCAP,
+        (EMachine::Ppc64, AT_HWCAP2) => PPC_HWCAP2,
+        // RISC-V sets a bit per single-letter ISA extension, from 'a'.
+        (EMachine::Riscv, AT_HWCAP) => {
+            return Some(
+                (0..26)
+                    .filter(|bit| value & (1 << bit) != 0)
+                    .map(|bit| (b'a' + bit as u8) as char)
+                    .collect(),
+            );
+        }
+        _ => return None,
+    };
+    Some(capability_names(value, names))
+}
+
+/// A NUL-terminated string in the process's memory.
+fn read_string(read: &dyn Fn(u64, usize) -> Option<Vec<u8>>, address: u64) -> Option<String> {
+    let mut bytes = Vec::new();
+    // Read a byte at a time so as not to run off the end of the memory the
+    // string is in, which is usually the top of the stack.
+    while bytes.len() < 4096 {
+        match read(address + bytes.len() as u64, 1)?[0] {
+            0 => return Some(String::from_utf8_lossy(&bytes).into_owned()),
+            byte => bytes.push(byte),
+        }
+    }
+    None
+}
+
+/// An entry's value as it is meant: an address, a number, a string or
+/// random bytes it points to, or the capability names its bits stand for.
+fn interpret(
+    key: u64,
+    value: u64,
+    machine: EMachine,
+    read: &dyn Fn(u64, usize) -> Option<Vec<u8>>,
+) -> String {
+    match key {
+        AT_PHDR | AT_BASE | AT_ENTRY | AT_SYSINFO | AT_SYSINFO_EHDR => format!("{:#x}", value),
+        AT_PLATFORM | AT_BASE_PLATFORM | AT_EXECFN => match read_string(read, value) {
+            Some(string) => format!("{:#x} \"{}\"", value, string),
+            None => format!("{:#x}", value),
+        },
+        AT_RANDOM => match read(value, 16) {
+            Some(bytes) => format!(
+                "{:#x} ({})",
+                value,
+                bytes
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>()
+            ),
+            None => format!("{:#x}", value),
+        },
+        AT_HWCAP | AT_HWCAP2 => match capabilities(machine, key, value) {
+            Some(names) if !names.is_empty() => format!("{:#x} ({})", value, names),
+            _ => format!("{:#x}", value),
+        },
+        AT_SECURE if value != 0 => format!("{} (set-user-ID or similar)", value),
+        8 | 34..=37 | 41 | 43 | 45 | 47 => format!("{:#x}", value),
+        _ => value.to_string(),
+    }
+}
+
+fn print_entries(
+    entries: &[(u64, u64)],
+    machine: EMachine,
+    read: &dyn Fn(u64, usize) -> Option<Vec<u8>>,
+) {
+    println!("Auxiliary vector ({} entries):", entries.len());
+    for &(key, value) in entries {
+        let name = type_name(key).map_or_else(|| format!("AT_<{}>", key), str::to_string);
+        println!(
+            "  {:<22} {}",
+            format!("{}:", name),
+            interpret(key, value, machine, read)
+        );
+    }
+}
+
+/// Prints the auxiliary vector of a core dump's process, with the strings
+/// and random bytes it points to read from the core's memory.
+pub fn print_auxv(elf_file: &ElfFile) -> Result<()> {
+    if elf_file.elf_type() != ElfType::CORE {
+        println!("This is not a core dump, so it has no auxiliary vector.");
+        return Ok(());
+    }
+    let entries = core::auxv(elf_file)?;
+    if entries.is_empty() {
+        println!("There is no NT_AUXV note in this core dump.");
+        return Ok(());
+    }
+    let memory = Memory::new(elf_file)?;
+    print_entries(&entries, elf_file.machine(), &|address, len| {
+        memory.read(address, len).map(<[u8]>::to_vec)
+    });
+    Ok(())
+}
+
+/// Prints the auxiliary vector of a running process from
+/// `/proc/<pid>/auxv`. Its class and machine are those of the executable it
+/// runs, and the strings it points to are read from `/proc/<pid>/mem` when
+/// the process may be traced.
+pub fn print_live_auxv(pid: u32) -> Result<()> {
+    let data = std::fs::read(format!("/proc/{}/auxv", pid))
+        .with_context(|| format!("Failed to read the auxiliary vector of process {}", pid))?;
+    let (machine, is_64) = match ElfFile::open(format!("/proc/{}/exe", pid)) {
+        Ok(executable) => (executable.machine(), executable.is_64()),
+        Err(_) => (host_machine(), cfg!(target_pointer_width = "64")),
+    };
+    let memory = File::open(format!("/proc/{}/mem", pid)).ok();
+
+    print_entries(&core::parse_auxv(&data, is_64), machine, &|address, len| {
+        let mut buffer = vec![0; len];
+        memory.as_ref()?.read_exact_at(&mut buffer, address).ok()?;
+        Some(buffer)
+    });
+    Ok(())
+}
+
+fn host_machine() -> EMachine {
+    if cfg!(target_arch = "x86_64") {
+        EMachine::X8664
+    } else if cfg!(target_arch = "x86") {
+        EMachine::I386
+    } else if cfg!(target_arch = "aarch64") {
+        EMachine::Aarch64
+    } else if cfg!(target_arch = "riscv64") {
+        EMachine::Riscv
+    } else if cfg!(target_arch = "powerpc64") {
+        EMachine::Ppc64
+    } else {
+        EMachine::None
+    }
+}
@@ -0,0 +1,541 @@
+//! Backtraces of the threads of a core dump, unwound through the call frame
+//! information of the executable and the libraries the process had mapped,
+//! and symbolized with their symbol tables and line tables.
+
+use anyhow::{Result, bail};
+use gimli::RegisterRule;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::core::{self, MappedFile, Memory, Thread};
+use crate::demangle;
+use crate::dwarf::{self, LineTable};
+use crate::elf::{ElfFile, ElfType};
+use crate::emachine::EMachine;
+use crate::notes::{self, NT_GNU_BUILD_ID};
+use crate::section::{SHN_LORESERVE, SHN_UNDEF};
+use crate::segment::{ProgramHeader, SegmentType};
+use crate::strtab::SymbolTable;
+use crate::symbol::SymbolType;
+use crate::unwind;
+
+/// Frames past this are assumed to be a corrupt stack going round in
+/// circles.
+const MAX_FRAMES: usize = 256;
+
+/// A file on disk that was matched to an object of the process.
+struct Loaded {
+    path: String,
+    elf_file: ElfFile<'static>,
+    /// (start, size, name) of the function symbols, sorted by start.
+    functions: Vec<(u64, u64, String)>,
+    lines: Option<LineTable>,
+}
+
+impl Loaded {
+    fn open(path: &str) -> Result<Self> {
+        let elf_file = ElfFile::open(path)?;
+        let sections = elf_file.section_headers()?;
+        let mut functions = Vec::new();
+        if let Some(table) = elf_file.symbol_table(&sections) {
+            let symbols = SymbolTable::new(&elf_file, &sections, table)?;
+            for (index, symbol) in symbols.iter().enumerate() {
+                if symbol.symbol_type() == SymbolType::FUNC
+                    && symbol.st_shndx != SHN_UNDEF
+                    && symbol.st_shndx < SHN_LORESERVE
+                {
+                    let name = demangle::demangle(symbols.name(index)?);
+                    functions.push((symbol.st_value, symbol.st_size, name));
+                }
+            }
+        }
+        functions.sort();
+        let lines = dwarf::load(&elf_file)?
+            .map(|dwarf| LineTable::new(&dwarf, false))
+            .transpose()?;
+        Ok(Self {
+            path: path.to_string(),
+            elf_file,
+            functions,
+            lines,
+        })
+    }
+
+    /// The name and start of the function containing a file address.
+    fn function(&self, address: u64) -> Option<(&str, u64)> {
+        let index = self
+            .functions
+            .partition_point(|&(start, _, _)| start <= address);
+        let (start, size, name) = self.functions.get(index.checked_sub(1)?)?;
+        (*size == 0 || address < start + size).then_some((name.as_str(), *start))
+    }
+}
+
+/// An object the process had mapped, from the mappings of one file in the
+/// NT_FILE note.
+struct Module {
+    path: String,
+    start: u64,
+    end: u64,
+    /// The build ID of the object as it was in memory, when the core holds
+    /// the page with its headers.
+    build_id: Option<String>,
+    file: Option<Loaded>,
+    bias: u64,
+    /// How `file` was matched to the object, or why none was.
+    matched: String,
+}
+
+/// How far a file's addresses were moved when it was loaded at `start`:
+/// the start less the address its first segment asks for.
+fn load_bias(start: u64, headers: &[ProgramHeader]) -> u64 {
+    headers
+        .iter()
+        .find(|h| h.p_type == SegmentType::LOAD)
+        .map_or(0, |h| start.wrapping_sub(h.p_vaddr - h.p_offset))
+}
+
+fn build_id_of(elf_file: &ElfFile) -> Option<String> {
+    notes::build_id(elf_file).ok().flatten()
+}
+
+/// The build ID of an object from its headers and notes as the core has
+/// them in memory. Cores keep the first page of every ELF object mapped,
+/// unless coredump_filter says otherwise, and the notes sit in it.
+fn build_id_in_memory(memory: &Memory, start: u64) -> Option<String> {
+    let ident = memory.read(start, 64)?;
+    let header = *ElfFile::parse(ident).ok()?.header();
+    let length = header.e_phoff + header.e_phnum as u64 * header.e_phentsize as u64;
+    let image = ElfFile::parse(memory.read(start, usize::try_from(length).ok()?)?).ok()?;
+    let headers = image.program_headers().ok()?;
+    let bias = load_bias(start, &headers);
+
+    headers
+        .iter()
+        .filter(|h| h.p_type == SegmentType::NOTE)
+        .filter_map(|h| memory.read(bias.wrapping_add(h.p_vaddr), h.p_filesz as usize))
+        .flat_map(|data| notes::parse_notes(data, 4).notes)
+        .find(|n| n.name == "GNU" && n.n_type == NT_GNU_BUILD_ID)
+        .map(|n| n.desc.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Groups the mappings of the core by file into the objects of the process,
+/// and matches the given files to them: by build ID, then by file name.
+/// Objects nobody gave a file for are looked up at the path they were
+/// mapped from, which is right when the core is read where it was dumped.
+fn modules(
+    mappings: &[MappedFile],
+    memory: &Memory,
+    mut candidates: Vec<Loaded>,
+) -> Result<(Vec<Module>, Vec<String>)> {
+    let mut modules: Vec<Module> = Vec::new();
+    for mapping in mappings {
+        match modules.iter_mut().find(|m| m.path == mapping.path) {
+            Some(module) => {
+                module.start = module.start.min(mapping.start);
+                module.end = module.end.max(mapping.end);
+            }
+            None => modules.push(Module {
+                path: mapping.path.clone(),
+                start: mapping.start,
+                end: mapping.end,
+                build_id: None,
+                file: None,
+                bias: 0,
+                matched: String::new(),
+            }),
+        }
+    }
+    for module in &mut modules {
+        if let Some(first) = mappings
+            .iter()
+            .find(|m| m.path == module.path && m.offset == 0)
+        {
+            module.start = first.start;
+            module.build_id = build_id_in_memory(memory, first.start);
+        }
+    }
+
+    let file_name = |path: &str| Path::new(path).file_name().map(|n| n.to_os_string());
+    for module in &mut modules {
+        let by_build_id = candidates
+            .iter()
+            .position(|c| module.build_id.is_some() && build_id_of(&c.elf_file) == module.build_id);
+        if let Some(index) = by_build_id {
+            module.file = Some(candidates.remove(index));
+            module.matched = "matched by build ID".to_string();
+            continue;
+        }
+        let by_name = candidates
+            .iter()
+            .position(|c| file_name(&c.path) == file_name(&module.path));
+        if let Some(index) = by_name {
+            let candidate = &candidates[index];
+            match (&module.build_id, build_id_of(&candidate.elf_file)) {
+                (Some(core), Some(file)) if *core != file => {
+                    module.matched = format!("{} has a different build ID", candidate.path);
+                }
+                _ => {
+                    module.file = Some(candidates.remove(index));
+                    module.matched = "matched by file name".to_string();
+                }
+            }
+            continue;
+        }
+
+        let Ok(loaded) = Loaded::open(&module.path) else {
+            module.matched = "no file".to_string();
+            continue;
+        };
+        match (&module.build_id, build_id_of(&loaded.elf_file)) {
+            (Some(core), Some(file)) if *core == file => {
+                module.file = Some(loaded);
+                module.matched = "found at its path, same build ID".to_string();
+            }
+            (Some(_), _) => {
+                module.matched = "the file at its path has a different build ID".to_string();
+            }
+            (None, _) => {
+                module.file = Some(loaded);
+                module.matched = "found at its path, build ID not checked".to_string();
+            }
+        }
+    }
+
+    for module in &mut modules {
+        if let Some(file) = &module.file {
+            module.bias = load_bias(module.start, &file.elf_file.program_headers()?);
+        }
+    }
+
+    // Data files the process mapped, such as locale archives, are not
+    // objects.
+    modules.retain(|m| m.file.is_some() || memory.read(m.start, 4) == Some(b"\x7fELF"));
+    let unmatched = candidates.into_iter().map(|c| c.path).collect();
+    Ok((modules, unmatched))
+}
+
+/// The DWARF number of the stack pointer, and of the frame pointer with
+/// where a frame keeps the caller's frame pointer and return address
+/// relative to it.
+fn frame_registers(machine: EMachine) -> Option<(u16, u16, i64, i64)> {
+    match machine {
+        EMachine::X8664 => Some((7, 6, 0, 8)),
+        EMachine::I386 => Some((4, 5, 0, 4)),
+        EMachine::Aarch64 => Some((31, 29, 0, 8)),
+        EMachine::Riscv => Some((2, 8, -16, -8)),
+        _ => None,
+    }
+}
+
+/// Steps out of a frame with the rules of its call frame information,
+/// returning the caller's registers and the return address.
+fn step_cfi(
+    rules: &unwind::FrameRules,
+    registers: &HashMap<u16, u64>,
+    memory: &Memory,
+    sp: u16,
+) -> Option<(HashMap<u16, u64>, u64)> {
+    let (register, offset) = rules.cfa?;
+    let cfa = registers.get(&register.0)?.wrapping_add_signed(offset);
+
+    // Registers without a rule are the callee-saved ones the function left
+    // alone, so they keep their values.
+    let mut caller = registers.clone();
+    for (register, rule) in &rules.registers {
+        let value = match rule {
+            RegisterRule::SameValue => registers.get(&register.0).copied(),
+            RegisterRule::Offset(offset) => memory.read_word(cfa.wrapping_add_signed(*offset)),
+            RegisterRule::ValOffset(offset) => Some(cfa.wrapping_add_signed(*offset)),
+            RegisterRule::Register(other) => registers.get(&other.0).copied(),
+            _ => None,
+        };
+        match value {
+            Some(value) => caller.insert(register.0, value),
+            None => caller.remove(&register.0),
+        };
+    }
+    caller.insert(sp, cfa);
+    let return_address = *caller.get(&rules.return_address.0)?;
+    Some((caller, return_address))
+}
+
+/// Steps out of a frame by following the frame pointer chain, for code
+/// without call frame information.
+fn step_frame_pointer(
+    machine: EMachine,
+    registers: &HashMap<u16, u64>,
+    memory: &Memory,
+) -> Option<(HashMap<u16, u64>, u64)> {
+    let (sp, fp, saved_fp, saved_ra) = frame_registers(machine)?;
+    let frame = *registers.get(&fp)?;
+    let return_address = memory.read_word(frame.wrapping_add_signed(saved_ra))?;
+    let mut caller = registers.clone();
+    caller.insert(fp, memory.read_word(frame.wrapping_add_signed(saved_fp))?);
+    // x86 and aarch64 frames keep the pair at the bottom of the frame, and
+    // RISC-V ones at the top, where the frame pointer points.
+    let frame_size = if saved_ra > 0 { saved_ra * 2 } else { 0 };
+    caller.insert(sp, frame.wrapping_add_signed(frame_size));
+    Some((caller, return_address))
+}
+
+/// Unwinds a thread, returning the program counter of each frame from the
+/// innermost one out.
+fn unwind_thread(
+    thread: &Thread,
+    modules: &[Module],
+    memory: &Memory,
+    machine: EMachine,
+) -> Result<Vec<u64>> {
+    let Some(mut pc) = thread.pc() else {
+        return Ok(Vec::new());
+    };
+    let Some((sp, ..)) = frame_registers(machine) else {
+        return Ok(vec![pc]);
+    };
+    let mut registers = thread.dwarf_registers();
+    let mut frames = vec![pc];
+
+    while frames.len() < MAX_FRAMES {
+        // Return addresses point after the call, which may already be the
+        // next function or past the end of the FDE.
+        let lookup = if frames.len() == 1 { pc } else { pc - 1 };
+        let module = modules
+            .iter()
+            .find(|m| (m.start..m.end).contains(&lookup) && m.file.is_some());
+        let rules = match module {
+            Some(module) => {
+                let file = &module.file.as_ref().unwrap().elf_file;
+                unwind::frame_rules(file, lookup.wrapping_sub(module.bias))?
+            }
+            None => None,
+        };
+        let step = match &rules {
+            Some(rules) => step_cfi(rules, &registers, memory, sp),
+            None => step_frame_pointer(machine, &registers, memory),
+        };
+        let Some((caller, return_address)) = step else {
+            break;
+        };
+        // The stack grows down, so each caller's frame is above its
+        // callee's; anything else is a corrupt stack.
+        if return_address == 0 || caller.get(&sp) <= registers.get(&sp) {
+            break;
+        }
+        registers = caller;
+        pc = return_address;
+        frames.push(pc);
+    }
+
+    Ok(frames)
+}
+
+/// Opens the given files and matches them to the objects the process of a
+/// core dump had mapped, warning about those that match none.
+fn open_modules(
+    core_file: &ElfFile,
+    memory: &Memory,
+    executable: Option<&str>,
+    libraries: &[&str],
+) -> Result<Vec<Module>> {
+    let machine = core_file.machine();
+    let mut candidates = Vec::new();
+    for path in executable.into_iter().chain(libraries.iter().copied()) {
+        let loaded = Loaded::open(path)?;
+        if loaded.elf_file.machine() != machine {
+            eprintln!(
+                "readelf-rs: Warning: {} is for {}, but the core dump is for {}",
+                path,
+                loaded.elf_file.machine(),
+                machine
+            );
+        }
+        candidates.push(loaded);
+    }
+    let (modules, unmatched) = modules(&core::mapped_files(core_file)?, memory, candidates)?;
+    for path in unmatched {
+        eprintln!(
+            "readelf-rs: Warning: {} does not match any object mapped in the core dump",
+            path
+        );
+    }
+    Ok(modules)
+}
+
+/// Describes the code at `pc` as `function+offset at file:line (object)`,
+/// leaving out what is not known. `lookup` is the address to look the
+/// function up by, which for return addresses is the call before them.
+fn symbolize(modules: &[Module], pc: u64, lookup: u64) -> String {
+    let Some(module) = modules.iter().find(|m| (m.start..m.end).contains(&lookup)) else {
+        return "??".to_string();
+    };
+    let object = Path::new(&module.path)
+        .file_name()
+        .map_or(module.path.as_str(), |n| n.to_str().unwrap_or(""));
+    let Some(file) = &module.file else {
+        return format!("?? ({})", object);
+    };
+    let file_address = lookup.wrapping_sub(module.bias);
+    let function = file.function(file_address).map_or_else(
+        || "??".to_string(),
+        |(name, start)| format!("{}+{:#x}", name, pc.wrapping_sub(module.bias) - start),
+    );
+    let location = file
+        .lines
+        .as_ref()
+        .and_then(|lines| lines.location(file_address))
+        .map(|(path, line)| format!(" at {}:{}", path, line))
+        .unwrap_or_default();
+    format!("{}{} ({})", function, location, object)
+}
+
+fn print_process(core_file: &ElfFile, core_path: &str) -> Result<()> {
+    match core::process(core_file)? {
+        Some(process) => println!(
+            "Core dump of {} (PID {}): {}",
+            process.name, process.pid, process.args
+        ),
+        None => println!("Core dump {}", core_path),
+    }
+    Ok(())
+}
+
+fn open_core(core_path: &str) -> Result<ElfFile<'static>> {
+    let core_file = ElfFile::open(core_path)?;
+    if core_file.elf_type() != ElfType::CORE {
+        bail!("{} is not a core dump", core_path);
+    }
+    Ok(core_file)
+}
+
+/// Prints a backtrace of every thread of a core dump, unwound with the call
+/// frame information of the executable and libraries and symbolized with
+/// their symbols and line tables. The files are matched to the objects the
+/// process had mapped by build ID, or else by name; objects no file was
+/// given for are looked for at the path they were mapped from.
+pub fn print_backtraces(
+    core_path: &str,
+    executable: Option<&str>,
+    libraries: &[&str],
+) -> Result<()> {
+    let core_file = open_core(core_path)?;
+    let machine = core_file.machine();
+    let memory = Memory::new(&core_file)?;
+    let modules = open_modules(&core_file, &memory, executable, libraries)?;
+
+    print_process(&core_file, core_path)?;
+    println!();
+
+    println!("Mapped objects:");
+    let width = if core_file.is_64() { 18 } else { 10 };
+    for module in &modules {
+        println!(
+            "  {:#0w$x}-{:#0w$x}  {}",
+            module.start,
+            module.end,
+            module.path,
+            w = width
+        );
+        let build_id = module.build_id.as_deref().unwrap_or("not in the core");
+        let file = match &module.file {
+            Some(file) => format!("{} ({})", file.path, module.matched),
+            None => format!("none ({})", module.matched),
+        };
+        println!("      build ID {}, symbols from {}", build_id, file);
+    }
+    if core::mapped_files(&core_file)?.is_empty() {
+        println!("  There is no NT_FILE note, so no addresses can be symbolized.");
+    }
+
+    for thread in core::threads(&core_file)? {
+        println!();
+        match core::signal_name(thread.signal) {
+            Some(name) => println!(
+                "Thread {} (signal {}, {}):",
+                thread.pid, thread.signal, name
+            ),
+            None => println!("Thread {}:", thread.pid),
+        }
+        let frames = unwind_thread(&thread, &modules, &memory, machine)?;
+        if frames.is_empty() {
+            println!("  The register layout of {} cores is not known.", machine);
+        }
+        for (index, &pc) in frames.iter().enumerate() {
+            // Return addresses are looked up by the call before them, but
+            // shown as they are.
+            let lookup = if index == 0 { pc } else { pc - 1 };
+            println!(
+                "  #{:<3}{:#0w$x} in {}",
+                index,
+                pc,
+                symbolize(&modules, pc, lookup),
+                w = width
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints a line per thread of a core dump with the signal that stopped
+/// it, its program counter and stack pointer and the function it was in.
+/// The kernel writes the thread that took the fatal signal first, so that
+/// is the one marked as crashed; the fault address comes from NT_SIGINFO.
+pub fn print_summary(core_path: &str, executable: Option<&str>, libraries: &[&str]) -> Result<()> {
+    let core_file = open_core(core_path)?;
+    let memory = Memory::new(&core_file)?;
+    let modules = open_modules(&core_file, &memory, executable, libraries)?;
+
+    print_process(&core_file, core_path)?;
+    if let Some(info) = core::signal_info(&core_file)? {
+        let name = u16::try_from(info.signal)
+            .ok()
+            .and_then(core::signal_name)
+            .unwrap_or("unknown signal");
+        match info.address {
+            Some(address) => println!(
+                "Killed by {} (signal {}, code {}) at address {:#x}",
+                name, info.signal, info.code, address
+            ),
+            None => println!(
+                "Killed by {} (signal {}, code {})",
+                name, info.signal, info.code
+            ),
+        }
+    }
+    println!();
+
+    let width = if core_file.is_64() { 18 } else { 10 };
+    println!(
+        "  {:<8} {:<14} {:<w$} {:<w$} Function",
+        "TID",
+        "Signal",
+        "PC",
+        "SP",
+        w = width
+    );
+    for (index, thread) in core::threads(&core_file)?.iter().enumerate() {
+        let signal = match core::signal_name(thread.signal) {
+            Some(name) => format!("{} {}", thread.signal, name),
+            None => thread.signal.to_string(),
+        };
+        let register = |value: Option<u64>| {
+            value.map_or_else(|| "?".to_string(), |v| format!("{:#0w$x}", v, w = width))
+        };
+        let function = thread
+            .pc()
+            .map_or_else(|| "??".to_string(), |pc| symbolize(&modules, pc, pc));
+        println!(
+            "{} {:<8} {:<14} {:<w$} {:<w$} {}",
+            if index == 0 { '*' } else { ' ' },
+            thread.pid,
+            signal,
+            register(thread.pc()),
+            register(thread.sp()),
+            function,
+            w = width
+        );
+    }
+
+    Ok(())
+}
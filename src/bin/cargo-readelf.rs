@@ -0,0 +1,367 @@
+//! `cargo readelf`: builds a target of the current Cargo project, or finds
+//! the one already built, and runs readelf-rs on it.
+//!
+//!     cargo readelf --bin foo --release -- -S -d
+//!
+//! The options before `--` choose the artifact the way `cargo build` does;
+//! the ones after it are handed to readelf-rs, with the artifact's path last.
+
+use anyhow::{Context, Result, bail};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use serde_json::Value;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command as Process, Stdio};
+
+fn build_cli() -> Command {
+    Command::new("cargo-readelf")
+        .bin_name("cargo readelf")
+        .about("Build a Cargo target and run readelf-rs on the artifact")
+        .arg(
+            Arg::new("bin")
+                .long("bin")
+                .help("The binary target to inspect")
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("example")
+                .long("example")
+                .help("The example target to inspect")
+                .value_name("NAME")
+                .conflicts_with("bin"),
+        )
+        .arg(
+            Arg::new("lib")
+                .long("lib")
+                .help("Inspect the package's library: its cdylib or staticlib if it builds one, or else its rlib")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["bin", "example"]),
+        )
+        .arg(
+            Arg::new("package")
+                .short('p')
+                .long("package")
+                .help("The package of the workspace the target is in")
+                .value_name("SPEC"),
+        )
+        .arg(
+            Arg::new("release")
+                .short('r')
+                .long("release")
+                .help("Use the release profile")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .help("Use the named profile")
+                .value_name("NAME")
+                .conflicts_with("release"),
+        )
+        .arg(
+            Arg::new("target")
+                .long("target")
+                .help("Build for the target triple")
+                .value_name("TRIPLE"),
+        )
+        .arg(
+            Arg::new("features")
+                .short('F')
+                .long("features")
+                .help("Space or comma separated list of features to activate")
+                .value_name("FEATURES")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("all-features")
+                .long("all-features")
+                .help("Activate all available features")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-default-features")
+                .long("no-default-features")
+                .help("Do not activate the default features")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("manifest-path")
+                .long("manifest-path")
+                .help("Path to Cargo.toml")
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::new("no-build")
+                .long("no-build")
+                .help("Do not build, and use the artifact from the last build")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("readelf-args")
+                .help("Options for readelf-rs, after --")
+                .num_args(0..)
+                .last(true)
+                .allow_hyphen_values(true)
+                .value_parser(clap::value_parser!(OsString)),
+        )
+}
+
+fn cargo() -> Process {
+    Process::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+}
+
+/// The options of `cargo build` and `cargo metadata` that select what to
+/// look at and how to build it, as given to us.
+fn cargo_args(matches: &ArgMatches, for_build: bool) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut value = |name: &str, flag: &str| {
+        for value in matches.get_many::<String>(name).into_iter().flatten() {
+            args.push(flag.to_string());
+            args.push(value.clone());
+        }
+    };
+    value("manifest-path", "--manifest-path");
+    value("features", "--features");
+    if !for_build {
+        return args;
+    }
+    value("package", "--package");
+    value("bin", "--bin");
+    value("example", "--example");
+    value("profile", "--profile");
+    value("target", "--target");
+    for flag in ["release", "lib", "all-features", "no-default-features"] {
+        if matches.get_flag(flag) {
+            args.push(format!("--{}", flag));
+        }
+    }
+    args
+}
+
+/// The file of an artifact to inspect: the executable of a binary, or the
+/// most linked-looking of a library's outputs.
+fn artifact_file(message: &Value) -> Option<PathBuf> {
+    if let Some(executable) = message.get("executable").and_then(Value::as_str) {
+        return Some(PathBuf::from(executable));
+    }
+    let filenames: Vec<&str> = message
+        .get("filenames")?
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    [".so", ".a", ".rlib"]
+        .iter()
+        .find_map(|extension| filenames.iter().find(|f| f.ends_with(extension)))
+        .map(PathBuf::from)
+}
+
+/// Builds the selected target and takes its artifact from the JSON messages
+/// cargo reports them with. When no target was named, the package must
+/// have built exactly one binary.
+fn build(matches: &ArgMatches) -> Result<PathBuf> {
+    let output = cargo()
+        .arg("build")
+        .arg("--message-format=json-render-diagnostics")
+        .args(cargo_args(matches, true))
+        .stderr(Stdio::inherit())
+        .output()
+        .context("Failed to run cargo build")?;
+    if !output.status.success() {
+        bail!("cargo build failed");
+    }
+
+    let selected = |target: &Value| {
+        let kinds: Vec<&str> = target
+            .get("kind")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect();
+        let name = target.get("name").and_then(Value::as_str);
+        if let Some(bin) = matches.get_one::<String>("bin") {
+            kinds.contains(&"bin") && name == Some(bin.as_str())
+        } else if let Some(example) = matches.get_one::<String>("example") {
+            kinds.contains(&"example") && name == Some(example.as_str())
+        } else if matches.get_flag("lib") {
+            kinds
+                .iter()
+                .any(|k| matches!(*k, "lib" | "rlib" | "dylib" | "cdylib" | "staticlib"))
+        } else {
+            kinds.contains(&"bin")
+        }
+    };
+
+    let mut artifacts = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(message) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if message.get("reason").and_then(Value::as_str) != Some("compiler-artifact") {
+            continue;
+        }
+        // Dependencies are built too; only the workspace's own targets
+        // are candidates.
+        let local = message
+            .get("package_id")
+            .and_then(Value::as_str)
+            .is_some_and(|id| id.starts_with("path+") || id.contains("(path+"));
+        if local
+            && message.get("target").is_some_and(selected)
+            && let Some(file) = artifact_file(&message)
+        {
+            artifacts.push(file);
+        }
+    }
+
+    match artifacts.len() {
+        0 => bail!("cargo build did not produce a matching artifact"),
+        1 => Ok(artifacts.remove(0)),
+        _ => bail!(
+            "cargo build produced several binaries ({}); choose one with --bin",
+            artifacts
+                .iter()
+                .filter_map(|a| a.file_name())
+                .map(|n| n.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Finds the artifact of the last build in the target directory, from the
+/// path cargo would have built it at.
+fn locate(matches: &ArgMatches) -> Result<PathBuf> {
+    let output = cargo()
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .args(cargo_args(matches, false))
+        .stderr(Stdio::inherit())
+        .output()
+        .context("Failed to run cargo metadata")?;
+    if !output.status.success() {
+        bail!("cargo metadata failed");
+    }
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).context("Bad output from cargo metadata")?;
+
+    let mut dir = PathBuf::from(
+        metadata
+            .get("target_directory")
+            .and_then(Value::as_str)
+            .context("cargo metadata did not say where the target directory is")?,
+    );
+    if let Some(target) = matches.get_one::<String>("target") {
+        dir.push(target);
+    }
+    let profile = matches.get_one::<String>("profile").map(String::as_str);
+    dir.push(match (profile, matches.get_flag("release")) {
+        (Some("dev" | "test"), _) | (None, false) => "debug",
+        (Some("release" | "bench"), _) | (None, true) => "release",
+        (Some(profile), _) => profile,
+    });
+
+    let name = if let Some(bin) = matches.get_one::<String>("bin") {
+        bin.clone()
+    } else if let Some(example) = matches.get_one::<String>("example") {
+        dir.push("examples");
+        example.clone()
+    } else {
+        let packages = metadata.get("packages").and_then(Value::as_array);
+        let package = match matches.get_one::<String>("package") {
+            Some(spec) => packages
+                .into_iter()
+                .flatten()
+                .find(|p| p.get("name").and_then(Value::as_str) == Some(spec.as_str())),
+            None => packages.and_then(|p| p.first()),
+        }
+        .context("No such package in the workspace")?;
+        let targets = package.get("targets").and_then(Value::as_array);
+        let kind = if matches.get_flag("lib") {
+            "lib"
+        } else {
+            "bin"
+        };
+        let names: Vec<&str> = targets
+            .into_iter()
+            .flatten()
+            .filter(|t| {
+                t.get("kind")
+                    .and_then(Value::as_array)
+                    .is_some_and(|kinds| {
+                        kinds
+                            .iter()
+                            .any(|k| k.as_str().is_some_and(|k| k.ends_with(kind)))
+                    })
+            })
+            .filter_map(|t| t.get("name").and_then(Value::as_str))
+            .collect();
+        match names.as_slice() {
+            [name] if kind == "lib" => {
+                let stem = format!("lib{}", name.replace('-', "_"));
+                return [".so", ".a", ".rlib"]
+                    .iter()
+                    .map(|extension| dir.join(format!("{}{}", stem, extension)))
+                    .find(|path| path.exists())
+                    .with_context(|| format!("{} has not been built in {}", stem, dir.display()));
+            }
+            [name] => name.to_string(),
+            [] => bail!("The package has no {} target", kind),
+            _ => bail!("The package has several binaries; choose one with --bin"),
+        }
+    };
+
+    let path = dir.join(name);
+    if !path.exists() {
+        bail!("{} has not been built yet", path.display());
+    }
+    Ok(path)
+}
+
+/// The readelf-rs installed alongside us, or else the one on the PATH.
+fn readelf() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            let sibling = exe.with_file_name(format!("readelf-rs{}", std::env::consts::EXE_SUFFIX));
+            sibling.exists().then_some(sibling)
+        })
+        .unwrap_or_else(|| PathBuf::from("readelf-rs"))
+}
+
+fn run() -> Result<i32> {
+    // Cargo runs us as `cargo-readelf readelf ...`.
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+    if args.get(1).is_some_and(|arg| arg == "readelf") {
+        args.remove(1);
+    }
+    let matches = build_cli().get_matches_from(args);
+
+    let artifact = if matches.get_flag("no-build") {
+        locate(&matches)?
+    } else {
+        build(&matches)?
+    };
+
+    let readelf_args = matches
+        .get_many::<OsString>("readelf-args")
+        .into_iter()
+        .flatten();
+    let status = Process::new(readelf())
+        .args(readelf_args)
+        .arg(Path::new(&artifact))
+        .status()
+        .context("Failed to run readelf-rs")?;
+    Ok(status.code().unwrap_or(1))
+}
+
+fn main() {
+    let status = match run() {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            1
+        }
+    };
+    std::process::exit(status);
+}
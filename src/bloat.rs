@@ -0,0 +1,105 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use crate::demangle;
+use crate::elf::ElfFile;
+use crate::section::{SHN_LORESERVE, SHN_UNDEF};
+use crate::strtab::SymbolTable;
+
+struct Total {
+    symbols: usize,
+    size: u64,
+}
+
+fn add(totals: &mut HashMap<String, Total>, key: String, size: u64) {
+    let total = totals.entry(key).or_insert(Total {
+        symbols: 0,
+        size: 0,
+    });
+    total.symbols += 1;
+    total.size = total.size.saturating_add(size);
+}
+
+/// Sorted by size, largest first; ties are broken by name to keep the
+/// output stable.
+fn largest(totals: HashMap<String, Total>, top: usize) -> Vec<(String, Total)> {
+    let mut totals: Vec<_> = totals.into_iter().collect();
+    totals.sort_by(|(a_name, a), (b_name, b)| b.size.cmp(&a.size).then(a_name.cmp(b_name)));
+    totals.truncate(top);
+    totals
+}
+
+/// Prints the biggest contributors to the binary's size, aggregated by
+/// section and by crate/namespace, as seen through the symbol table.
+pub fn print_bloat(elf_file: &ElfFile, top: usize) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let Some(symtab) = elf_file.symbol_table(&sections) else {
+        println!("No symbol table found; cannot attribute sizes to symbols.");
+        return Ok(());
+    };
+
+    let mut by_section = HashMap::new();
+    let mut by_namespace = HashMap::new();
+    let mut by_symbol = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut total = 0u64;
+
+    let symbols = SymbolTable::new(elf_file, &sections, symtab)?;
+    for (index, symbol) in symbols.iter().enumerate() {
+        if symbol.st_size == 0 || symbol.st_shndx == SHN_UNDEF || symbol.st_shndx >= SHN_LORESERVE {
+            continue;
+        }
+
+        // Aliases share an address and size; only count the storage once.
+        if !seen.insert((symbol.st_shndx, symbol.st_value, symbol.st_size)) {
+            continue;
+        }
+
+        let section_name = sections
+            .get(symbol.st_shndx as usize)
+            .and_then(|s| elf_file.section_name(&sections, s).ok())
+            .unwrap_or("<unknown>");
+        let name = demangle::demangle(symbols.name(index)?);
+        let namespace = demangle::namespace(&name).unwrap_or("<global>").to_string();
+
+        add(&mut by_section, section_name.to_string(), symbol.st_size);
+        add(&mut by_namespace, namespace, symbol.st_size);
+        add(&mut by_symbol, name, symbol.st_size);
+        total = total.saturating_add(symbol.st_size);
+    }
+
+    let percent = |size: u64| {
+        if total == 0 {
+            0.0
+        } else {
+            size as f64 * 100.0 / total as f64
+        }
+    };
+
+    println!(
+        "Symbol size accounting ({} bytes in {} symbols):",
+        total,
+        seen.len()
+    );
+
+    for (title, totals) in [
+        ("section", by_section),
+        ("crate/namespace", by_namespace),
+        ("symbol", by_symbol),
+    ] {
+        println!();
+        println!("Top {} by {}:", top, title);
+        println!("  {:>12} {:>6} {:>8}  Name", "Size", "%", "Symbols");
+        for (name, t) in largest(totals, top) {
+            println!(
+                "  {:>12} {:>5.1}% {:>8}  {}",
+                t.size,
+                percent(t.size),
+                t.symbols,
+                name
+            );
+        }
+    }
+
+    Ok(())
+}
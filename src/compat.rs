@@ -0,0 +1,398 @@
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::ffi::OsString;
+
+fn flag(id: &'static str, short: char, long: &'static str, help: &'static str) -> Arg {
+    Arg::new(id)
+        .short(short)
+        .long(long)
+        .help(help)
+        .action(ArgAction::SetTrue)
+}
+
+fn dump(id: &'static str, short: char, long: &'static str, help: &'static str) -> Arg {
+    Arg::new(id)
+        .short(short)
+        .long(long)
+        .help(help)
+        .value_name("NUMBER|NAME")
+        .action(ArgAction::Append)
+}
+
+/// Adds the options of GNU readelf that are not already part of the CLI,
+/// with the same short and long names, so that the binary can stand in for
+/// `readelf` in scripts. `-H` and `-v` take the place of clap's help and
+/// version flags, since `-h` and `-V` mean something else to readelf.
+pub fn gnu_args(command: Command) -> Command {
+    command
+        .disable_help_flag(true)
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("help")
+                .short('H')
+                .long("help")
+                .help("Display this information")
+                .action(ArgAction::Help),
+        )
+        .arg(
+            Arg::new("version")
+                .short('v')
+                .long("version")
+                .help("Display the version number of readelf-rs")
+                .action(ArgAction::Version),
+        )
+        .arg(flag(
+            "all",
+            'a',
+            "all",
+            "Equivalent to: -h -l -S -s -r -d -V -A -I -n -u -g",
+        ))
+        .arg(flag(
+            "file-header",
+            'h',
+            "file-header",
+            "Display the ELF file header",
+        ))
+        .arg(
+            flag(
+                "section-headers",
+                'S',
+                "section-headers",
+                "Display the sections' header",
+            )
+            .visible_alias("sections"),
+        )
+        .arg(flag(
+            "section-groups",
+            'g',
+            "section-groups",
+            "Display the section groups",
+        ))
+        .arg(flag(
+            "section-details",
+            't',
+            "section-details",
+            "Display the section details",
+        ))
+        .arg(flag("headers", 'e', "headers", "Equivalent to: -h -l -S"))
+        .arg(flag("syms", 's', "syms", "Display the symbol table").visible_alias("symbols"))
+        .arg(
+            Arg::new("dyn-syms")
+                .long("dyn-syms")
+                .help("Display the dynamic symbol table")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("lto-syms")
+                .long("lto-syms")
+                .help(
+                    "Display the LTO symbol tables of GCC LTO objects and of embedded LLVM bitcode",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("sym-base")
+                .long("sym-base")
+                .help("Force base for symbol sizes")
+                .value_parser(["0", "8", "10", "16"])
+                .require_equals(true),
+        )
+        .arg(
+            Arg::new("demangle")
+                .short('C')
+                .long("demangle")
+                .help("Decode mangled/processed symbol names")
+                .value_name("STYLE")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("auto"),
+        )
+        .arg(
+            Arg::new("no-demangle")
+                .long("no-demangle")
+                .help("Do not demangle low-level symbol names (default)")
+                .overrides_with("demangle")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("recurse-limit")
+                .long("recurse-limit")
+                .help("Enable a demangling recursion limit (default)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-recurse-limit")
+                .long("no-recurse-limit")
+                .help("Disable a demangling recursion limit")
+                .overrides_with("recurse-limit")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("unicode")
+                .short('U')
+                .long("unicode")
+                .help("Display unicode characters in symbol names")
+                .value_parser(["default", "show", "invalid", "hex", "escape", "highlight"])
+                .value_name("MODE"),
+        )
+        .arg(flag(
+            "extra-sym-info",
+            'X',
+            "extra-sym-info",
+            "Display extra information when showing symbols: the section each is defined in, and for objects where each relocation applies and points",
+        ))
+        .arg(flag(
+            "notes",
+            'n',
+            "notes",
+            "Display the core notes (if present)",
+        ))
+        .arg(flag(
+            "unwind",
+            'u',
+            "unwind",
+            "Display the unwind info (if present)",
+        ))
+        .arg(flag(
+            "dynamic",
+            'd',
+            "dynamic",
+            "Display the dynamic section (if present)",
+        ))
+        .arg(flag(
+            "version-info",
+            'V',
+            "version-info",
+            "Display the version sections (if present)",
+        ))
+        .arg(flag(
+            "arch-specific",
+            'A',
+            "arch-specific",
+            "Display architecture specific information (if any)",
+        ))
+        .arg(flag(
+            "archive-index",
+            'c',
+            "archive-index",
+            "Display the symbol/file index in an archive",
+        ))
+        .arg(flag(
+            "use-dynamic",
+            'D',
+            "use-dynamic",
+            "Use the dynamic section info when displaying symbols",
+        ))
+        .arg(
+            flag(
+                "lint",
+                'L',
+                "lint",
+                "Display warning messages for possible problems",
+            )
+            .visible_alias("enable-checks"),
+        )
+        .arg(dump(
+            "hex-dump",
+            'x',
+            "hex-dump",
+            "Dump the contents of section <number|name> as bytes",
+        ))
+        .arg(dump(
+            "string-dump",
+            'p',
+            "string-dump",
+            "Dump the contents of section <number|name> as strings",
+        ))
+        .arg(dump(
+            "relocated-dump",
+            'R',
+            "relocated-dump",
+            "Dump the relocated contents of section <number|name>",
+        ))
+        .arg(flag(
+            "decompress",
+            'z',
+            "decompress",
+            "Decompress section before dumping it",
+        ))
+        .arg(
+            Arg::new("debug-dump")
+                .short('w')
+                .long("debug-dump")
+                .help("Display the contents of DWARF debug sections")
+                .value_name("SECTIONS")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("all")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("dwarf-depth")
+                .long("dwarf-depth")
+                .help("Do not display DIEs at depth N or greater")
+                .value_name("N")
+                .require_equals(true),
+        )
+        .arg(
+            Arg::new("dwarf-start")
+                .long("dwarf-start")
+                .help("Display DIEs starting at offset N")
+                .value_name("N")
+                .require_equals(true),
+        )
+        .arg(
+            Arg::new("ctf")
+                .long("ctf")
+                .help("Display CTF info from section <number|name>")
+                .value_name("NUMBER|NAME")
+                .require_equals(true),
+        )
+        .arg(
+            Arg::new("sframe")
+                .long("sframe")
+                .help("Display SFrame info from section NAME")
+                .value_name("NAME")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value(".sframe"),
+        )
+        .arg(flag(
+            "histogram",
+            'I',
+            "histogram",
+            "Display histogram of bucket list lengths",
+        ))
+        .arg(flag(
+            "wide",
+            'W',
+            "wide",
+            "Allow output width to exceed 80 characters (always the case)",
+        ))
+        .arg(flag(
+            "silent-truncation",
+            'T',
+            "silent-truncation",
+            "Do not add [...] to truncated symbol names (they are never truncated)",
+        ))
+        .arg(flag(
+            "process-links",
+            'P',
+            "process-links",
+            "Display the contents of non-debug sections in separate debuginfo files",
+        ))
+}
+
+/// The readelf displays in the order GNU readelf prints them.
+pub const PASS_ORDER: &[&str] = &[
+    "file-header",
+    "section-headers",
+    "section-groups",
+    "program-headers",
+    "dynamic",
+    "relocs",
+    "unwind",
+    "syms",
+    "dyn-syms",
+    "histogram",
+    "lto-syms",
+    "version-info",
+    "hex-dump",
+    "string-dump",
+    "relocated-dump",
+    "debug-dump",
+    "ctf",
+    "sframe",
+    "notes",
+    "arch-specific",
+];
+
+/// The readelf displays process_file knows how to print.
+const IMPLEMENTED: &[&str] = &[
+    "file-header",
+    "section-headers",
+    "section-groups",
+    "program-headers",
+    "dynamic",
+    "relocs",
+    "unwind",
+    "syms",
+    "dyn-syms",
+    "histogram",
+    "lto-syms",
+    "version-info",
+    "hex-dump",
+    "string-dump",
+    "notes",
+    "arch-specific",
+];
+
+/// Rewrites readelf's `-wLETTERS` into `--debug-dump=LETTERS`. The letters
+/// are glued to the option, which clap would otherwise take for a string of
+/// short flags, while a separate argument after `-w` is an input file.
+pub fn normalize_args(args: &mut [OsString]) {
+    for arg in args.iter_mut() {
+        if let Some(letters) = arg.to_str().and_then(|a| a.strip_prefix("-w"))
+            && !letters.is_empty()
+            && !letters.starts_with('=')
+        {
+            *arg = OsString::from(format!("--debug-dump={}", letters));
+        }
+    }
+}
+
+/// Whether the display `id` is selected, either directly or through one of
+/// readelf's composite options. Only the command line selects displays.
+pub fn wants(matches: &ArgMatches, id: &str) -> bool {
+    let composites: &[&str] = match id {
+        "file-header" | "program-headers" | "section-headers" => &["all", "headers"],
+        "syms" | "relocs" | "dynamic" | "version-info" | "arch-specific" | "histogram"
+        | "notes" | "unwind" | "section-groups" => &["all"],
+        _ => &[],
+    };
+
+    given(matches, id) || composites.iter().any(|c| given(matches, c))
+}
+
+fn given(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+/// Whether `id` is an option that produces a report, out of readelf's and
+/// `displays`.
+pub fn is_display(id: &str, displays: &[&str]) -> bool {
+    displays
+        .iter()
+        .chain(PASS_ORDER)
+        .chain(&["all", "headers", "section-details", "archive-index", "lint"])
+        .any(|display| *display == id)
+}
+
+/// Whether any option that produces a report was given, out of readelf's
+/// and `displays`. Like readelf, there is nothing to print otherwise.
+pub fn has_display(matches: &ArgMatches, displays: &[&str]) -> bool {
+    matches
+        .ids()
+        .any(|id| is_display(id.as_str(), displays) && given(matches, id.as_str()))
+}
+
+/// The readelf displays and options that were asked for but are not
+/// implemented yet, so that nobody takes the output for what readelf would
+/// have printed. Their ids are also their long option names.
+pub fn unsupported(matches: &ArgMatches) -> Vec<&'static str> {
+    PASS_ORDER
+        .iter()
+        .chain(&[
+            "section-details",
+            "archive-index",
+            "use-dynamic",
+            "decompress",
+            "dwarf-depth",
+            "dwarf-start",
+            "no-recurse-limit",
+            "process-links",
+        ])
+        .copied()
+        .filter(|id| !IMPLEMENTED.contains(id) && wants(matches, id))
+        .collect()
+}
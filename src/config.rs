@@ -0,0 +1,148 @@
+use anyhow::{Context, Result, anyhow, bail};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, Command};
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::PathBuf;
+
+/// Environment variable holding extra default options, e.g. "--wide --demangle".
+const OPTS_ENV: &str = "READELF_RS_OPTS";
+
+fn config_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(base.join("readelf-rs").join("config.toml"))
+}
+
+/// A long option name and its value, if it was given one.
+type Setting = (String, Option<String>);
+
+/// Turns the config file's top-level keys into long options: `wide = true`
+/// stands for `--wide` and `format = "text"` for `--format=text`.
+fn settings_from_config(contents: &str) -> Result<Vec<Setting>> {
+    let table: toml::Table = contents.parse().context("Failed to parse config file")?;
+
+    let mut settings = Vec::new();
+    for (key, value) in table {
+        match value {
+            toml::Value::Boolean(true) => settings.push((key, None)),
+            toml::Value::Boolean(false) => {}
+            toml::Value::String(s) => settings.push((key, Some(s))),
+            toml::Value::Integer(i) => settings.push((key, Some(i.to_string()))),
+            _ => bail!("Unsupported value for '{}' in config file", key),
+        }
+    }
+
+    Ok(settings)
+}
+
+/// Splits the environment's `--name` and `--name=value` options.
+fn settings_from_env(opts: &str) -> Result<Vec<Setting>> {
+    opts.split_whitespace()
+        .map(|opt| {
+            let Some(opt) = opt.strip_prefix("--") else {
+                bail!("'{}' is not a long option", opt);
+            };
+            Ok(match opt.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (opt.to_string(), None),
+            })
+        })
+        .collect()
+}
+
+/// Makes `value` the default of the option named `name`. Options that
+/// select a report, as told by `is_report`, are refused: a default would
+/// print the report on every run, with no way to turn it off.
+fn set_default(
+    command: Command,
+    (name, value): Setting,
+    is_report: &impl Fn(&str) -> bool,
+) -> Result<Command> {
+    let Some(arg) = command
+        .get_arguments()
+        .find(|a| a.get_long() == Some(&name))
+    else {
+        bail!("Unknown option '{}'", name);
+    };
+    if is_report(arg.get_id().as_str()) {
+        bail!(
+            "'{}' selects a report, which only the command line can do",
+            name
+        );
+    }
+
+    if !matches!(
+        arg.get_action(),
+        ArgAction::SetTrue | ArgAction::Set | ArgAction::Append
+    ) {
+        bail!("'{}' cannot be given a default", name);
+    }
+
+    // Parse the option to fill in the value of an option given none, like
+    // `--demangle`, then check the values, which clap takes on trust for
+    // defaults.
+    let id = arg.get_id().clone();
+    let option = match &value {
+        Some(value) => format!("--{}={}", name, value),
+        None => format!("--{}", name),
+    };
+    let Some(values) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(["readelf-rs", &option])
+        .ok()
+        .filter(|m| m.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+        .and_then(|m| {
+            Some(
+                m.get_raw(id.as_str())?
+                    .map(OsStr::to_owned)
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .filter(|values| !values.is_empty())
+    else {
+        bail!("Invalid option '{}'", option);
+    };
+    Command::new("readelf-rs")
+        .no_binary_name(true)
+        .arg(
+            Arg::new("value")
+                .value_parser(arg.get_value_parser().clone())
+                .action(ArgAction::Append),
+        )
+        .try_get_matches_from(&values)
+        .map_err(|_| anyhow!("Invalid option '{}'", option))?;
+
+    Ok(command.mut_arg(id, |arg| arg.default_values(values)))
+}
+
+/// Applies the options of the config file, then those of the environment,
+/// as defaults of `command`, so that the command line overrides them and
+/// they are not mistaken for options given on it.
+pub fn apply_defaults(mut command: Command, is_report: impl Fn(&str) -> bool) -> Result<Command> {
+    if let Some(path) = config_path().filter(|p| p.exists()) {
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let settings =
+            settings_from_config(&contents).with_context(|| format!("In {}", path.display()))?;
+        for setting in settings {
+            command = set_default(command, setting, &is_report)
+                .with_context(|| format!("In {}", path.display()))?;
+        }
+    }
+
+    if let Ok(opts) = env::var(OPTS_ENV) {
+        let settings = settings_from_env(&opts).with_context(|| format!("In {}", OPTS_ENV))?;
+        for setting in settings {
+            command = set_default(command, setting, &is_report)
+                .with_context(|| format!("In {}", OPTS_ENV))?;
+        }
+    }
+
+    Ok(command)
+}
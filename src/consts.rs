@@ -0,0 +1,107 @@
+//! Named ELF constants, grouped by the field they are found in. Each field
+//! type gets an associated constant per value defined by the spec, a table of
+//! all of them, and conversions to and from their names, so that neither the
+//! parser nor the display code needs to spell out magic numbers.
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::dynamic::DynamicTag;
+use crate::section::SectionType;
+
+pub use crate::dynamic::dt;
+pub use crate::elf::{ELFOSABI_FREEBSD, ELFOSABI_GNU, ELFOSABI_NONE, ELFOSABI_SOLARIS, et};
+pub use crate::notes::{nt, nt_gnu};
+pub use crate::section::{
+    GRP_COMDAT, GRP_MASKOS, GRP_MASKPROC, SHF_MASKOS, SHF_MASKPROC, shf, sht,
+};
+pub use crate::section::{SHN_LORESERVE, SHN_UNDEF, SHN_XINDEX};
+pub use crate::segment::{PF_R, PF_W, PF_X, pt};
+pub use crate::symbol::{stb, stt, stv};
+
+// The processor-specific values, which only mean something for their
+// machine and so take the same numbers as those of other machines. They
+// are left out of the tables above, whose names are unique.
+
+pub const SHT_X86_64_UNWIND: SectionType = SectionType(0x70000001);
+pub const SHT_ARM_EXIDX: SectionType = SectionType(0x70000001);
+pub const SHT_ARM_PREEMPTMAP: SectionType = SectionType(0x70000002);
+pub const SHT_ARM_ATTRIBUTES: SectionType = SectionType(0x70000003);
+pub const SHT_AARCH64_ATTRIBUTES: SectionType = SectionType(0x70000003);
+pub const SHT_RISCV_ATTRIBUTES: SectionType = SectionType(0x70000003);
+
+pub const SHF_X86_64_LARGE: u64 = 0x10000000;
+pub const SHF_ARM_PURECODE: u64 = 0x20000000;
+pub const SHF_PPC_VLE: u64 = 0x10000000;
+
+pub const DT_AARCH64_BTI_PLT: DynamicTag = DynamicTag(0x70000001);
+pub const DT_AARCH64_PAC_PLT: DynamicTag = DynamicTag(0x70000003);
+pub const DT_AARCH64_MEMTAG_MODE: DynamicTag = DynamicTag(0x70000009);
+pub const DT_AARCH64_MEMTAG_HEAP: DynamicTag = DynamicTag(0x7000000b);
+pub const DT_AARCH64_MEMTAG_STACK: DynamicTag = DynamicTag(0x7000000c);
+pub const DT_AARCH64_MEMTAG_GLOBALS: DynamicTag = DynamicTag(0x7000000d);
+pub const DT_AARCH64_MEMTAG_GLOBALSSZ: DynamicTag = DynamicTag(0x7000000f);
+pub const DT_SPARC_REGISTER: DynamicTag = DynamicTag(0x70000001);
+
+/// Android asks for memory tagging with a note instead of dynamic tags.
+pub const NT_ANDROID_TYPE_MEMTAG: u32 = 4;
+pub const NT_MEMTAG_LEVEL_MASK: u32 = 3;
+pub const NT_MEMTAG_HEAP: u32 = 4;
+pub const NT_MEMTAG_STACK: u32 = 8;
+
+/// Parses a constant given by name, with or without `prefix` and in any
+/// case, or as a decimal or `0x` hexadecimal number.
+pub fn parse<T: Copy>(
+    value: &str,
+    prefix: &str,
+    all: &[(&str, T)],
+    from_number: impl Fn(i128) -> Option<T>,
+) -> Result<T> {
+    let upper = value.trim().to_ascii_uppercase();
+    let name = upper.strip_prefix(prefix).unwrap_or(&upper);
+    if let Some(&(_, constant)) = all.iter().find(|(n, _)| *n == name) {
+        return Ok(constant);
+    }
+
+    let number = match name.strip_prefix("0X") {
+        Some(hex) => i128::from_str_radix(hex, 16),
+        None => name.parse(),
+    }
+    .with_context(|| format!("Unknown {}* constant {:?}", prefix, value))?;
+    from_number(number).ok_or_else(|| anyhow!("{} is out of range for {}*", value, prefix))
+}
+
+/// Defines the named values of a newtype around an ELF field: associated
+/// constants, an `ALL` table in spec order, `name()` for the name without its
+/// prefix, `FromStr`, and a module of plain constants named like the spec's
+/// (`sht::PROGBITS` for `SHT_PROGBITS`). Values with several names keep the
+/// first one as their canonical name.
+macro_rules! constants {
+    ($type:ident($repr:ty), $module:ident, $prefix:literal, { $($name:ident = $value:expr),* $(,)? }) => {
+        impl $type {
+            $(pub const $name: Self = Self($value);)*
+
+            /// Every named value, in the order the spec lists them.
+            pub const ALL: &'static [(&'static str, Self)] = &[$((stringify!($name), Self($value))),*];
+
+            /// The name of the value without its prefix, if it has one.
+            pub fn name(&self) -> Option<&'static str> {
+                Self::ALL.iter().find(|(_, v)| v == self).map(|&(name, _)| name)
+            }
+        }
+
+        impl std::str::FromStr for $type {
+            type Err = anyhow::Error;
+
+            fn from_str(s: &str) -> anyhow::Result<Self> {
+                crate::consts::parse(s, $prefix, Self::ALL, |n| <$repr>::try_from(n).ok().map(Self))
+            }
+        }
+
+        pub mod $module {
+            use super::$type;
+            $(pub const $name: $type = $type::$name;)*
+        }
+    };
+}
+
+pub(crate) use constants;
@@ -0,0 +1,217 @@
+//! Converting between the three ways of naming a place in an ELF file: its
+//! offset in the file, the virtual address it is loaded at, and a section
+//! with an offset into it.
+
+use anyhow::{Result, bail};
+
+use crate::addr2line::parse_address;
+use crate::elf::{ElfFile, ElfType};
+use crate::section::{SHF_TLS, SectionHeader, SectionType};
+use crate::segment::{ProgramHeader, SegmentType};
+
+/// A place as given on the command line.
+enum Location {
+    Offset(u64),
+    Address(u64),
+    Section(String, u64),
+}
+
+/// Parses `offset:HEX`, `vaddr:HEX` or a bare hex address, or
+/// `SECTION+HEX`, such as `.text+0x10`.
+fn parse_location(spec: &str) -> Result<Location> {
+    if let Some(offset) = spec.strip_prefix("offset:") {
+        return Ok(Location::Offset(parse_address(offset)?));
+    }
+    if let Some(address) = spec.strip_prefix("vaddr:") {
+        return Ok(Location::Address(parse_address(address)?));
+    }
+    if spec.starts_with('.') || spec.contains('+') {
+        return Ok(match spec.rsplit_once('+') {
+            Some((name, offset)) => Location::Section(name.to_string(), parse_address(offset)?),
+            None => Location::Section(spec.to_string(), 0),
+        });
+    }
+    match parse_address(spec) {
+        Ok(address) => Ok(Location::Address(address)),
+        Err(_) => bail!(
+            "Expected offset:HEX, vaddr:HEX, a hex address or SECTION+HEX, got {:?}",
+            spec
+        ),
+    }
+}
+
+/// The section that holds a file offset: the allocated one when an empty
+/// or non-allocated section starts at the same place.
+fn section_at_offset(sections: &[SectionHeader], offset: u64) -> Option<&SectionHeader> {
+    sections
+        .iter()
+        .filter(|s| {
+            s.has_file_data()
+                && s.sh_size > 0
+                && (s.sh_offset..s.sh_offset + s.sh_size).contains(&offset)
+        })
+        .max_by_key(|s| s.is_alloc())
+}
+
+/// The section loaded at an address, leaving out `.tbss`, whose addresses
+/// are only a template for each thread's block.
+fn section_at_address(sections: &[SectionHeader], address: u64) -> Option<&SectionHeader> {
+    sections.iter().find(|s| {
+        s.contains_addr(address) && !(s.sh_type == SectionType::NOBITS && s.sh_flags & SHF_TLS != 0)
+    })
+}
+
+/// Prints every representation of the place `spec` names.
+pub fn print_conversion(elf_file: &ElfFile, spec: &str) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let segments = elf_file.program_headers()?;
+    let loads: Vec<(usize, &ProgramHeader)> = segments
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.p_type == SegmentType::LOAD)
+        .collect();
+
+    let mut notes = Vec::new();
+    let (offset, address, section) = match parse_location(spec)? {
+        Location::Offset(offset) => {
+            if offset >= elf_file.data().len() as u64 {
+                notes.push(format!(
+                    "The offset is past the end of the file ({:#x} bytes)",
+                    elf_file.data().len()
+                ));
+            }
+            let address = loads
+                .iter()
+                .find(|(_, s)| (s.p_offset..s.p_offset + s.p_filesz).contains(&offset))
+                .map(|(_, s)| s.p_vaddr + (offset - s.p_offset));
+            let section = section_at_offset(&sections, offset);
+            (
+                Some(offset),
+                address,
+                section.map(|s| (s, offset - s.sh_offset)),
+            )
+        }
+        Location::Address(address) => {
+            let offset = loads
+                .iter()
+                .find(|(_, s)| (s.p_vaddr..s.p_vaddr + s.p_filesz).contains(&address))
+                .map(|(_, s)| s.p_offset + (address - s.p_vaddr));
+            let section = section_at_address(&sections, address);
+            // Every section of an object starts at 0, so an address names a
+            // place in each of them.
+            let holding = sections.iter().filter(|s| s.contains_addr(address)).count();
+            if loads.is_empty() && elf_file.elf_type() == ElfType::REL && holding > 1 {
+                notes.push(format!(
+                    "Sections of relocatable objects all start at 0, so {} of them hold this \
+                     address; this is the first. Name one as SECTION+HEX instead",
+                    holding
+                ));
+            }
+            // Files without segments, like objects, still have section addresses.
+            let offset = offset.or_else(|| {
+                section
+                    .filter(|s| loads.is_empty() && s.has_file_data())
+                    .map(|s| s.sh_offset + (address - s.sh_addr))
+            });
+            (
+                offset,
+                Some(address),
+                section.map(|s| (s, address - s.sh_addr)),
+            )
+        }
+        Location::Section(name, within) => {
+            let mut matching = sections
+                .iter()
+                .filter(|s| elf_file.section_name(&sections, s).ok() == Some(name.as_str()));
+            let Some(section) = matching.next() else {
+                bail!("There is no section named {}", name);
+            };
+            if matching.next().is_some() {
+                notes.push(format!(
+                    "There is more than one {}; this is the first",
+                    name
+                ));
+            }
+            if within >= section.sh_size {
+                notes.push(format!(
+                    "The offset is past the end of {} ({:#x} bytes)",
+                    name, section.sh_size
+                ));
+            }
+            let offset = section
+                .has_file_data()
+                .then_some(section.sh_offset + within);
+            let address = (section.is_alloc() && (section.sh_addr != 0 || !loads.is_empty()))
+                .then_some(section.sh_addr + within);
+            (offset, address, Some((section, within)))
+        }
+    };
+
+    let segment = address
+        .and_then(|a| {
+            loads
+                .iter()
+                .find(|(_, s)| (s.p_vaddr..s.p_vaddr + s.p_memsz).contains(&a))
+        })
+        .or_else(|| {
+            offset.and_then(|o| {
+                loads
+                    .iter()
+                    .find(|(_, s)| (s.p_offset..s.p_offset + s.p_filesz).contains(&o))
+            })
+        });
+    println!("{}:", spec);
+    let zero_filled = section.is_some_and(|(s, _)| !s.has_file_data()) || segment.is_some();
+    match offset {
+        Some(offset) => println!("  File offset:      {:#x}", offset),
+        None if zero_filled => {
+            println!("  File offset:      none: the bytes are zero-filled at load time, not stored")
+        }
+        None if address.is_some() => {
+            println!("  File offset:      none: the address is not in any loadable segment")
+        }
+        None => println!("  File offset:      none"),
+    }
+    match address {
+        Some(address) => println!("  Virtual address:  {:#x}", address),
+        None => println!("  Virtual address:  none: not loaded"),
+    }
+    if let (Some(address), Some((_, segment))) = (address, segment)
+        && segment.p_paddr != segment.p_vaddr
+    {
+        println!(
+            "  Physical address: {:#x}",
+            segment.p_paddr + (address - segment.p_vaddr)
+        );
+    }
+    match section {
+        Some((section, within)) => println!(
+            "  Section:          {}+{:#x} (the section is at {:#x}, file offset {:#x})",
+            elf_file.section_name(&sections, section)?,
+            within,
+            section.sh_addr,
+            section.sh_offset
+        ),
+        None => println!("  Section:          none"),
+    }
+    match segment {
+        Some((index, segment)) => {
+            let start = match (address, offset) {
+                (Some(address), _) => address - segment.p_vaddr,
+                (None, Some(offset)) => offset - segment.p_offset,
+                (None, None) => 0,
+            };
+            println!(
+                "  Segment:          {} (LOAD, {}) +{:#x}",
+                index,
+                segment.flags_string().trim_end(),
+                start
+            );
+        }
+        None => println!("  Segment:          none"),
+    }
+    for note in notes {
+        println!("  Note: {}", note);
+    }
+    Ok(())
+}
@@ -0,0 +1,449 @@
+//! What a core dump says about the process it was taken from: its threads
+//! and their registers, the files it had mapped and the memory it had, from
+//! the notes the kernel writes and the PT_LOAD segments.
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::elf::ElfFile;
+use crate::emachine::EMachine;
+use crate::notes::{self, NT_AUXV, NT_FILE, NT_PRPSINFO, NT_PRSTATUS, NT_SIGINFO, Note};
+use crate::segment::SegmentType;
+
+/// A file mapping of the process, from the NT_FILE note.
+#[derive(Debug, Clone)]
+pub struct MappedFile {
+    pub start: u64,
+    pub end: u64,
+    /// Where in the file the mapping starts, in bytes.
+    pub offset: u64,
+    pub path: String,
+}
+
+/// A thread of the process, from its NT_PRSTATUS note.
+#[derive(Debug, Clone)]
+pub struct Thread {
+    pub pid: u32,
+    /// The signal the thread was stopped by, or 0.
+    pub signal: u16,
+    /// The general purpose registers, in the order of the kernel's
+    /// `user_regs_struct` for the machine.
+    pub registers: Vec<u64>,
+    machine: EMachine,
+}
+
+/// The name and command line of the process, from NT_PRPSINFO.
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub pid: u32,
+    pub name: String,
+    pub args: String,
+}
+
+/// Where a machine's `user_regs_struct` keeps the registers an unwinder
+/// needs.
+struct Layout {
+    count: usize,
+    pc: usize,
+    sp: usize,
+    /// The slot of a register, by its DWARF number.
+    dwarf: fn(u16) -> Option<usize>,
+}
+
+/// The `user_regs_struct` slots of the DWARF registers rax, rdx, rcx, rbx,
+/// rsi, rdi, rbp, rsp, r8-r15 and the return address column, rip.
+const X86_64_SLOTS: [usize; 17] = [10, 12, 11, 5, 13, 14, 4, 19, 9, 8, 7, 6, 3, 2, 1, 0, 16];
+/// The same for eax, ecx, edx, ebx, esp, ebp, esi, edi and eip.
+const I386_SLOTS: [usize; 9] = [6, 1, 2, 0, 15, 5, 3, 4, 12];
+
+fn layout(machine: EMachine) -> Option<Layout> {
+    match machine {
+        EMachine::X8664 => Some(Layout {
+            count: 27,
+            pc: 16,
+            sp: 19,
+            dwarf: |r| X86_64_SLOTS.get(r as usize).copied(),
+        }),
+        EMachine::I386 => Some(Layout {
+            count: 17,
+            pc: 12,
+            sp: 15,
+            dwarf: |r| I386_SLOTS.get(r as usize).copied(),
+        }),
+        // x0-x30 and sp, then pc and pstate.
+        EMachine::Aarch64 => Some(Layout {
+            count: 34,
+            pc: 32,
+            sp: 31,
+            dwarf: |r| (r <= 31).then_some(r as usize),
+        }),
+        // pc takes the slot of x0, which always reads as zero.
+        EMachine::Riscv => Some(Layout {
+            count: 32,
+            pc: 0,
+            sp: 2,
+            dwarf: |r| (1..=31).contains(&r).then_some(r as usize),
+        }),
+        // r0-r31, then nip, msr, orig_r3, ctr, lr, xer, ccr, softe, trap,
+        // dar, dsisr and result.
+        EMachine::Ppc64 => Some(Layout {
+            count: 44,
+            pc: 32,
+            sp: 1,
+            dwarf: |r| (r <= 31).then_some(r as usize),
+        }),
+        _ => None,
+    }
+}
+
+impl Thread {
+    pub fn pc(&self) -> Option<u64> {
+        self.registers.get(layout(self.machine)?.pc).copied()
+    }
+
+    pub fn sp(&self) -> Option<u64> {
+        self.registers.get(layout(self.machine)?.sp).copied()
+    }
+
+    /// The registers by their DWARF numbers, the way call frame
+    /// information refers to them. Empty for machines whose register set
+    /// layout is not known.
+    pub fn dwarf_registers(&self) -> HashMap<u16, u64> {
+        let Some(layout) = layout(self.machine) else {
+            return HashMap::new();
+        };
+        // No machine here numbers its general purpose registers past 31.
+        (0..32)
+            .filter_map(|r| Some((r, *self.registers.get((layout.dwarf)(r)?)?)))
+            .collect()
+    }
+}
+
+/// Reads a native-endian word of the file's class.
+fn word(elf_file: &ElfFile, data: &[u8], offset: usize) -> Option<u64> {
+    if elf_file.is_64() {
+        let bytes = data.get(offset..offset + 8)?;
+        Some(u64::from_ne_bytes(bytes.try_into().unwrap()))
+    } else {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_ne_bytes(bytes.try_into().unwrap()) as u64)
+    }
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_ne_bytes(bytes.try_into().unwrap()))
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn core_notes<'a>(elf_file: &'a ElfFile, n_type: u32) -> Result<Vec<Note<'a>>> {
+    Ok(notes::notes(elf_file)?
+        .into_iter()
+        .filter(|n| n.name == "CORE" && n.n_type == n_type)
+        .collect())
+}
+
+/// The file mappings listed in the NT_FILE note, in address order.
+pub fn mapped_files(elf_file: &ElfFile) -> Result<Vec<MappedFile>> {
+    let Some(note) = core_notes(elf_file, NT_FILE)?.into_iter().next() else {
+        return Ok(Vec::new());
+    };
+    let desc = note.desc;
+    let size = if elf_file.is_64() { 8 } else { 4 };
+    let (Some(count), Some(page_size)) = (word(elf_file, desc, 0), word(elf_file, desc, size))
+    else {
+        return Ok(Vec::new());
+    };
+
+    // The names follow the count, the page size and three words per entry.
+    let names_start = count
+        .checked_mul(3)
+        .and_then(|n| n.checked_add(2))
+        .and_then(|n| usize::try_from(n).ok()?.checked_mul(size));
+    let mut names = names_start
+        .and_then(|start| desc.get(start..))
+        .unwrap_or(&[])
+        .split(|&b| b == 0);
+    let mut files = Vec::new();
+    for index in 0..count as usize {
+        let entry = (2 + index * 3) * size;
+        let (Some(start), Some(end), Some(page)) = (
+            word(elf_file, desc, entry),
+            word(elf_file, desc, entry + size),
+            word(elf_file, desc, entry + 2 * size),
+        ) else {
+            break;
+        };
+        let path = String::from_utf8_lossy(names.next().unwrap_or(&[])).into_owned();
+        // The mapping and the part of the file it maps must both make sense
+        // as ranges; anything else is a corrupt note.
+        let offset = page.checked_mul(page_size);
+        if end < start || offset.and_then(|o| o.checked_add(end - start)).is_none() {
+            eprintln!(
+                "readelf-rs: Warning: NT_FILE entry {} for {} has an invalid range \
+                 {:#x}-{:#x} at page {:#x}; it is skipped",
+                index, path, start, end, page
+            );
+            continue;
+        }
+        files.push(MappedFile {
+            start,
+            end,
+            offset: offset.unwrap_or(0),
+            path,
+        });
+    }
+    files.sort_by_key(|f| f.start);
+    Ok(files)
+}
+
+/// Reads a thread from its NT_PRSTATUS note.
+fn thread(elf_file: &ElfFile, note: &Note) -> Option<Thread> {
+    let machine = elf_file.machine();
+    // `elf_prstatus` has the signal information first, then the ids, then
+    // four timevals, which is where the 32 and 64-bit layouts part ways.
+    let (pid_offset, registers_offset) = if elf_file.is_64() {
+        (32, 112)
+    } else {
+        (24, 72)
+    };
+    let size = if elf_file.is_64() { 8 } else { 4 };
+
+    let desc = note.desc;
+    let signal = u16::from_ne_bytes(desc.get(12..14)?.try_into().unwrap());
+    let pid = u32_at(desc, pid_offset)?;
+    let available = desc.len().saturating_sub(registers_offset) / size;
+    let count = layout(machine).map_or(available, |l| l.count.min(available));
+    let registers = (0..count)
+        .filter_map(|i| word(elf_file, desc, registers_offset + i * size))
+        .collect();
+    Some(Thread {
+        pid,
+        signal,
+        registers,
+        machine,
+    })
+}
+
+/// The threads of the process, one per NT_PRSTATUS note. The first is the
+/// one that received the signal that dumped the core.
+pub fn threads(elf_file: &ElfFile) -> Result<Vec<Thread>> {
+    Ok(core_notes(elf_file, NT_PRSTATUS)?
+        .iter()
+        .filter_map(|note| thread(elf_file, note))
+        .collect())
+}
+
+/// A thread with the register sets the kernel wrote for it besides the
+/// general purpose ones: floating point, vector and the like.
+pub struct ThreadNotes<'a> {
+    pub thread: Thread,
+    pub notes: Vec<Note<'a>>,
+}
+
+/// The threads of the process with their register set notes, which follow
+/// each thread's NT_PRSTATUS up to the next one. The notes about the whole
+/// process that the kernel writes among the first thread's are left out.
+pub fn thread_notes<'a>(elf_file: &'a ElfFile) -> Result<Vec<ThreadNotes<'a>>> {
+    let mut threads: Vec<ThreadNotes> = Vec::new();
+    for note in notes::notes(elf_file)? {
+        let core = note.name == "CORE";
+        if core && note.n_type == NT_PRSTATUS {
+            if let Some(thread) = thread(elf_file, &note) {
+                threads.push(ThreadNotes {
+                    thread,
+                    notes: Vec::new(),
+                });
+            }
+        } else if core && [NT_PRPSINFO, NT_AUXV, NT_FILE, NT_SIGINFO].contains(&note.n_type) {
+            continue;
+        } else if let Some(thread) = threads.last_mut() {
+            thread.notes.push(note);
+        }
+    }
+    Ok(threads)
+}
+
+/// The signal the process dumped core on, from NT_SIGINFO.
+#[derive(Debug, Clone)]
+pub struct SignalInfo {
+    pub signal: u32,
+    pub code: i32,
+    /// The faulting address, for the signals that have one.
+    pub address: Option<u64>,
+}
+
+/// The `siginfo_t` of the signal that killed the process.
+pub fn signal_info(elf_file: &ElfFile) -> Result<Option<SignalInfo>> {
+    let Some(note) = core_notes(elf_file, NT_SIGINFO)?.into_iter().next() else {
+        return Ok(None);
+    };
+    let desc = note.desc;
+    let (Some(signal), Some(code)) = (u32_at(desc, 0), u32_at(desc, 8)) else {
+        return Ok(None);
+    };
+    // SIGILL, SIGTRAP, SIGBUS, SIGFPE and SIGSEGV put si_addr first in the
+    // union, which 64-bit ABIs align to 8 bytes.
+    let address = matches!(signal, 4 | 5 | 7 | 8 | 11)
+        .then(|| word(elf_file, desc, if elf_file.is_64() { 16 } else { 12 }))
+        .flatten();
+    Ok(Some(SignalInfo {
+        signal,
+        code: code as i32,
+        address,
+    }))
+}
+
+/// The process the core was dumped from, if it has an NT_PRPSINFO note.
+pub fn process(elf_file: &ElfFile) -> Result<Option<Process>> {
+    let Some(note) = core_notes(elf_file, NT_PRPSINFO)?.into_iter().next() else {
+        return Ok(None);
+    };
+    // 64-bit `elf_prpsinfo` has a long pr_flag and 32-bit uids, where the
+    // 32-bit one has an int flag and 16-bit uids.
+    let (pid_offset, name_offset) = if elf_file.is_64() { (24, 40) } else { (12, 28) };
+    let desc = note.desc;
+    Ok(Some(Process {
+        pid: u32_at(desc, pid_offset).unwrap_or(0),
+        name: c_string(desc.get(name_offset..name_offset + 16).unwrap_or(&[])),
+        args: c_string(desc.get(name_offset + 16..name_offset + 96).unwrap_or(&[])),
+    }))
+}
+
+/// The auxiliary vector the kernel gave the process, as (type, value)
+/// pairs, from NT_AUXV. The AT_NULL entry that ends it is left out.
+pub fn auxv(elf_file: &ElfFile) -> Result<Vec<(u64, u64)>> {
+    Ok(core_notes(elf_file, NT_AUXV)?
+        .first()
+        .map_or_else(Vec::new, |note| parse_auxv(note.desc, elf_file.is_64())))
+}
+
+/// Splits an auxiliary vector, as the kernel lays it out in NT_AUXV and
+/// `/proc/<pid>/auxv`, into its (type, value) pairs, up to AT_NULL.
+pub fn parse_auxv(data: &[u8], is_64: bool) -> Vec<(u64, u64)> {
+    let size = if is_64 { 8 } else { 4 };
+    let word = |bytes: &[u8]| {
+        if is_64 {
+            u64::from_ne_bytes(bytes.try_into().unwrap())
+        } else {
+            u32::from_ne_bytes(bytes.try_into().unwrap()) as u64
+        }
+    };
+    data.chunks_exact(size * 2)
+        .map(|entry| (word(&entry[..size]), word(&entry[size..])))
+        .take_while(|&(key, _)| key != 0)
+        .collect()
+}
+
+/// The name of a Linux signal.
+pub fn signal_name(signal: u16) -> Option<&'static str> {
+    const NAMES: [&str; 31] = [
+        "SIGHUP",
+        "SIGINT",
+        "SIGQUIT",
+        "SIGILL",
+        "SIGTRAP",
+        "SIGABRT",
+        "SIGBUS",
+        "SIGFPE",
+        "SIGKILL",
+        "SIGUSR1",
+        "SIGSEGV",
+        "SIGUSR2",
+        "SIGPIPE",
+        "SIGALRM",
+        "SIGTERM",
+        "SIGSTKFLT",
+        "SIGCHLD",
+        "SIGCONT",
+        "SIGSTOP",
+        "SIGTSTP",
+        "SIGTTIN",
+        "SIGTTOU",
+        "SIGURG",
+        "SIGXCPU",
+        "SIGXFSZ",
+        "SIGVTALRM",
+        "SIGPROF",
+        "SIGWINCH",
+        "SIGIO",
+        "SIGPWR",
+        "SIGSYS",
+    ];
+    NAMES.get((signal as usize).checked_sub(1)?).copied()
+}
+
+/// The memory of the process that made it into the core, from the file
+/// contents of its PT_LOAD segments. Pages the kernel left out, such as
+/// unmodified file mappings, read as missing.
+pub struct Memory<'a> {
+    /// (address, contents), sorted by address.
+    segments: Vec<(u64, &'a [u8])>,
+    is_64: bool,
+}
+
+impl<'a> Memory<'a> {
+    pub fn new(elf_file: &'a ElfFile) -> Result<Self> {
+        let mut segments = Vec::new();
+        for segment in elf_file.program_headers()? {
+            // Segments of a truncated core are left out rather than
+            // failing the whole dump.
+            if segment.p_type == SegmentType::LOAD
+                && segment.p_filesz != 0
+                && let Ok(data) = elf_file.segment_data(&segment)
+            {
+                segments.push((segment.p_vaddr, data));
+            }
+        }
+        segments.sort_by_key(|&(address, _)| address);
+        Ok(Self {
+            segments,
+            is_64: elf_file.is_64(),
+        })
+    }
+
+    /// `len` bytes at `address`, if the core holds all of them.
+    pub fn read(&self, address: u64, len: usize) -> Option<&'a [u8]> {
+        let index = self.segments.partition_point(|&(a, _)| a <= address);
+        let &(start, data) = self.segments.get(index.checked_sub(1)?)?;
+        let offset = usize::try_from(address - start).ok()?;
+        data.get(offset..offset.checked_add(len)?)
+    }
+
+    /// Copies the memory at `address` into `buffer`, returning how many of
+    /// its bytes the core holds. The bytes it does not hold are left as
+    /// they were.
+    pub fn copy(&self, address: u64, buffer: &mut [u8]) -> u64 {
+        let end = address.saturating_add(buffer.len() as u64);
+        let mut found = 0;
+        for &(start, data) in &self.segments {
+            // No process can have memory past the end of the address space.
+            let Some(segment_end) = start.checked_add(data.len() as u64) else {
+                continue;
+            };
+            let from = start.max(address);
+            let to = segment_end.min(end);
+            if from < to {
+                buffer[(from - address) as usize..(to - address) as usize]
+                    .copy_from_slice(&data[(from - start) as usize..(to - start) as usize]);
+                found += to - from;
+            }
+        }
+        found
+    }
+
+    /// A pointer-sized word at `address`.
+    pub fn read_word(&self, address: u64) -> Option<u64> {
+        if self.is_64 {
+            let bytes = self.read(address, 8)?;
+            Some(u64::from_ne_bytes(bytes.try_into().unwrap()))
+        } else {
+            let bytes = self.read(address, 4)?;
+            Some(u32::from_ne_bytes(bytes.try_into().unwrap()) as u64)
+        }
+    }
+}
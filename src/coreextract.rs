@@ -0,0 +1,165 @@
+//! Pulling memory out of core dumps: a range of addresses, or the contents
+//! of every file the process had mapped, as far as the core holds them.
+
+use anyhow::{Context, Result, bail};
+use std::path::{Component, Path, PathBuf};
+
+use crate::addr2line;
+use crate::core::{self, Memory};
+use crate::elf::{ElfFile, ElfType};
+
+/// The largest file `--core-extract-files` rebuilds. Real mappings are far
+/// smaller; larger ones come from corrupt NT_FILE notes.
+const MAX_FILE_SIZE: u64 = 1 << 32;
+
+/// Parses an extraction request, `ADDR,LEN=FILE`, where the address is in
+/// hex and the length is decimal or `0x`-prefixed hex.
+fn parse_request(request: &str) -> Result<(u64, u64, PathBuf)> {
+    let (range, file) = request
+        .split_once('=')
+        .with_context(|| format!("Expected ADDR,LEN=FILE, got {:?}", request))?;
+    let (address, length) = range
+        .split_once(',')
+        .with_context(|| format!("Expected ADDR,LEN=FILE, got {:?}", request))?;
+    let length = match length.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => length.parse(),
+    }
+    .with_context(|| format!("Invalid length {:?}", length))?;
+    Ok((
+        addr2line::parse_address(address)?,
+        length,
+        PathBuf::from(file),
+    ))
+}
+
+fn ensure_core(elf_file: &ElfFile) -> Result<()> {
+    if elf_file.elf_type() != ElfType::CORE {
+        bail!("Only core dumps hold process memory to extract");
+    }
+    Ok(())
+}
+
+/// Writes the process memory a request asks for to its file. Bytes the core
+/// does not hold, such as unmodified pages of mapped files, are written as
+/// zeros, and counted so that they are not mistaken for data.
+pub fn extract_region(elf_file: &ElfFile, request: &str) -> Result<()> {
+    ensure_core(elf_file)?;
+    let (address, length, path) = parse_request(request)?;
+    let memory = Memory::new(elf_file)?;
+
+    let mut buffer = vec![0; usize::try_from(length)?];
+    let found = memory.copy(address, &mut buffer);
+    if found == 0 {
+        bail!(
+            "The core dump holds none of the memory at {:#x}-{:#x}",
+            address,
+            address.saturating_add(length)
+        );
+    }
+    std::fs::write(&path, &buffer)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    print!(
+        "Wrote {:#x}-{:#x} to {}",
+        address,
+        address + length,
+        path.display()
+    );
+    if found < length {
+        print!(
+            " ({:#x} of the {:#x} bytes are not in the core and were written as zeros)",
+            length - found,
+            length
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Where under `dir` a mapped file is rebuilt: at its own path, taken as
+/// relative, so that the directory can stand in for the root the process
+/// saw. Components that would climb out of it are dropped.
+fn destination(dir: &Path, path: &str) -> PathBuf {
+    let mut destination = dir.to_path_buf();
+    for component in Path::new(path).components() {
+        if let Component::Normal(part) = component {
+            destination.push(part);
+        }
+    }
+    destination
+}
+
+/// Rebuilds every file the process had mapped under `dir`, from the memory
+/// of its NT_FILE mappings placed at the file offsets they were mapped
+/// from. Cores leave out pages that still match the file, so most of the
+/// code of a file usually comes back as zeros; what was recovered is shown
+/// for each. Files of which nothing was dumped are not written.
+pub fn extract_mapped_files(elf_file: &ElfFile, dir: &Path) -> Result<()> {
+    ensure_core(elf_file)?;
+    let memory = Memory::new(elf_file)?;
+    let mappings = core::mapped_files(elf_file)?;
+    if mappings.is_empty() {
+        println!("There is no NT_FILE note in this core dump, so no mapped files to extract.");
+        return Ok(());
+    }
+
+    let mut paths: Vec<&str> = Vec::new();
+    for mapping in &mappings {
+        if !paths.contains(&mapping.path.as_str()) {
+            paths.push(&mapping.path);
+        }
+    }
+
+    println!("Mapped files rebuilt under {}:", dir.display());
+    println!("  {:>10} {:>10}  File", "Size", "Recovered");
+    for path in paths {
+        let pieces: Vec<_> = mappings.iter().filter(|m| m.path == path).collect();
+        let size = pieces
+            .iter()
+            .map(|m| m.offset.checked_add(m.end - m.start))
+            .try_fold(0, |size, end| end.map(|end| size.max(end)));
+        let Some(size) = size.filter(|&size| size <= MAX_FILE_SIZE) else {
+            eprintln!(
+                "readelf-rs: Warning: The mappings of {} describe a file larger than {:#x} \
+                 bytes; it is not rebuilt",
+                path, MAX_FILE_SIZE
+            );
+            continue;
+        };
+        let mut image = vec![0; usize::try_from(size)?];
+        let mut found = 0;
+        for piece in pieces {
+            let offset = piece.offset as usize;
+            found += memory.copy(
+                piece.start,
+                &mut image[offset..offset + (piece.end - piece.start) as usize],
+            );
+        }
+
+        if found == 0 {
+            println!(
+                "  {:>#10x} {:>10}  {} (not in the core)",
+                size, "none", path
+            );
+            continue;
+        }
+        let destination = destination(dir, path);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&destination, &image)
+            .with_context(|| format!("Failed to write {}", destination.display()))?;
+        println!(
+            "  {:>#10x} {:>#10x}  {} ({}%)",
+            size,
+            found,
+            path,
+            found * 100 / size.max(1)
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! The memory of a core dump laid out the way `/proc/<pid>/maps` shows it
+//! for a live process.
+
+use anyhow::Result;
+
+use crate::core;
+use crate::elf::{ElfFile, ElfType};
+use crate::emachine::EMachine;
+use crate::segment::SegmentType;
+
+const AT_ENTRY: u64 = 9;
+const AT_SYSINFO_EHDR: u64 = 33;
+
+/// How far past the end of the executable the kernel may move the start of
+/// the heap when it randomizes it, for 64 and 32-bit processes.
+const BRK_RANDOMIZATION_64: u64 = 0x4000_0000;
+const BRK_RANDOMIZATION_32: u64 = 0x0200_0000;
+
+/// Where x86-64 kernels map the legacy vsyscall page.
+const X86_64_VSYSCALL: u64 = 0xffff_ffff_ff60_0000;
+
+/// Prints a line per PT_LOAD segment of a core dump in the format of
+/// `/proc/<pid>/maps`. File-backed mappings get their offset and path from
+/// the NT_FILE note. Of the anonymous ones, the vDSO is found through the
+/// auxiliary vector and the stack by the main thread's stack pointer, and
+/// the heap is taken to be the first writable one the kernel could have
+/// put the program break at. Cores record neither device and inode numbers
+/// nor whether a mapping was shared, so those show as zeros and private.
+pub fn print_core_maps(elf_file: &ElfFile) -> Result<()> {
+    if elf_file.elf_type() != ElfType::CORE {
+        println!("This is not a core dump, so it has no process memory map.");
+        return Ok(());
+    }
+
+    let mappings = core::mapped_files(elf_file)?;
+    let auxv = core::auxv(elf_file)?;
+    let value = |key| auxv.iter().find(|&&(k, _)| k == key).map(|&(_, v)| v);
+    let threads = core::threads(elf_file)?;
+    let pid = core::process(elf_file)?.map(|p| p.pid);
+    let main_thread = threads
+        .iter()
+        .find(|t| Some(t.pid) == pid)
+        .or(threads.first());
+    let stack = main_thread.and_then(|t| t.sp());
+
+    // The end of the executable is where the program break starts out.
+    let executable_end = value(AT_ENTRY)
+        .and_then(|entry| mappings.iter().find(|m| (m.start..m.end).contains(&entry)))
+        .and_then(|exe| {
+            mappings
+                .iter()
+                .filter(|m| m.path == exe.path)
+                .map(|m| m.end)
+                .max()
+        });
+    let brk_range = if elf_file.is_64() {
+        BRK_RANDOMIZATION_64
+    } else {
+        BRK_RANDOMIZATION_32
+    };
+    let mut heap_seen = false;
+
+    // Names start at the same column as in /proc, which leaves room for
+    // the addresses of the file's class.
+    let width = if elf_file.is_64() { 72 } else { 48 };
+    for segment in elf_file.program_headers()? {
+        if segment.p_type != SegmentType::LOAD {
+            continue;
+        }
+        let start = segment.p_vaddr;
+        let end = start + segment.p_memsz;
+        let permissions = format!(
+            "{}{}{}p",
+            if segment.is_readable() { 'r' } else { '-' },
+            if segment.is_writable() { 'w' } else { '-' },
+            if segment.is_executable() { 'x' } else { '-' }
+        );
+
+        let file = mappings.iter().find(|m| (m.start..m.end).contains(&start));
+        let (offset, name) = match file {
+            Some(file) => (file.offset + (start - file.start), file.path.clone()),
+            None => {
+                let name = if Some(start) == value(AT_SYSINFO_EHDR) {
+                    "[vdso]"
+                } else if stack.is_some_and(|sp| (start..end).contains(&sp)) {
+                    "[stack]"
+                } else if !heap_seen
+                    && segment.is_writable()
+                    && executable_end
+                        .is_some_and(|exe| (exe..=exe.saturating_add(brk_range)).contains(&start))
+                {
+                    heap_seen = true;
+                    "[heap]"
+                } else if elf_file.machine() == EMachine::X8664 && start == X86_64_VSYSCALL {
+                    "[vsyscall]"
+                } else {
+                    ""
+                };
+                (0, name.to_string())
+            }
+        };
+
+        let line = format!(
+            "{:08x}-{:08x} {} {:08x} 00:00 0",
+            start, end, permissions, offset
+        );
+        if name.is_empty() {
+            println!("{}", line);
+        } else {
+            println!("{:<width$} {}", line, name, width = width);
+        }
+    }
+
+    Ok(())
+}
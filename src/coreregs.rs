@@ -0,0 +1,384 @@
+//! The register sets of a core dump's threads, named and grouped the way
+//! each architecture's debuggers show them: general purpose registers from
+//! NT_PRSTATUS, floating point and vector registers from NT_FPREGSET and
+//! NT_X86_XSTATE, and the pointer authentication masks of AArch64.
+
+use anyhow::Result;
+
+use crate::core::{self, Thread};
+use crate::elf::{ElfFile, ElfType};
+use crate::emachine::EMachine;
+use crate::notes::{NT_ARM_PAC_MASK, NT_FPREGSET, NT_X86_XSTATE, Note};
+
+/// A group of general purpose registers, as (name, slot in the kernel's
+/// `user_regs_struct`).
+type Group = (&'static str, Vec<(String, usize)>);
+
+fn named(names: &[&str], slots: impl IntoIterator<Item = usize>) -> Vec<(String, usize)> {
+    names.iter().map(|n| n.to_string()).zip(slots).collect()
+}
+
+fn numbered(prefix: &str, slots: std::ops::RangeInclusive<usize>) -> Vec<(String, usize)> {
+    let first = *slots.start();
+    slots
+        .map(|s| (format!("{}{}", prefix, s - first), s))
+        .collect()
+}
+
+/// The general purpose registers of a machine, grouped, with the slot each
+/// is kept in. Empty for machines whose layout is not known.
+fn groups(machine: EMachine) -> Vec<Group> {
+    match machine {
+        EMachine::X8664 => vec![
+            (
+                "General",
+                named(
+                    &[
+                        "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10",
+                        "r11", "r12", "r13", "r14", "r15",
+                    ],
+                    [10, 5, 11, 12, 13, 14, 4, 19, 9, 8, 7, 6, 3, 2, 1, 0],
+                ),
+            ),
+            (
+                "Control",
+                named(&["rip", "eflags", "orig_rax"], [16, 18, 15]),
+            ),
+            (
+                "Segment",
+                named(
+                    &["cs", "ss", "ds", "es", "fs", "gs", "fs_base", "gs_base"],
+                    [17, 20, 23, 24, 25, 26, 21, 22],
+                ),
+            ),
+        ],
+        EMachine::Aarch64 => vec![
+            ("General", numbered("x", 0..=30)),
+            ("Control", named(&["sp", "pc", "pstate"], [31, 32, 33])),
+        ],
+        EMachine::Riscv => vec![
+            (
+                "General",
+                named(
+                    &[
+                        "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2",
+                        "a3", "a4", "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8",
+                        "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+                    ],
+                    1..=31,
+                ),
+            ),
+            ("Control", named(&["pc"], [0])),
+        ],
+        EMachine::Ppc64 => vec![
+            ("General", numbered("r", 0..=31)),
+            (
+                "Control",
+                named(
+                    &[
+                        "nip", "msr", "orig_r3", "ctr", "lr", "xer", "cr", "softe", "trap", "dar",
+                        "dsisr", "result",
+                    ],
+                    32..=43,
+                ),
+            ),
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// The flags set in a flags register, like gdb shows them: `[ ZF IF ]`.
+fn flags(machine: EMachine, name: &str, value: u64) -> Option<String> {
+    let bits: &[(u32, &str)] = match (machine, name) {
+        (EMachine::X8664, "eflags") => &[
+            (0, "CF"),
+            (2, "PF"),
+            (4, "AF"),
+            (6, "ZF"),
+            (7, "SF"),
+            (8, "TF"),
+            (9, "IF"),
+            (10, "DF"),
+            (11, "OF"),
+            (16, "RF"),
+            (17, "VM"),
+            (18, "AC"),
+            (21, "ID"),
+        ],
+        (EMachine::Aarch64, "pstate") => &[(28, "V"), (29, "C"), (30, "Z"), (31, "N")],
+        _ => return None,
+    };
+    let set: Vec<&str> = bits
+        .iter()
+        .filter(|&&(bit, _)| value & (1 << bit) != 0)
+        .map(|&(_, name)| name)
+        .collect();
+    Some(format!("[ {} ]", set.join(" ")))
+}
+
+fn print_general(machine: EMachine, thread: &Thread, is_64: bool) {
+    let width = if is_64 { 18 } else { 10 };
+    let groups = groups(machine);
+    let groups = if groups.is_empty() {
+        vec![(
+            "General",
+            numbered("r", 0..=thread.registers.len().saturating_sub(1)),
+        )]
+    } else {
+        groups
+    };
+
+    println!("  General purpose registers (NT_PRSTATUS):");
+    for (title, registers) in groups {
+        let cells: Vec<String> = registers
+            .iter()
+            .filter_map(|(name, slot)| {
+                let value = thread.registers.get(*slot)?;
+                Some(format!("{:<8} {:#0w$x}", name, value, w = width))
+            })
+            .collect();
+        println!("    {}:", title);
+        for row in cells.chunks(if is_64 { 3 } else { 4 }) {
+            println!("      {}", row.join("   "));
+        }
+        for (name, slot) in &registers {
+            if let Some(value) = thread.registers.get(*slot)
+                && let Some(flags) = flags(machine, name, *value)
+            {
+                println!("      {:<8} {}", name, flags);
+            }
+        }
+    }
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_ne_bytes(
+        data.get(offset..offset + 4)?.try_into().unwrap(),
+    ))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_ne_bytes(
+        data.get(offset..offset + 8)?.try_into().unwrap(),
+    ))
+}
+
+fn u128_at(data: &[u8], offset: usize) -> Option<u128> {
+    Some(u128::from_ne_bytes(
+        data.get(offset..offset + 16)?.try_into().unwrap(),
+    ))
+}
+
+/// The value of an x87 80-bit extended precision register.
+fn extended(bytes: &[u8]) -> f64 {
+    let mantissa = u64::from_ne_bytes(bytes[..8].try_into().unwrap());
+    let exponent = u16::from_ne_bytes(bytes[8..10].try_into().unwrap());
+    let sign = if exponent & 0x8000 != 0 { -1.0 } else { 1.0 };
+    match exponent & 0x7fff {
+        0 if mantissa == 0 => 0.0 * sign,
+        0x7fff if mantissa << 1 == 0 => f64::INFINITY * sign,
+        0x7fff => f64::NAN,
+        e => sign * mantissa as f64 * 2f64.powi(e as i32 - 16383 - 63),
+    }
+}
+
+/// Prints a block of 16-byte vector registers, one per line.
+fn print_vectors(data: &[u8], offset: usize, count: usize, prefix: &str) {
+    for index in 0..count {
+        if let Some(value) = u128_at(data, offset + index * 16) {
+            println!(
+                "      {:<8} {:#034x}",
+                format!("{}{}", prefix, index),
+                value
+            );
+        }
+    }
+}
+
+/// Prints 8-byte floating point registers with the double they hold.
+fn print_doubles(data: &[u8], count: usize, prefix: &str) {
+    for index in 0..count {
+        if let Some(bits) = u64_at(data, index * 8) {
+            println!(
+                "      {:<8} {:#018x}  {}",
+                format!("{}{}", prefix, index),
+                bits,
+                f64::from_bits(bits)
+            );
+        }
+    }
+}
+
+/// The x87 and SSE state, in the FXSAVE layout that both NT_FPREGSET and
+/// the start of NT_X86_XSTATE use on x86-64.
+fn print_fxsave(data: &[u8]) {
+    let word = |offset| {
+        data.get(offset..offset + 2)
+            .map(|b| u16::from_ne_bytes(b.try_into().unwrap()))
+    };
+    println!(
+        "      fcw {:#06x}   fsw {:#06x}   ftw {:#04x}   mxcsr {:#010x}",
+        word(0).unwrap_or(0),
+        word(2).unwrap_or(0),
+        word(4).unwrap_or(0),
+        u32_at(data, 24).unwrap_or(0)
+    );
+    for index in 0..8 {
+        let offset = 32 + index * 16;
+        if let Some(bytes) = data.get(offset..offset + 10) {
+            let raw: String = bytes.iter().rev().map(|b| format!("{:02x}", b)).collect();
+            println!("      st{:<6} 0x{}  {}", index, raw, extended(bytes));
+        }
+    }
+    print_vectors(data, 160, 16, "xmm");
+}
+
+/// The state components of the XSAVE feature bitmap.
+const XSTATE_FEATURES: &[&str] = &[
+    "x87",
+    "SSE",
+    "AVX",
+    "MPX_BNDREGS",
+    "MPX_BNDCSR",
+    "AVX512_OPMASK",
+    "AVX512_ZMM_Hi256",
+    "AVX512_Hi16_ZMM",
+    "PT",
+    "PKRU",
+    "PASID",
+    "CET_U",
+    "CET_S",
+    "HDC",
+    "UINTR",
+    "LBR",
+    "HWP",
+    "AMX_TILECFG",
+    "AMX_TILEDATA",
+];
+
+/// Where the upper halves of the AVX registers are in the standard XSAVE
+/// layout cores use. The offsets of later components depend on the CPU.
+const XSAVE_YMM_HI128: usize = 576;
+
+fn feature_names(features: u64) -> String {
+    let names: Vec<String> = (0..64)
+        .filter(|bit| features & (1 << bit) != 0)
+        .map(|bit| {
+            XSTATE_FEATURES
+                .get(bit)
+                .map_or_else(|| format!("bit{}", bit), |n| n.to_string())
+        })
+        .collect();
+    names.join(" ")
+}
+
+fn print_xstate(data: &[u8]) {
+    let Some(features) = u64_at(data, 512) else {
+        println!("      <truncated>");
+        return;
+    };
+    // The kernel puts XCR0, the components the process may use, in bytes
+    // FXSAVE leaves unused. Components still in their initial state are
+    // left out of the saved ones.
+    if let Some(enabled) = u64_at(data, 464) {
+        println!("      Enabled components: {}", feature_names(enabled));
+    }
+    println!("      Saved components:   {}", feature_names(features));
+    if features & 0x4 != 0 {
+        for index in 0..16 {
+            let (Some(low), Some(high)) = (
+                u128_at(data, 160 + index * 16),
+                u128_at(data, XSAVE_YMM_HI128 + index * 16),
+            ) else {
+                break;
+            };
+            println!("      ymm{:<5} 0x{:032x}{:032x}", index, high, low);
+        }
+    }
+}
+
+/// Prints a register set note other than NT_PRSTATUS, returning whether it
+/// is one that is known for the machine.
+fn print_register_set(machine: EMachine, note: &Note) -> bool {
+    let data = note.desc;
+    match (note.name, note.n_type, machine) {
+        ("CORE", NT_FPREGSET, EMachine::X8664) => {
+            println!("  Floating point registers (NT_FPREGSET):");
+            print_fxsave(data);
+        }
+        ("CORE", NT_FPREGSET, EMachine::Aarch64) => {
+            println!("  Floating point and SIMD registers (NT_FPREGSET):");
+            print_vectors(data, 0, 32, "v");
+            println!(
+                "      fpsr {:#010x}   fpcr {:#010x}",
+                u32_at(data, 512).unwrap_or(0),
+                u32_at(data, 516).unwrap_or(0)
+            );
+        }
+        ("CORE", NT_FPREGSET, EMachine::Riscv) => {
+            println!("  Floating point registers (NT_FPREGSET):");
+            print_doubles(data, 32, "f");
+            println!("      fcsr {:#010x}", u32_at(data, 256).unwrap_or(0));
+        }
+        ("CORE", NT_FPREGSET, EMachine::Ppc64) => {
+            println!("  Floating point registers (NT_FPREGSET):");
+            print_doubles(data, 32, "f");
+            println!("      fpscr {:#018x}", u64_at(data, 256).unwrap_or(0));
+        }
+        ("LINUX", NT_X86_XSTATE, EMachine::X8664) => {
+            println!("  Extended state (NT_X86_XSTATE):");
+            print_xstate(data);
+        }
+        ("LINUX", NT_ARM_PAC_MASK, EMachine::Aarch64) => {
+            println!("  Pointer authentication masks (NT_ARM_PAC_MASK):");
+            println!(
+                "      data_mask {:#018x}   insn_mask {:#018x}",
+                u64_at(data, 0).unwrap_or(0),
+                u64_at(data, 8).unwrap_or(0)
+            );
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Prints the registers of every thread of a core dump, grouped and named
+/// for the architecture. The register sets that are not shown are listed
+/// by size, so that it is clear they are there.
+pub fn print_core_registers(elf_file: &ElfFile) -> Result<()> {
+    if elf_file.elf_type() != ElfType::CORE {
+        println!("This is not a core dump, so it has no registers.");
+        return Ok(());
+    }
+    let machine = elf_file.machine();
+
+    for (index, thread) in core::thread_notes(elf_file)?.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        match core::signal_name(thread.thread.signal) {
+            Some(name) => println!(
+                "Thread {} (signal {}, {}):",
+                thread.thread.pid, thread.thread.signal, name
+            ),
+            None => println!("Thread {}:", thread.thread.pid),
+        }
+        print_general(machine, &thread.thread, elf_file.is_64());
+
+        let mut others = Vec::new();
+        for note in &thread.notes {
+            if !print_register_set(machine, note) {
+                others.push(format!(
+                    "{} ({} bytes)",
+                    crate::notes::type_name(elf_file.elf_type(), note.name, note.n_type),
+                    note.desc.len()
+                ));
+            }
+        }
+        if !others.is_empty() {
+            println!("  Other register sets: {}", others.join(", "));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,332 @@
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::dwarf;
+use crate::elf::{ElfFile, read_str};
+use crate::notes;
+use crate::section::{SHF_COMPRESSED, SectionType};
+
+/// DWARF versions used by the compilation units and by their line programs.
+fn dwarf_versions(elf_file: &ElfFile) -> Option<(BTreeSet<u16>, BTreeSet<u16>, usize)> {
+    let dwarf = dwarf::load(elf_file).ok()??;
+    let mut unit_versions = BTreeSet::new();
+    let mut line_versions = BTreeSet::new();
+    let mut count = 0;
+
+    let mut units = dwarf.units();
+    while let Ok(Some(header)) = units.next() {
+        count += 1;
+        unit_versions.insert(header.version());
+        if let Ok(unit) = dwarf.unit(header)
+            && let Some(program) = &unit.line_program
+        {
+            line_versions.insert(program.header().version());
+        }
+    }
+
+    Some((unit_versions, line_versions, count))
+}
+
+fn versions(versions: &BTreeSet<u16>) -> String {
+    versions
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Summarises which debugging aids the file carries, and whether a debugger
+/// would be able to make sense of it.
+pub fn print_debug_inventory(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let named: Vec<_> = sections
+        .iter()
+        .map(|s| (elf_file.section_name(&sections, s).unwrap_or(""), s))
+        .collect();
+    let find = |name: &str| named.iter().find(|(n, _)| *n == name).map(|&(_, s)| s);
+
+    println!("Debug inventory:");
+
+    for table_type in [SectionType::SYMTAB, SectionType::DYNSYM] {
+        let table = sections.iter().find(|s| s.sh_type == table_type);
+        let label = if table_type == SectionType::SYMTAB {
+            "Symbol table:"
+        } else {
+            "Dynamic symbols:"
+        };
+        match table {
+            Some(table) => println!(
+                "  {:<18} {} symbols",
+                label,
+                elf_file.symbols(table)?.count()
+            ),
+            None => println!("  {:<18} not present", label),
+        }
+    }
+
+    let debug_sections: Vec<_> = named
+        .iter()
+        .filter(|(name, _)| name.starts_with(".debug_") || name.starts_with(".zdebug_"))
+        .collect();
+    let has_dwarf = debug_sections
+        .iter()
+        .any(|(name, _)| *name == ".debug_info" || *name == ".zdebug_info");
+
+    if debug_sections.is_empty() {
+        println!("  {:<18} not present", "DWARF:");
+    } else {
+        let compressed = debug_sections
+            .iter()
+            .any(|(name, s)| s.sh_flags & SHF_COMPRESSED != 0 || name.starts_with(".zdebug_"));
+        let versions = if compressed {
+            None
+        } else {
+            dwarf_versions(elf_file)
+        };
+
+        println!("  DWARF:");
+        for (name, section) in &debug_sections {
+            let mut detail = String::new();
+            if section.sh_flags & SHF_COMPRESSED != 0 || name.starts_with(".zdebug_") {
+                detail += " (compressed)";
+            }
+            if let Some((units, lines, count)) = &versions {
+                match *name {
+                    ".debug_info" if !units.is_empty() => {
+                        detail += &format!(" (DWARF {}, {} units)", self::versions(units), count)
+                    }
+                    ".debug_line" if !lines.is_empty() => {
+                        detail += &format!(" (version {})", self::versions(lines))
+                    }
+                    _ => {}
+                }
+            }
+            println!("    {:<24} {:>10} bytes{}", name, section.sh_size, detail);
+        }
+    }
+
+    let build_id = notes::build_id(elf_file)?;
+    match &build_id {
+        Some(id) => println!("  {:<18} {}", "Build ID:", id),
+        None => println!("  {:<18} not present", "Build ID:"),
+    }
+
+    let debuglink = match find(".gnu_debuglink") {
+        Some(section) => {
+            let data = elf_file.section_data(section)?;
+            let file = read_str(data, 0)?;
+            // The CRC follows the name once it is padded to 4 bytes.
+            let crc_offset = (file.len() + 1).div_ceil(4) * 4;
+            let crc = data
+                .get(crc_offset..crc_offset + 4)
+                .map(|b| u32::from_ne_bytes(b.try_into().unwrap()));
+            match crc {
+                Some(crc) => println!("  {:<18} {} (CRC {:#010x})", "Debug link:", file, crc),
+                None => println!("  {:<18} {}", "Debug link:", file),
+            }
+            true
+        }
+        None => {
+            println!("  {:<18} not present", "Debug link:");
+            false
+        }
+    };
+
+    if let Some(section) = find(".gnu_debugaltlink") {
+        let data = elf_file.section_data(section)?;
+        let file = read_str(data, 0)?;
+        let id: String = data[file.len() + 1..]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        println!("  {:<18} {} (build ID {})", "Debug alt link:", file, id);
+    }
+
+    for (label, name) in [("CTF:", ".ctf"), ("BTF:", ".BTF"), ("SFrame:", ".sframe")] {
+        match find(name) {
+            Some(section) => println!("  {:<18} {} ({} bytes)", label, name, section.sh_size),
+            None => println!("  {:<18} not present", label),
+        }
+    }
+
+    let has_symtab = sections.iter().any(|s| s.sh_type == SectionType::SYMTAB);
+    let verdict = if has_dwarf {
+        "yes, DWARF debug info is embedded"
+    } else if debuglink {
+        "only with the separate debug file named by .gnu_debuglink"
+    } else if has_symtab {
+        "partially, function names are known but there is no source information"
+    } else if build_id.is_some() {
+        "only with separate debug info looked up by build ID"
+    } else {
+        "no, the file is stripped"
+    };
+    println!();
+    println!("Debuggable: {}", verdict);
+
+    Ok(())
+}
+
+/// What one compilation unit contributes to the debug info.
+struct UnitSummary {
+    offset: usize,
+    name: String,
+    producer: Option<String>,
+    language: Option<String>,
+    comp_dir: Option<String>,
+    /// Code address ranges, as (lowest, highest, bytes covered, ranges).
+    code: Option<(u64, u64, u64, usize)>,
+    info_size: usize,
+    line_size: usize,
+}
+
+/// With `deterministic`, the compilation directories are dropped and the
+/// unit names are printed as `dwarf::display_path` has them.
+fn unit_summaries(
+    dwarf: &gimli::Dwarf<dwarf::Reader>,
+    deterministic: bool,
+) -> Result<Vec<UnitSummary>> {
+    let mut summaries = Vec::new();
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let offset = header.debug_info_offset().map_or(0, |o| o.0);
+        let info_size = header.length_including_self();
+        let unit = dwarf.unit(header)?;
+        let string = |value: Option<gimli::AttributeValue<_>>| -> Option<String> {
+            let value = value?;
+            Some(
+                dwarf
+                    .attr_string(&unit, value)
+                    .ok()?
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        };
+
+        let mut entries = unit.entries();
+        let (producer, language) = match entries.next_dfs()? {
+            Some(root) => (
+                string(root.attr_value(gimli::DW_AT_producer)),
+                match root.attr_value(gimli::DW_AT_language) {
+                    Some(gimli::AttributeValue::Language(language)) => Some(
+                        language
+                            .static_string()
+                            .map_or_else(|| format!("{:#x}", language.0), str::to_string),
+                    ),
+                    _ => None,
+                },
+            ),
+            None => (None, None),
+        };
+
+        let mut code: Option<(u64, u64, u64, usize)> = None;
+        let mut ranges = dwarf.unit_ranges(&unit)?;
+        while let Some(range) = ranges.next()? {
+            if range.end <= range.begin {
+                continue;
+            }
+            let (low, high, bytes, count) = code.unwrap_or((u64::MAX, 0, 0, 0));
+            code = Some((
+                low.min(range.begin),
+                high.max(range.end),
+                bytes + (range.end - range.begin),
+                count + 1,
+            ));
+        }
+
+        let line_size = unit.line_program.as_ref().map_or(0, |program| {
+            let header = program.header();
+            header.unit_length() + header.encoding().format.initial_length_size() as usize
+        });
+
+        let comp_dir = unit.comp_dir.map(|d| d.to_string_lossy().into_owned());
+        summaries.push(UnitSummary {
+            offset,
+            name: unit.name.map_or_else(
+                || "<unnamed>".to_string(),
+                |n| {
+                    let name = n.to_string_lossy();
+                    let comp_dir = comp_dir.as_deref().unwrap_or_default();
+                    dwarf::display_path(Path::new(&*name), comp_dir, deterministic)
+                },
+            ),
+            producer,
+            language,
+            comp_dir: comp_dir.filter(|_| !deterministic),
+            code,
+            info_size,
+            line_size,
+        });
+    }
+    Ok(summaries)
+}
+
+/// Lists the DWARF compilation units with their producer, language,
+/// directory and code, largest contribution to `.debug_info` and
+/// `.debug_line` first, to show which objects make up most of the debug
+/// info. With `deterministic`, where the code was built is left out.
+pub fn print_cu_summary(elf_file: &ElfFile, deterministic: bool) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let compressed = sections.iter().any(|s| {
+        elf_file.section_name(&sections, s).is_ok_and(|n| {
+            (n.starts_with(".debug_") && s.sh_flags & SHF_COMPRESSED != 0)
+                || n.starts_with(".zdebug_")
+        })
+    });
+    if compressed {
+        println!("The debug sections are compressed, which is not supported.");
+        return Ok(());
+    }
+    let Some(dwarf) = dwarf::load(elf_file)? else {
+        println!("There is no DWARF debug info in this file.");
+        return Ok(());
+    };
+
+    let mut summaries = unit_summaries(&dwarf, deterministic)?;
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.info_size + s.line_size));
+    let info: usize = summaries.iter().map(|s| s.info_size).sum();
+    let line: usize = summaries.iter().map(|s| s.line_size).sum();
+
+    println!(
+        "{} compilation unit(s), with {:#x} bytes of .debug_info and {:#x} bytes of .debug_line:",
+        summaries.len(),
+        info,
+        line
+    );
+    for summary in &summaries {
+        println!();
+        println!("  [{:#010x}] {}", summary.offset, summary.name);
+        println!(
+            "    {:<10} {:#x} bytes of .debug_info ({:.1}%), {:#x} bytes of .debug_line",
+            "Size:",
+            summary.info_size,
+            summary.info_size as f64 * 100.0 / info.max(1) as f64,
+            summary.line_size
+        );
+        if let Some(producer) = &summary.producer {
+            println!("    {:<10} {}", "Producer:", producer);
+        }
+        if let Some(language) = &summary.language {
+            println!("    {:<10} {}", "Language:", language);
+        }
+        if let Some(comp_dir) = &summary.comp_dir {
+            println!("    {:<10} {}", "Directory:", comp_dir);
+        }
+        match summary.code {
+            Some((low, high, bytes, 1)) => {
+                println!(
+                    "    {:<10} {:#x}-{:#x} ({:#x} bytes)",
+                    "Code:", low, high, bytes
+                )
+            }
+            Some((low, high, bytes, count)) => println!(
+                "    {:<10} {:#x}-{:#x} ({:#x} bytes in {} ranges)",
+                "Code:", low, high, bytes, count
+            ),
+            None => println!("    {:<10} none", "Code:"),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use std::io::IsTerminal;
+
+/// Demangles Rust (legacy and v0) and Itanium C++ symbol names, returning
+/// the name unchanged when it is not mangled. Rust hashes are dropped.
+pub fn demangle(name: &str) -> String {
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        return format!("{:#}", demangled);
+    }
+
+    if name.starts_with("_Z")
+        && let Some(demangled) = cpp_demangle::Symbol::new(name)
+            .ok()
+            .and_then(|s| s.demangle().ok())
+    {
+        return demangled;
+    }
+
+    name.to_string()
+}
+
+/// The outermost namespace (Rust crate or C++ namespace) of a demangled
+/// name, or `None` for names that live in the global namespace.
+pub fn namespace(demangled: &str) -> Option<&str> {
+    // Rust trait impls look like "<alloc::vec::Vec<T> as core::ops::Drop>::drop";
+    // attribute those to the crate of the implementing type.
+    if let Some(inner) = demangled.strip_prefix('<') {
+        return namespace(inner);
+    }
+
+    // Template and function arguments can contain "::" of their own, so only
+    // look at the part of the name before any of them open.
+    let mut depth = 0;
+    for (i, c) in demangled.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ':' if depth == 0 && demangled[i..].starts_with("::") => {
+                let prefix = &demangled[..i];
+                // C++ return types come before the name and are separated by
+                // a space, e.g. "void std::foo()".
+                let prefix = prefix.rsplit(' ').next().unwrap_or(prefix);
+                return (!prefix.is_empty()).then_some(prefix);
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// How symbol names are printed, after readelf's `-C` and `-U` options.
+#[derive(Clone, Copy, Default)]
+pub struct NameStyle {
+    /// Print mangled names demangled.
+    pub demangle: bool,
+    /// What to do with characters outside ASCII.
+    pub unicode: Unicode,
+}
+
+/// The `-U` modes. `default`, `locale` and `show` all print the characters
+/// as they are.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Unicode {
+    #[default]
+    Show,
+    /// `\uXXXX`.
+    Escape,
+    /// The UTF-8 bytes in hex, as `<0xe282ac>`.
+    Hex,
+    /// Like `Hex`, but as `{0xe282ac}`, as if they were not valid UTF-8.
+    Invalid,
+    /// Like `Escape`, in red on a terminal.
+    Highlight,
+}
+
+impl Unicode {
+    pub fn from_mode(mode: &str) -> Self {
+        match mode {
+            "escape" => Unicode::Escape,
+            "hex" => Unicode::Hex,
+            "invalid" => Unicode::Invalid,
+            "highlight" => Unicode::Highlight,
+            _ => Unicode::Show,
+        }
+    }
+}
+
+impl NameStyle {
+    /// `name` as the style asks for it to be printed.
+    pub fn display(&self, name: &str) -> String {
+        let name = if self.demangle {
+            demangle(name)
+        } else {
+            name.to_string()
+        };
+        if self.unicode == Unicode::Show || name.is_ascii() {
+            return name;
+        }
+
+        let mut shown = String::new();
+        for c in name.chars() {
+            if c.is_ascii() {
+                shown.push(c);
+                continue;
+            }
+            let mut bytes = [0; 4];
+            let hex: String = c
+                .encode_utf8(&mut bytes)
+                .bytes()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            match self.unicode {
+                Unicode::Hex => shown += &format!("<0x{}>", hex),
+                Unicode::Invalid => shown += &format!("{{0x{}}}", hex),
+                Unicode::Highlight if std::io::stdout().is_terminal() => {
+                    shown += &format!("\x1b[31;47m\\u{:04x}\x1b[0m", c as u32)
+                }
+                Unicode::Escape | Unicode::Highlight => shown += &format!("\\u{:04x}", c as u32),
+                Unicode::Show => shown.push(c),
+            }
+        }
+        shown
+    }
+}
@@ -0,0 +1,385 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::dynamic::DynamicTag;
+use crate::elf::ElfFile;
+
+struct Library {
+    name: String,
+    /// Where the library was found, or `None` if the search failed.
+    path: Option<PathBuf>,
+    needed: Vec<usize>,
+}
+
+/// The recursive DT_NEEDED graph of a file. Node 0 is the file itself, and
+/// every library is a single node no matter how many objects need it.
+pub struct DependencyGraph {
+    libraries: Vec<Library>,
+    class: u8,
+    machine: crate::emachine::EMachine,
+    system_dirs: Vec<PathBuf>,
+}
+
+impl DependencyGraph {
+    pub fn new(elf_file: &ElfFile, path: &str, display_path: &str) -> Result<Self> {
+        let mut graph = Self {
+            libraries: vec![Library {
+                name: display_path.to_string(),
+                path: Some(PathBuf::from(path)),
+                needed: Vec::new(),
+            }],
+            class: elf_file.ident().class,
+            machine: elf_file.machine(),
+            system_dirs: system_dirs(elf_file.is_64()),
+        };
+
+        let mut seen = HashMap::new();
+        graph.expand(0, elf_file, Path::new(path), &[], &mut seen)?;
+        Ok(graph)
+    }
+
+    /// Resolves the libraries needed by node `index` and recurses into the
+    /// ones not seen before. `inherited` holds the DT_RPATH entries of the
+    /// objects that led here, which the dynamic loader searches as well.
+    fn expand(
+        &mut self,
+        index: usize,
+        elf_file: &ElfFile,
+        path: &Path,
+        inherited: &[PathBuf],
+        seen: &mut HashMap<String, usize>,
+    ) -> Result<()> {
+        let origin = path
+            .parent()
+            .map(|p| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf()))
+            .unwrap_or_default();
+
+        // DT_RPATH is ignored, both here and for the objects loaded from
+        // here, when the object also has a DT_RUNPATH.
+        let runpath = search_path(&elf_file.dynamic_strings(DynamicTag::RUNPATH)?, &origin);
+        let mut rpath = Vec::new();
+        if runpath.is_empty() {
+            rpath = search_path(&elf_file.dynamic_strings(DynamicTag::RPATH)?, &origin);
+            rpath.extend_from_slice(inherited);
+        }
+
+        for name in elf_file.dynamic_strings(DynamicTag::NEEDED)? {
+            let found = self.search(name, &rpath, &runpath);
+            let key = match &found {
+                Some(path) => fs::canonicalize(path)
+                    .unwrap_or_else(|_| path.clone())
+                    .to_string_lossy()
+                    .into_owned(),
+                None => format!("missing:{}", name),
+            };
+
+            if let Some(&existing) = seen.get(&key) {
+                self.libraries[index].needed.push(existing);
+                continue;
+            }
+
+            let child = self.libraries.len();
+            seen.insert(key, child);
+            self.libraries.push(Library {
+                name: name.to_string(),
+                path: found.clone(),
+                needed: Vec::new(),
+            });
+            self.libraries[index].needed.push(child);
+
+            if let Some(found) = found {
+                let library = ElfFile::open(&found)?;
+                self.expand(child, &library, &found, &rpath, seen)
+                    .with_context(|| format!("Failed to read {}", found.display()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks a library up the way the dynamic loader does: names with a slash
+    /// are used as is, others are searched for in DT_RPATH, LD_LIBRARY_PATH,
+    /// DT_RUNPATH and then the system directories.
+    fn search(&self, name: &str, rpath: &[PathBuf], runpath: &[PathBuf]) -> Option<PathBuf> {
+        if name.contains('/') {
+            let path = PathBuf::from(name);
+            return self.is_compatible(&path).then_some(path);
+        }
+
+        let library_path: Vec<PathBuf> = std::env::var("LD_LIBRARY_PATH")
+            .unwrap_or_default()
+            .split([':', ';'])
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .collect();
+
+        rpath
+            .iter()
+            .chain(&library_path)
+            .chain(runpath)
+            .chain(&self.system_dirs)
+            .map(|dir| dir.join(name))
+            .find(|path| self.is_compatible(path))
+    }
+
+    /// Whether `path` is an ELF file the loader would accept for this graph,
+    /// skipping libraries built for another class or machine.
+    fn is_compatible(&self, path: &Path) -> bool {
+        ElfFile::open(path)
+            .is_ok_and(|elf| elf.ident().class == self.class && elf.machine() == self.machine)
+    }
+
+    /// The objects in the order the dynamic loader adds them to the global
+    /// lookup scope: the file itself, then its dependencies breadth first.
+    /// Libraries that could not be found are left out.
+    pub fn load_order(&self) -> Vec<(&str, &Path)> {
+        let mut order = vec![0];
+        let mut queued = vec![false; self.libraries.len()];
+        queued[0] = true;
+
+        let mut next = 0;
+        while next < order.len() {
+            for &child in &self.libraries[order[next]].needed {
+                if !queued[child] {
+                    queued[child] = true;
+                    order.push(child);
+                }
+            }
+            next += 1;
+        }
+
+        order
+            .into_iter()
+            .filter_map(|i| {
+                let library = &self.libraries[i];
+                Some((library.name.as_str(), library.path.as_deref()?))
+            })
+            .collect()
+    }
+
+    /// Names of the libraries that could not be found.
+    pub fn missing(&self) -> Vec<&str> {
+        self.libraries
+            .iter()
+            .filter(|l| l.path.is_none())
+            .map(|l| l.name.as_str())
+            .collect()
+    }
+
+    /// For each library, another node with the same name found at a different
+    /// path. The search paths of the objects disagree, and which copy gets
+    /// used depends on load order.
+    fn duplicates(&self) -> Vec<Option<usize>> {
+        self.libraries
+            .iter()
+            .enumerate()
+            .map(|(i, library)| {
+                library.path.as_ref()?;
+                self.libraries
+                    .iter()
+                    .enumerate()
+                    .skip(1)
+                    .find(|&(j, other)| {
+                        j != i && other.name == library.name && other.path.is_some()
+                    })
+                    .map(|(j, _)| j)
+            })
+            .collect()
+    }
+
+    pub fn print_tree(&self) {
+        let duplicates = self.duplicates();
+        let mut shown = vec![false; self.libraries.len()];
+        shown[0] = true;
+
+        println!("Dependency tree:");
+        println!("{}", self.libraries[0].name);
+        self.print_children(0, "", &mut shown, &duplicates);
+
+        let missing = self.missing().len();
+        println!();
+        println!(
+            "{} libraries, {} not found",
+            self.libraries.len() - 1,
+            missing
+        );
+    }
+
+    fn print_children(
+        &self,
+        index: usize,
+        prefix: &str,
+        shown: &mut [bool],
+        duplicates: &[Option<usize>],
+    ) {
+        let needed = &self.libraries[index].needed;
+        for (i, &child) in needed.iter().enumerate() {
+            let last = i + 1 == needed.len();
+            let library = &self.libraries[child];
+
+            let mut line = format!(
+                "{}{}{}",
+                prefix,
+                if last { "`-- " } else { "|-- " },
+                library.name
+            );
+            match &library.path {
+                Some(path) => line += &format!(" => {}", path.display()),
+                None => line += " => not found",
+            }
+            if let Some(other) = duplicates[child].and_then(|j| self.libraries[j].path.as_ref()) {
+                line += &format!(" [duplicate of {}]", other.display());
+            }
+
+            // Libraries needed from several places are only expanded the
+            // first time round.
+            if shown[child] {
+                if !library.needed.is_empty() {
+                    line += " (see above)";
+                }
+                println!("{}", line);
+                continue;
+            }
+            shown[child] = true;
+            println!("{}", line);
+
+            let prefix = format!("{}{}", prefix, if last { "    " } else { "|   " });
+            self.print_children(child, &prefix, shown, duplicates);
+        }
+    }
+
+    /// The graph in Graphviz DOT syntax, with missing libraries drawn dashed
+    /// in red and duplicated ones filled in orange.
+    pub fn print_dot(&self) {
+        let duplicates = self.duplicates();
+
+        println!("digraph dependencies {{");
+        println!("  rankdir=LR;");
+        println!("  node [shape=box, fontname=\"monospace\"];");
+        for (i, library) in self.libraries.iter().enumerate() {
+            let (label, style) = match (&library.path, duplicates[i]) {
+                _ if i == 0 => (library.name.clone(), "style=bold"),
+                (None, _) => (
+                    format!("{}\nnot found", library.name),
+                    "style=dashed, color=red, fontcolor=red",
+                ),
+                (Some(path), Some(_)) => (
+                    format!("{}\n{}", library.name, path.display()),
+                    "style=filled, fillcolor=orange",
+                ),
+                (Some(path), None) => (format!("{}\n{}", library.name, path.display()), ""),
+            };
+
+            let separator = if style.is_empty() { "" } else { ", " };
+            println!(
+                "  n{} [label=\"{}\"{}{}];",
+                i,
+                escape(&label),
+                separator,
+                style
+            );
+        }
+        for (i, library) in self.libraries.iter().enumerate() {
+            for &child in &library.needed {
+                let style = if self.libraries[child].path.is_none() {
+                    " [style=dashed, color=red]"
+                } else {
+                    ""
+                };
+                println!("  n{} -> n{}{};", i, child, style);
+            }
+        }
+        println!("}}");
+    }
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Splits colon-separated DT_RPATH/DT_RUNPATH values into directories,
+/// expanding `$ORIGIN` to the directory of the object.
+fn search_path(values: &[&str], origin: &Path) -> Vec<PathBuf> {
+    let origin = origin.to_string_lossy();
+    values
+        .iter()
+        .flat_map(|value| value.split(':'))
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| {
+            PathBuf::from(
+                dir.replace("${ORIGIN}", &origin)
+                    .replace("$ORIGIN", &origin),
+            )
+        })
+        .collect()
+}
+
+/// Directories from /etc/ld.so.conf, followed by the built-in defaults.
+fn system_dirs(is_64: bool) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    read_ld_so_conf(Path::new("/etc/ld.so.conf"), &mut dirs, 0);
+
+    let defaults: &[&str] = if is_64 {
+        &["/lib64", "/usr/lib64", "/lib", "/usr/lib"]
+    } else {
+        &["/lib", "/usr/lib"]
+    };
+    dirs.extend(defaults.iter().map(PathBuf::from));
+    dirs
+}
+
+fn read_ld_so_conf(path: &Path, dirs: &mut Vec<PathBuf>, depth: usize) {
+    // Guards against include loops.
+    if depth > 8 {
+        return;
+    }
+    let Ok(contents) = fs::read_to_string(path) else {
+        return;
+    };
+
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if let Some(pattern) = line.strip_prefix("include") {
+            for include in glob(pattern.trim()) {
+                read_ld_so_conf(&include, dirs, depth + 1);
+            }
+        } else if !line.is_empty() {
+            dirs.push(PathBuf::from(line));
+        }
+    }
+}
+
+/// Expands a pattern with a single `*` in its file name, which is all that
+/// ld.so.conf include lines use in practice.
+fn glob(pattern: &str) -> Vec<PathBuf> {
+    let pattern = Path::new(pattern);
+    let file_pattern = pattern
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let Some((prefix, suffix)) = file_pattern.split_once('*') else {
+        return vec![pattern.to_path_buf()];
+    };
+
+    let dir = pattern.parent().unwrap_or(Path::new("/"));
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .map(|n| n.to_string_lossy())
+                .is_some_and(|n| n.starts_with(prefix) && n.ends_with(suffix))
+        })
+        .collect();
+    paths.sort();
+    paths
+}
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use gimli::AttributeValue;
+
+use crate::dwarf::{self, Reader};
+use crate::dwarfexpr;
+use crate::elf::ElfFile;
+use crate::emachine::EMachine;
+
+/// The attributes whose blocks are DWARF expressions.
+const HOLDS_EXPRESSION: &[gimli::DwAt] = &[
+    gimli::DW_AT_location,
+    gimli::DW_AT_frame_base,
+    gimli::DW_AT_data_member_location,
+    gimli::DW_AT_vtable_elem_location,
+    gimli::DW_AT_use_location,
+    gimli::DW_AT_string_length,
+    gimli::DW_AT_return_addr,
+    gimli::DW_AT_static_link,
+    gimli::DW_AT_segment,
+    gimli::DW_AT_call_value,
+    gimli::DW_AT_call_data_value,
+    gimli::DW_AT_call_data_location,
+    gimli::DW_AT_call_target,
+    gimli::DW_AT_GNU_call_site_value,
+    gimli::DW_AT_GNU_call_site_target,
+];
+
+/// An attribute value as text. Expressions are decoded into their
+/// operations, and location lists into one line per range after the
+/// attribute.
+fn value<'a>(
+    dwarf: &gimli::Dwarf<Reader<'a>>,
+    unit: &gimli::Unit<Reader<'a>>,
+    attr: &gimli::Attribute<Reader<'a>>,
+    machine: EMachine,
+) -> Result<(String, Vec<String>)> {
+    let unit_start = unit.header.debug_info_offset().map_or(0, |o| o.0);
+    let expression = |data: &[u8]| {
+        format!(
+            "({})",
+            dwarfexpr::operations(data, unit.encoding(), machine)
+        )
+    };
+
+    let text = match attr.value() {
+        AttributeValue::Exprloc(expr) => expression(expr.0.slice()),
+        AttributeValue::LocationListsRef(offset) => {
+            let mut lines = Vec::new();
+            let mut locations = dwarf.locations(unit, offset)?;
+            while let Some(location) = locations.next()? {
+                lines.push(format!(
+                    "[{:#x}, {:#x}): {}",
+                    location.range.begin,
+                    location.range.end,
+                    expression(location.data.0.slice())
+                ));
+            }
+            return Ok((format!("location list at {:#x}", offset.0), lines));
+        }
+        AttributeValue::RangeListsRef(offset) => format!("range list at {:#x}", offset.0),
+        AttributeValue::Addr(address) => format!("{:#x}", address),
+        AttributeValue::DebugAddrIndex(index) => {
+            format!("{:#x} (index {})", dwarf.address(unit, index)?, index.0)
+        }
+        AttributeValue::UnitRef(offset) => format!("<{:#x}>", unit_start + offset.0),
+        AttributeValue::DebugInfoRef(offset) => format!("<{:#x}>", offset.0),
+        AttributeValue::Flag(flag) => (flag as u8).to_string(),
+        AttributeValue::Udata(value) => value.to_string(),
+        AttributeValue::Sdata(value) => value.to_string(),
+        AttributeValue::Data1(value) => value.to_string(),
+        AttributeValue::Data2(value) => value.to_string(),
+        AttributeValue::Data4(value) => value.to_string(),
+        AttributeValue::Data8(value) => value.to_string(),
+        // Before DWARF 4, expressions were encoded as plain blocks.
+        AttributeValue::Block(block) if HOLDS_EXPRESSION.contains(&attr.name()) => {
+            expression(block.slice())
+        }
+        AttributeValue::Block(block) => format!("{} byte block", block.len()),
+        AttributeValue::FileIndex(index) => {
+            let path = unit.line_program.as_ref().and_then(|program| {
+                let file = program.header().file(index)?;
+                let comp_dir = unit
+                    .comp_dir
+                    .map(|d| d.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                dwarf::file_path(dwarf, unit, &comp_dir, program.header(), file, false).ok()
+            });
+            match path {
+                Some(path) => format!("{} ({})", index, path),
+                None => index.to_string(),
+            }
+        }
+        AttributeValue::Language(language) => language.to_string(),
+        AttributeValue::Encoding(encoding) => encoding.to_string(),
+        AttributeValue::Accessibility(access) => access.to_string(),
+        AttributeValue::Inline(inline) => inline.to_string(),
+        AttributeValue::CallingConvention(convention) => convention.to_string(),
+        AttributeValue::DebugLineRef(offset) => format!("{:#x}", offset.0),
+        AttributeValue::SecOffset(offset) => format!("{:#x}", offset),
+        value => match dwarf.attr_string(unit, value) {
+            Ok(string) => string.to_string_lossy().into_owned(),
+            Err(_) => format!("{:?}", value),
+        },
+    };
+    Ok((text, Vec::new()))
+}
+
+/// Dumps the debugging information entries of every compilation unit,
+/// indented by depth, with location expressions and frame bases decoded
+/// into their DW_OP operations rather than left as byte blocks.
+pub fn print_dies(elf_file: &ElfFile) -> Result<()> {
+    let Some(dwarf) = dwarf::load(elf_file)? else {
+        println!("There is no DWARF debug info in this file.");
+        return Ok(());
+    };
+    let machine = elf_file.machine();
+
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let start = header.debug_info_offset().map_or(0, |o| o.0);
+        let unit = dwarf.unit(header)?;
+        println!(
+            "Compilation unit at {:#x}: DWARF {}, {}-byte addresses",
+            start,
+            unit.encoding().version,
+            unit.encoding().address_size
+        );
+
+        let mut entries = unit.entries();
+        while let Some(entry) = entries.next_dfs()? {
+            let indent = "  ".repeat(entry.depth() as usize);
+            println!(
+                " {}<{}><{:#x}>: {}",
+                indent,
+                entry.depth(),
+                start + entry.offset().0,
+                entry.tag()
+            );
+            for attr in entry.attrs() {
+                let (text, lines) = value(&dwarf, &unit, attr, machine)?;
+                println!("    {}{:<24} {}", indent, attr.name().to_string(), text);
+                for line in lines {
+                    println!("    {}  {}", indent, line);
+                }
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
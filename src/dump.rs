@@ -0,0 +1,228 @@
+use anyhow::Result;
+use std::io::{self, Write};
+
+use crate::elf::ElfFile;
+use crate::section::{SectionHeader, SectionType};
+
+/// The sections `selectors` ask for, in section order: each selector is a
+/// section number or a name, which selects every section of that name.
+/// Like readelf, names that match nothing are warned about here, latest
+/// first, and numbers past the last section are returned to be warned
+/// about once the dumps are done.
+fn selected(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    selectors: &[String],
+) -> (Vec<usize>, Vec<usize>) {
+    let mut indexes = Vec::new();
+    let mut missing = Vec::new();
+    for selector in selectors.iter().rev() {
+        if let Ok(index) = selector.parse::<usize>() {
+            if index < sections.len() {
+                indexes.push(index);
+            } else {
+                missing.push(index);
+            }
+            continue;
+        }
+
+        let before = indexes.len();
+        indexes.extend(
+            sections
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| elf_file.section_name(sections, s).ok() == Some(selector))
+                .map(|(i, _)| i),
+        );
+        if indexes.len() == before {
+            eprintln!(
+                "readelf-rs: Warning: Section '{}' was not dumped because it does not exist",
+                selector
+            );
+        }
+    }
+    indexes.sort_unstable();
+    indexes.dedup();
+    missing.sort_unstable();
+    missing.dedup();
+    (indexes, missing)
+}
+
+/// Whether a REL or RELA section applies to `index`, in which case the dump
+/// shows the bytes before relocation.
+fn has_relocations(sections: &[SectionHeader], index: usize) -> bool {
+    sections.iter().any(|s| {
+        (s.sh_type == SectionType::REL || s.sh_type == SectionType::RELA)
+            && s.sh_info as usize == index
+            && s.sh_size != 0
+            && (s.sh_link as usize) < sections.len()
+    })
+}
+
+/// The contents of a section to dump, or `None` after saying there are none.
+fn contents<'a>(
+    elf_file: &'a ElfFile,
+    sections: &[SectionHeader],
+    section: &SectionHeader,
+) -> Result<Option<&'a [u8]>> {
+    if section.sh_size == 0 || !section.has_file_data() {
+        println!(
+            "Section '{}' has no data to dump.",
+            elf_file
+                .section_name(sections, section)
+                .unwrap_or("<corrupt>")
+        );
+        return Ok(None);
+    }
+    Ok(Some(elf_file.section_data(section)?))
+}
+
+fn print_hex_dump(elf_file: &ElfFile, sections: &[SectionHeader], index: usize) -> Result<()> {
+    let section = &sections[index];
+    let Some(data) = contents(elf_file, sections, section)? else {
+        return Ok(());
+    };
+
+    println!();
+    println!(
+        "Hex dump of section '{}':",
+        elf_file
+            .section_name(sections, section)
+            .unwrap_or("<corrupt>")
+    );
+    if has_relocations(sections, index) {
+        println!(
+            " NOTE: This section has relocations against it, but these have NOT been applied to this dump."
+        );
+    }
+    let mut address = section.sh_addr;
+    for line in data.chunks(16) {
+        let mut hex = String::new();
+        for j in 0..16 {
+            match line.get(j) {
+                Some(byte) => hex.push_str(&format!("{:02x}", byte)),
+                None => hex.push_str("  "),
+            }
+            if j % 4 == 3 {
+                hex.push(' ');
+            }
+        }
+        let text: String = line
+            .iter()
+            .map(|&b| {
+                if (0x20..0x7f).contains(&b) {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        println!("  0x{:08x} {}{}", address, hex, text);
+        address = address.wrapping_add(16);
+    }
+    println!();
+    Ok(())
+}
+
+/// The strings of a section as readelf shows them: each run of bytes that
+/// starts with a printable character, up to a NUL or a newline, with its
+/// offset. A string that a newline ends is continued by the next one, which
+/// is indented instead. Control characters are shown with a caret, as
+/// readelf adds 0x40 to them.
+fn strings(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    let mut continuing = false;
+    loop {
+        while data.get(offset).is_some_and(|b| !(0x20..0x7f).contains(b)) {
+            offset += 1;
+        }
+        if offset >= data.len() {
+            break;
+        }
+
+        if continuing {
+            out.extend(b"            ");
+            continuing = false;
+        } else {
+            out.extend(format!("  [{:6x}]  ", offset).bytes());
+        }
+        let mut newline = false;
+        while let Some(&byte) = data.get(offset) {
+            offset += 1;
+            match byte {
+                0 => break,
+                b'\n' => {
+                    out.extend(b"\\n\n");
+                    continuing = data.get(offset).is_some_and(|&b| b != 0);
+                    newline = true;
+                    break;
+                }
+                0x00..0x20 | 0x7f => out.extend([b'^', byte.wrapping_add(0x40)]),
+                _ => out.push(byte),
+            }
+        }
+        if !newline {
+            out.push(b'\n');
+        }
+    }
+    out
+}
+
+fn print_string_dump(elf_file: &ElfFile, sections: &[SectionHeader], index: usize) -> Result<()> {
+    let section = &sections[index];
+    let Some(data) = contents(elf_file, sections, section)? else {
+        return Ok(());
+    };
+
+    println!();
+    println!(
+        "String dump of section '{}':",
+        elf_file
+            .section_name(sections, section)
+            .unwrap_or("<corrupt>")
+    );
+    if has_relocations(sections, index) {
+        println!(
+            "  Note: This section has relocations against it, but these have NOT been applied to this dump."
+        );
+    }
+    let strings = strings(data);
+    if strings.is_empty() {
+        println!("  No strings found in this section.");
+        return Ok(());
+    }
+    // The bytes are written as they are, since a string need not be UTF-8.
+    let mut out = io::stdout().lock();
+    out.write_all(&strings)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Prints the hex dumps and string dumps of the sections `hex` and
+/// `strings` select, in the style of `readelf -x` and `readelf -p`. Each
+/// section gets its hex dump, then its string dump.
+pub fn print_dumps(elf_file: &ElfFile, hex: &[String], strings: &[String]) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let (hex, mut missing) = selected(elf_file, &sections, hex);
+    let (strings, missing_strings) = selected(elf_file, &sections, strings);
+    missing.extend(missing_strings);
+    missing.sort_unstable();
+    missing.dedup();
+
+    for index in 0..sections.len() {
+        if hex.contains(&index) {
+            print_hex_dump(elf_file, &sections, index)?;
+        }
+        if strings.contains(&index) {
+            print_string_dump(elf_file, &sections, index)?;
+        }
+    }
+    for index in missing {
+        eprintln!(
+            "readelf-rs: Warning: Section {} was not dumped because it does not exist!",
+            index
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,158 @@
+//! String literals stored more than once. Linkers merge identical strings
+//! of `SHF_MERGE | SHF_STRINGS` sections, but not those of named arrays,
+//! of objects built without `-fmerge-constants` or of writable data, so
+//! each copy of those takes room of its own. Only NUL-terminated strings
+//! are seen, which leaves out the literals of languages like Rust.
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::elf::ElfFile;
+use crate::section::{SHF_EXECINSTR, SHF_WRITE, SectionType};
+use crate::strtab::SymbolTable;
+use crate::symbol::SymbolType;
+
+/// Shorter strings are too likely to be other data that happens to look
+/// like text.
+const MIN_LENGTH: usize = 4;
+
+/// How many copies of each string to list.
+const COPIES: usize = 5;
+
+/// Sections of the right type that hold tables rather than literals.
+const NOT_STRINGS: &[&str] = &[
+    ".eh_frame",
+    ".gcc_except_table",
+    ".got",
+    ".init_array",
+    ".fini_array",
+    ".interp",
+    ".note",
+];
+
+/// Where one copy of a string is.
+struct Copy<'a> {
+    section: &'a str,
+    address: u64,
+    symbol: Option<&'a str>,
+    writable: bool,
+}
+
+fn is_text(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes).is_ok_and(|s| {
+        s.chars()
+            .all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+    })
+}
+
+/// Prints the `top` strings whose extra copies take the most room, and
+/// how much merging all of them would save.
+pub fn print_duplicate_strings(elf_file: &ElfFile, top: usize) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+
+    // Named objects explain why a copy was not merged.
+    let mut objects: HashMap<u64, &str> = HashMap::new();
+    if let Some(symtab) = elf_file.symbol_table(&sections) {
+        let symbols = SymbolTable::new(elf_file, &sections, symtab)?;
+        for (index, symbol) in symbols.iter().enumerate() {
+            if symbol.symbol_type() == SymbolType::OBJECT && symbol.st_size > 0 {
+                objects.insert(symbol.st_value, symbols.name(index)?);
+            }
+        }
+    }
+
+    let mut strings: HashMap<&[u8], Vec<Copy>> = HashMap::new();
+    let mut scanned = 0;
+    for section in &sections {
+        let name = elf_file.section_name(&sections, section)?;
+        if section.sh_type != SectionType::PROGBITS
+            || !section.is_alloc()
+            || section.sh_flags & SHF_EXECINSTR != 0
+            || NOT_STRINGS.iter().any(|prefix| name.starts_with(prefix))
+        {
+            continue;
+        }
+        let data = elf_file.section_data(section)?;
+        scanned += data.len();
+        let writable = section.sh_flags & SHF_WRITE != 0;
+        let mut start = 0;
+        for (index, &byte) in data.iter().enumerate() {
+            if byte != 0 {
+                continue;
+            }
+            let string = &data[start..index];
+            let at = start;
+            start = index + 1;
+            if string.len() < MIN_LENGTH || !is_text(string) {
+                continue;
+            }
+            let address = section.sh_addr + at as u64;
+            strings.entry(string).or_default().push(Copy {
+                section: name,
+                address,
+                symbol: objects.get(&address).copied(),
+                writable,
+            });
+        }
+    }
+
+    let mut duplicates: Vec<(&[u8], Vec<Copy>)> = strings
+        .into_iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .collect();
+    let saving =
+        |string: &[u8], copies: &[Copy]| (copies.len() as u64 - 1) * (string.len() as u64 + 1);
+    duplicates.sort_by(|(a, a_copies), (b, b_copies)| {
+        saving(b, b_copies).cmp(&saving(a, a_copies)).then(a.cmp(b))
+    });
+    let total: u64 = duplicates
+        .iter()
+        .map(|(string, copies)| saving(string, copies))
+        .sum();
+
+    println!(
+        "Duplicate strings of at least {} bytes in {} bytes of data:",
+        MIN_LENGTH, scanned
+    );
+    if duplicates.is_empty() {
+        println!("  No string is stored more than once.");
+        return Ok(());
+    }
+    println!(
+        "  {} strings are stored more than once; merging them would save {} bytes",
+        duplicates.len(),
+        total
+    );
+    println!();
+    println!("  {:>6} {:>8}  String and copies", "Copies", "Saving");
+    for (string, copies) in duplicates.iter().take(top) {
+        let text = String::from_utf8_lossy(string);
+        let shown: String = if text.chars().count() > 60 {
+            text.chars().take(57).collect::<String>() + "..."
+        } else {
+            text.into_owned()
+        };
+        println!(
+            "  {:>6} {:>8}  {:?}",
+            copies.len(),
+            saving(string, copies),
+            shown
+        );
+        for copy in copies.iter().take(COPIES) {
+            let symbol = copy.symbol.map(|s| format!(" [{}]", s)).unwrap_or_default();
+            // Writable copies cannot be shared with anything.
+            let writable = if copy.writable { ", writable" } else { "" };
+            println!(
+                "  {:>16}  {:#x} in {}{}{}",
+                "", copy.address, copy.section, writable, symbol
+            );
+        }
+        if copies.len() > COPIES {
+            println!("  {:>16}  and {} more copies", "", copies.len() - COPIES);
+        }
+    }
+    if duplicates.len() > top {
+        println!("  and {} more", duplicates.len() - top);
+    }
+    Ok(())
+}
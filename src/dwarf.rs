@@ -0,0 +1,297 @@
+use anyhow::Result;
+use gimli::{EndianSlice, RunTimeEndian};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::elf::ElfFile;
+
+pub type Reader<'a> = EndianSlice<'a, RunTimeEndian>;
+
+/// The byte order of the file, as gimli wants it.
+pub fn endian(elf_file: &ElfFile) -> RunTimeEndian {
+    if elf_file.ident().data == 1 {
+        RunTimeEndian::Little
+    } else {
+        RunTimeEndian::Big
+    }
+}
+
+/// Loads the DWARF sections of the file, or `None` if it has no debug info.
+pub fn load<'a>(elf_file: &'a ElfFile) -> Result<Option<gimli::Dwarf<Reader<'a>>>> {
+    let sections = elf_file.section_headers()?;
+    let endian = endian(elf_file);
+
+    let find = |name: &str| -> Result<&'a [u8]> {
+        for section in &sections {
+            if elf_file.section_name(&sections, section).ok() == Some(name) {
+                return elf_file.section_data(section);
+            }
+        }
+        Ok(&[])
+    };
+
+    if find(".debug_info")?.is_empty() {
+        return Ok(None);
+    }
+
+    let dwarf = gimli::Dwarf::load(|id| -> Result<Reader<'a>> {
+        Ok(EndianSlice::new(find(id.name())?, endian))
+    })?;
+
+    Ok(Some(dwarf))
+}
+
+/// A source path as it is printed. In deterministic mode, paths under the
+/// compilation directory are made relative to it and other absolute paths
+/// are cut down to their file name, since both depend on where the code
+/// was built.
+pub fn display_path(path: &Path, comp_dir: &str, deterministic: bool) -> String {
+    if !deterministic {
+        return path.to_string_lossy().into_owned();
+    }
+    let path = path.strip_prefix(comp_dir).unwrap_or(path);
+    match path.file_name() {
+        Some(name) if path.is_absolute() => name.to_string_lossy().into_owned(),
+        _ => path.to_string_lossy().into_owned(),
+    }
+}
+
+/// The path of a file in a line program's file table, under the unit's
+/// compilation directory when it is relative, as `display_path` prints it.
+pub fn file_path(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    comp_dir: &str,
+    header: &gimli::LineProgramHeader<Reader>,
+    file: &gimli::FileEntry<Reader>,
+    deterministic: bool,
+) -> Result<String> {
+    let mut path = Path::new(comp_dir).to_path_buf();
+    if let Some(dir) = file.directory(header) {
+        path.push(&*dwarf.attr_string(unit, dir)?.to_string_lossy());
+    }
+    path.push(&*dwarf.attr_string(unit, file.path_name())?.to_string_lossy());
+    Ok(display_path(&path, comp_dir, deterministic))
+}
+
+/// Marks a row that ends a sequence, after which there is no location.
+const END_OF_SEQUENCE: usize = usize::MAX;
+
+/// Address to source location mapping built from the DWARF line programs.
+pub struct LineTable {
+    files: Vec<String>,
+    /// (address, index into `files`, line), sorted by address.
+    rows: Vec<(u64, usize, u32)>,
+}
+
+impl LineTable {
+    /// `deterministic` is passed on to `display_path` for the file names.
+    pub fn new(dwarf: &gimli::Dwarf<Reader>, deterministic: bool) -> Result<Self> {
+        let mut files = Vec::new();
+        let mut file_indices = HashMap::new();
+        let mut rows = Vec::new();
+
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+
+            let comp_dir = unit
+                .comp_dir
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let mut program_rows = program.rows();
+            while let Some((header, row)) = program_rows.next_row()? {
+                if row.end_sequence() {
+                    rows.push((row.address(), END_OF_SEQUENCE, 0));
+                    continue;
+                }
+
+                let Some(file) = row.file(header) else {
+                    continue;
+                };
+
+                let path = file_path(dwarf, &unit, &comp_dir, header, file, deterministic)?;
+
+                let index = *file_indices.entry(path.clone()).or_insert_with(|| {
+                    files.push(path);
+                    files.len() - 1
+                });
+                let line = row.line().map_or(0, |l| l.get() as u32);
+                rows.push((row.address(), index, line));
+            }
+        }
+
+        // A stable sort keeps the end of one sequence before the start of a
+        // sequence beginning at the same address.
+        rows.sort_by_key(|&(address, _, _)| address);
+
+        Ok(Self { files, rows })
+    }
+
+    /// Source file and line of the instruction at `address`.
+    pub fn location(&self, address: u64) -> Option<(&str, u32)> {
+        let index = self.rows.partition_point(|&(a, _, _)| a <= address);
+        let &(_, file, line) = self.rows.get(index.checked_sub(1)?)?;
+        if file == END_OF_SEQUENCE {
+            return None;
+        }
+
+        Some((&self.files[file], line))
+    }
+}
+
+/// A source location, as an index into the file names and a line.
+type Declared = (usize, u32);
+
+/// Where functions and variables are declared in the source, from the
+/// `DW_AT_decl_file` and `DW_AT_decl_line` of their DIEs. Unlike the line
+/// table, this covers data as well as code.
+pub struct Declarations {
+    files: Vec<String>,
+    addresses: HashMap<u64, Declared>,
+    /// `None` for names declared in more than one place, such as static
+    /// functions of different files.
+    names: HashMap<String, Option<Declared>>,
+}
+
+impl Declarations {
+    /// `deterministic` is passed on to `display_path` for the file names.
+    pub fn new(dwarf: &gimli::Dwarf<Reader>, deterministic: bool) -> Result<Self> {
+        let mut declarations = Self {
+            files: Vec::new(),
+            addresses: HashMap::new(),
+            names: HashMap::new(),
+        };
+        let mut file_indices = HashMap::new();
+
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let Some(program) = &unit.line_program else {
+                continue;
+            };
+            let comp_dir = unit
+                .comp_dir
+                .map(|dir| dir.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let mut entries = unit.entries();
+            while let Some(entry) = entries.next_dfs()? {
+                if entry.tag() != gimli::DW_TAG_subprogram && entry.tag() != gimli::DW_TAG_variable
+                {
+                    continue;
+                }
+                let Some(address) = die_address(dwarf, &unit, entry)? else {
+                    continue;
+                };
+
+                // Out-of-line definitions may leave the location, and the
+                // name, to the declaration they complete.
+                let mut origin = None;
+                for attr in [gimli::DW_AT_specification, gimli::DW_AT_abstract_origin] {
+                    if let Some(gimli::AttributeValue::UnitRef(offset)) = entry.attr_value(attr) {
+                        origin = Some(unit.entry(offset)?);
+                        break;
+                    }
+                }
+                let attr = |name| {
+                    entry
+                        .attr_value(name)
+                        .or_else(|| origin.as_ref().and_then(|o| o.attr_value(name)))
+                };
+
+                let (Some(file), Some(line)) = (
+                    attr(gimli::DW_AT_decl_file).and_then(|v| match v {
+                        gimli::AttributeValue::FileIndex(index) => Some(index),
+                        v => v.udata_value(),
+                    }),
+                    attr(gimli::DW_AT_decl_line).and_then(|v| v.udata_value()),
+                ) else {
+                    continue;
+                };
+                let Some(file) = program.header().file(file) else {
+                    continue;
+                };
+                let path = file_path(
+                    dwarf,
+                    &unit,
+                    &comp_dir,
+                    program.header(),
+                    file,
+                    deterministic,
+                )?;
+                let index = *file_indices.entry(path.clone()).or_insert_with(|| {
+                    declarations.files.push(path);
+                    declarations.files.len() - 1
+                });
+                let declared = (index, line as u32);
+
+                declarations.addresses.entry(address).or_insert(declared);
+                let name = attr(gimli::DW_AT_linkage_name)
+                    .or_else(|| attr(gimli::DW_AT_MIPS_linkage_name))
+                    .or_else(|| attr(gimli::DW_AT_name));
+                if let Some(name) = name {
+                    let name = dwarf
+                        .attr_string(&unit, name)?
+                        .to_string_lossy()
+                        .into_owned();
+                    declarations
+                        .names
+                        .entry(name)
+                        .and_modify(|d| {
+                            if *d != Some(declared) {
+                                *d = None;
+                            }
+                        })
+                        .or_insert(Some(declared));
+                }
+            }
+        }
+
+        Ok(declarations)
+    }
+
+    /// Where the function or variable at `address` is declared.
+    pub fn by_address(&self, address: u64) -> Option<(&str, u32)> {
+        let &(file, line) = self.addresses.get(&address)?;
+        Some((&self.files[file], line))
+    }
+
+    /// Where the function or variable with the given symbol name is
+    /// declared, if only one place declares it.
+    pub fn by_name(&self, name: &str) -> Option<(&str, u32)> {
+        let &(file, line) = self.names.get(name)?.as_ref()?;
+        Some((&self.files[file], line))
+    }
+}
+
+/// The address a function or variable DIE defines: the start of a
+/// function, or the location of a variable with static storage. `None`
+/// for declarations and for everything on the stack or in registers.
+fn die_address(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    entry: &gimli::DebuggingInformationEntry<Reader>,
+) -> Result<Option<u64>> {
+    if let Some(low_pc) = entry.attr_value(gimli::DW_AT_low_pc) {
+        return Ok(dwarf.attr_address(unit, low_pc)?);
+    }
+    let Some(expression) = entry
+        .attr_value(gimli::DW_AT_location)
+        .and_then(|v| v.exprloc_value())
+    else {
+        return Ok(None);
+    };
+    let mut operations = expression.operations(unit.encoding());
+    // Only a lone address is a fixed location.
+    let address = match operations.next()? {
+        Some(gimli::Operation::Address { address }) => address,
+        Some(gimli::Operation::AddressIndex { index }) => dwarf.address(unit, index)?,
+        _ => return Ok(None),
+    };
+    Ok(operations.next()?.is_none().then_some(address))
+}
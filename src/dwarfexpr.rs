@@ -0,0 +1,207 @@
+//! DWARF expression bytecode, decoded into the operations it is made of
+//! the way readelf prints them: `DW_OP_fbreg: -20; DW_OP_deref`. The
+//! opcodes are decoded as they are rather than through gimli's evaluator,
+//! which folds the many encodings of an operation into one.
+
+use gimli::{DwOp, Encoding, Register};
+
+use crate::attributes::read_uleb128;
+use crate::emachine::EMachine;
+
+fn read_sleb128(data: &[u8], offset: &mut usize) -> Option<i64> {
+    let mut result = 0i64;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*offset)?;
+        *offset += 1;
+        if shift < 64 {
+            result |= ((byte & 0x7f) as i64) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            if shift < 64 && byte & 0x40 != 0 {
+                result |= -1 << shift;
+            }
+            return Some(result);
+        }
+    }
+}
+
+fn read_fixed(data: &[u8], offset: &mut usize, size: usize) -> Option<u64> {
+    let bytes = data.get(*offset..*offset + size)?;
+    *offset += size;
+    Some(match size {
+        1 => bytes[0] as u64,
+        2 => u16::from_ne_bytes(bytes.try_into().unwrap()) as u64,
+        4 => u32::from_ne_bytes(bytes.try_into().unwrap()) as u64,
+        _ => u64::from_ne_bytes(bytes.try_into().unwrap()),
+    })
+}
+
+/// Sign-extends a fixed-size operand.
+fn signed(value: u64, size: usize) -> i64 {
+    let shift = 64 - size * 8;
+    ((value << shift) as i64) >> shift
+}
+
+/// The name of a DWARF register on the file's architecture, if gimli
+/// knows it.
+pub fn register_name(machine: EMachine, register: u64) -> Option<&'static str> {
+    let register = Register(u16::try_from(register).ok()?);
+    match machine {
+        EMachine::X8664 => gimli::X86_64::register_name(register),
+        EMachine::I386 => gimli::X86::register_name(register),
+        EMachine::Aarch64 => gimli::AArch64::register_name(register),
+        EMachine::Arm => gimli::Arm::register_name(register),
+        EMachine::Riscv => gimli::RiscV::register_name(register),
+        EMachine::LoongArch => gimli::LoongArch::register_name(register),
+        EMachine::Mips => gimli::MIPS::register_name(register),
+        EMachine::Ppc64 => gimli::PowerPc64::register_name(register),
+        _ => None,
+    }
+}
+
+fn register(machine: EMachine, register: u64) -> String {
+    match register_name(machine, register) {
+        Some(name) => format!(" ({})", name),
+        None => String::new(),
+    }
+}
+
+fn hex_block(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Decodes one operation, returning `None` if its operands run past the
+/// end of the expression.
+fn operation(
+    data: &[u8],
+    offset: &mut usize,
+    encoding: Encoding,
+    machine: EMachine,
+) -> Option<String> {
+    let opcode = *data.get(*offset)?;
+    *offset += 1;
+    let op = DwOp(opcode);
+    let name = op
+        .static_string()
+        .map_or_else(|| format!("DW_OP_<unknown {:#x}>", opcode), str::to_string);
+    let address_size = encoding.address_size as usize;
+    let offset_size = encoding.format.word_size() as usize;
+    let uleb = |offset: &mut usize| read_uleb128(data, offset);
+    let sleb = |offset: &mut usize| read_sleb128(data, offset);
+
+    let operands = match op {
+        gimli::DW_OP_addr => format!(": {:#x}", read_fixed(data, offset, address_size)?),
+        gimli::DW_OP_const1u
+        | gimli::DW_OP_pick
+        | gimli::DW_OP_deref_size
+        | gimli::DW_OP_xderef_size => format!(": {}", read_fixed(data, offset, 1)?),
+        gimli::DW_OP_const2u | gimli::DW_OP_call2 => format!(": {}", read_fixed(data, offset, 2)?),
+        gimli::DW_OP_const4u | gimli::DW_OP_call4 | gimli::DW_OP_GNU_parameter_ref => {
+            format!(": {}", read_fixed(data, offset, 4)?)
+        }
+        gimli::DW_OP_const8u => format!(": {}", read_fixed(data, offset, 8)?),
+        gimli::DW_OP_const1s => format!(": {}", signed(read_fixed(data, offset, 1)?, 1)),
+        gimli::DW_OP_const2s => format!(": {}", signed(read_fixed(data, offset, 2)?, 2)),
+        gimli::DW_OP_const4s => format!(": {}", signed(read_fixed(data, offset, 4)?, 4)),
+        gimli::DW_OP_const8s => format!(": {}", signed(read_fixed(data, offset, 8)?, 8)),
+        gimli::DW_OP_constu
+        | gimli::DW_OP_plus_uconst
+        | gimli::DW_OP_piece
+        | gimli::DW_OP_addrx
+        | gimli::DW_OP_constx
+        | gimli::DW_OP_GNU_addr_index
+        | gimli::DW_OP_GNU_const_index => format!(": {}", uleb(offset)?),
+        gimli::DW_OP_consts | gimli::DW_OP_fbreg => format!(": {}", sleb(offset)?),
+        gimli::DW_OP_bra | gimli::DW_OP_skip => {
+            format!(": {}", signed(read_fixed(data, offset, 2)?, 2))
+        }
+        gimli::DW_OP_regx => {
+            let reg = uleb(offset)?;
+            format!(": {}{}", reg, register(machine, reg))
+        }
+        gimli::DW_OP_bregx => {
+            let reg = uleb(offset)?;
+            format!(": {}{} {}", reg, register(machine, reg), sleb(offset)?)
+        }
+        gimli::DW_OP_bit_piece => {
+            format!(": size: {} offset: {}", uleb(offset)?, uleb(offset)?)
+        }
+        gimli::DW_OP_call_ref | gimli::DW_OP_GNU_variable_value => {
+            format!(": <{:#x}>", read_fixed(data, offset, offset_size)?)
+        }
+        gimli::DW_OP_implicit_pointer | gimli::DW_OP_GNU_implicit_pointer => {
+            let die = read_fixed(data, offset, offset_size)?;
+            format!(": <{:#x}> {}", die, sleb(offset)?)
+        }
+        gimli::DW_OP_implicit_value => {
+            let length = uleb(offset)? as usize;
+            let block = data.get(*offset..*offset + length)?;
+            *offset += length;
+            format!(": {} byte block: {}", length, hex_block(block))
+        }
+        gimli::DW_OP_entry_value | gimli::DW_OP_GNU_entry_value => {
+            let length = uleb(offset)? as usize;
+            let nested = data.get(*offset..*offset + length)?;
+            *offset += length;
+            format!(": ({})", operations(nested, encoding, machine))
+        }
+        gimli::DW_OP_const_type | gimli::DW_OP_GNU_const_type => {
+            let base_type = uleb(offset)?;
+            let length = read_fixed(data, offset, 1)? as usize;
+            let block = data.get(*offset..*offset + length)?;
+            *offset += length;
+            format!(
+                ": <{:#x}> {} byte block: {}",
+                base_type,
+                length,
+                hex_block(block)
+            )
+        }
+        gimli::DW_OP_regval_type | gimli::DW_OP_GNU_regval_type => {
+            let reg = uleb(offset)?;
+            format!(": {}{} <{:#x}>", reg, register(machine, reg), uleb(offset)?)
+        }
+        gimli::DW_OP_deref_type | gimli::DW_OP_GNU_deref_type | gimli::DW_OP_xderef_type => {
+            let size = read_fixed(data, offset, 1)?;
+            format!(": {} <{:#x}>", size, uleb(offset)?)
+        }
+        gimli::DW_OP_convert
+        | gimli::DW_OP_GNU_convert
+        | gimli::DW_OP_reinterpret
+        | gimli::DW_OP_GNU_reinterpret => format!(": <{:#x}>", uleb(offset)?),
+        DwOp(0x50..=0x6f) => register(machine, (opcode - 0x50) as u64),
+        DwOp(0x70..=0x8f) => {
+            let reg = (opcode - 0x70) as u64;
+            format!("{}: {}", register(machine, reg), sleb(offset)?)
+        }
+        // Everything else, from DW_OP_lit* to DW_OP_stack_value, has no
+        // operands.
+        _ => String::new(),
+    };
+
+    Some(format!("{}{}", name, operands))
+}
+
+/// Decodes a DWARF expression into its operations, separated by `; `. An
+/// expression that ends in the middle of an operation is marked as
+/// truncated rather than misread.
+pub fn operations(data: &[u8], encoding: Encoding, machine: EMachine) -> String {
+    let mut decoded = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        match operation(data, &mut offset, encoding, machine) {
+            Some(op) => decoded.push(op),
+            None => {
+                decoded.push("<truncated>".to_string());
+                break;
+            }
+        }
+    }
+    decoded.join("; ")
+}
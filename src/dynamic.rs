@@ -0,0 +1,156 @@
+use std::fmt;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf64Dyn {
+    pub d_tag: i64,
+    pub d_val: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf32Dyn {
+    pub d_tag: i32,
+    pub d_val: u32,
+}
+
+/// A dynamic section entry with its fields widened to 64 bits.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DynamicEntry {
+    pub d_tag: DynamicTag,
+    pub d_val: u64,
+}
+
+impl From<Elf64Dyn> for DynamicEntry {
+    fn from(dyn_: Elf64Dyn) -> Self {
+        Self {
+            d_tag: DynamicTag(dyn_.d_tag),
+            d_val: dyn_.d_val,
+        }
+    }
+}
+
+impl From<Elf32Dyn> for DynamicEntry {
+    fn from(dyn_: Elf32Dyn) -> Self {
+        Self {
+            d_tag: DynamicTag(dyn_.d_tag.into()),
+            d_val: dyn_.d_val.into(),
+        }
+    }
+}
+
+pub const DF_TEXTREL: u64 = 0x4;
+pub const DF_BIND_NOW: u64 = 0x8;
+pub const DF_1_NOW: u64 = 0x1;
+pub const DF_1_PIE: u64 = 0x08000000;
+
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DynamicTag(pub i64);
+
+crate::consts::constants!(DynamicTag(i64), dt, "DT_", {
+    NULL = 0,
+    NEEDED = 1,
+    PLTRELSZ = 2,
+    PLTGOT = 3,
+    HASH = 4,
+    STRTAB = 5,
+    SYMTAB = 6,
+    RELA = 7,
+    RELASZ = 8,
+    RELAENT = 9,
+    STRSZ = 10,
+    SYMENT = 11,
+    INIT = 12,
+    FINI = 13,
+    SONAME = 14,
+    RPATH = 15,
+    SYMBOLIC = 16,
+    REL = 17,
+    RELSZ = 18,
+    RELENT = 19,
+    PLTREL = 20,
+    DEBUG = 21,
+    TEXTREL = 22,
+    JMPREL = 23,
+    BIND_NOW = 24,
+    INIT_ARRAY = 25,
+    FINI_ARRAY = 26,
+    INIT_ARRAYSZ = 27,
+    FINI_ARRAYSZ = 28,
+    RUNPATH = 29,
+    FLAGS = 30,
+    PREINIT_ARRAY = 32,
+    PREINIT_ARRAYSZ = 33,
+    SYMTAB_SHNDX = 34,
+    RELRSZ = 35,
+    RELR = 36,
+    RELRENT = 37,
+    ANDROID_REL = 0x6000000f,
+    ANDROID_RELSZ = 0x60000010,
+    ANDROID_RELA = 0x60000011,
+    ANDROID_RELASZ = 0x60000012,
+    ANDROID_RELR = 0x6fffe000,
+    ANDROID_RELRSZ = 0x6fffe001,
+    ANDROID_RELRENT = 0x6fffe003,
+    GNU_PRELINKED = 0x6ffffdf5,
+    GNU_CONFLICTSZ = 0x6ffffdf6,
+    GNU_LIBLISTSZ = 0x6ffffdf7,
+    CHECKSUM = 0x6ffffdf8,
+    PLTPADSZ = 0x6ffffdf9,
+    MOVEENT = 0x6ffffdfa,
+    MOVESZ = 0x6ffffdfb,
+    FEATURE_1 = 0x6ffffdfc,
+    POSFLAG_1 = 0x6ffffdfd,
+    SYMINSZ = 0x6ffffdfe,
+    SYMINENT = 0x6ffffdff,
+    GNU_HASH = 0x6ffffef5,
+    TLSDESC_PLT = 0x6ffffef6,
+    TLSDESC_GOT = 0x6ffffef7,
+    GNU_CONFLICT = 0x6ffffef8,
+    GNU_LIBLIST = 0x6ffffef9,
+    CONFIG = 0x6ffffefa,
+    DEPAUDIT = 0x6ffffefb,
+    AUDIT = 0x6ffffefc,
+    PLTPAD = 0x6ffffefd,
+    MOVETAB = 0x6ffffefe,
+    SYMINFO = 0x6ffffeff,
+    VERSYM = 0x6ffffff0,
+    RELACOUNT = 0x6ffffff9,
+    RELCOUNT = 0x6ffffffa,
+    FLAGS_1 = 0x6ffffffb,
+    VERDEF = 0x6ffffffc,
+    VERDEFNUM = 0x6ffffffd,
+    VERNEED = 0x6ffffffe,
+    VERNEEDNUM = 0x6fffffff,
+    AUXILIARY = 0x7ffffffd,
+    FILTER = 0x7fffffff,
+});
+
+#[allow(dead_code)]
+impl DynamicTag {
+    /// Whether `d_val` is an offset into the dynamic string table.
+    pub fn is_string(&self) -> bool {
+        matches!(
+            *self,
+            Self::NEEDED
+                | Self::SONAME
+                | Self::RPATH
+                | Self::RUNPATH
+                | Self::AUXILIARY
+                | Self::FILTER
+                | Self::CONFIG
+                | Self::DEPAUDIT
+                | Self::AUDIT
+        )
+    }
+}
+
+impl fmt::Display for DynamicTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{:#x}", self.0),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use anyhow::Result;
+
+use crate::dynamic::{DF_BIND_NOW, DF_TEXTREL, DynamicEntry, DynamicTag, Elf32Dyn, Elf64Dyn};
+use crate::elf::ElfFile;
+use crate::plugin::Registry;
+use crate::section::SectionType;
+use crate::segment::SegmentType;
+use crate::strtab::StringTable;
+
+const DF_ORIGIN: u64 = 0x1;
+const DF_SYMBOLIC: u64 = 0x2;
+const DF_STATIC_TLS: u64 = 0x10;
+
+/// The `DF_1_*` flags, in bit order.
+const FLAGS_1: &[(u64, &str)] = &[
+    (0x1, "NOW"),
+    (0x2, "GLOBAL"),
+    (0x4, "GROUP"),
+    (0x8, "NODELETE"),
+    (0x10, "LOADFLTR"),
+    (0x20, "INITFIRST"),
+    (0x40, "NOOPEN"),
+    (0x80, "ORIGIN"),
+    (0x100, "DIRECT"),
+    (0x200, "TRANS"),
+    (0x400, "INTERPOSE"),
+    (0x800, "NODEFLIB"),
+    (0x1000, "NODUMP"),
+    (0x2000, "CONFALT"),
+    (0x4000, "ENDFILTEE"),
+    (0x8000, "DISPRELDNE"),
+    (0x10000, "DISPRELPND"),
+    (0x20000, "NODIRECT"),
+    (0x40000, "IGNMULDEF"),
+    (0x80000, "NOKSYMS"),
+    (0x100000, "NOHDR"),
+    (0x200000, "EDITED"),
+    (0x400000, "NORELOC"),
+    (0x800000, "SYMINTPOSE"),
+    (0x1000000, "GLOBAUDIT"),
+    (0x2000000, "SINGLETON"),
+    (0x4000000, "STUB"),
+    (0x8000000, "PIE"),
+    (0x10000000, "KMOD"),
+    (0x20000000, "WEAKFILTER"),
+    (0x40000000, "NOCOMMON"),
+];
+
+/// The `DF_P1_*` flags of `DT_POSFLAG_1`.
+const POSFLAG_1: &[(u64, &str)] = &[(0x1, "LAZY"), (0x2, "GROUP")];
+
+/// The `DTF_1_*` flags of `DT_FEATURE_1`.
+const FEATURE_1: &[(u64, &str)] = &[(0x1, "PARINIT"), (0x2, "CONFEXP")];
+
+/// The name readelf gives a tag, which for unknown ones says which range
+/// it is in.
+fn tag_name(elf_file: &ElfFile, registry: &Registry, tag: DynamicTag) -> String {
+    if let Some(decoder) = registry.dynamic_tag_decoder(elf_file, tag) {
+        return decoder.tag_name(tag);
+    }
+    match tag.name() {
+        Some(name) => name.to_string(),
+        None if (0x70000000..=0x7fffffff).contains(&tag.0) => {
+            format!("Processor Specific: {:x}", tag.0)
+        }
+        None if (0x6000000d..=0x6ffff000).contains(&tag.0) => {
+            format!("Operating System specific: {:x}", tag.0)
+        }
+        None => format!("<unknown>: {:x}", tag.0),
+    }
+}
+
+/// `Flags:` followed by the names of the set flags, and whatever bits are
+/// left over in hex.
+fn flag_names(value: u64, names: &[(u64, &str)]) -> String {
+    if value == 0 {
+        return "Flags: None".to_string();
+    }
+    let mut text = "Flags:".to_string();
+    let mut rest = value;
+    for &(flag, name) in names {
+        if rest & flag != 0 {
+            text.push(' ');
+            text.push_str(name);
+            rest &= !flag;
+        }
+    }
+    if rest != 0 {
+        text.push_str(&format!(" {:x}", rest));
+    }
+    text
+}
+
+/// The names of the `DF_*` flags of `DT_FLAGS`, in bit order.
+fn dt_flags(value: u64) -> String {
+    let mut names = Vec::new();
+    let mut rest = value;
+    while rest != 0 {
+        let flag = rest & rest.wrapping_neg();
+        rest &= !flag;
+        names.push(match flag {
+            DF_ORIGIN => "ORIGIN",
+            DF_SYMBOLIC => "SYMBOLIC",
+            DF_TEXTREL => "TEXTREL",
+            DF_BIND_NOW => "BIND_NOW",
+            DF_STATIC_TLS => "STATIC_TLS",
+            _ => "unknown",
+        });
+    }
+    names.join(" ")
+}
+
+/// The value of an entry, formatted as readelf formats it for its tag.
+fn value(
+    elf_file: &ElfFile,
+    registry: &Registry,
+    strtab: &StringTable,
+    entry: &DynamicEntry,
+) -> Result<String> {
+    let hex = format!("{:#x}", entry.d_val);
+    let string = strtab.get(entry.d_val as usize).ok();
+    let library = |kind: &str| match string {
+        Some(name) => format!("{}: [{}]", kind, name),
+        None => format!("{}: {}", kind, hex),
+    };
+
+    Ok(match entry.d_tag {
+        DynamicTag::NEEDED => match string {
+            Some(name) if elf_file.interpreter()? == Some(name) => {
+                format!("Shared library: [{}] program interpreter", name)
+            }
+            Some(name) => format!("Shared library: [{}]", name),
+            None => hex,
+        },
+        DynamicTag::SONAME => string.map_or(hex, |name| format!("Library soname: [{}]", name)),
+        DynamicTag::RPATH => string.map_or(hex, |name| format!("Library rpath: [{}]", name)),
+        DynamicTag::RUNPATH => string.map_or(hex, |name| format!("Library runpath: [{}]", name)),
+        DynamicTag::AUXILIARY => library("Auxiliary library"),
+        DynamicTag::FILTER => library("Filter library"),
+        DynamicTag::CONFIG => match string {
+            Some(name) => format!("Configuration file: {}", name),
+            None => format!("Configuration file: {}", hex),
+        },
+        DynamicTag::DEPAUDIT => library("Dependency audit library"),
+        DynamicTag::AUDIT => library("Audit library"),
+        DynamicTag::FLAGS => dt_flags(entry.d_val),
+        DynamicTag::FLAGS_1 => flag_names(entry.d_val, FLAGS_1),
+        DynamicTag::POSFLAG_1 => flag_names(entry.d_val, POSFLAG_1),
+        DynamicTag::FEATURE_1 => flag_names(entry.d_val, FEATURE_1),
+        DynamicTag::PLTREL => tag_name(elf_file, registry, DynamicTag(entry.d_val as i64)),
+        DynamicTag::BIND_NOW => String::new(),
+        DynamicTag::PLTRELSZ
+        | DynamicTag::RELASZ
+        | DynamicTag::STRSZ
+        | DynamicTag::RELSZ
+        | DynamicTag::RELAENT
+        | DynamicTag::RELRENT
+        | DynamicTag::RELRSZ
+        | DynamicTag::SYMENT
+        | DynamicTag::RELENT
+        | DynamicTag::PLTPADSZ
+        | DynamicTag::MOVEENT
+        | DynamicTag::MOVESZ
+        | DynamicTag::PREINIT_ARRAYSZ
+        | DynamicTag::INIT_ARRAYSZ
+        | DynamicTag::FINI_ARRAYSZ
+        | DynamicTag::GNU_CONFLICTSZ
+        | DynamicTag::GNU_LIBLISTSZ => format!("{} (bytes)", entry.d_val),
+        DynamicTag::VERDEFNUM
+        | DynamicTag::VERNEEDNUM
+        | DynamicTag::RELACOUNT
+        | DynamicTag::RELCOUNT => entry.d_val.to_string(),
+        tag => match registry.dynamic_tag_decoder(elf_file, tag) {
+            Some(decoder) => decoder.decode(elf_file, entry)?,
+            None => hex,
+        },
+    })
+}
+
+/// Prints the dynamic section in the style of `readelf -d`, up to and
+/// including its first `DT_NULL`.
+pub fn print_dynamic(elf_file: &ElfFile, registry: &Registry) -> Result<()> {
+    // Found as `ElfFile::dynamic_entries` finds it.
+    let sections = elf_file.section_headers()?;
+    let (offset, length) = match sections.iter().find(|s| s.sh_type == SectionType::DYNAMIC) {
+        Some(section) => (section.sh_offset, elf_file.section_data(section)?.len()),
+        None => match elf_file
+            .program_headers()?
+            .iter()
+            .find(|s| s.p_type == SegmentType::DYNAMIC)
+        {
+            Some(segment) => (segment.p_offset, elf_file.segment_data(segment)?.len()),
+            None => {
+                println!();
+                println!("There is no dynamic section in this file.");
+                return Ok(());
+            }
+        },
+    };
+
+    let mut entries = elf_file.dynamic_entries()?;
+    if length / elf_file.class_size::<Elf32Dyn, Elf64Dyn>() > entries.len() {
+        entries.push(DynamicEntry::default());
+    }
+    let strtab = StringTable::new(elf_file.dynamic_strtab(&entries).unwrap_or(&[]));
+
+    println!();
+    println!(
+        "Dynamic section at offset {:#x} contains {} {}:",
+        offset,
+        entries.len(),
+        if entries.len() == 1 {
+            "entry"
+        } else {
+            "entries"
+        }
+    );
+    println!("  Tag        Type                         Name/Value");
+    for entry in &entries {
+        let name = tag_name(elf_file, registry, entry.d_tag);
+        let value = value(elf_file, registry, &strtab, entry)?;
+        if elf_file.is_64() {
+            println!(
+                " 0x{:016x} {:<20} {}",
+                entry.d_tag.0,
+                format!("({})", name),
+                value
+            );
+        } else {
+            println!(
+                " 0x{:08x} {:<28} {}",
+                entry.d_tag.0 as u32,
+                format!("({})", name),
+                value
+            );
+        }
+    }
+
+    Ok(())
+}
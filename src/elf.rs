@@ -1,10 +1,27 @@
 use anyhow::{Context, Result, bail};
-use memmap2::Mmap;
+use memmap2::{Mmap, MmapOptions};
 use std::fmt;
 use std::fs::File;
 use std::path::Path;
 
+use crate::dynamic::{DF_1_PIE, DynamicEntry, DynamicTag, Elf32Dyn, Elf64Dyn};
 use crate::emachine::EMachine;
+use crate::relocation::{self, Elf32Rel, Elf32Rela, Elf64Rel, Elf64Rela, Relocation};
+use crate::section::{
+    Elf32SectionHeader, Elf64SectionHeader, SHF_ALLOC, SHN_XINDEX, SectionHeader, SectionType,
+};
+use crate::segment::{Elf32ProgramHeader, Elf64ProgramHeader, ProgramHeader, SegmentType};
+use crate::symbol::{Elf32Symbol, Elf64Symbol, Symbol};
+use crate::warning::{self, ElfWarning};
+
+/// Marks that the real number of program headers lives in section 0's `sh_info`.
+pub const PN_XNUM: u16 = 0xffff;
+
+/// The `EI_OSABI` values the displays treat differently from the rest.
+pub const ELFOSABI_NONE: u8 = 0;
+pub const ELFOSABI_GNU: u8 = 3;
+pub const ELFOSABI_SOLARIS: u8 = 6;
+pub const ELFOSABI_FREEBSD: u8 = 9;
 
 #[repr(C)]
 #[derive(Debug, Default, Copy, Clone)]
@@ -22,13 +39,21 @@ pub struct ElfIdent {
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 pub struct ElfType(pub u16);
 
+crate::consts::constants!(ElfType(u16), et, "ET_", {
+    NONE = 0,
+    REL = 1,
+    EXEC = 2,
+    DYN = 3,
+    CORE = 4,
+});
+
 impl fmt::Display for ElfType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.0 {
             0 => write!(f, "NONE (None)"),
             1 => write!(f, "REL (Relocatable file)"),
             2 => write!(f, "EXEC (Executable file)"),
-            3 => write!(f, "DYN (FIXME)"),
+            3 => write!(f, "DYN (Shared object file)"),
             4 => write!(f, "CORE (Core file)"),
             0xfe00..=0xfeff => write!(f, "OS Specific: ({:#x})", self.0),
             0xff00..=0xffff => write!(f, "Processor Specific: ({:#x})", self.0),
@@ -75,125 +100,989 @@ pub struct Elf32Header {
     pub e_shstrndx: u16,
 }
 
+/// The ELF header with all fields widened to their 64-bit sizes, so that
+/// callers don't need to care about the class of the file.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct FileHeader {
+    pub e_type: ElfType,
+    pub e_machine: EMachine,
+    pub e_version: u32,
+    pub e_entry: u64,
+    pub e_phoff: u64,
+    pub e_shoff: u64,
+    pub e_flags: u32,
+    pub e_ehsize: u16,
+    pub e_phentsize: u16,
+    pub e_phnum: u16,
+    pub e_shentsize: u16,
+    pub e_shnum: u16,
+    pub e_shstrndx: u16,
+}
+
+impl From<Elf32Header> for FileHeader {
+    fn from(h: Elf32Header) -> Self {
+        Self {
+            e_type: h.e_type,
+            e_machine: h.e_machine,
+            e_version: h.e_version,
+            e_entry: h.e_entry.into(),
+            e_phoff: h.e_phoff.into(),
+            e_shoff: h.e_shoff.into(),
+            e_flags: h.e_flags,
+            e_ehsize: h.e_ehsize,
+            e_phentsize: h.e_phentsize,
+            e_phnum: h.e_phnum,
+            e_shentsize: h.e_shentsize,
+            e_shnum: h.e_shnum,
+            e_shstrndx: h.e_shstrndx,
+        }
+    }
+}
+
+impl From<Elf64Header> for FileHeader {
+    fn from(h: Elf64Header) -> Self {
+        Self {
+            e_type: h.e_type,
+            e_machine: h.e_machine,
+            e_version: h.e_version,
+            e_entry: h.e_entry,
+            e_phoff: h.e_phoff,
+            e_shoff: h.e_shoff,
+            e_flags: h.e_flags,
+            e_ehsize: h.e_ehsize,
+            e_phentsize: h.e_phentsize,
+            e_phnum: h.e_phnum,
+            e_shentsize: h.e_shentsize,
+            e_shnum: h.e_shnum,
+            e_shstrndx: h.e_shstrndx,
+        }
+    }
+}
+
+/// The bytes of a file, either mapped by us or borrowed from the caller.
+enum Data<'a> {
+    Mapped(Mmap),
+    Borrowed(&'a [u8]),
+}
+
+/// A parsed ELF file. Files opened with `open` own their memory map and are
+/// `ElfFile<'static>`; those made with `parse` borrow the caller's bytes
+/// without copying them. The ident and header are copied out at parse time,
+/// and everything else is read from the bytes on demand. Problems the
+/// parser can work around are collected in `warnings` rather than printed.
+///
+/// Nothing is cached or mutated after parsing, so an `ElfFile` and the
+/// iterators it hands out are `Send` and `Sync`, and one file can be shared
+/// by threads that each look at a different part of it.
 pub struct ElfFile<'a> {
-    _mmap: Mmap,
-    ident: &'a ElfIdent,
-    header: ElfHeader<'a>,
+    data: Data<'a>,
+    ident: ElfIdent,
+    header: FileHeader,
+    warnings: Vec<ElfWarning>,
 }
 
-pub enum ElfHeader<'a> {
-    Elf32(&'a Elf32Header),
-    Elf64(&'a Elf64Header),
+impl ElfFile<'static> {
+    /// Maps the file at `path` and parses it.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path.as_ref()).context("Failed to open ELF file")?;
+        let mmap = unsafe { Mmap::map(&file).context("Failed to memory map ELF file")? };
+        Self::from_data(Data::Mapped(mmap))
+    }
+
+    /// Opens the ELF file that starts `offset` bytes into the file at `path`,
+    /// such as one of those bundled in a firmware image.
+    pub fn open_at(path: impl AsRef<Path>, offset: u64) -> Result<Self> {
+        let file = File::open(path.as_ref()).context("Failed to open ELF file")?;
+        if offset >= file.metadata()?.len() {
+            bail!("The offset {:#x} is past the end of the file", offset);
+        }
+        let mmap = unsafe {
+            MmapOptions::new()
+                .offset(offset)
+                .map(&file)
+                .context("Failed to memory map ELF file")?
+        };
+        Self::from_data(Data::Mapped(mmap))
+            .with_context(|| format!("No ELF file at offset {:#x}", offset))
+    }
 }
 
 impl<'a> ElfFile<'a> {
-    pub fn new(path: &str) -> Result<Self> {
-        let path = Path::new(path);
+    /// Parses an ELF image already in memory, borrowing it.
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        Self::from_data(Data::Borrowed(data))
+    }
 
-        let file = File::open(path).context("Failed to open ELF file")?;
-        let mmap = unsafe { Mmap::map(&file).context("Failed to memory map ELF file")? };
+    fn from_data(data: Data<'a>) -> Result<Self> {
+        let bytes: &[u8] = match &data {
+            Data::Mapped(mmap) => mmap,
+            Data::Borrowed(bytes) => bytes,
+        };
 
-        if mmap.len() < 4 || &mmap[0..4] != b"\x7fELF" {
+        if bytes.len() < 4 || &bytes[0..4] != b"\x7fELF" {
             bail!("Not a valid ELF file");
         }
 
-        let ident: &ElfIdent = unsafe { &*(mmap.as_ptr() as *const ElfIdent) };
+        let ident: ElfIdent =
+            read_struct(bytes, 0).context("File is too small to hold an ELF identification")?;
 
         if (ident.data == 1) != cfg!(target_endian = "little") {
             bail!("ELF file endianess does not match the platform's endianess");
         }
 
         let header = match ident.class {
-            1 => {
-                let elf_header: &Elf32Header = unsafe { &*(mmap.as_ptr() as *const Elf32Header) };
-                ElfHeader::Elf32(elf_header)
-            }
-            2 => {
-                let elf_header: &Elf64Header = unsafe { &*(mmap.as_ptr() as *const Elf64Header) };
-                ElfHeader::Elf64(elf_header)
-            }
+            1 | 2 => read_class::<Elf32Header, Elf64Header, FileHeader>(bytes, 0, ident.class == 2)
+                .context("File is too small to hold an ELF header")?,
             _ => {
                 bail!("Invalid ELF class (not 32-bit or 64-bit)");
             }
         };
 
+        let warnings = warning::check_header(&ident, &header, bytes);
         Ok(Self {
-            _mmap: mmap,
+            data,
             ident,
             header,
+            warnings,
         })
     }
-}
 
-macro_rules! display_header {
-    ($f:expr, $header:expr) => {{
-        writeln!(
-            $f,
-            "  Type:                              {}",
-            $header.e_type
-        )?;
-        writeln!(
-            $f,
-            "  Machine:                           {}",
-            $header.e_machine
-        )?;
-        writeln!(
-            $f,
-            "  Version:                           {}",
-            $header.e_version
-        )?;
-        writeln!(
-            $f,
-            "  Entry point address:               0x{:x}",
-            $header.e_entry
-        )?;
-        writeln!(
-            $f,
-            "  Start of program headers:          {} (bytes into file)",
-            $header.e_phoff
-        )?;
-        writeln!(
-            $f,
-            "  Start of section headers:          {} (bytes into file)",
-            $header.e_shoff
-        )?;
-        writeln!(
-            $f,
-            "  Flags:                             0x{:x}",
-            $header.e_flags
-        )?;
-        writeln!(
-            $f,
-            "  Size of this header:               {} (bytes)",
-            $header.e_ehsize
-        )?;
-        writeln!(
-            $f,
-            "  Size of program headers:           {} (bytes)",
-            $header.e_phentsize
-        )?;
-        writeln!(
-            $f,
-            "  Number of program headers:         {}",
-            $header.e_phnum
+    pub fn data(&self) -> &[u8] {
+        match &self.data {
+            Data::Mapped(mmap) => mmap,
+            Data::Borrowed(bytes) => bytes,
+        }
+    }
+
+    pub fn ident(&self) -> &ElfIdent {
+        &self.ident
+    }
+
+    pub fn header(&self) -> &FileHeader {
+        &self.header
+    }
+
+    /// What the parser found odd about the file, in file order. Parsing
+    /// only fails when nothing can be read; everything else ends up here.
+    ///
+    /// ```
+    /// use readelf_rs::elf::ElfFile;
+    /// use readelf_rs::warning::Severity;
+    ///
+    /// // An ELF64 header, in the host's byte order, with every field zero.
+    /// let mut image = [0u8; 64];
+    /// let data = if cfg!(target_endian = "little") { 1 } else { 2 };
+    /// image[..7].copy_from_slice(&[0x7f, b'E', b'L', b'F', 2, data, 1]);
+    ///
+    /// let elf_file = ElfFile::parse(&image)?;
+    /// let version = elf_file
+    ///     .warnings()
+    ///     .iter()
+    ///     .find(|w| w.message.contains("e_version"))
+    ///     .unwrap();
+    /// assert_eq!(version.severity, Severity::Warning);
+    /// assert_eq!(version.offset, 0x14);
+    /// # anyhow::Ok(())
+    /// ```
+    pub fn warnings(&self) -> &[ElfWarning] {
+        &self.warnings
+    }
+
+    pub fn entry(&self) -> u64 {
+        self.header.e_entry
+    }
+
+    pub fn elf_type(&self) -> ElfType {
+        self.header.e_type
+    }
+
+    pub fn machine(&self) -> EMachine {
+        self.header.e_machine
+    }
+
+    pub fn is_64(&self) -> bool {
+        self.ident.class == 2
+    }
+
+    /// Reads the 32 or 64-bit form of a structure, according to the class of
+    /// the file, and widens it into `T`.
+    pub fn read<N32, N64, T>(&self, data: &[u8], offset: u64) -> Option<T>
+    where
+        N32: Copy + Into<T>,
+        N64: Copy + Into<T>,
+    {
+        read_class::<N32, N64, T>(data, offset, self.is_64())
+    }
+
+    /// Size of the 32 or 64-bit form of a structure in this file.
+    pub fn class_size<N32, N64>(&self) -> usize {
+        if self.is_64() {
+            std::mem::size_of::<N64>()
+        } else {
+            std::mem::size_of::<N32>()
+        }
+    }
+
+    /// Reads the section header table, following the extended numbering
+    /// rules when the section count does not fit in `e_shnum`.
+    pub fn section_headers(&self) -> Result<Vec<SectionHeader>> {
+        let FileHeader {
+            e_shoff: shoff,
+            e_shnum: shnum,
+            e_shentsize: shentsize,
+            ..
+        } = self.header;
+
+        if shoff == 0 {
+            return Ok(Vec::new());
+        }
+
+        let first = self.read_section_header(shoff)?;
+        let count = if shnum == 0 {
+            first.sh_size
+        } else {
+            shnum as u64
+        };
+
+        let entsize = self.header_entsize(
+            "Section",
+            shentsize,
+            self.class_size::<Elf32SectionHeader, Elf64SectionHeader>(),
+            count,
         )?;
-        writeln!(
-            $f,
-            "  Size of section headers:           {} (bytes)",
-            $header.e_shentsize
+        if count > (self.data().len() as u64).saturating_sub(shoff) / entsize.max(1) {
+            bail!("Section header table extends past the end of the file");
+        }
+
+        (0..count)
+            .map(|i| self.read_section_header(shoff + i * entsize))
+            .collect()
+    }
+
+    /// Checks that table entries of `entsize` bytes can hold the `size`
+    /// bytes of the `kind` header they store. An empty table may leave it 0.
+    fn header_entsize(&self, kind: &str, entsize: u16, size: usize, count: u64) -> Result<u64> {
+        if count != 0 && (entsize as usize) < size {
+            bail!(
+                "{} header size {} is too small, at least {} is needed",
+                kind,
+                entsize,
+                size
+            );
+        }
+        Ok(entsize as u64)
+    }
+
+    fn read_section_header(&self, offset: u64) -> Result<SectionHeader> {
+        self.read::<Elf32SectionHeader, Elf64SectionHeader, _>(self.data(), offset)
+            .context("Section header table extends past the end of the file")
+    }
+
+    /// Reads the program header table, following the extended numbering
+    /// rules when the segment count does not fit in `e_phnum`.
+    pub fn program_headers(&self) -> Result<Vec<ProgramHeader>> {
+        let FileHeader {
+            e_phoff: phoff,
+            e_phnum: phnum,
+            e_phentsize: phentsize,
+            ..
+        } = self.header;
+
+        if phoff == 0 {
+            return Ok(Vec::new());
+        }
+
+        let count = if phnum == PN_XNUM {
+            self.section_headers()?
+                .first()
+                .map_or(0, |s| s.sh_info as u64)
+        } else {
+            phnum as u64
+        };
+
+        let entsize = self.header_entsize(
+            "Program",
+            phentsize,
+            self.class_size::<Elf32ProgramHeader, Elf64ProgramHeader>(),
+            count,
         )?;
-        writeln!(
-            $f,
-            "  Number of section headers:         {}",
-            $header.e_shnum
+        if count > (self.data().len() as u64).saturating_sub(phoff) / entsize.max(1) {
+            bail!("Program header table extends past the end of the file");
+        }
+
+        (0..count)
+            .map(|i| self.read_program_header(phoff + i * entsize))
+            .collect()
+    }
+
+    fn read_program_header(&self, offset: u64) -> Result<ProgramHeader> {
+        self.read::<Elf32ProgramHeader, Elf64ProgramHeader, _>(self.data(), offset)
+            .context("Program header table extends past the end of the file")
+    }
+
+    /// Index of the section holding section names, taking `SHN_XINDEX` into account.
+    pub fn shstrndx(&self, sections: &[SectionHeader]) -> Option<usize> {
+        match self.header.e_shstrndx {
+            SHN_XINDEX => sections.first().map(|s| s.sh_link as usize),
+            0 => None,
+            n => Some(n as usize),
+        }
+    }
+
+    /// File contents of a section; empty for sections without file data.
+    pub fn section_data(&self, section: &SectionHeader) -> Result<&[u8]> {
+        if !section.has_file_data() {
+            return Ok(&[]);
+        }
+
+        let start = section.sh_offset as usize;
+        let end = start
+            .checked_add(section.sh_size as usize)
+            .filter(|&end| end <= self.data().len())
+            .context("Section extends past the end of the file")?;
+
+        Ok(&self.data()[start..end])
+    }
+
+    /// File contents of a segment (its first `p_filesz` bytes).
+    pub fn segment_data(&self, segment: &ProgramHeader) -> Result<&[u8]> {
+        let start = segment.p_offset as usize;
+        let end = start
+            .checked_add(segment.p_filesz as usize)
+            .filter(|&end| end <= self.data().len())
+            .context("Segment extends past the end of the file")?;
+
+        Ok(&self.data()[start..end])
+    }
+
+    pub fn section_name(
+        &self,
+        sections: &[SectionHeader],
+        section: &SectionHeader,
+    ) -> Result<&str> {
+        // Only `dynamic_sections` makes section headers for such files.
+        if self.header.e_shoff == 0 {
+            return DYNAMIC_SECTION_NAMES
+                .get(section.sh_name as usize)
+                .copied()
+                .context("No section header string table");
+        }
+        let strtab = self
+            .shstrndx(sections)
+            .and_then(|i| sections.get(i))
+            .context("No section header string table")?;
+
+        read_str(self.section_data(strtab)?, section.sh_name as usize)
+    }
+
+    /// Iterates over the entries of a SYMTAB or DYNSYM section.
+    pub fn symbols(
+        &self,
+        section: &SectionHeader,
+    ) -> Result<impl Iterator<Item = Symbol> + Send + Sync + '_> {
+        let size = self.class_size::<Elf32Symbol, Elf64Symbol>();
+        let (data, entsize) = self.table(section, size)?;
+        Ok((0..data.len() / entsize).map(move |i| {
+            self.read::<Elf32Symbol, Elf64Symbol, _>(data, (i * entsize) as u64)
+                .unwrap()
+        }))
+    }
+
+    /// The most complete symbol table available: `.symtab` if the file has
+    /// not been stripped, `.dynsym` otherwise.
+    pub fn symbol_table<'s>(&self, sections: &'s [SectionHeader]) -> Option<&'s SectionHeader> {
+        sections
+            .iter()
+            .find(|s| s.sh_type == SectionType::SYMTAB)
+            .or_else(|| sections.iter().find(|s| s.sh_type == SectionType::DYNSYM))
+    }
+
+    /// Name of a symbol from a table whose string table is linked by `symtab`.
+    pub fn symbol_name(
+        &self,
+        sections: &[SectionHeader],
+        symtab: &SectionHeader,
+        symbol: &Symbol,
+    ) -> Result<&str> {
+        let strtab = sections
+            .get(symtab.sh_link as usize)
+            .context("Symbol table links to a nonexistent string table")?;
+
+        read_str(self.section_data(strtab)?, symbol.st_name as usize)
+    }
+
+    /// Iterates over the entries of a REL or RELA section.
+    pub fn relocations(
+        &self,
+        section: &SectionHeader,
+    ) -> Result<impl Iterator<Item = Relocation> + Send + Sync + '_> {
+        let is_64 = self.is_64();
+        let is_rela = section.sh_type == SectionType::RELA;
+        let size = match (is_64, is_rela) {
+            (true, true) => std::mem::size_of::<Elf64Rela>(),
+            (true, false) => std::mem::size_of::<Elf64Rel>(),
+            (false, true) => std::mem::size_of::<Elf32Rela>(),
+            (false, false) => std::mem::size_of::<Elf32Rel>(),
+        };
+
+        let (data, entsize) = self.table(section, size)?;
+        Ok((0..data.len() / entsize).map(move |i| {
+            let offset = (i * entsize) as u64;
+            match (is_64, is_rela) {
+                (true, true) => read_struct::<Elf64Rela>(data, offset).unwrap().into(),
+                (true, false) => read_struct::<Elf64Rel>(data, offset).unwrap().into(),
+                (false, true) => read_struct::<Elf32Rela>(data, offset).unwrap().into(),
+                (false, false) => read_struct::<Elf32Rel>(data, offset).unwrap().into(),
+            }
+        }))
+    }
+
+    /// Path of the program interpreter requested by `PT_INTERP`, if any.
+    pub fn interpreter(&self) -> Result<Option<&str>> {
+        let segments = self.program_headers()?;
+        let Some(interp) = segments.iter().find(|s| s.p_type == SegmentType::INTERP) else {
+            return Ok(None);
+        };
+
+        let data = self.segment_data(interp)?;
+        let len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        std::str::from_utf8(&data[..len])
+            .map(Some)
+            .context("Invalid UTF-8 in interpreter path")
+    }
+
+    /// Whether an ET_DYN file is a position-independent executable rather
+    /// than a shared library. Like readelf this trusts DF_1_PIE; for files
+    /// from linkers that predate the flag, a file that requests an
+    /// interpreter and has no soname is taken for an executable. The soname
+    /// check keeps runnable libraries such as libc.so.6 from counting.
+    pub fn is_pie(&self) -> Result<bool> {
+        if self.elf_type() != ElfType::DYN {
+            return Ok(false);
+        }
+        let dynamic = self.dynamic_entries()?;
+        if dynamic
+            .iter()
+            .any(|e| e.d_tag == DynamicTag::FLAGS_1 && e.d_val & DF_1_PIE != 0)
+        {
+            return Ok(true);
+        }
+        Ok(self.interpreter()?.is_some() && !dynamic.iter().any(|e| e.d_tag == DynamicTag::SONAME))
+    }
+
+    /// The file type as readelf describes it, telling PIEs apart from
+    /// shared libraries. A broken dynamic section makes it a shared library.
+    pub fn type_description(&self) -> String {
+        if self.is_pie().unwrap_or(false) {
+            "DYN (Position-Independent Executable file)".to_string()
+        } else {
+            self.elf_type().to_string()
+        }
+    }
+
+    /// File offset backing virtual address `addr`, going through the
+    /// loadable segments.
+    pub fn vaddr_to_offset(&self, addr: u64) -> Result<Option<u64>> {
+        Ok(self
+            .program_headers()?
+            .iter()
+            .filter(|s| s.p_type == SegmentType::LOAD)
+            .find(|s| addr >= s.p_vaddr && addr - s.p_vaddr < s.p_filesz)
+            .and_then(|s| s.p_offset.checked_add(addr - s.p_vaddr)))
+    }
+
+    /// Entries of the dynamic section, up to the terminating `DT_NULL`.
+    /// The `PT_DYNAMIC` segment is used when the file has no section headers.
+    pub fn dynamic_entries(&self) -> Result<Vec<DynamicEntry>> {
+        let sections = self.section_headers()?;
+        let data = match sections.iter().find(|s| s.sh_type == SectionType::DYNAMIC) {
+            Some(section) => self.section_data(section)?,
+            None => match self
+                .program_headers()?
+                .iter()
+                .find(|s| s.p_type == SegmentType::DYNAMIC)
+            {
+                Some(segment) => self.segment_data(segment)?,
+                None => return Ok(Vec::new()),
+            },
+        };
+
+        let size = self.class_size::<Elf32Dyn, Elf64Dyn>();
+        let mut entries = Vec::new();
+        for i in 0..data.len() / size {
+            let entry: DynamicEntry = self
+                .read::<Elf32Dyn, Elf64Dyn, _>(data, (i * size) as u64)
+                .unwrap();
+            if entry.d_tag == DynamicTag::NULL {
+                break;
+            }
+            entries.push(entry);
+        }
+
+        Ok(entries)
+    }
+
+    /// The string table referenced by `DT_STRTAB`, found through the section
+    /// linked from `.dynamic` or, failing that, through the segments.
+    pub fn dynamic_strtab(&self, entries: &[DynamicEntry]) -> Result<&[u8]> {
+        let sections = self.section_headers()?;
+        if let Some(dynamic) = sections.iter().find(|s| s.sh_type == SectionType::DYNAMIC)
+            && let Some(strtab) = sections.get(dynamic.sh_link as usize)
+        {
+            return self.section_data(strtab);
+        }
+
+        let value = |tag| entries.iter().find(|e| e.d_tag == tag).map(|e| e.d_val);
+        let addr = value(DynamicTag::STRTAB).context("Dynamic section has no DT_STRTAB")?;
+        let start =
+            self.vaddr_to_offset(addr)?
+                .context("DT_STRTAB does not point into a loadable segment")? as usize;
+        let size = value(DynamicTag::STRSZ)
+            .map_or(self.data().len().saturating_sub(start), |size| {
+                size as usize
+            });
+        let end = start
+            .checked_add(size)
+            .filter(|&end| end <= self.data().len())
+            .context("Dynamic string table extends past the end of the file")?;
+
+        Ok(&self.data()[start..end])
+    }
+
+    /// String values of all the entries with the given tag, in order.
+    pub fn dynamic_strings(&self, tag: DynamicTag) -> Result<Vec<&str>> {
+        let entries = self.dynamic_entries()?;
+        if !entries.iter().any(|e| e.d_tag == tag) {
+            return Ok(Vec::new());
+        }
+
+        let strtab = self.dynamic_strtab(&entries)?;
+        entries
+            .iter()
+            .filter(|e| e.d_tag == tag)
+            .map(|e| read_str(strtab, e.d_val as usize))
+            .collect()
+    }
+
+    /// Whether the section holds relocations with an offset, symbol and type
+    /// per entry, in any of the encodings `decoded_relocations` handles.
+    pub fn is_relocation_section(section: &SectionHeader) -> bool {
+        matches!(
+            section.sh_type,
+            SectionType::REL
+                | SectionType::RELA
+                | SectionType::CREL
+                | SectionType::ANDROID_REL
+                | SectionType::ANDROID_RELA
+        )
+    }
+
+    /// Relocations of a REL, RELA, CREL or Android packed section, whatever
+    /// its encoding. Other sections have none.
+    pub fn decoded_relocations(&self, section: &SectionHeader) -> Result<Vec<Relocation>> {
+        match section.sh_type {
+            SectionType::REL | SectionType::RELA => Ok(self.relocations(section)?.collect()),
+            SectionType::CREL => self.crel_relocations(section),
+            SectionType::ANDROID_REL | SectionType::ANDROID_RELA => {
+                self.android_relocations(section)
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Relocations of a CREL section, decoded.
+    pub fn crel_relocations(&self, section: &SectionHeader) -> Result<Vec<Relocation>> {
+        relocation::decode_crel(self.section_data(section)?)
+    }
+
+    /// Relocations of an ANDROID_REL or ANDROID_RELA section, unpacked.
+    pub fn android_relocations(&self, section: &SectionHeader) -> Result<Vec<Relocation>> {
+        relocation::decode_aps2(
+            self.section_data(section)?,
+            self.is_64(),
+            section.sh_type == SectionType::ANDROID_RELA,
+        )
+    }
+
+    /// Addresses relocated by a RELR or ANDROID_RELR section.
+    pub fn relr_addresses(&self, section: &SectionHeader) -> Result<Vec<u64>> {
+        Ok(relocation::decode_relr(
+            self.section_data(section)?,
+            self.is_64(),
+        ))
+    }
+
+    /// The section headers, or for files that have none, the headers of
+    /// the dynamic linking tables as `dynamic_sections` rebuilds them. For
+    /// passes that only need symbols, relocations and versions.
+    pub fn section_headers_or_dynamic(&self) -> Result<Vec<SectionHeader>> {
+        let sections = self.section_headers()?;
+        if !sections.is_empty() {
+            return Ok(sections);
+        }
+        self.dynamic_sections()
+    }
+
+    /// Section headers for the tables the dynamic section points to: the
+    /// dynamic symbols and strings, the relocations and the symbol versions,
+    /// linked to each other as a linker would have. Their `sh_name` indexes
+    /// `DYNAMIC_SECTION_NAMES`, which `section_name` uses for files without
+    /// section headers. Empty when there is no dynamic section.
+    fn dynamic_sections(&self) -> Result<Vec<SectionHeader>> {
+        let entries = self.dynamic_entries()?;
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let value = |tag| entries.iter().find(|e| e.d_tag == tag).map(|e| e.d_val);
+        let segments = self.program_headers()?;
+        // The bytes from `addr` to the end of the segment holding it, for
+        // tables that do not record their size.
+        let rest = |addr: u64| {
+            segments
+                .iter()
+                .filter(|s| s.p_type == SegmentType::LOAD)
+                .find(|s| addr >= s.p_vaddr && addr - s.p_vaddr < s.p_filesz)
+                .map(|s| s.p_filesz - (addr - s.p_vaddr))
+        };
+        let mut sections = vec![SectionHeader::default()];
+        let add = |sections: &mut Vec<SectionHeader>,
+                   name: usize,
+                   sh_type,
+                   addr: u64,
+                   size: u64,
+                   link,
+                   info,
+                   entsize|
+         -> Result<()> {
+            if let Some(offset) = self.vaddr_to_offset(addr)? {
+                sections.push(SectionHeader {
+                    sh_name: name as u32,
+                    sh_type,
+                    sh_flags: SHF_ALLOC,
+                    sh_addr: addr,
+                    sh_offset: offset,
+                    sh_size: size.min(rest(addr).unwrap_or(0)),
+                    sh_link: link,
+                    sh_info: info,
+                    sh_addralign: 0,
+                    sh_entsize: entsize,
+                });
+            }
+            Ok(())
+        };
+
+        // .dynsym and .dynstr come first, so that the others can link to
+        // them as 1 and 2.
+        let symbol_size = self.class_size::<Elf32Symbol, Elf64Symbol>() as u64;
+        let syment = value(DynamicTag::SYMENT).unwrap_or(symbol_size);
+        let symbol_count = self.dynamic_symbol_count(&entries)?;
+        let (Some(symtab), Some(strtab)) = (value(DynamicTag::SYMTAB), value(DynamicTag::STRTAB))
+        else {
+            return Ok(Vec::new());
+        };
+        let strsz = value(DynamicTag::STRSZ).unwrap_or(u64::MAX);
+        // The size is cut down to what the segment holds anyway.
+        let symtab_size = symbol_count.checked_mul(syment).unwrap_or_else(|| {
+            eprintln!(
+                "readelf-rs: Warning: {} dynamic symbols of {} bytes overflow the size of the dynamic symbol table",
+                symbol_count, syment
+            );
+            u64::MAX
+        });
+        add(
+            &mut sections,
+            1,
+            SectionType::DYNSYM,
+            symtab,
+            symtab_size,
+            2,
+            1,
+            syment,
         )?;
-        writeln!(
-            $f,
-            "  Section header string table index: {}",
-            $header.e_shstrndx
+        add(
+            &mut sections,
+            2,
+            SectionType::STRTAB,
+            strtab,
+            strsz,
+            0,
+            0,
+            0,
         )?;
-        Ok(())
-    }};
+        if sections.len() < 3 {
+            return Ok(Vec::new());
+        }
+
+        let (rel_size, rela_size) = if self.is_64() {
+            (
+                std::mem::size_of::<Elf64Rel>() as u64,
+                std::mem::size_of::<Elf64Rela>() as u64,
+            )
+        } else {
+            (
+                std::mem::size_of::<Elf32Rel>() as u64,
+                std::mem::size_of::<Elf32Rela>() as u64,
+            )
+        };
+        if let (Some(addr), Some(size)) = (value(DynamicTag::RELA), value(DynamicTag::RELASZ)) {
+            let entsize = value(DynamicTag::RELAENT).unwrap_or(rela_size);
+            add(
+                &mut sections,
+                3,
+                SectionType::RELA,
+                addr,
+                size,
+                1,
+                0,
+                entsize,
+            )?;
+        }
+        if let (Some(addr), Some(size)) = (value(DynamicTag::REL), value(DynamicTag::RELSZ)) {
+            let entsize = value(DynamicTag::RELENT).unwrap_or(rel_size);
+            add(
+                &mut sections,
+                4,
+                SectionType::REL,
+                addr,
+                size,
+                1,
+                0,
+                entsize,
+            )?;
+        }
+        if let (Some(addr), Some(size)) = (value(DynamicTag::JMPREL), value(DynamicTag::PLTRELSZ)) {
+            let (name, sh_type, entsize) =
+                if value(DynamicTag::PLTREL) == Some(DynamicTag::RELA.0 as u64) {
+                    (5, SectionType::RELA, rela_size)
+                } else {
+                    (6, SectionType::REL, rel_size)
+                };
+            add(&mut sections, name, sh_type, addr, size, 1, 0, entsize)?;
+        }
+        if let (Some(addr), Some(size)) = (value(DynamicTag::RELR), value(DynamicTag::RELRSZ)) {
+            let entsize = if self.is_64() { 8 } else { 4 };
+            add(
+                &mut sections,
+                7,
+                SectionType::RELR,
+                addr,
+                size,
+                0,
+                0,
+                entsize,
+            )?;
+        }
+        if let Some(addr) = value(DynamicTag::VERSYM) {
+            add(
+                &mut sections,
+                8,
+                SectionType::GNU_VERSYM,
+                addr,
+                symbol_count * 2,
+                1,
+                0,
+                2,
+            )?;
+        }
+        if let Some(addr) = value(DynamicTag::VERDEF) {
+            let count = value(DynamicTag::VERDEFNUM).unwrap_or(0) as u32;
+            add(
+                &mut sections,
+                9,
+                SectionType::GNU_VERDEF,
+                addr,
+                u64::MAX,
+                2,
+                count,
+                0,
+            )?;
+        }
+        if let Some(addr) = value(DynamicTag::VERNEED) {
+            let count = value(DynamicTag::VERNEEDNUM).unwrap_or(0) as u32;
+            add(
+                &mut sections,
+                10,
+                SectionType::GNU_VERNEED,
+                addr,
+                u64::MAX,
+                2,
+                count,
+                0,
+            )?;
+        }
+        if let Some(segment) = segments.iter().find(|s| s.p_type == SegmentType::DYNAMIC) {
+            let entsize = self.class_size::<Elf32Dyn, Elf64Dyn>() as u64;
+            add(
+                &mut sections,
+                11,
+                SectionType::DYNAMIC,
+                segment.p_vaddr,
+                segment.p_filesz,
+                2,
+                0,
+                entsize,
+            )?;
+        }
+        Ok(sections)
+    }
+
+    /// How many dynamic symbols there are. Nothing records it directly, but
+    /// the hash tables cover every symbol: DT_HASH has one chain entry per
+    /// symbol, and the last chain of DT_GNU_HASH ends at the last symbol.
+    fn dynamic_symbol_count(&self, entries: &[DynamicEntry]) -> Result<u64> {
+        let value = |tag| entries.iter().find(|e| e.d_tag == tag).map(|e| e.d_val);
+        let data = self.data();
+        let word = |offset: u64| -> Option<u32> {
+            let offset = usize::try_from(offset).ok()?;
+            let bytes = data.get(offset..offset.checked_add(4)?)?;
+            Some(u32::from_ne_bytes(bytes.try_into().ok()?))
+        };
+        if let Some(addr) = value(DynamicTag::HASH)
+            && let Some(offset) = self.vaddr_to_offset(addr)?
+            && let Some(nchain) = word(offset.saturating_add(4))
+        {
+            return Ok(nchain as u64);
+        }
+        if let Some(addr) = value(DynamicTag::GNU_HASH)
+            && let Some(offset) = self.vaddr_to_offset(addr)?
+        {
+            let (Some(nbuckets), Some(symoffset), Some(bloom_size)) = (
+                word(offset),
+                word(offset.saturating_add(4)),
+                word(offset.saturating_add(8)),
+            ) else {
+                return Ok(0);
+            };
+            let bloom_word = if self.is_64() { 8 } else { 4 };
+            let buckets = offset.saturating_add(16 + bloom_size as u64 * bloom_word);
+            let chains = buckets.saturating_add(nbuckets as u64 * 4);
+            let last = (0..nbuckets as u64)
+                .filter_map(|i| word(buckets.saturating_add(i * 4)))
+                .max()
+                .unwrap_or(0);
+            if last < symoffset {
+                return Ok(symoffset as u64);
+            }
+            // The low bit of a chain entry marks the end of the chain.
+            let mut index = last as u64;
+            while let Some(hash) = word(chains.saturating_add((index - symoffset as u64) * 4)) {
+                index += 1;
+                if hash & 1 != 0 {
+                    break;
+                }
+            }
+            return Ok(index);
+        }
+        bail!("There is neither DT_HASH nor DT_GNU_HASH to count the dynamic symbols with")
+    }
+
+    /// Contents and entry size of a section holding a table of fixed-size
+    /// entries, making sure every entry can hold `min_entsize` bytes.
+    fn table(&self, section: &SectionHeader, min_entsize: usize) -> Result<(&[u8], usize)> {
+        let entsize = match section.sh_entsize as usize {
+            0 => min_entsize,
+            n if n < min_entsize => bail!("Section entry size {} is too small", n),
+            n => n,
+        };
+
+        Ok((self.section_data(section)?, entsize))
+    }
+}
+
+// Keep the thread-safety promise above from being broken by accident, for
+// instance by adding a cache behind a `RefCell`. The iterators carry the
+// bounds in their signatures.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    // Lifetimes make no difference to auto traits.
+    assert_send_sync::<ElfFile<'static>>();
+    assert_send_sync::<ElfWarning>();
+    assert_send_sync::<SectionHeader>();
+    assert_send_sync::<ProgramHeader>();
+    assert_send_sync::<Symbol>();
+    assert_send_sync::<Relocation>();
+    assert_send_sync::<DynamicEntry>();
+};
+
+/// Copies a `T` out of `data` at `offset`, which need not be aligned.
+pub fn read_struct<T: Copy>(data: &[u8], offset: u64) -> Option<T> {
+    let offset = usize::try_from(offset).ok()?;
+    let end = offset.checked_add(std::mem::size_of::<T>())?;
+    if end > data.len() {
+        return None;
+    }
+
+    Some(unsafe { std::ptr::read_unaligned(data.as_ptr().add(offset) as *const T) })
+}
+
+/// Reads the 32-bit or the 64-bit form of a structure and widens it.
+fn read_class<N32, N64, T>(data: &[u8], offset: u64, is_64: bool) -> Option<T>
+where
+    N32: Copy + Into<T>,
+    N64: Copy + Into<T>,
+{
+    if is_64 {
+        read_struct::<N64>(data, offset).map(Into::into)
+    } else {
+        read_struct::<N32>(data, offset).map(Into::into)
+    }
+}
+
+/// Reads a NUL-terminated string starting at `offset` of a string table.
+pub fn read_str(strtab: &[u8], offset: usize) -> Result<&str> {
+    let bytes = strtab
+        .get(offset..)
+        .context("String table index out of range")?;
+    let len = bytes
+        .iter()
+        .position(|&b| b == 0)
+        .context("Unterminated string in string table")?;
+
+    std::str::from_utf8(&bytes[..len]).context("Invalid UTF-8 in string table")
+}
+
+/// The names of the sections `ElfFile::dynamic_sections` rebuilds, after
+/// the ones linkers give them.
+const DYNAMIC_SECTION_NAMES: &[&str] = &[
+    "",
+    ".dynsym",
+    ".dynstr",
+    ".rela.dyn",
+    ".rel.dyn",
+    ".rela.plt",
+    ".rel.plt",
+    ".relr.dyn",
+    ".gnu.version",
+    ".gnu.version_d",
+    ".gnu.version_r",
+    ".dynamic",
+];
+
+/// The name readelf gives an EI_OSABI value, if it is a known one.
+pub fn os_abi_name(os_abi: u8) -> Option<&'static str> {
+    Some(match os_abi {
+        0 => "UNIX - System V",
+        1 => "UNIX - HP-UX",
+        2 => "UNIX - NetBSD",
+        3 => "UNIX - GNU",
+        6 => "UNIX - Solaris",
+        7 => "UNIX - AIX",
+        8 => "UNIX - IRIX",
+        9 => "UNIX - FreeBSD",
+        10 => "UNIX - TRU64",
+        11 => "Novell - Modesto",
+        12 => "UNIX - OpenBSD",
+        13 => "VMS - OpenVMS",
+        14 => "HP - Non-Stop Kernel",
+        15 => "AROS",
+        16 => "FenixOS",
+        17 => "Nuxi CloudABI",
+        18 => "Stratus Technologies OpenVOS",
+        64 => "ARM EABI",
+        97 => "ARM",
+        255 => "Standalone App",
+        _ => return None,
+    })
 }
 
 impl<'a> fmt::Display for ElfFile<'a> {
@@ -202,10 +1091,8 @@ impl<'a> fmt::Display for ElfFile<'a> {
         writeln!(
             f,
             "  Magic:   {}",
-            self.ident
-                .magic
+            self.data()[..16]
                 .iter()
-                .chain(self.ident.padding.iter())
                 .map(|b| format!("{:02x}", b))
                 .collect::<Vec<_>>()
                 .join(" ")
@@ -233,16 +1120,78 @@ impl<'a> fmt::Display for ElfFile<'a> {
             "  Version:                           {} (current)",
             self.ident.version
         )?;
-        writeln!(f, "  OS/ABI:                            UNIX - System V")?; // Simplified for now
+        writeln!(
+            f,
+            "  OS/ABI:                            {}",
+            os_abi_name(self.ident.os_abi).map_or_else(
+                || format!("<unknown: {:x}>", self.ident.os_abi),
+                String::from
+            )
+        )?;
         writeln!(
             f,
             "  ABI Version:                       {}",
             self.ident.abi_version
         )?;
 
-        match &self.header {
-            ElfHeader::Elf32(header) => display_header!(f, header),
-            ElfHeader::Elf64(header) => display_header!(f, header),
-        }
+        let header = &self.header;
+        writeln!(
+            f,
+            "  Type:                              {}",
+            self.type_description()
+        )?;
+        writeln!(
+            f,
+            "  Machine:                           {}",
+            header.e_machine
+        )?;
+        writeln!(
+            f,
+            "  Version:                           {}",
+            header.e_version
+        )?;
+        writeln!(
+            f,
+            "  Entry point address:               0x{:x}",
+            header.e_entry
+        )?;
+        writeln!(
+            f,
+            "  Start of program headers:          {} (bytes into file)",
+            header.e_phoff
+        )?;
+        writeln!(
+            f,
+            "  Start of section headers:          {} (bytes into file)",
+            header.e_shoff
+        )?;
+        writeln!(
+            f,
+            "  Flags:                             0x{:x}{}",
+            header.e_flags,
+            header.e_machine.flags_description(header.e_flags)
+        )?;
+        writeln!(
+            f,
+            "  Size of this header:               {} (bytes)",
+            header.e_ehsize
+        )?;
+        writeln!(
+            f,
+            "  Size of program headers:           {} (bytes)",
+            header.e_phentsize
+        )?;
+        writeln!(f, "  Number of program headers:         {}", header.e_phnum)?;
+        writeln!(
+            f,
+            "  Size of section headers:           {} (bytes)",
+            header.e_shentsize
+        )?;
+        writeln!(f, "  Number of section headers:         {}", header.e_shnum)?;
+        writeln!(
+            f,
+            "  Section header string table index: {}",
+            header.e_shstrndx
+        )
     }
 }
@@ -10,7 +10,7 @@ pub enum EMachine {
     I386 = 3,               // Intel 80386
     M68K = 4,               // Motorola m68k family
     M88K = 5,               // Motorola m88k family
-    IAMCU = 6,              // Intel MCU
+    Iamcu = 6,              // Intel MCU
     I860 = 7,               // Intel 80860
     Mips = 8,               // MIPS R3000 big-endian
     S370 = 9,               // IBM System/370
@@ -188,6 +188,7 @@ pub enum EMachine {
     Alpha = 0x9026,         // Old spelling/synonym
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for EMachine {
     fn default() -> Self {
         EMachine::None
@@ -203,7 +204,7 @@ impl fmt::Display for EMachine {
             EMachine::I386 => "Intel 80386",
             EMachine::M68K => "MC68000",
             EMachine::M88K => "MC88000",
-            EMachine::IAMCU => "Intel MCU",
+            EMachine::Iamcu => "Intel MCU",
             EMachine::I860 => "Intel 80860",
             EMachine::Mips => "MIPS R3000",
             EMachine::S370 => "IBM System/370",
@@ -383,3 +384,100 @@ impl fmt::Display for EMachine {
         write!(f, "{}", description)
     }
 }
+
+impl EMachine {
+    /// What readelf lists after the raw `e_flags` value, for the machines
+    /// whose flags are decoded: each flag prefixed with ", ". Like readelf,
+    /// nothing is listed when no flag is set.
+    pub fn flags_description(self, e_flags: u32) -> String {
+        let mut flags = Vec::new();
+        if e_flags == 0 {
+            return String::new();
+        }
+        match self {
+            EMachine::Ppc => {
+                for (bit, name) in [
+                    (0x80000000, "emb"),
+                    (0x00010000, "relocatable"),
+                    (0x00008000, "relocatable-lib"),
+                ] {
+                    if e_flags & bit != 0 {
+                        flags.push(name.to_string());
+                    }
+                }
+            }
+            EMachine::Ppc64 if e_flags & 3 != 0 => flags.push(format!("abiv{}", e_flags & 3)),
+            EMachine::S390 if e_flags & 1 != 0 => flags.push("highgprs".to_string()),
+            EMachine::Avr => {
+                let mach = e_flags & 0x7f;
+                if matches!(mach, 1..=6 | 25 | 31 | 35 | 51 | 100..=107) {
+                    flags.push(format!("avr:{}", mach));
+                } else {
+                    flags.push("avr:<unknown>".to_string());
+                }
+                if e_flags & 0x80 != 0 {
+                    flags.push("link-relax".to_string());
+                }
+            }
+            EMachine::Sparc32Plus | EMachine::SparcV9 => {
+                for (bit, name) in [
+                    (0x000100, "v8+"),
+                    (0x000200, "ultrasparcI"),
+                    (0x000800, "ultrasparcIII"),
+                    (0x000400, "halr1"),
+                    (0x800000, "ledata"),
+                ] {
+                    if e_flags & bit != 0 {
+                        flags.push(name.to_string());
+                    }
+                }
+                // Only SPARC V9 chooses a memory model.
+                if self == EMachine::SparcV9 {
+                    flags.push(
+                        match e_flags & 3 {
+                            0 => "tso",
+                            1 => "pso",
+                            2 => "rmo",
+                            _ => "<unknown memory model>",
+                        }
+                        .to_string(),
+                    );
+                }
+            }
+            _ => {}
+        }
+        flags.iter().map(|f| format!(", {}", f)).collect()
+    }
+    /// The memory a machine with separate address spaces maps `address`
+    /// into, with the offset inside it. The AVR toolchain gives each of
+    /// its memories a range of the ELF address space, while on the MSP430
+    /// the peripherals and interrupt vectors have fixed addresses.
+    pub fn memory_region(self, address: u64) -> Option<(&'static str, u64)> {
+        const AVR_REGIONS: &[(u64, &str)] = &[
+            (0x000000, "flash"),
+            (0x800000, "data memory (SRAM)"),
+            (0x810000, "EEPROM"),
+            (0x820000, "fuses"),
+            (0x830000, "lock bits"),
+            (0x840000, "signature"),
+            (0x850000, "user signatures"),
+        ];
+        const MSP430_REGIONS: &[(u64, &str)] = &[
+            (0x0000, "peripherals"),
+            (0x0200, "RAM and flash"),
+            (0xffe0, "interrupt vectors"),
+            (0x10000, "upper memory (MSP430X)"),
+        ];
+
+        let regions = match self {
+            EMachine::Avr => AVR_REGIONS,
+            EMachine::Msp430 => MSP430_REGIONS,
+            _ => return None,
+        };
+        regions
+            .iter()
+            .rev()
+            .find(|&&(start, _)| address >= start)
+            .map(|&(start, name)| (name, address - start))
+    }
+}
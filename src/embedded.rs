@@ -0,0 +1,224 @@
+//! ELF files inside other files: firmware images, archives and installers
+//! that bundle several of them. Every `\x7fELF` is a candidate, and only
+//! those with a plausible header are reported.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use crate::elf::ElfFile;
+use crate::overlay;
+
+/// The machines firmware images most often bundle code for; others are
+/// shown by number.
+const MACHINES: &[(u16, &str)] = &[
+    (2, "SPARC"),
+    (3, "Intel 80386"),
+    (8, "MIPS"),
+    (20, "PowerPC"),
+    (21, "PowerPC64"),
+    (22, "S/390"),
+    (40, "ARM"),
+    (42, "SuperH"),
+    (62, "X86-64"),
+    (94, "Xtensa"),
+    (183, "AArch64"),
+    (243, "RISC-V"),
+    (247, "BPF"),
+    (258, "LoongArch"),
+];
+
+/// The fields of a candidate header needed to check it, read in the byte
+/// order it says it has.
+struct Candidate {
+    class: u8,
+    little_endian: bool,
+    elf_type: u16,
+    machine: u16,
+    /// The end of the header tables, relative to the candidate.
+    tables_end: u64,
+}
+
+/// Reads and checks the header at the start of `data`, returning why it
+/// is not an ELF header when it is not.
+fn check_header(data: &[u8]) -> Result<Candidate, &'static str> {
+    let ident = data.get(..16).ok_or("truncated identification")?;
+    let class = ident[4];
+    let little_endian = match ident[5] {
+        1 => true,
+        2 => false,
+        _ => return Err("invalid byte order"),
+    };
+    if ident[6] != 1 {
+        return Err("invalid version");
+    }
+    let is_64 = match class {
+        1 => false,
+        2 => true,
+        _ => return Err("invalid class"),
+    };
+    let u16_at = |offset: usize| -> Option<u64> {
+        let bytes: [u8; 2] = data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        } as u64)
+    };
+    let word_at = |offset: usize| -> Option<u64> {
+        if is_64 {
+            let bytes: [u8; 8] = data.get(offset..offset + 8)?.try_into().ok()?;
+            Some(if little_endian {
+                u64::from_le_bytes(bytes)
+            } else {
+                u64::from_be_bytes(bytes)
+            })
+        } else {
+            let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+            Some(if little_endian {
+                u32::from_le_bytes(bytes)
+            } else {
+                u32::from_be_bytes(bytes)
+            } as u64)
+        }
+    };
+    let (phoff, shoff, sizes) = if is_64 { (32, 40, 52) } else { (28, 32, 40) };
+    let truncated = "truncated header";
+    let elf_type = u16_at(16).ok_or(truncated)? as u16;
+    let machine = u16_at(18).ok_or(truncated)? as u16;
+    let phoff = word_at(phoff).ok_or(truncated)?;
+    let shoff = word_at(shoff).ok_or(truncated)?;
+    let ehsize = u16_at(sizes).ok_or(truncated)?;
+    let phentsize = u16_at(sizes + 2).ok_or(truncated)?;
+    let phnum = u16_at(sizes + 4).ok_or(truncated)?;
+    let shentsize = u16_at(sizes + 6).ok_or(truncated)?;
+    let shnum = u16_at(sizes + 8).ok_or(truncated)?;
+
+    if !(1..=4).contains(&elf_type) {
+        return Err("unknown file type");
+    }
+    if ehsize != if is_64 { 64 } else { 52 } {
+        return Err("wrong header size");
+    }
+    if phnum > 0 && phentsize != if is_64 { 56 } else { 32 } {
+        return Err("wrong program header size");
+    }
+    if shnum > 0 && shentsize != if is_64 { 64 } else { 40 } {
+        return Err("wrong section header size");
+    }
+    let mut tables_end = ehsize;
+    if phnum > 0 {
+        tables_end = tables_end.max(phoff.saturating_add(phnum * phentsize));
+    }
+    if shnum > 0 {
+        tables_end = tables_end.max(shoff.saturating_add(shnum * shentsize));
+    }
+    if tables_end > data.len() as u64 {
+        return Err("header tables past the end of the data");
+    }
+    Ok(Candidate {
+        class,
+        little_endian,
+        elf_type,
+        machine,
+        tables_end,
+    })
+}
+
+/// Lists the ELF files found anywhere in the file at `path`, which need
+/// not be an ELF file itself.
+pub fn print_embedded(path: &str) -> Result<()> {
+    let data =
+        std::fs::read(Path::new(path)).with_context(|| format!("Failed to read {}", path))?;
+    println!("Embedded ELF files in {} ({:#x} bytes):", path, data.len());
+
+    let mut found: Vec<(usize, u64, Candidate)> = Vec::new();
+    let mut rejected = Vec::new();
+    let mut at = 0;
+    while let Some(position) = data[at..].windows(4).position(|w| w == b"\x7fELF") {
+        let offset = at + position;
+        at = offset + 4;
+        match check_header(&data[offset..]) {
+            Ok(candidate) => {
+                // The header tables are all there is to go on for files
+                // in the other byte order.
+                let size = if candidate.little_endian == cfg!(target_endian = "little") {
+                    ElfFile::parse(&data[offset..])
+                        .and_then(|elf| overlay::described_end(&elf))
+                        .unwrap_or(candidate.tables_end)
+                } else {
+                    candidate.tables_end
+                };
+                found.push((offset, size, candidate));
+            }
+            Err(reason) => rejected.push((offset, reason)),
+        }
+    }
+
+    if found.is_empty() {
+        println!("  None found.");
+    } else {
+        println!(
+            "  {:<12} {:>12}  {:<5} {:<6} {:<4} {:<14} Note",
+            "Offset", "Size", "Class", "Data", "Type", "Machine"
+        );
+        for (index, (offset, size, candidate)) in found.iter().enumerate() {
+            let machine = MACHINES
+                .iter()
+                .find(|(number, _)| *number == candidate.machine)
+                .map_or_else(
+                    || format!("machine {}", candidate.machine),
+                    |(_, name)| name.to_string(),
+                );
+            let elf_type = ["REL", "EXEC", "DYN", "CORE"][candidate.elf_type as usize - 1];
+            let mut notes = Vec::new();
+            if *offset == 0 {
+                notes.push("the file itself".to_string());
+            }
+            if let Some((outer, _, _)) = found[..index].iter().rev().find(|(start, size, _)| {
+                (*start as u64..*start as u64 + size).contains(&(*offset as u64))
+            }) {
+                notes.push(format!("inside the one at {:#x}", outer));
+            }
+            if *offset as u64 + size > data.len() as u64 {
+                notes.push("truncated".to_string());
+            }
+            let line = format!(
+                "  {:<#12x} {:>#12x}  {:<5} {:<6} {:<4} {:<14} {}",
+                offset,
+                size,
+                if candidate.class == 2 {
+                    "ELF64"
+                } else {
+                    "ELF32"
+                },
+                if candidate.little_endian {
+                    "little"
+                } else {
+                    "big"
+                },
+                elf_type,
+                machine,
+                notes.join(", ")
+            );
+            println!("{}", line.trim_end());
+        }
+    }
+    if !rejected.is_empty() {
+        println!();
+        println!(
+            "  {} more \\x7fELF signatures do not start a valid header:",
+            rejected.len()
+        );
+        for (offset, reason) in rejected.iter().take(10) {
+            println!("    {:#x}: {}", offset, reason);
+        }
+        if rejected.len() > 10 {
+            println!("    and {} more", rejected.len() - 10);
+        }
+    }
+    if found.iter().any(|(offset, _, _)| *offset != 0) {
+        println!();
+        println!("Read one of them with --at-offset OFFSET.");
+    }
+    Ok(())
+}
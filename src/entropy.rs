@@ -0,0 +1,179 @@
+use anyhow::Result;
+
+use crate::elf::ElfFile;
+use crate::filter::SectionFilter;
+use crate::section::SHF_EXECINSTR;
+use crate::segment::SegmentType;
+
+/// Entropy, in bits per byte, above which data is most likely compressed or
+/// encrypted. Machine code usually sits between 5.5 and 6.5.
+const HIGH_ENTROPY: f64 = 7.2;
+
+/// Shannon entropy of `data` in bits per byte, from 0 to 8.
+pub fn entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+
+    let mut counts = [0usize; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let count = count as f64;
+            count / len * (len / count).log2()
+        })
+        .sum()
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// Prints the entropy of every section and segment, then runs a few
+/// heuristics that point to a packed or obfuscated binary. The filter
+/// trims the section table, but the heuristics always look at everything.
+pub fn print_entropy(elf_file: &ElfFile, filter: &SectionFilter) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let segments = elf_file.program_headers()?;
+    let mut indicators = Vec::new();
+    let mut upx = false;
+
+    let marker = |e: f64| if e >= HIGH_ENTROPY { "  <- high" } else { "" };
+
+    if !sections.is_empty() {
+        println!("Section entropy (bits per byte):");
+        println!("  [Nr] {:<24} {:>10} {:>8}", "Name", "Size", "Entropy");
+        for (index, section) in sections.iter().enumerate() {
+            let data = elf_file.section_data(section)?;
+            if data.is_empty() {
+                continue;
+            }
+
+            let name = elf_file.section_name(&sections, section).unwrap_or("");
+            let e = entropy(data);
+            if filter.matches(index, name) {
+                println!(
+                    "  [{:>2}] {:<24} {:>10} {:>8.3}{}",
+                    index,
+                    name,
+                    data.len(),
+                    e,
+                    marker(e)
+                );
+            }
+
+            if name.starts_with("UPX") {
+                upx = true;
+                indicators.push(format!("section named {}", name));
+            }
+            if section.is_alloc() && data.len() >= 1024 && e >= HIGH_ENTROPY {
+                indicators.push(format!(
+                    "allocated section {} has high entropy ({:.2})",
+                    name, e
+                ));
+            }
+        }
+        println!();
+    }
+
+    println!("Segment entropy (bits per byte):");
+    println!("  [Nr] {:<24} {:>10} {:>8}", "Type", "File size", "Entropy");
+    for (index, segment) in segments.iter().enumerate() {
+        let data = elf_file.segment_data(segment)?;
+        if data.is_empty() {
+            continue;
+        }
+
+        let e = entropy(data);
+        println!(
+            "  [{:>2}] {:<24} {:>10} {:>8.3}{}",
+            index,
+            format!("{} [{}]", segment.p_type, segment.flags_string()),
+            data.len(),
+            e,
+            marker(e)
+        );
+
+        if segment.p_type == SegmentType::LOAD {
+            if sections.is_empty() && data.len() >= 1024 && e >= HIGH_ENTROPY {
+                indicators.push(format!(
+                    "loadable segment {} has high entropy ({:.2})",
+                    index, e
+                ));
+            }
+            // Unpacking stubs reserve room to decompress into.
+            if segment.p_memsz > segment.p_filesz.saturating_mul(4) && segment.is_executable() {
+                indicators.push(format!(
+                    "executable segment {} is {}x larger in memory than in the file",
+                    index,
+                    segment.p_memsz / segment.p_filesz.max(1)
+                ));
+            }
+            if segment.is_writable() && segment.is_executable() {
+                indicators.push(format!(
+                    "loadable segment {} is writable and executable",
+                    index
+                ));
+            }
+        }
+    }
+    println!();
+
+    let data = elf_file.data();
+    if contains(data, b"UPX!") || contains(data, b"This file is packed with the UPX") {
+        upx = true;
+        indicators.push("UPX signature string found".to_string());
+    }
+
+    if sections.is_empty() && !segments.is_empty() {
+        indicators.push("the file has no section headers".to_string());
+    }
+
+    let entry = elf_file.entry();
+    if entry != 0 && !sections.is_empty() {
+        let in_code = sections.iter().any(|s| {
+            s.sh_flags & SHF_EXECINSTR != 0 && entry >= s.sh_addr && entry < s.sh_addr + s.sh_size
+        });
+        if !in_code {
+            indicators.push(format!(
+                "entry point {:#x} is outside every executable section",
+                entry
+            ));
+        }
+    }
+
+    if !sections.is_empty() && elf_file.symbol_table(&sections).is_none() {
+        indicators.push("there is no symbol table of any kind".to_string());
+    }
+
+    println!("Packer heuristics:");
+    if indicators.is_empty() {
+        println!("  (none triggered)");
+    }
+    for indicator in &indicators {
+        println!("  - {}", indicator);
+    }
+
+    // A stripped binary alone is common, so it takes more than one weak
+    // indicator to call a file packed.
+    let verdict = if upx {
+        "likely packed with UPX"
+    } else if indicators.len() >= 2 {
+        "likely packed or obfuscated"
+    } else if indicators.len() == 1 {
+        "probably not packed"
+    } else {
+        "no signs of packing"
+    };
+    println!("Verdict: {}", verdict);
+
+    Ok(())
+}
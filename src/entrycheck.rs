@@ -0,0 +1,339 @@
+//! Whether the entry point of a file is somewhere code can start: inside
+//! an executable segment, at a function, and at instructions that look like
+//! the `_start` of a C runtime rather than zeros, a trap or a jump away, as
+//! hand-crafted, patched and packed binaries have.
+
+use anyhow::Result;
+
+use crate::elf::{ElfFile, ElfType};
+use crate::emachine::EMachine;
+use crate::section::{SHF_EXECINSTR, SHN_UNDEF, SectionType};
+use crate::segment::SegmentType;
+use crate::strtab::SymbolTable;
+use crate::symbol::SymbolType;
+
+/// An instruction of the few kinds the start code of a program is made of.
+struct Instruction {
+    length: usize,
+    text: String,
+    kind: Kind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Clears the frame pointer, as the psABIs ask `_start` to do to mark
+    /// the outermost frame.
+    ClearFrame,
+    /// Sets up a frame of its own, which only a function called by someone
+    /// does.
+    Prologue,
+    Jump,
+    Trap,
+    Zero,
+    Other,
+}
+
+fn rel32(bytes: &[u8]) -> Option<i32> {
+    Some(i32::from_le_bytes(bytes.get(..4)?.try_into().ok()?))
+}
+
+/// Decodes the instructions x86-64 start code is usually made of, and
+/// nothing else.
+fn decode_x86_64(code: &[u8], address: u64) -> Option<Instruction> {
+    let instruction = |length: usize, text: String, kind: Kind| Instruction { length, text, kind };
+    let target = |length: usize, offset: i32| {
+        address
+            .wrapping_add(length as u64)
+            .wrapping_add(offset as i64 as u64)
+    };
+    Some(match code {
+        [0xf3, 0x0f, 0x1e, 0xfa, ..] => instruction(4, "endbr64".into(), Kind::Other),
+        [0x31, 0xed, ..] => instruction(2, "xor %ebp,%ebp".into(), Kind::ClearFrame),
+        [0x48, 0x31, 0xed, ..] => instruction(3, "xor %rbp,%rbp".into(), Kind::ClearFrame),
+        [0x49, 0x89, 0xd1, ..] => instruction(3, "mov %rdx,%r9".into(), Kind::Other),
+        [0x48, 0x89, 0xe2, ..] => instruction(3, "mov %rsp,%rdx".into(), Kind::Other),
+        [0x48, 0x89, 0xe7, ..] => instruction(3, "mov %rsp,%rdi".into(), Kind::Other),
+        [0x48, 0x83, 0xe4, 0xf0, ..] => instruction(4, "and $-16,%rsp".into(), Kind::Other),
+        [0x45, 0x31, 0xc0, ..] => instruction(3, "xor %r8d,%r8d".into(), Kind::Other),
+        [0x31, 0xc9, ..] => instruction(2, "xor %ecx,%ecx".into(), Kind::Other),
+        [0x5e, ..] => instruction(1, "pop %rsi".into(), Kind::Other),
+        [0x50, ..] => instruction(1, "push %rax".into(), Kind::Other),
+        [0x54, ..] => instruction(1, "push %rsp".into(), Kind::Other),
+        [0x55, ..] => instruction(1, "push %rbp".into(), Kind::Prologue),
+        [0x48, 0x89, 0xe5, ..] => instruction(3, "mov %rsp,%rbp".into(), Kind::Prologue),
+        [0x90, ..] => instruction(1, "nop".into(), Kind::Other),
+        [0xf4, ..] => instruction(1, "hlt".into(), Kind::Trap),
+        [0xcc, ..] => instruction(1, "int3".into(), Kind::Trap),
+        [0x0f, 0x0b, ..] => instruction(2, "ud2".into(), Kind::Trap),
+        [0x00, 0x00, ..] => instruction(2, "add %al,(%rax)".into(), Kind::Zero),
+        [0x48, 0x8b, 0x3d, rest @ ..] => {
+            let offset = rel32(rest)?;
+            instruction(
+                7,
+                format!("mov {:#x}(%rip),%rdi", target(7, offset)),
+                Kind::Other,
+            )
+        }
+        [0x48, 0xc7, 0xc7, rest @ ..] => {
+            instruction(7, format!("mov ${:#x},%rdi", rel32(rest)?), Kind::Other)
+        }
+        [0xff, 0x15, rest @ ..] => {
+            let offset = rel32(rest)?;
+            instruction(
+                6,
+                format!("call *{:#x}(%rip)", target(6, offset)),
+                Kind::Other,
+            )
+        }
+        [0xe8, rest @ ..] => {
+            let offset = rel32(rest)?;
+            instruction(5, format!("call {:#x}", target(5, offset)), Kind::Other)
+        }
+        [0xe9, rest @ ..] => {
+            let offset = rel32(rest)?;
+            instruction(5, format!("jmp {:#x}", target(5, offset)), Kind::Jump)
+        }
+        [0xeb, offset, ..] => instruction(
+            2,
+            format!("jmp {:#x}", target(2, *offset as i8 as i32)),
+            Kind::Jump,
+        ),
+        _ => return None,
+    })
+}
+
+/// Decodes the instructions AArch64 start code is usually made of.
+fn decode_aarch64(code: &[u8], address: u64) -> Option<Instruction> {
+    let word = u32::from_le_bytes(code.get(..4)?.try_into().ok()?);
+    let branch = |word: u32| {
+        let offset = ((word & 0x03ff_ffff) << 6) as i32 >> 4;
+        address.wrapping_add(offset as i64 as u64)
+    };
+    let (text, kind) = match word {
+        0xd503_245f => ("bti c".to_string(), Kind::Other),
+        0xd503_201f => ("nop".to_string(), Kind::Other),
+        0xd280_001d => ("mov x29, #0x0".to_string(), Kind::ClearFrame),
+        0xd280_001e => ("mov x30, #0x0".to_string(), Kind::Other),
+        0xa9bf_7bfd => ("stp x29, x30, [sp, #-16]!".to_string(), Kind::Prologue),
+        0x0000_0000 => ("udf #0".to_string(), Kind::Zero),
+        w if w & 0xffe0_001f == 0xd420_0000 => {
+            (format!("brk #{:#x}", (w >> 5) & 0xffff), Kind::Trap)
+        }
+        w if w & 0xfc00_0000 == 0x9400_0000 => (format!("bl {:#x}", branch(w)), Kind::Other),
+        w if w & 0xfc00_0000 == 0x1400_0000 => (format!("b {:#x}", branch(w)), Kind::Jump),
+        w if w & 0xffe0_ffe0 == 0xaa00_03e0 => (
+            format!("mov x{}, x{}", w & 0x1f, (w >> 16) & 0x1f),
+            Kind::Other,
+        ),
+        _ => return None,
+    };
+    Some(Instruction {
+        length: 4,
+        text,
+        kind,
+    })
+}
+
+/// Checks the entry point of the file, decoding up to `count` instructions
+/// there on the machines whose start code is recognized.
+pub fn print_entry_check(elf_file: &ElfFile, count: usize) -> Result<()> {
+    let entry = elf_file.entry();
+    println!("Entry point check:");
+    println!("  Entry point:  {:#x}", entry);
+    let mut problems = Vec::new();
+    let elf_type = elf_file.elf_type();
+    if entry == 0 {
+        if elf_type == ElfType::DYN && !elf_file.is_pie()? || elf_type == ElfType::REL {
+            println!(
+                "  There is none, as is usual for {} files.",
+                elf_file.type_description()
+            );
+        } else {
+            println!();
+            println!("Problems:");
+            println!(
+                "  An executable with an entry point of 0 jumps to the start of its load base."
+            );
+        }
+        return Ok(());
+    }
+
+    let segments = elf_file.program_headers()?;
+    let segment = segments.iter().enumerate().find(|(_, s)| {
+        s.p_type == SegmentType::LOAD && entry >= s.p_vaddr && entry - s.p_vaddr < s.p_memsz
+    });
+    match segment {
+        Some((index, segment)) => {
+            println!(
+                "  Segment:      {} (LOAD, {}) {:#x}-{:#x}",
+                index,
+                segment.flags_string().trim_end(),
+                segment.p_vaddr,
+                segment.p_vaddr.saturating_add(segment.p_memsz)
+            );
+            if !segment.is_executable() {
+                problems.push("The entry point is in a segment that is not executable".to_string());
+            }
+            if entry - segment.p_vaddr >= segment.p_filesz {
+                problems
+                    .push("The entry point is in the zero-filled part of its segment".to_string());
+            }
+        }
+        None => {
+            println!("  Segment:      none");
+            problems.push("The entry point is not in any loadable segment".to_string());
+        }
+    }
+
+    let sections = elf_file.section_headers()?;
+    if let Some(section) = sections.iter().find(|s| s.contains_addr(entry)) {
+        let name = elf_file.section_name(&sections, section)?;
+        println!("  Section:      {}+{:#x}", name, entry - section.sh_addr);
+        if section.sh_flags & SHF_EXECINSTR == 0 {
+            problems.push(format!(
+                "The entry point is in {}, which does not hold code",
+                name
+            ));
+        }
+    } else if !sections.is_empty() {
+        println!("  Section:      none");
+    }
+
+    match elf_file.symbol_table(&sections) {
+        Some(table) => {
+            let symbols = SymbolTable::new(elf_file, &sections, table)?;
+            let mut found: Option<(String, u64, SymbolType)> = None;
+            for (index, symbol) in symbols.iter().enumerate() {
+                let kind = symbol.symbol_type();
+                if symbol.st_shndx == SHN_UNDEF
+                    || !matches!(kind, SymbolType::FUNC | SymbolType::NOTYPE)
+                    || entry < symbol.st_value
+                    || entry - symbol.st_value >= symbol.st_size.max(1)
+                {
+                    continue;
+                }
+                let name = symbols.name(index)?;
+                let better = match &found {
+                    None => true,
+                    Some((_, value, found_kind)) => {
+                        symbol.st_value > *value
+                            || (symbol.st_value == *value
+                                && (name == "_start"
+                                    || (kind == SymbolType::FUNC
+                                        && *found_kind != SymbolType::FUNC)))
+                    }
+                };
+                if !name.is_empty() && better {
+                    found = Some((name.to_string(), symbol.st_value, kind));
+                }
+            }
+            match found {
+                Some((name, value, kind)) => {
+                    if value == entry {
+                        println!("  Symbol:       {} ({})", name, kind);
+                    } else {
+                        println!("  Symbol:       {}+{:#x} ({})", name, entry - value, kind);
+                        problems.push(format!(
+                            "The entry point is {:#x} bytes into {}, not at the start of a function",
+                            entry - value,
+                            name
+                        ));
+                    }
+                }
+                // Only the dynamic symbols are left in stripped files, and
+                // _start is not one of them.
+                None if table.sh_type == SectionType::DYNSYM => {
+                    println!("  Symbol:       unknown (stripped: there is only .dynsym)");
+                }
+                None => {
+                    println!("  Symbol:       none");
+                    problems.push("No function symbol covers the entry point".to_string());
+                }
+            }
+        }
+        None => println!("  Symbol:       unknown (no symbol table)"),
+    }
+
+    let decode: fn(&[u8], u64) -> Option<Instruction> = match elf_file.machine() {
+        EMachine::X8664 => decode_x86_64,
+        EMachine::Aarch64 => decode_aarch64,
+        _ => {
+            if count > 0 {
+                println!("  Instructions: not decoded for {}", elf_file.machine());
+            }
+            return print_problems(&problems);
+        }
+    };
+    let code = match elf_file.vaddr_to_offset(entry)? {
+        Some(offset) => elf_file.data().get(offset as usize..).unwrap_or(&[]),
+        None => &[],
+    };
+    if count > 0 && !code.is_empty() {
+        println!();
+        println!("  First instructions:");
+        let mut at = 0;
+        let mut kinds = Vec::new();
+        for _ in 0..count {
+            let rest = &code[at..];
+            let address = entry.wrapping_add(at as u64);
+            let Some(instruction) = decode(rest, address) else {
+                let shown: Vec<String> =
+                    rest.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+                println!(
+                    "    {:#x}: {:<24} (not one of the usual start instructions)",
+                    address,
+                    shown.join(" ")
+                );
+                break;
+            };
+            let bytes: Vec<String> = rest[..instruction.length]
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            println!(
+                "    {:#x}: {:<24} {}",
+                address,
+                bytes.join(" "),
+                instruction.text
+            );
+            kinds.push(instruction.kind);
+            at += instruction.length;
+            if matches!(instruction.kind, Kind::Jump | Kind::Trap | Kind::Zero) || at >= code.len()
+            {
+                break;
+            }
+        }
+        match kinds.first() {
+            Some(Kind::Zero) => {
+                problems.push("The entry point holds zero bytes, not code".to_string())
+            }
+            Some(Kind::Trap) => problems.push("The first instruction traps".to_string()),
+            Some(Kind::Jump) => problems.push(
+                "The first instruction jumps away, as in patched or packed binaries".to_string(),
+            ),
+            _ => {}
+        }
+        if kinds.contains(&Kind::Prologue) && !kinds.contains(&Kind::ClearFrame) {
+            problems.push(
+                "The entry code sets up a stack frame like a called function instead of clearing the frame pointer like _start"
+                    .to_string(),
+            );
+        } else if kinds.contains(&Kind::ClearFrame) {
+            println!("  The code clears the frame pointer first, as _start does.");
+        }
+    }
+    print_problems(&problems)
+}
+
+fn print_problems(problems: &[String]) -> Result<()> {
+    println!();
+    if problems.is_empty() {
+        println!("The entry point looks sound.");
+        return Ok(());
+    }
+    println!("Problems:");
+    for problem in problems {
+        println!("  {}", problem);
+    }
+    Ok(())
+}
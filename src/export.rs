@@ -0,0 +1,159 @@
+//! The loadable contents of a file laid out at their physical addresses, as
+//! bootloaders and flash programmers take them: a raw binary image, like
+//! `objcopy -O binary`, or Intel HEX.
+
+use anyhow::{Context, Result, bail};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::elf::{ElfFile, ElfType};
+use crate::segment::SegmentType;
+
+/// Raw images larger than this are almost always segments far apart, such
+/// as flash and RAM, that Intel HEX can hold without the gap between them.
+const MAX_RAW_SIZE: u64 = 256 * 1024 * 1024;
+
+/// The bytes of a PT_LOAD segment that are in the file, and the physical
+/// address to put them at. The zero-filled rest (.bss) is left out, as
+/// objcopy does.
+fn loads<'a>(elf_file: &'a ElfFile) -> Result<Vec<(u64, &'a [u8])>> {
+    let mut loads = Vec::new();
+    for segment in elf_file.program_headers()? {
+        if segment.p_type != SegmentType::LOAD || segment.p_filesz == 0 {
+            continue;
+        }
+        loads.push((segment.p_paddr, elf_file.segment_data(&segment)?));
+    }
+    if loads.is_empty() && elf_file.elf_type() == ElfType::REL {
+        bail!("Relocatable objects have no loadable segments to export; link the object first");
+    }
+    if loads.is_empty() {
+        bail!("There are no loadable segments with contents to export");
+    }
+    loads.sort_by_key(|&(address, _)| address);
+    for pair in loads.windows(2) {
+        let (address, data) = pair[0];
+        if address + data.len() as u64 > pair[1].0 {
+            bail!(
+                "The segments at physical addresses {:#x} and {:#x} overlap",
+                address,
+                pair[1].0
+            );
+        }
+    }
+    Ok(loads)
+}
+
+/// Parses a fill byte, in decimal or `0x` hex.
+pub fn parse_fill(text: &str) -> Result<u8> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => text.parse(),
+    }
+    .with_context(|| format!("Invalid fill byte {:?}", text))
+}
+
+/// Writes the segments as one image starting at the lowest physical
+/// address, with the gaps between them filled with `fill`.
+pub fn export_raw(elf_file: &ElfFile, path: &Path, fill: u8) -> Result<()> {
+    let loads = loads(elf_file)?;
+    let start = loads[0].0;
+    let end = loads
+        .iter()
+        .map(|&(address, data)| address + data.len() as u64)
+        .max()
+        .unwrap_or(start);
+    if end - start > MAX_RAW_SIZE {
+        bail!(
+            "The image would span {:#x}-{:#x}, {} bytes of it mostly gap; export Intel HEX instead",
+            start,
+            end,
+            end - start
+        );
+    }
+
+    let mut image = vec![fill; (end - start) as usize];
+    for &(address, data) in &loads {
+        let offset = (address - start) as usize;
+        image[offset..offset + data.len()].copy_from_slice(data);
+    }
+    std::fs::write(path, &image).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let contents: usize = loads.iter().map(|(_, data)| data.len()).sum();
+    println!(
+        "Wrote {:#x}-{:#x} to {} ({} bytes: {} from {} segments, {} of fill {:#04x})",
+        start,
+        end,
+        path.display(),
+        image.len(),
+        contents,
+        loads.len(),
+        image.len() - contents,
+        fill
+    );
+    Ok(())
+}
+
+/// One Intel HEX record, with its checksum, ended with CRLF as the format
+/// and objcopy have it.
+fn record(out: &mut String, kind: u8, address: u16, data: &[u8]) {
+    let mut sum = data.len() as u8;
+    sum = sum
+        .wrapping_add((address >> 8) as u8)
+        .wrapping_add(address as u8)
+        .wrapping_add(kind);
+    let _ = write!(out, ":{:02X}{:04X}{:02X}", data.len(), address, kind);
+    for &byte in data {
+        let _ = write!(out, "{:02X}", byte);
+        sum = sum.wrapping_add(byte);
+    }
+    let _ = write!(out, "{:02X}\r\n", sum.wrapping_neg());
+}
+
+/// Writes the segments as Intel HEX: 16-byte data records, extended linear
+/// address records where the upper 16 bits of the address change, and the
+/// entry point as the start linear address.
+pub fn export_ihex(elf_file: &ElfFile, path: &Path) -> Result<()> {
+    let loads = loads(elf_file)?;
+    let mut out = String::new();
+    let mut upper = 0;
+    let mut records = 0;
+    for &(address, data) in &loads {
+        if address + data.len() as u64 > 1 << 32 {
+            bail!(
+                "The segment at {:#x} is beyond the 4 GiB Intel HEX can address",
+                address
+            );
+        }
+        let mut offset = 0;
+        while offset < data.len() {
+            let current = address + offset as u64;
+            if current >> 16 != upper {
+                upper = current >> 16;
+                record(&mut out, 4, 0, &(upper as u16).to_be_bytes());
+            }
+            // Records do not cross a 64 KiB boundary.
+            let room = 0x10000 - (current & 0xffff) as usize;
+            let length = 16.min(data.len() - offset).min(room);
+            record(&mut out, 0, current as u16, &data[offset..offset + length]);
+            records += 1;
+            offset += length;
+        }
+    }
+    let entry = elf_file.entry();
+    if entry != 0 && entry < 1 << 32 {
+        record(&mut out, 5, 0, &(entry as u32).to_be_bytes());
+    }
+    record(&mut out, 1, 0, &[]);
+    std::fs::write(path, &out).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let contents: usize = loads.iter().map(|(_, data)| data.len()).sum();
+    println!(
+        "Wrote {} bytes from {} segments to {} as Intel HEX ({} data records)",
+        contents,
+        loads.len(),
+        path.display(),
+        records
+    );
+    Ok(())
+}
@@ -0,0 +1,238 @@
+//! What every byte of the file is for: a header, a section, segment
+//! contents no section describes, or slack. The bytes nothing accounts
+//! for are where packers, appended payloads and corruption hide.
+
+use anyhow::Result;
+
+use crate::elf::ElfFile;
+use crate::section::SectionType;
+
+/// What a region of the file holds, for the summary.
+#[derive(Clone, Copy, PartialEq)]
+enum Category {
+    Headers,
+    Allocated,
+    NotAllocated,
+    SegmentOnly,
+    Padding,
+    Unaccounted,
+}
+
+impl Category {
+    const ALL: [Category; 6] = [
+        Category::Headers,
+        Category::Allocated,
+        Category::NotAllocated,
+        Category::SegmentOnly,
+        Category::Padding,
+        Category::Unaccounted,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Category::Headers => "ELF header and header tables",
+            Category::Allocated => "Sections loaded into memory",
+            Category::NotAllocated => "Sections not loaded (symbols, debug info)",
+            Category::SegmentOnly => "Segment contents outside any section",
+            Category::Padding => "Padding (zero bytes between the above)",
+            Category::Unaccounted => "Unaccounted (non-zero bytes nothing describes)",
+        }
+    }
+}
+
+struct Region {
+    start: u64,
+    end: u64,
+    category: Category,
+    what: String,
+}
+
+/// Prints the regions of the file in order, with the gaps between them,
+/// and how much of the file each kind of region takes.
+pub fn print_file_usage(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let segments = elf_file.program_headers()?;
+    let header = elf_file.header();
+    let data = elf_file.data();
+    let file_size = data.len() as u64;
+
+    // The things that describe themselves: the headers and the sections
+    // with contents.
+    let mut described = vec![Region {
+        start: 0,
+        end: header.e_ehsize as u64,
+        category: Category::Headers,
+        what: "ELF header".to_string(),
+    }];
+    if !segments.is_empty() {
+        described.push(Region {
+            start: header.e_phoff,
+            end: header
+                .e_phoff
+                .saturating_add(segments.len() as u64 * header.e_phentsize as u64),
+            category: Category::Headers,
+            what: "program headers".to_string(),
+        });
+    }
+    if !sections.is_empty() {
+        described.push(Region {
+            start: header.e_shoff,
+            end: header
+                .e_shoff
+                .saturating_add(sections.len() as u64 * header.e_shentsize as u64),
+            category: Category::Headers,
+            what: "section headers".to_string(),
+        });
+    }
+    let mut nobits = Vec::new();
+    for section in &sections {
+        let name = elf_file.section_name(&sections, section)?;
+        if section.sh_type == SectionType::NOBITS {
+            if section.sh_size > 0 {
+                nobits.push((name, section.sh_size));
+            }
+            continue;
+        }
+        if section.sh_type == SectionType::NULL || section.sh_size == 0 {
+            continue;
+        }
+        described.push(Region {
+            start: section.sh_offset,
+            // Corrupt sizes are cut short rather than overflowing.
+            end: section.sh_offset.saturating_add(section.sh_size),
+            category: if section.is_alloc() {
+                Category::Allocated
+            } else {
+                Category::NotAllocated
+            },
+            what: name.to_string(),
+        });
+    }
+    described.sort_by_key(|r| (r.start, r.end));
+
+    // The gaps between them, split where segments start or stop covering
+    // them.
+    let mut regions = Vec::new();
+    let mut overlaps = Vec::new();
+    let mut cursor = 0;
+    let fill_gap = |regions: &mut Vec<Region>, start: u64, end: u64| {
+        let mut points = vec![start, end];
+        for segment in &segments {
+            let segment_end = segment.p_offset.saturating_add(segment.p_filesz);
+            for point in [segment.p_offset, segment_end] {
+                if point > start && point < end {
+                    points.push(point);
+                }
+            }
+        }
+        points.sort_unstable();
+        points.dedup();
+        for pair in points.windows(2) {
+            let (start, end) = (pair[0], pair[1]);
+            let bytes = data.get(start as usize..end as usize).unwrap_or(&[]);
+            // The smallest segment says most about what the bytes are.
+            let segment = segments
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| {
+                    s.p_filesz > 0 && start >= s.p_offset && start - s.p_offset < s.p_filesz
+                })
+                .min_by_key(|(_, s)| s.p_filesz);
+            let zero = bytes.iter().all(|&b| b == 0);
+            let (category, what) = match segment {
+                Some((index, segment)) if zero => (
+                    Category::Padding,
+                    format!("padding, in segment {} ({})", index, segment.p_type),
+                ),
+                Some((index, segment)) => (
+                    Category::SegmentOnly,
+                    format!(
+                        "segment {} ({}) contents, in no section",
+                        index, segment.p_type
+                    ),
+                ),
+                None if zero => (Category::Padding, "padding".to_string()),
+                None => (Category::Unaccounted, "unaccounted".to_string()),
+            };
+            regions.push(Region {
+                start,
+                end,
+                category,
+                what,
+            });
+        }
+    };
+    for region in described {
+        if region.end <= cursor {
+            overlaps.push(region.what);
+            continue;
+        }
+        if region.start > cursor {
+            fill_gap(&mut regions, cursor, region.start);
+        } else if region.start < cursor {
+            overlaps.push(region.what.clone());
+        }
+        // Only the part past what came before counts for this one.
+        let start = region.start.max(cursor);
+        cursor = region.end;
+        regions.push(Region { start, ..region });
+    }
+    if file_size > cursor {
+        fill_gap(&mut regions, cursor, file_size);
+    }
+
+    println!("File usage ({:#x} bytes):", file_size);
+    println!("  {:<12} {:<12} {:>10}  What", "Start", "End", "Size");
+    for region in &regions {
+        let marker = if region.category == Category::Unaccounted {
+            "  <-- nothing describes these bytes"
+        } else {
+            ""
+        };
+        println!(
+            "  {:<#12x} {:<#12x} {:>#10x}  {}{}",
+            region.start,
+            region.end,
+            region.end - region.start,
+            region.what,
+            marker
+        );
+    }
+    if cursor > file_size {
+        println!(
+            "  The file is truncated: its headers describe {:#x} bytes.",
+            cursor
+        );
+    }
+
+    println!();
+    println!("Coverage:");
+    for category in Category::ALL {
+        let size: u64 = regions
+            .iter()
+            .filter(|r| r.category == category)
+            .map(|r| r.end.min(file_size).saturating_sub(r.start))
+            .sum();
+        println!(
+            "  {:<48} {:>#10x} {:>6.1}%",
+            category.label(),
+            size,
+            size as f64 * 100.0 / file_size.max(1) as f64
+        );
+    }
+    if !nobits.is_empty() {
+        println!();
+        println!("Taking memory but no file space (SHT_NOBITS):");
+        for (name, size) in nobits {
+            println!("  {:<24} {:#x}", name, size);
+        }
+    }
+    if !overlaps.is_empty() {
+        println!();
+        println!(
+            "Overlapping what comes before them: {}",
+            overlaps.join(", ")
+        );
+    }
+    Ok(())
+}
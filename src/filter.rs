@@ -0,0 +1,90 @@
+use anyhow::{Context, Result, bail};
+
+use crate::elf::ElfFile;
+use crate::section::SectionHeader;
+
+enum Selector {
+    Glob(String),
+    /// An inclusive range of section indexes; a single index is `n..=n`.
+    Indexes(usize, usize),
+}
+
+/// The sections picked with `--section`, by name glob or by index. An empty
+/// filter selects every section. Only the reports that take one are
+/// limited by it: the section headers, the relocations, the symbol tables,
+/// and the section hashes, entropy, cross references and decoded sections.
+/// The DWARF dumps are not, since the DIEs and units they show are not
+/// sorted by the sections of the code they describe.
+#[derive(Default)]
+pub struct SectionFilter {
+    selectors: Vec<Selector>,
+}
+
+impl SectionFilter {
+    /// Parses comma-separated selectors: names with `*` and `?` wildcards,
+    /// section indexes, and index ranges like `3-7`.
+    pub fn new<'a>(values: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let mut selectors = Vec::new();
+        for selector in values.into_iter().flat_map(|v| v.split(',')) {
+            let selector = selector.trim();
+            if selector.is_empty() {
+                continue;
+            }
+            if selector.starts_with(|c: char| c.is_ascii_digit()) {
+                let (start, end) = selector.split_once('-').unwrap_or((selector, selector));
+                let parse = |n: &str| {
+                    n.parse::<usize>()
+                        .with_context(|| format!("Invalid section index {:?}", n))
+                };
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    bail!(
+                        "Invalid section range {:?}: {} comes after {}",
+                        selector,
+                        start,
+                        end
+                    );
+                }
+                selectors.push(Selector::Indexes(start, end));
+            } else {
+                selectors.push(Selector::Glob(selector.to_string()));
+            }
+        }
+        Ok(Self { selectors })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selectors.is_empty()
+    }
+
+    pub fn matches(&self, index: usize, name: &str) -> bool {
+        self.is_empty()
+            || self.selectors.iter().any(|selector| match selector {
+                Selector::Glob(pattern) => glob_match(pattern.as_bytes(), name.as_bytes()),
+                Selector::Indexes(start, end) => (*start..=*end).contains(&index),
+            })
+    }
+
+    /// Whether section `index` of `sections` is selected.
+    pub fn selects(&self, elf_file: &ElfFile, sections: &[SectionHeader], index: usize) -> bool {
+        self.is_empty()
+            || sections.get(index).is_some_and(|section| {
+                self.matches(
+                    index,
+                    elf_file.section_name(sections, section).unwrap_or(""),
+                )
+            })
+    }
+}
+
+/// Matches `name` against a pattern where `*` stands for any run of bytes
+/// and `?` for any single byte.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| glob_match(rest, &name[skip..])),
+        Some((&c, rest)) => name
+            .split_first()
+            .is_some_and(|(&n, name)| (c == b'?' || c == n) && glob_match(rest, name)),
+    }
+}
@@ -0,0 +1,248 @@
+//! Recognizing the other object file formats that end up next to ELF files
+//! in build output directories, so that being handed one is reported as
+//! what it is rather than as a broken ELF file.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Read;
+
+/// How much of a file is read to tell what it is. PE headers can start a
+/// little way in, after the DOS stub.
+const PREFIX: u64 = 4096;
+
+/// A file format other than ELF.
+pub struct Format {
+    /// A short name for machine-readable output: "pe", "mz", "coff",
+    /// "macho", "wasm" or "ar".
+    pub kind: &'static str,
+    /// What the file is, for people.
+    pub description: String,
+    /// Tools that read it.
+    pub hint: Option<&'static str>,
+}
+
+impl Format {
+    /// The error to report instead of failing to parse it as ELF.
+    pub fn message(&self) -> String {
+        match self.hint {
+            Some(hint) => format!("Not an ELF file but {}; try {}", self.description, hint),
+            None => format!("Not an ELF file but {}", self.description),
+        }
+    }
+}
+
+fn u16_le(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_le(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// The names of the COFF machine numbers, which PE files share.
+pub fn coff_machine(machine: u16) -> Option<&'static str> {
+    Some(match machine {
+        0x014c => "i386",
+        0x8664 => "x86-64",
+        0x01c0 => "ARM",
+        0x01c4 => "ARM Thumb-2",
+        0xaa64 => "AArch64",
+        0xa641 => "ARM64EC",
+        0x5032 => "RISC-V 32",
+        0x5064 => "RISC-V 64",
+        0x6264 => "LoongArch 64",
+        0x0ebc => "EFI byte code",
+        _ => return None,
+    })
+}
+
+/// The names of the Mach-O CPU types.
+pub fn macho_cpu(cpu: u32) -> &'static str {
+    match cpu {
+        0x0100_0007 => "x86-64",
+        0x0100_000c => "arm64",
+        0x0200_000c => "arm64_32",
+        7 => "x86",
+        12 => "ARM",
+        18 => "PowerPC",
+        0x0100_0012 => "PowerPC64",
+        _ => "an unknown CPU",
+    }
+}
+
+/// The names of the Mach-O file types.
+pub fn macho_file_type(value: u32) -> &'static str {
+    match value {
+        1 => "object file",
+        2 => "executable",
+        6 => "dynamic library",
+        7 => "dynamic linker",
+        8 => "bundle",
+        10 => "debug symbols file",
+        11 => "kernel extension",
+        _ => "file",
+    }
+}
+
+/// A PE image: an MZ stub pointing at a "PE\0\0" signature, followed by a
+/// COFF header and the optional header.
+fn detect_pe(data: &[u8]) -> Option<Format> {
+    let pe = u32_le(data, 0x3c)? as usize;
+    if data.get(pe..pe + 4)? != b"PE\0\0" {
+        return Some(Format {
+            kind: "mz",
+            description: "a DOS MZ executable".to_string(),
+            hint: None,
+        });
+    }
+    let machine = u16_le(data, pe + 4)?;
+    let characteristics = u16_le(data, pe + 22)?;
+    let optional = pe + 24;
+    let bits = match u16_le(data, optional) {
+        Some(0x20b) => "PE32+",
+        _ => "PE32",
+    };
+    let subsystem_offset = optional + 68;
+    let role = match u16_le(data, subsystem_offset) {
+        Some(10) => "UEFI application",
+        Some(11) => "UEFI boot service driver",
+        Some(12) => "UEFI runtime driver",
+        Some(13) => "UEFI ROM image",
+        _ if characteristics & 0x2000 != 0 => "DLL",
+        _ => "executable",
+    };
+    Some(Format {
+        kind: "pe",
+        description: format!(
+            "a {} {} for {}",
+            bits,
+            role,
+            coff_machine(machine).unwrap_or("an unknown machine")
+        ),
+        hint: Some("objdump -x or llvm-readobj"),
+    })
+}
+
+/// Works out which format other than ELF `data`, the start of a file, is
+/// in, if it is one this knows.
+pub fn detect(data: &[u8]) -> Option<Format> {
+    if data.starts_with(b"MZ") {
+        return detect_pe(data);
+    }
+    if data.starts_with(b"\0asm") {
+        let version = u32_le(data, 4).unwrap_or(0);
+        return Some(Format {
+            kind: "wasm",
+            description: format!("a WebAssembly module (version {})", version),
+            hint: Some("wasm-objdump -h or llvm-objdump"),
+        });
+    }
+    if data.starts_with(b"!<arch>\n") || data.starts_with(b"!<thin>\n") {
+        return Some(Format {
+            kind: "ar",
+            description: "an ar archive, a static library, whose members are object files"
+                .to_string(),
+            hint: Some("ar x, then readelf-rs on the members"),
+        });
+    }
+    let magic = u32::from_be_bytes(data.get(..4)?.try_into().ok()?);
+    let field = |offset| {
+        let word = u32_le(data, offset)?;
+        Some(if magic >> 24 == 0xfe {
+            word.swap_bytes()
+        } else {
+            word
+        })
+    };
+    match magic {
+        0xfeedface | 0xcefaedfe | 0xfeedfacf | 0xcffaedfe => {
+            let bits = if magic & 0xff == 0xcf || magic >> 24 == 0xcf {
+                64
+            } else {
+                32
+            };
+            let cpu = field(4)?;
+            let kind = macho_file_type(field(12)?);
+            return Some(Format {
+                kind: "macho",
+                description: format!("a {}-bit Mach-O {} for {}", bits, kind, macho_cpu(cpu)),
+                hint: Some("otool -l or llvm-objdump --macho"),
+            });
+        }
+        // Java class files share this magic; their version numbers are
+        // far larger than any count of architectures.
+        0xcafebabe if u32::from_be_bytes(data.get(4..8)?.try_into().ok()?) < 32 => {
+            return Some(Format {
+                kind: "macho",
+                description: "a universal (fat) Mach-O binary".to_string(),
+                hint: Some("lipo -info, then otool -l on the thin binaries"),
+            });
+        }
+        _ => {}
+    }
+    // A COFF object has no magic at all, only a machine number and a
+    // header whose fields must line up.
+    let machine = u16_le(data, 0)?;
+    let sections = u16_le(data, 2)?;
+    let optional_size = u16_le(data, 16)?;
+    if let Some(name) = coff_machine(machine)
+        && (1..=1000).contains(&sections)
+        && optional_size == 0
+    {
+        return Some(Format {
+            kind: "coff",
+            description: format!("a COFF object file for {}", name),
+            hint: Some("objdump -x or llvm-readobj"),
+        });
+    }
+    None
+}
+
+/// Reads the start of the file at `path` and, if it is in some format
+/// other than ELF, says which.
+pub fn identify(path: &str) -> Result<Option<Format>> {
+    let mut data = Vec::new();
+    File::open(path)
+        .context("Failed to open ELF file")?
+        .take(PREFIX)
+        .read_to_end(&mut data)
+        .context("Failed to read ELF file")?;
+    if data.starts_with(b"\x7fELF") {
+        return Ok(None);
+    }
+    Ok(detect(&data))
+}
+
+/// Prints the headers of a file in another format, if support for it is
+/// built in, returning whether it was.
+#[cfg_attr(
+    not(any(feature = "macho", feature = "pe", feature = "wasm")),
+    allow(unused_variables)
+)]
+pub fn print_headers(format: &Format, path: &str) -> Result<bool> {
+    match format.kind {
+        #[cfg(feature = "pe")]
+        "pe" | "coff" => {
+            let data = std::fs::read(path).context("Failed to read file")?;
+            crate::pe::print_pe(&data)?;
+            Ok(true)
+        }
+        #[cfg(feature = "macho")]
+        "macho" => {
+            let data = std::fs::read(path).context("Failed to read file")?;
+            crate::macho::print_macho(&data)?;
+            Ok(true)
+        }
+        #[cfg(feature = "wasm")]
+        "wasm" => {
+            let data = std::fs::read(path).context("Failed to read file")?;
+            crate::wasm::print_wasm(&data)?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
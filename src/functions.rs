@@ -0,0 +1,189 @@
+use anyhow::Result;
+use std::collections::HashSet;
+
+use crate::dwarf::{self, LineTable};
+use crate::elf::ElfFile;
+use crate::ppc64;
+use crate::section::{SHF_EXECINSTR, SHN_LORESERVE, SHN_UNDEF};
+use crate::strtab::SymbolTable;
+use crate::symbol::SymbolType;
+
+struct Function {
+    start: u64,
+    size: u64,
+    shndx: u16,
+    name: String,
+    /// Where the symbol's value points when that is not the code itself,
+    /// or where else the function can be entered.
+    note: String,
+}
+
+/// Lists function symbols by address, pointing out the stretches of
+/// executable sections that no function symbol covers. On PowerPC64,
+/// symbols of ELFv1 function descriptors are listed at the code they
+/// describe, and ELFv2 local entry points are shown next to the function.
+/// With `deterministic`, the source paths leave out where the code was
+/// built.
+pub fn print_functions(elf_file: &ElfFile, deterministic: bool) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let Some(symtab) = elf_file.symbol_table(&sections) else {
+        println!("No symbol table found in this file.");
+        return Ok(());
+    };
+
+    let opd =
+        ppc64::opd_section(elf_file, &sections).filter(|_| ppc64::descriptors_resolved(elf_file));
+    let descriptors = match opd {
+        Some(opd) => ppc64::descriptors(elf_file, opd)?,
+        None => Vec::new(),
+    };
+
+    let mut seen = HashSet::new();
+    let mut functions = Vec::new();
+    let symbols = SymbolTable::new(elf_file, &sections, symtab)?;
+    for (index, symbol) in symbols.iter().enumerate() {
+        if symbol.symbol_type() != SymbolType::FUNC
+            || symbol.st_shndx == SHN_UNDEF
+            || symbol.st_shndx >= SHN_LORESERVE
+        {
+            continue;
+        }
+
+        // Aliases would otherwise be listed several times over.
+        if !seen.insert((symbol.st_shndx, symbol.st_value, symbol.st_size)) {
+            continue;
+        }
+
+        let mut function = Function {
+            start: symbol.st_value,
+            size: symbol.st_size,
+            shndx: symbol.st_shndx,
+            name: symbols.name(index)?.to_string(),
+            note: String::new(),
+        };
+        if let Some(descriptor) = descriptors.iter().find(|d| d.address == symbol.st_value)
+            && let Some(code) = sections
+                .iter()
+                .position(|s| s.sh_flags & SHF_EXECINSTR != 0 && s.contains_addr(descriptor.entry))
+        {
+            function.start = descriptor.entry;
+            function.shndx = code as u16;
+            function.note = format!("  [descriptor {:#x}]", descriptor.address);
+        } else if ppc64::is_ppc64(elf_file) && ppc64::local_entry_offset(symbol.st_other) != 0 {
+            function.note = format!(
+                "  [local entry {:#x}]",
+                symbol
+                    .st_value
+                    .saturating_add(ppc64::local_entry_offset(symbol.st_other))
+            );
+        }
+        functions.push(function);
+    }
+    functions.sort_by_key(|f| (f.shndx, f.start));
+
+    let lines = match dwarf::load(elf_file)? {
+        Some(dwarf) => Some(LineTable::new(&dwarf, deterministic)?),
+        None => None,
+    };
+
+    let section_name = |index: usize| {
+        sections
+            .get(index)
+            .and_then(|s| elf_file.section_name(&sections, s).ok())
+            .unwrap_or("<unknown>")
+    };
+
+    println!(
+        "Functions from '{}' sorted by address:",
+        elf_file.section_name(&sections, symtab)?
+    );
+    if ppc64::is_ppc64(elf_file)
+        && let Some(toc) = ppc64::toc_base(elf_file, &sections)?
+    {
+        println!("TOC base: {:#x}", toc);
+    }
+    println!(
+        "  {:<18} {:<18} {:>8}  {:<12} Name",
+        "Start", "End", "Size", "Section"
+    );
+
+    let mut gaps = 0;
+    let mut gap_bytes = 0u64;
+    let mut print_gap = |section: usize, start: u64, end: u64| {
+        // Anything shorter than the section alignment is just padding
+        // between functions.
+        let align = sections[section].sh_addralign.max(1);
+        if end > start && end - start >= align {
+            println!(
+                "  {:#018x} {:#018x} {:>8}  {:<12} <gap: not covered by any function symbol>",
+                start,
+                end,
+                end - start,
+                section_name(section)
+            );
+            gaps += 1;
+            gap_bytes = gap_bytes.saturating_add(end - start);
+        }
+    };
+
+    let mut current: Option<(usize, u64)> = None;
+    for function in &functions {
+        let index = function.shndx as usize;
+        let Some(section) = sections.get(index) else {
+            continue;
+        };
+        let executable = section.sh_flags & SHF_EXECINSTR != 0;
+
+        // Close off the previous section and open the new one, reporting any
+        // uncovered space at either end.
+        if current.is_none_or(|(i, _)| i != index) {
+            if let Some((i, end)) = current {
+                let s = &sections[i];
+                print_gap(i, end, s.sh_addr.saturating_add(s.sh_size));
+            }
+            current = executable.then_some((index, section.sh_addr));
+        }
+
+        let function_end = function.start.saturating_add(function.size);
+        // Symbols without a size, like labels in assembly, say nothing
+        // about what is covered.
+        if let Some((i, end)) = current
+            && function.size != 0
+        {
+            print_gap(i, end, function.start);
+            current = Some((i, end.max(function_end)));
+        }
+
+        let source = lines
+            .as_ref()
+            .and_then(|l| l.location(function.start))
+            .map(|(file, line)| format!("  [{}:{}]", file, line))
+            .unwrap_or_default();
+
+        println!(
+            "  {:#018x} {:#018x} {:>8}  {:<12} {}{}{}",
+            function.start,
+            function_end,
+            function.size,
+            section_name(index),
+            function.name,
+            function.note,
+            source
+        );
+    }
+
+    if let Some((i, end)) = current {
+        let s = &sections[i];
+        print_gap(i, end, s.sh_addr.saturating_add(s.sh_size));
+    }
+
+    println!();
+    println!(
+        "{} functions, {} gaps covering {} bytes",
+        functions.len(),
+        gaps,
+        gap_bytes
+    );
+
+    Ok(())
+}
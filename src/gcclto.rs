@@ -0,0 +1,219 @@
+//! Objects GCC wrote for link-time optimization, whose `.gnu.lto_*`
+//! sections hold GIMPLE for the LTO plugin, either instead of machine code
+//! (slim objects, the default) or next to it (`-ffat-lto-objects`).
+
+use anyhow::Result;
+
+use crate::elf::ElfFile;
+use crate::section::SectionHeader;
+
+const LTO_PREFIX: &str = ".gnu.lto_";
+const OFFLOAD_PREFIX: &str = ".gnu.offload_lto_";
+
+/// What the `.gnu.lto_.lto` section says about the LTO data of an object.
+pub struct LtoInfo {
+    /// The LTO bytecode version, which is the GCC major version since
+    /// GCC 10; older objects have no such section.
+    pub version: Option<(u16, u16)>,
+    pub slim: bool,
+    pub compression: Option<&'static str>,
+    pub offload: bool,
+}
+
+fn lto_sections<'s>(
+    elf_file: &ElfFile,
+    sections: &'s [SectionHeader],
+    prefix: &str,
+) -> Vec<(&'s SectionHeader, String)> {
+    sections
+        .iter()
+        .filter_map(|s| {
+            let name = elf_file.section_name(sections, s).ok()?;
+            name.starts_with(prefix).then(|| (s, name.to_string()))
+        })
+        .collect()
+}
+
+/// The LTO properties of a GCC LTO object, or `None` for other files.
+pub fn lto_info(elf_file: &ElfFile) -> Result<Option<LtoInfo>> {
+    let sections = elf_file.section_headers()?;
+    let lto = lto_sections(elf_file, &sections, LTO_PREFIX);
+    if lto.is_empty() {
+        return Ok(None);
+    }
+
+    let header = lto
+        .iter()
+        .find(|(_, name)| name.starts_with(".gnu.lto_.lto."))
+        .and_then(|(s, _)| elf_file.section_data(s).ok())
+        .filter(|data| data.len() >= 8);
+    let half = |data: &[u8], offset: usize| u16::from_ne_bytes([data[offset], data[offset + 1]]);
+
+    // Before GCC 10, slim objects said so with a common symbol instead.
+    let slim_symbol = elf_file.symbol_table(&sections).is_some_and(|table| {
+        elf_file.symbols(table).is_ok_and(|mut symbols| {
+            symbols.any(|symbol| {
+                elf_file.symbol_name(&sections, table, &symbol).ok() == Some("__gnu_lto_slim")
+            })
+        })
+    });
+
+    Ok(Some(LtoInfo {
+        version: header.map(|data| (half(data, 0), half(data, 2))),
+        slim: header.map_or(slim_symbol, |data| data[4] != 0),
+        compression: header.map(|data| {
+            if half(data, 6) & 1 != 0 {
+                "zstd"
+            } else {
+                "zlib"
+            }
+        }),
+        offload: !lto_sections(elf_file, &sections, OFFLOAD_PREFIX).is_empty(),
+    }))
+}
+
+/// Says plainly that a file is a GCC LTO object, and whether it has any
+/// machine code, since tools without the LTO plugin see an object that
+/// looks empty.
+pub fn print_notice(elf_file: &ElfFile) -> Result<()> {
+    let Some(info) = lto_info(elf_file)? else {
+        return Ok(());
+    };
+    let version = match (info.version, info.compression) {
+        (Some((major, minor)), Some(compression)) => {
+            format!(
+                "LTO bytecode {}.{}, {}-compressed",
+                major, minor, compression
+            )
+        }
+        _ => "LTO bytecode from before GCC 10".to_string(),
+    };
+    if info.slim {
+        println!(
+            "This is a slim GCC LTO object ({}): it holds GIMPLE intermediate code instead of machine code, so its code and symbols only become real in an LTO link. Use --lto-syms to see the symbols.",
+            version
+        );
+    } else {
+        println!(
+            "This is a fat GCC LTO object ({}): it has machine code as usual, and GIMPLE intermediate code for LTO links besides.",
+            version
+        );
+    }
+    if info.offload {
+        println!("It also holds GIMPLE for offloading to an accelerator (.gnu.offload_lto_*).");
+    }
+    println!();
+    Ok(())
+}
+
+fn kind_name(kind: u8) -> &'static str {
+    match kind {
+        0 => "DEF",
+        1 => "WEAKDEF",
+        2 => "UNDEF",
+        3 => "WEAKUNDEF",
+        4 => "COMMON",
+        _ => "?",
+    }
+}
+
+fn visibility_name(visibility: u8) -> &'static str {
+    match visibility {
+        0 => "DEFAULT",
+        1 => "PROTECTED",
+        2 => "INTERNAL",
+        3 => "HIDDEN",
+        _ => "?",
+    }
+}
+
+/// Prints the symbol tables of a GCC LTO object, with the symbol types from
+/// the extension table when there is one. Returns whether there were any.
+pub fn print_lto_symbols(elf_file: &ElfFile) -> Result<bool> {
+    let sections = elf_file.section_headers()?;
+    let lto = lto_sections(elf_file, &sections, LTO_PREFIX);
+    let mut found = false;
+
+    for (section, name) in &lto {
+        let Some(suffix) = name.strip_prefix(".gnu.lto_.symtab") else {
+            continue;
+        };
+        found = true;
+        let extension = lto
+            .iter()
+            .find(|(_, n)| n.strip_prefix(".gnu.lto_.ext_symtab") == Some(suffix))
+            .and_then(|(s, n)| Some((elf_file.section_data(s).ok()?, n)));
+        match extension {
+            Some((_, extension_name)) => println!(
+                "LTO symbol table '{}' with extension table '{}':",
+                name, extension_name
+            ),
+            None => println!("LTO symbol table '{}':", name),
+        }
+        println!(
+            "  {:<10} {:<10} {:>8} {:>8}  {:<8} {:<8} Name",
+            "Kind", "Visibility", "Size", "Slot", "Type", "Section"
+        );
+
+        // Each entry: name, comdat key, kind, visibility, size, slot.
+        let data = elf_file.section_data(section)?;
+        let mut rest = data;
+        // The extension table starts with its version, then has a type and
+        // section kind for each symbol.
+        let mut types = extension.map(|(data, _)| data.get(1..).unwrap_or(&[]));
+        while !rest.is_empty() {
+            let Some(name_end) = rest.iter().position(|&b| b == 0) else {
+                break;
+            };
+            let symbol = String::from_utf8_lossy(&rest[..name_end]).into_owned();
+            rest = &rest[name_end + 1..];
+            let Some(comdat_end) = rest.iter().position(|&b| b == 0) else {
+                break;
+            };
+            let comdat = String::from_utf8_lossy(&rest[..comdat_end]).into_owned();
+            rest = &rest[comdat_end + 1..];
+            if rest.len() < 14 {
+                println!("  <truncated entry for {}>", symbol);
+                break;
+            }
+            let (kind, visibility) = (rest[0], rest[1]);
+            let size = u64::from_ne_bytes(rest[2..10].try_into().unwrap());
+            let slot = u32::from_ne_bytes(rest[10..14].try_into().unwrap());
+            rest = &rest[14..];
+
+            let (symbol_type, section_kind) = match types.as_mut().and_then(|t| {
+                let entry = t.get(..2)?;
+                *t = &t[2..];
+                Some((entry[0], entry[1]))
+            }) {
+                Some((symbol_type, section_kind)) => (
+                    match symbol_type {
+                        1 => "FUNCTION",
+                        2 => "VARIABLE",
+                        _ => "UNKNOWN",
+                    },
+                    if section_kind == 1 { "BSS" } else { "DEFAULT" },
+                ),
+                None => ("-", "-"),
+            };
+            let comdat = if comdat.is_empty() {
+                String::new()
+            } else {
+                format!(" [comdat {}]", comdat)
+            };
+            println!(
+                "  {:<10} {:<10} {:>8} {:>#8x}  {:<8} {:<8} {}{}",
+                kind_name(kind),
+                visibility_name(visibility),
+                size,
+                slot,
+                symbol_type,
+                section_kind,
+                symbol,
+                comdat
+            );
+        }
+        println!();
+    }
+    Ok(found)
+}
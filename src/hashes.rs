@@ -0,0 +1,74 @@
+use anyhow::{Result, bail};
+use sha2::{Digest, Sha256};
+
+use crate::elf::ElfFile;
+use crate::filter::SectionFilter;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "sha256" => Ok(Self::Sha256),
+            "blake3" => Ok(Self::Blake3),
+            _ => bail!("Unknown hash algorithm: {}", name),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    fn digest(&self, data: &[u8]) -> String {
+        let bytes: Vec<u8> = match self {
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+            Self::Blake3 => blake3::hash(data).as_bytes().to_vec(),
+        };
+
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Prints a digest of the contents of every allocated section.
+pub fn print_section_hashes(
+    elf_file: &ElfFile,
+    algorithm: HashAlgorithm,
+    filter: &SectionFilter,
+) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+
+    if sections.is_empty() {
+        println!("There are no sections in this file.");
+        return Ok(());
+    }
+
+    println!("Section hashes ({}):", algorithm.name());
+    println!("  [Nr] Name                 Size             Digest");
+
+    for (index, section) in sections.iter().enumerate() {
+        let name = elf_file.section_name(&sections, section)?;
+        if !section.is_alloc() || !filter.matches(index, name) {
+            continue;
+        }
+
+        let digest = if section.has_file_data() {
+            algorithm.digest(elf_file.section_data(section)?)
+        } else {
+            "(no file data)".to_string()
+        };
+
+        println!(
+            "  [{:>2}] {:<20} {:016x} {}",
+            index, name, section.sh_size, digest
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use anyhow::Result;
+
+use crate::dynamic::DynamicTag;
+use crate::elf::ElfFile;
+
+/// Reads the `count` words from `offset` on, as far as the file has them.
+fn words(elf_file: &ElfFile, offset: u64, count: u64) -> Vec<u32> {
+    let data = usize::try_from(offset)
+        .ok()
+        .and_then(|offset| elf_file.data().get(offset..))
+        .unwrap_or(&[]);
+    data.chunks_exact(4)
+        .take(usize::try_from(count).unwrap_or(usize::MAX))
+        .map(|word| u32::from_ne_bytes(word.try_into().unwrap()))
+        .collect()
+}
+
+/// Prints how many buckets have chains of each length, and how many of the
+/// symbols the buckets up to that length hold, as `readelf -I` does.
+fn print_lengths(title: &str, lengths: &[u64]) {
+    let buckets = lengths.len();
+    let symbols: u64 = lengths.iter().sum();
+    let longest = lengths.iter().copied().max().unwrap_or(0);
+    let mut counts = vec![0u64; longest as usize + 1];
+    for &length in lengths {
+        counts[length as usize] += 1;
+    }
+
+    println!();
+    println!(
+        "Histogram for {}bucket list length (total of {} {}):",
+        title,
+        buckets,
+        if buckets == 1 { "bucket" } else { "buckets" }
+    );
+    println!(" Length  Number     % of total  Coverage");
+    let share = |count: u64| count as f64 * 100.0 / buckets as f64;
+    println!("      0  {:<10} ({:5.1}%)", counts[0], share(counts[0]));
+    let mut covered = 0;
+    for (length, &count) in counts.iter().enumerate().skip(1) {
+        covered += count * length as u64;
+        println!(
+            "{:7}  {:<10} ({:5.1}%)    {:5.1}%",
+            length,
+            count,
+            share(count),
+            covered as f64 * 100.0 / symbols as f64
+        );
+    }
+}
+
+/// The chain lengths of the buckets of a SysV hash table. Chains that loop
+/// are cut off where they would come back to a symbol.
+fn sysv_lengths(elf_file: &ElfFile, offset: u64) -> Vec<u64> {
+    let header = words(elf_file, offset, 2);
+    let [nbucket, nchain] = header[..] else {
+        return Vec::new();
+    };
+    let buckets = words(elf_file, offset.saturating_add(8), nbucket as u64);
+    let chains = words(
+        elf_file,
+        offset.saturating_add(8 + nbucket as u64 * 4),
+        nchain as u64,
+    );
+
+    buckets
+        .iter()
+        .map(|&bucket| {
+            let mut length = 0;
+            let mut index = bucket as usize;
+            while index != 0 && index < chains.len() && length < chains.len() as u64 {
+                length += 1;
+                index = chains[index] as usize;
+            }
+            length
+        })
+        .collect()
+}
+
+/// The chain lengths of the buckets of a GNU hash table, whose chains end
+/// at the entry with the low bit set.
+fn gnu_lengths(elf_file: &ElfFile, offset: u64) -> Vec<u64> {
+    let header = words(elf_file, offset, 3);
+    let [nbuckets, symoffset, bloom_size] = header[..] else {
+        return Vec::new();
+    };
+    let bloom_word = if elf_file.is_64() { 8 } else { 4 };
+    let buckets_offset = offset.saturating_add(16 + bloom_size as u64 * bloom_word);
+    let buckets = words(elf_file, buckets_offset, nbuckets as u64);
+    let chains = words(
+        elf_file,
+        buckets_offset.saturating_add(nbuckets as u64 * 4),
+        u64::MAX,
+    );
+
+    buckets
+        .iter()
+        .map(|&bucket| {
+            if bucket == 0 || bucket < symoffset {
+                return 0;
+            }
+            let mut length = 0;
+            for &hash in chains.iter().skip((bucket - symoffset) as usize) {
+                length += 1;
+                if hash & 1 != 0 {
+                    break;
+                }
+            }
+            length
+        })
+        .collect()
+}
+
+/// Prints the histograms of the bucket list lengths of the SysV and GNU
+/// hash tables the dynamic section points to.
+pub fn print_histogram(elf_file: &ElfFile) -> Result<()> {
+    let entries = elf_file.dynamic_entries()?;
+    let value = |tag| entries.iter().find(|e| e.d_tag == tag).map(|e| e.d_val);
+
+    if let Some(addr) = value(DynamicTag::HASH)
+        && let Some(offset) = elf_file.vaddr_to_offset(addr)?
+    {
+        let lengths = sysv_lengths(elf_file, offset);
+        if !lengths.is_empty() {
+            print_lengths("", &lengths);
+        }
+    }
+
+    if let Some(addr) = value(DynamicTag::GNU_HASH)
+        && let Some(offset) = elf_file.vaddr_to_offset(addr)?
+    {
+        let sections = elf_file.section_headers_or_dynamic()?;
+        let name = sections
+            .iter()
+            .find(|s| s.sh_addr == addr && s.is_alloc())
+            .and_then(|s| elf_file.section_name(&sections, s).ok())
+            .unwrap_or(".gnu.hash");
+        // readelf leaves out a GNU hash table without any symbols.
+        let lengths = gnu_lengths(elf_file, offset);
+        if lengths.iter().any(|&length| length != 0) {
+            print_lengths(&format!("`{}' ", name), &lengths);
+        }
+    }
+
+    Ok(())
+}
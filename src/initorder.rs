@@ -0,0 +1,358 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::dynamic::DynamicTag;
+use crate::elf::{ElfFile, ElfType};
+use crate::relocation;
+use crate::section::{SHN_UNDEF, SectionHeader};
+use crate::strtab::SymbolTable;
+use crate::symbol::SymbolType;
+
+/// The priority of constructors and destructors that do not ask for one.
+/// Numbered sections at this priority still run ahead of them.
+const DEFAULT_PRIORITY: u32 = 65535;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Preinit,
+    Init,
+    Fini,
+}
+
+/// An input section of constructors or destructors, as relocatable objects
+/// have them before the linker merges them into one array.
+struct Table {
+    index: usize,
+    name: String,
+    kind: Kind,
+    /// From the suffix of the name, if it has one.
+    priority: Option<u32>,
+    /// `.ctors` and `.dtors`, which predate the arrays and number their
+    /// priorities backwards.
+    legacy: bool,
+}
+
+/// Recognises the sections of constructors and destructors, and the
+/// priority in their names. GCC writes priority P to `.init_array.P`, but
+/// to `.ctors.(65535 - P)`, so that sorting the names ascending puts the
+/// legacy sections in the order they are run backwards in.
+fn classify(index: usize, name: &str) -> Option<Table> {
+    const PREFIXES: &[(&str, Kind, bool)] = &[
+        (".preinit_array", Kind::Preinit, false),
+        (".init_array", Kind::Init, false),
+        (".fini_array", Kind::Fini, false),
+        (".ctors", Kind::Init, true),
+        (".dtors", Kind::Fini, true),
+    ];
+
+    PREFIXES.iter().find_map(|&(prefix, kind, legacy)| {
+        let rest = name.strip_prefix(prefix)?;
+        let priority = if rest.is_empty() {
+            None
+        } else {
+            let number: u32 = rest.strip_prefix('.')?.parse().ok()?;
+            Some(if legacy {
+                DEFAULT_PRIORITY.checked_sub(number)?
+            } else {
+                number
+            })
+        };
+        Some(Table {
+            index,
+            name: name.to_string(),
+            kind,
+            priority,
+            legacy,
+        })
+    })
+}
+
+/// Names functions by their address, from whichever symbol table is there.
+pub struct FunctionNames<'a> {
+    names: HashMap<u64, &'a str>,
+}
+
+impl<'a> FunctionNames<'a> {
+    pub fn new(elf_file: &'a ElfFile, sections: &[SectionHeader]) -> Result<Self> {
+        let mut names = HashMap::new();
+        if let Some(symtab) = elf_file.symbol_table(sections) {
+            let symbols = SymbolTable::new(elf_file, sections, symtab)?;
+            for (i, symbol) in symbols.iter().enumerate() {
+                if symbol.symbol_type() == SymbolType::FUNC && symbol.st_shndx != SHN_UNDEF {
+                    names.entry(symbol.st_value).or_insert(symbols.name(i)?);
+                }
+            }
+        }
+        Ok(Self { names })
+    }
+
+    pub fn get(&self, address: u64) -> Option<&'a str> {
+        self.names.get(&address).copied()
+    }
+
+    fn describe(&self, address: u64) -> String {
+        match self.names.get(&address) {
+            Some(name) => format!("{:#x} {}", address, name),
+            None => format!("{:#x}", address),
+        }
+    }
+}
+
+/// The pointers of an array, one per word.
+fn words(elf_file: &ElfFile, data: &[u8]) -> Vec<u64> {
+    if elf_file.is_64() {
+        data.chunks_exact(8)
+            .map(|w| u64::from_ne_bytes(w.try_into().unwrap()))
+            .collect()
+    } else {
+        data.chunks_exact(4)
+            .map(|w| u32::from_ne_bytes(w.try_into().unwrap()) as u64)
+            .collect()
+    }
+}
+
+/// What the pointers in a section of a relocatable object point to, from
+/// the relocations applied to it, by offset in the section.
+pub fn relocation_targets(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    index: usize,
+) -> Result<HashMap<u64, String>> {
+    let mut targets = HashMap::new();
+    let data = elf_file.section_data(&sections[index])?;
+    let word = |offset: u64| -> i64 {
+        let offset = offset as usize;
+        if elf_file.is_64() {
+            data.get(offset..offset + 8)
+                .map_or(0, |w| i64::from_ne_bytes(w.try_into().unwrap()))
+        } else {
+            data.get(offset..offset + 4)
+                .map_or(0, |w| i32::from_ne_bytes(w.try_into().unwrap()) as i64)
+        }
+    };
+    for rel in sections
+        .iter()
+        .filter(|s| ElfFile::is_relocation_section(s) && s.sh_info as usize == index)
+    {
+        let symbols = SymbolTable::linked(elf_file, sections, rel)?;
+        // Assemblers relocate against the section rather than local
+        // functions, so find the function at the section offset instead.
+        let mut functions = HashMap::new();
+        for (i, symbol) in symbols.iter().enumerate() {
+            if symbol.symbol_type() == SymbolType::FUNC {
+                functions
+                    .entry((symbol.st_shndx, symbol.st_value))
+                    .or_insert(symbols.name(i)?);
+            }
+        }
+
+        for reloc in elf_file.decoded_relocations(rel)? {
+            let Some(symbol) = symbols.get(reloc.r_sym as usize) else {
+                continue;
+            };
+            // REL relocations keep the addend in the word they relocate.
+            let addend = reloc.r_addend.unwrap_or_else(|| word(reloc.r_offset));
+            let target = match symbols.name(reloc.r_sym as usize)? {
+                "" => {
+                    let function = functions.get(&(symbol.st_shndx, addend as u64));
+                    let section = sections
+                        .get(symbol.st_shndx as usize)
+                        .and_then(|s| elf_file.section_name(sections, s).ok())
+                        .unwrap_or("");
+                    match (function, addend) {
+                        (Some(function), _) => function.to_string(),
+                        (None, 0) => section.to_string(),
+                        (None, _) => format!("{}+{:#x}", section, addend),
+                    }
+                }
+                name if addend == 0 => name.to_string(),
+                name => format!("{}+{:#x}", name, addend),
+            };
+            targets.insert(reloc.r_offset, target);
+        }
+    }
+    Ok(targets)
+}
+
+/// Lists the constructor and destructor sections of a relocatable object
+/// in the order the linker will lay them out, which is the order they run
+/// in, with the priority each was given.
+fn print_tables(elf_file: &ElfFile, sections: &[SectionHeader]) -> Result<()> {
+    let mut tables: Vec<Table> = sections
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| classify(i, elf_file.section_name(sections, s).ok()?))
+        .collect();
+    if tables.is_empty() {
+        println!("There are no constructor or destructor sections in this file.");
+        return Ok(());
+    }
+    // Numbered sections come first, by priority; a stable sort keeps the
+    // others in file order after them, as the linker scripts do.
+    tables.sort_by_key(|t| t.priority.map_or(u64::MAX, u64::from));
+
+    let word = if elf_file.is_64() { 8 } else { 4 };
+    for (kind, title) in [
+        (Kind::Preinit, "Pre-initializers"),
+        (Kind::Init, "Constructors"),
+        (Kind::Fini, "Destructors"),
+    ] {
+        let mut group: Vec<&Table> = tables.iter().filter(|t| t.kind == kind).collect();
+        if group.is_empty() {
+            continue;
+        }
+        // Destructors run from the end of the merged array.
+        if kind == Kind::Fini {
+            group.reverse();
+        }
+
+        println!("{}, in the order they run:", title);
+        println!("  {:<8} {:<20} Entries", "Priority", "Section");
+        for table in group {
+            let section = &sections[table.index];
+            let targets = relocation_targets(elf_file, sections, table.index)?;
+            let values = words(elf_file, elf_file.section_data(section)?);
+            let mut entries: Vec<String> = values
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| {
+                    targets
+                        .get(&((i * word) as u64))
+                        .cloned()
+                        .unwrap_or_else(|| format!("{:#x}", value))
+                })
+                .collect();
+            // The linker copies .ctors and .dtors backwards into the
+            // arrays, and fini arrays run backwards, so only the entries
+            // of one or the other run from last to first.
+            if table.legacy != (kind == Kind::Fini) {
+                entries.reverse();
+            }
+
+            let priority = table
+                .priority
+                .map_or_else(|| "default".to_string(), |p| p.to_string());
+            println!(
+                "  {:>8} {:<20} {}",
+                priority,
+                table.name,
+                entries.join(", ")
+            );
+        }
+        println!();
+    }
+
+    if tables.iter().any(|t| t.priority.is_some_and(|p| p <= 100)) {
+        println!("Priorities up to 100 are reserved for the implementation.");
+        println!();
+    }
+    Ok(())
+}
+
+/// The addresses in a linked array, with relocated entries taken from the
+/// addends of their dynamic relocations when the file leaves zeroes.
+fn linked_array(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    address: u64,
+    size: u64,
+) -> Result<Vec<u64>> {
+    let Some(offset) = elf_file.vaddr_to_offset(address)? else {
+        return Ok(Vec::new());
+    };
+    let data = elf_file.data();
+    let end = offset.saturating_add(size).min(data.len() as u64);
+    let mut values = words(elf_file, &data[offset.min(end) as usize..end as usize]);
+
+    if values.contains(&0) {
+        let word = if elf_file.is_64() { 8 } else { 4 };
+        for rel in sections
+            .iter()
+            .filter(|s| s.is_alloc() && ElfFile::is_relocation_section(s))
+        {
+            for reloc in elf_file.decoded_relocations(rel)? {
+                let slot = reloc.r_offset.wrapping_sub(address) / word;
+                if reloc.r_offset >= address
+                    && let Some(value) = values.get_mut(slot as usize)
+                    && *value == 0
+                    && relocation::is_relative(elf_file, reloc.r_type)
+                {
+                    *value = reloc.r_addend.unwrap_or(0) as u64;
+                }
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Shows the initialization and termination functions of a linked file in
+/// the order the dynamic loader calls them. The priorities are gone by
+/// then: the linker sorted the arrays by them.
+fn print_linked(elf_file: &ElfFile, sections: &[SectionHeader]) -> Result<()> {
+    let dynamic = elf_file.dynamic_entries()?;
+    let value = |tag| dynamic.iter().find(|e| e.d_tag == tag).map(|e| e.d_val);
+    let names = FunctionNames::new(elf_file, sections)?;
+    let array = |tag, size_tag| -> Result<Vec<u64>> {
+        match (value(tag), value(size_tag)) {
+            (Some(address), Some(size)) => linked_array(elf_file, sections, address, size),
+            _ => Ok(Vec::new()),
+        }
+    };
+
+    let mut init = Vec::new();
+    for (i, address) in array(DynamicTag::PREINIT_ARRAY, DynamicTag::PREINIT_ARRAYSZ)?
+        .into_iter()
+        .enumerate()
+    {
+        init.push((format!("DT_PREINIT_ARRAY[{}]", i), address));
+    }
+    if let Some(address) = value(DynamicTag::INIT) {
+        init.push(("DT_INIT".to_string(), address));
+    }
+    for (i, address) in array(DynamicTag::INIT_ARRAY, DynamicTag::INIT_ARRAYSZ)?
+        .into_iter()
+        .enumerate()
+    {
+        init.push((format!("DT_INIT_ARRAY[{}]", i), address));
+    }
+
+    let mut fini: Vec<(String, u64)> = array(DynamicTag::FINI_ARRAY, DynamicTag::FINI_ARRAYSZ)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, address)| (format!("DT_FINI_ARRAY[{}]", i), address))
+        .rev()
+        .collect();
+    if let Some(address) = value(DynamicTag::FINI) {
+        fini.push(("DT_FINI".to_string(), address));
+    }
+
+    if init.is_empty() && fini.is_empty() {
+        println!("The file has no initialization or termination functions in its dynamic section.");
+        return Ok(());
+    }
+    for (title, functions) in [("Initialization", init), ("Termination", fini)] {
+        if functions.is_empty() {
+            continue;
+        }
+        println!("{} order:", title);
+        for (source, address) in functions {
+            println!("  {:<20} {}", source, names.describe(address));
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Reports the order constructors and destructors run in: for relocatable
+/// objects, from the priority suffixes of their `.init_array.NNNNN` and
+/// `.ctors.NNNNN` style sections, and for linked files from the arrays in
+/// the dynamic section.
+pub fn print_init_order(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    if elf_file.elf_type() == ElfType::REL {
+        print_tables(elf_file, &sections)
+    } else {
+        print_linked(elf_file, &sections)
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use serde_json::{Map, Value, json};
+use std::io::{self, BufWriter, Write};
+
+use crate::elf::ElfFile;
+use crate::formats::Format;
+use crate::section::SectionType;
+use crate::strtab::SymbolTable;
+use crate::validate;
+
+/// Walks the file handing every record (header, section, segment, symbol,
+/// relocation and security finding) to `emit` as soon as it has been parsed.
+/// Each record carries a "type" key naming what it describes.
+fn walk(elf_file: &ElfFile, path: &str, emit: &mut dyn FnMut(Value) -> Result<()>) -> Result<()> {
+    let ident = elf_file.ident();
+    let header = elf_file.header();
+    emit(json!({
+        "type": "header",
+        "file": path,
+        "class": if ident.class == 2 { "ELF64" } else { "ELF32" },
+        "data": if ident.data == 1 { "little" } else { "big" },
+        "elf_type": elf_file.type_description(),
+        "machine": header.e_machine.to_string(),
+        "machine_number": header.e_machine as u16,
+        "entry": header.e_entry,
+        "flags": header.e_flags,
+    }))?;
+
+    let sections = elf_file.section_headers()?;
+    let name_of = |index: usize| {
+        sections
+            .get(index)
+            .and_then(|s| elf_file.section_name(&sections, s).ok())
+            .unwrap_or("")
+    };
+
+    for (index, section) in sections.iter().enumerate() {
+        emit(json!({
+            "type": "section",
+            "index": index,
+            "name": name_of(index),
+            "section_type": section.sh_type.to_string(),
+            "flags": section.sh_flags,
+            "address": section.sh_addr,
+            "offset": section.sh_offset,
+            "size": section.sh_size,
+            "link": section.sh_link,
+            "info": section.sh_info,
+            "alignment": section.sh_addralign,
+            "entry_size": section.sh_entsize,
+        }))?;
+    }
+
+    for (index, segment) in elf_file.program_headers()?.iter().enumerate() {
+        emit(json!({
+            "type": "segment",
+            "index": index,
+            "segment_type": segment.p_type.to_string(),
+            "flags": segment.flags_string(),
+            "offset": segment.p_offset,
+            "virtual_address": segment.p_vaddr,
+            "physical_address": segment.p_paddr,
+            "file_size": segment.p_filesz,
+            "memory_size": segment.p_memsz,
+            "alignment": segment.p_align,
+        }))?;
+    }
+
+    for (index, section) in sections.iter().enumerate() {
+        if section.sh_type != SectionType::SYMTAB && section.sh_type != SectionType::DYNSYM {
+            continue;
+        }
+
+        for (sym_index, symbol) in elf_file.symbols(section)?.enumerate() {
+            emit(json!({
+                "type": "symbol",
+                "table": name_of(index),
+                "index": sym_index,
+                "name": elf_file.symbol_name(&sections, section, &symbol).unwrap_or(""),
+                "value": symbol.st_value,
+                "size": symbol.st_size,
+                "symbol_type": symbol.symbol_type().to_string(),
+                "binding": symbol.binding().to_string(),
+                "visibility": symbol.visibility().to_string(),
+                "section_index": symbol.st_shndx,
+            }))?;
+        }
+    }
+
+    for (index, section) in sections.iter().enumerate() {
+        if !ElfFile::is_relocation_section(section) {
+            continue;
+        }
+
+        // Symbols referenced by the relocations come from the linked table.
+        let symbols = SymbolTable::linked(elf_file, &sections, section)?;
+
+        for relocation in elf_file.decoded_relocations(section)? {
+            let symbol_name = symbols.name(relocation.r_sym as usize).unwrap_or("");
+
+            emit(json!({
+                "type": "relocation",
+                "section": name_of(index),
+                "offset": relocation.r_offset,
+                "relocation_type": relocation.r_type,
+                "symbol_index": relocation.r_sym,
+                "symbol": symbol_name,
+                "addend": relocation.r_addend,
+            }))?;
+        }
+    }
+
+    for finding in validate::security(elf_file)? {
+        emit(json!({
+            "type": "finding",
+            "rule": finding.rule,
+            "level": finding.level.to_string(),
+            "message": finding.message,
+            "region": finding.region.map(|(offset, length)| json!({
+                "offset": offset,
+                "length": length,
+            })),
+        }))?;
+    }
+
+    Ok(())
+}
+
+/// Prints one JSON record per line as each one is produced, so that huge
+/// tables never need to be held in memory as a whole document. Every record
+/// names its file, so that the lines of several files can be told apart.
+pub fn print_ndjson(elf_file: &ElfFile, path: &str) -> Result<()> {
+    let mut out = BufWriter::new(io::stdout().lock());
+    walk(elf_file, path, &mut |mut record| {
+        record["file"] = json!(path);
+        serde_json::to_writer(&mut out, &record)?;
+        out.write_all(b"\n")?;
+        Ok(())
+    })?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Builds a single JSON document with the header and one array per kind of
+/// record.
+pub fn document(elf_file: &ElfFile, path: &str) -> Result<Value> {
+    let mut document = Map::new();
+    for key in ["sections", "segments", "symbols", "relocations", "findings"] {
+        document.insert(key.to_string(), Value::Array(Vec::new()));
+    }
+
+    walk(elf_file, path, &mut |mut record| {
+        let kind = record
+            .as_object_mut()
+            .and_then(|r| r.remove("type"))
+            .and_then(|t| t.as_str().map(String::from))
+            .unwrap_or_default();
+
+        if kind == "header" {
+            document.insert(kind, record);
+        } else if let Some(Value::Array(list)) = document.get_mut(&format!("{}s", kind)) {
+            list.push(record);
+        }
+        Ok(())
+    })?;
+
+    Ok(Value::Object(document))
+}
+
+/// Builds the one record there is for a file in another format: what that
+/// format is. As an ndjson line it is typed like the other records.
+pub fn other_format(format: &Format, path: &str, ndjson: bool) -> Value {
+    let mut record = json!({
+        "file": path,
+        "format": format.kind,
+        "description": format.description,
+        "error": format.message(),
+    });
+    if ndjson {
+        record["type"] = json!("format");
+    }
+    record
+}
@@ -0,0 +1,19 @@
+//! The ELF parser behind readelf-rs, for tools that want to read ELF files
+//! themselves: `elf::ElfFile` to open or parse one, the typed constants of
+//! `consts`, the diagnostics of `warning`, and the `plugin` registry through
+//! which decoders for vendor sections, notes and dynamic tags are added.
+
+pub mod consts;
+pub mod dynamic;
+pub mod elf;
+pub mod emachine;
+pub mod filter;
+pub mod notes;
+pub mod plugin;
+pub mod ppc64;
+pub mod relocation;
+pub mod section;
+pub mod segment;
+pub mod strtab;
+pub mod symbol;
+pub mod warning;
@@ -0,0 +1,174 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::dynamic::DynamicTag;
+use crate::elf::{ElfFile, ElfType};
+use crate::segment::SegmentType;
+use crate::validate;
+
+/// How the file gets loaded.
+pub fn linkage_kind(elf_file: &ElfFile, interpreter: Option<&str>) -> Result<&'static str> {
+    let has_dynamic = elf_file
+        .program_headers()?
+        .iter()
+        .any(|s| s.p_type == SegmentType::DYNAMIC);
+    let pie = elf_file.is_pie()?;
+
+    Ok(match (elf_file.elf_type(), interpreter) {
+        (ElfType::REL, _) => "relocatable object (not linked yet)",
+        (ElfType::CORE, _) => "core dump",
+        (ElfType::EXEC, Some(_)) => "dynamic executable",
+        (ElfType::EXEC, None) if has_dynamic => "static executable with a dynamic section",
+        (ElfType::EXEC, None) => "static executable",
+        (ElfType::DYN, Some(_)) if pie => "dynamic position-independent executable (PIE)",
+        (ElfType::DYN, None) if pie => "static position-independent executable (static-PIE)",
+        (ElfType::DYN, _) => "shared library",
+        _ => "unknown",
+    })
+}
+
+/// The first of `needles` that occurs anywhere in `haystack`.
+fn find_any<'n>(haystack: &[u8], needles: &[&'n str]) -> Option<&'n str> {
+    needles.iter().copied().find(|needle| {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle.as_bytes())
+    })
+}
+
+/// Guesses which C library the file was built against, returning the
+/// library and the evidence for it.
+pub fn libc_flavor(
+    elf_file: &ElfFile,
+    interpreter: Option<&str>,
+) -> Result<Option<(&'static str, String)>> {
+    if let Some(interp) = interpreter {
+        let flavor = if interp.contains("ld-musl") {
+            Some("musl")
+        } else if interp.contains("ld-linux") || interp.contains("ld64.so") {
+            Some("glibc")
+        } else if interp.contains("ld-uClibc") {
+            Some("uClibc")
+        } else if interp.starts_with("/system/bin/linker") {
+            Some("bionic")
+        } else {
+            None
+        };
+        if let Some(flavor) = flavor {
+            return Ok(Some((flavor, format!("interpreter {}", interp))));
+        }
+    }
+
+    for needed in elf_file.dynamic_strings(DynamicTag::NEEDED)? {
+        if needed == "libc.so.6" {
+            return Ok(Some(("glibc", format!("DT_NEEDED {}", needed))));
+        }
+        if needed.starts_with("libc.musl-") {
+            return Ok(Some(("musl", format!("DT_NEEDED {}", needed))));
+        }
+    }
+
+    // Statically linked binaries carry the library itself, so look for
+    // strings that only one of them contains.
+    let data = elf_file.data();
+    if let Some(found) = find_any(data, &["GLIBC_2.", "glibc-ld.so.cache", "GNU C Library"]) {
+        return Ok(Some(("glibc", format!("string \"{}\"", found))));
+    }
+    if let Some(found) = find_any(data, &["No error information", "musl libc"]) {
+        return Ok(Some(("musl", format!("string \"{}\"", found))));
+    }
+
+    Ok(None)
+}
+
+/// Summarises whether the file is statically or dynamically linked, which
+/// interpreter it asks for and which C library it uses.
+pub fn print_linkage(elf_file: &ElfFile) -> Result<()> {
+    let interpreter = elf_file.interpreter()?;
+
+    println!("Linkage:");
+    println!("  Type:        {}", linkage_kind(elf_file, interpreter)?);
+    println!("  Interpreter: {}", interpreter.unwrap_or("none"));
+    match libc_flavor(elf_file, interpreter)? {
+        Some((flavor, evidence)) => println!("  C library:   {} (from {})", flavor, evidence),
+        None => println!("  C library:   unknown"),
+    }
+
+    Ok(())
+}
+
+/// Whether the interpreter can be found on this system, and whether it is
+/// an ELF file for the same machine.
+fn check_local_interpreter(elf_file: &ElfFile, interpreter: &str) -> String {
+    let path = Path::new(interpreter);
+    let Ok(resolved) = path.canonicalize() else {
+        return "not found on this system".to_string();
+    };
+    let location = if resolved == path {
+        "found on this system".to_string()
+    } else {
+        format!("found on this system at {}", resolved.display())
+    };
+
+    // e_machine is at the same offset in both classes; as for the file
+    // itself, it is read in native byte order.
+    let mut header = [0u8; 20];
+    let read = File::open(&resolved).and_then(|mut file| file.read_exact(&mut header));
+    if read.is_err() || header[..4] != *b"\x7fELF" {
+        return format!("{}, but it is not an ELF file", location);
+    }
+    let machine = u16::from_ne_bytes([header[18], header[19]]);
+    if machine != elf_file.machine() as u16 {
+        return format!(
+            "{}, but it is for machine {} rather than {} ({})",
+            location,
+            machine,
+            elf_file.machine() as u16,
+            elf_file.machine()
+        );
+    }
+    location
+}
+
+/// Prints the program interpreter the file asks for, with the problems
+/// `--validate` would find with it. With `check`, also looks whether the
+/// interpreter is present on this system.
+pub fn print_interpreter(elf_file: &ElfFile, check: bool) -> Result<()> {
+    match elf_file.interpreter()? {
+        Some(interpreter) => {
+            println!("Program interpreter: {}", interpreter);
+            if check {
+                println!("  {}", check_local_interpreter(elf_file, interpreter));
+            }
+        }
+        None => {
+            // Only linked files have a PT_INTERP to load it from.
+            let sections = elf_file.section_headers()?;
+            let section = sections.iter().find(|s| {
+                elf_file
+                    .section_name(&sections, s)
+                    .is_ok_and(|n| n == ".interp")
+            });
+            match section {
+                Some(section) => {
+                    let data = elf_file.section_data(section)?;
+                    let len = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+                    println!(
+                        "Program interpreter: none (.interp holds {})",
+                        String::from_utf8_lossy(&data[..len])
+                    );
+                }
+                None => println!("Program interpreter: none"),
+            }
+        }
+    }
+    for finding in validate::interpreter(elf_file)? {
+        println!("  !! {}", finding);
+    }
+    println!();
+
+    Ok(())
+}
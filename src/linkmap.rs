@@ -0,0 +1,406 @@
+//! Cross-checking a binary against the map file its linker wrote with
+//! `-Map`: GNU ld and gold write one format, lld a columnar one of its own.
+
+use anyhow::{Context, Result, bail};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::elf::ElfFile;
+use crate::section::{SHF_ALLOC, SHN_UNDEF, SectionType};
+use crate::strtab::SymbolTable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MapFormat {
+    Gnu,
+    Lld,
+}
+
+impl std::fmt::Display for MapFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MapFormat::Gnu => "GNU ld or gold",
+            MapFormat::Lld => "lld",
+        })
+    }
+}
+
+/// An input section placed in an output section, and the file it came from,
+/// which is empty for what the linker made itself.
+struct InputSection {
+    name: String,
+    size: u64,
+    file: String,
+}
+
+struct OutputSection {
+    name: String,
+    address: u64,
+    size: u64,
+    inputs: Vec<InputSection>,
+}
+
+struct MapSymbol {
+    name: String,
+    address: u64,
+}
+
+struct LinkerMap {
+    format: MapFormat,
+    sections: Vec<OutputSection>,
+    symbols: Vec<MapSymbol>,
+}
+
+fn parse_hex(s: &str) -> Option<u64> {
+    u64::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+}
+
+/// The symbol a symbol line defines, which may be an assignment such as
+/// `__bss_start = .`; `None` for assignments to `.` and `PROVIDE`s.
+fn symbol_name(rest: &str) -> Option<&str> {
+    let name = rest.split_once(" = ").map_or(rest, |(name, _)| name).trim();
+    (!name.is_empty() && name != "." && !name.contains(char::is_whitespace) && !name.contains('('))
+        .then_some(name)
+}
+
+impl LinkerMap {
+    fn parse(text: &str) -> Result<LinkerMap> {
+        if let Some(header) = text
+            .lines()
+            .find(|l| l.contains(" Out ") && l.contains(" In ") && l.contains("Symbol"))
+        {
+            return Ok(LinkerMap::parse_lld(text, header));
+        }
+        if text
+            .lines()
+            .any(|l| l == "Linker script and memory map" || l == "Memory map")
+        {
+            return Ok(LinkerMap::parse_gnu(text));
+        }
+        bail!("Not a linker map from GNU ld, gold or lld")
+    }
+
+    fn output(&mut self, name: &str, address: u64, size: u64) {
+        self.sections.push(OutputSection {
+            name: name.to_string(),
+            address,
+            size,
+            inputs: Vec::new(),
+        });
+    }
+
+    fn input(&mut self, name: &str, size: u64, file: &str) {
+        if let Some(output) = self.sections.last_mut() {
+            output.inputs.push(InputSection {
+                name: name.to_string(),
+                size,
+                file: file.to_string(),
+            });
+        }
+    }
+
+    /// GNU ld and gold put output sections at the start of the line, input
+    /// sections one space in and symbols further in, each name followed by
+    /// its address and size: on the next line when the name is too long.
+    fn parse_gnu(text: &str) -> LinkerMap {
+        let mut map = LinkerMap {
+            format: MapFormat::Gnu,
+            sections: Vec::new(),
+            symbols: Vec::new(),
+        };
+        let lines = text
+            .lines()
+            .skip_while(|l| *l != "Linker script and memory map" && *l != "Memory map")
+            .skip(1);
+        // A section whose name was on a line of its own, and whether it is
+        // an input section.
+        let mut pending: Option<(String, bool)> = None;
+        for line in lines {
+            if line.starts_with("OUTPUT(") {
+                break;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.is_empty() {
+                pending = None;
+                continue;
+            }
+            let indent = line.len() - line.trim_start().len();
+
+            let (name, input, numbers) = match pending.take() {
+                Some((name, input)) => (name, input, &fields[..]),
+                None if indent > 1 => {
+                    // A symbol, unless it is the address and size of a
+                    // section we did not keep, such as gold's "** fill".
+                    if let Some(address) = parse_hex(fields[0])
+                        && fields.get(1).and_then(|f| parse_hex(f)).is_none()
+                        && let Some(name) = symbol_name(line.trim_start()[fields[0].len()..].trim())
+                    {
+                        map.symbols.push(MapSymbol {
+                            name: name.to_string(),
+                            address,
+                        });
+                    }
+                    continue;
+                }
+                None => {
+                    let name = fields[0];
+                    if name.starts_with('*')
+                        || (indent == 0
+                            && matches!(name, "LOAD" | "START" | "END" | "INSERT" | "OUTPUT"))
+                    {
+                        continue;
+                    }
+                    if fields.len() == 1 {
+                        pending = Some((name.to_string(), indent == 1));
+                        continue;
+                    }
+                    (name.to_string(), indent == 1, &fields[1..])
+                }
+            };
+            let (Some(address), Some(size)) = (
+                numbers.first().and_then(|f| parse_hex(f)),
+                numbers.get(1).and_then(|f| parse_hex(f)),
+            ) else {
+                continue;
+            };
+            if input {
+                let file = numbers.get(2..).unwrap_or(&[]).join(" ");
+                map.input(&name, size, &file);
+            } else {
+                map.output(&name, address, size);
+            }
+        }
+        map
+    }
+
+    /// lld writes a table whose columns are addresses in hexadecimal, then
+    /// the output section, input section or symbol, each further indented
+    /// under the heading for it.
+    fn parse_lld(text: &str, header: &str) -> LinkerMap {
+        let mut map = LinkerMap {
+            format: MapFormat::Lld,
+            sections: Vec::new(),
+            symbols: Vec::new(),
+        };
+        let column = |heading: &str| header.find(heading).unwrap_or(usize::MAX);
+        let (in_column, symbol_column) = (column(" In ") + 1, column("Symbol"));
+        let header_words: Vec<&str> = header.split_whitespace().collect();
+        // "VMA LMA Size Align" since lld 9, "Address Size Align" before.
+        let numbers = header_words.iter().position(|w| *w == "Out").unwrap_or(3);
+        let size_index = header_words.iter().position(|w| *w == "Size").unwrap_or(1);
+
+        for line in text.lines().skip_while(|l| *l != header).skip(1) {
+            let mut rest = line;
+            let mut values = Vec::new();
+            for _ in 0..numbers {
+                rest = rest.trim_start();
+                let end = rest.find(' ').unwrap_or(rest.len());
+                values.push(parse_hex(&rest[..end]));
+                rest = &rest[end..];
+            }
+            let (Some(Some(address)), Some(Some(size))) = (values.first(), values.get(size_index))
+            else {
+                continue;
+            };
+            let column = line.len() - rest.trim_start().len();
+            let rest = rest.trim();
+            if column >= symbol_column {
+                if let Some(name) = symbol_name(rest) {
+                    map.symbols.push(MapSymbol {
+                        name: name.to_string(),
+                        address: *address,
+                    });
+                }
+            } else if column >= in_column {
+                // "file.o:(.text.main)" or "libfoo.a(bar.o):(.text)"
+                if let Some((file, section)) = rest.rsplit_once(":(") {
+                    let file = if file == "<internal>" { "" } else { file };
+                    map.input(section.trim_end_matches(')'), *size, file);
+                }
+            } else {
+                map.output(rest, *address, *size);
+            }
+        }
+        map
+    }
+}
+
+/// Checks the sections and symbols of a binary against the linker map it
+/// was linked with, and shows the object files each section was made from.
+pub fn print_map_check(elf_file: &ElfFile, path: &Path) -> Result<()> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read linker map {}", path.display()))?;
+    let map = LinkerMap::parse(&text)
+        .with_context(|| format!("Failed to parse linker map {}", path.display()))?;
+    let sections = elf_file.section_headers()?;
+
+    let inputs: usize = map.sections.iter().map(|s| s.inputs.len()).sum();
+    let mut files: Vec<&str> = map
+        .sections
+        .iter()
+        .flat_map(|s| &s.inputs)
+        .map(|i| i.file.as_str())
+        .filter(|f| !f.is_empty())
+        .collect();
+    files.sort_unstable();
+    files.dedup();
+    println!(
+        "Linker map {} ({}): {} output sections, {} input sections from {} files, {} symbols",
+        path.display(),
+        map.format,
+        map.sections.len(),
+        inputs,
+        files.len(),
+        map.symbols.len()
+    );
+
+    println!();
+    println!("Section check:");
+    let mut problems = 0;
+    let mut checked = 0;
+    for output in map.sections.iter().filter(|s| s.size > 0) {
+        checked += 1;
+        let Some(section) = sections
+            .iter()
+            .find(|s| elf_file.section_name(&sections, s).ok() == Some(&output.name))
+        else {
+            println!(
+                "  {}: in the map but not in the file (stripped or removed after linking?)",
+                output.name
+            );
+            problems += 1;
+            continue;
+        };
+        if section.sh_addr != output.address {
+            println!(
+                "  {}: at {:#x} in the map but at {:#x} in the file",
+                output.name, output.address, section.sh_addr
+            );
+            problems += 1;
+        }
+        if section.sh_size != output.size {
+            println!(
+                "  {}: {:#x} bytes in the map but {:#x} in the file",
+                output.name, output.size, section.sh_size
+            );
+            problems += 1;
+        }
+    }
+    for section in sections.iter().filter(|s| s.sh_flags & SHF_ALLOC != 0) {
+        let name = elf_file.section_name(&sections, section)?;
+        if !map.sections.iter().any(|s| s.name == name) {
+            println!("  {}: in the file but not in the map", name);
+            problems += 1;
+        }
+    }
+    if problems == 0 {
+        println!(
+            "  All {} non-empty sections of the map match the file.",
+            checked
+        );
+    }
+
+    println!();
+    println!("Symbol check:");
+    let mut addresses: HashMap<&str, Vec<u64>> = HashMap::new();
+    let mut undefined = HashSet::new();
+    if let Some(table) = elf_file.symbol_table(&sections) {
+        let symbols = SymbolTable::new(elf_file, &sections, table)?;
+        for (index, symbol) in symbols.iter().enumerate() {
+            let name = symbols.name(index)?;
+            // Versioned names in .dynsym are written "name@VERSION".
+            let name = name.split('@').next().unwrap_or(name);
+            if symbol.st_shndx == SHN_UNDEF {
+                undefined.insert(name);
+            } else {
+                addresses.entry(name).or_default().push(symbol.st_value);
+            }
+        }
+    }
+    let mut matching = 0;
+    let mut imports = 0;
+    let mut differing = Vec::new();
+    let mut missing = Vec::new();
+    for symbol in &map.symbols {
+        let name = symbol.name.split('@').next().unwrap_or(&symbol.name);
+        match addresses.get(name) {
+            Some(values) if values.contains(&symbol.address) => matching += 1,
+            Some(values) => differing.push((symbol, values)),
+            // GNU ld lists the functions a binary imports at their PLT
+            // entries.
+            None if undefined.contains(name) => imports += 1,
+            None => missing.push(symbol.name.as_str()),
+        }
+    }
+    println!(
+        "  {} symbols in the map: {} match, {} are imports, {} at a different address, {} not in the symbol table",
+        map.symbols.len(),
+        matching,
+        imports,
+        differing.len(),
+        missing.len()
+    );
+    for (symbol, values) in &differing {
+        let values: Vec<String> = values.iter().map(|v| format!("{:#x}", v)).collect();
+        println!(
+            "    {}: {:#x} in the map but {} in the file",
+            symbol.name,
+            symbol.address,
+            values.join(", ")
+        );
+    }
+    if !missing.is_empty() {
+        println!("  Not in the symbol table: {}", missing.join(", "));
+        if !sections.iter().any(|s| s.sh_type == SectionType::SYMTAB) {
+            println!("  The file has no .symtab: it has been stripped since it was linked.");
+        }
+    }
+
+    println!();
+    println!("Sections by originating file:");
+    for output in map.sections.iter().filter(|s| s.size > 0) {
+        let mut by_file: Vec<(&str, u64, Vec<&str>)> = Vec::new();
+        for input in output.inputs.iter().filter(|i| i.size > 0) {
+            match by_file.iter_mut().find(|(file, _, _)| *file == input.file) {
+                Some(entry) => {
+                    entry.1 += input.size;
+                    entry.2.push(&input.name);
+                }
+                None => by_file.push((&input.file, input.size, vec![&input.name])),
+            }
+        }
+        if by_file.is_empty() {
+            continue;
+        }
+        by_file.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        // Merged sections such as .comment are smaller than their inputs.
+        let total: u64 = by_file.iter().map(|(_, size, _)| size).sum();
+        if total > output.size {
+            println!(
+                "  {} ({:#x}, {} bytes, merged from {}):",
+                output.name, output.address, output.size, total
+            );
+        } else {
+            println!(
+                "  {} ({:#x}, {} bytes):",
+                output.name, output.address, output.size
+            );
+        }
+        for (file, size, names) in by_file {
+            let file = if file.is_empty() { "<linker>" } else { file };
+            let names = match names.as_slice() {
+                [name] if *name == output.name => String::new(),
+                [name] => format!(" ({})", name),
+                names => format!(" ({} input sections)", names.len()),
+            };
+            println!(
+                "    {:>10} {:>5.1}%  {}{}",
+                size,
+                size as f64 * 100.0 / total.max(output.size) as f64,
+                file,
+                names
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,679 @@
+//! The sections LLVM-based compilers add to objects: embedded bitcode and
+//! the command line it was built with, the address-significance table that
+//! lets linkers fold identical code safely, offloading images for GPUs, and
+//! the symbol table LTO bitcode carries for linkers.
+
+use anyhow::{Result, bail};
+
+use crate::elf::ElfFile;
+use crate::section::{SectionHeader, SectionType};
+use crate::strtab::SymbolTable;
+
+/// Bitcode starts with "BC" 0xC0DE, or with a wrapper header pointing at it.
+const BITCODE_MAGIC: &[u8] = b"BC\xc0\xde";
+const WRAPPER_MAGIC: u32 = 0x0b17_c0de;
+const OFFLOAD_MAGIC: &[u8] = b"\x10\xff\x10\xad";
+
+const IDENTIFICATION_BLOCK: u64 = 13;
+const MODULE_BLOCK: u64 = 8;
+const STRTAB_BLOCK: u64 = 23;
+const SYMTAB_BLOCK: u64 = 25;
+
+/// How an operand of an abbreviated record is encoded.
+#[derive(Debug, Clone, Copy)]
+enum Operand {
+    Literal(u64),
+    Fixed(u32),
+    Vbr(u32),
+    Array,
+    Char6,
+    Blob,
+}
+
+/// A record of a bitstream block: its code, operands and blob.
+struct Record<'a> {
+    code: u64,
+    operands: Vec<u64>,
+    blob: Option<&'a [u8]>,
+}
+
+impl Record<'_> {
+    /// The operands as a string, for the records that hold one a
+    /// character per operand.
+    fn text(&self) -> String {
+        self.operands.iter().map(|&c| c as u8 as char).collect()
+    }
+}
+
+/// Reads an LLVM bitstream, least significant bit first.
+struct Bits<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn remaining(&self) -> usize {
+        (self.data.len() * 8).saturating_sub(self.position)
+    }
+
+    fn read(&mut self, width: u32) -> Option<u64> {
+        if width > 64 || self.remaining() < width as usize {
+            return None;
+        }
+        let mut value = 0;
+        for bit in 0..width as usize {
+            let position = self.position + bit;
+            let set = self.data[position / 8] >> (position % 8) & 1;
+            value |= (set as u64) << bit;
+        }
+        self.position += width as usize;
+        Some(value)
+    }
+
+    fn vbr(&mut self, width: u32) -> Option<u64> {
+        let high = 1 << (width - 1);
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let chunk = self.read(width)?;
+            value |= (chunk & (high - 1)).checked_shl(shift)?;
+            if chunk & high == 0 {
+                return Some(value);
+            }
+            shift += width - 1;
+        }
+    }
+
+    fn align32(&mut self) {
+        self.position = self.position.div_ceil(32) * 32;
+    }
+
+    fn operand(&mut self, operand: Operand) -> Option<u64> {
+        match operand {
+            Operand::Literal(value) => Some(value),
+            Operand::Fixed(width) => self.read(width),
+            Operand::Vbr(width) => self.vbr(width),
+            Operand::Char6 => {
+                let value = self.read(6)? as u8;
+                Some(match value {
+                    0..=25 => b'a' + value,
+                    26..=51 => b'A' + value - 26,
+                    52..=61 => b'0' + value - 52,
+                    62 => b'.',
+                    _ => b'_',
+                } as u64)
+            }
+            Operand::Array | Operand::Blob => None,
+        }
+    }
+
+    fn abbreviation(&mut self) -> Option<Vec<Operand>> {
+        let count = self.vbr(5)?;
+        let mut operands = Vec::new();
+        for _ in 0..count {
+            if self.read(1)? == 1 {
+                operands.push(Operand::Literal(self.vbr(8)?));
+                continue;
+            }
+            operands.push(match self.read(3)? {
+                1 => Operand::Fixed(self.vbr(5)? as u32),
+                2 => Operand::Vbr(self.vbr(5)? as u32),
+                3 => Operand::Array,
+                4 => Operand::Char6,
+                5 => Operand::Blob,
+                _ => return None,
+            });
+        }
+        Some(operands)
+    }
+
+    fn abbreviated(&mut self, abbreviation: &[Operand]) -> Option<Record<'a>> {
+        let mut values = Vec::new();
+        let mut blob = None;
+        let mut operands = abbreviation.iter();
+        while let Some(&operand) = operands.next() {
+            match operand {
+                Operand::Array => {
+                    let element = *operands.next()?;
+                    for _ in 0..self.vbr(6)? {
+                        values.push(self.operand(element)?);
+                    }
+                }
+                Operand::Blob => {
+                    let length = self.vbr(6)? as usize;
+                    self.align32();
+                    let start = self.position / 8;
+                    blob = Some(self.data.get(start..start.checked_add(length)?)?);
+                    self.position += length * 8;
+                    self.align32();
+                }
+                operand => values.push(self.operand(operand)?),
+            }
+        }
+        if values.is_empty() {
+            return None;
+        }
+        let code = values.remove(0);
+        Some(Record {
+            code,
+            operands: values,
+            blob,
+        })
+    }
+
+    /// The records of the block just entered, skipping the blocks nested
+    /// in it. Abbreviations from BLOCKINFO are not known, which the blocks
+    /// looked at here do not use.
+    fn records(&mut self, width: u32, end: usize) -> Option<Vec<Record<'a>>> {
+        let mut abbreviations = Vec::new();
+        let mut records = Vec::new();
+        while self.position < end {
+            match self.read(width)? {
+                0 => {
+                    self.align32();
+                    break;
+                }
+                1 => {
+                    self.vbr(8)?;
+                    self.vbr(4)?;
+                    self.align32();
+                    let words = self.read(32)? as usize;
+                    self.position += words * 32;
+                }
+                2 => abbreviations.push(self.abbreviation()?),
+                3 => {
+                    let code = self.vbr(6)?;
+                    let count = self.vbr(6)?;
+                    let operands = (0..count).map(|_| self.vbr(6)).collect::<Option<_>>()?;
+                    records.push(Record {
+                        code,
+                        operands,
+                        blob: None,
+                    });
+                }
+                id => {
+                    let abbreviation = abbreviations.get(id as usize - 4)?.clone();
+                    records.push(self.abbreviated(&abbreviation)?);
+                }
+            }
+        }
+        Some(records)
+    }
+}
+
+/// What a bitcode file says about itself, from the blocks at its top level.
+#[derive(Default)]
+struct Bitcode<'a> {
+    producer: Option<String>,
+    epoch: Option<u64>,
+    triple: Option<String>,
+    data_layout: Option<String>,
+    source: Option<String>,
+    modules: usize,
+    symtab: Option<&'a [u8]>,
+    strtab: Option<&'a [u8]>,
+}
+
+/// The bitcode in `data`, going through a wrapper header if there is one.
+fn bitcode_data(data: &[u8]) -> Option<&[u8]> {
+    let word = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    };
+    if word(0) == Some(WRAPPER_MAGIC) {
+        let offset = word(8)? as usize;
+        let size = word(12)? as usize;
+        return data.get(offset..offset.checked_add(size)?);
+    }
+    data.starts_with(BITCODE_MAGIC).then_some(data)
+}
+
+fn parse_bitcode(data: &[u8]) -> Result<Bitcode<'_>> {
+    let Some(data) = bitcode_data(data) else {
+        bail!("not LLVM bitcode");
+    };
+    let mut bits = Bits { data, position: 32 };
+    let mut bitcode = Bitcode::default();
+    let truncated = || anyhow::anyhow!("the bitcode is truncated or malformed");
+
+    // Top-level abbreviation IDs are 2 bits wide, and only blocks are
+    // allowed there.
+    while bits.remaining() >= 32 {
+        match bits.read(2).ok_or_else(truncated)? {
+            1 => {}
+            0 if bits.data[bits.position / 8..].iter().all(|&b| b == 0) => break,
+            _ => bail!("the bitcode has a record outside any block"),
+        }
+        let id = bits.vbr(8).ok_or_else(truncated)?;
+        let width = bits.vbr(4).ok_or_else(truncated)? as u32;
+        bits.align32();
+        let words = bits.read(32).ok_or_else(truncated)? as usize;
+        let end = bits.position + words * 32;
+        if !matches!(
+            id,
+            IDENTIFICATION_BLOCK | MODULE_BLOCK | STRTAB_BLOCK | SYMTAB_BLOCK
+        ) {
+            bits.position = end;
+            continue;
+        }
+        let records = bits.records(width, end).ok_or_else(truncated)?;
+        bits.position = end;
+
+        for record in records {
+            match (id, record.code) {
+                (IDENTIFICATION_BLOCK, 1) => bitcode.producer = Some(record.text()),
+                (IDENTIFICATION_BLOCK, 2) => bitcode.epoch = record.operands.first().copied(),
+                (MODULE_BLOCK, 2) => bitcode.triple = Some(record.text()),
+                (MODULE_BLOCK, 3) => bitcode.data_layout = Some(record.text()),
+                (MODULE_BLOCK, 16) => bitcode.source = Some(record.text()),
+                (STRTAB_BLOCK, 1) => bitcode.strtab = record.blob,
+                (SYMTAB_BLOCK, 1) => bitcode.symtab = record.blob,
+                _ => {}
+            }
+        }
+        if id == MODULE_BLOCK {
+            bitcode.modules += 1;
+        }
+    }
+    Ok(bitcode)
+}
+
+/// A symbol from the irsymtab of LTO bitcode.
+struct LtoSymbol {
+    name: String,
+    ir_name: String,
+    flags: u32,
+}
+
+/// The irsymtab LLVM stores in bitcode for linkers, so that they need not
+/// load the modules to resolve symbols: its version, producer, target
+/// triple, source file name and symbols.
+struct IrSymtab {
+    version: u32,
+    producer: String,
+    triple: String,
+    source: String,
+    symbols: Vec<LtoSymbol>,
+}
+
+fn parse_irsymtab(symtab: &[u8], strtab: &[u8]) -> Option<IrSymtab> {
+    let word = |offset: usize| {
+        symtab
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    };
+    // Strings are (offset, size) into the string table; ranges are
+    // (offset, count) into the symbol table.
+    let string = |offset: usize| -> Option<String> {
+        let start = word(offset)? as usize;
+        let size = word(offset + 4)? as usize;
+        let bytes = strtab.get(start..start.checked_add(size)?)?;
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    };
+
+    // Header: Version, Producer, Modules, Comdats, Symbols, Uncommons,
+    // TargetTriple, SourceFileName, COFFLinkerOpts, DependentLibraries.
+    let version = word(0)?;
+    let symbols_offset = word(28)? as usize;
+    let symbols_count = word(32)? as usize;
+    let mut symbols = Vec::new();
+    for index in 0..symbols_count {
+        // Symbol: Name, IRName, ComdatIndex, Flags.
+        let offset = symbols_offset + index * 24;
+        symbols.push(LtoSymbol {
+            name: string(offset)?,
+            ir_name: string(offset + 8)?,
+            flags: word(offset + 20)?,
+        });
+    }
+    Some(IrSymtab {
+        version,
+        producer: string(4)?,
+        triple: string(44)?,
+        source: string(52)?,
+        symbols,
+    })
+}
+
+/// The irsymtab symbol flags, as `llvm-lto2 dump-symtab` names them.
+fn lto_flags(flags: u32) -> String {
+    const NAMES: [(u32, &str); 11] = [
+        (3, "undefined"),
+        (4, "weak"),
+        (5, "common"),
+        (6, "indirect"),
+        (7, "used"),
+        (8, "tls"),
+        (9, "may_omit"),
+        (10, "global"),
+        (11, "format_specific"),
+        (12, "unnamed_addr"),
+        (13, "executable"),
+    ];
+    let mut names = vec![match flags & 3 {
+        0 => "default",
+        1 => "hidden",
+        2 => "protected",
+        _ => "visibility 3",
+    }];
+    names.extend(
+        NAMES
+            .iter()
+            .filter(|&&(bit, _)| flags & (1 << bit) != 0)
+            .map(|&(_, name)| name),
+    );
+    names.join(" ")
+}
+
+/// The sections that hold bitcode: embedded with -fembed-bitcode or
+/// -C embed-bitcode, or the IR half of a fat LTO object.
+fn is_bitcode_section(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    section: &SectionHeader,
+) -> bool {
+    section.sh_type == SectionType::LLVM_LTO
+        || matches!(
+            elf_file.section_name(sections, section),
+            Ok(".llvmbc" | ".llvm.lto")
+        )
+}
+
+fn print_bitcode(data: &[u8]) {
+    let bitcode = match parse_bitcode(data) {
+        Ok(bitcode) => bitcode,
+        Err(e) => {
+            println!("    {:#}", e);
+            return;
+        }
+    };
+    if data.starts_with(&WRAPPER_MAGIC.to_le_bytes()) {
+        println!("    Wrapper:     bitcode wrapper header");
+    }
+    if let Some(producer) = &bitcode.producer {
+        match bitcode.epoch {
+            Some(epoch) => println!("    Producer:    {} (epoch {})", producer, epoch),
+            None => println!("    Producer:    {}", producer),
+        }
+    }
+    if bitcode.modules != 1 {
+        println!("    Modules:     {}", bitcode.modules);
+    }
+    for (label, value) in [
+        ("Triple:     ", &bitcode.triple),
+        ("Data layout:", &bitcode.data_layout),
+        ("Source file:", &bitcode.source),
+    ] {
+        if let Some(value) = value {
+            println!("    {} {}", label, value);
+        }
+    }
+    match bitcode
+        .symtab
+        .zip(bitcode.strtab)
+        .and_then(|(s, t)| parse_irsymtab(s, t))
+    {
+        Some(symtab) => println!(
+            "    Symbols:     {} in the LTO symbol table (see --lto-syms)",
+            symtab.symbols.len()
+        ),
+        None => println!("    Symbols:     no LTO symbol table"),
+    }
+}
+
+/// The NUL-separated strings of a section: command lines, linker options
+/// and dependent libraries.
+fn strings(data: &[u8]) -> Vec<String> {
+    data.split(|&b| b == 0)
+        .filter(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .collect()
+}
+
+fn print_addrsig(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    section: &SectionHeader,
+    data: &[u8],
+) -> Result<()> {
+    let mut indices = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let (mut value, mut shift) = (0u64, 0);
+        loop {
+            let Some((&byte, tail)) = rest.split_first() else {
+                bail!("the table ends in the middle of an entry");
+            };
+            rest = tail;
+            value |= ((byte & 0x7f) as u64).checked_shl(shift).unwrap_or(0);
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        indices.push(value);
+    }
+
+    let symbols = match sections.get(section.sh_link as usize) {
+        Some(table) if section.sh_link != 0 => Some(SymbolTable::new(elf_file, sections, table)?),
+        _ => None,
+    };
+    println!("    {} address-significant symbols:", indices.len());
+    for index in indices {
+        let name = symbols
+            .as_ref()
+            .and_then(|s| s.name(index as usize).ok())
+            .unwrap_or("<invalid index>");
+        println!("      [{:>4}] {}", index, name);
+    }
+    Ok(())
+}
+
+fn offload_image_kind(kind: u16) -> &'static str {
+    match kind {
+        1 => "object",
+        2 => "bitcode",
+        3 => "cubin",
+        4 => "fatbinary",
+        5 => "PTX",
+        6 => "SPIR-V",
+        _ => "unknown",
+    }
+}
+
+fn offload_kind(kind: u16) -> &'static str {
+    match kind {
+        1 => "OpenMP",
+        2 => "CUDA",
+        3 => "HIP",
+        4 => "SYCL",
+        _ => "unknown",
+    }
+}
+
+/// The offloading binaries of `.llvm.offloading`: one or more, each with
+/// its images and the string pairs (triple, arch) describing them.
+fn print_offloading(data: &[u8]) -> Result<()> {
+    let u16_at = |d: &[u8], o: usize| {
+        d.get(o..o + 2)
+            .map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+    };
+    let u32_at = |d: &[u8], o: usize| {
+        d.get(o..o + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    };
+    let u64_at = |d: &[u8], o: usize| {
+        d.get(o..o + 8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()) as usize)
+    };
+    let malformed = || anyhow::anyhow!("the offloading binary is malformed");
+
+    let mut offset = 0;
+    let mut count = 0;
+    while offset < data.len() {
+        let binary = &data[offset..];
+        if !binary.starts_with(OFFLOAD_MAGIC) {
+            bail!("there is no offloading binary at offset {:#x}", offset);
+        }
+        let version = u32_at(binary, 4).ok_or_else(malformed)?;
+        let size = u64_at(binary, 8).ok_or_else(malformed)?;
+        let entries = u64_at(binary, 16).ok_or_else(malformed)?;
+        let entries_size = u64_at(binary, 24).ok_or_else(malformed)?;
+        let binary = binary.get(..size).ok_or_else(malformed)?;
+        println!(
+            "    Offloading binary {} at {:#x}: version {}, {} bytes",
+            count, offset, version, size
+        );
+
+        // Each entry: ImageKind, OffloadKind, Flags, StringOffset,
+        // NumStrings, ImageOffset, ImageSize.
+        for entry in (entries..entries + entries_size).step_by(40) {
+            let image_kind = u16_at(binary, entry).ok_or_else(malformed)?;
+            let kind = u16_at(binary, entry + 2).ok_or_else(malformed)?;
+            let flags = u32_at(binary, entry + 4).ok_or_else(malformed)?;
+            let strings = u64_at(binary, entry + 8).ok_or_else(malformed)?;
+            let string_count = u64_at(binary, entry + 16).ok_or_else(malformed)?;
+            let image_size = u64_at(binary, entry + 32).ok_or_else(malformed)?;
+            println!(
+                "      {} {} image, {} bytes, flags {:#x}",
+                offload_kind(kind),
+                offload_image_kind(image_kind),
+                image_size,
+                flags
+            );
+            let string = |at: usize| {
+                let start = u64_at(binary, at)?;
+                let bytes = binary.get(start..)?;
+                let end = bytes.iter().position(|&b| b == 0)?;
+                Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+            };
+            for index in 0..string_count {
+                let at = strings + index * 16;
+                if let (Some(key), Some(value)) = (string(at), string(at + 8)) {
+                    println!("        {}: {}", key, value);
+                }
+            }
+        }
+        count += 1;
+        offset += size.div_ceil(8) * 8;
+    }
+    Ok(())
+}
+
+/// Decodes every LLVM-specific section of the file.
+pub fn print_llvm(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let mut found = false;
+    for section in &sections {
+        let name = elf_file.section_name(&sections, section).unwrap_or("");
+        let known = is_bitcode_section(elf_file, &sections, section)
+            || matches!(
+                name,
+                ".llvmcmd" | ".llvm.embedded.object" | ".llvm.offloading"
+            )
+            || (SectionType::LLVM_ODRTAB.0..=SectionType::LLVM_LTO.0).contains(&section.sh_type.0);
+        if !known {
+            continue;
+        }
+        if !found {
+            println!("LLVM sections:");
+            found = true;
+        }
+        println!();
+        println!(
+            "  {} ({}, {} bytes):",
+            name, section.sh_type, section.sh_size
+        );
+        let data = elf_file.section_data(section)?;
+
+        let result = if is_bitcode_section(elf_file, &sections, section) {
+            print_bitcode(data);
+            Ok(())
+        } else if section.sh_type == SectionType::LLVM_ADDRSIG {
+            print_addrsig(elf_file, &sections, section, data)
+        } else if section.sh_type == SectionType::LLVM_OFFLOADING
+            || matches!(name, ".llvm.embedded.object" | ".llvm.offloading")
+        {
+            print_offloading(data)
+        } else if name == ".llvmcmd" {
+            let arguments = strings(data);
+            if arguments.is_empty() {
+                println!("    Command line: none recorded");
+            } else {
+                println!("    Command line: {}", arguments.join(" "));
+            }
+            Ok(())
+        } else if section.sh_type == SectionType::LLVM_LINKER_OPTIONS {
+            let options = strings(data);
+            for pair in options.chunks(2) {
+                println!("    {}", pair.join(" "));
+            }
+            Ok(())
+        } else if section.sh_type == SectionType::LLVM_DEPENDENT_LIBRARIES {
+            for library in strings(data) {
+                println!("    {}", library);
+            }
+            Ok(())
+        } else {
+            println!("    Not decoded.");
+            Ok(())
+        };
+        if let Err(e) = result {
+            println!("    {:#}", e);
+        }
+    }
+    if !found {
+        println!("There are no LLVM sections in this file.");
+    }
+    Ok(())
+}
+
+/// Lists the symbols of the LTO symbol table in each section of the file
+/// that holds bitcode, the way `llvm-lto2 dump-symtab` does. Returns
+/// whether there was any bitcode.
+pub fn print_lto_symbols(elf_file: &ElfFile) -> Result<bool> {
+    let sections = elf_file.section_headers()?;
+    let mut found = false;
+    for section in &sections {
+        if !is_bitcode_section(elf_file, &sections, section) {
+            continue;
+        }
+        found = true;
+        let name = elf_file.section_name(&sections, section).unwrap_or("");
+        let bitcode = match parse_bitcode(elf_file.section_data(section)?) {
+            Ok(bitcode) => bitcode,
+            Err(e) => {
+                println!("Section '{}': {:#}", name, e);
+                continue;
+            }
+        };
+        let Some(symtab) = bitcode
+            .symtab
+            .zip(bitcode.strtab)
+            .and_then(|(s, t)| parse_irsymtab(s, t))
+        else {
+            println!(
+                "Section '{}' has bitcode without an LTO symbol table.",
+                name
+            );
+            continue;
+        };
+
+        println!(
+            "LTO symbol table of section '{}' ({} symbols, version {}):",
+            name,
+            symtab.symbols.len(),
+            symtab.version
+        );
+        println!("  Producer:    {}", symtab.producer);
+        println!("  Triple:      {}", symtab.triple);
+        println!("  Source file: {}", symtab.source);
+        for symbol in &symtab.symbols {
+            println!("  {:<40} {}", symbol.name, lto_flags(symbol.flags));
+            if !symbol.ir_name.is_empty() && symbol.ir_name != symbol.name {
+                println!("      IR name: {}", symbol.ir_name);
+            }
+        }
+    }
+    Ok(found)
+}
@@ -0,0 +1,300 @@
+//! A first look at Mach-O files: the header, the load commands and the
+//! sections of each segment, and the architectures of universal binaries.
+//! Nothing past the load commands is decoded.
+
+use anyhow::{Context, Result, bail};
+
+use crate::formats;
+
+const LC_SEGMENT: u32 = 0x1;
+const LC_SEGMENT_64: u32 = 0x19;
+const LC_REQ_DYLD: u32 = 0x8000_0000;
+
+/// The names of the load commands.
+const COMMANDS: &[(u32, &str)] = &[
+    (0x1, "LC_SEGMENT"),
+    (0x2, "LC_SYMTAB"),
+    (0xb, "LC_DYSYMTAB"),
+    (0xc, "LC_LOAD_DYLIB"),
+    (0xd, "LC_ID_DYLIB"),
+    (0xe, "LC_LOAD_DYLINKER"),
+    (0xf, "LC_ID_DYLINKER"),
+    (0x19, "LC_SEGMENT_64"),
+    (0x1b, "LC_UUID"),
+    (0x1d, "LC_CODE_SIGNATURE"),
+    (0x1e, "LC_SEGMENT_SPLIT_INFO"),
+    (0x21, "LC_ENCRYPTION_INFO"),
+    (0x22, "LC_DYLD_INFO"),
+    (0x24, "LC_VERSION_MIN_MACOSX"),
+    (0x25, "LC_VERSION_MIN_IPHONEOS"),
+    (0x26, "LC_FUNCTION_STARTS"),
+    (0x29, "LC_DATA_IN_CODE"),
+    (0x2a, "LC_SOURCE_VERSION"),
+    (0x2c, "LC_ENCRYPTION_INFO_64"),
+    (0x2e, "LC_LINKER_OPTIMIZATION_HINT"),
+    (0x32, "LC_BUILD_VERSION"),
+    (0x8000_0018, "LC_LOAD_WEAK_DYLIB"),
+    (0x8000_001c, "LC_RPATH"),
+    (0x8000_001f, "LC_REEXPORT_DYLIB"),
+    (0x8000_0022, "LC_DYLD_INFO_ONLY"),
+    (0x8000_0028, "LC_MAIN"),
+    (0x8000_0033, "LC_DYLD_EXPORTS_TRIE"),
+    (0x8000_0034, "LC_DYLD_CHAINED_FIXUPS"),
+];
+
+/// Reads fields in the byte order of one Mach-O file.
+struct Reader<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&self, offset: usize) -> Result<[u8; N]> {
+        let bytes = self
+            .data
+            .get(offset..offset + N)
+            .context("The load commands run past the end of the file")?;
+        Ok(bytes.try_into()?)
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    fn u64(&self, offset: usize) -> Result<u64> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        })
+    }
+
+    /// An address-sized field.
+    fn word(&self, offset: usize, is_64: bool) -> Result<u64> {
+        if is_64 {
+            self.u64(offset)
+        } else {
+            Ok(self.u32(offset)? as u64)
+        }
+    }
+
+    /// A NUL-padded name of up to `len` bytes.
+    fn name(&self, offset: usize, len: usize) -> String {
+        let raw = self.data.get(offset..offset + len).unwrap_or(&[]);
+        let end = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+        String::from_utf8_lossy(&raw[..end]).into_owned()
+    }
+}
+
+/// The `rwx` form of a VM protection.
+fn protection(value: u32) -> String {
+    [(1, 'r'), (2, 'w'), (4, 'x')]
+        .iter()
+        .map(|&(bit, c)| if value & bit != 0 { c } else { '-' })
+        .collect()
+}
+
+/// What a load command says beyond its name, for the ones with something
+/// short to say.
+fn describe_command(reader: &Reader, command: usize, cmd: u32) -> Result<String> {
+    Ok(match cmd & !LC_REQ_DYLD {
+        // The dylib and dylinker commands and LC_RPATH start with the
+        // offset of a path within the command.
+        0xc | 0xd | 0xe | 0xf | 0x18 | 0x1c | 0x1f => {
+            let offset = reader.u32(command + 8)? as usize;
+            let size = reader.u32(command + 4)? as usize;
+            reader.name(command + offset, size.saturating_sub(offset))
+        }
+        0x1b => reader
+            .bytes::<16>(command + 8)?
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+        0x2 => format!(
+            "{} symbols at {:#x}, strings at {:#x}",
+            reader.u32(command + 12)?,
+            reader.u32(command + 8)?,
+            reader.u32(command + 16)?
+        ),
+        0x28 => format!("entry at file offset {:#x}", reader.u64(command + 8)?),
+        _ => String::new(),
+    })
+}
+
+/// Prints the header, load commands and sections of a thin Mach-O file.
+fn print_thin(data: &[u8]) -> Result<()> {
+    let magic = u32::from_le_bytes(data.get(..4).context("Truncated header")?.try_into()?);
+    let (big_endian, is_64) = match magic {
+        0xfeedface => (false, false),
+        0xfeedfacf => (false, true),
+        0xcefaedfe => (true, false),
+        0xcffaedfe => (true, true),
+        _ => bail!("Not a Mach-O file"),
+    };
+    let reader = Reader { data, big_endian };
+    let cpu = reader.u32(4)?;
+    let file_type = reader.u32(12)?;
+    let command_count = reader.u32(16)? as usize;
+    let commands_size = reader.u32(20)?;
+
+    println!("Mach-O header:");
+    println!(
+        "  Class:                             {}",
+        if is_64 { "64-bit" } else { "32-bit" }
+    );
+    println!(
+        "  Data:                              {}",
+        if big_endian {
+            "big endian"
+        } else {
+            "little endian"
+        }
+    );
+    println!(
+        "  CPU:                               {} ({:#x}, subtype {:#x})",
+        formats::macho_cpu(cpu),
+        cpu,
+        reader.u32(8)?
+    );
+    println!(
+        "  File type:                         {} ({})",
+        formats::macho_file_type(file_type),
+        file_type
+    );
+    println!(
+        "  Load commands:                     {} ({:#x} bytes)",
+        command_count, commands_size
+    );
+    println!(
+        "  Flags:                             {:#x}",
+        reader.u32(24)?
+    );
+
+    println!();
+    println!("Load commands:");
+    let mut sections = Vec::new();
+    let mut command = if is_64 { 32 } else { 28 };
+    for index in 0..command_count {
+        let cmd = reader.u32(command)?;
+        let size = reader.u32(command + 4)? as usize;
+        if size < 8 {
+            bail!("Load command {} has an invalid size {}", index, size);
+        }
+        let name = COMMANDS
+            .iter()
+            .find(|(number, _)| *number == cmd)
+            .map_or_else(|| format!("{:#x}", cmd), |(_, name)| name.to_string());
+        if cmd == LC_SEGMENT || cmd == LC_SEGMENT_64 {
+            let is_64 = cmd == LC_SEGMENT_64;
+            let width = if is_64 { 8 } else { 4 };
+            let fields = command + 24;
+            let vmaddr = reader.word(fields, is_64)?;
+            let vmsize = reader.word(fields + width, is_64)?;
+            let fileoff = reader.word(fields + 2 * width, is_64)?;
+            let filesize = reader.word(fields + 3 * width, is_64)?;
+            let rest = fields + 4 * width;
+            let segment = reader.name(command + 8, 16);
+            println!(
+                "  [{:>2}] {:<24} {:<16} vmaddr {:#x} vmsize {:#x} fileoff {:#x} filesize {:#x} {}",
+                index,
+                name,
+                segment,
+                vmaddr,
+                vmsize,
+                fileoff,
+                filesize,
+                protection(reader.u32(rest + 4)?)
+            );
+            let count = reader.u32(rest + 8)? as usize;
+            let mut section = rest + 16;
+            for _ in 0..count {
+                let address = reader.word(section + 32, is_64)?;
+                let size = reader.word(section + 32 + width, is_64)?;
+                let fields = section + 32 + 2 * width;
+                sections.push((
+                    reader.name(section + 16, 16),
+                    reader.name(section, 16),
+                    address,
+                    size,
+                    reader.u32(fields)?,
+                    reader.u32(fields + 4)?,
+                ));
+                section += if is_64 { 80 } else { 68 };
+            }
+        } else {
+            println!(
+                "  [{:>2}] {:<24} {}",
+                index,
+                name,
+                describe_command(&reader, command, cmd)?
+            );
+        }
+        command += size;
+    }
+
+    println!();
+    println!("Sections:");
+    println!(
+        "  [Nr] {:<28} {:>18} {:>10} {:>10} Align",
+        "Segment,Section", "Address", "Size", "Offset"
+    );
+    for (index, (segment, section, address, size, offset, align)) in sections.iter().enumerate() {
+        println!(
+            "  [{:>2}] {:<28} {:>#18x} {:>#10x} {:>#10x} 2^{}",
+            index + 1,
+            format!("{},{}", segment, section),
+            address,
+            size,
+            offset,
+            align
+        );
+    }
+    Ok(())
+}
+
+/// Prints a Mach-O file, or each architecture of a universal binary.
+pub fn print_macho(data: &[u8]) -> Result<()> {
+    if !data.starts_with(&[0xca, 0xfe, 0xba, 0xbe]) {
+        return print_thin(data);
+    }
+    let reader = Reader {
+        data,
+        big_endian: true,
+    };
+    let count = reader.u32(4)? as usize;
+    println!(
+        "Universal binary with {} architecture{}:",
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    let mut architectures = Vec::new();
+    for index in 0..count {
+        let entry = 8 + index * 20;
+        let cpu = reader.u32(entry)?;
+        let offset = reader.u32(entry + 8)? as usize;
+        let size = reader.u32(entry + 12)? as usize;
+        println!(
+            "  {:<12} offset {:#x} size {:#x} align 2^{}",
+            formats::macho_cpu(cpu),
+            offset,
+            size,
+            reader.u32(entry + 16)?
+        );
+        architectures.push((cpu, offset, size));
+    }
+    for (cpu, offset, size) in architectures {
+        println!();
+        println!("Architecture {}:", formats::macho_cpu(cpu));
+        let thin = data
+            .get(offset..offset.saturating_add(size))
+            .context("The architecture runs past the end of the file")?;
+        print_thin(thin)?;
+    }
+    Ok(())
+}
@@ -1,29 +1,1411 @@
-use clap::{Arg, Command};
+use anyhow::{Context, Result, bail};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use clap_complete::Shell;
 use elf::ElfFile;
+use hashes::HashAlgorithm;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 
-mod elf;
-mod emachine;
+// The parser and the decoders are in the library, for other tools to use;
+// the reports are built on them here.
+use readelf_rs::{
+    consts, dynamic, elf, emachine, filter, notes, plugin, ppc64, relocation, section, segment,
+    strtab, symbol, warning,
+};
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let matches = Command::new("readelf-rs")
+mod aarch64;
+mod abi;
+mod addr2line;
+mod aslr;
+mod attributes;
+mod audit;
+mod auxv;
+mod backtrace;
+mod bloat;
+mod compat;
+mod config;
+mod convert;
+mod core;
+mod coreextract;
+mod coremaps;
+mod coreregs;
+mod debuginfo;
+mod demangle;
+mod deps;
+mod dies;
+mod dump;
+mod dupstrings;
+mod dwarf;
+mod dwarfexpr;
+mod dynsection;
+mod embedded;
+mod entropy;
+mod entrycheck;
+mod export;
+mod fileusage;
+mod formats;
+mod functions;
+mod gcclto;
+mod hashes;
+mod histogram;
+mod initorder;
+mod json;
+mod linkage;
+mod linkmap;
+mod llvm;
+#[cfg(feature = "macho")]
+mod macho;
+mod memmap;
+mod output;
+mod overlay;
+mod padding;
+mod pager;
+#[cfg(feature = "pe")]
+mod pe;
+mod placement;
+mod relocs;
+mod relocstats;
+mod resolve;
+mod rust;
+mod sarif;
+mod search;
+mod sections;
+mod segments;
+mod soname;
+mod stacksizes;
+mod startup;
+mod stats;
+mod symbols;
+mod symbolsin;
+mod unwind;
+mod validate;
+mod veraudit;
+mod version;
+mod visibility;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod watch;
+mod x86;
+mod xref;
+
+/// The options that print a report of their own, as opposed to those that
+/// only change how reports are printed.
+const DISPLAYS: &[&str] = &[
+    "program-headers",
+    "relocs",
+    "section-hashes",
+    "bloat",
+    "padding",
+    "file-usage",
+    "stats",
+    "duplicate-strings",
+    "rust",
+    "llvm",
+    "map",
+    "functions",
+    "init-order",
+    "stack-sizes",
+    "memory-map",
+    "placement",
+    "debug-inventory",
+    "cu-summary",
+    "dies",
+    "unwind-report",
+    "eh-frame-hdr",
+    "cfa-at",
+    "entropy",
+    "linkage",
+    "startup",
+    "entry-check",
+    "interp",
+    "version-audit",
+    "aarch64-report",
+    "x86-isa-level",
+    "aslr",
+    "resolve",
+    "would-interpose",
+    "visibility-advisor",
+    "abi-dump",
+    "soname-check",
+    "undefined",
+    "dependency-tree",
+    "reloc-stats",
+    "search",
+    "xref",
+    "addr2line",
+    "convert",
+    "symbols-in",
+    "core-maps",
+    "core-registers",
+    "auxv",
+    "core-extract",
+    "core-extract-files",
+    "export-raw",
+    "export-ihex",
+    "overlay",
+    "extract-overlay",
+    "scan-embedded",
+    "decode",
+    "validate",
+    "exit-on-warning",
+];
+
+/// The reports `--section` limits to the sections it selects.
+const FILTERED: &[&str] = &[
+    "section-headers",
+    "relocs",
+    "syms",
+    "dyn-syms",
+    "section-hashes",
+    "entropy",
+    "xref",
+    "decode",
+];
+
+/// Checks a `--section` value, so that a bad one is refused with the other
+/// arguments rather than once per file.
+fn parse_section_filter(value: &str) -> Result<String> {
+    filter::SectionFilter::new([value])?;
+    Ok(value.to_string())
+}
+
+/// The argument definitions, shared by the parser and by the completion and
+/// man page generators.
+fn build_cli() -> Command {
+    let command = Command::new("readelf-rs")
         .version("1.0")
         .author("Gustavo Noronha Silva <gustavo@noronha.dev.br>")
         .about("A simple implementation of readelf in Rust")
+        .args_conflicts_with_subcommands(true)
+        .args_override_self(true)
+        .subcommand_negates_reqs(true)
+        .subcommand(
+            Command::new("completions")
+                .about("Print a shell completion script to stdout")
+                .arg(
+                    Arg::new("shell")
+                        .help("Shell to generate completions for")
+                        .required(true)
+                        .value_parser(value_parser!(Shell)),
+                ),
+        )
         .arg(
             Arg::new("elf")
-                .help("Path to the ELF file")
-                .required(true)
+                .help("Path to the ELF file(s)")
+                .required_unless_present_any([
+                    "generate-man",
+                    "core",
+                    "pid",
+                    "audit-image",
+                    "abi-compare",
+                ])
+                .num_args(1..)
                 .index(1),
         )
-        .get_matches();
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .help("Write the output to FILE instead of stdout, or into directory FILE with --split")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("split")
+                .long("split")
+                .help("Write one report per input file into the --output directory, named after the file with the format's extension added")
+                .requires("output")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("generate-man")
+                .long("generate-man")
+                .help("Print a man page in roff format to stdout")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("program-headers")
+                .short('l')
+                .long("program-headers")
+                .visible_alias("segments")
+                .help("Display the program headers, explaining the GNU stack, RELRO and property segments")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("relocs")
+                .short('r')
+                .long("relocs")
+                .help("Display the relocations, expanding RELR and Android packed tables")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("section-hashes")
+                .long("section-hashes")
+                .help("Print a digest of the contents of each allocated section")
+                .value_name("ALGORITHM")
+                .value_parser(["sha256", "blake3"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("sha256"),
+        )
+        .arg(
+            Arg::new("bloat")
+                .long("bloat")
+                .help("Show the top N contributors to binary size by section, namespace and symbol")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("20"),
+        )
+        .arg(
+            Arg::new("padding")
+                .long("padding")
+                .help("Show how many file and memory bytes alignment padding takes, by segment, and the N largest gaps")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("10"),
+        )
+        .arg(
+            Arg::new("file-usage")
+                .long("file-usage")
+                .help("Show what every byte of the file is for: headers, sections, segment contents outside sections, padding, or nothing at all")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .help("Print a one-screen summary: sections by type, symbols by binding and type, relocation counts, notes and debug info")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("duplicate-strings")
+                .long("duplicate-strings")
+                .help("Show the N string literals stored the most times over in the file's data, and how much merging them would save")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("20"),
+        )
+        .arg(
+            Arg::new("rust")
+                .long("rust")
+                .help("Show how a Rust binary was built: rustc version, panic strategy, embedded bitcode, mangling, and the top N crates by symbol size")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("10"),
+        )
+        .arg(
+            Arg::new("llvm")
+                .long("llvm")
+                .help("Decode the LLVM-specific sections: embedded bitcode, .llvmcmd, .llvm_addrsig, offloading images, linker options and dependent libraries")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("map")
+                .long("map")
+                .help("Cross-check the file against the GNU ld, gold or lld map file it was linked with: section and symbol addresses, and the object files each section came from")
+                .value_name("MAPFILE")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("functions")
+                .long("functions")
+                .help("List function symbols by address, with source files when DWARF is present, and report uncovered gaps")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("init-order")
+                .long("init-order")
+                .help("Show the order constructors and destructors run in, with the priorities of .init_array.NNNNN and .ctors.NNNNN sections")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("stack-sizes")
+                .long("stack-sizes")
+                .help("Show the stack usage of each function from .stack_sizes, largest first")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("memory-map")
+                .long("memory-map")
+                .help("Draw the virtual address space layout of the loadable segments")
+                .value_name("STYLE")
+                .value_parser(["text", "svg"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("text"),
+        )
+        .arg(
+            Arg::new("placement")
+                .long("placement")
+                .help("Group the allocated sections by memory region, as loaded and as run; the regions are the PT_LOAD segments unless given with --region or --memory")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("region")
+                .long("region")
+                .help("A memory region for --placement, as NAME=ORIGIN:LENGTH (FLASH=0x08000000:512K), to show how much of it is used")
+                .value_name("REGION")
+                .action(ArgAction::Append)
+                .requires("placement"),
+        )
+        .arg(
+            Arg::new("memory")
+                .long("memory")
+                .help("Read the memory regions for --placement from the MEMORY command of a linker script")
+                .value_name("SCRIPT")
+                .value_parser(value_parser!(PathBuf))
+                .requires("placement"),
+        )
+        .arg(
+            Arg::new("debug-inventory")
+                .long("debug-inventory")
+                .help("Summarise the debug information present: symbol tables, DWARF, build ID, debug links, CTF and SFrame")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("cu-summary")
+                .long("cu-summary")
+                .help("List the DWARF compilation units with their producer, language, directory, code ranges and debug info size, largest first")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dies")
+                .long("dies")
+                .help("Dump the DWARF debugging information entries, with location expressions and location lists decoded into DW_OP operations")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("unwind-report")
+                .long("unwind-report")
+                .help("Check whether the file has the unwind information profilers need for backtraces: .eh_frame, .eh_frame_hdr, SFrame and frame pointers")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("eh-frame-hdr")
+                .long("eh-frame-hdr")
+                .help("Dump the .eh_frame_hdr encodings and search table, and check each entry against the FDE it points to")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("cfa-at")
+                .long("cfa-at")
+                .help("Run the call frame information up to a hex address and show the CFA and register recovery rules there")
+                .value_name("ADDRESS"),
+        )
+        .arg(
+            Arg::new("entropy")
+                .long("entropy")
+                .help("Show the entropy of each section and segment and check for signs of a packed binary")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("linkage")
+                .long("linkage")
+                .help("Report whether the file is static, static-PIE or dynamic, its interpreter and which C library it uses")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("startup")
+                .long("startup")
+                .help("Report which start files (crt1.o, crti.o, crtbegin.o and their variants) were linked in, and the link mode they point to")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("entry-check")
+                .long("entry-check")
+                .help("Check that the entry point is in an executable segment at a function, decoding up to N instructions there (x86-64 and AArch64) to see whether it looks like _start")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("8"),
+        )
+        .arg(
+            Arg::new("interp")
+                .long("interp")
+                .help("Show the program interpreter and check it against the file type; with =check, also look for it on this system")
+                .value_name("MODE")
+                .value_parser(["show", "check"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("show"),
+        )
+        .arg(
+            Arg::new("version-audit")
+                .long("version-audit")
+                .help("Check the symbol versioning tables for dangling requirements, duplicate definitions and unknown version indexes")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("aarch64-report")
+                .long("aarch64-report")
+                .help("Report the AArch64 hardening the file was built with: BTI, PAC, GCS and memory tagging")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("x86-isa-level")
+                .long("x86-isa-level")
+                .help("Report the x86-64 microarchitecture level (baseline, v2, v3, v4) the file needs, from its GNU properties")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("aslr")
+                .long("aslr")
+                .help("Summarize ASLR compatibility: whether the file is position-independent, has text relocations, or asks for fixed addresses")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("resolve")
+                .long("resolve")
+                .help("Simulate the dynamic loader's symbol lookup and report which library satisfies each undefined symbol")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("undefined")
+                .long("undefined")
+                .help("List the undefined symbols, marking weak ones, grouped by the dependency that provides them")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("would-interpose")
+                .long("would-interpose")
+                .help("Report which imports would bind to LIB if it were preloaded with LD_PRELOAD")
+                .value_name("LIB")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("visibility-advisor")
+                .long("visibility-advisor")
+                .help("Suggest which exported symbols of a shared library could be hidden, checking them against the library's own references and the imports of --consumer files")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("consumer")
+                .long("consumer")
+                .help("A program or library linked against the file, whose imports --visibility-advisor keeps exported")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .action(ArgAction::Append)
+                .requires("visibility-advisor"),
+        )
+        .arg(
+            Arg::new("soname-check")
+                .long("soname-check")
+                .help("Check the SONAME of a shared library against its file name, the links next to it and its version definitions")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("abi-dump")
+                .long("abi-dump")
+                .help("Print the public ABI of a shared library as sorted JSON: exported symbols with their versions, kinds and data sizes, for diffing between releases")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dependency-tree")
+                .long("dependency-tree")
+                .help("Show the recursive DT_NEEDED tree, resolving each library like the dynamic loader")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("reloc-stats")
+                .long("reloc-stats")
+                .help("Summarise relocations by type and symbol, and estimate the savings of packing relative ones with RELR")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("search")
+                .long("search")
+                .help("Find a string, or hex bytes given as hex:PATTERN with ?? wildcards, and report the section, offset and address of each match")
+                .value_name("PATTERN")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("addr2line")
+                .long("addr2line")
+                .help("Show the function and source line of a hex address, with the chain of inlined calls it is in, like addr2line -f -i")
+                .value_name("ADDRESS")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("convert")
+                .long("convert")
+                .help("Convert between a file offset (offset:HEX), a virtual address (vaddr:HEX or HEX) and a section offset (SECTION+HEX), printing all three")
+                .value_name("LOCATION")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("symbols-in")
+                .long("symbols-in")
+                .help("List the symbols that overlap the hex address range START-END, or cover a single address, by address with their sections")
+                .value_name("START-END")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("abi-compare")
+                .long("abi-compare")
+                .help("Compare the ABIs of two releases of a shared library, each the library or a saved --abi-dump, instead of reading ELF files; exits with 1 if the change is incompatible")
+                .value_names(["OLD", "NEW"])
+                .num_args(2)
+                .value_parser(value_parser!(PathBuf))
+                .conflicts_with("elf"),
+        )
+        .arg(
+            Arg::new("audit-image")
+                .long("audit-image")
+                .help("Audit every ELF file of a container image tarball (docker save or OCI layout) or root filesystem, printing a JSON report of their machine, linkage, hardening and needed libraries")
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .conflicts_with("elf"),
+        )
+        .arg(
+            Arg::new("core")
+                .long("core")
+                .help("Print a symbolized backtrace of every thread of core dump CORE, instead of reading ELF files")
+                .value_name("CORE")
+                .conflicts_with("elf"),
+        )
+        .arg(
+            Arg::new("core-summary")
+                .long("core-summary")
+                .help("With --core, print one line per thread with its signal, PC, SP and function instead of full backtraces")
+                .action(ArgAction::SetTrue)
+                .requires("core")
+                .conflicts_with("elf"),
+        )
+        .arg(
+            Arg::new("exe")
+                .long("exe")
+                .help("The executable the core dump given with --core is of")
+                .value_name("BIN")
+                .requires("core")
+                .conflicts_with("elf"),
+        )
+        .arg(
+            Arg::new("lib")
+                .long("lib")
+                .help("A shared library the process of the core dump given with --core had loaded, for when it is not at the path it was loaded from")
+                .value_name("PATH")
+                .action(ArgAction::Append)
+                .requires("core")
+                .conflicts_with("elf"),
+        )
+        .arg(
+            Arg::new("core-maps")
+                .long("core-maps")
+                .help("Show the memory of a core dump's process the way /proc/<pid>/maps does")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("core-registers")
+                .long("core-registers")
+                .help("Show the registers of each thread of a core dump, named and grouped for its architecture")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("auxv")
+                .long("auxv")
+                .help("Decode the auxiliary vector of a core dump, or of a running process with --pid")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("pid")
+                .long("pid")
+                .help("Read the auxiliary vector of running process PID instead of an ELF file")
+                .value_name("PID")
+                .value_parser(value_parser!(u32))
+                .requires("auxv")
+                .conflicts_with("elf"),
+        )
+        .arg(
+            Arg::new("core-extract")
+                .long("core-extract")
+                .help("Write the memory of a core dump at hex address ADDR, LEN bytes long, to FILE")
+                .value_name("ADDR,LEN=FILE")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("core-extract-files")
+                .long("core-extract-files")
+                .help("Rebuild the files a core dump's process had mapped under DIR, from the memory the core holds")
+                .value_name("DIR")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("export-raw")
+                .long("export-raw")
+                .help("Write the contents of the loadable segments to FILE as a raw binary image laid out by physical address, like objcopy -O binary")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("export-ihex")
+                .long("export-ihex")
+                .help("Write the contents of the loadable segments to FILE in Intel HEX format, at their physical addresses")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("gap-fill")
+                .long("gap-fill")
+                .help("The byte to fill the gaps between segments with in --export-raw images (default 0)")
+                .value_name("BYTE")
+                .requires("export-raw"),
+        )
+        .arg(
+            Arg::new("overlay")
+                .long("overlay")
+                .help("Report data appended past the end of everything the headers describe, such as installer payloads and signatures, with a guess at its type")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("extract-overlay")
+                .long("extract-overlay")
+                .help("Write the data appended past the end of the ELF contents to FILE")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("scan-embedded")
+                .long("scan-embedded")
+                .help("List the ELF files embedded anywhere in the file, which need not be an ELF file itself, such as the ones bundled in a firmware image")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("at-offset")
+                .long("at-offset")
+                .help("Read the ELF file that starts OFFSET (hex) bytes into the file, such as one found by --scan-embedded")
+                .value_name("OFFSET"),
+        )
+        .arg(
+            Arg::new("xref")
+                .long("xref")
+                .help("List every relocation that references SYMBOL, and the function it is in")
+                .value_name("SYMBOL"),
+        )
+        .arg(
+            Arg::new("decode")
+                .long("decode")
+                .help("Decode vendor-specific sections, notes and dynamic tags with the built-in decoders")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("validate")
+                .long("validate")
+                .help("Check the file layout for inconsistencies, exiting with 1 on errors")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("section")
+                .long("section")
+                .help("Limit -S, -r, -s, --dyn-syms, --section-hashes, --entropy, --xref and --decode to sections matching a name glob, index or index range (comma-separated); the DWARF dumps such as --dies and --cu-summary are not limited")
+                .value_name("SECTIONS")
+                .value_parser(parse_section_filter)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("symbol-sources")
+                .long("symbol-sources")
+                .help("With -s and --dyn-syms, follow the function and object symbols of linked files with the source file and line the DWARF debug info declares them at")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("Output format; json/ndjson dump headers, sections, segments, symbols and relocations, sarif emits the --validate findings as a SARIF log, dot renders --dependency-tree as a Graphviz graph")
+                .value_name("FORMAT")
+                .value_parser(["text", "json", "ndjson", "sarif", "dot"])
+                .requires_if("dot", "dependency-tree")
+                .default_value("text"),
+        )
+        .arg(
+            Arg::new("deterministic")
+                .long("deterministic")
+                .help("Omit environment-dependent details such as file paths, so output can be diffed across machines")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("Print nothing; only report through the exit status whether the file is well-formed")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("exit-on-warning")
+                .long("exit-on-warning")
+                .help("Validate the file and exit with a failure status on warnings as well as errors")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("watch")
+                .long("watch")
+                .help("Run again whenever one of the input files changes on disk")
+                .conflicts_with("output")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-pager")
+                .long("no-pager")
+                .help("Do not pipe output through $PAGER when writing to a terminal")
+                .action(ArgAction::SetTrue),
+        );
+
+    compat::gnu_args(command)
+}
+
+fn main() {
+    pager::reset_sigpipe();
+
+    let status = match run() {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            1
+        }
+    };
+
+    pager::finish();
+    std::process::exit(status);
+}
+
+/// Runs the requested passes, returning the exit status.
+fn run() -> Result<i32, Box<dyn std::error::Error>> {
+    // Defaults from the config file and environment become the defaults of
+    // the options, so that the explicit command line overrides them.
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+    let mut cli = build_cli();
+    if args.get(1).is_none_or(|arg| arg != "completions") {
+        cli = config::apply_defaults(cli, |id| {
+            compat::is_display(id, DISPLAYS)
+                || ["core", "audit-image", "abi-compare", "generate-man"].contains(&id)
+        })?;
+    }
+
+    compat::normalize_args(&mut args);
+    let matches = cli.get_matches_from(args);
+
+    if let Some(completions) = matches.subcommand_matches("completions") {
+        let shell = *completions.get_one::<Shell>("shell").unwrap();
+        let mut cli = build_cli();
+        let name = cli.get_name().to_string();
+        clap_complete::generate(shell, &mut cli, name, &mut std::io::stdout());
+        return Ok(0);
+    }
+
+    if matches.get_flag("generate-man") {
+        clap_mangen::Man::new(build_cli()).render(&mut std::io::stdout())?;
+        return Ok(0);
+    }
+
+    // Rather than print nothing for them, which would pass for readelf
+    // having nothing to show.
+    let unsupported = compat::unsupported(&matches);
+    for option in &unsupported {
+        eprintln!("readelf-rs: Error: --{} is not supported yet", option);
+    }
+    if !unsupported.is_empty() {
+        return Ok(1);
+    }
+    if matches.contains_id("section") && !FILTERED.iter().any(|id| compat::wants(&matches, id)) {
+        eprintln!(
+            "readelf-rs: Warning: --section only limits {} and was ignored",
+            FILTERED
+                .iter()
+                .map(|id| format!("--{}", id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    // Machine-readable formats and --quiet always have something to report.
+    if matches.get_one::<String>("format").unwrap() == "text"
+        && !matches.get_flag("quiet")
+        && !matches.contains_id("core")
+        && !matches.contains_id("audit-image")
+        && !matches.contains_id("abi-compare")
+        && !compat::has_display(&matches, DISPLAYS)
+    {
+        eprintln!("readelf-rs: Warning: Nothing to do.");
+        eprintln!("Use -h to display the ELF file header, or --help for a list of options.");
+        return Ok(1);
+    }
+
+    let paths: Vec<&String> = matches
+        .get_many::<String>("elf")
+        .into_iter()
+        .flatten()
+        .collect();
+    let output = matches.get_one::<PathBuf>("output");
+    let split = matches.get_flag("split");
+
+    if let Some(output) = output {
+        if split {
+            std::fs::create_dir_all(output)
+                .with_context(|| format!("Failed to create {}", output.display()))?;
+        } else {
+            output::redirect_stdout(output)?;
+        }
+    } else if !matches.get_flag("no-pager")
+        && !matches.get_flag("quiet")
+        && !matches.get_flag("watch")
+    {
+        pager::start();
+    }
+
+    if let Some(mut paths) = matches.get_many::<PathBuf>("abi-compare") {
+        let (Some(old), Some(new)) = (paths.next(), paths.next()) else {
+            unreachable!("clap takes exactly two values");
+        };
+        let compatible = abi::print_abi_comparison(old, new)?;
+        return Ok(if compatible { 0 } else { 1 });
+    }
+
+    if let Some(path) = matches.get_one::<PathBuf>("audit-image") {
+        audit::print_image_audit(path)?;
+        return Ok(0);
+    }
+
+    if let Some(core) = matches.get_one::<String>("core") {
+        let libraries: Vec<&str> = matches
+            .get_many::<String>("lib")
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        let executable = matches.get_one::<String>("exe").map(String::as_str);
+        if matches.get_flag("core-summary") {
+            backtrace::print_summary(core, executable, &libraries)?;
+        } else {
+            backtrace::print_backtraces(core, executable, &libraries)?;
+        }
+        return Ok(0);
+    }
+
+    if let Some(&pid) = matches.get_one::<u32>("pid") {
+        auxv::print_live_auxv(pid)?;
+        return Ok(0);
+    }
+
+    // Like readelf, keep going after a file fails so that one bad input does
+    // not hide the reports for the others.
+    // With --split, each input gets a file named after it plus the format's
+    // extension, numbered when two inputs share a name.
+    let extension = match matches.get_one::<String>("format").unwrap().as_str() {
+        "sarif" => "sarif",
+        "json" => "json",
+        "ndjson" => "ndjson",
+        "dot" => "dot",
+        _ => "txt",
+    };
+    let mut split_names: Vec<String> = Vec::new();
+    for path in &paths {
+        let name = Path::new(path)
+            .file_name()
+            .unwrap_or(path.as_ref())
+            .to_string_lossy();
+        let mut candidate = format!("{}.{}", name, extension);
+        let mut index = 1;
+        while split_names.contains(&candidate) {
+            index += 1;
+            candidate = format!("{}-{}.{}", name, index, extension);
+        }
+        split_names.push(candidate);
+    }
+
+    // The json and sarif documents of several files going to one output are
+    // joined into one, which a header between them would only break.
+    let format = matches.get_one::<String>("format").unwrap().as_str();
+    let combine = paths.len() > 1 && !(output.is_some() && split);
+    let deterministic = matches.get_flag("deterministic");
+    let process_all = || -> Result<i32> {
+        let mut status = 0;
+        let mut documents = Vec::new();
+        for (path, split_name) in paths.iter().zip(&split_names) {
+            if let (Some(dir), true) = (output, split) {
+                output::redirect_stdout(&dir.join(split_name))?;
+            } else if paths.len() > 1 && !matches.get_flag("quiet") && format == "text" {
+                println!();
+                println!("File: {}", display_path(path, deterministic));
+            }
+
+            let file_status = process_file(path, &matches, &mut documents).unwrap_or_else(|err| {
+                eprintln!("Error: {}: {:#}", display_path(path, deterministic), err);
+                1
+            });
+            status = status.max(file_status);
+
+            if !combine {
+                for document in documents.drain(..) {
+                    println!("{}", serde_json::to_string_pretty(&document)?);
+                }
+            }
+        }
+
+        if combine && (format == "json" || format == "sarif" && !matches.get_flag("quiet")) {
+            let document = if format == "sarif" {
+                sarif::merge(documents)
+            } else {
+                serde_json::Value::Array(documents)
+            };
+            println!("{}", serde_json::to_string_pretty(&document)?);
+        }
+        Ok(status)
+    };
+
+    if matches.get_flag("watch") {
+        watch::watch(&paths, || {
+            if let Err(err) = process_all() {
+                eprintln!("Error: {:#}", err);
+            }
+        });
+    }
+
+    Ok(process_all()?)
+}
+
+/// In deterministic mode only the file name is shown, since the directory
+/// the file happens to live in differs across machines.
+fn display_path(path: &str, deterministic: bool) -> String {
+    if deterministic {
+        Path::new(path).file_name().map_or_else(
+            || path.to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    } else {
+        path.to_string()
+    }
+}
+
+/// Runs the requested passes on a single input, returning its exit status.
+/// The json and sarif documents are left in `documents` for the caller to
+/// print, since it may join them with those of other inputs.
+fn process_file(
+    path: &str,
+    matches: &ArgMatches,
+    documents: &mut Vec<serde_json::Value>,
+) -> Result<i32> {
+    // Before opening it, since the file need not be an ELF file.
+    if matches.get_flag("scan-embedded") {
+        embedded::print_embedded(path)?;
+        return Ok(0);
+    }
+
+    let deterministic = matches.get_flag("deterministic");
+    let display_path = display_path(path, deterministic);
+
+    let elf_file = match matches.get_one::<String>("at-offset") {
+        Some(offset) => ElfFile::open_at(path, addr2line::parse_address(offset)?)?,
+        None => {
+            // Say what a file in some other format is rather than only that
+            // it is not ELF.
+            if let Some(format) = formats::identify(path)? {
+                let format_name = matches.get_one::<String>("format").unwrap();
+                if format_name == "json" {
+                    documents.push(json::other_format(&format, &display_path, false));
+                    return Ok(1);
+                }
+                if format_name == "ndjson" {
+                    let record = json::other_format(&format, &display_path, true);
+                    println!("{}", serde_json::to_string(&record)?);
+                    return Ok(1);
+                }
+                if formats::print_headers(&format, path)? {
+                    return Ok(0);
+                }
+                bail!("{}", format.message());
+            }
+            ElfFile::open(path)?
+        }
+    };
+
+    match matches.get_one::<String>("format").unwrap().as_str() {
+        "json" => {
+            documents.push(json::document(&elf_file, &display_path)?);
+            return Ok(0);
+        }
+        "ndjson" => {
+            json::print_ndjson(&elf_file, &display_path)?;
+            return Ok(0);
+        }
+        "dot" => {
+            deps::DependencyGraph::new(&elf_file, path, &display_path)?.print_dot();
+            return Ok(0);
+        }
+        _ => {}
+    }
+
+    // Only the dump, so that it can be saved and diffed as it is.
+    if matches.get_flag("abi-dump") {
+        abi::print_abi_dump(&elf_file)?;
+        return Ok(0);
+    }
+
+    let quiet = matches.get_flag("quiet");
+    if !quiet && matches.get_one::<String>("format").unwrap() == "text" {
+        for warning in elf_file.warnings() {
+            eprintln!("readelf-rs: {}", warning);
+        }
+    }
+    let exit_on_warning = matches.get_flag("exit-on-warning");
+
+    // Exit statuses: 0 when the file is fine, 1 when it is malformed (failed
+    // to parse or has validation errors, or warnings with --exit-on-warning),
+    // and 2 for usage errors, which clap reports on its own.
+    if quiet || matches.get_one::<String>("format").unwrap() == "sarif" {
+        let findings = validate::validate(&elf_file)?;
+        if !quiet {
+            documents.push(sarif::sarif_log(&display_path, &findings));
+        }
+        return Ok(if validate::passed(&findings, exit_on_warning) {
+            0
+        } else {
+            1
+        });
+    }
+
+    let filter = filter::SectionFilter::new(
+        matches
+            .get_many::<String>("section")
+            .into_iter()
+            .flatten()
+            .map(String::as_str),
+    )?;
+
+    let extra_info = matches.get_flag("extra-sym-info");
+    let names = demangle::NameStyle {
+        // --no-demangle on the command line beats a default -C.
+        demangle: matches
+            .get_one::<String>("demangle")
+            .is_some_and(|style| style != "none")
+            && matches.value_source("no-demangle") != Some(ValueSource::CommandLine),
+        unicode: matches
+            .get_one::<String>("unicode")
+            .map_or_else(Default::default, |mode| demangle::Unicode::from_mode(mode)),
+    };
+    let symbol_options = symbols::Options {
+        sources: matches.get_flag("symbol-sources"),
+        deterministic,
+        extra_info,
+        names,
+        size_base: matches
+            .get_one::<String>("sym-base")
+            .map_or(0, |base| base.parse().unwrap()),
+    };
+
+    // The readelf displays come first, in GNU readelf's order no matter how
+    // they were ordered on the command line, so that the output diffs cleanly
+    // against it. The warnings follow the header, or lead when there is none.
+    let findings = validate::security(&elf_file)?;
+    if !compat::wants(matches, "file-header") {
+        validate::print_security_warnings(&findings);
+        gcclto::print_notice(&elf_file)?;
+    }
+    for &pass in compat::PASS_ORDER {
+        if !compat::wants(matches, pass) {
+            continue;
+        }
+        match pass {
+            "file-header" => {
+                println!("{}", elf_file);
+                validate::print_security_warnings(&findings);
+                gcclto::print_notice(&elf_file)?;
+            }
+            "section-headers" => sections::print_section_headers(
+                &elf_file,
+                compat::wants(matches, "file-header"),
+                &filter,
+            )?,
+            "section-groups" => sections::print_section_groups(&elf_file)?,
+            "program-headers" => segments::print_program_headers(&elf_file)?,
+            "dynamic" => dynsection::print_dynamic(&elf_file, &plugin::Registry::with_builtins())?,
+            "relocs" => relocs::print_relocations(&elf_file, &filter, extra_info, names)?,
+            "syms" => symbols::print_symbols(&elf_file, false, &filter, &symbol_options)?,
+            "unwind" => unwind::print_unwind_info(&elf_file),
+            "notes" => notes::print_notes(&elf_file, &plugin::Registry::with_builtins())?,
+            // -s already includes the dynamic symbols.
+            "dyn-syms" if !compat::wants(matches, "syms") => {
+                symbols::print_symbols(&elf_file, true, &filter, &symbol_options)?
+            }
+            "lto-syms" => {
+                let gcc = gcclto::print_lto_symbols(&elf_file)?;
+                if !llvm::print_lto_symbols(&elf_file)? && !gcc {
+                    println!("There are no LTO symbol tables in this file.");
+                }
+            }
+            "histogram" => histogram::print_histogram(&elf_file)?,
+            "version-info" => version::print_version_info(&elf_file)?,
+            "hex-dump" | "string-dump" => {
+                let values = |id| {
+                    matches
+                        .get_many::<String>(id)
+                        .map_or(Vec::new(), |values| values.cloned().collect())
+                };
+                // Both are printed together, section by section.
+                if pass == "hex-dump" || !compat::wants(matches, "hex-dump") {
+                    dump::print_dumps(&elf_file, &values("hex-dump"), &values("string-dump"))?;
+                }
+            }
+            "arch-specific" => attributes::print_attributes(&elf_file)?,
+            // Rejected before any file is processed.
+            _ => {}
+        }
+    }
+
+    // Then the readelf-rs reports, in a fixed order of their own.
+
+    if let Some(algorithm) = matches.get_one::<String>("section-hashes") {
+        hashes::print_section_hashes(&elf_file, HashAlgorithm::from_name(algorithm)?, &filter)?;
+    }
+
+    if let Some(&top) = matches.get_one::<usize>("bloat") {
+        bloat::print_bloat(&elf_file, top)?;
+    }
+
+    if let Some(&top) = matches.get_one::<usize>("padding") {
+        padding::print_padding(&elf_file, top)?;
+    }
+
+    if matches.get_flag("file-usage") {
+        fileusage::print_file_usage(&elf_file)?;
+    }
+
+    if matches.get_flag("stats") {
+        stats::print_stats(&elf_file)?;
+    }
+
+    if let Some(&top) = matches.get_one::<usize>("duplicate-strings") {
+        dupstrings::print_duplicate_strings(&elf_file, top)?;
+    }
+
+    if let Some(&top) = matches.get_one::<usize>("rust") {
+        rust::print_rust(&elf_file, top)?;
+    }
+
+    if matches.get_flag("llvm") {
+        llvm::print_llvm(&elf_file)?;
+    }
+
+    if let Some(map) = matches.get_one::<PathBuf>("map") {
+        linkmap::print_map_check(&elf_file, map)?;
+    }
+
+    if matches.get_flag("functions") {
+        functions::print_functions(&elf_file, deterministic)?;
+    }
+
+    if matches.get_flag("init-order") {
+        initorder::print_init_order(&elf_file)?;
+    }
+
+    if matches.get_flag("stack-sizes") {
+        stacksizes::print_stack_sizes(&elf_file)?;
+    }
+
+    match matches.get_one::<String>("memory-map").map(String::as_str) {
+        Some("svg") => memmap::print_memory_map_svg(&elf_file)?,
+        Some(_) => memmap::print_memory_map(&elf_file)?,
+        None => {}
+    }
+
+    if matches.get_flag("placement") {
+        let mut regions = match matches.get_one::<PathBuf>("memory") {
+            Some(script) => placement::parse_memory(script)?,
+            None => Vec::new(),
+        };
+        for spec in matches.get_many::<String>("region").into_iter().flatten() {
+            regions.push(placement::parse_region(spec)?);
+        }
+        placement::print_placement(&elf_file, (!regions.is_empty()).then_some(regions))?;
+    }
+
+    if matches.get_flag("debug-inventory") {
+        debuginfo::print_debug_inventory(&elf_file)?;
+    }
+
+    if matches.get_flag("cu-summary") {
+        debuginfo::print_cu_summary(&elf_file, deterministic)?;
+    }
+
+    if matches.get_flag("dies") {
+        dies::print_dies(&elf_file)?;
+    }
+
+    if matches.get_flag("unwind-report") {
+        unwind::print_unwind_report(&elf_file)?;
+    }
+
+    if matches.get_flag("eh-frame-hdr") {
+        unwind::print_eh_frame_hdr(&elf_file)?;
+    }
+
+    if let Some(address) = matches.get_one::<String>("cfa-at") {
+        unwind::print_cfa_at(&elf_file, addr2line::parse_address(address)?)?;
+    }
+
+    if matches.get_flag("entropy") {
+        entropy::print_entropy(&elf_file, &filter)?;
+    }
+
+    if matches.get_flag("linkage") {
+        linkage::print_linkage(&elf_file)?;
+    }
+
+    if matches.get_flag("startup") {
+        startup::print_startup(&elf_file)?;
+    }
+
+    if let Some(&count) = matches.get_one::<usize>("entry-check") {
+        entrycheck::print_entry_check(&elf_file, count)?;
+    }
+
+    if let Some(mode) = matches.get_one::<String>("interp") {
+        linkage::print_interpreter(&elf_file, mode == "check")?;
+    }
+
+    if matches.get_flag("version-audit") {
+        veraudit::print_version_audit(&elf_file)?;
+    }
+
+    if matches.get_flag("aarch64-report") {
+        aarch64::print_aarch64_report(&elf_file)?;
+    }
+
+    if matches.get_flag("x86-isa-level") {
+        x86::print_isa_level(&elf_file)?;
+    }
+
+    if matches.get_flag("aslr") {
+        aslr::print_aslr(&elf_file)?;
+    }
+
+    if matches.get_flag("dependency-tree") {
+        deps::DependencyGraph::new(&elf_file, path, &display_path)?.print_tree();
+    }
+
+    if matches.get_flag("resolve") {
+        let graph = deps::DependencyGraph::new(&elf_file, path, &display_path)?;
+        resolve::Resolver::new(&graph)?.print_resolution();
+    }
+
+    if matches.get_flag("undefined") {
+        let graph = deps::DependencyGraph::new(&elf_file, path, &display_path)?;
+        resolve::Resolver::new(&graph)?.print_undefined();
+    }
+
+    if let Some(library) = matches.get_one::<PathBuf>("would-interpose") {
+        let graph = deps::DependencyGraph::new(&elf_file, path, &display_path)?;
+        resolve::print_interposition(&graph, library)?;
+    }
+
+    if matches.get_flag("visibility-advisor") {
+        let consumers: Vec<PathBuf> = matches
+            .get_many::<PathBuf>("consumer")
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        visibility::print_visibility_advice(&elf_file, &consumers)?;
+    }
+
+    if matches.get_flag("soname-check") {
+        soname::print_soname_check(&elf_file, path)?;
+    }
+
+    if matches.get_flag("reloc-stats") {
+        relocstats::print_reloc_stats(&elf_file)?;
+    }
+
+    for pattern in matches.get_many::<String>("search").into_iter().flatten() {
+        search::print_search(&elf_file, pattern)?;
+    }
+
+    if let Some(symbol) = matches.get_one::<String>("xref") {
+        xref::print_xref(&elf_file, symbol, &filter)?;
+    }
+
+    if let Some(addresses) = matches.get_many::<String>("addr2line") {
+        let addresses = addresses
+            .map(|a| addr2line::parse_address(a))
+            .collect::<Result<Vec<_>>>()?;
+        addr2line::print_addr2line(&elf_file, &addresses, deterministic)?;
+    }
+
+    if let Some(locations) = matches.get_many::<String>("convert") {
+        for location in locations {
+            convert::print_conversion(&elf_file, location)?;
+        }
+    }
+
+    if let Some(ranges) = matches.get_many::<String>("symbols-in") {
+        for range in ranges {
+            let (start, end) = symbolsin::parse_range(range)?;
+            symbolsin::print_symbols_in(&elf_file, start, end)?;
+        }
+    }
+
+    if matches.get_flag("core-maps") {
+        coremaps::print_core_maps(&elf_file)?;
+    }
+
+    if matches.get_flag("core-registers") {
+        coreregs::print_core_registers(&elf_file)?;
+    }
+
+    if matches.get_flag("auxv") {
+        auxv::print_auxv(&elf_file)?;
+    }
+
+    for request in matches
+        .get_many::<String>("core-extract")
+        .into_iter()
+        .flatten()
+    {
+        coreextract::extract_region(&elf_file, request)?;
+    }
+
+    if let Some(dir) = matches.get_one::<PathBuf>("core-extract-files") {
+        coreextract::extract_mapped_files(&elf_file, dir)?;
+    }
+
+    if let Some(path) = matches.get_one::<PathBuf>("export-raw") {
+        let fill = match matches.get_one::<String>("gap-fill") {
+            Some(fill) => export::parse_fill(fill)?,
+            None => 0,
+        };
+        export::export_raw(&elf_file, path, fill)?;
+    }
+
+    if let Some(path) = matches.get_one::<PathBuf>("export-ihex") {
+        export::export_ihex(&elf_file, path)?;
+    }
 
-    let path = matches.get_one::<String>("elf").unwrap();
+    if matches.get_flag("overlay") {
+        overlay::print_overlay(&elf_file)?;
+    }
 
-    let elf_file = ElfFile::new(path)?;
+    if let Some(path) = matches.get_one::<PathBuf>("extract-overlay") {
+        overlay::extract_overlay(&elf_file, path)?;
+    }
 
-    println!("Successfully memory-mapped ELF file: {}", path);
+    if matches.get_flag("decode") {
+        plugin::print_decoded(&elf_file, &plugin::Registry::with_builtins(), &filter)?;
+    }
 
-    println!("{}", elf_file);
+    if matches.get_flag("validate") || matches.get_flag("lint") || exit_on_warning {
+        let findings = validate::validate(&elf_file)?;
+        validate::print_findings(&findings);
+        if !validate::passed(&findings, exit_on_warning) {
+            return Ok(1);
+        }
+    }
 
-    Ok(())
+    Ok(0)
 }
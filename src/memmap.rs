@@ -0,0 +1,271 @@
+use anyhow::Result;
+
+use crate::elf::{ElfFile, ElfType};
+use crate::section::{SHF_EXECINSTR, SHF_WRITE, SectionType};
+use crate::segment::{ProgramHeader, SegmentType};
+
+const BOX_WIDTH: usize = 36;
+
+/// A notable address inside the loaded image.
+struct Landmark {
+    address: u64,
+    description: String,
+}
+
+fn landmarks(elf_file: &ElfFile, segments: &[ProgramHeader]) -> Vec<Landmark> {
+    let mut landmarks = vec![Landmark {
+        address: elf_file.entry(),
+        description: "entry point".to_string(),
+    }];
+
+    for segment in segments {
+        let end = segment.p_vaddr.saturating_add(segment.p_memsz);
+        let description = match segment.p_type {
+            SegmentType::INTERP => format!(
+                "interpreter: {}",
+                elf_file.interpreter().ok().flatten().unwrap_or_default()
+            ),
+            SegmentType::TLS => format!("TLS template (to {:#x})", end),
+            SegmentType::DYNAMIC => "dynamic section".to_string(),
+            SegmentType::GNU_RELRO => format!("RELRO start (to {:#x})", end),
+            SegmentType::GNU_EH_FRAME => ".eh_frame_hdr".to_string(),
+            SegmentType::PHDR => "program headers".to_string(),
+            // Where each memory of a target with several starts.
+            SegmentType::LOAD => match elf_file.machine().memory_region(segment.p_vaddr) {
+                Some((region, 0)) => format!("start of {}", region),
+                Some((region, offset)) => format!("{} + {:#x}", region, offset),
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        landmarks.push(Landmark {
+            address: segment.p_vaddr,
+            description,
+        });
+    }
+
+    landmarks.sort_by_key(|l| l.address);
+    landmarks
+}
+
+fn loads(segments: &[ProgramHeader]) -> Vec<&ProgramHeader> {
+    let mut loads: Vec<_> = segments
+        .iter()
+        .filter(|s| s.p_type == SegmentType::LOAD)
+        .collect();
+    loads.sort_by_key(|s| s.p_vaddr);
+    loads
+}
+
+fn stack_note(segments: &[ProgramHeader]) -> Option<String> {
+    segments
+        .iter()
+        .find(|s| s.p_type == SegmentType::GNU_STACK)
+        .map(|s| format!("Stack: {} (from PT_GNU_STACK)", s.flags_string().trim_end()))
+}
+
+/// Draws the loadable segments as boxes in address order, with landmarks
+/// such as the entry point and interpreter annotated next to the segment
+/// containing them.
+pub fn print_memory_map(elf_file: &ElfFile) -> Result<()> {
+    let segments = elf_file.program_headers()?;
+    let loads = loads(&segments);
+    if loads.is_empty() && elf_file.elf_type() == ElfType::REL {
+        return print_object_sections(elf_file);
+    }
+    if loads.is_empty() {
+        println!("There are no loadable segments in this file.");
+        return Ok(());
+    }
+
+    let landmarks = landmarks(elf_file, &segments);
+    let border = format!("+{}+", "-".repeat(BOX_WIDTH));
+
+    println!("Virtual memory map:");
+    let mut previous_end = None;
+    for load in &loads {
+        let start = load.p_vaddr;
+        // Corrupt sizes are cut short at the end of the address space.
+        let end = start.saturating_add(load.p_memsz);
+
+        if let Some(prev) = previous_end.filter(|&prev| start > prev) {
+            println!("  {:#018x} {}", prev, border);
+            let gap = format!("unmapped ({:#x} bytes)", start - prev);
+            println!("  {:18} :{:^width$}:", "", gap, width = BOX_WIDTH);
+        }
+
+        println!("  {:#018x} {}", start, border);
+
+        let label = format!("LOAD [{}] {:#x} bytes", load.flags_string(), load.p_memsz);
+        let mut notes: Vec<String> = landmarks
+            .iter()
+            .filter(|l| l.address >= start && l.address < end)
+            .map(|l| format!("<- {:#x} {}", l.address, l.description))
+            .collect();
+        if load.p_memsz > load.p_filesz {
+            notes.push(format!(
+                "<- {:#x} zero-filled (.bss)",
+                start.saturating_add(load.p_filesz)
+            ));
+        }
+
+        println!(
+            "  {:18} |{:^width$}| {}",
+            "",
+            label,
+            notes.first().map_or("", String::as_str),
+            width = BOX_WIDTH
+        );
+        for note in notes.iter().skip(1) {
+            println!("  {:18} |{:width$}| {}", "", "", note, width = BOX_WIDTH);
+        }
+
+        previous_end = Some(end);
+    }
+
+    if let Some(end) = previous_end {
+        println!("  {:#018x} {}", end, border);
+    }
+
+    if let Some(stack) = stack_note(&segments) {
+        println!();
+        println!("{}", stack);
+    }
+
+    Ok(())
+}
+
+/// For objects, which have no segments yet: the allocated sections grouped
+/// by the permissions of the segment a linker would put them in.
+fn print_object_sections(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let mut groups: Vec<(&str, u64, Vec<&str>)> = ["R  ", "R E", "RW "]
+        .into_iter()
+        .map(|flags| (flags, 0, Vec::new()))
+        .collect();
+    for section in sections.iter().filter(|s| s.is_alloc() && s.sh_size > 0) {
+        let group = if section.sh_flags & SHF_WRITE != 0 {
+            2
+        } else if section.sh_flags & SHF_EXECINSTR != 0 {
+            1
+        } else {
+            0
+        };
+        let name = elf_file.section_name(&sections, section).unwrap_or("?");
+        groups[group].1 = groups[group].1.saturating_add(section.sh_size);
+        groups[group].2.push(name);
+    }
+    groups.retain(|(_, _, names)| !names.is_empty());
+
+    println!("There are no loadable segments in this file: it is a relocatable object,");
+    println!("whose sections get their addresses when it is linked.");
+    if groups.is_empty() {
+        println!("None of its sections would be loaded.");
+        return Ok(());
+    }
+    println!("Allocated sections, by the permissions of the segment they would be loaded in:");
+    for (flags, size, names) in groups {
+        println!("  [{}] {:#10x} bytes  {}", flags, size, names.join(" "));
+    }
+    if sections
+        .iter()
+        .any(|s| s.is_alloc() && s.sh_size > 0 && s.sh_type == SectionType::NOBITS)
+    {
+        println!("The sizes include zero-filled sections, which take no room in the file.");
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Same layout as the text map, rendered as a standalone SVG document.
+pub fn print_memory_map_svg(elf_file: &ElfFile) -> Result<()> {
+    const ROW: usize = 60;
+    const GAP_ROW: usize = 24;
+    const LEFT: usize = 170;
+    const WIDTH: usize = 260;
+
+    let segments = elf_file.program_headers()?;
+    let loads = loads(&segments);
+    let landmarks = landmarks(elf_file, &segments);
+
+    let mut body = String::new();
+    let mut y = 20;
+    let mut previous_end = None;
+    for load in &loads {
+        let start = load.p_vaddr;
+        // Corrupt sizes are cut short at the end of the address space.
+        let end = start.saturating_add(load.p_memsz);
+
+        if let Some(prev) = previous_end.filter(|&prev| start > prev) {
+            body += &format!(
+                "<rect x=\"{LEFT}\" y=\"{y}\" width=\"{WIDTH}\" height=\"{GAP_ROW}\" fill=\"none\" stroke=\"#999\" stroke-dasharray=\"4\"/>\n\
+                 <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"#999\">unmapped ({:#x} bytes)</text>\n",
+                LEFT + WIDTH / 2,
+                y + 16,
+                start - prev
+            );
+            y += GAP_ROW;
+        }
+
+        let fill = match (load.is_writable(), load.is_executable()) {
+            (true, true) => "#f4a6a6",
+            (false, true) => "#a6c8f4",
+            (true, false) => "#f4e3a6",
+            (false, false) => "#d9d9d9",
+        };
+        body += &format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{:#x}</text>\n\
+             <rect x=\"{LEFT}\" y=\"{y}\" width=\"{WIDTH}\" height=\"{ROW}\" fill=\"{fill}\" stroke=\"black\"/>\n\
+             <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">LOAD [{}] {:#x} bytes</text>\n",
+            LEFT - 8,
+            y + 4,
+            start,
+            LEFT + WIDTH / 2,
+            y + ROW / 2 + 5,
+            escape(load.flags_string().trim_end()),
+            load.p_memsz
+        );
+
+        for (i, landmark) in landmarks
+            .iter()
+            .filter(|l| l.address >= start && l.address < end)
+            .enumerate()
+        {
+            body += &format!(
+                "<text x=\"{}\" y=\"{}\">&lt;- {:#x} {}</text>\n",
+                LEFT + WIDTH + 10,
+                y + 16 + i * 16,
+                landmark.address,
+                escape(&landmark.description)
+            );
+        }
+
+        y += ROW;
+        previous_end = Some(end);
+    }
+
+    if let Some(end) = previous_end {
+        body += &format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{:#x}</text>\n",
+            LEFT - 8,
+            y + 4,
+            end
+        );
+    }
+
+    println!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"12\">",
+        LEFT + WIDTH + 420,
+        y + 30
+    );
+    print!("{}", body);
+    println!("</svg>");
+
+    Ok(())
+}
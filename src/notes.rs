@@ -0,0 +1,635 @@
+use anyhow::Result;
+
+use crate::elf::{ElfFile, ElfType, read_struct};
+use crate::plugin::Registry;
+use crate::section::SectionType;
+use crate::segment::SegmentType;
+
+/// The type of a note in a core file, whose owner is `CORE` or `LINUX`.
+/// Other owners number their notes on their own.
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct CoreNoteType(pub u32);
+
+crate::consts::constants!(CoreNoteType(u32), nt, "NT_", {
+    PRSTATUS = 1,
+    FPREGSET = 2,
+    PRPSINFO = 3,
+    TASKSTRUCT = 4,
+    AUXV = 6,
+    PSTATUS = 10,
+    FPREGS = 12,
+    PSINFO = 13,
+    LWPSTATUS = 16,
+    LWPSINFO = 17,
+    WIN32PSTATUS = 18,
+    X86_XSTATE = 0x202,
+    X86_SHSTK = 0x204,
+    ARM_VFP = 0x400,
+    ARM_TLS = 0x401,
+    ARM_HW_BREAK = 0x402,
+    ARM_HW_WATCH = 0x403,
+    ARM_SYSTEM_CALL = 0x404,
+    ARM_SVE = 0x405,
+    ARM_PAC_MASK = 0x406,
+    ARM_TAGGED_ADDR_CTRL = 0x409,
+    FILE = 0x46494c45,
+    PRXFPREG = 0x46e62b7f,
+    SIGINFO = 0x53494749,
+});
+
+/// The type of a note whose owner is `GNU`.
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct GnuNoteType(pub u32);
+
+crate::consts::constants!(GnuNoteType(u32), nt_gnu, "NT_GNU_", {
+    ABI_TAG = 1,
+    HWCAP = 2,
+    BUILD_ID = 3,
+    GOLD_VERSION = 4,
+    PROPERTY_TYPE_0 = 5,
+    BUILD_ATTRIBUTE_OPEN = 0x100,
+    BUILD_ATTRIBUTE_FUNC = 0x101,
+});
+
+pub const NT_PRSTATUS: u32 = CoreNoteType::PRSTATUS.0;
+pub const NT_FPREGSET: u32 = CoreNoteType::FPREGSET.0;
+pub const NT_PRPSINFO: u32 = CoreNoteType::PRPSINFO.0;
+pub const NT_AUXV: u32 = CoreNoteType::AUXV.0;
+pub const NT_X86_XSTATE: u32 = CoreNoteType::X86_XSTATE.0;
+pub const NT_ARM_PAC_MASK: u32 = CoreNoteType::ARM_PAC_MASK.0;
+pub const NT_FILE: u32 = CoreNoteType::FILE.0;
+pub const NT_SIGINFO: u32 = CoreNoteType::SIGINFO.0;
+
+pub const NT_GNU_ABI_TAG: u32 = GnuNoteType::ABI_TAG.0;
+pub const NT_GNU_HWCAP: u32 = GnuNoteType::HWCAP.0;
+pub const NT_GNU_BUILD_ID: u32 = GnuNoteType::BUILD_ID.0;
+pub const NT_GNU_GOLD_VERSION: u32 = GnuNoteType::GOLD_VERSION.0;
+pub const NT_GNU_PROPERTY_TYPE_0: u32 = GnuNoteType::PROPERTY_TYPE_0.0;
+pub const NT_GNU_BUILD_ATTRIBUTE_OPEN: u32 = GnuNoteType::BUILD_ATTRIBUTE_OPEN.0;
+pub const NT_GNU_BUILD_ATTRIBUTE_FUNC: u32 = GnuNoteType::BUILD_ATTRIBUTE_FUNC.0;
+
+const NT_FREEBSD_ABI_TAG: u32 = 1;
+const NT_FREEBSD_NOINIT_TAG: u32 = 2;
+const NT_FREEBSD_ARCH_TAG: u32 = 3;
+const NT_FREEBSD_FEATURE_CTL: u32 = 4;
+
+const NT_NETBSD_IDENT: u32 = 1;
+const NT_NETBSD_PAX: u32 = 3;
+const NT_NETBSD_MARCH: u32 = 5;
+
+const NT_OPENBSD_IDENT: u32 = 1;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+struct NoteHeader {
+    n_namesz: u32,
+    n_descsz: u32,
+    n_type: u32,
+}
+
+/// A single entry of a note section or segment.
+#[derive(Debug, Clone)]
+pub struct Note<'a> {
+    pub name: &'a str,
+    pub n_type: u32,
+    pub desc: &'a [u8],
+}
+
+/// What parsing a note section or segment found: the well-formed entries,
+/// and a description of every malformed one that was skipped.
+#[derive(Debug, Default)]
+pub struct ParsedNotes<'a> {
+    pub notes: Vec<Note<'a>>,
+    pub warnings: Vec<String>,
+}
+
+/// Splits the contents of a note section or segment into its entries.
+/// `align` is the section or segment alignment, which decides whether the
+/// descriptor and the next entry start on a 4 or 8 byte boundary.
+///
+/// Not every producer agrees with its own alignment: notes padded to 4 bytes
+/// turn up in 8-byte aligned segments, so when the 8-byte layout does not
+/// parse cleanly the 4-byte one is tried as well. Alignments other than 4
+/// and 8 are read as 4, like readelf does.
+pub fn parse_notes(data: &[u8], align: u64) -> ParsedNotes<'_> {
+    let mut parsed = parse_aligned(data, if align == 8 { 8 } else { 4 });
+    if align == 8 && !parsed.warnings.is_empty() {
+        let fallback = parse_aligned(data, 4);
+        if fallback.warnings.is_empty() {
+            return fallback;
+        }
+    }
+    if !matches!(align, 0 | 1 | 2 | 4 | 8) {
+        parsed.warnings.insert(
+            0,
+            format!("Unexpected note alignment {}, reading it as 4", align),
+        );
+    }
+    parsed
+}
+
+fn parse_aligned(data: &[u8], align: usize) -> ParsedNotes<'_> {
+    let pad = |n: usize| n.div_ceil(align) * align;
+    let header_size = std::mem::size_of::<NoteHeader>();
+
+    let mut parsed = ParsedNotes::default();
+    let mut offset = 0;
+    while offset + header_size <= data.len() {
+        // Zero padding at the end of a segment is not an entry.
+        if data[offset..].iter().all(|&b| b == 0) {
+            break;
+        }
+
+        let header: NoteHeader = read_struct(data, offset as u64).unwrap();
+        let name_start = offset + header_size;
+        let desc_start = pad(name_start + header.n_namesz as usize);
+        let desc_end = desc_start + header.n_descsz as usize;
+
+        let Some(name) = data.get(name_start..name_start + header.n_namesz as usize) else {
+            parsed.warnings.push(format!(
+                "Corrupt note at offset {:#x}: the name extends past the end of the note data",
+                offset
+            ));
+            break;
+        };
+        let Some(desc) = data.get(desc_start..desc_end) else {
+            parsed.warnings.push(format!(
+                "Corrupt note at offset {:#x}: the descriptor extends past the end of the note data",
+                offset
+            ));
+            break;
+        };
+
+        // The sizes are fine, so a bad name only costs this entry.
+        match std::str::from_utf8(name) {
+            Ok(name) => parsed.notes.push(Note {
+                name: name.trim_end_matches('\0'),
+                n_type: header.n_type,
+                desc,
+            }),
+            Err(_) => parsed.warnings.push(format!(
+                "Skipping note at offset {:#x}: its name is not valid UTF-8",
+                offset
+            )),
+        }
+
+        offset = pad(desc_end);
+    }
+
+    if offset < data.len() && offset + header_size > data.len() && parsed.warnings.is_empty() {
+        let rest = &data[offset..];
+        if rest.iter().any(|&b| b != 0) {
+            parsed.warnings.push(format!(
+                "Ignoring {} trailing bytes at offset {:#x} that are too short for a note",
+                rest.len(),
+                offset
+            ));
+        }
+    }
+
+    parsed
+}
+
+/// The note sections of the file or, when it has no section headers, its
+/// PT_NOTE segments, as (label, data, alignment). The label is what readelf
+/// prints above the notes of each.
+fn note_areas<'a>(elf_file: &'a ElfFile) -> Result<Vec<(String, &'a [u8], u64)>> {
+    let sections = elf_file.section_headers()?;
+    let mut areas = Vec::new();
+
+    if sections.is_empty() {
+        for segment in elf_file.program_headers()? {
+            if segment.p_type == SegmentType::NOTE {
+                areas.push((
+                    format!(
+                        "at file offset 0x{:08x} with length 0x{:08x}:",
+                        segment.p_offset, segment.p_filesz
+                    ),
+                    elf_file.segment_data(&segment)?,
+                    segment.p_align,
+                ));
+            }
+        }
+    } else {
+        for section in &sections {
+            if section.sh_type == SectionType::NOTE {
+                areas.push((
+                    format!(
+                        "in: {}",
+                        elf_file.section_name(&sections, section).unwrap_or("")
+                    ),
+                    elf_file.section_data(section)?,
+                    section.sh_addralign,
+                ));
+            }
+        }
+    }
+
+    Ok(areas)
+}
+
+/// All notes in the file, taken from the NOTE sections or, when the file
+/// has no section headers, from the PT_NOTE segments. Malformed entries
+/// are left out.
+pub fn notes<'a>(elf_file: &'a ElfFile) -> Result<Vec<Note<'a>>> {
+    Ok(note_areas(elf_file)?
+        .into_iter()
+        .flat_map(|(_, data, align)| parse_notes(data, align).notes)
+        .collect())
+}
+
+/// The GNU build ID as a hex string, if the file has one.
+pub fn build_id(elf_file: &ElfFile) -> Result<Option<String>> {
+    Ok(notes(elf_file)?
+        .iter()
+        .find(|n| n.name == "GNU" && n.n_type == NT_GNU_BUILD_ID)
+        .map(|n| n.desc.iter().map(|b| format!("{:02x}", b)).collect()))
+}
+
+const GNU_PROPERTY_STACK_SIZE: u32 = 1;
+const GNU_PROPERTY_NO_COPY_ON_PROTECTED: u32 = 2;
+const GNU_PROPERTY_1_NEEDED: u32 = 0xb0008000;
+pub const GNU_PROPERTY_AARCH64_FEATURE_1_AND: u32 = 0xc0000000;
+const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc0000002;
+pub const GNU_PROPERTY_X86_ISA_1_NEEDED: u32 = 0xc0008002;
+pub const GNU_PROPERTY_X86_FEATURE_2_NEEDED: u32 = 0xc0008001;
+pub const GNU_PROPERTY_X86_FEATURE_2_USED: u32 = 0xc0010001;
+pub const GNU_PROPERTY_X86_ISA_1_USED: u32 = 0xc0010002;
+
+/// The x86-64 microarchitecture levels, by bit of the ISA properties.
+pub const X86_ISA: &[&str] = &["x86-64-baseline", "x86-64-v2", "x86-64-v3", "x86-64-v4"];
+/// The register and instruction set features, by bit of the FEATURE_2
+/// properties.
+pub const X86_FEATURE_2: &[&str] = &[
+    "x86", "x87", "MMX", "XMM", "YMM", "ZMM", "FXSR", "XSAVE", "XSAVEOPT", "XSAVEC", "TMM", "MASK",
+];
+
+/// Names of the bits set in `value`, or "<None>" when there are none.
+pub fn bit_names(value: u32, names: &[&str]) -> String {
+    let mut set: Vec<String> = (0..32)
+        .filter(|bit| value & (1 << bit) != 0)
+        .map(|bit| {
+            names.get(bit).map_or_else(
+                || format!("<unknown: {:x}>", 1u32 << bit),
+                |n| n.to_string(),
+            )
+        })
+        .collect();
+    if set.is_empty() {
+        set.push("<None>".to_string());
+    }
+    set.join(", ")
+}
+
+/// Decodes the properties of an NT_GNU_PROPERTY_TYPE_0 note into one
+/// readelf-style description per property.
+pub fn gnu_properties(desc: &[u8], is_64: bool) -> Vec<String> {
+    let mut properties = Vec::new();
+    for (pr_type, data) in property_entries(desc, is_64) {
+        let Some(data) = data else {
+            properties.push(format!("<corrupt property of type {:#x}>", pr_type));
+            break;
+        };
+        let size = data.len();
+        let word = (size == 4).then(|| u32::from_ne_bytes(data.try_into().unwrap()));
+
+        let text = match (pr_type, word) {
+            (GNU_PROPERTY_STACK_SIZE, _) if size == 8 => format!(
+                "stack size: {:#x}",
+                u64::from_ne_bytes(data.try_into().unwrap())
+            ),
+            (GNU_PROPERTY_STACK_SIZE, Some(w)) => format!("stack size: {:#x}", w),
+            (GNU_PROPERTY_NO_COPY_ON_PROTECTED, _) => "no copy on protected".to_string(),
+            (GNU_PROPERTY_1_NEEDED, Some(w)) => {
+                format!("1_needed: {}", bit_names(w, &["indirect external access"]))
+            }
+            (GNU_PROPERTY_X86_FEATURE_1_AND, Some(w)) => {
+                format!(
+                    "x86 feature: {}",
+                    bit_names(w, &["IBT", "SHSTK", "LAM_U48", "LAM_U57"])
+                )
+            }
+            (GNU_PROPERTY_X86_ISA_1_NEEDED, Some(w)) => {
+                format!("x86 ISA needed: {}", bit_names(w, X86_ISA))
+            }
+            (GNU_PROPERTY_X86_ISA_1_USED, Some(w)) => {
+                format!("x86 ISA used: {}", bit_names(w, X86_ISA))
+            }
+            (GNU_PROPERTY_X86_FEATURE_2_NEEDED, Some(w)) => {
+                format!("x86 feature needed: {}", bit_names(w, X86_FEATURE_2))
+            }
+            (GNU_PROPERTY_X86_FEATURE_2_USED, Some(w)) => {
+                format!("x86 feature used: {}", bit_names(w, X86_FEATURE_2))
+            }
+            (GNU_PROPERTY_AARCH64_FEATURE_1_AND, Some(w)) => {
+                format!("AArch64 feature: {}", bit_names(w, &["BTI", "PAC", "GCS"]))
+            }
+            _ => format!("<unknown property {:#x}, {} bytes>", pr_type, size),
+        };
+        properties.push(text);
+    }
+
+    properties
+}
+
+/// The type and data of each property in an NT_GNU_PROPERTY_TYPE_0
+/// descriptor. A property that runs past the end of the descriptor ends
+/// the list, with `None` for its data.
+fn property_entries(desc: &[u8], is_64: bool) -> Vec<(u32, Option<&[u8]>)> {
+    let align = if is_64 { 8 } else { 4 };
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + 8 <= desc.len() {
+        let pr_type = u32::from_ne_bytes(desc[offset..offset + 4].try_into().unwrap());
+        let size = u32::from_ne_bytes(desc[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let data = desc.get(offset + 8..offset + 8 + size);
+        entries.push((pr_type, data));
+        if data.is_none() {
+            break;
+        }
+        offset += 8 + size.div_ceil(align) * align;
+    }
+    entries
+}
+
+/// The value of the 4-byte GNU property `pr_type`, from the property
+/// notes of the file.
+pub fn gnu_property(elf_file: &ElfFile, pr_type: u32) -> Result<Option<u32>> {
+    Ok(notes(elf_file)?
+        .iter()
+        .filter(|n| n.name == "GNU" && n.n_type == NT_GNU_PROPERTY_TYPE_0)
+        .flat_map(|n| property_entries(n.desc, elf_file.is_64()))
+        .find_map(|(t, data)| match data {
+            Some(data) if t == pr_type && data.len() == 4 => {
+                Some(u32::from_ne_bytes(data.try_into().unwrap()))
+            }
+            _ => None,
+        }))
+}
+
+/// Core file note types shared by Linux and most other systems.
+fn core_type_name(n_type: u32) -> &'static str {
+    match CoreNoteType(n_type) {
+        CoreNoteType::PRSTATUS => "NT_PRSTATUS (prstatus structure)",
+        CoreNoteType::FPREGSET => "NT_FPREGSET (floating point registers)",
+        CoreNoteType::PRPSINFO => "NT_PRPSINFO (prpsinfo structure)",
+        CoreNoteType::TASKSTRUCT => "NT_TASKSTRUCT (task structure)",
+        CoreNoteType::AUXV => "NT_AUXV (auxiliary vector)",
+        CoreNoteType::PSTATUS => "NT_PSTATUS (pstatus structure)",
+        CoreNoteType::FPREGS => "NT_FPREGS (floating point registers)",
+        CoreNoteType::PSINFO => "NT_PSINFO (psinfo structure)",
+        CoreNoteType::LWPSTATUS => "NT_LWPSTATUS (lwpstatus_t structure)",
+        CoreNoteType::LWPSINFO => "NT_LWPSINFO (lwpsinfo_t structure)",
+        CoreNoteType::WIN32PSTATUS => "NT_WIN32PSTATUS (win32_pstatus structure)",
+        // NT_386_TLS and NT_386_IOPERM, whose names are no identifiers.
+        CoreNoteType(0x200) => "NT_386_TLS (x86 TLS information)",
+        CoreNoteType(0x201) => "NT_386_IOPERM (x86 I/O permissions)",
+        CoreNoteType::X86_XSTATE => "NT_X86_XSTATE (x86 XSAVE extended state)",
+        CoreNoteType::X86_SHSTK => "NT_X86_SHSTK (x86 SHSTK state)",
+        CoreNoteType::ARM_VFP => "NT_ARM_VFP (arm VFP registers)",
+        CoreNoteType::ARM_TLS => "NT_ARM_TLS (AArch TLS registers)",
+        CoreNoteType::ARM_HW_BREAK => "NT_ARM_HW_BREAK (AArch hardware breakpoint registers)",
+        CoreNoteType::ARM_HW_WATCH => "NT_ARM_HW_WATCH (AArch hardware watchpoint registers)",
+        CoreNoteType::ARM_SYSTEM_CALL => "NT_ARM_SYSTEM_CALL (AArch system call number)",
+        CoreNoteType::ARM_SVE => "NT_ARM_SVE (AArch SVE registers)",
+        CoreNoteType::ARM_PAC_MASK => "NT_ARM_PAC_MASK (AArch pointer authentication code masks)",
+        CoreNoteType::ARM_TAGGED_ADDR_CTRL => {
+            "NT_ARM_TAGGED_ADDR_CTRL (AArch tagged address control)"
+        }
+        CoreNoteType::FILE => "NT_FILE (mapped files)",
+        CoreNoteType::PRXFPREG => "NT_PRXFPREG (user_xfpregs structure)",
+        CoreNoteType::SIGINFO => "NT_SIGINFO (siginfo_t data)",
+        _ => "",
+    }
+}
+
+/// FreeBSD core notes, which reuse the common numbers below 7.
+fn freebsd_core_type_name(n_type: u32) -> &'static str {
+    match n_type {
+        7 => "NT_THRMISC (thrmisc structure)",
+        8 => "NT_PROCSTAT_PROC (proc data)",
+        9 => "NT_PROCSTAT_FILES (files data)",
+        10 => "NT_PROCSTAT_VMMAP (vmmap data)",
+        11 => "NT_PROCSTAT_GROUPS (groups data)",
+        12 => "NT_PROCSTAT_UMASK (umask data)",
+        13 => "NT_PROCSTAT_RLIMIT (rlimit data)",
+        14 => "NT_PROCSTAT_OSREL (osreldate data)",
+        15 => "NT_PROCSTAT_PSSTRINGS (ps_strings data)",
+        16 => "NT_PROCSTAT_AUXV (auxv data)",
+        17 => "NT_PTLWPINFO (ptrace_lwpinfo structure)",
+        0x200 => "NT_X86_SEGBASES (x86 segment base registers)",
+        n => core_type_name(n),
+    }
+}
+
+/// How readelf names a note type. Owners other than GNU and the BSDs share
+/// one set of numbers, which mean something else in core files.
+pub fn type_name(elf_type: ElfType, owner: &str, n_type: u32) -> String {
+    let core = elf_type == ElfType::CORE;
+    let name = match (owner, n_type) {
+        ("GNU", NT_GNU_ABI_TAG) => "NT_GNU_ABI_TAG (ABI version tag)",
+        ("GNU", NT_GNU_HWCAP) => "NT_GNU_HWCAP (DSO-supplied software HWCAP info)",
+        ("GNU", NT_GNU_BUILD_ID) => "NT_GNU_BUILD_ID (unique build ID bitstring)",
+        ("GNU", NT_GNU_GOLD_VERSION) => "NT_GNU_GOLD_VERSION (gold version)",
+        ("GNU", NT_GNU_PROPERTY_TYPE_0) => "NT_GNU_PROPERTY_TYPE_0",
+        ("GNU", NT_GNU_BUILD_ATTRIBUTE_OPEN) => "NT_GNU_BUILD_ATTRIBUTE_OPEN",
+        ("GNU", NT_GNU_BUILD_ATTRIBUTE_FUNC) => "NT_GNU_BUILD_ATTRIBUTE_FUNC",
+        ("GNU", _) => "",
+        ("FreeBSD", n_type) if core => freebsd_core_type_name(n_type),
+        ("FreeBSD", NT_FREEBSD_ABI_TAG) => "NT_FREEBSD_ABI_TAG (ABI version tag)",
+        ("FreeBSD", NT_FREEBSD_NOINIT_TAG) => "NT_FREEBSD_NOINIT_TAG (no .init section)",
+        ("FreeBSD", NT_FREEBSD_ARCH_TAG) => "NT_FREEBSD_ARCH_TAG (architecture tag)",
+        ("FreeBSD", NT_FREEBSD_FEATURE_CTL) => "NT_FREEBSD_FEATURE_CTL (feature control)",
+        ("NetBSD", NT_NETBSD_IDENT) => "NT_NETBSD_IDENT (version tag)",
+        ("NetBSD" | "PaX", NT_NETBSD_PAX) => "NT_NETBSD_PAX (PaX flags)",
+        ("NetBSD", NT_NETBSD_MARCH) => "NT_NETBSD_MARCH (machine architecture)",
+        ("NetBSD-CORE", 1) => "NetBSD procinfo structure",
+        ("NetBSD-CORE", 2) => "NetBSD ELF auxiliary vector data",
+        ("NetBSD-CORE", 24) => "PT_LWPSTATUS (ptrace_lwpstatus structure)",
+        // Machine dependent register sets, named after the ptrace request
+        // that reads them.
+        ("NetBSD-CORE", n_type @ 32..) => return format!("PT_FIRSTMACH+{}", n_type - 32),
+        ("OpenBSD", 10) if core => "NT_OPENBSD_PROCINFO",
+        ("OpenBSD", 11) if core => "NT_OPENBSD_AUXV",
+        ("OpenBSD", 20) if core => "NT_OPENBSD_REGS",
+        ("OpenBSD", 21) if core => "NT_OPENBSD_FPREGS",
+        ("OpenBSD", 22) if core => "NT_OPENBSD_XFPREGS",
+        ("OpenBSD", 80) if core => "NT_OPENBSD_WCOOKIE",
+        ("OpenBSD", NT_OPENBSD_IDENT) if !core => "NT_OPENBSD_IDENT (version tag)",
+        (_, n_type) if core => core_type_name(n_type),
+        (_, 1) => "NT_VERSION (version)",
+        (_, 2) => "NT_ARCH (architecture)",
+        _ => "",
+    };
+
+    if name.is_empty() {
+        format!("Unknown note type: (0x{:08x})", n_type)
+    } else {
+        name.to_string()
+    }
+}
+
+/// A NetBSD version number, `MMmmrrpp00`, written the way NetBSD does:
+/// `9.99.81` for a development snapshot, `10.0` for a release, and with
+/// the branch letter of old releases, such as `1.6A`.
+fn netbsd_version(version: u32) -> String {
+    let major = version / 100000000;
+    let minor = (version / 1000000) % 100;
+    let patch = (version / 100) % 100;
+    match (version / 10000) % 100 {
+        0 if patch == 0 => format!("{}.{}", major, minor),
+        0 => format!("{}.{}.{}", major, minor, patch),
+        branch => format!(
+            "{}.{}{}{}",
+            major,
+            minor,
+            if branch > 26 { "Z" } else { "" },
+            (b'A' + ((branch - 1) % 26) as u8) as char
+        ),
+    }
+}
+
+/// The descriptor of a note decoded the way readelf shows it, for the note
+/// types it knows.
+pub fn describe(elf_file: &ElfFile, note: &Note) -> Option<String> {
+    let words: Vec<u32> = note
+        .desc
+        .chunks_exact(4)
+        .map(|w| u32::from_ne_bytes(w.try_into().unwrap()))
+        .collect();
+
+    match (note.name, note.n_type) {
+        ("GNU", NT_GNU_ABI_TAG) if words.len() >= 4 => {
+            let os = match words[0] {
+                0 => "Linux",
+                1 => "Hurd",
+                2 => "Solaris",
+                3 => "FreeBSD",
+                4 => "NetBSD",
+                5 => "Syllable",
+                _ => "Unknown",
+            };
+            Some(format!(
+                "OS: {}, ABI: {}.{}.{}",
+                os, words[1], words[2], words[3]
+            ))
+        }
+        ("GNU", NT_GNU_ABI_TAG) => Some("<corrupt GNU_ABI_TAG>".to_string()),
+        ("GNU", NT_GNU_HWCAP) if words.len() >= 2 => Some(format!(
+            "Hardware capabilities: {}, mask {:#x}",
+            words[0], words[1]
+        )),
+        ("GNU", NT_GNU_HWCAP) => Some("<corrupt GNU_HWCAP>".to_string()),
+        ("GNU", NT_GNU_BUILD_ID) => Some(format!(
+            "Build ID: {}",
+            note.desc
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        )),
+        ("GNU", NT_GNU_GOLD_VERSION) => Some(format!(
+            "Version: {}",
+            String::from_utf8_lossy(note.desc).trim_end_matches('\0')
+        )),
+        ("GNU", NT_GNU_PROPERTY_TYPE_0) => Some(format!(
+            "Properties: {}",
+            gnu_properties(note.desc, elf_file.is_64()).join(", ")
+        )),
+        _ if elf_file.elf_type() == ElfType::CORE => None,
+        ("FreeBSD", NT_FREEBSD_ABI_TAG) if words.len() == 1 => {
+            Some(format!("ABI tag: {}", words[0]))
+        }
+        ("FreeBSD", NT_FREEBSD_NOINIT_TAG) => Some("No .init section".to_string()),
+        ("FreeBSD", NT_FREEBSD_ARCH_TAG) => Some(format!(
+            "Arch tag: {}",
+            String::from_utf8_lossy(note.desc).trim_end_matches('\0')
+        )),
+        ("FreeBSD", NT_FREEBSD_FEATURE_CTL) if words.len() == 1 => Some(format!(
+            "Features: {}",
+            bit_names(
+                words[0],
+                &[
+                    "ASLR_DISABLE",
+                    "PROTMAX_DISABLE",
+                    "STKGAP_DISABLE",
+                    "WXNEEDED",
+                    "LA48",
+                    "ASG_DISABLE",
+                ]
+            )
+        )),
+        ("NetBSD", NT_NETBSD_IDENT) if words.len() == 1 => Some(format!(
+            "Version: {} ({})",
+            netbsd_version(words[0]),
+            words[0]
+        )),
+        ("NetBSD" | "PaX", NT_NETBSD_PAX) if words.len() == 1 => Some(format!(
+            "PaX: {}",
+            bit_names(
+                words[0],
+                &[
+                    "+mprotect",
+                    "-mprotect",
+                    "+guard",
+                    "-guard",
+                    "+ASLR",
+                    "-ASLR"
+                ]
+            )
+        )),
+        ("NetBSD", NT_NETBSD_MARCH) => Some(format!(
+            "Machine: {}",
+            String::from_utf8_lossy(note.desc).trim_end_matches('\0')
+        )),
+        ("OpenBSD", NT_OPENBSD_IDENT) if words.len() == 1 => Some(format!("Version: {}", words[0])),
+        _ => None,
+    }
+}
+
+/// Prints every note in readelf's `-n` format. Notes readelf does not know
+/// are handed to the registered decoders, and dumped as bytes if none of
+/// them knows the note either. Malformed entries are skipped with a warning
+/// rather than ending the report.
+pub fn print_notes(elf_file: &ElfFile, registry: &Registry) -> Result<()> {
+    let areas = note_areas(elf_file)?;
+
+    for (label, data, align) in areas {
+        let parsed = parse_notes(data, align);
+
+        println!();
+        println!("Displaying notes found {}", label);
+        println!("  {:<20} Data size \tDescription", "Owner");
+        for note in &parsed.notes {
+            print!(
+                "  {:<20} 0x{:08x}\t{}\t",
+                note.name,
+                note.desc.len(),
+                type_name(elf_file.elf_type(), note.name, note.n_type)
+            );
+
+            let indent = if note.name == "GNU" && note.n_type == NT_GNU_PROPERTY_TYPE_0 {
+                6
+            } else {
+                4
+            };
+            if let Some(description) = describe(elf_file, note) {
+                println!("{:indent$}{}", "", description);
+            } else if let Some(decoder) = registry.note_decoder(note.name, note.n_type) {
+                let lines = decoder.decode(elf_file, note)?;
+                println!("{:indent$}{}", "", lines.join(&format!("\n{:indent$}", "")));
+            } else if note.desc.is_empty() {
+                println!();
+            } else {
+                let bytes: String = note.desc.iter().map(|b| format!("{:02x} ", b)).collect();
+                println!("   description data: {}", bytes);
+            }
+        }
+        for warning in &parsed.warnings {
+            eprintln!("readelf-rs: Warning: {}", warning);
+        }
+    }
+
+    Ok(())
+}
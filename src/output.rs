@@ -0,0 +1,27 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Points the process's stdout at `path`, truncating it, so every later
+/// `println!` ends up in the file.
+#[cfg(unix)]
+pub fn redirect_stdout(path: &Path) -> Result<()> {
+    use std::os::fd::AsRawFd;
+
+    std::io::stdout().flush()?;
+
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    if unsafe { libc::dup2(file.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("Failed to redirect output to {}", path.display()));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn redirect_stdout(_path: &Path) -> Result<()> {
+    anyhow::bail!("Output redirection is only supported on Unix systems");
+}
@@ -0,0 +1,168 @@
+//! Data appended to an ELF file past everything its headers describe: the
+//! payloads of installers and self-extracting archives, bundled
+//! interpreters' archives, and signatures. The loader never maps it, so
+//! the program reads it back from its own file.
+
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+
+use crate::elf::ElfFile;
+use crate::entropy;
+
+/// The marker the kernel's sign-file puts at the very end of a signed
+/// module.
+const MODULE_SIGNATURE: &[u8] = b"~Module signature appended~\n";
+
+/// The end of the last thing the headers account for: the header tables,
+/// the sections and the file contents of the segments. Ends past the
+/// largest offset, from corrupt sizes, are cut short there.
+pub fn described_end(elf_file: &ElfFile) -> Result<u64> {
+    let header = elf_file.header();
+    let sections = elf_file.section_headers()?;
+    let segments = elf_file.program_headers()?;
+    let mut end = header.e_ehsize as u64;
+    if !segments.is_empty() {
+        end = end.max(
+            header
+                .e_phoff
+                .saturating_add(segments.len() as u64 * header.e_phentsize as u64),
+        );
+    }
+    if !sections.is_empty() {
+        end = end.max(
+            header
+                .e_shoff
+                .saturating_add(sections.len() as u64 * header.e_shentsize as u64),
+        );
+    }
+    for section in &sections {
+        if section.has_file_data() {
+            end = end.max(section.sh_offset.saturating_add(section.sh_size));
+        }
+    }
+    for segment in &segments {
+        end = end.max(segment.p_offset.saturating_add(segment.p_filesz));
+    }
+    Ok(end)
+}
+
+/// What the appended data looks like, from its first bytes and, for
+/// formats that put their trailer last, its last ones.
+fn guess(data: &[u8]) -> String {
+    let starts = |magic: &[u8]| data.starts_with(magic);
+    let ends = |magic: &[u8]| data.ends_with(magic);
+    if ends(MODULE_SIGNATURE) {
+        return "a kernel module signature".to_string();
+    }
+    // PyInstaller puts its cookie, starting with this magic, at the end.
+    if data.len() >= 88
+        && data[data.len() - 88..]
+            .windows(8)
+            .any(|w| w == b"MEI\x0c\x0b\x0a\x0b\x0e")
+    {
+        return "a PyInstaller archive".to_string();
+    }
+    // The end of central directory record of a zip is in its last 64 KiB.
+    let tail = &data[data.len().saturating_sub(65557)..];
+    if tail.windows(4).any(|w| w == b"PK\x05\x06") {
+        return if starts(b"PK\x03\x04") {
+            "a zip archive (or jar, or a self-extracting archive's payload)".to_string()
+        } else {
+            "a zip archive, with its entries after other data".to_string()
+        };
+    }
+    if data.len() > 262 && &data[257..262] == b"ustar" {
+        return "a tar archive".to_string();
+    }
+    let magics: &[(&[u8], &str)] = &[
+        (b"\x1f\x8b", "gzip-compressed data"),
+        (b"\xfd7zXZ\x00", "xz-compressed data"),
+        (b"\x28\xb5\x2f\xfd", "zstd-compressed data"),
+        (b"BZh", "bzip2-compressed data"),
+        (b"7z\xbc\xaf\x27\x1c", "a 7-Zip archive"),
+        (b"Rar!\x1a\x07", "a RAR archive"),
+        (b"hsqs", "a SquashFS image (as in AppImages)"),
+        (b"070701", "a cpio archive"),
+        (b"\x7fELF", "another ELF file"),
+        (b"MZ", "a PE (Windows) executable"),
+        (b"#!", "a script"),
+        (b"\x30\x82", "DER-encoded data, such as a PKCS#7 signature"),
+    ];
+    for (magic, name) in magics {
+        if starts(magic) {
+            return name.to_string();
+        }
+    }
+    if data.iter().all(|&b| b == 0) {
+        return "zero padding".to_string();
+    }
+    if data
+        .iter()
+        .all(|&b| b.is_ascii_graphic() || b.is_ascii_whitespace())
+    {
+        return "text".to_string();
+    }
+    let entropy = entropy::entropy(data);
+    if entropy > 7.5 {
+        format!(
+            "unrecognized; {:.2} bits of entropy per byte suggest compressed or encrypted data",
+            entropy
+        )
+    } else {
+        format!("unrecognized ({:.2} bits of entropy per byte)", entropy)
+    }
+}
+
+/// The bytes after the end of the ELF contents, and where they start.
+fn overlay<'a>(elf_file: &'a ElfFile) -> Result<(u64, &'a [u8])> {
+    let end = described_end(elf_file)?;
+    let data = elf_file.data();
+    Ok((end, data.get(end as usize..).unwrap_or(&[])))
+}
+
+/// Reports data appended past the end of what the headers describe.
+pub fn print_overlay(elf_file: &ElfFile) -> Result<()> {
+    let (start, data) = overlay(elf_file)?;
+    let file_size = elf_file.data().len() as u64;
+    println!("Appended data:");
+    if start > file_size {
+        println!(
+            "  None, and the file is truncated: its headers describe {:#x} bytes but it has {:#x}.",
+            start, file_size
+        );
+        return Ok(());
+    }
+    if data.is_empty() {
+        println!(
+            "  None: the ELF contents end at {:#x}, the end of the file.",
+            start
+        );
+        return Ok(());
+    }
+    println!("  Offset:  {:#x}", start);
+    println!(
+        "  Size:    {:#x} bytes ({:.1}% of the file)",
+        data.len(),
+        data.len() as f64 * 100.0 / file_size.max(1) as f64
+    );
+    println!("  Type:    {}", guess(data));
+    let preview: Vec<String> = data.iter().take(16).map(|b| format!("{:02x}", b)).collect();
+    println!("  Starts:  {}", preview.join(" "));
+    Ok(())
+}
+
+/// Writes the appended data to `path`.
+pub fn extract_overlay(elf_file: &ElfFile, path: &Path) -> Result<()> {
+    let (start, data) = overlay(elf_file)?;
+    if data.is_empty() {
+        bail!("There is no data appended to the file to extract");
+    }
+    std::fs::write(path, data).with_context(|| format!("Failed to write {}", path.display()))?;
+    println!(
+        "Wrote {:#x} bytes appended at {:#x} to {}",
+        data.len(),
+        start,
+        path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! The bytes alignment leaves between sections: in the file, where the
+//! linker pads each section out to its alignment and each segment to its
+//! page, and in memory, where the loader maps whole pages.
+
+use anyhow::Result;
+
+use crate::elf::ElfFile;
+use crate::section::{SHF_TLS, SectionType};
+use crate::segment::{ProgramHeader, SegmentType};
+
+/// The page size the loader rounds segments to when their alignment is
+/// smaller.
+const PAGE_SIZE: u64 = 0x1000;
+
+/// A run of bytes nothing in the file or image uses, and what comes after
+/// it.
+struct Gap {
+    memory: bool,
+    start: u64,
+    size: u64,
+    before: String,
+    cause: String,
+}
+
+/// Why there are `size` bytes before something aligned to `align` at
+/// `start`, which `segment` starts at when it is the first thing in it.
+fn cause(start: u64, size: u64, align: u64, segment: Option<&ProgramHeader>) -> String {
+    if let Some(segment) = segment {
+        return format!(
+            "starts a new segment, aligned to {:#x}",
+            segment.p_align.max(1)
+        );
+    }
+    if align > 1 && start.is_multiple_of(align) && size < align {
+        format!("alignment to {:#x}", align)
+    } else {
+        "unused bytes".to_string()
+    }
+}
+
+/// Prints how many file and memory bytes go to padding, by segment, and
+/// the `top` largest gaps.
+pub fn print_padding(elf_file: &ElfFile, top: usize) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let segments = elf_file.program_headers()?;
+    let loads: Vec<&ProgramHeader> = segments
+        .iter()
+        .filter(|s| s.p_type == SegmentType::LOAD)
+        .collect();
+    let header = elf_file.header();
+    let file_size = elf_file.data().len() as u64;
+
+    // Everything that takes up room in the file: the headers and the
+    // sections with contents.
+    let mut contents: Vec<(u64, u64, u64, String)> =
+        vec![(0, header.e_ehsize as u64, 1, "the ELF header".to_string())];
+    if !segments.is_empty() {
+        contents.push((
+            header.e_phoff,
+            segments.len() as u64 * header.e_phentsize as u64,
+            8,
+            "the program headers".to_string(),
+        ));
+    }
+    if !sections.is_empty() {
+        contents.push((
+            header.e_shoff,
+            sections.len() as u64 * header.e_shentsize as u64,
+            8,
+            "the section headers".to_string(),
+        ));
+    }
+    for section in &sections {
+        if section.sh_type == SectionType::NOBITS
+            || section.sh_type == SectionType::NULL
+            || section.sh_size == 0
+        {
+            continue;
+        }
+        contents.push((
+            section.sh_offset,
+            section.sh_size,
+            section.sh_addralign,
+            elf_file.section_name(&sections, section)?.to_string(),
+        ));
+    }
+    contents.sort_by_key(|&(offset, size, _, _)| (offset, size));
+
+    let mut gaps = Vec::new();
+    let mut end = 0;
+    for (offset, size, align, name) in &contents {
+        if *offset > end {
+            let segment = loads.iter().find(|s| s.p_offset == *offset).copied();
+            gaps.push(Gap {
+                memory: false,
+                start: end,
+                size: offset - end,
+                before: name.clone(),
+                cause: cause(*offset, offset - end, *align, segment),
+            });
+        }
+        end = end.max(offset.saturating_add(*size));
+    }
+    if file_size > end {
+        gaps.push(Gap {
+            memory: false,
+            start: end,
+            size: file_size - end,
+            before: "the end of the file".to_string(),
+            cause: "unused bytes".to_string(),
+        });
+    }
+
+    // The sections of each segment, as the loader maps them.
+    let mut alloc: Vec<_> = sections
+        .iter()
+        .filter(|s| s.is_alloc() && s.sh_size > 0 && s.sh_type != SectionType::NULL)
+        .collect();
+    alloc.sort_by_key(|s| (s.sh_addr, s.sh_size));
+    let mut rows = Vec::new();
+    let mut page_rounding = 0u64;
+    for (index, segment) in loads.iter().enumerate() {
+        // Sizes from a corrupt file can reach past the address space; they
+        // are cut short at its end rather than overflowing.
+        let segment_end = segment.p_vaddr.saturating_add(segment.p_memsz);
+        let inside: Vec<_> = alloc
+            .iter()
+            .filter(|s| {
+                s.sh_addr >= segment.p_vaddr
+                    && s.sh_addr < segment_end
+                    && (s.sh_type != SectionType::NOBITS || s.sh_flags & SHF_TLS == 0)
+            })
+            .collect();
+        // The first segment usually maps the headers too.
+        let headers = if segment.p_offset == 0 {
+            (header.e_ehsize as u64)
+                .max(
+                    header
+                        .e_phoff
+                        .saturating_add(segments.len() as u64 * header.e_phentsize as u64),
+                )
+                .min(segment.p_filesz)
+        } else {
+            0
+        };
+        let mut cursor = segment.p_vaddr.saturating_add(headers);
+        let mut memory_padding = 0;
+        for section in &inside {
+            if section.sh_addr > cursor {
+                let size = section.sh_addr - cursor;
+                memory_padding += size;
+                // Before sections with contents, the same gap is in the file.
+                if section.has_file_data() {
+                    cursor = section.sh_addr.saturating_add(section.sh_size);
+                    continue;
+                }
+                gaps.push(Gap {
+                    memory: true,
+                    start: cursor,
+                    size,
+                    before: elf_file.section_name(&sections, section)?.to_string(),
+                    cause: cause(section.sh_addr, size, section.sh_addralign, None),
+                });
+            }
+            cursor = cursor.max(section.sh_addr.saturating_add(section.sh_size));
+        }
+        memory_padding += segment_end.saturating_sub(cursor);
+
+        let file_contents = inside
+            .iter()
+            .filter(|s| s.has_file_data())
+            .fold(0u64, |sum, s| sum.saturating_add(s.sh_size));
+        let page = segment.p_align.max(PAGE_SIZE);
+        let rounding = (page - segment_end % page) % page;
+        page_rounding = page_rounding.saturating_add(rounding);
+        rows.push((
+            index,
+            segment,
+            segment
+                .p_filesz
+                .saturating_sub(file_contents.saturating_add(headers)),
+            memory_padding,
+            rounding,
+        ));
+    }
+
+    let file_padding: u64 = gaps.iter().filter(|g| !g.memory).map(|g| g.size).sum();
+    let memory_padding = rows.iter().fold(0u64, |sum, r| sum.saturating_add(r.3));
+    println!("Alignment padding:");
+    println!(
+        "  File:    {} of {} bytes are padding ({:.1}%)",
+        file_padding,
+        file_size,
+        file_padding as f64 * 100.0 / file_size.max(1) as f64
+    );
+    if !loads.is_empty() {
+        println!(
+            "  Memory:  {} bytes of padding inside the loadable segments, and {} more rounding them up to whole pages",
+            memory_padding, page_rounding
+        );
+
+        println!();
+        println!(
+            "  {:<7} {:<5} {:>10} {:>10} {:>12} {:>10} {:>12} {:>10}",
+            "Segment", "Flags", "FileSiz", "File pad", "MemSiz", "Mem pad", "Page tail", "Align"
+        );
+        for (index, segment, file, memory, rounding) in &rows {
+            println!(
+                "  {:<7} {:<5} {:>#10x} {:>10} {:>#12x} {:>10} {:>12} {:>#10x}",
+                index,
+                segment.flags_string().trim_end(),
+                segment.p_filesz,
+                file,
+                segment.p_memsz,
+                memory,
+                rounding,
+                segment.p_align
+            );
+        }
+    }
+
+    gaps.sort_by(|a, b| b.size.cmp(&a.size).then(a.start.cmp(&b.start)));
+    gaps.truncate(top);
+    if gaps.is_empty() {
+        println!();
+        println!("There is no padding between sections.");
+        return Ok(());
+    }
+    println!();
+    println!("Largest gaps:");
+    println!(
+        "  {:>10}  {:<6} {:<18}  Before / cause",
+        "Bytes", "In", "At"
+    );
+    for gap in &gaps {
+        println!(
+            "  {:>10}  {:<6} {:#018x}  {} ({})",
+            gap.size,
+            if gap.memory { "memory" } else { "file" },
+            gap.start,
+            gap.before,
+            gap.cause
+        );
+    }
+    Ok(())
+}
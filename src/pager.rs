@@ -0,0 +1,70 @@
+use std::io::{IsTerminal, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+
+static PAGER: Mutex<Option<Child>> = Mutex::new(None);
+
+/// Restores the default SIGPIPE behaviour, so that quitting the pager (or
+/// piping into `head`) ends the process quietly instead of panicking on the
+/// next write.
+pub fn reset_sigpipe() {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+}
+
+/// Redirects stdout into `$PAGER` (`less` by default) when it is a terminal.
+/// `less` is told to quit straight away when the output fits on one screen
+/// and to pass color escapes through, like git does.
+#[cfg(unix)]
+pub fn start() {
+    use std::os::fd::AsRawFd;
+
+    if !std::io::stdout().is_terminal() {
+        return;
+    }
+
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "less".to_string());
+    if pager == "cat" {
+        return;
+    }
+
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(&pager).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+
+    let Ok(mut child) = command.spawn() else {
+        return;
+    };
+
+    let stdin = child.stdin.take().unwrap();
+    unsafe {
+        libc::dup2(stdin.as_raw_fd(), libc::STDOUT_FILENO);
+    }
+    drop(stdin);
+
+    *PAGER.lock().unwrap() = Some(child);
+}
+
+#[cfg(not(unix))]
+pub fn start() {}
+
+/// Closes our end of the pipe and waits for the user to quit the pager.
+pub fn finish() {
+    let Some(mut child) = PAGER.lock().unwrap().take() else {
+        return;
+    };
+
+    let _ = std::io::stdout().flush();
+    #[cfg(unix)]
+    unsafe {
+        libc::close(libc::STDOUT_FILENO);
+    }
+    let _ = child.wait();
+}
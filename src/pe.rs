@@ -0,0 +1,221 @@
+//! A first look at PE images and COFF objects: the file header, the
+//! optional header's essentials and the section table. Nothing past the
+//! headers is decoded.
+
+use anyhow::{Context, Result, bail};
+
+use crate::formats;
+
+const IMAGE_SCN_CNT_CODE: u32 = 0x20;
+const IMAGE_SCN_CNT_INITIALIZED_DATA: u32 = 0x40;
+const IMAGE_SCN_CNT_UNINITIALIZED_DATA: u32 = 0x80;
+const IMAGE_SCN_MEM_DISCARDABLE: u32 = 0x0200_0000;
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+const IMAGE_SCN_MEM_READ: u32 = 0x4000_0000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+
+/// The file header characteristics worth naming.
+const CHARACTERISTICS: &[(u16, &str)] = &[
+    (0x0001, "RELOCS_STRIPPED"),
+    (0x0002, "EXECUTABLE_IMAGE"),
+    (0x0020, "LARGE_ADDRESS_AWARE"),
+    (0x0100, "32BIT_MACHINE"),
+    (0x0200, "DEBUG_STRIPPED"),
+    (0x1000, "SYSTEM"),
+    (0x2000, "DLL"),
+];
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .context("The headers run past the end of the file")?;
+    Ok(u16::from_le_bytes(bytes.try_into()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .context("The headers run past the end of the file")?;
+    Ok(u32::from_le_bytes(bytes.try_into()?))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Result<u64> {
+    let bytes = data
+        .get(offset..offset + 8)
+        .context("The headers run past the end of the file")?;
+    Ok(u64::from_le_bytes(bytes.try_into()?))
+}
+
+fn subsystem(value: u16) -> &'static str {
+    match value {
+        1 => "native",
+        2 => "Windows GUI",
+        3 => "Windows console",
+        9 => "Windows CE GUI",
+        10 => "EFI application",
+        11 => "EFI boot service driver",
+        12 => "EFI runtime driver",
+        13 => "EFI ROM",
+        14 => "Xbox",
+        16 => "Windows boot application",
+        _ => "unknown",
+    }
+}
+
+/// A section name: eight bytes padded with NULs or, in objects, `/N` for
+/// the name at offset N of the string table after the symbols.
+fn section_name(data: &[u8], raw: &[u8], strings: Option<usize>) -> String {
+    let name = &raw[..raw.iter().position(|&b| b == 0).unwrap_or(raw.len())];
+    let name = String::from_utf8_lossy(name);
+    if let (Some(index), Some(strings)) = (name.strip_prefix('/'), strings)
+        && let Ok(index) = index.parse::<usize>()
+        && let Some(long) = data.get(strings + index..)
+    {
+        let end = long.iter().position(|&b| b == 0).unwrap_or(long.len());
+        return String::from_utf8_lossy(&long[..end]).into_owned();
+    }
+    name.into_owned()
+}
+
+fn section_flags(characteristics: u32) -> String {
+    let mut flags = String::new();
+    for (bit, letter) in [
+        (IMAGE_SCN_MEM_READ, 'R'),
+        (IMAGE_SCN_MEM_WRITE, 'W'),
+        (IMAGE_SCN_MEM_EXECUTE, 'X'),
+        (IMAGE_SCN_CNT_CODE, 'C'),
+        (IMAGE_SCN_CNT_INITIALIZED_DATA, 'I'),
+        (IMAGE_SCN_CNT_UNINITIALIZED_DATA, 'U'),
+        (IMAGE_SCN_MEM_DISCARDABLE, 'D'),
+    ] {
+        if characteristics & bit != 0 {
+            flags.push(letter);
+        }
+    }
+    flags
+}
+
+/// Prints the headers and section table of a PE image or COFF object.
+pub fn print_pe(data: &[u8]) -> Result<()> {
+    let (coff, image) = if data.starts_with(b"MZ") {
+        let pe = u32_at(data, 0x3c)? as usize;
+        if data.get(pe..pe + 4) != Some(b"PE\0\0") {
+            bail!("There is no PE header after the DOS stub");
+        }
+        (pe + 4, true)
+    } else {
+        (0, false)
+    };
+    let machine = u16_at(data, coff)?;
+    let section_count = u16_at(data, coff + 2)? as usize;
+    let timestamp = u32_at(data, coff + 4)?;
+    let symbol_table = u32_at(data, coff + 8)? as usize;
+    let symbol_count = u32_at(data, coff + 12)? as usize;
+    let optional_size = u16_at(data, coff + 16)? as usize;
+    let characteristics = u16_at(data, coff + 18)?;
+
+    println!("{} header:", if image { "PE" } else { "COFF object" });
+    println!(
+        "  Machine:                           {} ({:#x})",
+        formats::coff_machine(machine).unwrap_or("unknown"),
+        machine
+    );
+    println!("  Number of sections:                {}", section_count);
+    println!("  Time stamp:                        {:#x}", timestamp);
+    if symbol_count > 0 {
+        println!(
+            "  Symbols:                           {} at {:#x}",
+            symbol_count, symbol_table
+        );
+    }
+    let names: Vec<&str> = CHARACTERISTICS
+        .iter()
+        .filter(|(bit, _)| characteristics & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    let line = format!(
+        "  Characteristics:                   {:#x} {}",
+        characteristics,
+        names.join(" ")
+    );
+    println!("{}", line.trim_end());
+
+    let optional = coff + 20;
+    if optional_size > 0 {
+        let magic = u16_at(data, optional)?;
+        let is_64 = match magic {
+            0x10b => false,
+            0x20b => true,
+            _ => bail!("Unknown optional header magic {:#x}", magic),
+        };
+        let entry = u32_at(data, optional + 16)?;
+        let image_base = if is_64 {
+            u64_at(data, optional + 24)?
+        } else {
+            u32_at(data, optional + 28)? as u64
+        };
+        let data_directories = if is_64 { 108 } else { 92 };
+        let value = u16_at(data, optional + 68)?;
+        println!(
+            "  Format:                            {}",
+            if is_64 { "PE32+" } else { "PE32" }
+        );
+        println!("  Entry point (RVA):                 {:#x}", entry);
+        println!("  Image base:                        {:#x}", image_base);
+        println!(
+            "  Section alignment:                 {:#x}",
+            u32_at(data, optional + 32)?
+        );
+        println!(
+            "  File alignment:                    {:#x}",
+            u32_at(data, optional + 36)?
+        );
+        println!(
+            "  Size of image:                     {:#x}",
+            u32_at(data, optional + 56)?
+        );
+        println!(
+            "  Subsystem:                         {} ({})",
+            subsystem(value),
+            value
+        );
+        println!(
+            "  DLL characteristics:               {:#x}",
+            u16_at(data, optional + 70)?
+        );
+        println!(
+            "  Data directories:                  {}",
+            u32_at(data, optional + data_directories)?
+        );
+    }
+
+    // Objects keep long section names in the string table right after the
+    // symbols.
+    let strings = (symbol_table != 0).then_some(symbol_table + symbol_count * 18);
+    let table = optional + optional_size;
+    println!();
+    println!("Sections:");
+    println!(
+        "  [Nr] {:<16} {:>10} {:>10} {:>10} {:>10} Flags",
+        "Name", "VirtAddr", "VirtSize", "Offset", "RawSize"
+    );
+    for index in 0..section_count {
+        let header = table + index * 40;
+        let raw = data
+            .get(header..header + 40)
+            .context("The section table runs past the end of the file")?;
+        println!(
+            "  [{:>2}] {:<16} {:>#10x} {:>#10x} {:>#10x} {:>#10x} {}",
+            index + 1,
+            section_name(data, &raw[..8], strings),
+            u32_at(raw, 12)?,
+            u32_at(raw, 8)?,
+            u32_at(raw, 20)?,
+            u32_at(raw, 16)?,
+            section_flags(u32_at(raw, 36)?)
+        );
+    }
+    println!("Flags: R (read), W (write), X (execute), C (code), I (initialized data),");
+    println!("  U (uninitialized data), D (discardable)");
+    Ok(())
+}
@@ -0,0 +1,402 @@
+//! Where the sections of a binary end up in memory, grouped by region the
+//! way a linker script's `MEMORY` command lays them out: for firmware, how
+//! full each of flash and RAM is.
+
+use anyhow::{Context, Result, bail};
+use std::path::Path;
+
+use crate::elf::{ElfFile, ElfType};
+use crate::section::{SHF_TLS, SectionHeader, SectionType};
+use crate::segment::{ProgramHeader, SegmentType};
+
+/// A range of memory sections are placed in. Regions inferred from the
+/// segments have no length to check against.
+pub struct Region {
+    name: String,
+    origin: u64,
+    length: Option<u64>,
+    attributes: String,
+    end: u64,
+}
+
+impl Region {
+    fn new(name: &str, origin: u64, length: u64, attributes: &str) -> Region {
+        Region {
+            name: name.to_string(),
+            origin,
+            length: Some(length),
+            attributes: attributes.to_string(),
+            end: origin.saturating_add(length),
+        }
+    }
+
+    fn contains(&self, address: u64) -> bool {
+        (self.origin..self.end).contains(&address)
+    }
+}
+
+/// Parses a number of a linker script: decimal, `0x` hex or octal with a
+/// leading zero, optionally followed by `K` or `M`.
+fn parse_number(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (digits, scale) = match text.as_bytes().last()? {
+        b'K' | b'k' => (&text[..text.len() - 1], 1024),
+        b'M' | b'm' => (&text[..text.len() - 1], 1024 * 1024),
+        _ => (text, 1),
+    };
+    let value = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    value.checked_mul(scale)
+}
+
+/// Evaluates the expressions of a `MEMORY` command, which are sums,
+/// differences and products of numbers and of the `ORIGIN()` and `LENGTH()`
+/// of the regions before.
+fn evaluate(expression: &str, regions: &[Region]) -> Result<u64> {
+    let term = |text: &str| -> Result<u64> {
+        let mut product: u64 = 1;
+        for factor in text.split('*') {
+            let factor = factor.trim();
+            let value = if let Some(name) = factor
+                .strip_prefix("ORIGIN(")
+                .and_then(|f| f.strip_suffix(')'))
+            {
+                regions
+                    .iter()
+                    .find(|r| r.name == name.trim())
+                    .map(|r| r.origin)
+            } else if let Some(name) = factor
+                .strip_prefix("LENGTH(")
+                .and_then(|f| f.strip_suffix(')'))
+            {
+                regions
+                    .iter()
+                    .find(|r| r.name == name.trim())
+                    .and_then(|r| r.length)
+            } else {
+                parse_number(factor)
+            }
+            .with_context(|| format!("Cannot evaluate {:?}", factor))?;
+            product = product.wrapping_mul(value);
+        }
+        Ok(product)
+    };
+
+    let mut total: u64 = 0;
+    let mut subtract = false;
+    let mut start = 0;
+    for (index, c) in expression.char_indices().chain([(expression.len(), '+')]) {
+        if c == '+' || c == '-' {
+            let value = term(&expression[start..index])?;
+            total = if subtract {
+                total.wrapping_sub(value)
+            } else {
+                total.wrapping_add(value)
+            };
+            subtract = c == '-';
+            start = index + 1;
+        }
+    }
+    Ok(total)
+}
+
+/// Parses `NAME=ORIGIN:LENGTH`, as in `FLASH=0x08000000:512K`.
+pub fn parse_region(spec: &str) -> Result<Region> {
+    let (name, range) = spec
+        .split_once('=')
+        .with_context(|| format!("Expected NAME=ORIGIN:LENGTH, got {:?}", spec))?;
+    let (origin, length) = range
+        .split_once(':')
+        .with_context(|| format!("Expected NAME=ORIGIN:LENGTH, got {:?}", spec))?;
+    let origin = parse_number(origin).with_context(|| format!("Invalid origin {:?}", origin))?;
+    let length = parse_number(length).with_context(|| format!("Invalid length {:?}", length))?;
+    Ok(Region::new(name, origin, length, ""))
+}
+
+/// Reads the regions of the `MEMORY` command of a linker script, such as
+/// `FLASH (rx) : ORIGIN = 0x08000000, LENGTH = 512K`.
+pub fn parse_memory(path: &Path) -> Result<Vec<Region>> {
+    let script = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read linker script {}", path.display()))?;
+    let mut text = String::new();
+    let mut rest = script.as_str();
+    while let Some(start) = rest.find("/*") {
+        text += &rest[..start];
+        rest = rest[start..]
+            .find("*/")
+            .map_or("", |end| &rest[start + end + 2..]);
+    }
+    text += rest;
+
+    let block = text
+        .find("MEMORY")
+        .and_then(|start| {
+            let open = start + text[start..].find('{')?;
+            let close = open + text[open..].find('}')?;
+            Some(&text[open + 1..close])
+        })
+        .with_context(|| format!("{} has no MEMORY command", path.display()))?;
+
+    let mut regions: Vec<Region> = Vec::new();
+    let mut entry = String::new();
+    for line in block.lines() {
+        entry += line;
+        entry.push(' ');
+        let Some((head, body)) = entry.split_once(':') else {
+            continue;
+        };
+        // ORIGIN and LENGTH can be abbreviated to org, o, len and l.
+        let mut origin = None;
+        let mut length = None;
+        for assignment in body.split(',') {
+            let Some((key, value)) = assignment.split_once('=') else {
+                continue;
+            };
+            match key.trim() {
+                "ORIGIN" | "org" | "o" => origin = Some(evaluate(value, &regions)?),
+                "LENGTH" | "len" | "l" => length = Some(evaluate(value, &regions)?),
+                key => bail!("Unknown MEMORY attribute {:?}", key),
+            }
+        }
+        let (Some(origin), Some(length)) = (origin, length) else {
+            continue;
+        };
+        let (name, attributes) = match head.split_once('(') {
+            Some((name, attributes)) => (name, attributes.trim().trim_end_matches(')')),
+            None => (head, ""),
+        };
+        regions.push(Region::new(name.trim(), origin, length, attributes));
+        entry.clear();
+    }
+    if regions.is_empty() {
+        bail!("The MEMORY command of {} has no regions", path.display());
+    }
+    Ok(regions)
+}
+
+/// One region per loadable segment, named after the target's memories when
+/// the machine has a fixed map of them.
+fn segment_regions(elf_file: &ElfFile, segments: &[ProgramHeader]) -> Vec<Region> {
+    segments
+        .iter()
+        .filter(|s| s.p_type == SegmentType::LOAD && s.p_memsz > 0)
+        .enumerate()
+        .map(|(index, segment)| {
+            let name = match elf_file.machine().memory_region(segment.p_vaddr) {
+                Some((memory, _)) => format!("LOAD {} in {}", index, memory),
+                None => format!("LOAD {}", index),
+            };
+            Region {
+                name,
+                origin: segment.p_vaddr,
+                length: None,
+                attributes: segment.flags_string().trim_end().to_string(),
+                end: segment.p_vaddr.saturating_add(segment.p_memsz),
+            }
+        })
+        .collect()
+}
+
+/// Where a section is stored in the image, when that is not where it runs:
+/// initialized data of firmware is copied from flash to RAM at startup.
+fn load_address(section: &SectionHeader, segments: &[ProgramHeader]) -> Option<u64> {
+    if !section.has_file_data() {
+        return None;
+    }
+    segments
+        .iter()
+        .find(|s| {
+            s.p_type == SegmentType::LOAD
+                && section.sh_addr >= s.p_vaddr
+                && section.sh_addr - s.p_vaddr < s.p_memsz
+        })
+        .and_then(|s| s.p_paddr.checked_add(section.sh_addr - s.p_vaddr))
+        .filter(|&lma| lma != section.sh_addr)
+}
+
+/// A section, or its load image, in a region.
+struct Placed<'a> {
+    address: u64,
+    size: u64,
+    name: &'a str,
+    note: String,
+}
+
+/// Prints the sections by memory region. With `regions` from the user, how
+/// much of each is used and what overflows it; otherwise the regions are
+/// the loadable segments.
+pub fn print_placement(elf_file: &ElfFile, regions: Option<Vec<Region>>) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let segments = elf_file.program_headers()?;
+    let given = regions.is_some();
+    let regions = regions.unwrap_or_else(|| segment_regions(elf_file, &segments));
+    if regions.is_empty() && elf_file.elf_type() == ElfType::REL {
+        println!("Relocatable objects have no loadable segments to infer memory regions from,");
+        println!("and their sections have no addresses; give the regions with --region or");
+        println!("--memory to see what the sections would take of each.");
+        return Ok(());
+    }
+    if regions.is_empty() {
+        println!("There are no loadable segments in this file to infer memory regions from.");
+        return Ok(());
+    }
+    let region_of = |address: u64| regions.iter().position(|r| r.contains(address));
+
+    let mut placed: Vec<Vec<Placed>> = regions.iter().map(|_| Vec::new()).collect();
+    let mut outside = Vec::new();
+    for section in &sections {
+        // .tbss takes no room of its own: each thread gets a copy.
+        if !section.is_alloc()
+            || section.sh_size == 0
+            || (section.sh_flags & SHF_TLS != 0 && section.sh_type == SectionType::NOBITS)
+        {
+            continue;
+        }
+        let name = elf_file.section_name(&sections, section)?;
+        let runs = region_of(section.sh_addr);
+        let region_name = |index: Option<usize>| index.map_or("no region", |i| &regions[i].name);
+
+        let mut note = String::new();
+        if section.sh_type == SectionType::NOBITS {
+            note = "zero-filled".to_string();
+        }
+        match load_address(section, &segments) {
+            // Segments have no regions for their physical addresses.
+            Some(lma) if !given => {
+                note = format!("stored at {:#x}, the segment's physical address", lma);
+            }
+            Some(lma) => {
+                let loads = region_of(lma);
+                note = format!("copied from {:#x} in {}", lma, region_name(loads));
+                let image = Placed {
+                    address: lma,
+                    size: section.sh_size,
+                    name,
+                    note: format!(
+                        "load image; runs at {:#x} in {}",
+                        section.sh_addr,
+                        region_name(runs)
+                    ),
+                };
+                match loads {
+                    Some(index) => placed[index].push(image),
+                    None => outside.push(image),
+                }
+            }
+            None => {}
+        }
+        let run = Placed {
+            address: section.sh_addr,
+            size: section.sh_size,
+            name,
+            note,
+        };
+        match runs {
+            Some(index) => placed[index].push(run),
+            None => outside.push(run),
+        }
+    }
+
+    if given {
+        println!("Section placement by memory region:");
+    } else {
+        println!("Section placement by memory region (inferred from the PT_LOAD segments):");
+    }
+    let mut problems = Vec::new();
+    for (region, sections) in regions.iter().zip(&mut placed) {
+        sections.sort_by_key(|p| p.address);
+        let used = sections
+            .iter()
+            .fold(0u64, |used, p| used.saturating_add(p.size));
+
+        println!();
+        let attributes = if region.attributes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", region.attributes)
+        };
+        println!(
+            "{}{}  {:#x}-{:#x}",
+            region.name, attributes, region.origin, region.end
+        );
+        match region.length {
+            Some(length) => {
+                println!(
+                    "  Used {} of {} bytes ({:.1}%), {} free",
+                    used,
+                    length,
+                    used as f64 * 100.0 / length.max(1) as f64,
+                    length.saturating_sub(used)
+                );
+                if used > length {
+                    problems.push(format!(
+                        "{} is overfull by {} bytes",
+                        region.name,
+                        used - length
+                    ));
+                }
+            }
+            None => println!("  {} bytes in sections", used),
+        }
+        if sections.is_empty() {
+            continue;
+        }
+        println!("    {:<18} {:>10}  Section", "Address", "Size");
+        for section in sections.iter() {
+            let note = if section.note.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", section.note)
+            };
+            println!(
+                "    {:#018x} {:>#10x}  {}{}",
+                section.address, section.size, section.name, note
+            );
+            let end = section.address.saturating_add(section.size);
+            if given && end > region.end {
+                problems.push(format!(
+                    "{} ends at {:#x}, {} bytes past the end of {}",
+                    section.name,
+                    end,
+                    end - region.end,
+                    region.name
+                ));
+            }
+        }
+    }
+
+    if !outside.is_empty() {
+        println!();
+        println!("Outside every region:");
+        outside.sort_by_key(|p| p.address);
+        for section in &outside {
+            println!(
+                "    {:#018x} {:>#10x}  {}",
+                section.address, section.size, section.name
+            );
+            if given {
+                problems.push(format!(
+                    "{} at {:#x} is not in any region",
+                    section.name, section.address
+                ));
+            }
+        }
+    }
+
+    if !problems.is_empty() {
+        println!();
+        println!("Problems:");
+        for problem in problems {
+            println!("  {}", problem);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1256 @@
+//! Decoders for vendor-specific extensions of the format. Section types,
+//! note types and dynamic tags in the OS and processor ranges mean different
+//! things to different toolchains, and embedded vendors routinely add their
+//! own, so instead of hard-coding them the reports look them up in a
+//! `Registry`. Other code can register decoders of its own next to the
+//! built-in ones, which are enabled by Cargo features.
+
+use anyhow::Result;
+
+use crate::dynamic::{DynamicEntry, DynamicTag};
+use crate::elf::ElfFile;
+use crate::filter::SectionFilter;
+use crate::notes::{self, Note};
+use crate::section::SectionHeader;
+
+/// Decodes the contents of sections of a type the core parser does not know.
+pub trait SectionDecoder: Send + Sync {
+    /// Whether this decoder understands `section`. The file is passed along
+    /// since the same type means different things for different machines
+    /// and operating systems.
+    fn handles(&self, elf_file: &ElfFile, section: &SectionHeader) -> bool;
+
+    /// The name of the section type, as readelf would print it.
+    fn type_name(&self) -> &str;
+
+    /// One line of description per item in the section.
+    fn decode(&self, elf_file: &ElfFile, section: &SectionHeader) -> Result<Vec<String>>;
+}
+
+/// Decodes the descriptor of notes from one owner.
+pub trait NoteDecoder: Send + Sync {
+    /// Whether this decoder understands notes of type `n_type` from `owner`.
+    fn handles(&self, owner: &str, n_type: u32) -> bool;
+
+    /// The name of the note type.
+    fn type_name(&self, n_type: u32) -> String;
+
+    /// One line of description per item in the descriptor.
+    fn decode(&self, elf_file: &ElfFile, note: &Note) -> Result<Vec<String>>;
+}
+
+/// Names and formats the values of dynamic tags outside the generic range.
+pub trait DynamicTagDecoder: Send + Sync {
+    /// Whether this decoder understands `tag` in `elf_file`.
+    fn handles(&self, elf_file: &ElfFile, tag: DynamicTag) -> bool;
+
+    /// The name of the tag, without the `DT_` prefix.
+    fn tag_name(&self, tag: DynamicTag) -> String;
+
+    /// The value of the entry, formatted for display.
+    fn decode(&self, elf_file: &ElfFile, entry: &DynamicEntry) -> Result<String>;
+}
+
+/// The decoders the reports consult. When several decoders handle the same
+/// thing, the one registered last wins, so a registration can override a
+/// built-in decoder. Decoders must be `Send + Sync` so that a registry can
+/// be shared by threads decoding different files.
+///
+/// ```
+/// use anyhow::Result;
+/// use readelf_rs::elf::ElfFile;
+/// use readelf_rs::notes::Note;
+/// use readelf_rs::plugin::{NoteDecoder, Registry};
+///
+/// /// The firmware version notes of some vendor toolchain.
+/// struct FirmwareVersion;
+///
+/// impl NoteDecoder for FirmwareVersion {
+///     fn handles(&self, owner: &str, n_type: u32) -> bool {
+///         owner == "ACME" && n_type == 1
+///     }
+///
+///     fn type_name(&self, _n_type: u32) -> String {
+///         "NT_ACME_VERSION (firmware version)".to_string()
+///     }
+///
+///     fn decode(&self, _elf_file: &ElfFile, note: &Note) -> Result<Vec<String>> {
+///         Ok(vec![format!("Version: {}", String::from_utf8_lossy(note.desc))])
+///     }
+/// }
+///
+/// let mut registry = Registry::with_builtins();
+/// registry.register_note(Box::new(FirmwareVersion));
+/// let decoder = registry.note_decoder("ACME", 1).unwrap();
+/// assert_eq!(decoder.type_name(1), "NT_ACME_VERSION (firmware version)");
+/// assert!(registry.note_decoder("ACME", 2).is_none());
+/// ```
+#[derive(Default)]
+pub struct Registry {
+    sections: Vec<Box<dyn SectionDecoder>>,
+    notes: Vec<Box<dyn NoteDecoder>>,
+    dynamic_tags: Vec<Box<dyn DynamicTagDecoder>>,
+}
+
+impl Registry {
+    /// A registry without any decoders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry with the built-in decoders enabled at build time.
+    pub fn with_builtins() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = Self::new();
+        #[cfg(feature = "gnu")]
+        registry.register_note(Box::new(gnu::Notes));
+        #[cfg(feature = "arm")]
+        registry.register_section(Box::new(arm::Exidx));
+        #[cfg(feature = "mips")]
+        registry.register_dynamic_tag(Box::new(mips::DynamicTags));
+        #[cfg(feature = "ppc")]
+        registry
+            .register_section(Box::new(ppc::Opd))
+            .register_dynamic_tag(Box::new(ppc::DynamicTags));
+        #[cfg(feature = "solaris")]
+        registry
+            .register_section(Box::new(solaris::Capabilities))
+            .register_dynamic_tag(Box::new(solaris::DynamicTags));
+        #[cfg(feature = "sparc")]
+        registry.register_dynamic_tag(Box::new(sparc::DynamicTags));
+        #[cfg(feature = "xtensa")]
+        registry
+            .register_section(Box::new(xtensa::Properties))
+            .register_section(Box::new(xtensa::Literals));
+        #[cfg(feature = "bpf")]
+        registry.register_section(Box::new(bpf::Maps));
+        registry
+    }
+
+    pub fn register_section(&mut self, decoder: Box<dyn SectionDecoder>) -> &mut Self {
+        self.sections.push(decoder);
+        self
+    }
+
+    pub fn register_note(&mut self, decoder: Box<dyn NoteDecoder>) -> &mut Self {
+        self.notes.push(decoder);
+        self
+    }
+
+    pub fn register_dynamic_tag(&mut self, decoder: Box<dyn DynamicTagDecoder>) -> &mut Self {
+        self.dynamic_tags.push(decoder);
+        self
+    }
+
+    pub fn section_decoder(
+        &self,
+        elf_file: &ElfFile,
+        section: &SectionHeader,
+    ) -> Option<&dyn SectionDecoder> {
+        self.sections
+            .iter()
+            .rev()
+            .find(|d| d.handles(elf_file, section))
+            .map(|d| d.as_ref())
+    }
+
+    pub fn note_decoder(&self, owner: &str, n_type: u32) -> Option<&dyn NoteDecoder> {
+        self.notes
+            .iter()
+            .rev()
+            .find(|d| d.handles(owner, n_type))
+            .map(|d| d.as_ref())
+    }
+
+    pub fn dynamic_tag_decoder(
+        &self,
+        elf_file: &ElfFile,
+        tag: DynamicTag,
+    ) -> Option<&dyn DynamicTagDecoder> {
+        self.dynamic_tags
+            .iter()
+            .rev()
+            .find(|d| d.handles(elf_file, tag))
+            .map(|d| d.as_ref())
+    }
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Registry>();
+};
+
+/// Prints everything in the file that one of the registered decoders knows
+/// about. The filter selects the sections to decode.
+pub fn print_decoded(
+    elf_file: &ElfFile,
+    registry: &Registry,
+    filter: &SectionFilter,
+) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let mut decoded = 0;
+
+    for (index, section) in sections.iter().enumerate() {
+        let Some(decoder) = registry.section_decoder(elf_file, section) else {
+            continue;
+        };
+        if !filter.selects(elf_file, &sections, index) {
+            continue;
+        }
+        decoded += 1;
+        println!(
+            "Section [{:2}] '{}' ({}):",
+            index,
+            elf_file.section_name(&sections, section).unwrap_or(""),
+            decoder.type_name()
+        );
+        for line in decoder.decode(elf_file, section)? {
+            println!("  {}", line);
+        }
+        println!();
+    }
+
+    for note in notes::notes(elf_file)? {
+        let Some(decoder) = registry.note_decoder(note.name, note.n_type) else {
+            continue;
+        };
+        decoded += 1;
+        println!(
+            "Note '{}' {} ({} bytes):",
+            note.name,
+            decoder.type_name(note.n_type),
+            note.desc.len()
+        );
+        for line in decoder.decode(elf_file, &note)? {
+            println!("  {}", line);
+        }
+        println!();
+    }
+
+    let entries: Vec<(&dyn DynamicTagDecoder, DynamicEntry)> = elf_file
+        .dynamic_entries()?
+        .into_iter()
+        .filter_map(|entry| {
+            registry
+                .dynamic_tag_decoder(elf_file, entry.d_tag)
+                .map(|decoder| (decoder, entry))
+        })
+        .collect();
+    if !entries.is_empty() {
+        decoded += entries.len();
+        println!("Dynamic tags:");
+        for (decoder, entry) in entries {
+            println!(
+                "  {:<24} {}",
+                decoder.tag_name(entry.d_tag),
+                decoder.decode(elf_file, &entry)?
+            );
+        }
+        println!();
+    }
+
+    if decoded == 0 {
+        println!("Nothing in the file is handled by the registered decoders.");
+    }
+    Ok(())
+}
+
+#[cfg(feature = "gnu")]
+mod gnu {
+    use super::*;
+    use crate::elf::ElfType;
+
+    /// The notes GNU toolchains add to every binary.
+    pub struct Notes;
+
+    impl NoteDecoder for Notes {
+        fn handles(&self, owner: &str, n_type: u32) -> bool {
+            owner == "GNU"
+                && (notes::NT_GNU_ABI_TAG..=notes::NT_GNU_PROPERTY_TYPE_0).contains(&n_type)
+        }
+
+        fn type_name(&self, n_type: u32) -> String {
+            notes::type_name(ElfType::NONE, "GNU", n_type)
+        }
+
+        fn decode(&self, elf_file: &ElfFile, note: &Note) -> Result<Vec<String>> {
+            Ok(match note.n_type {
+                notes::NT_GNU_PROPERTY_TYPE_0 => notes::gnu_properties(note.desc, elf_file.is_64()),
+                _ => vec![
+                    notes::describe(elf_file, note).unwrap_or_else(|| "<corrupt note>".to_string()),
+                ],
+            })
+        }
+    }
+}
+
+#[cfg(feature = "arm")]
+mod arm {
+    use super::*;
+    use crate::consts::SHT_ARM_EXIDX;
+    use crate::emachine::EMachine;
+
+    const EXIDX_CANTUNWIND: u32 = 1;
+
+    /// Sign-extends a 31-bit place-relative offset and applies it to `place`.
+    fn prel31(place: u64, word: u32) -> u64 {
+        let offset = ((word << 1) as i32 >> 1) as i64;
+        place.wrapping_add_signed(offset) & 0xffffffff
+    }
+
+    /// The ARM exception index table, which maps each function to its
+    /// unwinding instructions.
+    pub struct Exidx;
+
+    impl SectionDecoder for Exidx {
+        fn handles(&self, elf_file: &ElfFile, section: &SectionHeader) -> bool {
+            elf_file.machine() == EMachine::Arm && section.sh_type == SHT_ARM_EXIDX
+        }
+
+        fn type_name(&self) -> &str {
+            "ARM_EXIDX"
+        }
+
+        fn decode(&self, elf_file: &ElfFile, section: &SectionHeader) -> Result<Vec<String>> {
+            let data = elf_file.section_data(section)?;
+            let mut lines = Vec::new();
+            for (i, entry) in data.chunks_exact(8).enumerate() {
+                let place = section.sh_addr + i as u64 * 8;
+                let function = u32::from_ne_bytes(entry[..4].try_into().unwrap());
+                let value = u32::from_ne_bytes(entry[4..].try_into().unwrap());
+                let unwind = if value == EXIDX_CANTUNWIND {
+                    "cannot unwind".to_string()
+                } else if value & 0x80000000 != 0 {
+                    format!("inline {:#010x}", value)
+                } else {
+                    format!("table at {:#010x}", prel31(place + 4, value))
+                };
+                lines.push(format!(
+                    "{:#010x}: {}",
+                    prel31(place, function & 0x7fffffff),
+                    unwind
+                ));
+            }
+            Ok(lines)
+        }
+    }
+}
+
+#[cfg(feature = "mips")]
+mod mips {
+    use super::*;
+    use crate::emachine::EMachine;
+
+    /// The names of the MIPS-specific tags, by offset from DT_LOPROC.
+    const TAGS: &[(i64, &str, Format)] = &[
+        (0x01, "MIPS_RLD_VERSION", Format::Number),
+        (0x02, "MIPS_TIME_STAMP", Format::Hex),
+        (0x03, "MIPS_ICHECKSUM", Format::Hex),
+        (0x04, "MIPS_IVERSION", Format::Hex),
+        (0x05, "MIPS_FLAGS", Format::Flags),
+        (0x06, "MIPS_BASE_ADDRESS", Format::Hex),
+        (0x07, "MIPS_MSYM", Format::Hex),
+        (0x08, "MIPS_CONFLICT", Format::Hex),
+        (0x09, "MIPS_LIBLIST", Format::Hex),
+        (0x0a, "MIPS_LOCAL_GOTNO", Format::Number),
+        (0x0b, "MIPS_CONFLICTNO", Format::Number),
+        (0x10, "MIPS_LIBLISTNO", Format::Number),
+        (0x11, "MIPS_SYMTABNO", Format::Number),
+        (0x12, "MIPS_UNREFEXTNO", Format::Number),
+        (0x13, "MIPS_GOTSYM", Format::Number),
+        (0x14, "MIPS_HIPAGENO", Format::Number),
+        (0x16, "MIPS_RLD_MAP", Format::Hex),
+        (0x32, "MIPS_PLTGOT", Format::Hex),
+        (0x34, "MIPS_RWPLT", Format::Hex),
+        (0x35, "MIPS_RLD_MAP_REL", Format::Hex),
+        (0x36, "MIPS_XHASH", Format::Hex),
+    ];
+
+    const RHF_FLAGS: &[&str] = &[
+        "QUICKSTART",
+        "NOTPOT",
+        "NO_LIBRARY_REPLACEMENT",
+        "NO_MOVE",
+        "SGI_ONLY",
+        "GUARANTEE_INIT",
+        "DELTA_C_PLUS_PLUS",
+        "GUARANTEE_START_INIT",
+        "PIXIE",
+        "DEFAULT_DELAY_LOAD",
+        "REQUICKSTART",
+        "REQUICKSTARTED",
+        "CORD",
+        "NO_UNRES_UNDEF",
+        "RLD_ORDER_SAFE",
+    ];
+
+    #[derive(Clone, Copy)]
+    enum Format {
+        Number,
+        Hex,
+        Flags,
+    }
+
+    fn lookup(tag: DynamicTag) -> Option<&'static (i64, &'static str, Format)> {
+        TAGS.iter()
+            .find(|(offset, _, _)| 0x70000000 + offset == tag.0)
+    }
+
+    /// The tags the MIPS ABI defines in the processor-specific range.
+    pub struct DynamicTags;
+
+    impl DynamicTagDecoder for DynamicTags {
+        fn handles(&self, elf_file: &ElfFile, tag: DynamicTag) -> bool {
+            matches!(elf_file.machine(), EMachine::Mips | EMachine::MipsRs3Le)
+                && lookup(tag).is_some()
+        }
+
+        fn tag_name(&self, tag: DynamicTag) -> String {
+            lookup(tag).map_or_else(|| format!("{:#x}", tag.0), |(_, name, _)| name.to_string())
+        }
+
+        fn decode(&self, _elf_file: &ElfFile, entry: &DynamicEntry) -> Result<String> {
+            let value = entry.d_val;
+            Ok(match lookup(entry.d_tag).map(|&(_, _, format)| format) {
+                Some(Format::Number) => value.to_string(),
+                Some(Format::Flags) if value == 0 => "NONE".to_string(),
+                Some(Format::Flags) => (0..64)
+                    .filter(|bit| value & (1 << bit) != 0)
+                    .map(|bit| {
+                        RHF_FLAGS
+                            .get(bit)
+                            .map_or_else(|| format!("{:#x}", 1u64 << bit), |n| n.to_string())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => format!("{:#x}", value),
+            })
+        }
+    }
+}
+
+#[cfg(feature = "ppc")]
+mod ppc {
+    use super::*;
+    use crate::emachine::EMachine;
+    use crate::ppc64;
+    use crate::strtab::SymbolTable;
+    use crate::symbol::SymbolType;
+
+    /// The ELFv1 function descriptors, one per function, with the TOC
+    /// pointer the functions expect.
+    pub struct Opd;
+
+    impl SectionDecoder for Opd {
+        fn handles(&self, elf_file: &ElfFile, section: &SectionHeader) -> bool {
+            ppc64::uses_descriptors(elf_file)
+                && elf_file.section_headers().is_ok_and(|sections| {
+                    elf_file
+                        .section_name(&sections, section)
+                        .is_ok_and(|name| name == ".opd")
+                })
+        }
+
+        fn type_name(&self) -> &str {
+            "PPC64 function descriptors"
+        }
+
+        fn decode(&self, elf_file: &ElfFile, section: &SectionHeader) -> Result<Vec<String>> {
+            let sections = elf_file.section_headers()?;
+            let symbols = match elf_file.symbol_table(&sections) {
+                Some(symtab) => SymbolTable::new(elf_file, &sections, symtab)?,
+                None => SymbolTable::linked(elf_file, &sections, section)?,
+            };
+            let name = |address: u64| {
+                symbols
+                    .iter()
+                    .position(|s| s.symbol_type() == SymbolType::FUNC && s.st_value == address)
+                    .and_then(|i| symbols.name(i).ok())
+                    .unwrap_or("")
+            };
+
+            let mut lines = Vec::new();
+            if let Some(toc) = ppc64::toc_base(elf_file, &sections)? {
+                lines.push(format!("TOC base: {:#x}", toc));
+            }
+            for descriptor in ppc64::descriptors(elf_file, section)? {
+                lines.push(format!(
+                    "{:#018x}: entry {:#018x} toc {:#018x} env {:#x}  {}",
+                    descriptor.address,
+                    descriptor.entry,
+                    descriptor.toc,
+                    descriptor.environment,
+                    name(descriptor.address)
+                ));
+            }
+            Ok(lines)
+        }
+    }
+
+    /// The tags the PowerPC ABIs define in the processor-specific range.
+    const TAGS: &[(EMachine, i64, &str)] = &[
+        (EMachine::Ppc, 0x70000000, "PPC_GOT"),
+        (EMachine::Ppc, 0x70000001, "PPC_OPT"),
+        (EMachine::Ppc64, 0x70000000, "PPC64_GLINK"),
+        (EMachine::Ppc64, 0x70000001, "PPC64_OPD"),
+        (EMachine::Ppc64, 0x70000002, "PPC64_OPDSZ"),
+        (EMachine::Ppc64, 0x70000003, "PPC64_OPT"),
+    ];
+
+    fn lookup(machine: EMachine, tag: DynamicTag) -> Option<&'static str> {
+        TAGS.iter()
+            .find(|&&(m, t, _)| m == machine && t == tag.0)
+            .map(|&(_, _, name)| name)
+    }
+
+    pub struct DynamicTags;
+
+    impl DynamicTagDecoder for DynamicTags {
+        fn handles(&self, elf_file: &ElfFile, tag: DynamicTag) -> bool {
+            lookup(elf_file.machine(), tag).is_some()
+        }
+
+        fn tag_name(&self, tag: DynamicTag) -> String {
+            // The names only differ in their prefix between the two.
+            lookup(EMachine::Ppc64, tag)
+                .or_else(|| lookup(EMachine::Ppc, tag))
+                .map_or_else(|| format!("{:#x}", tag.0), str::to_string)
+        }
+
+        fn decode(&self, elf_file: &ElfFile, entry: &DynamicEntry) -> Result<String> {
+            let value = entry.d_val;
+            Ok(match lookup(elf_file.machine(), entry.d_tag) {
+                Some("PPC64_OPDSZ") => value.to_string(),
+                Some("PPC_OPT" | "PPC64_OPT") => {
+                    let options: Vec<&str> = [(1, "TLS"), (2, "MULTI_TOC"), (4, "LOCALENTRY")]
+                        .iter()
+                        .filter(|&&(bit, _)| value & bit != 0)
+                        .map(|&(_, name)| name)
+                        .collect();
+                    format!("{:#x} {}", value, options.join(" "))
+                }
+                _ => format!("{:#x}", value),
+            })
+        }
+    }
+}
+
+#[cfg(feature = "solaris")]
+mod solaris {
+    use super::*;
+    use crate::consts::ELFOSABI_SOLARIS;
+    use crate::section::SectionType;
+    use crate::strtab::StringTable;
+
+    fn is_solaris(elf_file: &ElfFile) -> bool {
+        elf_file.ident().os_abi == ELFOSABI_SOLARIS
+    }
+
+    const CA_SUNW_NULL: u64 = 0;
+    const CA_SUNW_SF_1: u64 = 2;
+
+    /// Capability tags, and whether their value is a string.
+    const CAPABILITIES: &[(u64, &str, bool)] = &[
+        (1, "CA_SUNW_HW_1", false),
+        (CA_SUNW_SF_1, "CA_SUNW_SF_1", false),
+        (3, "CA_SUNW_HW_2", false),
+        (4, "CA_SUNW_PLAT", true),
+        (5, "CA_SUNW_MACH", true),
+        (6, "CA_SUNW_ID", true),
+        (7, "CA_SUNW_HW_3", false),
+    ];
+
+    const SF1_SUNW: &[&str] = &["FPKNWN", "FPUSED", "ADDR32"];
+
+    /// The capabilities an object needs from the hardware and software it
+    /// runs on. The first group, up to a CA_SUNW_NULL entry, holds the
+    /// capabilities of the object as a whole; any further groups belong to
+    /// symbols that have several capability-specific implementations.
+    pub struct Capabilities;
+
+    impl SectionDecoder for Capabilities {
+        fn handles(&self, elf_file: &ElfFile, section: &SectionHeader) -> bool {
+            is_solaris(elf_file) && section.sh_type == SectionType::SUNW_CAP
+        }
+
+        fn type_name(&self) -> &str {
+            "SUNW_cap"
+        }
+
+        fn decode(&self, elf_file: &ElfFile, section: &SectionHeader) -> Result<Vec<String>> {
+            let data = elf_file.section_data(section)?;
+            let sections = elf_file.section_headers()?;
+            // Named capabilities live in the string table sh_info points at.
+            let strings = match sections.get(section.sh_info as usize) {
+                Some(strtab) if section.sh_info != 0 => {
+                    StringTable::new(elf_file.section_data(strtab)?)
+                }
+                _ => StringTable::default(),
+            };
+
+            let size = if elf_file.is_64() { 16 } else { 8 };
+            let mut lines = vec!["Object capabilities:".to_string()];
+            let mut group = 0;
+            for (i, entry) in data.chunks_exact(size).enumerate() {
+                let (tag, value) = if elf_file.is_64() {
+                    (
+                        u64::from_ne_bytes(entry[..8].try_into().unwrap()),
+                        u64::from_ne_bytes(entry[8..].try_into().unwrap()),
+                    )
+                } else {
+                    (
+                        u32::from_ne_bytes(entry[..4].try_into().unwrap()) as u64,
+                        u32::from_ne_bytes(entry[4..].try_into().unwrap()) as u64,
+                    )
+                };
+
+                if tag == CA_SUNW_NULL {
+                    group += 1;
+                    lines.push(format!("Symbol capabilities, group {}:", group));
+                    continue;
+                }
+                let text = match CAPABILITIES.iter().find(|&&(t, _, _)| t == tag) {
+                    Some(&(_, name, true)) => format!(
+                        "{:<14} {}",
+                        name,
+                        strings.get(value as usize).unwrap_or("<corrupt>")
+                    ),
+                    Some(&(CA_SUNW_SF_1, name, _)) => {
+                        let flags: Vec<String> = (0..64)
+                            .filter(|bit| value & (1 << bit) != 0)
+                            .map(|bit| {
+                                SF1_SUNW.get(bit).map_or_else(
+                                    || format!("{:#x}", 1u64 << bit),
+                                    |n| n.to_string(),
+                                )
+                            })
+                            .collect();
+                        format!("{:<14} {:#x}  [ {} ]", name, value, flags.join(" "))
+                    }
+                    Some(&(_, name, _)) => format!("{:<14} {:#x}", name, value),
+                    None => format!("{:<14} {:#x}", format!("{:#x}", tag), value),
+                };
+                lines.push(format!("  [{}] {}", i, text));
+            }
+            // The table ends with a CA_SUNW_NULL entry of its own, which
+            // does not start another group.
+            if lines
+                .last()
+                .is_some_and(|l| l.starts_with("Symbol capabilities"))
+            {
+                lines.pop();
+            }
+            Ok(lines)
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    enum Format {
+        Hex,
+        Number,
+        String,
+        Machine,
+        Switch,
+    }
+
+    /// The Solaris tags in the OS-specific range, by offset from DT_LOOS.
+    const TAGS: &[(i64, &str, Format)] = &[
+        (0x0d, "SUNW_AUXILIARY", Format::String),
+        (0x0e, "SUNW_RTLDINF", Format::Hex),
+        (0x0f, "SUNW_FILTER", Format::String),
+        (0x10, "SUNW_CAP", Format::Hex),
+        (0x11, "SUNW_SYMTAB", Format::Hex),
+        (0x12, "SUNW_SYMSZ", Format::Number),
+        (0x13, "SUNW_SORTENT", Format::Number),
+        (0x14, "SUNW_SYMSORT", Format::Hex),
+        (0x15, "SUNW_SYMSORTSZ", Format::Number),
+        (0x16, "SUNW_TLSSORT", Format::Hex),
+        (0x17, "SUNW_TLSSORTSZ", Format::Number),
+        (0x18, "SUNW_CAPINFO", Format::Hex),
+        (0x19, "SUNW_STRPAD", Format::Number),
+        (0x1a, "SUNW_CAPCHAIN", Format::Hex),
+        (0x1b, "SUNW_LDMACH", Format::Machine),
+        (0x1c, "SUNW_SYMTAB_SHNDX", Format::Hex),
+        (0x1d, "SUNW_CAPCHAINENT", Format::Number),
+        (0x1f, "SUNW_CAPCHAINSZ", Format::Number),
+        (0x21, "SUNW_PARENT", Format::String),
+        (0x23, "SUNW_ASLR", Format::Switch),
+        (0x25, "SUNW_RELAX", Format::Hex),
+        (0x29, "SUNW_NXHEAP", Format::Switch),
+        (0x2b, "SUNW_NXSTACK", Format::Switch),
+    ];
+
+    fn lookup(tag: DynamicTag) -> Option<&'static (i64, &'static str, Format)> {
+        TAGS.iter()
+            .find(|(offset, _, _)| 0x60000000 + offset == tag.0)
+    }
+
+    /// The tags the Solaris link editor puts in the OS-specific range,
+    /// some of which Android reuses for its packed relocations.
+    pub struct DynamicTags;
+
+    impl DynamicTagDecoder for DynamicTags {
+        fn handles(&self, elf_file: &ElfFile, tag: DynamicTag) -> bool {
+            is_solaris(elf_file) && lookup(tag).is_some()
+        }
+
+        fn tag_name(&self, tag: DynamicTag) -> String {
+            lookup(tag).map_or_else(|| format!("{:#x}", tag.0), |(_, name, _)| name.to_string())
+        }
+
+        fn decode(&self, elf_file: &ElfFile, entry: &DynamicEntry) -> Result<String> {
+            let value = entry.d_val;
+            Ok(match lookup(entry.d_tag).map(|&(_, _, format)| format) {
+                Some(Format::Number) => value.to_string(),
+                Some(Format::String) => {
+                    let strtab = elf_file.dynamic_strtab(&elf_file.dynamic_entries()?)?;
+                    crate::elf::read_str(strtab, value as usize)?.to_string()
+                }
+                Some(Format::Machine) => format!("machine {}", value),
+                Some(Format::Switch) if value == 0 => "disabled".to_string(),
+                Some(Format::Switch) => "enabled".to_string(),
+                _ => format!("{:#x}", value),
+            })
+        }
+    }
+}
+
+#[cfg(feature = "sparc")]
+mod sparc {
+    use super::*;
+    use crate::consts::DT_SPARC_REGISTER;
+    use crate::emachine::EMachine;
+
+    /// The one SPARC-specific tag, DT_SPARC_REGISTER, which points at the
+    /// dynamic symbol of a global register the object uses.
+    pub struct DynamicTags;
+
+    impl DynamicTagDecoder for DynamicTags {
+        fn handles(&self, elf_file: &ElfFile, tag: DynamicTag) -> bool {
+            matches!(
+                elf_file.machine(),
+                EMachine::Sparc | EMachine::Sparc32Plus | EMachine::SparcV9
+            ) && tag == DT_SPARC_REGISTER
+        }
+
+        fn tag_name(&self, _tag: DynamicTag) -> String {
+            "SPARC_REGISTER".to_string()
+        }
+
+        fn decode(&self, _elf_file: &ElfFile, entry: &DynamicEntry) -> Result<String> {
+            Ok(format!("{:#x}", entry.d_val))
+        }
+    }
+}
+
+#[cfg(feature = "xtensa")]
+mod xtensa {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::emachine::EMachine;
+    use crate::section::SectionType;
+    use crate::strtab::SymbolTable;
+
+    /// What each bit of a property entry's flags says about its range.
+    const PROPERTY_FLAGS: &[(u32, &str)] = &[
+        (0x00001, "literal"),
+        (0x00002, "insn"),
+        (0x00004, "data"),
+        (0x00008, "unreachable"),
+        (0x00010, "loop-target"),
+        (0x00020, "branch-target"),
+        (0x00040, "no-density"),
+        (0x00080, "no-reorder"),
+        (0x00100, "no-transform"),
+        (0x20000, "abslit"),
+    ];
+    const XTENSA_PROP_BT_ALIGN_MASK: u32 = 0x600;
+    const XTENSA_PROP_ALIGN: u32 = 0x800;
+    const XTENSA_PROP_ALIGNMENT_MASK: u32 = 0x1f000;
+
+    fn property_flags(flags: u32) -> String {
+        let mut names: Vec<String> = PROPERTY_FLAGS
+            .iter()
+            .filter(|&&(bit, _)| flags & bit != 0)
+            .map(|&(_, name)| name.to_string())
+            .collect();
+        match (flags & XTENSA_PROP_BT_ALIGN_MASK) >> 9 {
+            0 => {}
+            1 => names.push("bt-align low".to_string()),
+            2 => names.push("bt-align high".to_string()),
+            _ => names.push("bt-align required".to_string()),
+        }
+        if flags & XTENSA_PROP_ALIGN != 0 {
+            let shift = (flags & XTENSA_PROP_ALIGNMENT_MASK) >> 12;
+            names.push(format!("align {}", 1u64 << shift));
+        }
+        names.join(", ")
+    }
+
+    /// Whether `name` is one of the tables, including the per-group ones
+    /// the assembler makes for COMDAT sections.
+    fn is_table(name: &str, table: &str, linkonce: &str) -> bool {
+        name == table
+            || name.strip_prefix(table).is_some_and(|s| s.starts_with('.'))
+            || name.starts_with(linkonce)
+    }
+
+    fn table_name<'a>(elf_file: &'a ElfFile, section: &SectionHeader) -> Option<&'a str> {
+        if elf_file.machine() != EMachine::Xtensa {
+            return None;
+        }
+        let sections = elf_file.section_headers().ok()?;
+        elf_file.section_name(&sections, section).ok()
+    }
+
+    /// The start addresses of the table's entries. In relocatable objects
+    /// the addresses are left to relocations, so those are shown as the
+    /// symbol and addend they refer to.
+    fn addresses(elf_file: &ElfFile, section: &SectionHeader) -> Result<HashMap<u64, String>> {
+        let mut addresses = HashMap::new();
+        let sections = elf_file.section_headers()?;
+        let Some(index) = sections
+            .iter()
+            .position(|s| s.sh_name == section.sh_name && s.sh_offset == section.sh_offset)
+        else {
+            return Ok(addresses);
+        };
+
+        for rel in sections.iter().filter(|s| {
+            matches!(s.sh_type, SectionType::REL | SectionType::RELA) && s.sh_info as usize == index
+        }) {
+            let symbols = SymbolTable::linked(elf_file, &sections, rel)?;
+            for reloc in elf_file.relocations(rel)? {
+                let Some(symbol) = symbols.get(reloc.r_sym as usize) else {
+                    continue;
+                };
+                let name = match symbols.name(reloc.r_sym as usize)? {
+                    // Section symbols have no name of their own.
+                    "" => sections
+                        .get(symbol.st_shndx as usize)
+                        .and_then(|s| elf_file.section_name(&sections, s).ok())
+                        .unwrap_or(""),
+                    name => name,
+                };
+                addresses.insert(
+                    reloc.r_offset,
+                    format!("{}+{:#x}", name, reloc.r_addend.unwrap_or(0)),
+                );
+            }
+        }
+        Ok(addresses)
+    }
+
+    /// Applies `line` to each entry of a table of 32-bit words, with the
+    /// description of the address in its first word.
+    fn entries(
+        elf_file: &ElfFile,
+        section: &SectionHeader,
+        words: usize,
+        line: impl Fn(String, &[u32]) -> String,
+    ) -> Result<Vec<String>> {
+        let addresses = addresses(elf_file, section)?;
+        let data = elf_file.section_data(section)?;
+        Ok(data
+            .chunks_exact(words * 4)
+            .enumerate()
+            .map(|(i, entry)| {
+                let entry: Vec<u32> = entry
+                    .chunks_exact(4)
+                    .map(|w| u32::from_ne_bytes(w.try_into().unwrap()))
+                    .collect();
+                let address = addresses
+                    .get(&((i * words * 4) as u64))
+                    .cloned()
+                    .unwrap_or_else(|| format!("{:#010x}", entry[0]));
+                line(address, &entry)
+            })
+            .collect())
+    }
+
+    /// The property tables, which tell the linker which ranges of a
+    /// section hold instructions, literals or data, so that it can relax
+    /// the code without breaking it.
+    pub struct Properties;
+
+    impl SectionDecoder for Properties {
+        fn handles(&self, elf_file: &ElfFile, section: &SectionHeader) -> bool {
+            table_name(elf_file, section)
+                .is_some_and(|name| is_table(name, ".xt.prop", ".gnu.linkonce.prop."))
+        }
+
+        fn type_name(&self) -> &str {
+            "Xtensa properties"
+        }
+
+        fn decode(&self, elf_file: &ElfFile, section: &SectionHeader) -> Result<Vec<String>> {
+            entries(elf_file, section, 3, |address, entry| {
+                format!(
+                    "{:<24} size {:#8x}  {}",
+                    address,
+                    entry[1],
+                    property_flags(entry[2])
+                )
+                .trim_end()
+                .to_string()
+            })
+        }
+    }
+
+    /// The literal tables, the ranges that hold literals for `l32r`
+    /// instructions to load.
+    pub struct Literals;
+
+    impl SectionDecoder for Literals {
+        fn handles(&self, elf_file: &ElfFile, section: &SectionHeader) -> bool {
+            table_name(elf_file, section)
+                .is_some_and(|name| is_table(name, ".xt.lit", ".gnu.linkonce.p."))
+        }
+
+        fn type_name(&self) -> &str {
+            "Xtensa literals"
+        }
+
+        fn decode(&self, elf_file: &ElfFile, section: &SectionHeader) -> Result<Vec<String>> {
+            entries(elf_file, section, 2, |address, entry| {
+                format!("{:<24} size {:#8x}", address, entry[1])
+            })
+        }
+    }
+}
+
+#[cfg(feature = "bpf")]
+mod bpf {
+    use super::*;
+    use crate::emachine::EMachine;
+    use crate::strtab::SymbolTable;
+
+    const MAP_TYPES: &[&str] = &[
+        "UNSPEC",
+        "HASH",
+        "ARRAY",
+        "PROG_ARRAY",
+        "PERF_EVENT_ARRAY",
+        "PERCPU_HASH",
+        "PERCPU_ARRAY",
+        "STACK_TRACE",
+        "CGROUP_ARRAY",
+        "LRU_HASH",
+        "LRU_PERCPU_HASH",
+        "LPM_TRIE",
+        "ARRAY_OF_MAPS",
+        "HASH_OF_MAPS",
+        "DEVMAP",
+        "SOCKMAP",
+        "CPUMAP",
+        "XSKMAP",
+        "SOCKHASH",
+        "CGROUP_STORAGE",
+        "REUSEPORT_SOCKARRAY",
+        "PERCPU_CGROUP_STORAGE",
+        "QUEUE",
+        "STACK",
+        "SK_STORAGE",
+        "DEVMAP_HASH",
+        "STRUCT_OPS",
+        "RINGBUF",
+        "INODE_STORAGE",
+        "TASK_STORAGE",
+        "BLOOM_FILTER",
+        "USER_RINGBUF",
+        "CGRP_STORAGE",
+        "ARENA",
+    ];
+
+    fn map_type(value: u64) -> String {
+        MAP_TYPES
+            .get(value as usize)
+            .map_or_else(|| format!("<unknown: {}>", value), |name| name.to_string())
+    }
+
+    const BTF_MAGIC: u16 = 0xeb9f;
+
+    const BTF_KIND_INT: u32 = 1;
+    const BTF_KIND_PTR: u32 = 2;
+    const BTF_KIND_ARRAY: u32 = 3;
+    const BTF_KIND_STRUCT: u32 = 4;
+    const BTF_KIND_UNION: u32 = 5;
+    const BTF_KIND_ENUM: u32 = 6;
+    const BTF_KIND_TYPEDEF: u32 = 8;
+    const BTF_KIND_VOLATILE: u32 = 9;
+    const BTF_KIND_CONST: u32 = 10;
+    const BTF_KIND_RESTRICT: u32 = 11;
+    const BTF_KIND_FUNC_PROTO: u32 = 13;
+    const BTF_KIND_VAR: u32 = 14;
+    const BTF_KIND_DATASEC: u32 = 15;
+    const BTF_KIND_FLOAT: u32 = 16;
+    const BTF_KIND_DECL_TAG: u32 = 17;
+    const BTF_KIND_TYPE_TAG: u32 = 18;
+    const BTF_KIND_ENUM64: u32 = 19;
+
+    /// One entry of the BTF type section: the common header, with the
+    /// kind-specific data that follows it.
+    struct BtfType<'a> {
+        name_off: u32,
+        kind: u32,
+        vlen: usize,
+        /// The size, or the type referred to, depending on the kind.
+        size_or_type: u32,
+        extra: &'a [u8],
+    }
+
+    /// The type information of a `.BTF` section, as far as describing maps
+    /// needs it.
+    struct Btf<'a> {
+        /// Indexed by type ID; ID 0, void, has no entry.
+        types: Vec<BtfType<'a>>,
+        strings: &'a [u8],
+    }
+
+    fn word(data: &[u8], offset: usize) -> Option<u32> {
+        data.get(offset..offset + 4)
+            .map(|w| u32::from_ne_bytes(w.try_into().unwrap()))
+    }
+
+    impl<'a> Btf<'a> {
+        fn parse(data: &'a [u8]) -> Option<Self> {
+            if data.get(..2)? != BTF_MAGIC.to_ne_bytes() {
+                return None;
+            }
+            let header_len = word(data, 4)? as usize;
+            let range = |offset, len| {
+                let start = header_len + word(data, offset)? as usize;
+                data.get(start..start + word(data, len)? as usize)
+            };
+            let (mut section, strings) = (range(8, 12)?, range(16, 20)?);
+
+            let mut types = Vec::new();
+            while section.len() >= 12 {
+                let info = word(section, 4)?;
+                let (kind, vlen) = ((info >> 24) & 0x1f, (info & 0xffff) as usize);
+                let extra = match kind {
+                    BTF_KIND_INT | BTF_KIND_VAR | BTF_KIND_DECL_TAG => 4,
+                    BTF_KIND_ARRAY => 12,
+                    BTF_KIND_STRUCT | BTF_KIND_UNION | BTF_KIND_DATASEC | BTF_KIND_ENUM64 => {
+                        12 * vlen
+                    }
+                    BTF_KIND_ENUM | BTF_KIND_FUNC_PROTO => 8 * vlen,
+                    0..=BTF_KIND_ENUM64 => 0,
+                    _ => return None,
+                };
+                types.push(BtfType {
+                    name_off: word(section, 0)?,
+                    kind,
+                    vlen,
+                    size_or_type: word(section, 8)?,
+                    extra: section.get(12..12 + extra)?,
+                });
+                section = &section[12 + extra..];
+            }
+            Some(Self { types, strings })
+        }
+
+        fn get(&self, id: u32) -> Option<&BtfType<'a>> {
+            self.types.get((id as usize).checked_sub(1)?)
+        }
+
+        fn string(&self, offset: u32) -> &'a str {
+            let bytes = self.strings.get(offset as usize..).unwrap_or_default();
+            let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            std::str::from_utf8(&bytes[..len]).unwrap_or("")
+        }
+
+        /// Follows typedefs and qualifiers to the type they stand for.
+        fn resolve(&self, mut id: u32) -> Option<&BtfType<'a>> {
+            for _ in 0..32 {
+                let ty = self.get(id)?;
+                match ty.kind {
+                    BTF_KIND_TYPEDEF | BTF_KIND_VOLATILE | BTF_KIND_CONST | BTF_KIND_RESTRICT
+                    | BTF_KIND_TYPE_TAG => id = ty.size_or_type,
+                    _ => return Some(ty),
+                }
+            }
+            None
+        }
+
+        fn size(&self, id: u32) -> Option<u64> {
+            let ty = self.resolve(id)?;
+            match ty.kind {
+                BTF_KIND_INT | BTF_KIND_STRUCT | BTF_KIND_UNION | BTF_KIND_ENUM
+                | BTF_KIND_ENUM64 | BTF_KIND_FLOAT => Some(ty.size_or_type as u64),
+                BTF_KIND_PTR => Some(8),
+                BTF_KIND_ARRAY => Some(self.size(word(ty.extra, 0)?)? * word(ty.extra, 8)? as u64),
+                _ => None,
+            }
+        }
+
+        /// The `(name, type)` of each member of a struct.
+        fn members(&self, ty: &BtfType<'a>) -> Vec<(&'a str, u32)> {
+            ty.extra
+                .chunks_exact(12)
+                .take(ty.vlen)
+                .map(|m| (self.string(word(m, 0).unwrap()), word(m, 4).unwrap()))
+                .collect()
+        }
+
+        /// The variables of the data section `name`, with their offsets.
+        fn datasec(&self, name: &str) -> Vec<(u32, u32)> {
+            self.types
+                .iter()
+                .find(|t| t.kind == BTF_KIND_DATASEC && self.string(t.name_off) == name)
+                .map(|t| {
+                    t.extra
+                        .chunks_exact(12)
+                        .map(|v| (word(v, 0).unwrap(), word(v, 4).unwrap()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    }
+
+    /// Describes a map defined with the BTF conventions of libbpf: a
+    /// variable whose type is a struct of pointers, which encode numbers
+    /// as the length of the array they point to (`__uint`) and the key and
+    /// value types as what they point to (`__type`).
+    fn btf_map(btf: &Btf, var: u32) -> Option<(String, String)> {
+        let var = btf.get(var).filter(|v| v.kind == BTF_KIND_VAR)?;
+        let definition = btf.resolve(var.size_or_type)?;
+        if definition.kind != BTF_KIND_STRUCT {
+            return None;
+        }
+
+        let pointee = |member: u32| {
+            let pointer = btf.resolve(member).filter(|p| p.kind == BTF_KIND_PTR)?;
+            Some(pointer.size_or_type)
+        };
+        let number = |member: u32| {
+            let array = btf
+                .resolve(pointee(member)?)
+                .filter(|a| a.kind == BTF_KIND_ARRAY)?;
+            word(array.extra, 8)
+        };
+        let mut fields = Vec::new();
+        for (name, member) in btf.members(definition) {
+            let field = match name {
+                "type" => number(member).map(|n| format!("type {}", map_type(n as u64))),
+                "key" | "value" => pointee(member).and_then(|id| {
+                    let size = btf.size(id)?;
+                    Some(match btf.string(btf.get(id)?.name_off) {
+                        "" => format!("{} {} bytes", name, size),
+                        ty => format!("{} {} bytes ({})", name, size, ty),
+                    })
+                }),
+                "key_size" | "value_size" => number(member)
+                    .map(|n| format!("{} {} bytes", name.trim_end_matches("_size"), n)),
+                "map_flags" => number(member).map(|n| format!("flags {:#x}", n)),
+                "max_entries" | "pinning" | "numa_node" | "map_extra" => {
+                    number(member).map(|n| format!("{} {}", name, n))
+                }
+                "values" => Some("values initialized".to_string()),
+                _ => None,
+            };
+            fields.extend(field);
+        }
+        Some((btf.string(var.name_off).to_string(), fields.join(", ")))
+    }
+
+    /// The map definitions of a BPF object: `.maps` holds the variables
+    /// BTF describes, while the older `maps` holds `struct bpf_map_def`s.
+    pub struct Maps;
+
+    impl SectionDecoder for Maps {
+        fn handles(&self, elf_file: &ElfFile, section: &SectionHeader) -> bool {
+            elf_file.machine() == EMachine::Bpf
+                && elf_file.section_headers().is_ok_and(|sections| {
+                    elf_file
+                        .section_name(&sections, section)
+                        .is_ok_and(|name| matches!(name, "maps" | ".maps"))
+                })
+        }
+
+        fn type_name(&self) -> &str {
+            "BPF maps"
+        }
+
+        fn decode(&self, elf_file: &ElfFile, section: &SectionHeader) -> Result<Vec<String>> {
+            let sections = elf_file.section_headers()?;
+            let name = elf_file.section_name(&sections, section)?;
+
+            if name == ".maps" {
+                let btf = sections
+                    .iter()
+                    .find(|s| {
+                        elf_file
+                            .section_name(&sections, s)
+                            .is_ok_and(|n| n == ".BTF")
+                    })
+                    .map(|s| elf_file.section_data(s))
+                    .transpose()?
+                    .and_then(Btf::parse);
+                let Some(btf) = btf else {
+                    return Ok(vec![
+                        "No usable .BTF section to describe the maps.".to_string(),
+                    ]);
+                };
+                let mut lines: Vec<String> = btf
+                    .datasec(".maps")
+                    .into_iter()
+                    .filter_map(|(var, offset)| {
+                        let (name, fields) = btf_map(&btf, var)?;
+                        Some(format!("{:#06x} {}: {}", offset, name, fields))
+                    })
+                    .collect();
+                lines.sort();
+                return Ok(lines);
+            }
+
+            // The legacy definitions are only told apart by the symbols
+            // naming them, all of the same size.
+            let Some(index) = sections
+                .iter()
+                .position(|s| s.sh_name == section.sh_name && s.sh_offset == section.sh_offset)
+            else {
+                return Ok(Vec::new());
+            };
+            let Some(symtab) = elf_file.symbol_table(&sections) else {
+                return Ok(vec!["No symbol table to name the maps.".to_string()]);
+            };
+            let symbols = SymbolTable::new(elf_file, &sections, symtab)?;
+            let mut maps: Vec<(u64, &str)> = symbols
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.st_shndx as usize == index)
+                .filter_map(|(i, s)| Some((s.st_value, symbols.name(i).ok()?)))
+                .filter(|(_, name)| !name.is_empty())
+                .collect();
+            maps.sort();
+            let data = elf_file.section_data(section)?;
+            let Some(size) = data.len().checked_div(maps.len()) else {
+                return Ok(Vec::new());
+            };
+
+            Ok(maps
+                .into_iter()
+                .map(|(offset, name)| {
+                    let field =
+                        |i: usize| word(data, offset as usize + i * 4).filter(|_| i * 4 < size);
+                    let mut line = format!("{:#06x} {}:", offset, name);
+                    if let Some(ty) = field(0) {
+                        line += &format!(" type {}", map_type(ty as u64));
+                    }
+                    if let (Some(key), Some(value)) = (field(1), field(2)) {
+                        line += &format!(", key {} bytes, value {} bytes", key, value);
+                    }
+                    if let Some(max) = field(3) {
+                        line += &format!(", max_entries {}", max);
+                    }
+                    if let Some(flags) = field(4).filter(|&f| f != 0) {
+                        line += &format!(", flags {:#x}", flags);
+                    }
+                    line
+                })
+                .collect())
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! PowerPC64 specifics. The original ABI (ELFv1) calls functions through
+//! descriptors in `.opd`, so function symbols hold the address of the
+//! descriptor rather than of the code. ELFv2 does away with descriptors but
+//! gives functions a second, local entry point that skips setting up the
+//! TOC pointer, recorded in the symbol's `st_other`.
+
+use anyhow::Result;
+
+use crate::elf::{ElfFile, ElfType};
+use crate::emachine::EMachine;
+use crate::section::{SHN_UNDEF, SectionHeader};
+use crate::strtab::SymbolTable;
+
+const EF_PPC64_ABI: u32 = 3;
+const STO_PPC64_LOCAL_MASK: u8 = 0xe0;
+
+/// The TOC pointer is this far past the start of the `.got`, so that a
+/// signed 16-bit offset reaches 64KiB of it.
+const TOC_BIAS: u64 = 0x8000;
+
+/// Size of an ELFv1 function descriptor: entry point, TOC pointer and
+/// environment pointer.
+pub const DESCRIPTOR_SIZE: usize = 24;
+
+pub fn is_ppc64(elf_file: &ElfFile) -> bool {
+    elf_file.machine() == EMachine::Ppc64
+}
+
+/// The ABI version from `e_flags`: 1 or 2, or 0 for objects that predate
+/// the field, which follow ELFv1.
+pub fn abi_version(elf_file: &ElfFile) -> u32 {
+    elf_file.header().e_flags & EF_PPC64_ABI
+}
+
+/// Whether functions are called through descriptors.
+pub fn uses_descriptors(elf_file: &ElfFile) -> bool {
+    is_ppc64(elf_file) && abi_version(elf_file) != 2
+}
+
+/// Whether descriptors can be followed to the code: in relocatable
+/// objects, they are only filled in by relocations.
+pub fn descriptors_resolved(elf_file: &ElfFile) -> bool {
+    uses_descriptors(elf_file) && elf_file.elf_type() != ElfType::REL
+}
+
+/// How far past the global entry point a function's local entry point is,
+/// from its `st_other`. Zero when they are the same.
+pub fn local_entry_offset(st_other: u8) -> u64 {
+    match (st_other & STO_PPC64_LOCAL_MASK) >> 5 {
+        0 | 1 => 0,
+        n => ((1u64 << n) >> 2) << 2,
+    }
+}
+
+/// The value of the TOC pointer: the `.TOC.` symbol if there is one,
+/// otherwise the conventional offset into `.got`.
+pub fn toc_base(elf_file: &ElfFile, sections: &[SectionHeader]) -> Result<Option<u64>> {
+    if let Some(symtab) = elf_file.symbol_table(sections) {
+        let symbols = SymbolTable::new(elf_file, sections, symtab)?;
+        let toc = symbols
+            .iter()
+            .enumerate()
+            .find(|&(i, s)| s.st_shndx != SHN_UNDEF && symbols.name(i).is_ok_and(|n| n == ".TOC."));
+        if let Some((_, symbol)) = toc {
+            return Ok(Some(symbol.st_value));
+        }
+    }
+
+    Ok(sections
+        .iter()
+        .find(|s| {
+            elf_file
+                .section_name(sections, s)
+                .is_ok_and(|n| n == ".got")
+        })
+        .map(|got| got.sh_addr + TOC_BIAS))
+}
+
+/// An ELFv1 function descriptor.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy)]
+pub struct Descriptor {
+    pub address: u64,
+    pub entry: u64,
+    pub toc: u64,
+    pub environment: u64,
+}
+
+/// The function descriptors in the `.opd` section.
+pub fn descriptors(elf_file: &ElfFile, opd: &SectionHeader) -> Result<Vec<Descriptor>> {
+    let word = |bytes: &[u8]| u64::from_ne_bytes(bytes.try_into().unwrap());
+    Ok(elf_file
+        .section_data(opd)?
+        .chunks_exact(DESCRIPTOR_SIZE)
+        .enumerate()
+        .map(|(i, d)| Descriptor {
+            address: opd.sh_addr + (i * DESCRIPTOR_SIZE) as u64,
+            entry: word(&d[..8]),
+            toc: word(&d[8..16]),
+            environment: word(&d[16..]),
+        })
+        .collect())
+}
+
+/// The `.opd` section, in files that have one.
+pub fn opd_section<'s>(
+    elf_file: &ElfFile,
+    sections: &'s [SectionHeader],
+) -> Option<&'s SectionHeader> {
+    sections.iter().find(|s| {
+        elf_file
+            .section_name(sections, s)
+            .is_ok_and(|n| n == ".opd")
+    })
+}
@@ -0,0 +1,955 @@
+use anyhow::{Context, Result, bail};
+
+use crate::elf::ElfFile;
+use crate::emachine::EMachine;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf64Rela {
+    pub r_offset: u64,
+    pub r_info: u64,
+    pub r_addend: i64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf64Rel {
+    pub r_offset: u64,
+    pub r_info: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf32Rela {
+    pub r_offset: u32,
+    pub r_info: u32,
+    pub r_addend: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf32Rel {
+    pub r_offset: u32,
+    pub r_info: u32,
+}
+
+/// A relocation entry with `r_info` split into its symbol index and type.
+/// `r_addend` is `None` for REL-style entries, whose addend lives at the
+/// relocated location instead.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Relocation {
+    pub r_offset: u64,
+    pub r_sym: u32,
+    pub r_type: u32,
+    pub r_addend: Option<i64>,
+}
+
+impl From<Elf64Rela> for Relocation {
+    fn from(rela: Elf64Rela) -> Self {
+        Self {
+            r_offset: rela.r_offset,
+            r_sym: (rela.r_info >> 32) as u32,
+            r_type: rela.r_info as u32,
+            r_addend: Some(rela.r_addend),
+        }
+    }
+}
+
+impl From<Elf64Rel> for Relocation {
+    fn from(rel: Elf64Rel) -> Self {
+        Self {
+            r_offset: rel.r_offset,
+            r_sym: (rel.r_info >> 32) as u32,
+            r_type: rel.r_info as u32,
+            r_addend: None,
+        }
+    }
+}
+
+impl From<Elf32Rela> for Relocation {
+    fn from(rela: Elf32Rela) -> Self {
+        Self {
+            r_offset: rela.r_offset.into(),
+            r_sym: rela.r_info >> 8,
+            r_type: rela.r_info & 0xff,
+            r_addend: Some(rela.r_addend.into()),
+        }
+    }
+}
+
+impl From<Elf32Rel> for Relocation {
+    fn from(rel: Elf32Rel) -> Self {
+        Self {
+            r_offset: rel.r_offset.into(),
+            r_sym: rel.r_info >> 8,
+            r_type: rel.r_info & 0xff,
+            r_addend: None,
+        }
+    }
+}
+
+/// Expands a RELR table into the addresses it relocates. Each even entry
+/// is an address; each odd entry is a bitmap of which of the following
+/// `word * 8 - 1` words need relocating as well. Addresses wrap around
+/// the address space, as they do for GNU readelf and llvm-readelf.
+pub fn decode_relr(data: &[u8], is_64: bool) -> Vec<u64> {
+    let word: u64 = if is_64 { 8 } else { 4 };
+    let bits = word * 8 - 1;
+
+    let mut addresses = Vec::new();
+    let mut base = 0;
+    for chunk in data.chunks_exact(word as usize) {
+        let entry = if is_64 {
+            u64::from_ne_bytes(chunk.try_into().unwrap())
+        } else {
+            u32::from_ne_bytes(chunk.try_into().unwrap()).into()
+        };
+
+        if entry & 1 == 0 {
+            addresses.push(entry);
+            base = entry.wrapping_add(word);
+        } else {
+            let mut bitmap = entry >> 1;
+            let mut address = base;
+            while bitmap != 0 {
+                if bitmap & 1 != 0 {
+                    addresses.push(address);
+                }
+                bitmap >>= 1;
+                address = address.wrapping_add(word);
+            }
+            base = base.wrapping_add(bits * word);
+        }
+    }
+
+    addresses
+}
+
+fn read_sleb128(data: &[u8], offset: &mut usize) -> Result<i64> {
+    let mut result: i64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *data
+            .get(*offset)
+            .context("Packed relocations end in the middle of a number")?;
+        *offset += 1;
+        if shift < 64 {
+            result |= ((byte & 0x7f) as i64) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            if shift < 64 && byte & 0x40 != 0 {
+                result |= -1 << shift;
+            }
+            return Ok(result);
+        }
+    }
+}
+
+fn read_uleb128(data: &[u8], offset: &mut usize) -> Result<u64> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *data
+            .get(*offset)
+            .context("Relocations end in the middle of a number")?;
+        *offset += 1;
+        if shift < 64 {
+            result |= ((byte & 0x7f) as u64) << shift;
+        }
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+    }
+}
+
+/// Set in the CREL header when the entries carry explicit addends.
+const CREL_HDR_ADDEND: u64 = 4;
+
+/// Decodes a compact relocation (CREL) section. A ULEB128 header holds the
+/// count, whether addends are present, and how far offsets are shifted;
+/// each entry then stores deltas from the previous one, with flag bits in
+/// its first byte saying which of the symbol, type and addend change.
+pub fn decode_crel(data: &[u8]) -> Result<Vec<Relocation>> {
+    let mut cursor = 0;
+    let header = read_uleb128(data, &mut cursor)?;
+    let count = header / 8;
+    let has_addend = header & CREL_HDR_ADDEND != 0;
+    let flag_bits = if has_addend { 3 } else { 2 };
+    let shift = header % CREL_HDR_ADDEND;
+
+    let (mut offset, mut addend) = (0u64, 0i64);
+    let (mut r_sym, mut r_type) = (0u32, 0u32);
+    let mut relocations = Vec::new();
+    for _ in 0..count {
+        let byte = *data
+            .get(cursor)
+            .context("CREL section ends in the middle of an entry")?;
+        cursor += 1;
+
+        // The first byte holds the flags and the low bits of the offset
+        // delta, which continues as a ULEB128 if the top bit is set.
+        offset = offset.wrapping_add((byte >> flag_bits) as u64);
+        if byte >= 0x80 {
+            offset = offset
+                .wrapping_add(read_uleb128(data, &mut cursor)? << (7 - flag_bits))
+                .wrapping_sub(0x80 >> flag_bits);
+        }
+        if byte & 1 != 0 {
+            r_sym = r_sym.wrapping_add(read_sleb128(data, &mut cursor)? as u32);
+        }
+        if byte & 2 != 0 {
+            r_type = r_type.wrapping_add(read_sleb128(data, &mut cursor)? as u32);
+        }
+        if byte & 4 != 0 && has_addend {
+            addend = addend.wrapping_add(read_sleb128(data, &mut cursor)?);
+        }
+
+        relocations.push(Relocation {
+            r_offset: offset << shift,
+            r_sym,
+            r_type,
+            r_addend: has_addend.then_some(addend),
+        });
+    }
+
+    Ok(relocations)
+}
+
+const RELOCATION_GROUPED_BY_INFO_FLAG: i64 = 1;
+const RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG: i64 = 2;
+const RELOCATION_GROUPED_BY_ADDEND_FLAG: i64 = 4;
+const RELOCATION_GROUP_HAS_ADDEND_FLAG: i64 = 8;
+
+/// Decodes Android's packed relocation format ("APS2"), a stream of SLEB128
+/// numbers describing groups of relocations that share fields. Offsets and
+/// addends wrap around, as they do for llvm-readelf.
+pub fn decode_aps2(data: &[u8], is_64: bool, is_rela: bool) -> Result<Vec<Relocation>> {
+    if !data.starts_with(b"APS2") {
+        bail!("Packed relocation section does not start with APS2");
+    }
+
+    let mut offset = 4;
+    let mut next = || read_sleb128(data, &mut offset);
+
+    let count = next()?;
+    let mut r_offset = next()? as u64;
+    let mut r_info = 0;
+    let mut r_addend = 0;
+
+    let mut relocations = Vec::new();
+    while (relocations.len() as i64) < count {
+        let group_size = next()?;
+        let flags = next()?;
+        let grouped_by_info = flags & RELOCATION_GROUPED_BY_INFO_FLAG != 0;
+        let grouped_by_offset = flags & RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG != 0;
+        let grouped_by_addend = flags & RELOCATION_GROUPED_BY_ADDEND_FLAG != 0;
+        let has_addend = flags & RELOCATION_GROUP_HAS_ADDEND_FLAG != 0;
+
+        let offset_delta = if grouped_by_offset { next()? } else { 0 };
+        if grouped_by_info {
+            r_info = next()? as u64;
+        }
+        if has_addend && grouped_by_addend {
+            if !is_rela {
+                bail!("Packed REL relocations cannot have addends");
+            }
+            r_addend = next()?.wrapping_add(r_addend);
+        } else if !has_addend {
+            r_addend = 0;
+        }
+
+        // The loop only runs while fewer than `count` have been decoded,
+        // so what is left is positive.
+        if group_size < 0 || group_size > count - relocations.len() as i64 {
+            bail!("Packed relocation group of {} is out of range", group_size);
+        }
+
+        for _ in 0..group_size {
+            r_offset = r_offset.wrapping_add(if grouped_by_offset {
+                offset_delta
+            } else {
+                next()?
+            } as u64);
+            if !grouped_by_info {
+                r_info = next()? as u64;
+            }
+            if is_rela && has_addend && !grouped_by_addend {
+                r_addend = next()?.wrapping_add(r_addend);
+            }
+
+            let (r_sym, r_type) = if is_64 {
+                ((r_info >> 32) as u32, r_info as u32)
+            } else {
+                ((r_info as u32) >> 8, r_info as u32 & 0xff)
+            };
+            relocations.push(Relocation {
+                r_offset,
+                r_sym,
+                r_type,
+                r_addend: is_rela.then_some(r_addend),
+            });
+        }
+    }
+
+    Ok(relocations)
+}
+
+const X86_64_TYPES: &[&str] = &[
+    "R_X86_64_NONE",
+    "R_X86_64_64",
+    "R_X86_64_PC32",
+    "R_X86_64_GOT32",
+    "R_X86_64_PLT32",
+    "R_X86_64_COPY",
+    "R_X86_64_GLOB_DAT",
+    "R_X86_64_JUMP_SLOT",
+    "R_X86_64_RELATIVE",
+    "R_X86_64_GOTPCREL",
+    "R_X86_64_32",
+    "R_X86_64_32S",
+    "R_X86_64_16",
+    "R_X86_64_PC16",
+    "R_X86_64_8",
+    "R_X86_64_PC8",
+    "R_X86_64_DTPMOD64",
+    "R_X86_64_DTPOFF64",
+    "R_X86_64_TPOFF64",
+    "R_X86_64_TLSGD",
+    "R_X86_64_TLSLD",
+    "R_X86_64_DTPOFF32",
+    "R_X86_64_GOTTPOFF",
+    "R_X86_64_TPOFF32",
+    "R_X86_64_PC64",
+    "R_X86_64_GOTOFF64",
+    "R_X86_64_GOTPC32",
+    "R_X86_64_GOT64",
+    "R_X86_64_GOTPCREL64",
+    "R_X86_64_GOTPC64",
+    "R_X86_64_GOTPLT64",
+    "R_X86_64_PLTOFF64",
+    "R_X86_64_SIZE32",
+    "R_X86_64_SIZE64",
+    "R_X86_64_GOTPC32_TLSDESC",
+    "R_X86_64_TLSDESC_CALL",
+    "R_X86_64_TLSDESC",
+    "R_X86_64_IRELATIVE",
+    "R_X86_64_RELATIVE64",
+    "R_X86_64_PC32_BND",
+    "R_X86_64_PLT32_BND",
+    "R_X86_64_GOTPCRELX",
+    "R_X86_64_REX_GOTPCRELX",
+];
+
+const I386_TYPES: &[&str] = &[
+    "R_386_NONE",
+    "R_386_32",
+    "R_386_PC32",
+    "R_386_GOT32",
+    "R_386_PLT32",
+    "R_386_COPY",
+    "R_386_GLOB_DAT",
+    "R_386_JUMP_SLOT",
+    "R_386_RELATIVE",
+    "R_386_GOTOFF",
+    "R_386_GOTPC",
+    "R_386_32PLT",
+    "",
+    "",
+    "R_386_TLS_TPOFF",
+    "R_386_TLS_IE",
+    "R_386_TLS_GOTIE",
+    "R_386_TLS_LE",
+    "R_386_TLS_GD",
+    "R_386_TLS_LDM",
+    "R_386_16",
+    "R_386_PC16",
+    "R_386_8",
+    "R_386_PC8",
+    "R_386_TLS_GD_32",
+    "R_386_TLS_GD_PUSH",
+    "R_386_TLS_GD_CALL",
+    "R_386_TLS_GD_POP",
+    "R_386_TLS_LDM_32",
+    "R_386_TLS_LDM_PUSH",
+    "R_386_TLS_LDM_CALL",
+    "R_386_TLS_LDM_POP",
+    "R_386_TLS_LDO_32",
+    "R_386_TLS_IE_32",
+    "R_386_TLS_LE_32",
+    "R_386_TLS_DTPMOD32",
+    "R_386_TLS_DTPOFF32",
+    "R_386_TLS_TPOFF32",
+    "R_386_SIZE32",
+    "R_386_TLS_GOTDESC",
+    "R_386_TLS_DESC_CALL",
+    "R_386_TLS_DESC",
+    "R_386_IRELATIVE",
+    "R_386_GOT32X",
+];
+
+const AARCH64_TYPES: &[(u32, &str)] = &[
+    (0, "R_AARCH64_NONE"),
+    (257, "R_AARCH64_ABS64"),
+    (258, "R_AARCH64_ABS32"),
+    (259, "R_AARCH64_ABS16"),
+    (260, "R_AARCH64_PREL64"),
+    (261, "R_AARCH64_PREL32"),
+    (262, "R_AARCH64_PREL16"),
+    (275, "R_AARCH64_ADR_PREL_PG_HI21"),
+    (277, "R_AARCH64_ADD_ABS_LO12_NC"),
+    (278, "R_AARCH64_LDST8_ABS_LO12_NC"),
+    (282, "R_AARCH64_JUMP26"),
+    (283, "R_AARCH64_CALL26"),
+    (284, "R_AARCH64_LDST16_ABS_LO12_NC"),
+    (285, "R_AARCH64_LDST32_ABS_LO12_NC"),
+    (286, "R_AARCH64_LDST64_ABS_LO12_NC"),
+    (299, "R_AARCH64_LDST128_ABS_LO12_NC"),
+    (311, "R_AARCH64_ADR_GOT_PAGE"),
+    (312, "R_AARCH64_LD64_GOT_LO12_NC"),
+    (1024, "R_AARCH64_COPY"),
+    (1025, "R_AARCH64_GLOB_DAT"),
+    (1026, "R_AARCH64_JUMP_SLOT"),
+    (1027, "R_AARCH64_RELATIVE"),
+    (1028, "R_AARCH64_TLS_DTPMOD"),
+    (1029, "R_AARCH64_TLS_DTPREL"),
+    (1030, "R_AARCH64_TLS_TPREL"),
+    (1031, "R_AARCH64_TLSDESC"),
+    (1032, "R_AARCH64_IRELATIVE"),
+];
+
+const ARM_TYPES: &[(u32, &str)] = &[
+    (0, "R_ARM_NONE"),
+    (2, "R_ARM_ABS32"),
+    (3, "R_ARM_REL32"),
+    (17, "R_ARM_TLS_DTPMOD32"),
+    (18, "R_ARM_TLS_DTPOFF32"),
+    (19, "R_ARM_TLS_TPOFF32"),
+    (20, "R_ARM_COPY"),
+    (21, "R_ARM_GLOB_DAT"),
+    (22, "R_ARM_JUMP_SLOT"),
+    (23, "R_ARM_RELATIVE"),
+    (28, "R_ARM_CALL"),
+    (29, "R_ARM_JUMP24"),
+    (30, "R_ARM_THM_JUMP24"),
+    (42, "R_ARM_PREL31"),
+    (43, "R_ARM_MOVW_ABS_NC"),
+    (44, "R_ARM_MOVT_ABS"),
+    (160, "R_ARM_IRELATIVE"),
+];
+
+const RISCV_TYPES: &[(u32, &str)] = &[
+    (0, "R_RISCV_NONE"),
+    (1, "R_RISCV_32"),
+    (2, "R_RISCV_64"),
+    (3, "R_RISCV_RELATIVE"),
+    (4, "R_RISCV_COPY"),
+    (5, "R_RISCV_JUMP_SLOT"),
+    (6, "R_RISCV_TLS_DTPMOD32"),
+    (7, "R_RISCV_TLS_DTPMOD64"),
+    (8, "R_RISCV_TLS_DTPREL32"),
+    (9, "R_RISCV_TLS_DTPREL64"),
+    (10, "R_RISCV_TLS_TPREL32"),
+    (11, "R_RISCV_TLS_TPREL64"),
+    (12, "R_RISCV_TLSDESC"),
+    (16, "R_RISCV_BRANCH"),
+    (17, "R_RISCV_JAL"),
+    (18, "R_RISCV_CALL"),
+    (19, "R_RISCV_CALL_PLT"),
+    (20, "R_RISCV_GOT_HI20"),
+    (23, "R_RISCV_PCREL_HI20"),
+    (24, "R_RISCV_PCREL_LO12_I"),
+    (25, "R_RISCV_PCREL_LO12_S"),
+    (26, "R_RISCV_HI20"),
+    (27, "R_RISCV_LO12_I"),
+    (28, "R_RISCV_LO12_S"),
+    (51, "R_RISCV_RELAX"),
+    (58, "R_RISCV_IRELATIVE"),
+];
+
+const S390_TYPES: &[&str] = &[
+    "R_390_NONE",
+    "R_390_8",
+    "R_390_12",
+    "R_390_16",
+    "R_390_32",
+    "R_390_PC32",
+    "R_390_GOT12",
+    "R_390_GOT32",
+    "R_390_PLT32",
+    "R_390_COPY",
+    "R_390_GLOB_DAT",
+    "R_390_JMP_SLOT",
+    "R_390_RELATIVE",
+    "R_390_GOTOFF32",
+    "R_390_GOTPC",
+    "R_390_GOT16",
+    "R_390_PC16",
+    "R_390_PC16DBL",
+    "R_390_PLT16DBL",
+    "R_390_PC32DBL",
+    "R_390_PLT32DBL",
+    "R_390_GOTPCDBL",
+    "R_390_64",
+    "R_390_PC64",
+    "R_390_GOT64",
+    "R_390_PLT64",
+    "R_390_GOTENT",
+    "R_390_GOTOFF16",
+    "R_390_GOTOFF64",
+    "R_390_GOTPLT12",
+    "R_390_GOTPLT16",
+    "R_390_GOTPLT32",
+    "R_390_GOTPLT64",
+    "R_390_GOTPLTENT",
+    "R_390_PLTOFF16",
+    "R_390_PLTOFF32",
+    "R_390_PLTOFF64",
+    "R_390_TLS_LOAD",
+    "R_390_TLS_GDCALL",
+    "R_390_TLS_LDCALL",
+    "R_390_TLS_GD32",
+    "R_390_TLS_GD64",
+    "R_390_TLS_GOTIE12",
+    "R_390_TLS_GOTIE32",
+    "R_390_TLS_GOTIE64",
+    "R_390_TLS_LDM32",
+    "R_390_TLS_LDM64",
+    "R_390_TLS_IE32",
+    "R_390_TLS_IE64",
+    "R_390_TLS_IEENT",
+    "R_390_TLS_LE32",
+    "R_390_TLS_LE64",
+    "R_390_TLS_LDO32",
+    "R_390_TLS_LDO64",
+    "R_390_TLS_DTPMOD",
+    "R_390_TLS_DTPOFF",
+    "R_390_TLS_TPOFF",
+    "R_390_20",
+    "R_390_GOT20",
+    "R_390_GOTPLT20",
+    "R_390_TLS_GOTIE20",
+    "R_390_IRELATIVE",
+    "R_390_PC12DBL",
+    "R_390_PLT12DBL",
+    "R_390_PC24DBL",
+    "R_390_PLT24DBL",
+];
+
+const SPARC_TYPES: &[(u32, &str)] = &[
+    (0, "R_SPARC_NONE"),
+    (1, "R_SPARC_8"),
+    (2, "R_SPARC_16"),
+    (3, "R_SPARC_32"),
+    (4, "R_SPARC_DISP8"),
+    (5, "R_SPARC_DISP16"),
+    (6, "R_SPARC_DISP32"),
+    (7, "R_SPARC_WDISP30"),
+    (8, "R_SPARC_WDISP22"),
+    (9, "R_SPARC_HI22"),
+    (10, "R_SPARC_22"),
+    (11, "R_SPARC_13"),
+    (12, "R_SPARC_LO10"),
+    (13, "R_SPARC_GOT10"),
+    (14, "R_SPARC_GOT13"),
+    (15, "R_SPARC_GOT22"),
+    (16, "R_SPARC_PC10"),
+    (17, "R_SPARC_PC22"),
+    (18, "R_SPARC_WPLT30"),
+    (19, "R_SPARC_COPY"),
+    (20, "R_SPARC_GLOB_DAT"),
+    (21, "R_SPARC_JMP_SLOT"),
+    (22, "R_SPARC_RELATIVE"),
+    (23, "R_SPARC_UA32"),
+    (24, "R_SPARC_PLT32"),
+    (25, "R_SPARC_HIPLT22"),
+    (26, "R_SPARC_LOPLT10"),
+    (27, "R_SPARC_PCPLT32"),
+    (28, "R_SPARC_PCPLT22"),
+    (29, "R_SPARC_PCPLT10"),
+    (30, "R_SPARC_10"),
+    (31, "R_SPARC_11"),
+    (32, "R_SPARC_64"),
+    (33, "R_SPARC_OLO10"),
+    (34, "R_SPARC_HH22"),
+    (35, "R_SPARC_HM10"),
+    (36, "R_SPARC_LM22"),
+    (37, "R_SPARC_PC_HH22"),
+    (38, "R_SPARC_PC_HM10"),
+    (39, "R_SPARC_PC_LM22"),
+    (40, "R_SPARC_WDISP16"),
+    (41, "R_SPARC_WDISP19"),
+    (42, "R_SPARC_GLOB_JMP"),
+    (43, "R_SPARC_7"),
+    (44, "R_SPARC_5"),
+    (45, "R_SPARC_6"),
+    (46, "R_SPARC_DISP64"),
+    (47, "R_SPARC_PLT64"),
+    (48, "R_SPARC_HIX22"),
+    (49, "R_SPARC_LOX10"),
+    (50, "R_SPARC_H44"),
+    (51, "R_SPARC_M44"),
+    (52, "R_SPARC_L44"),
+    (53, "R_SPARC_REGISTER"),
+    (54, "R_SPARC_UA64"),
+    (55, "R_SPARC_UA16"),
+    (56, "R_SPARC_TLS_GD_HI22"),
+    (57, "R_SPARC_TLS_GD_LO10"),
+    (58, "R_SPARC_TLS_GD_ADD"),
+    (59, "R_SPARC_TLS_GD_CALL"),
+    (60, "R_SPARC_TLS_LDM_HI22"),
+    (61, "R_SPARC_TLS_LDM_LO10"),
+    (62, "R_SPARC_TLS_LDM_ADD"),
+    (63, "R_SPARC_TLS_LDM_CALL"),
+    (64, "R_SPARC_TLS_LDO_HIX22"),
+    (65, "R_SPARC_TLS_LDO_LOX10"),
+    (66, "R_SPARC_TLS_LDO_ADD"),
+    (67, "R_SPARC_TLS_IE_HI22"),
+    (68, "R_SPARC_TLS_IE_LO10"),
+    (69, "R_SPARC_TLS_IE_LD"),
+    (70, "R_SPARC_TLS_IE_LDX"),
+    (71, "R_SPARC_TLS_IE_ADD"),
+    (72, "R_SPARC_TLS_LE_HIX22"),
+    (73, "R_SPARC_TLS_LE_LOX10"),
+    (74, "R_SPARC_TLS_DTPMOD32"),
+    (75, "R_SPARC_TLS_DTPMOD64"),
+    (76, "R_SPARC_TLS_DTPOFF32"),
+    (77, "R_SPARC_TLS_DTPOFF64"),
+    (78, "R_SPARC_TLS_TPOFF32"),
+    (79, "R_SPARC_TLS_TPOFF64"),
+    (80, "R_SPARC_GOTDATA_HIX22"),
+    (81, "R_SPARC_GOTDATA_LOX10"),
+    (82, "R_SPARC_GOTDATA_OP_HIX22"),
+    (83, "R_SPARC_GOTDATA_OP_LOX10"),
+    (84, "R_SPARC_GOTDATA_OP"),
+    (85, "R_SPARC_H34"),
+    (86, "R_SPARC_SIZE32"),
+    (87, "R_SPARC_SIZE64"),
+    (88, "R_SPARC_WDISP10"),
+    (248, "R_SPARC_JMP_IREL"),
+    (249, "R_SPARC_IRELATIVE"),
+    (250, "R_SPARC_GNU_VTINHERIT"),
+    (251, "R_SPARC_GNU_VTENTRY"),
+    (252, "R_SPARC_REV32"),
+];
+
+const XTENSA_TYPES: &[(u32, &str)] = &[
+    (0, "R_XTENSA_NONE"),
+    (1, "R_XTENSA_32"),
+    (2, "R_XTENSA_RTLD"),
+    (3, "R_XTENSA_GLOB_DAT"),
+    (4, "R_XTENSA_JMP_SLOT"),
+    (5, "R_XTENSA_RELATIVE"),
+    (6, "R_XTENSA_PLT"),
+    (8, "R_XTENSA_OP0"),
+    (9, "R_XTENSA_OP1"),
+    (10, "R_XTENSA_OP2"),
+    (11, "R_XTENSA_ASM_EXPAND"),
+    (12, "R_XTENSA_ASM_SIMPLIFY"),
+    (14, "R_XTENSA_32_PCREL"),
+    (15, "R_XTENSA_GNU_VTINHERIT"),
+    (16, "R_XTENSA_GNU_VTENTRY"),
+    (17, "R_XTENSA_DIFF8"),
+    (18, "R_XTENSA_DIFF16"),
+    (19, "R_XTENSA_DIFF32"),
+    (20, "R_XTENSA_SLOT0_OP"),
+    (21, "R_XTENSA_SLOT1_OP"),
+    (22, "R_XTENSA_SLOT2_OP"),
+    (23, "R_XTENSA_SLOT3_OP"),
+    (24, "R_XTENSA_SLOT4_OP"),
+    (25, "R_XTENSA_SLOT5_OP"),
+    (26, "R_XTENSA_SLOT6_OP"),
+    (27, "R_XTENSA_SLOT7_OP"),
+    (28, "R_XTENSA_SLOT8_OP"),
+    (29, "R_XTENSA_SLOT9_OP"),
+    (30, "R_XTENSA_SLOT10_OP"),
+    (31, "R_XTENSA_SLOT11_OP"),
+    (32, "R_XTENSA_SLOT12_OP"),
+    (33, "R_XTENSA_SLOT13_OP"),
+    (34, "R_XTENSA_SLOT14_OP"),
+    (35, "R_XTENSA_SLOT0_ALT"),
+    (36, "R_XTENSA_SLOT1_ALT"),
+    (37, "R_XTENSA_SLOT2_ALT"),
+    (38, "R_XTENSA_SLOT3_ALT"),
+    (39, "R_XTENSA_SLOT4_ALT"),
+    (40, "R_XTENSA_SLOT5_ALT"),
+    (41, "R_XTENSA_SLOT6_ALT"),
+    (42, "R_XTENSA_SLOT7_ALT"),
+    (43, "R_XTENSA_SLOT8_ALT"),
+    (44, "R_XTENSA_SLOT9_ALT"),
+    (45, "R_XTENSA_SLOT10_ALT"),
+    (46, "R_XTENSA_SLOT11_ALT"),
+    (47, "R_XTENSA_SLOT12_ALT"),
+    (48, "R_XTENSA_SLOT13_ALT"),
+    (49, "R_XTENSA_SLOT14_ALT"),
+    (50, "R_XTENSA_TLSDESC_FN"),
+    (51, "R_XTENSA_TLSDESC_ARG"),
+    (52, "R_XTENSA_TLS_DTPOFF"),
+    (53, "R_XTENSA_TLS_TPOFF"),
+    (54, "R_XTENSA_TLS_FUNC"),
+    (55, "R_XTENSA_TLS_ARG"),
+    (56, "R_XTENSA_TLS_CALL"),
+    (57, "R_XTENSA_PDIFF8"),
+    (58, "R_XTENSA_PDIFF16"),
+    (59, "R_XTENSA_PDIFF32"),
+    (60, "R_XTENSA_NDIFF8"),
+    (61, "R_XTENSA_NDIFF16"),
+    (62, "R_XTENSA_NDIFF32"),
+];
+
+const AVR_TYPES: &[&str] = &[
+    "R_AVR_NONE",
+    "R_AVR_32",
+    "R_AVR_7_PCREL",
+    "R_AVR_13_PCREL",
+    "R_AVR_16",
+    "R_AVR_16_PM",
+    "R_AVR_LO8_LDI",
+    "R_AVR_HI8_LDI",
+    "R_AVR_HH8_LDI",
+    "R_AVR_LO8_LDI_NEG",
+    "R_AVR_HI8_LDI_NEG",
+    "R_AVR_HH8_LDI_NEG",
+    "R_AVR_LO8_LDI_PM",
+    "R_AVR_HI8_LDI_PM",
+    "R_AVR_HH8_LDI_PM",
+    "R_AVR_LO8_LDI_PM_NEG",
+    "R_AVR_HI8_LDI_PM_NEG",
+    "R_AVR_HH8_LDI_PM_NEG",
+    "R_AVR_CALL",
+    "R_AVR_LDI",
+    "R_AVR_6",
+    "R_AVR_6_ADIW",
+    "R_AVR_MS8_LDI",
+    "R_AVR_MS8_LDI_NEG",
+    "R_AVR_LO8_LDI_GS",
+    "R_AVR_HI8_LDI_GS",
+    "R_AVR_8",
+    "R_AVR_8_LO8",
+    "R_AVR_8_HI8",
+    "R_AVR_8_HLO8",
+    "R_AVR_DIFF8",
+    "R_AVR_DIFF16",
+    "R_AVR_DIFF32",
+    "R_AVR_LDS_STS_16",
+    "R_AVR_PORT6",
+    "R_AVR_PORT5",
+    "R_AVR_32_PCREL",
+];
+
+const MSP430_TYPES: &[&str] = &[
+    "R_MSP430_NONE",
+    "R_MSP430_32",
+    "R_MSP430_10_PCREL",
+    "R_MSP430_16",
+    "R_MSP430_16_PCREL",
+    "R_MSP430_16_BYTE",
+    "R_MSP430_16_PCREL_BYTE",
+    "R_MSP430_2X_PCREL",
+    "R_MSP430_RL_PCREL",
+    "R_MSP430_8",
+    "R_MSP430_SYM_DIFF",
+    "R_MSP430_GNU_SET_ULEB128",
+    "R_MSP430_GNU_SUB_ULEB128",
+];
+
+/// The numbering of the MSP430X ABI, which TI's toolchain also uses.
+const MSP430X_TYPES: &[&str] = &[
+    "R_MSP430_NONE",
+    "R_MSP430_ABS32",
+    "R_MSP430_ABS16",
+    "R_MSP430_ABS8",
+    "R_MSP430_PCR16",
+    "R_MSP430X_PCR20_EXT_SRC",
+    "R_MSP430X_PCR20_EXT_DST",
+    "R_MSP430X_PCR20_EXT_ODST",
+    "R_MSP430X_ABS20_EXT_SRC",
+    "R_MSP430X_ABS20_EXT_DST",
+    "R_MSP430X_ABS20_EXT_ODST",
+    "R_MSP430X_ABS20_ADR_SRC",
+    "R_MSP430X_ABS20_ADR_DST",
+    "R_MSP430X_PCR16",
+    "R_MSP430X_PCR20_CALL",
+    "R_MSP430X_ABS16",
+    "R_MSP430_ABS_HI16",
+    "R_MSP430_PREL31",
+    "R_MSP430_EHTYPE",
+    "R_MSP430X_10_PCREL",
+    "R_MSP430X_2X_PCREL",
+    "R_MSP430X_SYM_DIFF",
+    "R_MSP430_GNU_SET_ULEB128",
+    "R_MSP430_GNU_SUB_ULEB128",
+];
+
+const BPF_TYPES: &[(u32, &str)] = &[
+    (0, "R_BPF_NONE"),
+    (1, "R_BPF_64_64"),
+    (2, "R_BPF_64_ABS64"),
+    (3, "R_BPF_64_ABS32"),
+    (4, "R_BPF_64_NODYLD32"),
+    (10, "R_BPF_64_32"),
+    (256, "R_BPF_GNU_64_16"),
+];
+
+/// The MSP430 machine number in `e_flags` of the MSP430X, the 20-bit
+/// extension of the architecture.
+const E_MSP430_MACH_MSP430X: u32 = 45;
+
+/// Symbolic name of a relocation type, for the machines we know about.
+fn machine_type_name(machine: EMachine, r_type: u32) -> Option<&'static str> {
+    let lookup = |table: &[(u32, &'static str)]| {
+        table
+            .iter()
+            .find(|&&(value, _)| value == r_type)
+            .map(|&(_, name)| name)
+    };
+
+    match machine {
+        EMachine::X8664 => X86_64_TYPES.get(r_type as usize).copied(),
+        EMachine::I386 => I386_TYPES
+            .get(r_type as usize)
+            .copied()
+            .filter(|name| !name.is_empty()),
+        EMachine::Aarch64 => lookup(AARCH64_TYPES),
+        EMachine::Arm => lookup(ARM_TYPES),
+        EMachine::Riscv => lookup(RISCV_TYPES),
+        EMachine::S390 => S390_TYPES.get(r_type as usize).copied(),
+        EMachine::Sparc | EMachine::Sparc32Plus => lookup(SPARC_TYPES),
+        // The upper bits hold the extra addend of R_SPARC_OLO10.
+        EMachine::SparcV9 => machine_type_name(EMachine::Sparc, r_type & 0xff),
+        EMachine::Xtensa => lookup(XTENSA_TYPES),
+        EMachine::Avr => AVR_TYPES.get(r_type as usize).copied(),
+        EMachine::Msp430 => MSP430_TYPES.get(r_type as usize).copied(),
+        EMachine::Bpf => lookup(BPF_TYPES),
+        _ => None,
+    }
+}
+
+/// Symbolic name of a relocation type in `elf_file`. This mostly depends
+/// on the machine, but MSP430X objects number their relocations
+/// differently, as do those of TI's compiler, which unlike GCC leaves the
+/// OS/ABI unset.
+pub fn type_name(elf_file: &ElfFile, r_type: u32) -> Option<&'static str> {
+    let machine = elf_file.machine();
+    if machine == EMachine::Msp430
+        && (elf_file.header().e_flags & 0xff == E_MSP430_MACH_MSP430X
+            || elf_file.ident().os_abi == 0)
+    {
+        return MSP430X_TYPES.get(r_type as usize).copied();
+    }
+    machine_type_name(machine, r_type)
+}
+
+/// Name of a relocation type, falling back to its number.
+pub fn type_display(elf_file: &ElfFile, r_type: u32) -> String {
+    type_name(elf_file, r_type).map_or_else(|| format!("<type {}>", r_type), String::from)
+}
+
+/// Whether the relocation only adds the load base to its addend, the kind
+/// that RELR can encode.
+pub fn is_relative(elf_file: &ElfFile, r_type: u32) -> bool {
+    type_name(elf_file, r_type).is_some_and(|name| name.ends_with("_RELATIVE"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words64(words: &[u64]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_ne_bytes()).collect()
+    }
+
+    fn summary(relocations: &[Relocation]) -> Vec<(u64, u32, u32, Option<i64>)> {
+        relocations
+            .iter()
+            .map(|r| (r.r_offset, r.r_sym, r.r_type, r.r_addend))
+            .collect()
+    }
+
+    #[test]
+    fn relr_addresses_and_bitmaps() {
+        // An address, a bitmap of the next three words, then a bitmap whose
+        // top bit covers the last word of the following 63.
+        let data = words64(&[0x10000, 0xf, 1 << 63 | 1]);
+        assert_eq!(
+            decode_relr(&data, true),
+            [0x10000, 0x10008, 0x10010, 0x10018, 0x103f0]
+        );
+
+        let data: Vec<u8> = [0x1000u32, 0x3]
+            .iter()
+            .flat_map(|w| w.to_ne_bytes())
+            .collect();
+        assert_eq!(decode_relr(&data, false), [0x1000, 0x1004]);
+    }
+
+    #[test]
+    fn relr_wraps_at_the_end_of_the_address_space() {
+        let data = words64(&[0xffff_ffff_ffff_fff8, 0x3]);
+        assert_eq!(decode_relr(&data, true), [0xffff_ffff_ffff_fff8, 0]);
+    }
+
+    #[test]
+    fn crel_deltas() {
+        // Three entries with addends: the first sets the symbol, type and
+        // addend, the second only the addend, and the third moves the offset
+        // by more than the first byte can hold.
+        let data = [0x1c, 0x47, 0x01, 0x01, 0x7c, 0x44, 0x0c, 0x80, 0x02];
+        assert_eq!(
+            summary(&decode_crel(&data).unwrap()),
+            [
+                (0x08, 1, 1, Some(-4)),
+                (0x10, 1, 1, Some(8)),
+                (0x30, 1, 1, Some(8)),
+            ]
+        );
+    }
+
+    #[test]
+    fn crel_without_addends_and_shifted() {
+        // Two entries, offsets shifted by 3: deltas of 1 and 2 words.
+        let data = [0x13, 0x06, 0x08, 0x08];
+        assert_eq!(
+            summary(&decode_crel(&data).unwrap()),
+            [(0x08, 0, 8, None), (0x18, 0, 8, None)]
+        );
+    }
+
+    #[test]
+    fn aps2_groups() {
+        // Two REL relocations sharing their info and offset delta.
+        let data = b"APS2\x02\x80\x20\x02\x03\x08\x08";
+        assert_eq!(
+            summary(&decode_aps2(data, true, false).unwrap()),
+            [(0x1008, 0, 8, None), (0x1010, 0, 8, None)]
+        );
+
+        // Two RELA relocations sharing their info and addend.
+        let data = b"APS2\x02\x00\x02\x0d\x08\x10\x08\x08";
+        assert_eq!(
+            summary(&decode_aps2(data, true, true).unwrap()),
+            [(0x08, 0, 8, Some(16)), (0x10, 0, 8, Some(16))]
+        );
+    }
+
+    #[test]
+    fn aps2_addends_wrap() {
+        // A group with i64::MAX as the addend, then one adding 1 to it.
+        let mut data = b"APS2\x02\x00\x01\x0d\x08".to_vec();
+        data.extend([0xff; 8]);
+        data.extend([0xff, 0x00, 0x00, 0x01, 0x0d, 0x08, 0x01, 0x00]);
+        assert_eq!(
+            summary(&decode_aps2(&data, true, true).unwrap()),
+            [(0, 0, 8, Some(i64::MAX)), (0, 0, 8, Some(i64::MIN))]
+        );
+    }
+
+    #[test]
+    fn aps2_rejects_oversized_groups() {
+        let mut data = b"APS2\x05\x00".to_vec();
+        data.extend([0xff; 8]);
+        data.extend([0xff, 0x00, 0x03, 0x08, 0x08]);
+        assert!(decode_aps2(&data, true, false).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::Result;
+
+use crate::demangle::NameStyle;
+use crate::elf::{ElfFile, ElfType};
+use crate::filter::SectionFilter;
+use crate::relocation::{self, Relocation};
+use crate::section::{SHN_LORESERVE, SHN_UNDEF, SectionHeader, SectionType};
+use crate::strtab::SymbolTable;
+
+/// Resolves addresses to "symbol+offset" using the defined symbols of the
+/// most complete symbol table.
+struct AddressNames {
+    /// (address, size, name), sorted by address.
+    symbols: Vec<(u64, u64, String)>,
+}
+
+impl AddressNames {
+    fn new(elf_file: &ElfFile, sections: &[SectionHeader]) -> Result<Self> {
+        let mut symbols = Vec::new();
+        if let Some(symtab) = elf_file.symbol_table(sections) {
+            let table = SymbolTable::new(elf_file, sections, symtab)?;
+            for (index, symbol) in table.iter().enumerate() {
+                if symbol.st_shndx == SHN_UNDEF || symbol.st_value == 0 {
+                    continue;
+                }
+                let name = table.name(index)?;
+                if !name.is_empty() {
+                    symbols.push((symbol.st_value, symbol.st_size, name.to_string()));
+                }
+            }
+        }
+        symbols.sort();
+        Ok(Self { symbols })
+    }
+
+    fn name(&self, address: u64) -> String {
+        let index = self.symbols.partition_point(|&(a, _, _)| a <= address);
+        match index.checked_sub(1).map(|i| &self.symbols[i]) {
+            Some((start, size, name)) if address == *start || address - start < *size => {
+                if address == *start {
+                    name.clone()
+                } else {
+                    format!("{} + {:x}", name, address - start)
+                }
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+/// `base` with a signed offset, as in `.data-0x4`.
+fn plus(base: &str, offset: i128) -> String {
+    if offset < 0 {
+        format!("{}-{:#x}", base, offset.unsigned_abs())
+    } else {
+        format!("{}+{:#x}", base, offset)
+    }
+}
+
+/// With `targets`, each relocation of an object is followed by the place it
+/// patches and the place it points to, each as section+offset, or
+/// symbol+offset when the symbol is not defined in the object.
+fn print_table(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    section: &SectionHeader,
+    relocations: &[Relocation],
+    targets: bool,
+    style: NameStyle,
+) -> Result<()> {
+    let symbols = SymbolTable::linked(elf_file, sections, section)?;
+    let place_name = sections
+        .get(section.sh_info as usize)
+        .filter(|_| section.sh_info != 0)
+        .and_then(|s| elf_file.section_name(sections, s).ok());
+
+    println!(
+        "  {:<12}  {:<12} {:<22} {:<16}  Sym. Name + Addend",
+        "Offset", "Info", "Type", "Sym. Value"
+    );
+    for reloc in relocations {
+        let info = if elf_file.is_64() {
+            ((reloc.r_sym as u64) << 32) | reloc.r_type as u64
+        } else {
+            ((reloc.r_sym as u64) << 8) | reloc.r_type as u64
+        };
+        let mut line = format!(
+            "{:012x}  {:012x} {:<22}",
+            reloc.r_offset,
+            info,
+            relocation::type_display(elf_file, reloc.r_type)
+        );
+
+        let symbol = symbols
+            .get(reloc.r_sym as usize)
+            .filter(|_| reloc.r_sym != 0);
+        let mut target = None;
+        match symbol {
+            Some(symbol) => {
+                let defined_in = sections
+                    .get(symbol.st_shndx as usize)
+                    .filter(|_| symbol.st_shndx != SHN_UNDEF && symbol.st_shndx < SHN_LORESERVE)
+                    .and_then(|s| elf_file.section_name(sections, s).ok());
+                let name = match symbols.name(reloc.r_sym as usize)? {
+                    // Section symbols have no name of their own.
+                    "" => defined_in.unwrap_or("").to_string(),
+                    name => style.display(name),
+                };
+                line += &format!(" {:016x}  {}", symbol.st_value, name);
+                let addend = reloc.r_addend.unwrap_or(0) as i128;
+                target = Some(match defined_in {
+                    Some(section) => plus(section, symbol.st_value as i128 + addend),
+                    None => plus(&name, addend),
+                });
+            }
+            None if reloc.r_sym != 0 => line += &format!(" {:>16}  <symbol {}>", "", reloc.r_sym),
+            None => line += &format!(" {:>16} ", ""),
+        }
+
+        match reloc.r_addend {
+            Some(addend) if addend < 0 => line += &format!(" - {:x}", addend.unsigned_abs()),
+            Some(addend) => line += &format!(" + {:x}", addend),
+            None => {}
+        }
+        if targets {
+            let place = plus(place_name.unwrap_or("?"), reloc.r_offset as i128);
+            let target = target.unwrap_or_else(|| "none".to_string());
+            line = format!("{}  ({} -> {})", line.trim_end(), place, target);
+        }
+        println!("{}", line.trim_end());
+    }
+
+    Ok(())
+}
+
+fn print_relr(addresses: &[u64], names: &AddressNames) {
+    println!("  {:<16}  Symbolic address", "Offset");
+    for &address in addresses {
+        println!("{:016x}  {}", address, names.name(address))
+    }
+}
+
+/// Prints the contents of every relocation section, in the order of the
+/// section headers. Compact CREL, packed RELR and Android APS2 tables are
+/// expanded into the relocations they stand for. With a filter, only the
+/// relocation sections it selects, or that apply to a section it selects,
+/// are shown. With `extra_info`, the relocations of objects also say what
+/// they patch and where they point, by section and offset. Symbol names
+/// are printed in `style`.
+pub fn print_relocations(
+    elf_file: &ElfFile,
+    filter: &SectionFilter,
+    extra_info: bool,
+    style: NameStyle,
+) -> Result<()> {
+    let sections = elf_file.section_headers_or_dynamic()?;
+    let targets = extra_info && elf_file.elf_type() == ElfType::REL;
+    let names = AddressNames::new(elf_file, &sections)?;
+    let mut found = false;
+
+    for (index, section) in sections.iter().enumerate() {
+        if !filter.selects(elf_file, &sections, index)
+            && (section.sh_info == 0
+                || !filter.selects(elf_file, &sections, section.sh_info as usize))
+        {
+            continue;
+        }
+
+        let name = elf_file.section_name(&sections, section).unwrap_or("");
+        match section.sh_type {
+            SectionType::REL | SectionType::RELA | SectionType::CREL => {
+                let relocations = elf_file.decoded_relocations(section)?;
+                println!();
+                println!(
+                    "Relocation section '{}' at offset {:#x} contains {} entries:",
+                    name,
+                    section.sh_offset,
+                    relocations.len()
+                );
+                print_table(elf_file, &sections, section, &relocations, targets, style)?;
+            }
+            SectionType::ANDROID_REL | SectionType::ANDROID_RELA => {
+                let relocations = elf_file.decoded_relocations(section)?;
+                println!();
+                println!(
+                    "Relocation section '{}' at offset {:#x} contains {} bytes packed into {} relocations:",
+                    name,
+                    section.sh_offset,
+                    section.sh_size,
+                    relocations.len()
+                );
+                print_table(elf_file, &sections, section, &relocations, targets, style)?;
+            }
+            SectionType::RELR | SectionType::ANDROID_RELR => {
+                let word = if elf_file.is_64() { 8 } else { 4 };
+                let addresses = elf_file.relr_addresses(section)?;
+                println!();
+                println!(
+                    "Relocation section '{}' at offset {:#x} contains {} entries which relocate {} locations:",
+                    name,
+                    section.sh_offset,
+                    section.sh_size / word,
+                    addresses.len()
+                );
+                print_relr(&addresses, &names);
+            }
+            _ => continue,
+        }
+        found = true;
+    }
+
+    if !found {
+        println!();
+        println!("There are no relocations in this file.");
+    }
+
+    Ok(())
+}
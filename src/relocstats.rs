@@ -0,0 +1,190 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::elf::ElfFile;
+use crate::relocation::{self, Relocation};
+use crate::section::SectionType;
+use crate::strtab::SymbolTable;
+
+/// Number of entries `-z pack-relative-relocs` would need to encode the
+/// given relative relocation offsets, using the RELR address and bitmap
+/// scheme. Returns the entry count and how many offsets it covers, since
+/// unaligned ones have to stay in the REL/RELA table.
+fn relr_entries(offsets: &mut [u64], word: u64) -> (usize, usize) {
+    offsets.sort_unstable();
+    let aligned: Vec<u64> = offsets.iter().copied().filter(|o| o % word == 0).collect();
+    let bits = word * 8 - 1;
+
+    let mut entries = 0;
+    let mut i = 0;
+    while i < aligned.len() {
+        entries += 1;
+        let mut base = aligned[i] + word;
+        i += 1;
+
+        loop {
+            let mut bitmap = false;
+            while i < aligned.len() && aligned[i] >= base && (aligned[i] - base) / word < bits {
+                bitmap = true;
+                i += 1;
+            }
+            if !bitmap {
+                break;
+            }
+            entries += 1;
+            base += bits * word;
+        }
+    }
+
+    (entries, aligned.len())
+}
+
+/// Summarises the relocations of the file by type and by symbol, and
+/// estimates what packing the relative ones with RELR would save.
+pub fn print_reloc_stats(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+
+    let mut by_type: HashMap<u32, usize> = HashMap::new();
+    let mut by_symbol: HashMap<String, usize> = HashMap::new();
+    let (mut total, mut relative, mut irelative, mut symbolic) = (0, 0, 0, 0);
+    let mut dynamic_bytes = 0;
+    let mut entry_size = 0;
+    let mut relative_offsets = Vec::new();
+    let mut relr = 0;
+
+    for section in &sections {
+        let relocations: Vec<Relocation> = match section.sh_type {
+            _ if ElfFile::is_relocation_section(section) => {
+                elf_file.decoded_relocations(section)?
+            }
+            SectionType::RELR | SectionType::ANDROID_RELR => {
+                let count = elf_file.relr_addresses(section)?.len();
+                relr += count;
+                relative += count;
+                total += count;
+                dynamic_bytes += section.sh_size;
+                continue;
+            }
+            _ => continue,
+        };
+
+        let symbols = SymbolTable::linked(elf_file, &sections, section)?;
+
+        if section.is_alloc() && !relocations.is_empty() {
+            dynamic_bytes += section.sh_size;
+            // What each relocation costs unpacked, which is also what
+            // Android's packed tables expand to at load time.
+            let is_rela = matches!(
+                section.sh_type,
+                SectionType::RELA | SectionType::ANDROID_RELA
+            );
+            entry_size = match (elf_file.is_64(), is_rela) {
+                (true, true) => 24,
+                (true, false) => 16,
+                (false, true) => 12,
+                (false, false) => 8,
+            };
+        }
+
+        for reloc in relocations {
+            total += 1;
+            *by_type.entry(reloc.r_type).or_default() += 1;
+
+            let name = relocation::type_name(elf_file, reloc.r_type).unwrap_or("");
+            if relocation::is_relative(elf_file, reloc.r_type) {
+                relative += 1;
+                if section.is_alloc() {
+                    relative_offsets.push(reloc.r_offset);
+                }
+            } else if name.ends_with("_IRELATIVE") {
+                irelative += 1;
+            } else if reloc.r_sym != 0 {
+                symbolic += 1;
+            }
+
+            if reloc.r_sym != 0 {
+                let symbol = symbols
+                    .name(reloc.r_sym as usize)
+                    .ok()
+                    .filter(|name| !name.is_empty())
+                    .map_or_else(|| format!("<symbol {}>", reloc.r_sym), String::from);
+                *by_symbol.entry(symbol).or_default() += 1;
+            }
+        }
+    }
+
+    if total == 0 {
+        println!("There are no relocations in this file.");
+        return Ok(());
+    }
+
+    let percent = |n: usize| n as f64 * 100.0 / total as f64;
+
+    println!("Relocation statistics:");
+    println!("  Total:      {:>8}", total);
+    println!("  Relative:   {:>8} ({:.1}%)", relative, percent(relative));
+    println!(
+        "  IRELATIVE:  {:>8} ({:.1}%)",
+        irelative,
+        percent(irelative)
+    );
+    println!("  Symbolic:   {:>8} ({:.1}%)", symbolic, percent(symbolic));
+    let other = total - relative - irelative - symbolic;
+    if other > 0 {
+        println!("  Other:      {:>8} ({:.1}%)", other, percent(other));
+    }
+
+    let mut types: Vec<_> = by_type.into_iter().collect();
+    types.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    println!();
+    println!("By type:");
+    for (r_type, count) in types {
+        println!(
+            "  {:<28} {:>8}",
+            relocation::type_display(elf_file, r_type),
+            count
+        );
+    }
+
+    if !by_symbol.is_empty() {
+        let mut symbols: Vec<_> = by_symbol.into_iter().collect();
+        symbols.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        println!();
+        println!("Most referenced symbols:");
+        for (name, count) in symbols.iter().take(20) {
+            println!("  {:>8}  {}", count, name);
+        }
+        if symbols.len() > 20 {
+            println!("  ... and {} more symbols", symbols.len() - 20);
+        }
+    }
+
+    if relr > 0 {
+        println!();
+        println!("Startup relocation cost:");
+        println!("  Dynamic relocation tables: {} bytes", dynamic_bytes);
+        println!(
+            "  The file already packs {} relative relocations with RELR.",
+            relr
+        );
+    } else if !relative_offsets.is_empty() {
+        let word = if elf_file.is_64() { 8 } else { 4 };
+        let (entries, covered) = relr_entries(&mut relative_offsets, word);
+        let current = covered as u64 * entry_size;
+        let packed = entries as u64 * word;
+
+        println!();
+        println!("Startup relocation cost:");
+        println!("  Dynamic relocation tables: {} bytes", dynamic_bytes);
+        println!(
+            "  With -z pack-relative-relocs, {} relative relocations taking {} bytes would fit in {} RELR entries ({} bytes), saving {} bytes",
+            covered,
+            current,
+            entries,
+            packed,
+            current.saturating_sub(packed)
+        );
+    }
+
+    Ok(())
+}
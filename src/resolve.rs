@@ -0,0 +1,391 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::deps::DependencyGraph;
+use crate::elf::{ElfFile, ElfType};
+use crate::section::{SHN_UNDEF, SectionType};
+use crate::strtab::SymbolTable;
+use crate::symbol::{SymbolBinding, SymbolType, SymbolVisibility};
+use crate::version::{VERSYM_HIDDEN, VERSYM_LOCAL, Version, VersionTables};
+
+/// A symbol an object exports through its dynamic symbol table.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub version: Option<String>,
+    /// Non-default versions (`sym@VER` rather than `sym@@VER`) only satisfy
+    /// references asking for that version.
+    pub hidden: bool,
+    pub weak: bool,
+    /// Protected symbols are exported, but the object itself always binds
+    /// to its own copy.
+    pub protected: bool,
+}
+
+/// An undefined symbol an object needs someone else to provide.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub name: String,
+    pub version: Option<Version>,
+    pub weak: bool,
+}
+
+impl Reference {
+    /// The name in `sym@VERSION` notation.
+    pub fn display_name(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{}@{}", self.name, version.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// The dynamic symbols of one loaded object.
+pub struct Object {
+    pub name: String,
+    definitions: HashMap<String, Vec<Definition>>,
+    pub references: Vec<Reference>,
+    /// Whether the object has version tables. Unversioned objects satisfy
+    /// versioned references to any of their symbols.
+    versioned: bool,
+    /// False for relocatable objects, whose references are left for the
+    /// link rather than for the loader.
+    linked: bool,
+}
+
+impl Object {
+    pub fn load(name: &str, path: &Path) -> Result<Self> {
+        let elf_file = ElfFile::open(path)?;
+        let sections = elf_file.section_headers_or_dynamic()?;
+        let versions = VersionTables::new(&elf_file, &sections)?;
+
+        let mut object = Self {
+            name: name.to_string(),
+            definitions: HashMap::new(),
+            references: Vec::new(),
+            versioned: !versions.versym.is_empty(),
+            linked: elf_file.elf_type() != ElfType::REL,
+        };
+
+        // Relocatable objects are not linked yet, so only their full
+        // symbol table says what they import and export.
+        let wanted = if !object.linked {
+            SectionType::SYMTAB
+        } else {
+            SectionType::DYNSYM
+        };
+        let Some(dynsym) = sections.iter().find(|s| s.sh_type == wanted) else {
+            return Ok(object);
+        };
+
+        let symbols = SymbolTable::new(&elf_file, &sections, dynsym)?;
+        for (index, symbol) in symbols.iter().enumerate().skip(1) {
+            let binding = symbol.binding();
+            if binding == SymbolBinding::LOCAL
+                || matches!(symbol.symbol_type(), SymbolType::SECTION | SymbolType::FILE)
+            {
+                continue;
+            }
+            let name = symbols.name(index)?;
+            if name.is_empty() {
+                continue;
+            }
+
+            if symbol.st_shndx == SHN_UNDEF {
+                object.references.push(Reference {
+                    name: name.to_string(),
+                    version: versions.version(index).cloned(),
+                    weak: binding == SymbolBinding::WEAK,
+                });
+                continue;
+            }
+
+            let visibility = symbol.visibility();
+            if visibility != SymbolVisibility::DEFAULT && visibility != SymbolVisibility::PROTECTED
+            {
+                continue;
+            }
+            let versym = versions.versym(index);
+            if versym == Some(VERSYM_LOCAL) {
+                continue;
+            }
+            object
+                .definitions
+                .entry(name.to_string())
+                .or_default()
+                .push(Definition {
+                    version: versions.version(index).map(|v| v.name.clone()),
+                    hidden: versym.is_some_and(|v| v & VERSYM_HIDDEN != 0),
+                    weak: binding == SymbolBinding::WEAK,
+                    protected: visibility == SymbolVisibility::PROTECTED,
+                });
+        }
+
+        object.references.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(object)
+    }
+
+    /// The definition that would satisfy `reference`, following the
+    /// loader's version matching rules.
+    pub fn defines(&self, reference: &Reference) -> Option<&Definition> {
+        let definitions = self.definitions.get(&reference.name)?;
+        definitions.iter().find(|def| match &reference.version {
+            Some(wanted) => {
+                !self.versioned
+                    || def.version.as_deref() == Some(wanted.name.as_str())
+                    || (def.version.is_none() && !def.hidden)
+            }
+            None => !def.hidden,
+        })
+    }
+}
+
+/// Where a reference ends up once the loader is done with it.
+pub enum Binding<'a> {
+    Resolved {
+        object: usize,
+        definition: &'a Definition,
+    },
+    /// An undefined weak reference nobody defines, which is left as zero.
+    WeakUndefined,
+    Unresolved,
+}
+
+/// The objects of a process in global lookup scope order, as the dynamic
+/// loader would see them at startup.
+pub struct Resolver {
+    pub objects: Vec<Object>,
+    missing: Vec<String>,
+}
+
+impl Resolver {
+    pub fn new(graph: &DependencyGraph) -> Result<Self> {
+        let objects = graph
+            .load_order()
+            .into_iter()
+            .map(|(name, path)| {
+                Object::load(name, path)
+                    .with_context(|| format!("Failed to read {}", path.display()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            objects,
+            missing: graph.missing().into_iter().map(String::from).collect(),
+        })
+    }
+
+    /// Adds an object to the scope the way LD_PRELOAD does, right after the
+    /// executable and ahead of all of its dependencies.
+    pub fn preload(&mut self, object: Object) {
+        let position = self.objects.len().min(1);
+        self.objects.insert(position, object);
+    }
+
+    /// Resolves a reference by searching the scope in order. The first match
+    /// wins, weak definitions included, as glibc does unless LD_DYNAMIC_WEAK
+    /// is set.
+    pub fn resolve(&self, reference: &Reference) -> Binding<'_> {
+        let found = self
+            .objects
+            .iter()
+            .enumerate()
+            .find_map(|(i, object)| object.defines(reference).map(|def| (i, def)));
+
+        match found {
+            Some((object, definition)) => Binding::Resolved { object, definition },
+            None if reference.weak => Binding::WeakUndefined,
+            None => Binding::Unresolved,
+        }
+    }
+
+    /// Reports, for every object, which library satisfies each undefined
+    /// symbol, and flags the ones nothing provides.
+    pub fn print_resolution(&self) {
+        let scope: Vec<&str> = self.objects.iter().map(|o| o.name.as_str()).collect();
+        println!("Symbol resolution (lookup scope: {}):", scope.join(", "));
+
+        let (mut resolved, mut weak, mut unresolved) = (0, 0, 0);
+        for object in &self.objects {
+            if object.references.is_empty() {
+                continue;
+            }
+
+            println!();
+            println!("{}:", object.name);
+            for reference in &object.references {
+                let mut name = reference.display_name();
+                if reference.weak {
+                    name += " [weak]";
+                }
+
+                let target = match self.resolve(reference) {
+                    Binding::Resolved { object, definition } => {
+                        resolved += 1;
+                        let mut target = self.objects[object].name.clone();
+                        if definition.weak {
+                            target += " (weak definition)";
+                        }
+                        if definition.protected {
+                            target += " (protected)";
+                        }
+                        target
+                    }
+                    Binding::WeakUndefined => {
+                        weak += 1;
+                        "undefined weak, left as 0".to_string()
+                    }
+                    Binding::Unresolved => {
+                        unresolved += 1;
+                        match &reference.version {
+                            Some(Version {
+                                file: Some(file), ..
+                            }) => format!("!! UNRESOLVED (expected in {})", file),
+                            _ => "!! UNRESOLVED".to_string(),
+                        }
+                    }
+                };
+                println!("  {:<48} -> {}", name, target);
+            }
+        }
+
+        println!();
+        println!(
+            "{} references: {} resolved, {} undefined weak, {} unresolved",
+            resolved + weak + unresolved,
+            resolved,
+            weak,
+            unresolved
+        );
+        if !self.missing.is_empty() {
+            println!(
+                "Libraries not found, whose symbols could not be considered: {}",
+                self.missing.join(", ")
+            );
+        }
+    }
+
+    /// Lists the undefined symbols of the file itself, grouped by the
+    /// dependency that provides them, then the undefined weak ones and
+    /// those nothing provides.
+    pub fn print_undefined(&self) {
+        let Some(target) = self.objects.first() else {
+            return;
+        };
+        let label = |reference: &Reference| {
+            let mut name = reference.display_name();
+            if reference.weak {
+                name += " [weak]";
+            }
+            name
+        };
+
+        let weak = target.references.iter().filter(|r| r.weak).count();
+        println!(
+            "Undefined symbols of {}: {} ({} weak)",
+            target.name,
+            target.references.len(),
+            weak
+        );
+
+        let mut provided = vec![Vec::new(); self.objects.len()];
+        let (mut weak_undefined, mut unresolved) = (Vec::new(), Vec::new());
+        for reference in &target.references {
+            match self.resolve(reference) {
+                Binding::Resolved { object, .. } => provided[object].push(reference),
+                Binding::WeakUndefined => weak_undefined.push(reference),
+                Binding::Unresolved => unresolved.push(reference),
+            }
+        }
+
+        for (object, references) in provided.iter().enumerate() {
+            if references.is_empty() {
+                continue;
+            }
+            println!();
+            println!("Provided by {}:", self.objects[object].name);
+            for reference in references {
+                println!("  {}", label(reference));
+            }
+        }
+        if !weak_undefined.is_empty() {
+            println!();
+            println!("Not provided, undefined weak (left as 0):");
+            for reference in weak_undefined {
+                println!("  {}", label(reference));
+            }
+        }
+        if !unresolved.is_empty() && !target.linked {
+            println!();
+            println!("Left for the link:");
+            for reference in unresolved {
+                println!("  {}", label(reference));
+            }
+        } else if !unresolved.is_empty() {
+            println!();
+            println!("Not provided by any dependency:");
+            for reference in unresolved {
+                match &reference.version {
+                    Some(Version {
+                        file: Some(file), ..
+                    }) => println!("  {:<48} !! expected in {}", label(reference), file),
+                    _ => println!("  {:<48} !!", label(reference)),
+                }
+            }
+        }
+        if !self.missing.is_empty() {
+            println!();
+            println!(
+                "Libraries not found, whose symbols could not be considered: {}",
+                self.missing.join(", ")
+            );
+        }
+    }
+}
+
+/// Reports which imports of the file would bind to `library` if it were
+/// preloaded, and what they bind to today.
+pub fn print_interposition(graph: &DependencyGraph, library: &Path) -> Result<()> {
+    let resolver = Resolver::new(graph)?;
+    let mut preloaded = Resolver::new(graph)?;
+    let name = library.file_name().map_or_else(
+        || library.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    );
+    preloaded.preload(
+        Object::load(&name, library)
+            .with_context(|| format!("Failed to read {}", library.display()))?,
+    );
+
+    let Some(target) = resolver.objects.first() else {
+        return Ok(());
+    };
+    println!("Interposition by {} on {}:", library.display(), target.name);
+
+    let mut count = 0;
+    for reference in &target.references {
+        let Binding::Resolved { object: 1, .. } = preloaded.resolve(reference) else {
+            continue;
+        };
+        count += 1;
+        let current = match resolver.resolve(reference) {
+            Binding::Resolved { object, .. } => resolver.objects[object].name.clone(),
+            Binding::WeakUndefined => "undefined weak".to_string(),
+            Binding::Unresolved => "unresolved".to_string(),
+        };
+        println!("  {:<48} {} -> {}", reference.display_name(), current, name);
+    }
+
+    if count == 0 {
+        println!("  No imports would be interposed.");
+    } else {
+        println!();
+        println!(
+            "{} of {} imports would be interposed",
+            count,
+            target.references.len()
+        );
+    }
+
+    Ok(())
+}
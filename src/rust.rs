@@ -0,0 +1,247 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use crate::demangle;
+use crate::dynamic::DynamicTag;
+use crate::elf::ElfFile;
+use crate::section::{SHN_LORESERVE, SHN_UNDEF, SectionHeader};
+use crate::strtab::SymbolTable;
+
+/// How a symbol name is mangled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Mangling {
+    RustV0,
+    RustLegacy,
+    Itanium,
+    None,
+}
+
+impl std::fmt::Display for Mangling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Mangling::RustV0 => "Rust v0",
+            Mangling::RustLegacy => "Rust legacy",
+            Mangling::Itanium => "C++ (Itanium)",
+            Mangling::None => "not mangled",
+        })
+    }
+}
+
+/// Legacy Rust symbols are Itanium names whose last component is the
+/// 17-character `h<hash>` rustc appends.
+fn is_legacy_rust(name: &str) -> bool {
+    let Some(body) = name.strip_prefix("_ZN").and_then(|n| n.strip_suffix('E')) else {
+        return false;
+    };
+    body.len() >= 19
+        && body[body.len() - 19..].starts_with("17h")
+        && body[body.len() - 16..]
+            .bytes()
+            .all(|b| b.is_ascii_hexdigit())
+}
+
+fn mangling(name: &str) -> Mangling {
+    // Symbols of LLVM-made clones carry suffixes like ".llvm.1234" after
+    // the mangled name.
+    let name = name.split('.').next().unwrap_or(name);
+    if name.starts_with("_R") {
+        Mangling::RustV0
+    } else if is_legacy_rust(name) {
+        Mangling::RustLegacy
+    } else if name.starts_with("_Z") {
+        Mangling::Itanium
+    } else {
+        Mangling::None
+    }
+}
+
+fn find_section<'s>(
+    elf_file: &ElfFile,
+    sections: &'s [SectionHeader],
+    name: &str,
+) -> Option<&'s SectionHeader> {
+    sections
+        .iter()
+        .find(|s| elf_file.section_name(sections, s).ok() == Some(name))
+}
+
+/// The rustc commit hash the standard library was built from, from the
+/// `/rustc/<hash>/library/...` paths panic locations point at.
+fn rustc_commit(data: &[u8]) -> Option<String> {
+    let needle = b"/rustc/";
+    let mut rest = data;
+    while let Some(index) = rest.windows(needle.len()).position(|w| w == needle) {
+        let hash = &rest[index + needle.len()..];
+        if hash.len() > 40 && hash[40] == b'/' && hash[..40].iter().all(u8::is_ascii_hexdigit) {
+            return Some(String::from_utf8_lossy(&hash[..40]).into_owned());
+        }
+        rest = &rest[index + needle.len()..];
+    }
+    None
+}
+
+/// Prints what can be told about how a Rust binary was built: the compiler
+/// version, how it panics, the mangling scheme and the crates that take up
+/// the most space. `top` is how many crates to list.
+pub fn print_rust(elf_file: &ElfFile, top: usize) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+
+    let comment = find_section(elf_file, &sections, ".comment")
+        .and_then(|s| elf_file.section_data(s).ok())
+        .unwrap_or(&[]);
+    let versions: Vec<String> = comment
+        .split(|&b| b == 0)
+        .map(String::from_utf8_lossy)
+        .filter(|s| s.starts_with("rustc version "))
+        .map(|s| s.trim_start_matches("rustc version ").to_string())
+        .collect();
+    let commit = rustc_commit(elf_file.data());
+
+    let mut schemes: HashMap<Mangling, usize> = HashMap::new();
+    let mut names = HashSet::new();
+    let mut by_crate: HashMap<String, (usize, u64)> = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut rust_total = 0;
+    if let Some(symtab) = elf_file.symbol_table(&sections) {
+        let symbols = SymbolTable::new(elf_file, &sections, symtab)?;
+        for (index, symbol) in symbols.iter().enumerate() {
+            let name = symbols.name(index)?;
+            if name.is_empty() {
+                continue;
+            }
+            let scheme = mangling(name);
+            *schemes.entry(scheme).or_default() += 1;
+            names.insert(name);
+
+            let defined = symbol.st_shndx != SHN_UNDEF && symbol.st_shndx < SHN_LORESERVE;
+            if !matches!(scheme, Mangling::RustV0 | Mangling::RustLegacy)
+                || !defined
+                || symbol.st_size == 0
+                || !seen.insert((symbol.st_shndx, symbol.st_value, symbol.st_size))
+            {
+                continue;
+            }
+            let demangled = demangle::demangle(name);
+            let krate = demangle::namespace(&demangled).unwrap_or("<global>");
+            // v0 names of generic instances look like "<T as Trait>", whose
+            // crate is that of T; strip what is left of the angle brackets.
+            let krate = krate.trim_start_matches(['<', '&', '*', ' ']);
+            let entry = by_crate.entry(krate.to_string()).or_default();
+            entry.0 += 1;
+            entry.1 += symbol.st_size;
+            rust_total += symbol.st_size;
+        }
+    }
+
+    let rust_symbols = schemes.get(&Mangling::RustV0).unwrap_or(&0)
+        + schemes.get(&Mangling::RustLegacy).unwrap_or(&0);
+    let has_section = |name: &str| find_section(elf_file, &sections, name).is_some();
+    if versions.is_empty() && commit.is_none() && rust_symbols == 0 && !has_section(".rustc") {
+        println!(
+            "This does not look like a Rust binary: there is no rustc version in .comment, no Rust-mangled symbol and no standard library path."
+        );
+        return Ok(());
+    }
+
+    println!("Rust binary insights:");
+    match versions.as_slice() {
+        [] => println!("  Compiler:        unknown (no rustc version in .comment)"),
+        [version] => println!("  Compiler:        rustc {}", version),
+        versions => println!("  Compilers:       rustc {}", versions.join(", rustc ")),
+    }
+    if let Some(commit) = commit {
+        println!("  Std built from:  rustc commit {}", commit);
+    }
+
+    let needed = elf_file.dynamic_strings(DynamicTag::NEEDED)?;
+    if let Some(std) = needed.iter().find(|n| n.starts_with("libstd-")) {
+        println!(
+            "  Std linkage:     dynamic ({}, from -C prefer-dynamic)",
+            std
+        );
+    }
+    if has_section(".rustc") {
+        println!("  Crate metadata:  .rustc section present (a Rust dylib or proc-macro)");
+    }
+
+    // panic_unwind and panic_abort each define __rust_start_panic, and
+    // only the one the binary was built with is linked in.
+    let has = |needle: &str| names.iter().any(|n| n.contains(needle));
+    let (strategy, evidence) = if has("panic_abort") {
+        ("abort", "the panic_abort runtime is linked in")
+    } else if has("panic_unwind") {
+        ("unwind", "the panic_unwind runtime is linked in")
+    } else if has("rust_eh_personality") {
+        ("unwind", "rust_eh_personality is present")
+    } else if rust_symbols == 0 {
+        ("unknown", "the symbols that would tell have been stripped")
+    } else if has_section(".gcc_except_table") {
+        (
+            "unknown",
+            "there is no panic runtime, but there are landing pads",
+        )
+    } else {
+        (
+            "abort",
+            "there is no panic runtime and there are no landing pads",
+        )
+    };
+    println!("  Panic strategy:  {} ({})", strategy, evidence);
+    if !has("rust_begin_unwind") && rust_symbols > 0 {
+        println!(
+            "                   no rust_begin_unwind: the panic handler may be custom (no_std) or immediate-abort"
+        );
+    }
+
+    match find_section(elf_file, &sections, ".llvmbc") {
+        Some(section) => println!(
+            "  Bitcode:         .llvmbc embedded ({} bytes), from -C embed-bitcode or for LTO",
+            section.sh_size
+        ),
+        None => println!("  Bitcode:         none embedded"),
+    }
+    let debug = if has_section(".debug_info") {
+        "DWARF in the file"
+    } else if has_section(".gnu_debuglink") {
+        "separate (.gnu_debuglink)"
+    } else {
+        "none"
+    };
+    println!("  Debug info:      {}", debug);
+
+    let mut schemes: Vec<_> = schemes.into_iter().collect();
+    schemes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let summary: Vec<String> = schemes
+        .iter()
+        .map(|(scheme, count)| format!("{} {}", count, scheme))
+        .collect();
+    if summary.is_empty() {
+        println!("  Mangling:        no symbols");
+    } else {
+        println!("  Mangling:        {}", summary.join(", "));
+    }
+
+    if by_crate.is_empty() {
+        return Ok(());
+    }
+    let mut crates: Vec<_> = by_crate.into_iter().collect();
+    crates.sort_by(|(a_name, a), (b_name, b)| b.1.cmp(&a.1).then(a_name.cmp(b_name)));
+    crates.truncate(top);
+    println!();
+    println!(
+        "Top {} crates by symbol size ({} bytes of Rust symbols):",
+        top, rust_total
+    );
+    println!("  {:>12} {:>6} {:>8}  Crate", "Size", "%", "Symbols");
+    for (name, (count, size)) in crates {
+        println!(
+            "  {:>12} {:>5.1}% {:>8}  {}",
+            size,
+            size as f64 * 100.0 / rust_total as f64,
+            count,
+            name
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use serde_json::{Value, json};
+use std::collections::BTreeSet;
+
+use crate::validate::{Finding, Level};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+fn sarif_level(level: Level) -> &'static str {
+    match level {
+        Level::Warning => "warning",
+        Level::Error => "error",
+    }
+}
+
+/// Builds a SARIF 2.1.0 log holding one run with the given findings, so CI
+/// systems such as GitHub code scanning can ingest them directly.
+pub fn sarif_log(path: &str, findings: &[Finding]) -> Value {
+    let rules: BTreeSet<&str> = findings.iter().map(|f| f.rule).collect();
+
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let mut location = json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": path, "index": 0 },
+                }
+            });
+            if let Some((offset, length)) = finding.region {
+                location["physicalLocation"]["region"] = json!({
+                    "byteOffset": offset,
+                    "byteLength": length,
+                });
+            }
+
+            json!({
+                "ruleId": finding.rule,
+                "level": sarif_level(finding.level),
+                "message": { "text": finding.message },
+                "locations": [location],
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "readelf-rs",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+                }
+            },
+            "artifacts": [{ "location": { "uri": path } }],
+            "results": results,
+        }]
+    })
+}
+
+/// Joins the logs of several files into one, with a run per file, so that
+/// the output stays a single SARIF document.
+pub fn merge(logs: Vec<Value>) -> Value {
+    let runs: Vec<Value> = logs
+        .into_iter()
+        .filter_map(|mut log| match log["runs"].take() {
+            Value::Array(runs) => Some(runs),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": runs,
+    })
+}
@@ -0,0 +1,102 @@
+use anyhow::{Context, Result, bail};
+
+use crate::elf::ElfFile;
+use crate::segment::SegmentType;
+
+/// Parses a search pattern: `hex:` followed by hex byte pairs (spaces are
+/// ignored, `??` matches any byte), or otherwise a literal string.
+fn parse_pattern(pattern: &str) -> Result<Vec<Option<u8>>> {
+    let Some(hex) = pattern.strip_prefix("hex:") else {
+        if pattern.is_empty() {
+            bail!("Empty search pattern");
+        }
+        return Ok(pattern.bytes().map(Some).collect());
+    };
+
+    let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        bail!(
+            "Hex pattern {:?} must have an even, nonzero number of digits",
+            hex
+        );
+    }
+
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair: String = pair.iter().collect();
+            if pair == "??" {
+                return Ok(None);
+            }
+            u8::from_str_radix(&pair, 16)
+                .map(Some)
+                .with_context(|| format!("Invalid hex byte {:?} in search pattern", pair))
+        })
+        .collect()
+}
+
+fn matches_at(data: &[u8], pattern: &[Option<u8>]) -> bool {
+    data.iter()
+        .zip(pattern)
+        .all(|(&byte, expected)| expected.is_none_or(|e| e == byte))
+}
+
+/// Finds every occurrence of `pattern` in the file and reports it with
+/// the section and segment containing it and its virtual address.
+pub fn print_search(elf_file: &ElfFile, pattern: &str) -> Result<()> {
+    let needle = parse_pattern(pattern)?;
+    let data = elf_file.data();
+    let sections = elf_file.section_headers()?;
+    let segments = elf_file.program_headers()?;
+
+    println!("Matches for {:?} ({} bytes):", pattern, needle.len());
+    println!(
+        "  {:<18} {:<18} {:<24} Segment",
+        "Offset", "Address", "Section"
+    );
+
+    let mut count = 0;
+    for offset in 0..data.len().saturating_sub(needle.len() - 1) {
+        if !matches_at(&data[offset..], &needle) {
+            continue;
+        }
+        count += 1;
+
+        let offset = offset as u64;
+        let section = sections
+            .iter()
+            .enumerate()
+            .find(|(_, s)| {
+                s.has_file_data() && offset >= s.sh_offset && offset - s.sh_offset < s.sh_size
+            })
+            .map(|(index, s)| {
+                format!(
+                    "[{:>2}] {}",
+                    index,
+                    elf_file.section_name(&sections, s).unwrap_or("")
+                )
+            })
+            .unwrap_or_else(|| "-".to_string());
+
+        let segment = segments.iter().enumerate().find(|(_, s)| {
+            s.p_type == SegmentType::LOAD
+                && offset >= s.p_offset
+                && offset - s.p_offset < s.p_filesz
+        });
+        let (address, segment) = match segment {
+            Some((index, s)) => (
+                format!("{:#018x}", s.p_vaddr + (offset - s.p_offset)),
+                format!("[{:>2}] LOAD", index),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+
+        println!(
+            "  {:#018x} {:<18} {:<24} {}",
+            offset, address, section, segment
+        );
+    }
+
+    println!("{} match(es)", count);
+    Ok(())
+}
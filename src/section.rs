@@ -0,0 +1,280 @@
+use std::fmt;
+
+pub const SHN_UNDEF: u16 = 0;
+pub const SHN_LORESERVE: u16 = 0xff00;
+pub const SHN_LOPROC: u16 = 0xff00;
+pub const SHN_HIPROC: u16 = 0xff1f;
+pub const SHN_LOOS: u16 = 0xff20;
+pub const SHN_HIOS: u16 = 0xff3f;
+pub const SHN_ABS: u16 = 0xfff1;
+pub const SHN_COMMON: u16 = 0xfff2;
+pub const SHN_XINDEX: u16 = 0xffff;
+
+pub const SHF_WRITE: u64 = SectionFlag::WRITE.0;
+pub const SHF_ALLOC: u64 = SectionFlag::ALLOC.0;
+pub const SHF_EXECINSTR: u64 = SectionFlag::EXECINSTR.0;
+pub const SHF_MERGE: u64 = SectionFlag::MERGE.0;
+pub const SHF_STRINGS: u64 = SectionFlag::STRINGS.0;
+pub const SHF_INFO_LINK: u64 = SectionFlag::INFO_LINK.0;
+pub const SHF_LINK_ORDER: u64 = SectionFlag::LINK_ORDER.0;
+pub const SHF_OS_NONCONFORMING: u64 = SectionFlag::OS_NONCONFORMING.0;
+pub const SHF_GROUP: u64 = SectionFlag::GROUP.0;
+pub const SHF_TLS: u64 = SectionFlag::TLS.0;
+pub const SHF_COMPRESSED: u64 = SectionFlag::COMPRESSED.0;
+pub const SHF_GNU_RETAIN: u64 = SectionFlag::GNU_RETAIN.0;
+pub const SHF_GNU_MBIND: u64 = SectionFlag::GNU_MBIND.0;
+pub const SHF_EXCLUDE: u64 = SectionFlag::EXCLUDE.0;
+pub const SHF_MASKOS: u64 = 0x0ff00000;
+pub const SHF_MASKPROC: u64 = 0xf0000000;
+
+/// The flags of the first word of a `SHT_GROUP` section.
+pub const GRP_COMDAT: u32 = 0x1;
+pub const GRP_MASKOS: u32 = 0x0ff00000;
+pub const GRP_MASKPROC: u32 = 0xf0000000;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf64SectionHeader {
+    pub sh_name: u32,
+    pub sh_type: SectionType,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf32SectionHeader {
+    pub sh_name: u32,
+    pub sh_type: SectionType,
+    pub sh_flags: u32,
+    pub sh_addr: u32,
+    pub sh_offset: u32,
+    pub sh_size: u32,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u32,
+    pub sh_entsize: u32,
+}
+
+/// A section header with all fields widened to their 64-bit sizes, so that
+/// callers don't need to care about the class of the file.
+#[allow(dead_code)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SectionHeader {
+    pub sh_name: u32,
+    pub sh_type: SectionType,
+    pub sh_flags: u64,
+    pub sh_addr: u64,
+    pub sh_offset: u64,
+    pub sh_size: u64,
+    pub sh_link: u32,
+    pub sh_info: u32,
+    pub sh_addralign: u64,
+    pub sh_entsize: u64,
+}
+
+impl From<Elf32SectionHeader> for SectionHeader {
+    fn from(sh: Elf32SectionHeader) -> Self {
+        Self {
+            sh_name: sh.sh_name,
+            sh_type: sh.sh_type,
+            sh_flags: sh.sh_flags.into(),
+            sh_addr: sh.sh_addr.into(),
+            sh_offset: sh.sh_offset.into(),
+            sh_size: sh.sh_size.into(),
+            sh_link: sh.sh_link,
+            sh_info: sh.sh_info,
+            sh_addralign: sh.sh_addralign.into(),
+            sh_entsize: sh.sh_entsize.into(),
+        }
+    }
+}
+
+impl From<Elf64SectionHeader> for SectionHeader {
+    fn from(sh: Elf64SectionHeader) -> Self {
+        Self {
+            sh_name: sh.sh_name,
+            sh_type: sh.sh_type,
+            sh_flags: sh.sh_flags,
+            sh_addr: sh.sh_addr,
+            sh_offset: sh.sh_offset,
+            sh_size: sh.sh_size,
+            sh_link: sh.sh_link,
+            sh_info: sh.sh_info,
+            sh_addralign: sh.sh_addralign,
+            sh_entsize: sh.sh_entsize,
+        }
+    }
+}
+
+impl SectionHeader {
+    pub fn is_alloc(&self) -> bool {
+        self.sh_flags & SHF_ALLOC != 0
+    }
+
+    /// Whether the section occupies bytes in the file.
+    pub fn has_file_data(&self) -> bool {
+        self.sh_type != SectionType::NOBITS && self.sh_type != SectionType::NULL
+    }
+
+    /// Whether `addr` falls inside the section once it is loaded.
+    pub fn contains_addr(&self, addr: u64) -> bool {
+        self.is_alloc() && addr >= self.sh_addr && addr - self.sh_addr < self.sh_size
+    }
+}
+
+/// One of the bits of `sh_flags`. The bare `SHF_*` constants are the same
+/// bits as plain masks, to test `sh_flags` with.
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SectionFlag(pub u64);
+
+crate::consts::constants!(SectionFlag(u64), shf, "SHF_", {
+    WRITE = 0x1,
+    ALLOC = 0x2,
+    EXECINSTR = 0x4,
+    MERGE = 0x10,
+    STRINGS = 0x20,
+    INFO_LINK = 0x40,
+    LINK_ORDER = 0x80,
+    OS_NONCONFORMING = 0x100,
+    GROUP = 0x200,
+    TLS = 0x400,
+    COMPRESSED = 0x800,
+    GNU_RETAIN = 0x200000,
+    GNU_MBIND = 0x1000000,
+    EXCLUDE = 0x80000000,
+});
+
+impl fmt::Display for SectionFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "{:#x}", self.0),
+        }
+    }
+}
+
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SectionType(pub u32);
+
+crate::consts::constants!(SectionType(u32), sht, "SHT_", {
+    NULL = 0,
+    PROGBITS = 1,
+    SYMTAB = 2,
+    STRTAB = 3,
+    RELA = 4,
+    HASH = 5,
+    DYNAMIC = 6,
+    NOTE = 7,
+    NOBITS = 8,
+    REL = 9,
+    SHLIB = 10,
+    DYNSYM = 11,
+    INIT_ARRAY = 14,
+    FINI_ARRAY = 15,
+    PREINIT_ARRAY = 16,
+    GROUP = 17,
+    SYMTAB_SHNDX = 18,
+    RELR = 19,
+    CREL = 0x40000014,
+    ANDROID_REL = 0x60000001,
+    ANDROID_RELA = 0x60000002,
+    ANDROID_RELR = 0x6fffff00,
+    LLVM_ODRTAB = 0x6fff4c00,
+    LLVM_LINKER_OPTIONS = 0x6fff4c01,
+    LLVM_ADDRSIG = 0x6fff4c03,
+    LLVM_DEPENDENT_LIBRARIES = 0x6fff4c04,
+    LLVM_SYMPART = 0x6fff4c05,
+    LLVM_PART_EHDR = 0x6fff4c06,
+    LLVM_PART_PHDR = 0x6fff4c07,
+    LLVM_BB_ADDR_MAP_V0 = 0x6fff4c08,
+    LLVM_CALL_GRAPH_PROFILE = 0x6fff4c09,
+    LLVM_BB_ADDR_MAP = 0x6fff4c0a,
+    LLVM_OFFLOADING = 0x6fff4c0b,
+    LLVM_LTO = 0x6fff4c0c,
+    GNU_ATTRIBUTES = 0x6ffffff5,
+    GNU_HASH = 0x6ffffff6,
+    GNU_LIBLIST = 0x6ffffff7,
+    GNU_VERDEF = 0x6ffffffd,
+    GNU_VERNEED = 0x6ffffffe,
+    GNU_VERSYM = 0x6fffffff,
+    // Solaris and illumos. The types from SUNW_cap on are shared with GNU,
+    // so these only add names to parse.
+    SUNW_ANCILLARY = 0x6fffffee,
+    SUNW_CAPCHAIN = 0x6fffffef,
+    SUNW_CAPINFO = 0x6ffffff0,
+    SUNW_SYMSORT = 0x6ffffff1,
+    SUNW_TLSSORT = 0x6ffffff2,
+    SUNW_LDYNSYM = 0x6ffffff3,
+    SUNW_DOF = 0x6ffffff4,
+    SUNW_CAP = 0x6ffffff5,
+    SUNW_SIGNATURE = 0x6ffffff6,
+    SUNW_ANNOTATE = 0x6ffffff7,
+    SUNW_DEBUGSTR = 0x6ffffff8,
+    SUNW_DEBUG = 0x6ffffff9,
+    SUNW_MOVE = 0x6ffffffa,
+    SUNW_COMDAT = 0x6ffffffb,
+    SUNW_SYMINFO = 0x6ffffffc,
+    SUNW_VERDEF = 0x6ffffffd,
+    SUNW_VERNEED = 0x6ffffffe,
+    SUNW_VERSYM = 0x6fffffff,
+});
+
+impl fmt::Display for SectionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NULL => write!(f, "NULL"),
+            Self::PROGBITS => write!(f, "PROGBITS"),
+            Self::SYMTAB => write!(f, "SYMTAB"),
+            Self::STRTAB => write!(f, "STRTAB"),
+            Self::RELA => write!(f, "RELA"),
+            Self::HASH => write!(f, "HASH"),
+            Self::DYNAMIC => write!(f, "DYNAMIC"),
+            Self::NOTE => write!(f, "NOTE"),
+            Self::NOBITS => write!(f, "NOBITS"),
+            Self::REL => write!(f, "REL"),
+            Self::SHLIB => write!(f, "SHLIB"),
+            Self::DYNSYM => write!(f, "DYNSYM"),
+            Self::INIT_ARRAY => write!(f, "INIT_ARRAY"),
+            Self::FINI_ARRAY => write!(f, "FINI_ARRAY"),
+            Self::PREINIT_ARRAY => write!(f, "PREINIT_ARRAY"),
+            Self::GROUP => write!(f, "GROUP"),
+            Self::SYMTAB_SHNDX => write!(f, "SYMTAB SECTION INDICES"),
+            Self::RELR => write!(f, "RELR"),
+            Self::CREL => write!(f, "CREL"),
+            Self::ANDROID_REL => write!(f, "ANDROID_REL"),
+            Self::ANDROID_RELA => write!(f, "ANDROID_RELA"),
+            Self::ANDROID_RELR => write!(f, "ANDROID_RELR"),
+            Self::LLVM_ODRTAB => write!(f, "LLVM_ODRTAB"),
+            Self::LLVM_LINKER_OPTIONS => write!(f, "LLVM_LINKER_OPTIONS"),
+            Self::LLVM_ADDRSIG => write!(f, "LLVM_ADDRSIG"),
+            Self::LLVM_DEPENDENT_LIBRARIES => write!(f, "LLVM_DEPENDENT_LIBRARIES"),
+            Self::LLVM_SYMPART => write!(f, "LLVM_SYMPART"),
+            Self::LLVM_PART_EHDR => write!(f, "LLVM_PART_EHDR"),
+            Self::LLVM_PART_PHDR => write!(f, "LLVM_PART_PHDR"),
+            Self::LLVM_BB_ADDR_MAP_V0 => write!(f, "LLVM_BB_ADDR_MAP_V0"),
+            Self::LLVM_CALL_GRAPH_PROFILE => write!(f, "LLVM_CALL_GRAPH_PROFILE"),
+            Self::LLVM_BB_ADDR_MAP => write!(f, "LLVM_BB_ADDR_MAP"),
+            Self::LLVM_OFFLOADING => write!(f, "LLVM_OFFLOADING"),
+            Self::LLVM_LTO => write!(f, "LLVM_LTO"),
+            Self::GNU_ATTRIBUTES => write!(f, "GNU_ATTRIBUTES"),
+            Self::GNU_HASH => write!(f, "GNU_HASH"),
+            Self::GNU_LIBLIST => write!(f, "GNU_LIBLIST"),
+            Self::GNU_VERDEF => write!(f, "VERDEF"),
+            Self::GNU_VERNEED => write!(f, "VERNEED"),
+            Self::GNU_VERSYM => write!(f, "VERSYM"),
+            Self(0x60000000..=0x6fffffff) => write!(f, "LOOS+{:#x}", self.0 - 0x60000000),
+            Self(0x70000000..=0x7fffffff) => write!(f, "LOPROC+{:#x}", self.0 - 0x70000000),
+            Self(0x80000000..=0xffffffff) => write!(f, "LOUSER+{:#x}", self.0 - 0x80000000),
+            _ => write!(f, "{:08x}: <unknown>", self.0),
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use anyhow::Result;
+
+use crate::consts::{
+    ELFOSABI_FREEBSD, ELFOSABI_GNU, ELFOSABI_NONE, SHF_ARM_PURECODE, SHF_PPC_VLE, SHF_X86_64_LARGE,
+    SHT_AARCH64_ATTRIBUTES, SHT_ARM_ATTRIBUTES, SHT_ARM_EXIDX, SHT_ARM_PREEMPTMAP,
+    SHT_RISCV_ATTRIBUTES, SHT_X86_64_UNWIND,
+};
+use crate::elf::ElfFile;
+use crate::emachine::EMachine;
+use crate::filter::SectionFilter;
+use crate::section::{
+    GRP_COMDAT, GRP_MASKOS, GRP_MASKPROC, SHF_ALLOC, SHF_COMPRESSED, SHF_EXCLUDE, SHF_EXECINSTR,
+    SHF_GNU_MBIND, SHF_GNU_RETAIN, SHF_GROUP, SHF_INFO_LINK, SHF_LINK_ORDER, SHF_MASKOS,
+    SHF_MASKPROC, SHF_MERGE, SHF_OS_NONCONFORMING, SHF_STRINGS, SHF_TLS, SHF_WRITE, SectionHeader,
+    SectionType,
+};
+use crate::strtab::SymbolTable;
+use crate::symbol::SymbolType;
+
+/// The name readelf gives a section type, taking the processor-specific
+/// types of the machines it knows into account.
+fn type_name(elf_file: &ElfFile, sh_type: SectionType) -> String {
+    let name = match (elf_file.machine(), sh_type) {
+        (EMachine::X8664, SHT_X86_64_UNWIND) => "X86_64_UNWIND",
+        (EMachine::Arm, SHT_ARM_EXIDX) => "ARM_EXIDX",
+        (EMachine::Arm, SHT_ARM_PREEMPTMAP) => "ARM_PREEMPTMAP",
+        (EMachine::Arm, SHT_ARM_ATTRIBUTES) => "ARM_ATTRIBUTES",
+        (EMachine::Aarch64, SHT_AARCH64_ATTRIBUTES) => "AARCH64_ATTRIBUTES",
+        (EMachine::Riscv, SHT_RISCV_ATTRIBUTES) => "RISCV_ATTRIBUTES",
+        _ => return sh_type.to_string(),
+    };
+    name.to_string()
+}
+
+/// The letters of the flags, in the order of their bits, as in readelf's
+/// key to flags.
+fn flags_string(elf_file: &ElfFile, flags: u64) -> String {
+    let machine = elf_file.machine();
+    let os_abi = elf_file.ident().os_abi;
+    let mut letters = String::new();
+    let mut rest = flags;
+    while rest != 0 {
+        let flag = rest & rest.wrapping_neg();
+        rest &= !flag;
+        letters.push(match flag {
+            SHF_WRITE => 'W',
+            SHF_ALLOC => 'A',
+            SHF_EXECINSTR => 'X',
+            SHF_MERGE => 'M',
+            SHF_STRINGS => 'S',
+            SHF_INFO_LINK => 'I',
+            SHF_LINK_ORDER => 'L',
+            SHF_OS_NONCONFORMING => 'O',
+            SHF_GROUP => 'G',
+            SHF_TLS => 'T',
+            SHF_EXCLUDE => 'E',
+            SHF_COMPRESSED => 'C',
+            SHF_X86_64_LARGE if machine == EMachine::X8664 => 'l',
+            SHF_ARM_PURECODE if machine == EMachine::Arm => 'y',
+            SHF_PPC_VLE if machine == EMachine::Ppc => 'v',
+            SHF_GNU_RETAIN if matches!(os_abi, ELFOSABI_GNU | ELFOSABI_FREEBSD) => 'R',
+            SHF_GNU_MBIND if matches!(os_abi, ELFOSABI_NONE | ELFOSABI_GNU | ELFOSABI_FREEBSD) => {
+                'D'
+            }
+            // The other OS and processor bits only get one letter each.
+            _ if flag & SHF_MASKOS != 0 => {
+                rest &= !SHF_MASKOS;
+                'o'
+            }
+            _ if flag & SHF_MASKPROC != 0 => {
+                rest &= !SHF_MASKPROC;
+                'p'
+            }
+            _ => 'x',
+        });
+    }
+    letters
+}
+
+fn print_key_to_flags(elf_file: &ElfFile) {
+    println!("Key to Flags:");
+    println!("  W (write), A (alloc), X (execute), M (merge), S (strings), I (info),");
+    println!("  L (link order), O (extra OS processing required), G (group), T (TLS),");
+    println!("  C (compressed), x (unknown), o (OS specific), E (exclude),");
+    print!("  ");
+    let os_abi = elf_file.ident().os_abi;
+    if matches!(os_abi, ELFOSABI_GNU | ELFOSABI_FREEBSD) {
+        print!("R (retain), ");
+    }
+    if matches!(os_abi, ELFOSABI_NONE | ELFOSABI_GNU | ELFOSABI_FREEBSD) {
+        print!("D (mbind), ");
+    }
+    match elf_file.machine() {
+        EMachine::X8664 => print!("l (large), "),
+        EMachine::Arm => print!("y (purecode), "),
+        EMachine::Ppc => print!("v (VLE), "),
+        _ => {}
+    }
+    println!("p (processor specific)");
+}
+
+/// Prints the section headers `filter` selects in the style of
+/// `readelf -SW`. The count and offset of the table are left out after the
+/// file header, which has them and ends in the blank line that comes before
+/// the table.
+pub fn print_section_headers(
+    elf_file: &ElfFile,
+    after_file_header: bool,
+    filter: &SectionFilter,
+) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    if sections.is_empty() {
+        if !after_file_header {
+            println!();
+        }
+        println!("There are no sections in this file.");
+        return Ok(());
+    }
+
+    if !after_file_header {
+        println!(
+            "There are {} section headers, starting at offset {:#x}:",
+            sections.len(),
+            elf_file.header().e_shoff
+        );
+        println!();
+    }
+    println!(
+        "{}",
+        if sections.len() == 1 {
+            "Section Header:"
+        } else {
+            "Section Headers:"
+        }
+    );
+    if elf_file.is_64() {
+        println!(
+            "  [Nr] Name              Type            Address          Off    Size   ES Flg Lk Inf Al"
+        );
+    } else {
+        println!(
+            "  [Nr] Name              Type            Addr     Off    Size   ES Flg Lk Inf Al"
+        );
+    }
+
+    for (index, section) in sections.iter().enumerate() {
+        if !filter.selects(elf_file, &sections, index) {
+            continue;
+        }
+        let name = elf_file
+            .section_name(&sections, section)
+            .unwrap_or("<corrupt>");
+        let address = if elf_file.is_64() {
+            format!("{:016x}", section.sh_addr)
+        } else {
+            format!("{:08x}", section.sh_addr)
+        };
+        println!(
+            "  [{:2}] {:<17} {:<15} {} {:06x} {:06x} {:02x} {:>3} {:2} {:3} {:2}",
+            index,
+            name,
+            type_name(elf_file, section.sh_type),
+            address,
+            section.sh_offset,
+            section.sh_size,
+            section.sh_entsize,
+            flags_string(elf_file, section.sh_flags),
+            section.sh_link,
+            section.sh_info,
+            section.sh_addralign
+        );
+    }
+
+    print_key_to_flags(elf_file);
+    Ok(())
+}
+
+/// How readelf describes the flags word of a group.
+fn group_flags(flags: u32) -> String {
+    match flags {
+        0 => String::new(),
+        GRP_COMDAT => "COMDAT ".to_string(),
+        _ => format!(
+            "[{:#x}: {}{}{}]",
+            flags,
+            if flags & GRP_MASKOS != 0 {
+                "<OS specific>"
+            } else {
+                ""
+            },
+            if flags & GRP_MASKPROC != 0 {
+                "<PROC specific>"
+            } else {
+                ""
+            },
+            if flags & !(GRP_COMDAT | GRP_MASKOS | GRP_MASKPROC) != 0 {
+                "<unknown>"
+            } else {
+                ""
+            }
+        ),
+    }
+}
+
+/// The name of the symbol a group is keyed on: its signature. Section
+/// symbols stand for the name of their section.
+fn group_signature(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    group: &SectionHeader,
+) -> Result<String> {
+    let symbols = SymbolTable::linked(elf_file, sections, group)?;
+    let index = group.sh_info as usize;
+    let Some(symbol) = symbols.get(index) else {
+        return Ok("<corrupt>".to_string());
+    };
+    if symbol.symbol_type() == SymbolType::SECTION
+        && let Some(section) = sections.get(symbol.st_shndx as usize)
+    {
+        return Ok(elf_file
+            .section_name(sections, section)
+            .unwrap_or("<corrupt>")
+            .to_string());
+    }
+    Ok(symbols.name(index).unwrap_or("<corrupt>").to_string())
+}
+
+/// Prints the section groups and their members in the style of `readelf -g`.
+pub fn print_section_groups(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let groups: Vec<(usize, &SectionHeader)> = sections
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.sh_type == SectionType::GROUP)
+        .collect();
+    if groups.is_empty() {
+        println!();
+        println!("There are no section groups in this file.");
+        return Ok(());
+    }
+
+    for (index, group) in groups {
+        let words: Vec<u32> = elf_file
+            .section_data(group)?
+            .chunks_exact(4)
+            .map(|word| u32::from_ne_bytes(word.try_into().unwrap()))
+            .collect();
+        let Some((&flags, members)) = words.split_first() else {
+            continue;
+        };
+
+        println!();
+        println!(
+            "{}group section [{:5}] `{}' [{}] contains {} sections:",
+            group_flags(flags),
+            index,
+            elf_file
+                .section_name(&sections, group)
+                .unwrap_or("<corrupt>"),
+            group_signature(elf_file, &sections, group)?,
+            members.len()
+        );
+        println!("   [Index]    Name");
+        for &member in members {
+            let name = sections
+                .get(member as usize)
+                .and_then(|s| elf_file.section_name(&sections, s).ok())
+                .unwrap_or("<corrupt>");
+            println!("   [{:5}]   {}", member, name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+
+pub const PF_X: u32 = 0x1;
+pub const PF_W: u32 = 0x2;
+pub const PF_R: u32 = 0x4;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf64ProgramHeader {
+    pub p_type: SegmentType,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_paddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf32ProgramHeader {
+    pub p_type: SegmentType,
+    pub p_offset: u32,
+    pub p_vaddr: u32,
+    pub p_paddr: u32,
+    pub p_filesz: u32,
+    pub p_memsz: u32,
+    pub p_flags: u32,
+    pub p_align: u32,
+}
+
+/// A program header with all fields widened to their 64-bit sizes, so that
+/// callers don't need to care about the class of the file.
+#[allow(dead_code)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ProgramHeader {
+    pub p_type: SegmentType,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_paddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
+}
+
+impl From<Elf32ProgramHeader> for ProgramHeader {
+    fn from(ph: Elf32ProgramHeader) -> Self {
+        Self {
+            p_type: ph.p_type,
+            p_flags: ph.p_flags,
+            p_offset: ph.p_offset.into(),
+            p_vaddr: ph.p_vaddr.into(),
+            p_paddr: ph.p_paddr.into(),
+            p_filesz: ph.p_filesz.into(),
+            p_memsz: ph.p_memsz.into(),
+            p_align: ph.p_align.into(),
+        }
+    }
+}
+
+impl From<Elf64ProgramHeader> for ProgramHeader {
+    fn from(ph: Elf64ProgramHeader) -> Self {
+        Self {
+            p_type: ph.p_type,
+            p_flags: ph.p_flags,
+            p_offset: ph.p_offset,
+            p_vaddr: ph.p_vaddr,
+            p_paddr: ph.p_paddr,
+            p_filesz: ph.p_filesz,
+            p_memsz: ph.p_memsz,
+            p_align: ph.p_align,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl ProgramHeader {
+    pub fn is_readable(&self) -> bool {
+        self.p_flags & PF_R != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.p_flags & PF_W != 0
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.p_flags & PF_X != 0
+    }
+
+    /// Permissions in readelf's "RWE" notation, with spaces for unset bits.
+    pub fn flags_string(&self) -> String {
+        [(PF_R, 'R'), (PF_W, 'W'), (PF_X, 'E')]
+            .iter()
+            .map(|&(bit, c)| if self.p_flags & bit != 0 { c } else { ' ' })
+            .collect()
+    }
+}
+
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SegmentType(pub u32);
+
+crate::consts::constants!(SegmentType(u32), pt, "PT_", {
+    NULL = 0,
+    LOAD = 1,
+    DYNAMIC = 2,
+    INTERP = 3,
+    NOTE = 4,
+    SHLIB = 5,
+    PHDR = 6,
+    TLS = 7,
+    GNU_EH_FRAME = 0x6474e550,
+    GNU_STACK = 0x6474e551,
+    GNU_RELRO = 0x6474e552,
+    GNU_PROPERTY = 0x6474e553,
+    GNU_SFRAME = 0x6474e554,
+    OPENBSD_MUTABLE = 0x65a3dbe5,
+    OPENBSD_RANDOMIZE = 0x65a3dbe6,
+    OPENBSD_WXNEEDED = 0x65a3dbe7,
+    OPENBSD_NOBTCFI = 0x65a3dbe8,
+    OPENBSD_SYSCALLS = 0x65a3dbe9,
+    OPENBSD_BOOTDATA = 0x65a41be6,
+});
+
+impl fmt::Display for SegmentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.name(), self.0) {
+            (Some(name), _) => write!(f, "{}", name),
+            (None, 0x60000000..=0x6fffffff) => write!(f, "LOOS+{:#x}", self.0 - 0x60000000),
+            (None, 0x70000000..=0x7fffffff) => write!(f, "LOPROC+{:#x}", self.0 - 0x70000000),
+            _ => write!(f, "<unknown>: {:#x}", self.0),
+        }
+    }
+}
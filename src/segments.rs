@@ -0,0 +1,219 @@
+use anyhow::Result;
+
+use crate::elf::ElfFile;
+use crate::notes::{self, NT_GNU_PROPERTY_TYPE_0};
+use crate::section::{SHF_TLS, SectionHeader, SectionType};
+use crate::segment::{ProgramHeader, SegmentType};
+
+/// Whether `section` belongs to `segment`, following the rules readelf
+/// uses for its section to segment mapping: only allocated sections count,
+/// TLS data only sits in TLS, LOAD and RELRO segments, and the .tbss
+/// placeholder only in the TLS segment.
+fn section_in_segment(section: &SectionHeader, segment: &ProgramHeader) -> bool {
+    if !section.is_alloc() {
+        return false;
+    }
+
+    let tls = section.sh_flags & SHF_TLS != 0;
+    let tbss = tls && section.sh_type == SectionType::NOBITS;
+    match segment.p_type {
+        SegmentType::TLS if !tls => return false,
+        SegmentType::TLS | SegmentType::LOAD | SegmentType::GNU_RELRO => {}
+        _ if tls => return false,
+        _ => {}
+    }
+    if tbss && segment.p_type != SegmentType::TLS {
+        return false;
+    }
+
+    // Ends past the address space have been warned about; they are cut
+    // short at its end.
+    let start = segment.p_vaddr;
+    let end = segment.p_vaddr.saturating_add(segment.p_memsz);
+    if section.sh_addr < start || section.sh_addr > end {
+        return false;
+    }
+    // Empty sections at the very end of a segment belong to the next one.
+    if section.sh_size == 0 {
+        return section.sh_addr < end || (segment.p_memsz == 0 && section.sh_addr == start);
+    }
+    section.sh_addr.saturating_add(section.sh_size) <= end
+}
+
+/// Warns about a segment or section whose end does not fit in the address
+/// space, which the mapping then cuts short.
+fn check_end(kind: &str, index: usize, start: u64, size: u64) {
+    if start.checked_add(size).is_none() {
+        eprintln!(
+            "readelf-rs: Warning: {} {} at {:#x} has a size of {:#x}, which ends past the end of the address space",
+            kind, index, start, size
+        );
+    }
+}
+
+fn print_stack(segment: &ProgramHeader) {
+    if segment.is_executable() {
+        println!("      [Stack is executable: code can be run from the stack]");
+    } else {
+        println!("      [Stack is not executable]");
+    }
+    if segment.p_memsz != 0 {
+        println!("      [Stack size: {:#x} bytes]", segment.p_memsz);
+    }
+}
+
+/// Lists which sections the RELRO segment makes read-only once relocation
+/// is done, and how much of each.
+fn print_relro(elf_file: &ElfFile, sections: &[SectionHeader], segment: &ProgramHeader) {
+    let start = segment.p_vaddr;
+    let end = segment.p_vaddr.saturating_add(segment.p_memsz);
+    println!(
+        "      [Read-only after relocation: {:#x}-{:#x}, {} bytes]",
+        start, end, segment.p_memsz
+    );
+
+    for section in sections {
+        if !section.is_alloc() || section.sh_size == 0 {
+            continue;
+        }
+        let section_end = section.sh_addr.saturating_add(section.sh_size);
+        let covered = section_end
+            .min(end)
+            .saturating_sub(section.sh_addr.max(start));
+        if covered == 0 {
+            continue;
+        }
+
+        let name = elf_file.section_name(sections, section).unwrap_or("");
+        let extent = if covered == section.sh_size {
+            "fully covered".to_string()
+        } else {
+            format!("{} of {} bytes covered", covered, section.sh_size)
+        };
+        println!(
+            "        {:<20} {:#018x} {:>8} bytes  ({})",
+            name, section.sh_addr, section.sh_size, extent
+        );
+    }
+}
+
+/// Decodes the property note the segment points at, naming the section
+/// that holds it.
+fn print_property(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    segment: &ProgramHeader,
+) -> Result<()> {
+    let section = sections
+        .iter()
+        .find(|s| s.sh_type == SectionType::NOTE && s.sh_offset == segment.p_offset)
+        .and_then(|s| elf_file.section_name(sections, s).ok())
+        .unwrap_or("property note");
+
+    let notes = notes::parse_notes(elf_file.segment_data(segment)?, segment.p_align).notes;
+    let properties: Vec<String> = notes
+        .iter()
+        .filter(|n| n.name == "GNU" && n.n_type == NT_GNU_PROPERTY_TYPE_0)
+        .flat_map(|n| notes::gnu_properties(n.desc, elf_file.is_64()))
+        .collect();
+
+    if properties.is_empty() {
+        println!("      [See {}: no properties]", section);
+    }
+    for property in properties {
+        println!("      [See {}: {}]", section, property);
+    }
+
+    Ok(())
+}
+
+/// Prints the program headers in the style of `readelf -lW`, explaining the
+/// GNU_STACK, GNU_RELRO and GNU_PROPERTY segments and OpenBSD's marker
+/// segments, then the section to segment mapping.
+pub fn print_program_headers(elf_file: &ElfFile) -> Result<()> {
+    let segments = elf_file.program_headers()?;
+    let sections = elf_file.section_headers()?;
+
+    println!();
+    if segments.is_empty() {
+        println!("There are no program headers in this file.");
+        return Ok(());
+    }
+
+    println!("Elf file type is {}", elf_file.type_description());
+    println!("Entry point {:#x}", elf_file.entry());
+    println!(
+        "There are {} program headers, starting at offset {}",
+        segments.len(),
+        elf_file.header().e_phoff
+    );
+
+    println!();
+    println!("Program Headers:");
+    println!(
+        "  {:<14} {:<8} {:<18} {:<18} {:<8} {:<8} Flg Align",
+        "Type", "Offset", "VirtAddr", "PhysAddr", "FileSiz", "MemSiz"
+    );
+    for (index, segment) in segments.iter().enumerate() {
+        check_end("Segment", index, segment.p_vaddr, segment.p_memsz);
+        println!(
+            "  {:<14} 0x{:06x} 0x{:016x} 0x{:016x} 0x{:06x} 0x{:06x} {} {:#x}",
+            segment.p_type.to_string(),
+            segment.p_offset,
+            segment.p_vaddr,
+            segment.p_paddr,
+            segment.p_filesz,
+            segment.p_memsz,
+            segment.flags_string(),
+            segment.p_align
+        );
+
+        match segment.p_type {
+            SegmentType::INTERP => {
+                if let Some(interpreter) = elf_file.interpreter()? {
+                    println!("      [Requesting program interpreter: {}]", interpreter);
+                }
+            }
+            SegmentType::GNU_STACK => print_stack(segment),
+            SegmentType::GNU_RELRO => print_relro(elf_file, &sections, segment),
+            SegmentType::GNU_PROPERTY => print_property(elf_file, &sections, segment)?,
+            SegmentType::OPENBSD_WXNEEDED => {
+                println!("      [Needs writable and executable mappings (W^X exemption)]")
+            }
+            SegmentType::OPENBSD_NOBTCFI => {
+                println!("      [Branch target CFI not enforced]")
+            }
+            SegmentType::OPENBSD_RANDOMIZE => {
+                println!("      [Filled with random data at load time]")
+            }
+            SegmentType::OPENBSD_MUTABLE => {
+                println!("      [Stays mutable when the rest is made immutable]")
+            }
+            _ => {}
+        }
+    }
+
+    if sections.is_empty() {
+        return Ok(());
+    }
+
+    for (index, section) in sections.iter().enumerate() {
+        if section.is_alloc() {
+            check_end("Section", index, section.sh_addr, section.sh_size);
+        }
+    }
+
+    println!();
+    println!(" Section to Segment mapping:");
+    println!("  Segment Sections...");
+    for (index, segment) in segments.iter().enumerate() {
+        let names: Vec<&str> = sections
+            .iter()
+            .filter(|s| section_in_segment(s, segment))
+            .map(|s| elf_file.section_name(&sections, s).unwrap_or(""))
+            .collect();
+        println!("   {:02}     {}", index, names.join(" "));
+    }
+
+    Ok(())
+}
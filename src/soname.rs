@@ -0,0 +1,241 @@
+//! Whether a shared library is named and linked the way the loader and
+//! ldconfig expect: `libfoo.so.1.2.3` with a SONAME of `libfoo.so.1`, a
+//! `libfoo.so.1` link next to it for the loader and a `libfoo.so` link for
+//! the linker.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::dynamic::DynamicTag;
+use crate::elf::{ElfFile, ElfType};
+use crate::version::{self, VER_FLG_BASE};
+
+/// Splits `libfoo.so.1.2` into `libfoo` and its version numbers.
+fn split_name(name: &str) -> Option<(&str, Vec<&str>)> {
+    if let Some(stem) = name.strip_suffix(".so") {
+        return Some((stem, Vec::new()));
+    }
+    let (stem, numbers) = name.split_once(".so.")?;
+    Some((stem, numbers.split('.').collect()))
+}
+
+/// The major version a version node like `LIBFOO_2.1` or `FOO_2` gives,
+/// when it is named after the library `stem`.
+fn node_major(node: &str, stem: &str) -> Option<u64> {
+    let (prefix, number) = node.rsplit_once('_')?;
+    let major = number.split('.').next()?.parse().ok()?;
+    let normalize = |name: &str| {
+        let name: String = name
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_uppercase();
+        name.strip_prefix("LIB").map(str::to_string).unwrap_or(name)
+    };
+    (normalize(prefix) == normalize(stem)).then_some(major)
+}
+
+/// What a path that should be a link to the library points to, and whether
+/// that is the library; `None` when there is nothing there.
+fn link_state(path: &Path, library: &Path) -> Option<(String, bool)> {
+    let target = path.canonicalize().ok()?;
+    if let Ok(link) = std::fs::read_link(path) {
+        return Some((format!("-> {}", link.display()), target == library));
+    }
+    // Distributions install some linker links as scripts naming the
+    // library, as glibc's libc.so.
+    let script = std::fs::read(path).is_ok_and(|data| !data.starts_with(b"\x7fELF"));
+    if script {
+        Some(("(a linker script)".to_string(), true))
+    } else {
+        Some((
+            "(a copy, not a symbolic link)".to_string(),
+            target == library,
+        ))
+    }
+}
+
+/// Checks the SONAME of a shared library against its file name, the
+/// symbolic links next to it and its version definitions.
+pub fn print_soname_check(elf_file: &ElfFile, path: &str) -> Result<()> {
+    println!("SONAME check:");
+    if elf_file.elf_type() != ElfType::DYN || elf_file.is_pie()? {
+        println!(
+            "  Not applicable: {} files are not loaded by their SONAME.",
+            elf_file.type_description()
+        );
+        return Ok(());
+    }
+
+    let mut problems = Vec::new();
+    let mut notes = Vec::new();
+    let library = Path::new(path)
+        .canonicalize()
+        .unwrap_or_else(|_| Path::new(path).to_path_buf());
+    let file_name = library
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let directory = library.parent().unwrap_or(Path::new("."));
+    println!("  File:            {}", library.display());
+
+    let Some(soname) = elf_file
+        .dynamic_strings(DynamicTag::SONAME)?
+        .first()
+        .map(|s| s.to_string())
+    else {
+        println!("  SONAME:          none");
+        println!();
+        println!("Problems:");
+        println!(
+            "  There is no DT_SONAME, so programs record whatever name they were linked against; link with -Wl,-soname,{}",
+            split_name(&file_name)
+                .map(|(stem, numbers)| match numbers.first() {
+                    Some(major) => format!("{}.so.{}", stem, major),
+                    None => format!("{}.so", stem),
+                })
+                .unwrap_or(file_name)
+        );
+        return Ok(());
+    };
+
+    let parsed = split_name(&soname);
+    let major = parsed.as_ref().and_then(|(_, numbers)| numbers.first());
+    match major {
+        Some(major) => println!("  SONAME:          {} (major version {})", soname, major),
+        None => {
+            println!("  SONAME:          {}", soname);
+            if parsed.is_some() {
+                notes.push(
+                    "The SONAME has no major version, so incompatible releases cannot be installed side by side"
+                        .to_string(),
+                );
+            }
+        }
+    }
+    if soname.contains('/') {
+        problems.push(format!(
+            "The SONAME {} is a path; the loader would only ever look there",
+            soname
+        ));
+    }
+
+    // The file is the SONAME itself, or it with the minor numbers added.
+    if file_name != soname && !file_name.starts_with(&format!("{}.", soname)) {
+        match (&parsed, split_name(&file_name)) {
+            (Some((stem, numbers)), Some((file_stem, file_numbers))) if *stem == file_stem => {
+                problems.push(format!(
+                    "The file is version {} but the SONAME says {}",
+                    file_numbers.join("."),
+                    numbers.join(".")
+                ));
+            }
+            _ => problems.push(format!(
+                "The file name {} does not start with the SONAME {}",
+                file_name, soname
+            )),
+        }
+    }
+
+    if file_name != soname && !soname.contains('/') {
+        let link = directory.join(&soname);
+        match link_state(&link, &library) {
+            Some((shown, same)) => {
+                println!("  SONAME link:     {} {}", link.display(), shown);
+                if !same {
+                    problems.push(format!(
+                        "{} is another file, so programs load another build",
+                        link.display()
+                    ));
+                }
+            }
+            None => {
+                println!("  SONAME link:     {} (missing)", link.display());
+                problems.push(format!(
+                    "There is no {} for the loader to find; run ldconfig or create the link",
+                    link.display()
+                ));
+            }
+        }
+    }
+    if let Some((stem, _)) = &parsed {
+        let link = directory.join(format!("{}.so", stem));
+        if link != library {
+            match link_state(&link, &library) {
+                Some((shown, same)) => {
+                    println!("  Linker link:     {} {}", link.display(), shown);
+                    if !same {
+                        notes.push(format!(
+                            "{} is another file, so programs linked with -l{} get another SONAME",
+                            link.display(),
+                            stem.strip_prefix("lib").unwrap_or(stem)
+                        ));
+                    }
+                }
+                None => println!(
+                    "  Linker link:     {} (missing; only needed to link against the library)",
+                    link.display()
+                ),
+            }
+        }
+    }
+
+    let sections = elf_file.section_headers()?;
+    let definitions = version::definitions(elf_file, &sections)?;
+    let mut nodes = Vec::new();
+    for definition in &definitions {
+        let Some(name) = definition.names.first() else {
+            continue;
+        };
+        if definition.flags & VER_FLG_BASE != 0 {
+            if *name != soname {
+                problems.push(format!(
+                    "The base version definition is named {}, not the SONAME",
+                    name
+                ));
+            }
+        } else {
+            nodes.push(name.as_str());
+        }
+    }
+    if nodes.is_empty() {
+        println!("  Versions:        none: the exported symbols are unversioned");
+    } else if nodes.len() > 6 {
+        println!(
+            "  Versions:        {} nodes, {} to {}",
+            nodes.len(),
+            nodes[0],
+            nodes[nodes.len() - 1]
+        );
+    } else {
+        println!("  Versions:        {}", nodes.join(", "));
+    }
+    if let (Some((stem, _)), Some(major)) = (&parsed, major.and_then(|m| m.parse::<u64>().ok()))
+        && let Some((node, node_major)) = nodes
+            .iter()
+            .filter_map(|n| Some((n, node_major(n, stem)?)))
+            .max_by_key(|&(_, m)| m)
+        && node_major > major
+    {
+        problems.push(format!(
+            "The version node {} is for major version {}, but the SONAME is still major {}",
+            node, node_major, major
+        ));
+    }
+
+    for (title, list) in [("Problems", &problems), ("Notes", &notes)] {
+        if list.is_empty() {
+            continue;
+        }
+        println!();
+        println!("{}:", title);
+        for item in list {
+            println!("  {}", item);
+        }
+    }
+    if problems.is_empty() {
+        println!();
+        println!("The SONAME, file name and links are consistent.");
+    }
+    Ok(())
+}
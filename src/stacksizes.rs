@@ -0,0 +1,103 @@
+use anyhow::Result;
+
+use crate::attributes::read_uleb128;
+use crate::elf::{ElfFile, ElfType};
+use crate::initorder::{self, FunctionNames};
+
+/// The function an entry is for, and the stack it uses.
+struct StackSize {
+    address: Option<u64>,
+    function: String,
+    size: u64,
+}
+
+/// Reads the `.stack_sizes` sections that `-fstack-size-section` emits:
+/// for each function, a pointer to it followed by its stack frame size in
+/// ULEB128. Relocatable objects usually have one section per function
+/// section, with the pointers left to relocations.
+fn stack_sizes(elf_file: &ElfFile) -> Result<Vec<StackSize>> {
+    let sections = elf_file.section_headers()?;
+    let names = FunctionNames::new(elf_file, &sections)?;
+    let word = if elf_file.is_64() { 8 } else { 4 };
+    let mut entries = Vec::new();
+
+    for (index, section) in sections.iter().enumerate() {
+        if elf_file.section_name(&sections, section)? != ".stack_sizes" {
+            continue;
+        }
+        let targets = if elf_file.elf_type() == ElfType::REL {
+            initorder::relocation_targets(elf_file, &sections, index)?
+        } else {
+            Default::default()
+        };
+
+        let data = elf_file.section_data(section)?;
+        let mut offset = 0;
+        while let Some(pointer) = data.get(offset..offset + word) {
+            let address = if elf_file.is_64() {
+                u64::from_ne_bytes(pointer.try_into().unwrap())
+            } else {
+                u32::from_ne_bytes(pointer.try_into().unwrap()) as u64
+            };
+            let entry = offset as u64;
+            offset += word;
+            let Some(size) = read_uleb128(data, &mut offset) else {
+                break;
+            };
+
+            let (address, function) = match targets.get(&entry) {
+                Some(target) => (None, target.clone()),
+                None => (
+                    Some(address),
+                    names
+                        .get(address)
+                        .map_or_else(|| format!("{:#x}", address), str::to_string),
+                ),
+            };
+            entries.push(StackSize {
+                address,
+                function,
+                size,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Lists the stack usage of each function recorded in `.stack_sizes`,
+/// largest first.
+pub fn print_stack_sizes(elf_file: &ElfFile) -> Result<()> {
+    let mut entries = stack_sizes(elf_file)?;
+    if entries.is_empty() {
+        println!(
+            "There is no stack size information in this file (compile with -fstack-size-section)."
+        );
+        return Ok(());
+    }
+    entries.sort_by(|a, b| {
+        b.size
+            .cmp(&a.size)
+            .then_with(|| a.function.cmp(&b.function))
+    });
+
+    println!("Stack sizes of {} function(s):", entries.len());
+    // Relocatable objects only know which symbol each entry is for.
+    if entries.iter().all(|e| e.address.is_none()) {
+        println!("  {:>8}  Function", "Size");
+        for entry in &entries {
+            println!("  {:>8}  {}", entry.size, entry.function);
+        }
+    } else {
+        println!("  {:>8}  {:<18}  Function", "Size", "Address");
+        for entry in &entries {
+            let address = entry
+                .address
+                .map_or_else(String::new, |a| format!("{:#x}", a));
+            println!("  {:>8}  {:<18}  {}", entry.size, address, entry.function);
+        }
+    }
+    println!();
+
+    Ok(())
+}
@@ -0,0 +1,314 @@
+//! Which start files the compiler driver linked into a binary: the C
+//! library's `crt1.o` and its variants, which define `_start`, `crti.o` and
+//! `crtn.o` around `.init` and `.fini`, and the compiler's `crtbegin.o` and
+//! `crtend.o`. Each leaves symbols and code behind that tell them apart, and
+//! with them which of `-pie`, `-static`, `-static-pie`, `-nostartfiles` or
+//! `-shared` the binary was linked with.
+
+use anyhow::Result;
+use std::collections::HashSet;
+
+use crate::dynamic::DynamicTag;
+use crate::elf::{ElfFile, ElfType};
+use crate::emachine::EMachine;
+use crate::linkage;
+use crate::section::{SHN_UNDEF, SectionType};
+use crate::strtab::SymbolTable;
+
+/// Symbols GCC's crtstuff.c defines in every crtbegin variant.
+const CRTBEGIN: &[&str] = &[
+    "__do_global_dtors_aux",
+    "frame_dummy",
+    "register_tm_clones",
+    "deregister_tm_clones",
+];
+
+/// How the start code of x86-64 glibc passes `main` to
+/// `__libc_start_main`: `Scrt1.o` and `rcrt1.o` load it through the GOT,
+/// position-dependent `crt1.o` as an immediate.
+fn x86_64_start(elf_file: &ElfFile) -> Result<Option<bool>> {
+    let Some(offset) = elf_file.vaddr_to_offset(elf_file.entry())? else {
+        return Ok(None);
+    };
+    let code = elf_file
+        .data()
+        .get(offset as usize..)
+        .map(|code| &code[..code.len().min(64)])
+        .unwrap_or(&[]);
+    for window in code.windows(3) {
+        match window {
+            // mov main@GOTPCREL(%rip), %rdi
+            [0x48, 0x8b, 0x3d] => return Ok(Some(true)),
+            // mov $main, %rdi
+            [0x48, 0xc7, 0xc7] => return Ok(Some(false)),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// What the symbol tables say: the names defined and the names imported.
+struct Symbols {
+    defined: HashSet<String>,
+    undefined: HashSet<String>,
+    entry: Option<String>,
+    full: bool,
+}
+
+impl Symbols {
+    fn read(elf_file: &ElfFile) -> Result<Symbols> {
+        let sections = elf_file.section_headers()?;
+        let mut symbols = Symbols {
+            defined: HashSet::new(),
+            undefined: HashSet::new(),
+            entry: None,
+            full: sections.iter().any(|s| s.sh_type == SectionType::SYMTAB),
+        };
+        for table in sections
+            .iter()
+            .filter(|s| matches!(s.sh_type, SectionType::SYMTAB | SectionType::DYNSYM))
+        {
+            let table = SymbolTable::new(elf_file, &sections, table)?;
+            for (index, symbol) in table.iter().enumerate() {
+                let name = table.name(index)?;
+                if name.is_empty() {
+                    continue;
+                }
+                if symbol.st_shndx == SHN_UNDEF {
+                    symbols.undefined.insert(name.to_string());
+                    continue;
+                }
+                if symbol.st_value == elf_file.entry()
+                    && elf_file.entry() != 0
+                    && (symbols.entry.is_none() || name == "_start")
+                {
+                    symbols.entry = Some(name.to_string());
+                }
+                symbols.defined.insert(name.to_string());
+            }
+        }
+        Ok(symbols)
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.defined.contains(name) || self.undefined.contains(name)
+    }
+}
+
+/// Prints the start files that built the file, the evidence for each, and
+/// the link mode they point to.
+pub fn print_startup(elf_file: &ElfFile) -> Result<()> {
+    println!("Startup objects:");
+    let elf_type = elf_file.elf_type();
+    if elf_type != ElfType::EXEC && elf_type != ElfType::DYN {
+        println!(
+            "  Not applicable: {} files have not been linked with start files.",
+            elf_file.type_description()
+        );
+        return Ok(());
+    }
+
+    let interpreter = elf_file.interpreter()?;
+    let sections = elf_file.section_headers()?;
+    let symbols = Symbols::read(elf_file)?;
+    let library = elf_type == ElfType::DYN && !elf_file.is_pie()?;
+    let static_pie = elf_type == ElfType::DYN && interpreter.is_none() && !library;
+    let section = |name: &str| {
+        sections
+            .iter()
+            .any(|s| elf_file.section_name(&sections, s).ok() == Some(name))
+    };
+
+    println!(
+        "  Type:         {}",
+        linkage::linkage_kind(elf_file, interpreter)?
+    );
+    match &symbols.entry {
+        Some(name) => println!("  Entry point:  {:#x} ({})", elf_file.entry(), name),
+        None => println!("  Entry point:  {:#x}", elf_file.entry()),
+    }
+    let libc = linkage::libc_flavor(elf_file, interpreter)?;
+    if let Some((flavor, evidence)) = &libc {
+        println!("  C library:    {} (from {})", flavor, evidence);
+    }
+    let comment = sections
+        .iter()
+        .find(|s| elf_file.section_name(&sections, s).ok() == Some(".comment"))
+        .and_then(|s| elf_file.section_data(s).ok())
+        .unwrap_or(&[]);
+    let mut toolchain: Vec<String> = Vec::new();
+    for entry in comment.split(|&b| b == 0).map(String::from_utf8_lossy) {
+        if !entry.is_empty() && !toolchain.iter().any(|t| *t == entry) {
+            toolchain.push(entry.into_owned());
+        }
+    }
+    for (index, entry) in toolchain.iter().enumerate() {
+        let label = if index == 0 { "Toolchain:" } else { "" };
+        println!("  {:<13} {} (.comment)", label, entry);
+    }
+
+    let mut objects: Vec<(String, String)> = Vec::new();
+    let starts = symbols.defined.contains("_start");
+    if symbols.defined.contains("_start_c") || symbols.defined.contains("_dlstart_c") {
+        let object = if symbols.defined.contains("_dlstart_c") {
+            "rcrt1.o"
+        } else if elf_type == ElfType::DYN {
+            "Scrt1.o"
+        } else {
+            "crt1.o"
+        };
+        objects.push((
+            format!("musl {}", object),
+            "_start hands over to _start_c".to_string(),
+        ));
+    } else if (starts || !symbols.full) && symbols.has("__libc_start_main") {
+        let pic = if elf_file.machine() == EMachine::X8664 {
+            x86_64_start(elf_file)?
+        } else {
+            None
+        };
+        let (object, why) = if symbols.has("__monstartup") || symbols.has("mcount") {
+            (
+                "gcrt1.o",
+                "_start sets up profiling with __monstartup (-pg)",
+            )
+        } else if static_pie {
+            (
+                "rcrt1.o",
+                "_start relocates the static-PIE itself before __libc_start_main",
+            )
+        } else if pic == Some(true) || (pic.is_none() && elf_type == ElfType::DYN) {
+            (
+                "Scrt1.o",
+                "_start passes main to __libc_start_main position-independently",
+            )
+        } else {
+            (
+                "crt1.o",
+                "_start passes main to __libc_start_main at a fixed address",
+            )
+        };
+        objects.push((object.to_string(), why.to_string()));
+        if pic == Some(true) && elf_type == ElfType::EXEC {
+            objects.push((
+                String::new(),
+                "(position-independent start code in a position-dependent executable)".to_string(),
+            ));
+        }
+    } else if symbols.undefined.contains("__libc_init") {
+        let object = if interpreter.is_some() {
+            "crtbegin_dynamic.o"
+        } else {
+            "crtbegin_static.o"
+        };
+        objects.push((
+            format!("bionic {}", object),
+            "_start calls __libc_init".to_string(),
+        ));
+    }
+    if symbols.has("__libc_csu_init") {
+        objects.push((
+            "libc_nonshared.a".to_string(),
+            "__libc_csu_init runs the constructors, as before glibc 2.34".to_string(),
+        ));
+    }
+
+    let init = section(".init") && symbols.defined.contains("_init");
+    let fini = section(".fini") && symbols.defined.contains("_fini");
+    if init || fini || (!symbols.full && section(".init")) {
+        objects.push((
+            "crti.o, crtn.o".to_string(),
+            "the prologue and epilogue of _init in .init and _fini in .fini".to_string(),
+        ));
+    }
+
+    let crtstuff: Vec<&str> = CRTBEGIN
+        .iter()
+        .copied()
+        .filter(|name| symbols.defined.contains(*name))
+        .collect();
+    if !crtstuff.is_empty() {
+        let object = if elf_type == ElfType::DYN {
+            "crtbeginS.o"
+        } else if interpreter.is_none() {
+            "crtbeginT.o"
+        } else {
+            "crtbegin.o"
+        };
+        objects.push((
+            format!("GCC {}", object),
+            format!("defines {}", crtstuff.join(", ")),
+        ));
+    } else if symbols.defined.contains("__do_init") || symbols.defined.contains("__do_fini") {
+        objects.push((
+            "compiler-rt crtbegin.o".to_string(),
+            "defines __do_init and __do_fini".to_string(),
+        ));
+    }
+    if symbols.defined.contains("__FRAME_END__") {
+        let object = if elf_type == ElfType::DYN {
+            "crtendS.o"
+        } else {
+            "crtend.o"
+        };
+        objects.push((
+            object.to_string(),
+            "__FRAME_END__ terminates .eh_frame".to_string(),
+        ));
+    }
+    if symbols.defined.contains("set_fast_math") {
+        objects.push((
+            "crtfastmath.o".to_string(),
+            "set_fast_math flushes denormals to zero (-ffast-math)".to_string(),
+        ));
+    }
+
+    println!();
+    if objects.is_empty() {
+        println!("  No start files were recognized.");
+    }
+    for (object, why) in &objects {
+        println!("  {:<24} {}", object, why);
+    }
+
+    let startfiles = objects.iter().any(|(object, _)| object.contains("crt1.o"))
+        || objects
+            .iter()
+            .any(|(object, _)| object.contains("crtbegin_"));
+    let mode = match (library, startfiles, elf_type, interpreter) {
+        (true, _, _, _) if symbols.entry.is_some() && starts => {
+            "-shared, with an entry point of its own (like libc.so.6 or the dynamic loader)"
+                .to_string()
+        }
+        (true, _, _, _) => "-shared".to_string(),
+        (false, true, ElfType::DYN, Some(_)) => "-pie".to_string(),
+        (false, true, ElfType::DYN, None) => "-static-pie".to_string(),
+        (false, true, _, Some(_)) => "-no-pie".to_string(),
+        (false, true, _, None) => "-static".to_string(),
+        (false, false, _, _) if !symbols.full => {
+            "unknown: without a .symtab there is too little to go on".to_string()
+        }
+        (false, false, _, _) => {
+            let entry = match &symbols.entry {
+                Some(name) if name != "_start" => format!(" -e {}", name),
+                _ => String::new(),
+            };
+            if libc.is_none() && elf_file.dynamic_strings(DynamicTag::NEEDED)?.is_empty() {
+                format!("-nostdlib{} (no start files and no C library)", entry)
+            } else {
+                format!(
+                    "-nostartfiles{} (the program defines its own entry point)",
+                    entry
+                )
+            }
+        }
+    };
+    println!();
+    println!("  Linked with:  {}", mode);
+    if !symbols.full {
+        println!(
+            "  The file has no .symtab, so only the dynamic symbols and the entry code were looked at."
+        );
+    }
+    Ok(())
+}
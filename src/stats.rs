@@ -0,0 +1,187 @@
+//! A one-screen structural fingerprint of a file: what its sections,
+//! symbols, relocations and notes add up to, and whether it carries debug
+//! info. Comparing two of these says quickly how two builds differ.
+
+use anyhow::Result;
+
+use crate::elf::ElfFile;
+use crate::notes;
+use crate::section::{SHF_COMPRESSED, SectionType};
+
+/// Counts of each distinct key, most common first.
+fn tally<K: Ord>(keys: impl IntoIterator<Item = K>) -> Vec<(K, usize)> {
+    let mut counts: Vec<(K, usize)> = Vec::new();
+    for key in keys {
+        match counts.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((key, 1)),
+        }
+    }
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    counts
+}
+
+/// `NAME count` pairs on one line.
+fn join(counts: &[(String, usize)]) -> String {
+    counts
+        .iter()
+        .map(|(name, count)| format!("{} {}", name, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Prints the summary.
+pub fn print_stats(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let segments = elf_file.program_headers()?;
+    let file_size = elf_file.data().len() as u64;
+    // Without section headers, the symbols and relocations can still be
+    // found through the dynamic section.
+    let tables = elf_file.section_headers_or_dynamic()?;
+
+    println!("Statistics:");
+    println!(
+        "  File:        {}, {}, {}, {:#x} bytes",
+        elf_file.elf_type(),
+        if elf_file.is_64() { "ELF64" } else { "ELF32" },
+        elf_file.machine(),
+        file_size
+    );
+    println!(
+        "  Headers:     {} segment(s), {} section(s)",
+        segments.len(),
+        sections.len()
+    );
+
+    println!();
+    println!("Sections by type:");
+    if sections.is_empty() {
+        println!("  There are no section headers.");
+    }
+    let mut by_type: Vec<(SectionType, usize, u64)> = Vec::new();
+    for section in sections.iter().filter(|s| s.sh_type != SectionType::NULL) {
+        match by_type.iter_mut().find(|(t, _, _)| *t == section.sh_type) {
+            Some((_, count, size)) => {
+                *count += 1;
+                *size += section.sh_size;
+            }
+            None => by_type.push((section.sh_type, 1, section.sh_size)),
+        }
+    }
+    by_type.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.0.cmp(&b.0.0)));
+    for (sh_type, count, size) in &by_type {
+        println!(
+            "  {:<24} {:>5} {:>#12x} bytes",
+            sh_type.to_string(),
+            count,
+            size
+        );
+    }
+
+    println!();
+    println!("Symbols:");
+    let mut any_table = false;
+    for table in tables
+        .iter()
+        .filter(|s| matches!(s.sh_type, SectionType::SYMTAB | SectionType::DYNSYM))
+    {
+        any_table = true;
+        let name = elf_file.section_name(&tables, table).unwrap_or("?");
+        // The null symbol at index 0 is not a symbol.
+        let symbols: Vec<_> = elf_file.symbols(table)?.skip(1).collect();
+        let bindings = tally(symbols.iter().map(|s| s.binding().to_string()));
+        let types = tally(symbols.iter().map(|s| s.symbol_type().to_string()));
+        println!("  {:<24} {:>5}", name, symbols.len());
+        if !symbols.is_empty() {
+            println!("    by binding:  {}", join(&bindings));
+            println!("    by type:     {}", join(&types));
+        }
+    }
+    if !any_table {
+        println!("  There is no symbol table.");
+    }
+
+    println!();
+    println!("Relocations:");
+    let mut total = 0;
+    for section in &tables {
+        let count = if ElfFile::is_relocation_section(section) {
+            elf_file.decoded_relocations(section)?.len()
+        } else if matches!(
+            section.sh_type,
+            SectionType::RELR | SectionType::ANDROID_RELR
+        ) {
+            elf_file.relr_addresses(section)?.len()
+        } else {
+            continue;
+        };
+        total += count;
+        println!(
+            "  {:<24} {:>5} ({})",
+            elf_file.section_name(&tables, section).unwrap_or("?"),
+            count,
+            section.sh_type
+        );
+    }
+    if total == 0 {
+        println!("  There are no relocations.");
+    } else {
+        println!("  {:<24} {:>5}", "Total", total);
+    }
+
+    println!();
+    println!("Notes:");
+    let notes = notes::notes(elf_file)?;
+    if notes.is_empty() {
+        println!("  There are no notes.");
+    }
+    let kinds = tally(notes.iter().map(|n| {
+        (
+            n.name,
+            notes::type_name(elf_file.elf_type(), n.name, n.n_type),
+        )
+    }));
+    for ((owner, kind), count) in kinds {
+        println!("  {:<12} {:>5}  {}", owner, count, kind);
+    }
+
+    println!();
+    println!("Debug info:");
+    let named: Vec<_> = sections
+        .iter()
+        .map(|s| (elf_file.section_name(&sections, s).unwrap_or(""), s))
+        .collect();
+    let debug: Vec<_> = named
+        .iter()
+        .filter(|(name, _)| name.starts_with(".debug_") || name.starts_with(".zdebug_"))
+        .collect();
+    if debug.is_empty() {
+        println!("  DWARF:       not present");
+    } else {
+        let size: u64 = debug.iter().map(|(_, s)| s.sh_size).sum();
+        let compressed = debug
+            .iter()
+            .any(|(name, s)| s.sh_flags & SHF_COMPRESSED != 0 || name.starts_with(".zdebug_"));
+        println!(
+            "  DWARF:       {} section(s), {:#x} bytes ({:.1}% of the file){}",
+            debug.len(),
+            size,
+            size as f64 * 100.0 / file_size.max(1) as f64,
+            if compressed { ", compressed" } else { "" }
+        );
+    }
+    let present = |name: &str| {
+        if named.iter().any(|(n, _)| *n == name) {
+            "present"
+        } else {
+            "not present"
+        }
+    };
+    println!(
+        "  Build ID:    {}",
+        notes::build_id(elf_file)?.unwrap_or_else(|| "not present".to_string())
+    );
+    println!("  Debug link:  {}", present(".gnu_debuglink"));
+    println!("  CTF:         {}", present(".ctf"));
+    Ok(())
+}
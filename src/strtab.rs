@@ -0,0 +1,120 @@
+use anyhow::{Context, Result, anyhow};
+use std::sync::OnceLock;
+
+use crate::elf::ElfFile;
+use crate::section::SectionHeader;
+use crate::symbol::Symbol;
+
+/// A string table, checked once when it is opened so that looking a string
+/// up only has to find its end. Anything after the last NUL is cut off, so
+/// no lookup can run past the end of the table; strings that started there
+/// are reported as unterminated.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct StringTable<'a> {
+    data: &'a [u8],
+    /// Length of the table before it was cut at the last NUL.
+    len: usize,
+}
+
+impl<'a> StringTable<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        let terminated = data.iter().rposition(|&b| b == 0).map_or(0, |end| end + 1);
+        Self {
+            data: &data[..terminated],
+            len: data.len(),
+        }
+    }
+
+    /// The string starting at `offset`.
+    pub fn get(&self, offset: usize) -> Result<&'a str> {
+        self.lookup(offset).map_err(|message| anyhow!(message))
+    }
+
+    fn lookup(&self, offset: usize) -> Result<&'a str, &'static str> {
+        let Some(bytes) = self.data.get(offset..).filter(|b| !b.is_empty()) else {
+            return Err(if offset < self.len {
+                "Unterminated string in string table"
+            } else {
+                "String table index out of range"
+            });
+        };
+        // Always found, since the table ends with a NUL.
+        let len = bytes.iter().position(|&b| b == 0).unwrap();
+        std::str::from_utf8(&bytes[..len]).map_err(|_| "Invalid UTF-8 in string table")
+    }
+}
+
+/// The entries of a SYMTAB or DYNSYM section together with their string
+/// table. Each name is looked up the first time it is asked for and then
+/// remembered, since passes over relocations ask for the same few symbols
+/// over and over. The memo is a `OnceLock` per symbol, so a table can be
+/// shared between threads.
+pub struct SymbolTable<'a> {
+    symbols: Vec<Symbol>,
+    strtab: StringTable<'a>,
+    names: Vec<OnceLock<Result<&'a str, &'static str>>>,
+}
+
+impl<'a> SymbolTable<'a> {
+    pub fn new(
+        elf_file: &'a ElfFile,
+        sections: &[SectionHeader],
+        section: &SectionHeader,
+    ) -> Result<Self> {
+        let symbols: Vec<Symbol> = elf_file.symbols(section)?.collect();
+        let strtab = sections
+            .get(section.sh_link as usize)
+            .context("Symbol table links to a nonexistent string table")?;
+        Ok(Self {
+            names: symbols.iter().map(|_| OnceLock::new()).collect(),
+            symbols,
+            strtab: StringTable::new(elf_file.section_data(strtab)?),
+        })
+    }
+
+    /// The symbol table linked from a relocation or other section through
+    /// `sh_link`, or an empty table when there is none.
+    pub fn linked(
+        elf_file: &'a ElfFile,
+        sections: &[SectionHeader],
+        section: &SectionHeader,
+    ) -> Result<Self> {
+        match sections
+            .get(section.sh_link as usize)
+            .filter(|_| section.sh_link != 0)
+        {
+            Some(symtab) => Self::new(elf_file, sections, symtab),
+            None => Ok(Self {
+                symbols: Vec::new(),
+                strtab: StringTable::default(),
+                names: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Symbol> {
+        self.symbols.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Name of the symbol at `index`.
+    pub fn name(&self, index: usize) -> Result<&'a str> {
+        let name = self
+            .names
+            .get(index)
+            .context("Symbol index out of range")?
+            .get_or_init(|| self.strtab.lookup(self.symbols[index].st_name as usize));
+        name.map_err(|message| anyhow!(message))
+    }
+}
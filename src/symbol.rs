@@ -0,0 +1,155 @@
+use std::fmt;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf64Symbol {
+    pub st_name: u32,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+    pub st_value: u64,
+    pub st_size: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Elf32Symbol {
+    pub st_name: u32,
+    pub st_value: u32,
+    pub st_size: u32,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+}
+
+/// A symbol table entry with all fields widened to their 64-bit sizes, so
+/// that callers don't need to care about the class of the file.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Symbol {
+    pub st_name: u32,
+    pub st_info: u8,
+    pub st_other: u8,
+    pub st_shndx: u16,
+    pub st_value: u64,
+    pub st_size: u64,
+}
+
+impl From<Elf32Symbol> for Symbol {
+    fn from(sym: Elf32Symbol) -> Self {
+        Self {
+            st_name: sym.st_name,
+            st_info: sym.st_info,
+            st_other: sym.st_other,
+            st_shndx: sym.st_shndx,
+            st_value: sym.st_value.into(),
+            st_size: sym.st_size.into(),
+        }
+    }
+}
+
+impl From<Elf64Symbol> for Symbol {
+    fn from(sym: Elf64Symbol) -> Self {
+        Self {
+            st_name: sym.st_name,
+            st_info: sym.st_info,
+            st_other: sym.st_other,
+            st_shndx: sym.st_shndx,
+            st_value: sym.st_value,
+            st_size: sym.st_size,
+        }
+    }
+}
+
+impl Symbol {
+    pub fn binding(&self) -> SymbolBinding {
+        SymbolBinding(self.st_info >> 4)
+    }
+
+    pub fn symbol_type(&self) -> SymbolType {
+        SymbolType(self.st_info & 0xf)
+    }
+
+    pub fn visibility(&self) -> SymbolVisibility {
+        SymbolVisibility(self.st_other & 0x3)
+    }
+}
+
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SymbolBinding(pub u8);
+
+crate::consts::constants!(SymbolBinding(u8), stb, "STB_", {
+    LOCAL = 0,
+    GLOBAL = 1,
+    WEAK = 2,
+    GNU_UNIQUE = 10,
+});
+
+impl fmt::Display for SymbolBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::LOCAL => write!(f, "LOCAL"),
+            Self::GLOBAL => write!(f, "GLOBAL"),
+            Self::WEAK => write!(f, "WEAK"),
+            Self::GNU_UNIQUE => write!(f, "UNIQUE"),
+            Self(10..=12) => write!(f, "<OS specific>: {}", self.0),
+            Self(13..=15) => write!(f, "<processor specific>: {}", self.0),
+            _ => write!(f, "<unknown>: {}", self.0),
+        }
+    }
+}
+
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SymbolType(pub u8);
+
+crate::consts::constants!(SymbolType(u8), stt, "STT_", {
+    NOTYPE = 0,
+    OBJECT = 1,
+    FUNC = 2,
+    SECTION = 3,
+    FILE = 4,
+    COMMON = 5,
+    TLS = 6,
+    GNU_IFUNC = 10,
+});
+
+impl fmt::Display for SymbolType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NOTYPE => write!(f, "NOTYPE"),
+            Self::OBJECT => write!(f, "OBJECT"),
+            Self::FUNC => write!(f, "FUNC"),
+            Self::SECTION => write!(f, "SECTION"),
+            Self::FILE => write!(f, "FILE"),
+            Self::COMMON => write!(f, "COMMON"),
+            Self::TLS => write!(f, "TLS"),
+            Self::GNU_IFUNC => write!(f, "IFUNC"),
+            Self(10..=12) => write!(f, "<OS specific>: {}", self.0),
+            Self(13..=15) => write!(f, "<processor specific>: {}", self.0),
+            _ => write!(f, "<unknown>: {}", self.0),
+        }
+    }
+}
+
+#[repr(transparent)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SymbolVisibility(pub u8);
+
+crate::consts::constants!(SymbolVisibility(u8), stv, "STV_", {
+    DEFAULT = 0,
+    INTERNAL = 1,
+    HIDDEN = 2,
+    PROTECTED = 3,
+});
+
+impl fmt::Display for SymbolVisibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::DEFAULT => write!(f, "DEFAULT"),
+            Self::INTERNAL => write!(f, "INTERNAL"),
+            Self::HIDDEN => write!(f, "HIDDEN"),
+            _ => write!(f, "PROTECTED"),
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::Result;
+
+use crate::demangle::NameStyle;
+use crate::dwarf::{self, Declarations};
+use crate::elf::{ElfFile, ElfType};
+use crate::emachine::EMachine;
+use crate::filter::SectionFilter;
+use crate::ppc64;
+use crate::section::{
+    SHN_ABS, SHN_COMMON, SHN_HIOS, SHN_HIPROC, SHN_LOOS, SHN_LOPROC, SHN_LORESERVE, SHN_UNDEF,
+    SHN_XINDEX, SectionHeader, SectionType,
+};
+use crate::strtab::SymbolTable;
+use crate::symbol::{Symbol, SymbolType};
+use crate::version::{VERSYM_HIDDEN, VersionTables};
+
+const SHN_X86_64_LCOMMON: u16 = 0xff02;
+const SHN_MIPS_SCOMMON: u16 = 0xff03;
+const SHN_MIPS_SUNDEFINED: u16 = 0xff04;
+
+const STO_PPC64_LOCAL_MASK: u8 = 0xe0;
+const STO_AARCH64_VARIANT_PCS: u8 = 0x80;
+const STO_RISCV_VARIANT_CC: u8 = 0x80;
+
+/// The real section indexes of the symbols of a table whose `st_shndx` is
+/// `SHN_XINDEX`, from the SYMTAB_SHNDX section linked to the table.
+fn extended_indexes(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    table_index: usize,
+) -> Result<Vec<u32>> {
+    let Some(shndx) = sections
+        .iter()
+        .find(|s| s.sh_type == SectionType::SYMTAB_SHNDX && s.sh_link as usize == table_index)
+    else {
+        return Ok(Vec::new());
+    };
+    Ok(elf_file
+        .section_data(shndx)?
+        .chunks_exact(4)
+        .map(|c| u32::from_ne_bytes(c.try_into().unwrap()))
+        .collect())
+}
+
+/// The section a symbol is defined in, resolving `SHN_XINDEX` through the
+/// extended index table. Reserved indexes are returned as they are.
+fn section_index(symbol: &Symbol, index: usize, extended: &[u32]) -> u32 {
+    match symbol.st_shndx {
+        SHN_XINDEX => extended.get(index).copied().unwrap_or(SHN_XINDEX as u32),
+        shndx => shndx as u32,
+    }
+}
+
+/// The Ndx column: a section index, or the name of a reserved index.
+fn index_display(machine: EMachine, shndx: u32, section_count: usize) -> String {
+    if shndx > u16::MAX as u32 {
+        return format!("{:3}", shndx);
+    }
+    match shndx as u16 {
+        SHN_UNDEF => "UND".to_string(),
+        SHN_ABS => "ABS".to_string(),
+        SHN_COMMON => "COM".to_string(),
+        SHN_X86_64_LCOMMON if machine == EMachine::X8664 => "LARGE_COM".to_string(),
+        SHN_MIPS_SCOMMON if machine == EMachine::Mips => "SCOM".to_string(),
+        SHN_MIPS_SUNDEFINED if machine == EMachine::Mips => "SUND".to_string(),
+        n @ SHN_LOPROC..=SHN_HIPROC => format!("PRC[{:#06x}]", n),
+        n @ SHN_LOOS..=SHN_HIOS => format!("OS [{:#06x}]", n),
+        n @ SHN_LORESERVE.. => format!("RSV[{:#06x}]", n),
+        n if section_count != 0 && n as usize >= section_count => {
+            format!("bad section index[{:3}]", n)
+        }
+        n => format!("{:3}", n),
+    }
+}
+
+/// The `st_other` bits besides the visibility, which some processors use
+/// for flags of their own. Empty when none are set.
+fn other_display(machine: EMachine, st_other: u8) -> String {
+    let other = st_other & !0x3;
+    match machine {
+        _ if other == 0 => String::new(),
+        EMachine::Ppc64 if other & !STO_PPC64_LOCAL_MASK == 0 => {
+            format!(" [<localentry>: {}] ", ppc64::local_entry_offset(st_other))
+        }
+        EMachine::Aarch64 if other == STO_AARCH64_VARIANT_PCS => " [VARIANT_PCS] ".to_string(),
+        EMachine::Riscv if other == STO_RISCV_VARIANT_CC => " [VARIANT_CC] ".to_string(),
+        _ => format!(" [<other>: {:x}] ", other),
+    }
+}
+
+/// The version suffix readelf appends to dynamic symbol names: `@@V` for
+/// the default version of a definition, `@V` for a hidden one and
+/// `@V (n)` for a version required from another object. The symbols that
+/// stand for the versions a library defines are named after the version
+/// and get no suffix.
+fn version_suffix(versions: &VersionTables, index: usize, name: &str) -> String {
+    let (Some(versym), Some(version)) = (versions.versym(index), versions.version(index)) else {
+        return String::new();
+    };
+    match &version.file {
+        None if version.name == name => String::new(),
+        Some(_) => format!("@{} ({})", version.name, versym & !VERSYM_HIDDEN),
+        None if versym & VERSYM_HIDDEN != 0 => format!("@{}", version.name),
+        None => format!("@@{}", version.name),
+    }
+}
+
+/// How the symbol tables are printed, beyond which ones.
+#[derive(Default)]
+pub struct Options {
+    /// Follow functions and objects with where they are declared.
+    pub sources: bool,
+    /// Leave out of those source paths where the code was built.
+    pub deterministic: bool,
+    /// Name the section a symbol is defined in, as readelf's -X.
+    pub extra_info: bool,
+    /// How symbol names are printed.
+    pub names: NameStyle,
+    /// The base of the Size column, as readelf's --sym-base: 8, 10 or 16,
+    /// or 0 for decimal up to 99999 and hex above.
+    pub size_base: u32,
+}
+
+/// The Size column.
+fn size_display(size: u64, base: u32) -> String {
+    match base {
+        8 => format!("{:5o}", size),
+        10 => format!("{:5}", size),
+        16 => format!("{:#07x}", size),
+        _ if size <= 99999 => format!("{:5}", size),
+        _ => format!("{:#x}", size),
+    }
+}
+
+fn print_table(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    table_index: usize,
+    versions: &VersionTables,
+    declarations: Option<&Declarations>,
+    options: &Options,
+) -> Result<()> {
+    let extra_info = options.extra_info;
+    let table = &sections[table_index];
+    let symbols = SymbolTable::new(elf_file, sections, table)?;
+    let extended = extended_indexes(elf_file, sections, table_index)?;
+    let machine = elf_file.machine();
+    let dynamic = table.sh_type == SectionType::DYNSYM;
+    // Sections rebuilt from the dynamic segment are not the ones symbols
+    // refer to, so their indexes cannot be checked.
+    let section_count = if elf_file.header().e_shoff == 0 {
+        0
+    } else {
+        sections.len()
+    };
+
+    let count = symbols.iter().count();
+    println!();
+    println!(
+        "Symbol table '{}' contains {} {}:",
+        elf_file.section_name(sections, table).unwrap_or(""),
+        count,
+        if count == 1 { "entry" } else { "entries" }
+    );
+    if extra_info && elf_file.elf_type() == ElfType::REL {
+        println!("The values of defined symbols are offsets into the section named after Ndx.");
+    }
+    if elf_file.is_64() {
+        println!("   Num:    Value          Size Type    Bind   Vis      Ndx Name");
+    } else {
+        println!("   Num:    Value  Size Type    Bind   Vis      Ndx Name");
+    }
+
+    for (index, symbol) in symbols.iter().enumerate() {
+        let shndx = section_index(symbol, index, &extended);
+        let mut name = symbols.name(index).unwrap_or("<corrupt>").to_string();
+        // Section symbols have no name of their own.
+        if name.is_empty() && symbol.symbol_type() == SymbolType::SECTION {
+            name = sections
+                .get(shndx as usize)
+                .and_then(|s| elf_file.section_name(sections, s).ok())
+                .unwrap_or("")
+                .to_string();
+        }
+        // Markers such as __bss_start share an address with the first
+        // object after them.
+        let source = declarations
+            .filter(|_| {
+                matches!(symbol.symbol_type(), SymbolType::FUNC | SymbolType::OBJECT)
+                    && symbol.st_shndx != SHN_UNDEF
+                    && symbol.st_size != 0
+            })
+            .and_then(|d| d.by_address(symbol.st_value).or_else(|| d.by_name(&name)))
+            .map(|(file, line)| format!("  [{}:{}]", file, line))
+            .unwrap_or_default();
+        let mut shown = options.names.display(&name);
+        if dynamic {
+            shown += &version_suffix(versions, index, &name);
+        }
+        shown += &source;
+
+        let value = if elf_file.is_64() {
+            format!("{:016x}", symbol.st_value)
+        } else {
+            format!("{:08x}", symbol.st_value)
+        };
+        let mut ndx = index_display(machine, shndx, section_count);
+        if extra_info
+            && shndx != SHN_UNDEF as u32
+            && (shndx as usize) < section_count
+            && (shndx > u16::MAX as u32 || (shndx as u16) < SHN_LORESERVE)
+        {
+            let section = elf_file.section_name(sections, &sections[shndx as usize]);
+            ndx = format!("{:>4} ({})", ndx, section.unwrap_or("?"));
+        }
+        let size = size_display(symbol.st_size, options.size_base);
+        println!(
+            "{:6}: {} {} {:<7} {:<6} {:<7}{} {:>4} {}",
+            index,
+            value,
+            size,
+            symbol.symbol_type().to_string(),
+            symbol.binding().to_string(),
+            symbol.visibility().to_string(),
+            other_display(machine, symbol.st_other),
+            ndx,
+            shown
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints the symbol tables in readelf's format: `.dynsym` and `.symtab`,
+/// or only `.dynsym` when `dynamic_only` is set. Dynamic symbols carry
+/// their version. The filter selects the symbol table sections to print,
+/// and `options` how they are printed.
+pub fn print_symbols(
+    elf_file: &ElfFile,
+    dynamic_only: bool,
+    filter: &SectionFilter,
+    options: &Options,
+) -> Result<()> {
+    let sections = elf_file.section_headers_or_dynamic()?;
+    let versions = VersionTables::new(elf_file, &sections)?;
+    // The debug info of relocatable objects points into the string
+    // sections through relocations, which are not applied to it.
+    let dwarf = if options.sources && elf_file.elf_type() != ElfType::REL {
+        dwarf::load(elf_file)?
+    } else {
+        None
+    };
+    let declarations = dwarf
+        .as_ref()
+        .map(|dwarf| Declarations::new(dwarf, options.deterministic))
+        .transpose()?;
+
+    for (index, section) in sections.iter().enumerate() {
+        let wanted = match section.sh_type {
+            SectionType::DYNSYM => true,
+            SectionType::SYMTAB => !dynamic_only,
+            _ => false,
+        };
+        if wanted && filter.selects(elf_file, &sections, index) {
+            print_table(
+                elf_file,
+                &sections,
+                index,
+                &versions,
+                declarations.as_ref(),
+                options,
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+//! The symbols that cover a range of addresses, for going from crash
+//! addresses or profiler samples back to code and data.
+
+use anyhow::{Context, Result, bail};
+
+use crate::addr2line::parse_address;
+use crate::demangle;
+use crate::elf::ElfFile;
+use crate::section::{SHN_ABS, SHN_LORESERVE, SHN_UNDEF};
+use crate::strtab::SymbolTable;
+use crate::symbol::SymbolType;
+
+/// Parses `START-END`, the end excluded, or a single address, which stands
+/// for the byte there.
+pub fn parse_range(spec: &str) -> Result<(u64, u64)> {
+    let (start, end) = match spec.split_once('-') {
+        Some((start, end)) => (parse_address(start)?, parse_address(end)?),
+        None => {
+            let address = parse_address(spec)?;
+            (
+                address,
+                address.checked_add(1).context("Address out of range")?,
+            )
+        }
+    };
+    if end <= start {
+        bail!("The range {} ends before it starts", spec);
+    }
+    Ok((start, end))
+}
+
+/// Prints the symbols whose extent overlaps `start..end`, by address, with
+/// the section each is in. Symbols without a size count as covering
+/// their address alone.
+pub fn print_symbols_in(elf_file: &ElfFile, start: u64, end: u64) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    // Files without section headers still have their dynamic symbols, but
+    // no sections to place them in.
+    let tables = elf_file.section_headers_or_dynamic()?;
+    println!("Symbols in {:#x}-{:#x}:", start, end);
+    let Some(table) = elf_file.symbol_table(&tables) else {
+        println!("  There is no symbol table.");
+        return Ok(());
+    };
+    let symbols = SymbolTable::new(elf_file, &tables, table)?;
+
+    let mut found = Vec::new();
+    for (index, symbol) in symbols.iter().enumerate() {
+        let kind = symbol.symbol_type();
+        if symbol.st_shndx == SHN_UNDEF
+            || (symbol.st_shndx >= SHN_LORESERVE && symbol.st_shndx != SHN_ABS)
+            || matches!(
+                kind,
+                SymbolType::FILE | SymbolType::SECTION | SymbolType::TLS
+            )
+        {
+            continue;
+        }
+        let name = symbols.name(index)?;
+        if name.is_empty() {
+            continue;
+        }
+        let symbol_end = symbol.st_value.saturating_add(symbol.st_size.max(1));
+        if symbol.st_value < end && symbol_end > start {
+            let section = if symbol.st_shndx == SHN_ABS {
+                "ABS"
+            } else {
+                sections
+                    .get(symbol.st_shndx as usize)
+                    .and_then(|s| elf_file.section_name(&sections, s).ok())
+                    .unwrap_or("?")
+            };
+            found.push((symbol, name, section));
+        }
+    }
+    if found.is_empty() {
+        println!("  No symbol covers any of it.");
+        return Ok(());
+    }
+    found.sort_by(|(a, a_name, _), (b, b_name, _)| {
+        a.st_value
+            .cmp(&b.st_value)
+            .then(b.st_size.cmp(&a.st_size))
+            .then(a_name.cmp(b_name))
+    });
+
+    let width = if elf_file.is_64() { 16 } else { 8 };
+    println!(
+        "  {:<width$} {:>8} {:<7} {:<6} {:<20} Name",
+        "Address",
+        "Size",
+        "Type",
+        "Bind",
+        "Section",
+        width = width
+    );
+    for (symbol, name, section) in found {
+        let demangled = demangle::demangle(name);
+        let name = if demangled != name {
+            format!("{} [{}]", name, demangled)
+        } else {
+            name.to_string()
+        };
+        // Where the range starts inside a symbol that begins before it.
+        let offset = if symbol.st_value < start {
+            format!(" (the range starts at +{:#x})", start - symbol.st_value)
+        } else {
+            String::new()
+        };
+        println!(
+            "  {:0width$x} {:>8} {:<7} {:<6} {:<20} {}{}",
+            symbol.st_value,
+            symbol.st_size,
+            symbol.symbol_type().to_string(),
+            symbol.binding().to_string(),
+            section,
+            name,
+            offset,
+            width = width
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,803 @@
+use anyhow::Result;
+use gimli::{
+    BaseAddresses, CfaRule, CieOrFde, DebugFrame, DwEhPe, EhFrame, EhFrameHdr, EndianSlice,
+    FrameDescriptionEntry, Pointer, Register, RegisterRule, UnwindContext, UnwindSection,
+};
+
+use crate::dwarf::{self, Reader};
+use crate::dwarfexpr;
+use crate::elf::{ElfFile, ElfType};
+use crate::emachine::EMachine;
+use crate::section::{SHF_EXECINSTR, SHN_UNDEF, SectionHeader};
+use crate::segment::SegmentType;
+use crate::strtab::SymbolTable;
+use crate::symbol::SymbolType;
+use crate::validate::Finding;
+
+/// Below this share of code covered by FDEs, backtraces through the rest
+/// are cut short often enough to matter.
+const GOOD_COVERAGE: f64 = 0.9;
+
+/// The address ranges described by the FDEs of a call frame section, or
+/// `None` if it does not parse.
+fn fde_ranges<'a, S: UnwindSection<Reader<'a>>>(
+    section: &S,
+    bases: &BaseAddresses,
+) -> Option<Vec<(u64, u64)>> {
+    let mut ranges = Vec::new();
+    let mut entries = section.entries(bases);
+    while let Some(entry) = entries.next().ok()? {
+        if let CieOrFde::Fde(partial) = entry {
+            let fde = partial
+                .parse(|section, bases, offset| section.cie_from_offset(bases, offset))
+                .ok()?;
+            ranges.push((fde.initial_address(), fde.end_address()));
+        }
+    }
+    Some(ranges)
+}
+
+/// How many bytes of the code sections the ranges cover.
+fn covered(code: &[(u64, u64)], mut ranges: Vec<(u64, u64)>) -> u64 {
+    ranges.sort();
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    code.iter()
+        .flat_map(|&(start, end)| {
+            merged
+                .iter()
+                .map(move |&(s, e)| e.min(end).saturating_sub(s.max(start)))
+        })
+        .fold(0, u64::saturating_add)
+}
+
+/// Whether a function starts by setting up a frame pointer, judged from
+/// its first instructions, or `None` for architectures this does not know.
+fn sets_frame_pointer(machine: EMachine, code: &[u8]) -> Option<bool> {
+    let window = &code[..code.len().min(16)];
+    match machine {
+        // mov %rsp,%rbp, after push %rbp and possibly endbr64.
+        EMachine::X8664 => Some(window.windows(3).any(|w| w == [0x48, 0x89, 0xe5])),
+        // mov %esp,%ebp
+        EMachine::I386 => Some(window.windows(2).any(|w| w == [0x89, 0xe5])),
+        // mov x29, sp, after the stp that saves the frame record.
+        EMachine::Aarch64 => Some(
+            code[..code.len().min(32)]
+                .chunks_exact(4)
+                .any(|i| u32::from_ne_bytes(i.try_into().unwrap()) == 0x910003fd),
+        ),
+        _ => None,
+    }
+}
+
+/// Counts the functions that set up a frame pointer, out of those that
+/// could be checked.
+fn frame_pointers(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+) -> Result<Option<(usize, usize)>> {
+    let Some(symtab) = elf_file.symbol_table(sections) else {
+        return Ok(None);
+    };
+    let symbols = SymbolTable::new(elf_file, sections, symtab)?;
+    let data = elf_file.data();
+    let (mut with, mut checked) = (0, 0);
+
+    for symbol in symbols.iter() {
+        if symbol.symbol_type() != SymbolType::FUNC
+            || symbol.st_shndx == SHN_UNDEF
+            || symbol.st_size == 0
+        {
+            continue;
+        }
+        let offset = if elf_file.elf_type() == ElfType::REL {
+            sections
+                .get(symbol.st_shndx as usize)
+                .and_then(|s| s.sh_offset.checked_add(symbol.st_value))
+        } else {
+            elf_file.vaddr_to_offset(symbol.st_value)?
+        };
+        let Some(code) = offset.and_then(|o| {
+            let end = o.saturating_add(symbol.st_size).min(data.len() as u64);
+            data.get(o as usize..end as usize)
+        }) else {
+            continue;
+        };
+        let Some(sets) = sets_frame_pointer(elf_file.machine(), code) else {
+            return Ok(None);
+        };
+        checked += 1;
+        with += sets as usize;
+    }
+
+    Ok((checked > 0).then_some((with, checked)))
+}
+
+/// The addresses that pointers in the call frame sections can be relative
+/// to.
+fn bases(elf_file: &ElfFile, sections: &[SectionHeader]) -> BaseAddresses {
+    let mut bases = BaseAddresses::default();
+    for (name, set) in [
+        (".eh_frame", BaseAddresses::set_eh_frame as fn(_, _) -> _),
+        (".eh_frame_hdr", BaseAddresses::set_eh_frame_hdr),
+        (".text", BaseAddresses::set_text),
+        (".got", BaseAddresses::set_got),
+    ] {
+        if let Some(section) = sections
+            .iter()
+            .find(|s| elf_file.section_name(sections, s).is_ok_and(|n| n == name))
+        {
+            bases = set(bases, section.sh_addr);
+        }
+    }
+    bases
+}
+
+/// Reports whether a profiler walking the stack would be able to get
+/// backtraces through this file: which unwind information it carries, how
+/// much of the code its call frame information covers, and whether the
+/// functions keep frame pointers.
+pub fn print_unwind_report(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let find = |name: &str| {
+        sections
+            .iter()
+            .find(|s| elf_file.section_name(&sections, s).is_ok_and(|n| n == name))
+    };
+    let endian = dwarf::endian(elf_file);
+    let address_size = if elf_file.is_64() { 8 } else { 4 };
+    let linked = elf_file.elf_type() != ElfType::REL;
+
+    let bases = bases(elf_file, &sections);
+    let code: Vec<(u64, u64)> = sections
+        .iter()
+        .filter(|s| s.is_alloc() && s.sh_flags & SHF_EXECINSTR != 0 && s.sh_size > 0)
+        .map(|s| (s.sh_addr, s.sh_addr.saturating_add(s.sh_size)))
+        .collect();
+    let code_size = code
+        .iter()
+        .map(|(start, end)| end - start)
+        .fold(0, u64::saturating_add);
+
+    println!("Unwind information:");
+
+    // FDEs only have their addresses once linked, so coverage means
+    // nothing in relocatable objects.
+    let describe = |ranges: &[(u64, u64)]| {
+        if linked && code_size > 0 {
+            let share = covered(&code, ranges.to_vec()) as f64 / code_size as f64;
+            (
+                format!(
+                    "{} FDEs covering {:.1}% of {:#x} bytes of code",
+                    ranges.len(),
+                    share * 100.0,
+                    code_size
+                ),
+                share,
+            )
+        } else {
+            (format!("{} FDEs", ranges.len()), 1.0)
+        }
+    };
+
+    let mut eh_frame = None;
+    match find(".eh_frame") {
+        Some(section) => {
+            let mut frame = EhFrame::new(elf_file.section_data(section)?, endian);
+            frame.set_address_size(address_size);
+            match fde_ranges(&frame, &bases) {
+                Some(ranges) => {
+                    let (text, share) = describe(&ranges);
+                    println!("  {:<18} {}", ".eh_frame:", text);
+                    eh_frame = Some(share);
+                }
+                None => println!("  {:<18} present but malformed", ".eh_frame:"),
+            }
+        }
+        None => println!("  {:<18} not present", ".eh_frame:"),
+    }
+
+    let segments = elf_file.program_headers()?;
+    let has_segment = |segment_type| segments.iter().any(|p| p.p_type == segment_type);
+    let mut search_table = false;
+    match find(".eh_frame_hdr") {
+        Some(section) => {
+            let data = elf_file.section_data(section)?;
+            let header = EhFrameHdr::new(data, endian);
+            // A corrupt fde_count can claim far more entries than fit after
+            // the 12-byte header, as pairs of 4-byte values.
+            let room = data.len().saturating_sub(12) / 8;
+            let table = header.parse(&bases, address_size).ok().map(|parsed| {
+                parsed.table().map_or(0, |t| {
+                    t.iter(&bases).take_while(Result::is_ok).take(room).count()
+                })
+            });
+            let segment = if has_segment(SegmentType::GNU_EH_FRAME) {
+                "PT_GNU_EH_FRAME"
+            } else {
+                "no PT_GNU_EH_FRAME, so unwinders will not find it"
+            };
+            match table {
+                Some(0) => println!("  {:<18} no search table ({})", ".eh_frame_hdr:", segment),
+                Some(entries) => {
+                    println!(
+                        "  {:<18} search table of {} entries ({})",
+                        ".eh_frame_hdr:", entries, segment
+                    );
+                    search_table = has_segment(SegmentType::GNU_EH_FRAME);
+                }
+                None => println!("  {:<18} present but malformed", ".eh_frame_hdr:"),
+            }
+        }
+        None if linked => println!(
+            "  {:<18} not present (link with --eh-frame-hdr)",
+            ".eh_frame_hdr:"
+        ),
+        None => println!("  {:<18} built by the linker", ".eh_frame_hdr:"),
+    }
+
+    let mut debug_frame = false;
+    match find(".debug_frame") {
+        Some(section) => {
+            let mut frame =
+                DebugFrame::from(EndianSlice::new(elf_file.section_data(section)?, endian));
+            frame.set_address_size(address_size);
+            match fde_ranges(&frame, &bases) {
+                Some(ranges) => {
+                    println!("  {:<18} {}", ".debug_frame:", describe(&ranges).0);
+                    debug_frame = !ranges.is_empty();
+                }
+                None => println!("  {:<18} present but malformed", ".debug_frame:"),
+            }
+        }
+        None => println!("  {:<18} not present", ".debug_frame:"),
+    }
+
+    let mut sframe = false;
+    match find(".sframe") {
+        Some(section) => {
+            let data = elf_file.section_data(section)?;
+            let magic = data
+                .get(..2)
+                .map(|m| u16::from_ne_bytes(m.try_into().unwrap()));
+            let functions = data
+                .get(8..12)
+                .map(|n| u32::from_ne_bytes(n.try_into().unwrap()));
+            match (magic, data.get(2), functions) {
+                (Some(0xdee2), Some(version), Some(functions)) => {
+                    println!(
+                        "  {:<18} version {}, {} functions",
+                        "SFrame:", version, functions
+                    );
+                    sframe = functions > 0;
+                }
+                _ => println!("  {:<18} present but malformed", "SFrame:"),
+            }
+        }
+        None => println!("  {:<18} not present", "SFrame:"),
+    }
+
+    let frame_pointers = frame_pointers(elf_file, &sections)?;
+    match frame_pointers {
+        Some((with, checked)) => println!(
+            "  {:<18} set up by {} of {} functions",
+            "Frame pointers:", with, checked
+        ),
+        None => println!("  {:<18} unknown", "Frame pointers:"),
+    }
+
+    let mut methods = Vec::new();
+    match eh_frame {
+        Some(share) if search_table || !linked => {
+            if share < GOOD_COVERAGE {
+                println!("  Code without FDEs will cut backtraces short.");
+                methods.push(format!(
+                    "DWARF call frame information for {:.0}% of the code",
+                    share * 100.0
+                ));
+            } else {
+                methods.push(
+                    "DWARF call frame information (perf --call-graph=dwarf, libunwind)".to_string(),
+                );
+            }
+        }
+        Some(_) => methods
+            .push("DWARF call frame information, if the unwinder scans .eh_frame".to_string()),
+        None => {}
+    }
+    if sframe {
+        methods.push("SFrame".to_string());
+    }
+    if let Some((with, checked)) = frame_pointers
+        && with * 2 >= checked
+    {
+        methods.push("frame pointers (perf --call-graph=fp)".to_string());
+    }
+    if methods.is_empty() && debug_frame {
+        methods.push(".debug_frame, for unwinders that read it from the file".to_string());
+    }
+
+    println!();
+    if methods.is_empty() {
+        let frame_pointers = if frame_pointers.is_some() {
+            "most functions omit the frame pointer"
+        } else {
+            "frame pointers could not be checked"
+        };
+        println!(
+            "Profilable with backtraces: no, there is no unwind information and {}",
+            frame_pointers
+        );
+    } else {
+        println!(
+            "Profilable with backtraces: yes, with {}",
+            methods.join("; ")
+        );
+    }
+
+    Ok(())
+}
+
+/// A pointer encoding of `.eh_frame_hdr`, by the names of its parts.
+fn pointer_encoding(encoding: u8) -> String {
+    let encoding = DwEhPe(encoding);
+    if encoding == gimli::DW_EH_PE_omit {
+        return "DW_EH_PE_omit".to_string();
+    }
+    let mut parts = Vec::new();
+    if encoding.is_indirect() {
+        parts.push("DW_EH_PE_indirect".to_string());
+    }
+    if encoding.application() != gimli::DW_EH_PE_absptr {
+        parts.push(encoding.application().to_string());
+    }
+    parts.push(encoding.format().to_string());
+    format!("{} ({:#04x})", parts.join(" | "), encoding.0)
+}
+
+/// Checks the search table of `.eh_frame_hdr` against `.eh_frame`: that
+/// it is sorted, that every entry leads to an FDE for the address it is
+/// listed under, and that it lists every FDE. Unwinders trust the table
+/// blindly, so a tool that rewrites code or `.eh_frame` without redoing it
+/// breaks unwinding in ways that are hard to trace back.
+pub fn eh_frame_hdr_findings(elf_file: &ElfFile) -> Result<Vec<Finding>> {
+    let sections = elf_file.section_headers()?;
+    let find = |name: &str| {
+        sections
+            .iter()
+            .find(|s| elf_file.section_name(&sections, s).is_ok_and(|n| n == name))
+    };
+    let mut findings = Vec::new();
+    let (Some(hdr), Some(eh_frame)) = (find(".eh_frame_hdr"), find(".eh_frame")) else {
+        return Ok(findings);
+    };
+    let endian = dwarf::endian(elf_file);
+    let address_size = if elf_file.is_64() { 8 } else { 4 };
+    let bases = bases(elf_file, &sections);
+    let data = elf_file.section_data(hdr)?;
+
+    if data.first() != Some(&1) {
+        findings.push(Finding::error(
+            "eh-frame-hdr-version",
+            format!(
+                ".eh_frame_hdr has version {}, where unwinders only accept 1",
+                data.first().map_or(0, |&v| v)
+            ),
+        ));
+        return Ok(findings);
+    }
+    let parsed = match EhFrameHdr::new(data, endian).parse(&bases, address_size) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            findings.push(Finding::error(
+                "eh-frame-hdr-malformed",
+                format!(".eh_frame_hdr cannot be parsed: {}", e),
+            ));
+            return Ok(findings);
+        }
+    };
+
+    match parsed.eh_frame_ptr() {
+        Pointer::Direct(address) if address == eh_frame.sh_addr => {}
+        pointer => findings.push(Finding::error(
+            "eh-frame-hdr-pointer",
+            format!(
+                ".eh_frame_hdr points to {:#x} for .eh_frame, which is at {:#x}",
+                pointer.pointer(),
+                eh_frame.sh_addr
+            ),
+        )),
+    }
+    if !elf_file
+        .program_headers()?
+        .iter()
+        .any(|p| p.p_type == SegmentType::GNU_EH_FRAME && p.p_vaddr == hdr.sh_addr)
+    {
+        findings.push(Finding::error(
+            "eh-frame-hdr-segment",
+            "No PT_GNU_EH_FRAME segment maps .eh_frame_hdr, so unwinders cannot find it"
+                .to_string(),
+        ));
+    }
+
+    let table_encoding = data.get(3).copied().unwrap_or(0);
+    let Some(table) = parsed.table() else {
+        findings.push(Finding::warning(
+            "eh-frame-hdr-no-table",
+            ".eh_frame_hdr has no search table, so unwinders have to scan .eh_frame".to_string(),
+        ));
+        return Ok(findings);
+    };
+    // The only encoding with fixed-size entries that libgcc will search.
+    if table_encoding != 0x3b {
+        findings.push(Finding::warning(
+            "eh-frame-hdr-encoding",
+            format!(
+                "The search table is encoded as {}, where unwinders expect DW_EH_PE_datarel | DW_EH_PE_sdata4",
+                pointer_encoding(table_encoding)
+            ),
+        ));
+    }
+
+    let mut frame = EhFrame::new(elf_file.section_data(eh_frame)?, endian);
+    frame.set_address_size(address_size);
+    let mut previous = None;
+    let mut listed = 0;
+    let mut entries = table.iter(&bases);
+    while let Some((location, fde)) = entries.next()? {
+        listed += 1;
+        let location = location.pointer();
+        if previous.is_some_and(|p| p >= location) {
+            findings.push(Finding::error(
+                "eh-frame-hdr-unsorted",
+                format!(
+                    "Search table entry {} for {:#x} is out of order after {:#x}",
+                    listed - 1,
+                    location,
+                    previous.unwrap_or(0)
+                ),
+            ));
+        }
+        previous = Some(location);
+
+        let parsed_fde = table
+            .pointer_to_offset(fde)
+            .and_then(|offset| frame.fde_from_offset(&bases, offset, EhFrame::cie_from_offset));
+        match parsed_fde {
+            Ok(parsed) if parsed.initial_address() == location => {}
+            Ok(parsed) => findings.push(Finding::error(
+                "eh-frame-hdr-mismatch",
+                format!(
+                    "Search table entry for {:#x} leads to the FDE for {:#x}",
+                    location,
+                    parsed.initial_address()
+                ),
+            )),
+            Err(e) => findings.push(Finding::error(
+                "eh-frame-hdr-dangling",
+                format!(
+                    "Search table entry for {:#x} points to {:#x}, where there is no FDE ({})",
+                    location,
+                    fde.pointer(),
+                    e
+                ),
+            )),
+        }
+    }
+
+    if let Some(ranges) = fde_ranges(&frame, &bases) {
+        // Zero-sized FDEs, left behind for discarded code, need no entry.
+        let fdes = ranges.iter().filter(|(start, end)| end > start).count();
+        if listed < fdes {
+            findings.push(Finding::error(
+                "eh-frame-hdr-missing",
+                format!(
+                    "The search table has {} entries for {} FDEs, so some functions cannot be unwound",
+                    listed, fdes
+                ),
+            ));
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Prints the header and search table of `.eh_frame_hdr`, followed by the
+/// problems `eh_frame_hdr_findings` finds in them.
+pub fn print_eh_frame_hdr(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let Some(hdr) = sections.iter().find(|s| {
+        elf_file
+            .section_name(&sections, s)
+            .is_ok_and(|n| n == ".eh_frame_hdr")
+    }) else {
+        println!("There is no .eh_frame_hdr section in this file.");
+        return Ok(());
+    };
+    let data = elf_file.section_data(hdr)?;
+    let address_size = if elf_file.is_64() { 8 } else { 4 };
+    let bases = bases(elf_file, &sections);
+
+    println!(
+        ".eh_frame_hdr at {:#x} ({:#x} bytes):",
+        hdr.sh_addr, hdr.sh_size
+    );
+    if let [version, frame, count, table, ..] = *data {
+        println!("  {:<16} {}", "Version:", version);
+        println!("  {:<16} {}", "eh_frame_ptr:", pointer_encoding(frame));
+        println!("  {:<16} {}", "fde_count:", pointer_encoding(count));
+        println!("  {:<16} {}", "Table:", pointer_encoding(table));
+    }
+
+    if let Ok(parsed) = EhFrameHdr::new(data, dwarf::endian(elf_file)).parse(&bases, address_size) {
+        println!(
+            "  {:<16} {:#x}",
+            ".eh_frame at:",
+            parsed.eh_frame_ptr().pointer()
+        );
+        if let Some(table) = parsed.table() {
+            println!();
+            println!("  {:<18} FDE", "Initial location");
+            let mut entries = table.iter(&bases);
+            while let Ok(Some((location, fde))) = entries.next() {
+                println!("  {:<#18x} {:#x}", location.pointer(), fde.pointer());
+            }
+        }
+    }
+
+    let findings = eh_frame_hdr_findings(elf_file)?;
+    println!();
+    if findings.is_empty() {
+        println!("  The search table matches .eh_frame.");
+    }
+    for finding in findings {
+        println!("  {}", finding);
+    }
+    println!();
+
+    Ok(())
+}
+
+fn register(machine: EMachine, register: Register) -> String {
+    dwarfexpr::register_name(machine, register.0 as u64)
+        .map_or_else(|| format!("r{}", register.0), str::to_string)
+}
+
+/// The FDE of a call frame section that covers `address`.
+fn find_fde<'a, S: UnwindSection<Reader<'a>>>(
+    section: &S,
+    bases: &BaseAddresses,
+    address: u64,
+) -> Result<Option<FrameDescriptionEntry<Reader<'a>>>> {
+    let mut entries = section.entries(bases);
+    while let Some(entry) = entries.next()? {
+        if let CieOrFde::Fde(partial) = entry {
+            let fde =
+                partial.parse(|section, bases, offset| section.cie_from_offset(bases, offset))?;
+            if fde.contains(address) {
+                return Ok(Some(fde));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// A file's `.eh_frame` and `.debug_frame`, when it has them.
+type FrameSections<'a> = (Option<EhFrame<Reader<'a>>>, Option<DebugFrame<Reader<'a>>>);
+
+/// The call frame sections of a file, `.eh_frame` and `.debug_frame`.
+fn frame_sections<'a>(
+    elf_file: &'a ElfFile,
+    sections: &[SectionHeader],
+) -> Result<FrameSections<'a>> {
+    let find = |name: &str| {
+        sections
+            .iter()
+            .find(|s| elf_file.section_name(sections, s).is_ok_and(|n| n == name))
+    };
+    let endian = dwarf::endian(elf_file);
+    let address_size = if elf_file.is_64() { 8 } else { 4 };
+
+    let eh_frame = match find(".eh_frame") {
+        Some(section) => {
+            let mut frame = EhFrame::new(elf_file.section_data(section)?, endian);
+            frame.set_address_size(address_size);
+            Some(frame)
+        }
+        None => None,
+    };
+    let debug_frame = match find(".debug_frame") {
+        Some(section) => {
+            let mut frame =
+                DebugFrame::from(EndianSlice::new(elf_file.section_data(section)?, endian));
+            frame.set_address_size(address_size);
+            Some(frame)
+        }
+        None => None,
+    };
+    Ok((eh_frame, debug_frame))
+}
+
+/// How to step out of the frame an address is in, from the call frame
+/// information covering it.
+pub struct FrameRules {
+    /// The register and offset the CFA is computed from, or `None` when it
+    /// is given by a DWARF expression.
+    pub cfa: Option<(Register, i64)>,
+    pub registers: Vec<(Register, RegisterRule<usize>)>,
+    pub return_address: Register,
+}
+
+fn rules_at<'a, S: UnwindSection<Reader<'a>>>(
+    section: &S,
+    bases: &BaseAddresses,
+    address: u64,
+) -> Result<Option<FrameRules>> {
+    let Some(fde) = find_fde(section, bases, address)? else {
+        return Ok(None);
+    };
+    let mut context = UnwindContext::new();
+    let row = fde.unwind_info_for_address(section, bases, &mut context, address)?;
+    let cfa = match row.cfa() {
+        CfaRule::RegisterAndOffset { register, offset } => Some((*register, *offset)),
+        CfaRule::Expression(_) => None,
+    };
+    Ok(Some(FrameRules {
+        cfa,
+        registers: row.registers().cloned().collect(),
+        return_address: fde.cie().return_address_register(),
+    }))
+}
+
+/// The CFI rules in effect at `address`, from `.eh_frame` or, failing
+/// that, `.debug_frame`.
+pub fn frame_rules(elf_file: &ElfFile, address: u64) -> Result<Option<FrameRules>> {
+    let sections = elf_file.section_headers()?;
+    let bases = bases(elf_file, &sections);
+    let (eh_frame, debug_frame) = frame_sections(elf_file, &sections)?;
+    if let Some(frame) = eh_frame
+        && let Some(rules) = rules_at(&frame, &bases, address)?
+    {
+        return Ok(Some(rules));
+    }
+    match debug_frame {
+        Some(frame) => rules_at(&frame, &bases, address),
+        None => Ok(None),
+    }
+}
+
+/// Finds the FDE of a call frame section that covers `address` and runs
+/// its CFI program up to the address, printing the rules for finding the
+/// CFA and the caller's registers there. Returns whether there was one.
+fn print_rules_at<'a, S: UnwindSection<Reader<'a>>>(
+    section: &S,
+    name: &str,
+    bases: &BaseAddresses,
+    address: u64,
+    machine: EMachine,
+) -> Result<bool> {
+    let Some(fde) = find_fde(section, bases, address)? else {
+        return Ok(false);
+    };
+
+    let mut context = UnwindContext::new();
+    let row = fde.unwind_info_for_address(section, bases, &mut context, address)?;
+    let encoding = fde.cie().encoding();
+    let expression = |e: &gimli::UnwindExpression<usize>| -> Result<String> {
+        let bytes = e.get(section)?.0.slice();
+        Ok(format!(
+            "({})",
+            dwarfexpr::operations(bytes, encoding, machine)
+        ))
+    };
+    let return_address = fde.cie().return_address_register();
+
+    println!(
+        "  {:<12} {:#x}-{:#x} in {} (CIE at {:#x}, return address in {})",
+        "FDE:",
+        fde.initial_address(),
+        fde.end_address(),
+        name,
+        fde.cie().offset(),
+        register(machine, return_address)
+    );
+    println!(
+        "  {:<12} {:#x}-{:#x}",
+        "Rules for:",
+        row.start_address(),
+        row.end_address()
+    );
+    let cfa = match row.cfa() {
+        CfaRule::RegisterAndOffset {
+            register: r,
+            offset,
+        } => {
+            format!("{}{:+}", register(machine, *r), offset)
+        }
+        CfaRule::Expression(e) => expression(e)?,
+    };
+    println!("  {:<12} {}", "CFA:", cfa);
+
+    for (r, rule) in row.registers() {
+        let rule = match rule {
+            RegisterRule::Undefined => "undefined".to_string(),
+            RegisterRule::SameValue => "unchanged".to_string(),
+            RegisterRule::Offset(offset) => format!("saved at CFA{:+}", offset),
+            RegisterRule::ValOffset(offset) => format!("is CFA{:+}", offset),
+            RegisterRule::Register(other) => format!("saved in {}", register(machine, *other)),
+            RegisterRule::Expression(e) => format!("saved at {}", expression(e)?),
+            RegisterRule::ValExpression(e) => format!("is {}", expression(e)?),
+            RegisterRule::Architectural => "architecture-specific".to_string(),
+            RegisterRule::Constant(value) => format!("is {:#x}", value),
+        };
+        let note = if *r == return_address {
+            " (return address)"
+        } else {
+            ""
+        };
+        println!(
+            "  {:<12} {}{}",
+            format!("{}:", register(machine, *r)),
+            rule,
+            note
+        );
+    }
+    if !row.registers().any(|(r, _)| *r == return_address) {
+        println!(
+            "  {:<12} no rule; the return address is in {} itself",
+            format!("{}:", register(machine, return_address)),
+            register(machine, return_address)
+        );
+    }
+
+    Ok(true)
+}
+
+/// Evaluates the call frame information at `address`: how an unwinder
+/// stopped there would find the canonical frame address and recover the
+/// caller's registers. `.eh_frame` is preferred, as unwinders do, with
+/// `.debug_frame` as the fallback.
+pub fn print_cfa_at(elf_file: &ElfFile, address: u64) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let machine = elf_file.machine();
+    let bases = bases(elf_file, &sections);
+    let (eh_frame, debug_frame) = frame_sections(elf_file, &sections)?;
+
+    println!("Call frame information at {:#x}:", address);
+    if let Some(frame) = eh_frame
+        && print_rules_at(&frame, ".eh_frame", &bases, address, machine)?
+    {
+        return Ok(());
+    }
+    if let Some(frame) = debug_frame
+        && print_rules_at(&frame, ".debug_frame", &bases, address, machine)?
+    {
+        return Ok(());
+    }
+    println!("  No FDE covers this address, so unwinders cannot step out of it.");
+
+    Ok(())
+}
+
+/// Prints what `readelf -u` prints: readelf decodes the unwind tables of a
+/// few processors, which do not include x86, whose unwind information is
+/// the call frame information the other reports decode.
+pub fn print_unwind_info(elf_file: &ElfFile) {
+    match elf_file.machine() {
+        EMachine::I386 | EMachine::X8664 => {
+            println!("No processor specific unwind information to decode")
+        }
+        machine => {
+            println!();
+            println!(
+                "The decoding of unwind sections for machine type {} is not currently supported.",
+                machine
+            );
+        }
+    }
+}
@@ -0,0 +1,638 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::dynamic::{DF_TEXTREL, DynamicTag};
+use crate::elf::{ElfFile, ElfType};
+use crate::section::{SHF_EXECINSTR, SHF_TLS, SHF_WRITE, SectionHeader, SectionType};
+use crate::segment::{PF_W, PF_X, ProgramHeader, SegmentType};
+use crate::warning::Severity;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Level::Warning => write!(f, "warning"),
+            Level::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A single problem found by a lint pass. `rule` is a short stable
+/// identifier that scripts can match on.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub level: Level,
+    pub message: String,
+    /// File offset and length of the bytes the finding is about, if any.
+    pub region: Option<(u64, u64)>,
+}
+
+impl Finding {
+    pub fn error(rule: &'static str, message: String) -> Self {
+        Self {
+            rule,
+            level: Level::Error,
+            message,
+            region: None,
+        }
+    }
+
+    pub fn warning(rule: &'static str, message: String) -> Self {
+        Self {
+            rule,
+            level: Level::Warning,
+            message,
+            region: None,
+        }
+    }
+
+    fn at(mut self, offset: u64, length: u64) -> Self {
+        self.region = Some((offset, length));
+        self
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: [{}] {}", self.level, self.rule, self.message)
+    }
+}
+
+/// Runs all layout checks and returns the findings, most severe first.
+pub fn validate(elf_file: &ElfFile) -> Result<Vec<Finding>> {
+    let sections = elf_file.section_headers()?;
+    let segments = elf_file.program_headers()?;
+    let file_size = elf_file.data().len() as u64;
+
+    let names: Vec<String> = sections
+        .iter()
+        .enumerate()
+        .map(
+            |(index, section)| match elf_file.section_name(&sections, section) {
+                Ok(name) => format!("[{:>2}] {}", index, name),
+                Err(_) => format!("[{:>2}]", index),
+            },
+        )
+        .collect();
+
+    let mut findings: Vec<Finding> = elf_file
+        .warnings()
+        .iter()
+        .map(|warning| {
+            let finding = match warning.severity {
+                Severity::Warning => Finding::warning,
+                Severity::Error => Finding::error,
+            };
+            // The identification is the first 16 bytes.
+            let rule = if warning.offset < 16 {
+                "ident"
+            } else {
+                "header"
+            };
+            finding(rule, warning.message.clone()).at(warning.offset, 0)
+        })
+        .collect();
+
+    check_sections_in_file(&sections, &names, file_size, &mut findings);
+    check_section_overlaps(&sections, &names, &mut findings);
+    check_section_links(&sections, &names, &mut findings);
+    check_string_table(elf_file, &sections, &names, &mut findings);
+    check_section_mapping(&sections, &segments, &names, &mut findings);
+
+    for (index, segment) in segments.iter().enumerate() {
+        if segment.p_offset.saturating_add(segment.p_filesz) > file_size {
+            findings.push(
+                Finding::error(
+                    "segment-past-eof",
+                    format!(
+                        "Segment {} ({}) extends past the end of the file",
+                        index, segment.p_type
+                    ),
+                )
+                .at(segment.p_offset, segment.p_filesz),
+            );
+        }
+
+        if segment.p_type != SegmentType::LOAD || segment.p_align <= 1 {
+            continue;
+        }
+
+        if !segment.p_align.is_power_of_two() {
+            findings.push(Finding::error(
+                "segment-alignment",
+                format!(
+                    "Segment {} has an alignment of {:#x}, which is not a power of two",
+                    index, segment.p_align
+                ),
+            ));
+        } else if segment.p_offset % segment.p_align != segment.p_vaddr % segment.p_align {
+            findings.push(Finding::error(
+                "segment-alignment",
+                format!(
+                    "Segment {} has p_offset {:#x} and p_vaddr {:#x} not congruent modulo p_align {:#x}",
+                    index, segment.p_offset, segment.p_vaddr, segment.p_align
+                ),
+            ));
+        }
+    }
+
+    findings.extend(interpreter(elf_file)?);
+    findings.extend(security(elf_file)?);
+    sort(&mut findings);
+    Ok(findings)
+}
+
+/// Checks that the file asks for a program interpreter when, and only
+/// when, it needs one, and that the request is well-formed.
+pub fn interpreter(elf_file: &ElfFile) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    let segments = elf_file.program_headers()?;
+    let interp = segments
+        .iter()
+        .position(|s| s.p_type == SegmentType::INTERP);
+
+    match elf_file.elf_type() {
+        ElfType::REL => {
+            let sections = elf_file.section_headers()?;
+            if interp.is_some()
+                || sections.iter().any(|s| {
+                    elf_file
+                        .section_name(&sections, s)
+                        .is_ok_and(|n| n == ".interp")
+                })
+            {
+                findings.push(Finding::warning(
+                    "unexpected-interpreter",
+                    "The relocatable object names a program interpreter, which only means something in a linked executable"
+                        .to_string(),
+                ));
+            }
+        }
+        ElfType::EXEC | ElfType::DYN if interp.is_none() => {
+            let executable = elf_file.elf_type() == ElfType::EXEC || elf_file.is_pie()?;
+            if executable && !elf_file.dynamic_strings(DynamicTag::NEEDED)?.is_empty() {
+                findings.push(Finding::warning(
+                    "missing-interpreter",
+                    "The executable needs shared libraries but has no PT_INTERP, so nothing will load them"
+                        .to_string(),
+                ));
+            }
+        }
+        _ => {}
+    }
+
+    let Some(index) = interp else {
+        return Ok(findings);
+    };
+    let segment = &segments[index];
+    if segments[..index]
+        .iter()
+        .any(|s| s.p_type == SegmentType::LOAD)
+    {
+        findings.push(Finding::warning(
+            "interpreter-order",
+            format!(
+                "PT_INTERP (segment {}) follows a PT_LOAD segment, but must precede them",
+                index
+            ),
+        ));
+    }
+    if !elf_file.segment_data(segment)?.contains(&0) {
+        findings.push(
+            Finding::error(
+                "interpreter-unterminated",
+                "The interpreter path in PT_INTERP is not NUL-terminated".to_string(),
+            )
+            .at(segment.p_offset, segment.p_filesz),
+        );
+    }
+
+    Ok(findings)
+}
+
+/// Fully orders the findings, most severe first, so that reports do not
+/// depend on the order the checks happen to run in.
+fn sort(findings: &mut [Finding]) {
+    findings.sort_by(|a, b| {
+        (b.level, a.rule, &a.message, a.region).cmp(&(a.level, b.rule, &b.message, b.region))
+    });
+}
+
+/// Flags properties that weaken the memory protections of the loaded image:
+/// text relocations, an executable stack and writable+executable segments.
+/// Only linked executables and shared objects are loaded that way: objects
+/// have no segments yet and core dumps never have PT_GNU_STACK.
+pub fn security(elf_file: &ElfFile) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    if !matches!(elf_file.elf_type(), ElfType::EXEC | ElfType::DYN) {
+        return Ok(findings);
+    }
+
+    let dynamic = elf_file.dynamic_entries()?;
+    let textrel = dynamic.iter().any(|e| {
+        e.d_tag == DynamicTag::TEXTREL
+            || (e.d_tag == DynamicTag::FLAGS && e.d_val & DF_TEXTREL != 0)
+    });
+    if textrel {
+        findings.push(Finding::warning(
+            "textrel",
+            "The file has text relocations (DT_TEXTREL): the dynamic loader must make code pages writable to patch them"
+                .to_string(),
+        ));
+    }
+
+    let segments = elf_file.program_headers()?;
+    match segments.iter().find(|s| s.p_type == SegmentType::GNU_STACK) {
+        Some(stack) if stack.is_executable() => findings.push(Finding::warning(
+            "executable-stack",
+            "PT_GNU_STACK requests an executable stack".to_string(),
+        )),
+        None if segments.iter().any(|s| s.p_type == SegmentType::LOAD) => findings
+            .push(Finding::warning(
+            "executable-stack",
+            "There is no PT_GNU_STACK segment, so most platforms default to an executable stack"
+                .to_string(),
+        )),
+        _ => {}
+    }
+
+    for (index, segment) in segments.iter().enumerate() {
+        if segment.p_type == SegmentType::GNU_STACK
+            || segment.p_flags & (PF_W | PF_X) != PF_W | PF_X
+        {
+            continue;
+        }
+        findings.push(
+            Finding::warning(
+                "rwx-segment",
+                format!(
+                    "Segment {} ({}) at {:#x} is mapped both writable and executable",
+                    index, segment.p_type, segment.p_vaddr
+                ),
+            )
+            .at(segment.p_offset, segment.p_filesz),
+        );
+    }
+
+    sort(&mut findings);
+    Ok(findings)
+}
+
+/// Prints the `security` findings as a block that stands out from the rest
+/// of the report. Nothing is printed for a clean file.
+pub fn print_security_warnings(findings: &[Finding]) {
+    if findings.is_empty() {
+        return;
+    }
+
+    println!("Security warnings:");
+    for finding in findings {
+        println!("  !! {}", finding);
+    }
+    println!();
+}
+
+fn check_sections_in_file(
+    sections: &[SectionHeader],
+    names: &[String],
+    file_size: u64,
+    findings: &mut Vec<Finding>,
+) {
+    for (index, section) in sections.iter().enumerate() {
+        if section.has_file_data() && section.sh_offset.saturating_add(section.sh_size) > file_size
+        {
+            findings.push(
+                Finding::error(
+                    "section-past-eof",
+                    format!("Section {} extends past the end of the file", names[index]),
+                )
+                .at(section.sh_offset, section.sh_size),
+            );
+        }
+    }
+}
+
+/// Checks that every allocated section is loaded by a PT_LOAD segment,
+/// from the file offset the section header gives, and with the permissions
+/// its flags ask for. Files without loadable segments are not checked.
+fn check_section_mapping(
+    sections: &[SectionHeader],
+    segments: &[ProgramHeader],
+    names: &[String],
+    findings: &mut Vec<Finding>,
+) {
+    let loads: Vec<(usize, &ProgramHeader)> = segments
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.p_type == SegmentType::LOAD)
+        .collect();
+    if loads.is_empty() {
+        return;
+    }
+
+    for (index, section) in sections.iter().enumerate() {
+        // The TLS .tbss takes no space in the loaded image: each thread
+        // gets its own copy.
+        let tbss = section.sh_flags & SHF_TLS != 0 && !section.has_file_data();
+        if !section.is_alloc() || section.sh_size == 0 || tbss {
+            continue;
+        }
+        let start = section.sh_addr;
+        let end = start.saturating_add(section.sh_size);
+
+        let Some(&(segment_index, segment)) = loads.iter().find(|(_, s)| {
+            start >= s.p_vaddr
+                && s.p_vaddr
+                    .checked_add(s.p_memsz)
+                    .is_some_and(|end| start < end)
+        }) else {
+            findings.push(
+                Finding::error(
+                    "section-not-loaded",
+                    format!(
+                        "Section {} at {:#x} is allocated but no PT_LOAD segment maps it",
+                        names[index], start
+                    ),
+                )
+                .at(section.sh_offset, section.sh_size),
+            );
+            continue;
+        };
+
+        // Bytes from the file must lie in the part of the segment that
+        // is read from the file, at the matching offset.
+        let (limit, part) = if section.has_file_data() {
+            (
+                segment.p_vaddr.saturating_add(segment.p_filesz),
+                "file contents",
+            )
+        } else {
+            (segment.p_vaddr.saturating_add(segment.p_memsz), "memory")
+        };
+        if end > limit {
+            findings.push(
+                Finding::error(
+                    "section-not-loaded",
+                    format!(
+                        "Section {} ends at {:#x}, past the {} of segment {} (to {:#x})",
+                        names[index], end, part, segment_index, limit
+                    ),
+                )
+                .at(section.sh_offset, section.sh_size),
+            );
+        }
+        if section.has_file_data()
+            && section.sh_offset.wrapping_sub(segment.p_offset) != start - segment.p_vaddr
+        {
+            findings.push(
+                Finding::error(
+                    "section-segment-offset",
+                    format!(
+                        "Section {} is at offset {:#x} but segment {} loads address {:#x} from offset {:#x}",
+                        names[index],
+                        section.sh_offset,
+                        segment_index,
+                        start,
+                        segment.p_offset.wrapping_add(start - segment.p_vaddr)
+                    ),
+                )
+                .at(section.sh_offset, section.sh_size),
+            );
+        }
+
+        let writable = section.sh_flags & SHF_WRITE != 0;
+        let executable = section.sh_flags & SHF_EXECINSTR != 0;
+        let mut problems = Vec::new();
+        if writable && !segment.is_writable() {
+            problems.push("is writable but mapped read-only");
+        }
+        if executable && !segment.is_executable() {
+            problems.push("holds code but is mapped without execute permission");
+        }
+        if executable && !writable && segment.is_writable() {
+            problems.push("holds code but is mapped writable");
+        }
+        for problem in problems {
+            findings.push(
+                Finding::warning(
+                    "section-permissions",
+                    format!(
+                        "Section {} {} (segment {} is {})",
+                        names[index],
+                        problem,
+                        segment_index,
+                        segment.flags_string().trim_end()
+                    ),
+                )
+                .at(section.sh_offset, section.sh_size),
+            );
+        }
+    }
+}
+
+fn check_section_overlaps(
+    sections: &[SectionHeader],
+    names: &[String],
+    findings: &mut Vec<Finding>,
+) {
+    let in_file = |s: &SectionHeader| s.has_file_data() && s.sh_size > 0;
+    report_overlaps(
+        sections,
+        names,
+        in_file,
+        |s| s.sh_offset,
+        "section-overlap",
+        "the file",
+        findings,
+    );
+
+    // .tbss legitimately shares addresses with whatever follows it, and
+    // sections of relocatable objects are not yet assigned addresses.
+    let in_memory = |s: &SectionHeader| {
+        let tbss = s.sh_flags & SHF_TLS != 0 && !s.has_file_data();
+        s.is_alloc() && s.sh_size > 0 && s.sh_addr != 0 && !tbss
+    };
+    report_overlaps(
+        sections,
+        names,
+        in_memory,
+        |s| s.sh_addr,
+        "section-overlap",
+        "memory",
+        findings,
+    );
+}
+
+fn report_overlaps(
+    sections: &[SectionHeader],
+    names: &[String],
+    filter: impl Fn(&SectionHeader) -> bool,
+    start: impl Fn(&SectionHeader) -> u64,
+    rule: &'static str,
+    space: &str,
+    findings: &mut Vec<Finding>,
+) {
+    let mut ranges: Vec<(u64, u64, usize)> = sections
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| filter(s))
+        .map(|(i, s)| (start(s), start(s).saturating_add(s.sh_size), i))
+        .collect();
+    ranges.sort();
+
+    // Compare each range against the one reaching furthest so far, so that a
+    // large section swallowing several smaller ones is reported for each.
+    let mut furthest: Option<(u64, usize)> = None;
+    for (begin, end, index) in ranges {
+        if let Some((furthest_end, other)) = furthest {
+            if begin < furthest_end {
+                let section = &sections[index];
+                let mut finding = Finding::error(
+                    rule,
+                    format!(
+                        "Sections {} and {} overlap in {}",
+                        names[other], names[index], space
+                    ),
+                );
+                if section.has_file_data() {
+                    finding = finding.at(section.sh_offset, section.sh_size);
+                }
+                findings.push(finding);
+            }
+            if end <= furthest_end {
+                continue;
+            }
+        }
+        furthest = Some((end, index));
+    }
+}
+
+fn check_section_links(sections: &[SectionHeader], names: &[String], findings: &mut Vec<Finding>) {
+    for (index, section) in sections.iter().enumerate().skip(1) {
+        let link = section.sh_link as usize;
+        if link >= sections.len() {
+            findings.push(Finding::error(
+                "section-link-range",
+                format!(
+                    "Section {} has sh_link {} but there are only {} sections",
+                    names[index],
+                    link,
+                    sections.len()
+                ),
+            ));
+            continue;
+        }
+
+        // Walk the sh_link chain; any chain longer than the section count
+        // must revisit a section.
+        let mut current = index;
+        let mut steps = 0;
+        while let Some(next) = sections.get(current).map(|s| s.sh_link as usize) {
+            if next == 0 || next >= sections.len() {
+                break;
+            }
+            if next == index || steps > sections.len() {
+                findings.push(Finding::error(
+                    "section-link-cycle",
+                    format!("Section {} has an sh_link chain that loops", names[index]),
+                ));
+                break;
+            }
+            current = next;
+            steps += 1;
+        }
+    }
+}
+
+fn check_string_table(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    names: &[String],
+    findings: &mut Vec<Finding>,
+) {
+    if sections.is_empty() {
+        return;
+    }
+
+    let Some(shstrndx) = elf_file.shstrndx(sections) else {
+        findings.push(Finding::warning(
+            "shstrtab-missing",
+            "File has sections but no section header string table".to_string(),
+        ));
+        return;
+    };
+
+    let Some(strtab) = sections.get(shstrndx) else {
+        findings.push(Finding::error(
+            "shstrtab-range",
+            format!(
+                "Section header string table index {} is out of range ({} sections)",
+                shstrndx,
+                sections.len()
+            ),
+        ));
+        return;
+    };
+
+    if strtab.sh_type != SectionType::STRTAB {
+        findings.push(Finding::error(
+            "shstrtab-type",
+            format!(
+                "Section header string table {} is not of type STRTAB",
+                names[shstrndx]
+            ),
+        ));
+    }
+
+    for (index, section) in sections.iter().enumerate().skip(1) {
+        if section.sh_name as u64 >= strtab.sh_size {
+            findings.push(Finding::error(
+                "section-name-range",
+                format!(
+                    "Section {} has name offset {:#x} past the end of the string table ({:#x} bytes)",
+                    names[index],
+                    section.sh_name,
+                    strtab.sh_size
+                ),
+            ));
+        }
+    }
+}
+
+/// Whether the findings still allow the file to be considered well-formed.
+pub fn passed(findings: &[Finding], exit_on_warning: bool) -> bool {
+    let threshold = if exit_on_warning {
+        Level::Warning
+    } else {
+        Level::Error
+    };
+    findings.iter().all(|f| f.level < threshold)
+}
+
+pub fn print_findings(findings: &[Finding]) {
+    let errors = findings.iter().filter(|f| f.level == Level::Error).count();
+    let warnings = findings.len() - errors;
+
+    if findings.is_empty() {
+        println!("Validation passed: no problems found.");
+        return;
+    }
+
+    println!("Validation findings:");
+    for finding in findings {
+        println!("  {}", finding);
+    }
+
+    println!(
+        "Validation {}: {} error(s), {} warning(s)",
+        if errors == 0 { "passed" } else { "failed" },
+        errors,
+        warnings
+    );
+}
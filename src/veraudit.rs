@@ -0,0 +1,244 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+
+use crate::dynamic::DynamicTag;
+use crate::elf::ElfFile;
+use crate::section::{SHN_UNDEF, SectionType};
+use crate::strtab::SymbolTable;
+use crate::validate::Finding;
+use crate::version::{
+    self, VER_FLG_BASE, VER_FLG_WEAK, VERSYM_GLOBAL, VERSYM_HIDDEN, VERSYM_LOCAL, VersionTables,
+};
+
+/// Checks the symbol versioning tables for the mistakes that make the
+/// dynamic loader fail at run time, usually with an unhelpful "version not
+/// found": requirements it cannot match, definitions that clash and
+/// symbols tagged with versions that do not exist.
+pub fn audit(elf_file: &ElfFile) -> Result<Vec<Finding>> {
+    let sections = elf_file.section_headers_or_dynamic()?;
+    let definitions = version::definitions(elf_file, &sections)?;
+    let requirements = version::requirements(elf_file, &sections)?;
+    let tables = VersionTables::new(elf_file, &sections)?;
+    let mut findings = Vec::new();
+
+    // Indexes are shared between definitions and requirements, and the
+    // first two are reserved for local and global symbols.
+    let mut owners: HashMap<u16, Vec<String>> = HashMap::new();
+    for definition in &definitions {
+        let name = definition.names.first().map_or("?", String::as_str);
+        owners
+            .entry(definition.index)
+            .or_default()
+            .push(format!("definition {}", name));
+    }
+    for requirement in &requirements {
+        owners
+            .entry(requirement.index)
+            .or_default()
+            .push(format!("{} from {}", requirement.name, requirement.file));
+        if requirement.index == VERSYM_LOCAL || requirement.index == VERSYM_GLOBAL {
+            findings.push(Finding::error(
+                "reserved-version-index",
+                format!(
+                    "Requirement {} from {} uses the reserved version index {}",
+                    requirement.name, requirement.file, requirement.index
+                ),
+            ));
+        }
+    }
+    let mut indexes: Vec<_> = owners.iter().filter(|(_, o)| o.len() > 1).collect();
+    indexes.sort();
+    for (index, users) in indexes {
+        findings.push(Finding::error(
+            "duplicate-version-index",
+            format!("Version index {} is used by {}", index, users.join(", ")),
+        ));
+    }
+
+    let mut defined = HashSet::new();
+    let mut versions = HashSet::new();
+    for definition in &definitions {
+        let Some(name) = definition.names.first() else {
+            findings.push(Finding::error(
+                "unnamed-definition",
+                format!("Version definition {} has no name", definition.index),
+            ));
+            continue;
+        };
+        // The base definition names the file, which version scripts
+        // often reuse as the name of its one version.
+        defined.insert(name.as_str());
+        if definition.flags & VER_FLG_BASE == 0 && !versions.insert(name.as_str()) {
+            findings.push(Finding::error(
+                "duplicate-definition",
+                format!("Version {} is defined more than once", name),
+            ));
+        }
+        if definition.hash != version::elf_hash(name) {
+            findings.push(Finding::error(
+                "version-hash",
+                format!(
+                    "Definition of {} has hash {:#x}, but the name hashes to {:#x}",
+                    name,
+                    definition.hash,
+                    version::elf_hash(name)
+                ),
+            ));
+        }
+    }
+    for definition in &definitions {
+        for parent in definition.names.iter().skip(1) {
+            if !defined.contains(parent.as_str()) {
+                findings.push(Finding::warning(
+                    "dangling-parent",
+                    format!(
+                        "Version {} inherits from {}, which the file does not define",
+                        definition.names[0], parent
+                    ),
+                ));
+            }
+        }
+    }
+    let bases = definitions
+        .iter()
+        .filter(|d| d.flags & VER_FLG_BASE != 0)
+        .count();
+    if !definitions.is_empty() && bases != 1 {
+        findings.push(Finding::warning(
+            "base-definition",
+            format!(
+                "There are {} base version definitions (VER_FLG_BASE), where one is expected",
+                bases
+            ),
+        ));
+    }
+
+    let needed = elf_file.dynamic_strings(DynamicTag::NEEDED)?;
+    let mut required = HashSet::new();
+    for requirement in &requirements {
+        if !required.insert((&requirement.file, &requirement.name)) {
+            findings.push(Finding::warning(
+                "duplicate-requirement",
+                format!(
+                    "Version {} from {} is required more than once",
+                    requirement.name, requirement.file
+                ),
+            ));
+        }
+        if !needed.contains(&requirement.file.as_str()) {
+            let finding = if requirement.flags & VER_FLG_WEAK != 0 {
+                Finding::warning
+            } else {
+                Finding::error
+            };
+            findings.push(finding(
+                "dangling-requirement",
+                format!(
+                    "Version {} is required from {}, which is not in DT_NEEDED",
+                    requirement.name, requirement.file
+                ),
+            ));
+        }
+        if requirement.hash != version::elf_hash(&requirement.name) {
+            findings.push(Finding::error(
+                "version-hash",
+                format!(
+                    "Requirement of {} from {} has hash {:#x}, but the name hashes to {:#x}",
+                    requirement.name,
+                    requirement.file,
+                    requirement.hash,
+                    version::elf_hash(&requirement.name)
+                ),
+            ));
+        }
+    }
+
+    let Some(dynsym) = sections.iter().find(|s| s.sh_type == SectionType::DYNSYM) else {
+        return Ok(findings);
+    };
+    let symbols = SymbolTable::new(elf_file, &sections, dynsym)?;
+    if tables.versym.is_empty() {
+        if !definitions.is_empty() || !requirements.is_empty() {
+            findings.push(Finding::error(
+                "missing-versym",
+                "The file has version definitions or requirements but no .gnu.version section"
+                    .to_string(),
+            ));
+        }
+        return Ok(findings);
+    }
+    if tables.versym.len() != symbols.len() {
+        findings.push(Finding::error(
+            "versym-count",
+            format!(
+                ".gnu.version has {} entries for {} dynamic symbols",
+                tables.versym.len(),
+                symbols.len()
+            ),
+        ));
+    }
+
+    let mut used = HashSet::new();
+    for (i, symbol) in symbols.iter().enumerate().skip(1) {
+        let Some(versym) = tables.versym(i) else {
+            break;
+        };
+        let index = versym & !VERSYM_HIDDEN;
+        used.insert(index);
+        if index == VERSYM_LOCAL || index == VERSYM_GLOBAL || owners.contains_key(&index) {
+            continue;
+        }
+        let what = if symbol.st_shndx == SHN_UNDEF {
+            "Undefined symbol"
+        } else {
+            "Symbol"
+        };
+        findings.push(Finding::error(
+            "unknown-version-index",
+            format!(
+                "{} {} is tagged with version index {}, which is neither defined nor required",
+                what,
+                symbols.name(i).unwrap_or("?"),
+                index
+            ),
+        ));
+    }
+    // glibc's GLIBC_ABI_* versions mark ABI requirements rather than
+    // symbols, so nothing is expected to use them.
+    for requirement in &requirements {
+        if !used.contains(&requirement.index) && !requirement.name.starts_with("GLIBC_ABI_") {
+            findings.push(Finding::warning(
+                "unused-requirement",
+                format!(
+                    "Version {} from {} is required but no symbol uses it",
+                    requirement.name, requirement.file
+                ),
+            ));
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Prints the findings of `audit` with a count of the versioning entries
+/// they are about.
+pub fn print_version_audit(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers_or_dynamic()?;
+    let definitions = version::definitions(elf_file, &sections)?.len();
+    let requirements = version::requirements(elf_file, &sections)?.len();
+    let findings = audit(elf_file)?;
+
+    println!(
+        "Version audit: {} definition(s), {} requirement(s)",
+        definitions, requirements
+    );
+    if findings.is_empty() {
+        println!("  No versioning problems found.");
+    }
+    for finding in findings {
+        println!("  {}", finding);
+    }
+    println!();
+
+    Ok(())
+}
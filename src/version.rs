@@ -0,0 +1,511 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+use crate::elf::{ElfFile, read_struct};
+use crate::section::{SectionHeader, SectionType};
+use crate::strtab::StringTable;
+
+/// Set in a `.gnu.version` entry when the symbol is a non-default version,
+/// which only references asking for that version explicitly can bind to.
+pub const VERSYM_HIDDEN: u16 = 0x8000;
+pub const VERSYM_LOCAL: u16 = 0;
+pub const VERSYM_GLOBAL: u16 = 1;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+struct Verdef {
+    vd_version: u16,
+    vd_flags: u16,
+    vd_ndx: u16,
+    vd_cnt: u16,
+    vd_hash: u32,
+    vd_aux: u32,
+    vd_next: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+struct Verdaux {
+    vda_name: u32,
+    vda_next: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+struct Verneed {
+    vn_version: u16,
+    vn_cnt: u16,
+    vn_file: u32,
+    vn_aux: u32,
+    vn_next: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+struct Vernaux {
+    vna_hash: u32,
+    vna_flags: u16,
+    vna_other: u16,
+    vna_name: u32,
+    vna_next: u32,
+}
+
+/// Set in the definition of the base version, which names the object
+/// itself.
+pub const VER_FLG_BASE: u16 = 1;
+/// Set in a requirement the loader only warns about when it is missing.
+pub const VER_FLG_WEAK: u16 = 2;
+/// Set in a definition or requirement that is there for information only.
+const VER_FLG_INFO: u16 = 4;
+
+/// A version a symbol can be tagged with: either one the object defines, or
+/// one it needs from the library named by `file`.
+#[derive(Debug, Clone)]
+pub struct Version {
+    pub name: String,
+    pub file: Option<String>,
+}
+
+/// An entry of `.gnu.version_d`, with the names of its auxiliary entries:
+/// the version's own, then those of the versions it inherits from.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub index: u16,
+    pub flags: u16,
+    pub hash: u32,
+    pub names: Vec<String>,
+}
+
+/// An auxiliary entry of `.gnu.version_r`: one version needed from `file`.
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    pub file: String,
+    pub name: String,
+    pub index: u16,
+    pub flags: u16,
+    pub hash: u32,
+}
+
+/// The hash of the SysV hash table, which version entries also store so
+/// that the dynamic loader can compare names quickly.
+pub fn elf_hash(name: &str) -> u32 {
+    let mut hash = 0u32;
+    for &byte in name.as_bytes() {
+        hash = (hash << 4).wrapping_add(byte as u32);
+        let high = hash & 0xf000_0000;
+        if high != 0 {
+            hash ^= high >> 24;
+        }
+        hash &= !high;
+    }
+    hash
+}
+
+fn version_strtab<'a>(
+    elf_file: &'a ElfFile,
+    sections: &[SectionHeader],
+    section: &SectionHeader,
+) -> Result<StringTable<'a>> {
+    let strtab = sections
+        .get(section.sh_link as usize)
+        .context("Version section links to a nonexistent string table")?;
+    Ok(StringTable::new(elf_file.section_data(strtab)?))
+}
+
+fn read_verdef(data: &[u8], strtab: StringTable, count: u32) -> Result<Vec<Definition>> {
+    let mut definitions = Vec::new();
+    let mut offset = 0u64;
+    for _ in 0..count {
+        let verdef: Verdef =
+            read_struct(data, offset).context("Version definition out of range")?;
+        let mut names = Vec::new();
+        let mut aux_offset = offset + verdef.vd_aux as u64;
+        for _ in 0..verdef.vd_cnt {
+            let aux: Verdaux =
+                read_struct(data, aux_offset).context("Version definition name out of range")?;
+            names.push(strtab.get(aux.vda_name as usize)?.to_string());
+            if aux.vda_next == 0 {
+                break;
+            }
+            aux_offset += aux.vda_next as u64;
+        }
+        definitions.push(Definition {
+            index: verdef.vd_ndx,
+            flags: verdef.vd_flags,
+            hash: verdef.vd_hash,
+            names,
+        });
+        if verdef.vd_next == 0 {
+            break;
+        }
+        offset += verdef.vd_next as u64;
+    }
+    Ok(definitions)
+}
+
+fn read_verneed(data: &[u8], strtab: StringTable, count: u32) -> Result<Vec<Requirement>> {
+    let mut requirements = Vec::new();
+    let mut offset = 0u64;
+    for _ in 0..count {
+        let verneed: Verneed =
+            read_struct(data, offset).context("Version requirement out of range")?;
+        let file = strtab.get(verneed.vn_file as usize)?;
+
+        let mut aux_offset = offset + verneed.vn_aux as u64;
+        for _ in 0..verneed.vn_cnt {
+            let aux: Vernaux =
+                read_struct(data, aux_offset).context("Version requirement entry out of range")?;
+            requirements.push(Requirement {
+                file: file.to_string(),
+                name: strtab.get(aux.vna_name as usize)?.to_string(),
+                index: aux.vna_other,
+                flags: aux.vna_flags,
+                hash: aux.vna_hash,
+            });
+            if aux.vna_next == 0 {
+                break;
+            }
+            aux_offset += aux.vna_next as u64;
+        }
+
+        if verneed.vn_next == 0 {
+            break;
+        }
+        offset += verneed.vn_next as u64;
+    }
+    Ok(requirements)
+}
+
+/// Every entry of the version definition sections, duplicates included.
+pub fn definitions(elf_file: &ElfFile, sections: &[SectionHeader]) -> Result<Vec<Definition>> {
+    let mut definitions = Vec::new();
+    for section in sections
+        .iter()
+        .filter(|s| s.sh_type == SectionType::GNU_VERDEF)
+    {
+        definitions.extend(read_verdef(
+            elf_file.section_data(section)?,
+            version_strtab(elf_file, sections, section)?,
+            section.sh_info,
+        )?);
+    }
+    Ok(definitions)
+}
+
+/// Every entry of the version requirement sections, duplicates included.
+pub fn requirements(elf_file: &ElfFile, sections: &[SectionHeader]) -> Result<Vec<Requirement>> {
+    let mut requirements = Vec::new();
+    for section in sections
+        .iter()
+        .filter(|s| s.sh_type == SectionType::GNU_VERNEED)
+    {
+        requirements.extend(read_verneed(
+            elf_file.section_data(section)?,
+            version_strtab(elf_file, sections, section)?,
+            section.sh_info,
+        )?);
+    }
+    Ok(requirements)
+}
+
+/// The symbol versioning tables of a file, taken from its `.gnu.version`,
+/// `.gnu.version_d` and `.gnu.version_r` sections.
+#[derive(Debug, Default)]
+pub struct VersionTables {
+    /// One entry per dynamic symbol; empty if the file is not versioned.
+    pub versym: Vec<u16>,
+    /// Version names by version index.
+    pub versions: HashMap<u16, Version>,
+}
+
+impl VersionTables {
+    pub fn new(elf_file: &ElfFile, sections: &[SectionHeader]) -> Result<Self> {
+        let mut tables = Self::default();
+
+        if let Some(section) = sections
+            .iter()
+            .find(|s| s.sh_type == SectionType::GNU_VERSYM)
+        {
+            tables.versym = elf_file
+                .section_data(section)?
+                .chunks_exact(2)
+                .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+                .collect();
+        }
+        // The first name of a definition is the version itself, the
+        // others its parents.
+        for definition in definitions(elf_file, sections)? {
+            if let Some(name) = definition.names.into_iter().next() {
+                tables
+                    .versions
+                    .insert(definition.index, Version { name, file: None });
+            }
+        }
+        for requirement in requirements(elf_file, sections)? {
+            tables.versions.insert(
+                requirement.index,
+                Version {
+                    name: requirement.name,
+                    file: Some(requirement.file),
+                },
+            );
+        }
+
+        Ok(tables)
+    }
+
+    /// The raw `.gnu.version` entry of dynamic symbol `index`, if the file
+    /// is versioned.
+    pub fn versym(&self, index: usize) -> Option<u16> {
+        self.versym.get(index).copied()
+    }
+
+    /// The version a dynamic symbol is tagged with, ignoring the local and
+    /// global base indexes.
+    pub fn version(&self, index: usize) -> Option<&Version> {
+        let versym = self.versym(index)? & !VERSYM_HIDDEN;
+        if versym == VERSYM_LOCAL || versym == VERSYM_GLOBAL {
+            return None;
+        }
+        self.versions.get(&versym)
+    }
+}
+
+/// How readelf describes the flags of a version definition or requirement.
+fn flags_name(flags: u16) -> String {
+    if flags == 0 {
+        return "none".to_string();
+    }
+    let mut names = Vec::new();
+    if flags & VER_FLG_BASE != 0 {
+        names.push("BASE");
+    }
+    if flags & VER_FLG_WEAK != 0 {
+        names.push("WEAK");
+    }
+    if flags & VER_FLG_INFO != 0 {
+        names.push("INFO");
+    }
+    if flags & !(VER_FLG_BASE | VER_FLG_WEAK | VER_FLG_INFO) != 0 {
+        names.push("<unknown>");
+    }
+    names.join(" | ")
+}
+
+/// An offset into a version section as readelf prints it, where C's `%#06x`
+/// leaves the `0x` off zero.
+fn entry_offset(offset: u64) -> String {
+    if offset == 0 {
+        "000000".to_string()
+    } else {
+        format!("{:#06x}", offset)
+    }
+}
+
+fn print_section_header(
+    elf_file: &ElfFile,
+    sections: &[SectionHeader],
+    section: &SectionHeader,
+    kind: &str,
+    count: u64,
+) {
+    let name = |s: &SectionHeader| elf_file.section_name(sections, s).unwrap_or("<corrupt>");
+    println!();
+    println!(
+        "Version {} section '{}' contains {} {}:",
+        kind,
+        name(section),
+        count,
+        if count == 1 { "entry" } else { "entries" }
+    );
+    println!(
+        " Addr: 0x{:016x}  Offset: 0x{:08x}  Link: {} ({})",
+        section.sh_addr,
+        section.sh_offset,
+        section.sh_link,
+        sections
+            .get(section.sh_link as usize)
+            .map_or("<corrupt>", name)
+    );
+}
+
+fn print_verdef(data: &[u8], strtab: StringTable, count: u32) {
+    let name = |offset: u32| strtab.get(offset as usize).unwrap_or("<corrupt>");
+    let mut offset = 0u64;
+    for _ in 0..count {
+        let Some(verdef) = read_struct::<Verdef>(data, offset) else {
+            println!("  Version definition past end of section");
+            break;
+        };
+        print!(
+            "  {}: Rev: {}  Flags: {}  Index: {}  Cnt: {}  ",
+            entry_offset(offset),
+            verdef.vd_version,
+            flags_name(verdef.vd_flags),
+            verdef.vd_ndx,
+            verdef.vd_cnt
+        );
+        let mut aux_offset = offset.saturating_add(verdef.vd_aux as u64);
+        match read_struct::<Verdaux>(data, aux_offset) {
+            Some(aux) => println!("Name: {}", name(aux.vda_name)),
+            None => println!(),
+        }
+        for parent in 1..verdef.vd_cnt {
+            let Some(aux) = read_struct::<Verdaux>(data, aux_offset) else {
+                break;
+            };
+            if aux.vda_next == 0 {
+                break;
+            }
+            aux_offset = aux_offset.saturating_add(aux.vda_next as u64);
+            let Some(aux) = read_struct::<Verdaux>(data, aux_offset) else {
+                println!("  Version definition aux past end of section");
+                break;
+            };
+            println!(
+                "  {}: Parent {}: {}",
+                entry_offset(aux_offset),
+                parent,
+                name(aux.vda_name)
+            );
+        }
+
+        if verdef.vd_next == 0 {
+            break;
+        }
+        offset = offset.saturating_add(verdef.vd_next as u64);
+    }
+}
+
+fn print_verneed(data: &[u8], strtab: StringTable, count: u32) {
+    let name = |offset: u32| strtab.get(offset as usize).unwrap_or("<corrupt>");
+    let mut offset = 0u64;
+    for _ in 0..count {
+        let Some(verneed) = read_struct::<Verneed>(data, offset) else {
+            println!("  Version need past end of section");
+            break;
+        };
+        println!(
+            "  {}: Version: {}  File: {}  Cnt: {}",
+            entry_offset(offset),
+            verneed.vn_version,
+            name(verneed.vn_file),
+            verneed.vn_cnt
+        );
+        let mut aux_offset = offset.saturating_add(verneed.vn_aux as u64);
+        for _ in 0..verneed.vn_cnt {
+            let Some(aux) = read_struct::<Vernaux>(data, aux_offset) else {
+                println!("  Version need aux past end of section");
+                break;
+            };
+            println!(
+                "  {}:   Name: {}  Flags: {}  Version: {}",
+                entry_offset(aux_offset),
+                name(aux.vna_name),
+                flags_name(aux.vna_flags),
+                aux.vna_other
+            );
+            if aux.vna_next == 0 {
+                break;
+            }
+            aux_offset = aux_offset.saturating_add(aux.vna_next as u64);
+        }
+
+        if verneed.vn_next == 0 {
+            break;
+        }
+        offset = offset.saturating_add(verneed.vn_next as u64);
+    }
+}
+
+fn print_versym(tables: &VersionTables) {
+    for (row, entries) in tables.versym.chunks(4).enumerate() {
+        print!("  {:03x}:", row * 4);
+        for &versym in entries {
+            match versym {
+                VERSYM_LOCAL => print!("   0 (*local*)    "),
+                VERSYM_GLOBAL => print!("   1 (*global*)   "),
+                _ => {
+                    let name = tables
+                        .versions
+                        .get(&(versym & !VERSYM_HIDDEN))
+                        .map_or("*invalid*", |version| &version.name);
+                    let hidden = if versym & VERSYM_HIDDEN != 0 {
+                        'h'
+                    } else {
+                        ' '
+                    };
+                    let entry = format!(
+                        "{:4x}{}({}{:<width$}",
+                        versym & !VERSYM_HIDDEN,
+                        hidden,
+                        name,
+                        ")",
+                        // C takes a negative width for a positive one.
+                        width = (12 - name.len() as isize).unsigned_abs()
+                    );
+                    print!("{:<18}", entry);
+                }
+            }
+        }
+        println!();
+    }
+}
+
+/// Prints the version sections in the style of `readelf -V`, in section
+/// order.
+pub fn print_version_info(elf_file: &ElfFile) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let mut found = false;
+    for section in &sections {
+        match section.sh_type {
+            SectionType::GNU_VERDEF => {
+                print_section_header(
+                    elf_file,
+                    &sections,
+                    section,
+                    "definition",
+                    section.sh_info as u64,
+                );
+                print_verdef(
+                    elf_file.section_data(section)?,
+                    version_strtab(elf_file, &sections, section)?,
+                    section.sh_info,
+                );
+            }
+            SectionType::GNU_VERNEED => {
+                print_section_header(
+                    elf_file,
+                    &sections,
+                    section,
+                    "needs",
+                    section.sh_info as u64,
+                );
+                print_verneed(
+                    elf_file.section_data(section)?,
+                    version_strtab(elf_file, &sections, section)?,
+                    section.sh_info,
+                );
+            }
+            SectionType::GNU_VERSYM => {
+                let tables = VersionTables::new(elf_file, &sections)?;
+                print_section_header(
+                    elf_file,
+                    &sections,
+                    section,
+                    "symbols",
+                    tables.versym.len() as u64,
+                );
+                print_versym(&tables);
+            }
+            _ => continue,
+        }
+        found = true;
+    }
+
+    if !found {
+        println!();
+        println!("No version information found in this file.");
+    }
+    Ok(())
+}
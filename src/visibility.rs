@@ -0,0 +1,221 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::demangle;
+use crate::dynamic::DynamicTag;
+use crate::elf::ElfFile;
+use crate::section::{SHN_ABS, SHN_UNDEF, SectionType};
+use crate::strtab::SymbolTable;
+use crate::symbol::{SymbolBinding, SymbolType, SymbolVisibility};
+
+/// Symbols the linker defines in every shared object, which are not the
+/// library's to hide.
+const LINKER_DEFINED: &[&str] = &["_init", "_fini", "_edata", "_end", "__bss_start"];
+
+/// A symbol the library exports.
+struct Export<'a> {
+    name: &'a str,
+    function: bool,
+    /// Dynamic relocations of the library itself that refer to it, which
+    /// go through the dynamic symbol table because it may be interposed.
+    internal: usize,
+}
+
+fn exports<'a>(elf_file: &'a ElfFile) -> Result<Vec<Export<'a>>> {
+    let sections = elf_file.section_headers()?;
+    let Some(dynsym) = sections.iter().find(|s| s.sh_type == SectionType::DYNSYM) else {
+        return Ok(Vec::new());
+    };
+    let symbols = SymbolTable::new(elf_file, &sections, dynsym)?;
+
+    let mut references: HashMap<u32, usize> = HashMap::new();
+    for section in &sections {
+        if section.is_alloc() && ElfFile::is_relocation_section(section) {
+            for reloc in elf_file.decoded_relocations(section)? {
+                *references.entry(reloc.r_sym).or_default() += 1;
+            }
+        }
+    }
+
+    let mut exports = Vec::new();
+    for (index, symbol) in symbols.iter().enumerate() {
+        let name = symbols.name(index)?;
+        let exported = matches!(
+            symbol.binding(),
+            SymbolBinding::GLOBAL | SymbolBinding::WEAK | SymbolBinding::GNU_UNIQUE
+        ) && matches!(
+            symbol.visibility(),
+            SymbolVisibility::DEFAULT | SymbolVisibility::PROTECTED
+        );
+        // Version definitions show up as absolute symbols at 0.
+        let version_node = symbol.st_shndx == SHN_ABS && symbol.st_value == 0;
+        if !exported
+            || name.is_empty()
+            || symbol.st_shndx == SHN_UNDEF
+            || version_node
+            || LINKER_DEFINED.contains(&name)
+        {
+            continue;
+        }
+        exports.push(Export {
+            name,
+            function: matches!(
+                symbol.symbol_type(),
+                SymbolType::FUNC | SymbolType::GNU_IFUNC
+            ),
+            internal: references.get(&(index as u32)).copied().unwrap_or(0),
+        });
+    }
+    exports.sort_by(|a, b| a.name.cmp(b.name));
+    Ok(exports)
+}
+
+/// The symbols a consumer imports, and whether it lists `soname` among the
+/// libraries it needs.
+fn imports(path: &PathBuf, soname: &str) -> Result<(HashSet<String>, bool)> {
+    let consumer = ElfFile::open(path)
+        .with_context(|| format!("Failed to read consumer {}", path.display()))?;
+    let sections = consumer.section_headers()?;
+    let mut imports = HashSet::new();
+    if let Some(dynsym) = sections.iter().find(|s| s.sh_type == SectionType::DYNSYM) {
+        let symbols = SymbolTable::new(&consumer, &sections, dynsym)?;
+        for (index, symbol) in symbols.iter().enumerate() {
+            if symbol.st_shndx == SHN_UNDEF {
+                imports.insert(symbols.name(index)?.to_string());
+            }
+        }
+    }
+    let needs = consumer
+        .dynamic_strings(DynamicTag::NEEDED)?
+        .contains(&soname);
+    Ok((imports, needs))
+}
+
+fn print_list(title: &str, exports: &[&Export]) {
+    if exports.is_empty() {
+        return;
+    }
+    println!();
+    println!("{} ({}):", title, exports.len());
+    for export in exports {
+        let kind = if export.function { "FUNC" } else { "OBJECT" };
+        let references = if export.internal > 0 {
+            format!(
+                " ({} internal relocation{})",
+                export.internal,
+                if export.internal == 1 { "" } else { "s" }
+            )
+        } else {
+            String::new()
+        };
+        let demangled = demangle::demangle(export.name);
+        if demangled != export.name {
+            println!(
+                "  {:<6} {}{}  [{}]",
+                kind, export.name, references, demangled
+            );
+        } else {
+            println!("  {:<6} {}{}", kind, export.name, references);
+        }
+    }
+}
+
+/// Sorts the symbols a shared library exports by who refers to them: the
+/// `consumers` linked against it, only the library itself, or nothing, and
+/// suggests a version script that keeps only what the consumers use.
+pub fn print_visibility_advice(elf_file: &ElfFile, consumers: &[PathBuf]) -> Result<()> {
+    let exports = exports(elf_file)?;
+    let soname = elf_file
+        .dynamic_strings(DynamicTag::SONAME)?
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    println!("Visibility advisor:");
+    if exports.is_empty() {
+        println!("  The file exports no dynamic symbols.");
+        return Ok(());
+    }
+    let functions = exports.iter().filter(|e| e.function).count();
+    println!(
+        "  {} exported symbols, {} of them functions",
+        exports.len(),
+        functions
+    );
+
+    let mut used = HashSet::new();
+    for consumer in consumers {
+        let (imports, needs) = imports(consumer, soname)?;
+        let count = exports
+            .iter()
+            .filter(|e| imports.contains(e.name))
+            .inspect(|e| {
+                used.insert(e.name);
+            })
+            .count();
+        let linked = if needs || soname.is_empty() {
+            String::new()
+        } else {
+            format!(", but does not list {} in DT_NEEDED", soname)
+        };
+        println!(
+            "  Consumer {}: imports {} of them{}",
+            consumer.display(),
+            count,
+            linked
+        );
+    }
+
+    let (kept, candidates): (Vec<&Export>, Vec<&Export>) =
+        exports.iter().partition(|e| used.contains(e.name));
+    let (internal, unreferenced): (Vec<&Export>, Vec<&Export>) =
+        candidates.iter().partition(|e| e.internal > 0);
+    if consumers.is_empty() {
+        println!(
+            "  No consumers were given with --consumer, so which exports are used from outside is unknown."
+        );
+        print_list(
+            "Referenced by the library itself, which would bind them directly once hidden",
+            &internal,
+        );
+        print_list("Never referenced by the library", &unreferenced);
+        return Ok(());
+    }
+
+    print_list("Used by the consumers, to keep exported", &kept);
+    print_list(
+        "Candidates referenced only by the library itself, which would bind them directly once hidden",
+        &internal,
+    );
+    print_list("Candidates referenced by nothing seen here", &unreferenced);
+    if candidates
+        .iter()
+        .any(|e| e.name.starts_with("_ZTI") || e.name.starts_with("_ZTS"))
+    {
+        println!();
+        println!(
+            "  Type information (_ZTI, _ZTS) must stay exported if exceptions or dynamic_cast cross the library boundary."
+        );
+    }
+    if candidates.is_empty() {
+        println!();
+        println!("Every export is used by a consumer.");
+        return Ok(());
+    }
+
+    println!();
+    println!("Suggested version script (link with -Wl,--version-script=FILE):");
+    println!("  {{");
+    println!("    global:");
+    for export in &kept {
+        println!("      {};", export.name);
+    }
+    println!("    local:");
+    println!("      *;");
+    println!("  }};");
+    println!();
+    println!(
+        "Or build with -fvisibility=hidden and mark the API __attribute__((visibility(\"default\"))). Symbols only looked up with dlsym() are not seen by this check."
+    );
+    Ok(())
+}
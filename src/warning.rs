@@ -0,0 +1,290 @@
+use std::fmt;
+use std::mem::{offset_of, size_of};
+
+use crate::elf::{self, Elf32Header, Elf64Header, ElfIdent, ElfType, FileHeader, PN_XNUM};
+use crate::emachine::EMachine;
+use crate::section::{Elf32SectionHeader, Elf64SectionHeader};
+use crate::segment::{Elf32ProgramHeader, Elf64ProgramHeader};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The file is unusual, but everything can still be read.
+    Warning,
+    /// Part of the file cannot be read, or would be read wrongly.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "Warning"),
+            Severity::Error => write!(f, "Error"),
+        }
+    }
+}
+
+/// A problem the parser noticed but tolerated. `offset` is the file offset
+/// of the field or structure the warning is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfWarning {
+    pub severity: Severity,
+    pub offset: u64,
+    pub message: String,
+}
+
+impl ElfWarning {
+    fn new(severity: Severity, offset: usize, message: String) -> Self {
+        Self {
+            severity,
+            offset: offset as u64,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ElfWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} (at offset {:#x})",
+            self.severity, self.message, self.offset
+        )
+    }
+}
+
+/// The size of the file header of a class.
+fn size_of_header(class: u8) -> usize {
+    if class == 2 {
+        size_of::<Elf64Header>()
+    } else {
+        size_of::<Elf32Header>()
+    }
+}
+
+/// Checks the identification bytes on their own and against the rest of
+/// the header, which is where a wrong EI_CLASS or EI_DATA shows.
+fn check_ident(
+    ident: &ElfIdent,
+    header: &FileHeader,
+    bytes: &[u8],
+    warnings: &mut Vec<ElfWarning>,
+) {
+    if ident.version != 1 {
+        warnings.push(ElfWarning::new(
+            Severity::Warning,
+            offset_of!(ElfIdent, version),
+            format!("Unknown EI_VERSION {}, expected 1", ident.version),
+        ));
+    }
+    // Values from 64 up are specific to the machine.
+    if ident.os_abi < 64 && elf::os_abi_name(ident.os_abi).is_none() {
+        warnings.push(ElfWarning::new(
+            Severity::Warning,
+            offset_of!(ElfIdent, os_abi),
+            format!("Unknown EI_OSABI {}", ident.os_abi),
+        ));
+    }
+    // glibc's loader only accepts a non-zero ABI version along with the
+    // GNU OS/ABI.
+    if ident.os_abi == 0
+        && ident.abi_version != 0
+        && matches!(header.e_type, ElfType::EXEC | ElfType::DYN)
+    {
+        warnings.push(ElfWarning::new(
+            Severity::Warning,
+            offset_of!(ElfIdent, abi_version),
+            format!(
+                "EI_ABIVERSION is {} with the System V OS/ABI; glibc refuses to load such files",
+                ident.abi_version
+            ),
+        ));
+    }
+    if ident.padding.iter().any(|&b| b != 0) {
+        let bytes: Vec<String> = ident.padding.iter().map(|b| format!("{:02x}", b)).collect();
+        warnings.push(ElfWarning::new(
+            Severity::Warning,
+            offset_of!(ElfIdent, padding),
+            format!(
+                "The EI_PAD bytes are not zero ({}); packers and infectors hide markers there",
+                bytes.join(" ")
+            ),
+        ));
+    }
+
+    // A header of the other class, read where that class keeps e_ehsize,
+    // gives a wrong EI_CLASS away.
+    let (offset, size, other_class) = if ident.class == 2 {
+        (
+            offset_of!(Elf32Header, e_ehsize),
+            size_of::<Elf32Header>(),
+            "ELF32",
+        )
+    } else {
+        (
+            offset_of!(Elf64Header, e_ehsize),
+            size_of::<Elf64Header>(),
+            "ELF64",
+        )
+    };
+    let other_ehsize = bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_ne_bytes([b[0], b[1]]) as usize);
+    if header.e_ehsize as usize != size_of_header(ident.class) && other_ehsize == Some(size) {
+        warnings.push(ElfWarning::new(
+            Severity::Error,
+            offset_of!(ElfIdent, class),
+            format!(
+                "EI_CLASS disagrees with the header, which reads as an {} header",
+                other_class
+            ),
+        ));
+    }
+    // A byte order mismatch would make e_version, a one, read as 1 << 24.
+    if header.e_version == 1 << 24 {
+        warnings.push(ElfWarning::new(
+            Severity::Error,
+            offset_of!(ElfIdent, data),
+            "EI_DATA disagrees with the header, which reads correctly in the other byte order"
+                .to_string(),
+        ));
+    }
+    let machine = header.e_machine as u16;
+    if ident.class == 2 && (machine == EMachine::I386 as u16 || machine == EMachine::Arm as u16) {
+        warnings.push(ElfWarning::new(
+            Severity::Warning,
+            offset_of!(ElfIdent, class),
+            format!(
+                "EI_CLASS is ELF64, but {} only has 32-bit ELF files",
+                header.e_machine
+            ),
+        ));
+    }
+}
+
+/// Checks the identification and file header for values the parser works
+/// around: an unexpected version, structure sizes that differ from the ones
+/// the spec defines, and tables that point outside of the file.
+pub fn check_header(ident: &ElfIdent, header: &FileHeader, bytes: &[u8]) -> Vec<ElfWarning> {
+    let file_size = bytes.len() as u64;
+    let is_64 = ident.class == 2;
+    // Field offsets are the same in both classes up to e_entry.
+    let field = |offset32: usize, offset64: usize| if is_64 { offset64 } else { offset32 };
+    let mut warnings = Vec::new();
+
+    check_ident(ident, header, bytes, &mut warnings);
+    if header.e_version != 1 {
+        warnings.push(ElfWarning::new(
+            Severity::Warning,
+            offset_of!(Elf64Header, e_version),
+            format!("Unknown e_version {}, expected 1", header.e_version),
+        ));
+    }
+
+    let ehsize = field(size_of::<Elf32Header>(), size_of::<Elf64Header>());
+    if header.e_ehsize as usize != ehsize {
+        warnings.push(ElfWarning::new(
+            Severity::Warning,
+            field(
+                offset_of!(Elf32Header, e_ehsize),
+                offset_of!(Elf64Header, e_ehsize),
+            ),
+            format!("e_ehsize is {} bytes, expected {}", header.e_ehsize, ehsize),
+        ));
+    }
+
+    if header.e_shoff != 0 {
+        let shentsize = field(
+            size_of::<Elf32SectionHeader>(),
+            size_of::<Elf64SectionHeader>(),
+        );
+        if header.e_shentsize as usize != shentsize {
+            warnings.push(ElfWarning::new(
+                Severity::Error,
+                field(
+                    offset_of!(Elf32Header, e_shentsize),
+                    offset_of!(Elf64Header, e_shentsize),
+                ),
+                format!(
+                    "e_shentsize is {} bytes, expected {}; section headers will be misread",
+                    header.e_shentsize, shentsize
+                ),
+            ));
+        }
+        // With extended numbering the count is in section 0, which is
+        // checked by whoever reads it.
+        let end = header
+            .e_shoff
+            .saturating_add(header.e_shnum as u64 * header.e_shentsize.max(1) as u64);
+        if header.e_shoff >= file_size || end > file_size {
+            warnings.push(ElfWarning::new(
+                Severity::Error,
+                field(
+                    offset_of!(Elf32Header, e_shoff),
+                    offset_of!(Elf64Header, e_shoff),
+                ),
+                format!(
+                    "The section header table at {:#x} extends past the end of the file",
+                    header.e_shoff
+                ),
+            ));
+        }
+    } else if header.e_type != ElfType::CORE {
+        // Core files never have sections; anything else has lost them to
+        // stripping or packing.
+        warnings.push(ElfWarning::new(
+            Severity::Warning,
+            field(
+                offset_of!(Elf32Header, e_shoff),
+                offset_of!(Elf64Header, e_shoff),
+            ),
+            "There are no section headers, so section names, the static symbol table and \
+             debug info are unavailable; only what the program headers and the dynamic \
+             segment describe can be shown"
+                .to_string(),
+        ));
+    }
+
+    if header.e_phoff != 0 {
+        let phentsize = field(
+            size_of::<Elf32ProgramHeader>(),
+            size_of::<Elf64ProgramHeader>(),
+        );
+        if header.e_phentsize as usize != phentsize {
+            warnings.push(ElfWarning::new(
+                Severity::Error,
+                field(
+                    offset_of!(Elf32Header, e_phentsize),
+                    offset_of!(Elf64Header, e_phentsize),
+                ),
+                format!(
+                    "e_phentsize is {} bytes, expected {}; program headers will be misread",
+                    header.e_phentsize, phentsize
+                ),
+            ));
+        }
+        // As with sections, an extended count lives in section 0.
+        let phnum = if header.e_phnum == PN_XNUM {
+            0
+        } else {
+            header.e_phnum
+        };
+        let end = header
+            .e_phoff
+            .saturating_add(phnum as u64 * header.e_phentsize.max(1) as u64);
+        if header.e_phoff >= file_size || end > file_size {
+            warnings.push(ElfWarning::new(
+                Severity::Error,
+                field(
+                    offset_of!(Elf32Header, e_phoff),
+                    offset_of!(Elf64Header, e_phoff),
+                ),
+                format!(
+                    "The program header table at {:#x} extends past the end of the file",
+                    header.e_phoff
+                ),
+            ));
+        }
+    }
+
+    warnings
+}
@@ -0,0 +1,433 @@
+//! A first look at WebAssembly modules and the relocatable objects wasm-ld
+//! links: the section inventory, imports and exports, and the symbols,
+//! segments and relocation counts of the "linking" and "reloc.*" custom
+//! sections. Code and data are not decoded.
+
+use anyhow::{Context, Result, bail};
+
+/// The names of the known section ids.
+const SECTIONS: &[&str] = &[
+    "custom",
+    "type",
+    "import",
+    "function",
+    "table",
+    "memory",
+    "global",
+    "export",
+    "start",
+    "element",
+    "code",
+    "data",
+    "datacount",
+    "tag",
+];
+
+/// The kinds of imports, exports and linking symbols share these names,
+/// except that symbols put data and sections where imports have tables
+/// and memories.
+const EXTERNAL_KINDS: &[&str] = &["function", "table", "memory", "global", "tag"];
+const SYMBOL_KINDS: &[&str] = &["function", "data", "global", "section", "tag", "table"];
+
+const WASM_SYMBOL_BINDING_WEAK: u32 = 0x1;
+const WASM_SYMBOL_BINDING_LOCAL: u32 = 0x2;
+const WASM_SYMBOL_VISIBILITY_HIDDEN: u32 = 0x4;
+const WASM_SYMBOL_UNDEFINED: u32 = 0x10;
+const WASM_SYMBOL_EXPORTED: u32 = 0x20;
+const WASM_SYMBOL_EXPLICIT_NAME: u32 = 0x40;
+const WASM_SYMBOL_NO_STRIP: u32 = 0x80;
+const WASM_SYMBOL_TLS: u32 = 0x100;
+
+/// The subsections of the "linking" section.
+const WASM_SEGMENT_INFO: u8 = 5;
+const WASM_INIT_FUNCS: u8 = 6;
+const WASM_COMDAT_INFO: u8 = 7;
+const WASM_SYMBOL_TABLE: u8 = 8;
+
+/// Reads the LEB128 numbers, names and bytes sections are made of.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .context("Unexpected end of section")?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn uleb(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                value |= ((byte & 0x7f) as u64) << shift;
+            }
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(self.uleb()? as u32)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + len)
+            .context("Unexpected end of section")?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    /// Table and memory limits: a flags byte, the minimum and, if the flags
+    /// say so, the maximum.
+    fn limits(&mut self) -> Result<String> {
+        let flags = self.u8()?;
+        let min = self.uleb()?;
+        Ok(if flags & 1 != 0 {
+            format!("{}..{}", min, self.uleb()?)
+        } else {
+            format!("{}..", min)
+        })
+    }
+}
+
+fn kind_name(kinds: &[&'static str], kind: u8) -> &'static str {
+    kinds.get(kind as usize).copied().unwrap_or("unknown")
+}
+
+fn symbol_flags(flags: u32) -> String {
+    let mut names = Vec::new();
+    for (bit, name) in [
+        (WASM_SYMBOL_BINDING_WEAK, "WEAK"),
+        (WASM_SYMBOL_BINDING_LOCAL, "LOCAL"),
+        (WASM_SYMBOL_VISIBILITY_HIDDEN, "HIDDEN"),
+        (WASM_SYMBOL_UNDEFINED, "UNDEFINED"),
+        (WASM_SYMBOL_EXPORTED, "EXPORTED"),
+        (WASM_SYMBOL_EXPLICIT_NAME, "EXPLICIT_NAME"),
+        (WASM_SYMBOL_NO_STRIP, "NO_STRIP"),
+        (WASM_SYMBOL_TLS, "TLS"),
+    ] {
+        if flags & bit != 0 {
+            names.push(name);
+        }
+    }
+    if names.is_empty() {
+        "GLOBAL".to_string()
+    } else {
+        names.join(" ")
+    }
+}
+
+/// A section of the module, with its custom name if it has one.
+struct Section<'a> {
+    id: u8,
+    name: Option<String>,
+    offset: usize,
+    size: usize,
+    contents: &'a [u8],
+}
+
+impl Section<'_> {
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => format!("custom \"{}\"", name),
+            None => kind_name(SECTIONS, self.id).to_string(),
+        }
+    }
+}
+
+/// Prints the imports, returning the kind and `module.name` of each.
+fn print_imports(contents: &[u8]) -> Result<Vec<(u8, String)>> {
+    let mut reader = Reader::new(contents);
+    let count = reader.u32()?;
+    let mut imports = Vec::new();
+    println!();
+    println!("Imports ({}):", count);
+    for _ in 0..count {
+        let module = reader.name()?;
+        let name = reader.name()?;
+        let kind = reader.u8()?;
+        let detail = match kind {
+            0 => format!("type {}", reader.u32()?),
+            1 => {
+                reader.u8()?;
+                format!("limits {}", reader.limits()?)
+            }
+            2 => format!("pages {}", reader.limits()?),
+            3 => {
+                let value_type = reader.u8()?;
+                let mutable = reader.u8()? != 0;
+                format!(
+                    "type {:#x}{}",
+                    value_type,
+                    if mutable { ", mutable" } else { "" }
+                )
+            }
+            4 => {
+                reader.u8()?;
+                format!("type {}", reader.u32()?)
+            }
+            _ => bail!("Unknown import kind {}", kind),
+        };
+        println!(
+            "  {:<8} {}.{} ({})",
+            kind_name(EXTERNAL_KINDS, kind),
+            module,
+            name,
+            detail
+        );
+        imports.push((kind, format!("{}.{}", module, name)));
+    }
+    Ok(imports)
+}
+
+fn print_exports(contents: &[u8]) -> Result<()> {
+    let mut reader = Reader::new(contents);
+    let count = reader.u32()?;
+    println!();
+    println!("Exports ({}):", count);
+    for _ in 0..count {
+        let name = reader.name()?;
+        let kind = reader.u8()?;
+        let index = reader.u32()?;
+        println!(
+            "  {:<8} {} (index {})",
+            kind_name(EXTERNAL_KINDS, kind),
+            name,
+            index
+        );
+    }
+    Ok(())
+}
+
+fn print_linking(contents: &[u8], imports: &[(u8, String)]) -> Result<()> {
+    let mut reader = Reader::new(contents);
+    let version = reader.u32()?;
+    println!();
+    println!("Linking metadata (version {}):", version);
+    while !reader.at_end() {
+        let kind = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let mut sub = Reader::new(reader.bytes(size)?);
+        match kind {
+            WASM_SEGMENT_INFO => {
+                let count = sub.u32()?;
+                println!("  Data segments ({}):", count);
+                for index in 0..count {
+                    let name = sub.name()?;
+                    let alignment = sub.u32()?;
+                    let flags = sub.u32()?;
+                    println!(
+                        "    [{:>2}] {:<24} align 2^{} flags {:#x}",
+                        index, name, alignment, flags
+                    );
+                }
+            }
+            WASM_INIT_FUNCS => {
+                let count = sub.u32()?;
+                println!("  Init functions ({}):", count);
+                for _ in 0..count {
+                    let priority = sub.u32()?;
+                    let symbol = sub.u32()?;
+                    println!("    symbol {} priority {}", symbol, priority);
+                }
+            }
+            WASM_COMDAT_INFO => {
+                let count = sub.u32()?;
+                println!("  Comdats ({}):", count);
+                for _ in 0..count {
+                    let name = sub.name()?;
+                    sub.u32()?;
+                    let members = sub.u32()?;
+                    for _ in 0..members {
+                        sub.u8()?;
+                        sub.u32()?;
+                    }
+                    println!("    {} ({} members)", name, members);
+                }
+            }
+            WASM_SYMBOL_TABLE => print_symbols(&mut sub, imports)?,
+            _ => println!("  Subsection {} ({:#x} bytes)", kind, size),
+        }
+    }
+    Ok(())
+}
+
+/// Prints the symbol table, naming undefined symbols after the imports
+/// they stand for.
+fn print_symbols(reader: &mut Reader, imports: &[(u8, String)]) -> Result<()> {
+    let count = reader.u32()?;
+    println!("  Symbols ({}):", count);
+    println!("    [Nr] {:<8} {:<28} Name", "Kind", "Flags");
+    for index in 0..count {
+        let kind = reader.u8()?;
+        let flags = reader.u32()?;
+        let defined = flags & WASM_SYMBOL_UNDEFINED == 0;
+        let name = match kind {
+            // Functions, globals, tags and tables: an index into their own
+            // space, and no name when an undefined one takes its import's.
+            0 | 2 | 4 | 5 => {
+                let target = reader.u32()?;
+                if defined || flags & WASM_SYMBOL_EXPLICIT_NAME != 0 {
+                    format!("{} (index {})", reader.name()?, target)
+                } else {
+                    // Symbols and imports number the same things
+                    // differently.
+                    let import_kind = match kind {
+                        0 => 0,
+                        2 => 3,
+                        4 => 4,
+                        _ => 1,
+                    };
+                    imports
+                        .iter()
+                        .filter(|(k, _)| *k == import_kind)
+                        .nth(target as usize)
+                        .map_or_else(
+                            || format!("(import {})", target),
+                            |(_, name)| format!("{} (import {})", name, target),
+                        )
+                }
+            }
+            1 => {
+                let name = reader.name()?;
+                if defined {
+                    let segment = reader.u32()?;
+                    let offset = reader.uleb()?;
+                    let size = reader.uleb()?;
+                    format!(
+                        "{} (segment {} +{:#x}, {:#x} bytes)",
+                        name, segment, offset, size
+                    )
+                } else {
+                    name
+                }
+            }
+            3 => format!("(section {})", reader.u32()?),
+            _ => bail!("Unknown symbol kind {}", kind),
+        };
+        println!(
+            "    [{:>2}] {:<8} {:<28} {}",
+            index,
+            kind_name(SYMBOL_KINDS, kind),
+            symbol_flags(flags),
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Prints the sections of a WebAssembly module or object and what its
+/// imports, exports and linking metadata say.
+pub fn print_wasm(data: &[u8]) -> Result<()> {
+    if !data.starts_with(b"\0asm") || data.len() < 8 {
+        bail!("Not a WebAssembly module");
+    }
+    let version = u32::from_le_bytes(data[4..8].try_into()?);
+    let mut reader = Reader::new(data);
+    reader.position = 8;
+    let mut sections = Vec::new();
+    while !reader.at_end() {
+        let id = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let offset = reader.position;
+        let mut contents = reader.bytes(size)?;
+        let mut name = None;
+        if id == 0 {
+            let mut custom = Reader::new(contents);
+            name = Some(custom.name()?);
+            contents = &contents[custom.position..];
+        }
+        sections.push(Section {
+            id,
+            name,
+            offset,
+            size,
+            contents,
+        });
+    }
+
+    let object = sections
+        .iter()
+        .any(|s| s.name.as_deref() == Some("linking"));
+    println!("WebAssembly header:");
+    println!("  Version:                           {}", version);
+    println!(
+        "  Kind:                              {}",
+        if object {
+            "relocatable object (it has a linking section)"
+        } else {
+            "module"
+        }
+    );
+
+    println!();
+    println!("Sections:");
+    println!(
+        "  [Nr] {:<24} {:>10} {:>10} Contents",
+        "Type", "Offset", "Size"
+    );
+    for (index, section) in sections.iter().enumerate() {
+        let mut reader = Reader::new(section.contents);
+        let contents = match (section.id, section.name.as_deref()) {
+            (8, _) => format!("function {}", reader.u32()?),
+            (12, _) => format!("{} data segments", reader.u32()?),
+            (0, Some(name)) if name.starts_with("reloc.") => {
+                let target = reader.u32()? as usize;
+                let count = reader.u32()?;
+                format!(
+                    "{} relocations against {}",
+                    count,
+                    sections
+                        .get(target)
+                        .map_or_else(|| format!("section {}", target), |s| s.label())
+                )
+            }
+            (0, _) => String::new(),
+            _ => format!("{} entries", reader.u32()?),
+        };
+        let line = format!(
+            "  [{:>2}] {:<24} {:>#10x} {:>#10x} {}",
+            index,
+            section.label(),
+            section.offset,
+            section.size,
+            contents
+        );
+        println!("{}", line.trim_end());
+    }
+
+    let mut imports = Vec::new();
+    for section in &sections {
+        match (section.id, section.name.as_deref()) {
+            (2, _) => imports = print_imports(section.contents)?,
+            (7, _) => print_exports(section.contents)?,
+            (0, Some("linking")) => print_linking(section.contents, &imports)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What identifies one version of a file: a rebuild either rewrites it in
+/// place or replaces it with a new inode.
+#[derive(Debug, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    #[cfg(unix)]
+    inode: u64,
+}
+
+fn stamp(path: &str) -> Option<Stamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(Stamp {
+        modified: metadata.modified().ok(),
+        len: metadata.len(),
+        #[cfg(unix)]
+        inode: std::os::unix::fs::MetadataExt::ino(&metadata),
+    })
+}
+
+/// Runs `report` once, then again every time one of `paths` changes on
+/// disk, until the process is interrupted. Files are polled rather than
+/// watched with inotify so this works the same on every platform, and a
+/// change is only acted on once the file has stopped changing, so a linker
+/// still writing its output is not caught halfway through.
+pub fn watch(paths: &[&String], mut report: impl FnMut()) -> ! {
+    let clear = std::io::stdout().is_terminal();
+    let mut stamps: Vec<Option<Stamp>> = paths.iter().map(|p| stamp(p)).collect();
+
+    loop {
+        if clear {
+            print!("\x1b[2J\x1b[H");
+        }
+        report();
+        println!();
+        println!(
+            "Watching {} file(s) for changes, press Ctrl-C to stop.",
+            paths.len()
+        );
+        let _ = std::io::stdout().flush();
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let current: Vec<Option<Stamp>> = paths.iter().map(|p| stamp(p)).collect();
+            if current == stamps {
+                continue;
+            }
+
+            // Wait for the file to settle before reading it.
+            let mut settled = current;
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                let next: Vec<Option<Stamp>> = paths.iter().map(|p| stamp(p)).collect();
+                if next == settled {
+                    break;
+                }
+                settled = next;
+            }
+            stamps = settled;
+            break;
+        }
+    }
+}
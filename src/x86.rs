@@ -0,0 +1,69 @@
+use anyhow::Result;
+
+use crate::elf::ElfFile;
+use crate::emachine::EMachine;
+use crate::notes::{self, X86_FEATURE_2, X86_ISA};
+
+/// The highest microarchitecture level set in an ISA property.
+fn highest_level(bits: u32) -> Option<&'static str> {
+    (0..X86_ISA.len())
+        .rev()
+        .find(|&bit| bits & (1 << bit) != 0)
+        .map(|bit| X86_ISA[bit])
+}
+
+/// Reports which x86-64 microarchitecture level the file needs, from the
+/// ISA and feature properties the linker merges into the GNU property
+/// note. The needed level is what the loader checks; the used level also
+/// counts code that is only run after checking the CPU at runtime, so it
+/// can be higher without the file needing it.
+pub fn print_isa_level(elf_file: &ElfFile) -> Result<()> {
+    println!("x86 ISA level:");
+    if !matches!(elf_file.machine(), EMachine::X8664 | EMachine::I386) {
+        println!("  Not an x86 file ({}).", elf_file.machine());
+        return Ok(());
+    }
+
+    let property = |pr_type| notes::gnu_property(elf_file, pr_type);
+    let needed = property(notes::GNU_PROPERTY_X86_ISA_1_NEEDED)?;
+    let used = property(notes::GNU_PROPERTY_X86_ISA_1_USED)?;
+    let rows = [
+        ("ISA needed:", needed, X86_ISA),
+        ("ISA used:", used, X86_ISA),
+        (
+            "Features needed:",
+            property(notes::GNU_PROPERTY_X86_FEATURE_2_NEEDED)?,
+            X86_FEATURE_2,
+        ),
+        (
+            "Features used:",
+            property(notes::GNU_PROPERTY_X86_FEATURE_2_USED)?,
+            X86_FEATURE_2,
+        ),
+    ];
+    for (label, value, names) in rows {
+        match value {
+            Some(bits) => println!("  {:<17} {}", label, notes::bit_names(bits, names)),
+            None => println!("  {:<17} not recorded", label),
+        }
+    }
+
+    match needed.and_then(highest_level) {
+        Some(level) => println!("  {:<17} {}", "Minimum level:", level),
+        None => println!(
+            "  {:<17} unknown (no ISA needed property; link with -z x86-64-vN or assemble with -mneeded to record one)",
+            "Minimum level:"
+        ),
+    }
+    if let (Some(needed), Some(used)) = (needed, used)
+        && used > needed
+        && highest_level(used) != highest_level(needed)
+    {
+        println!(
+            "  Code for {} is present, presumably selected at runtime.",
+            highest_level(used).unwrap_or("?")
+        );
+    }
+
+    Ok(())
+}
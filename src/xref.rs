@@ -0,0 +1,116 @@
+use anyhow::Result;
+
+use crate::elf::{ElfFile, ElfType};
+use crate::filter::SectionFilter;
+use crate::relocation;
+use crate::section::{SHN_UNDEF, SectionHeader};
+use crate::strtab::SymbolTable;
+use crate::symbol::SymbolType;
+
+/// A function symbol: (section index, start, size, name).
+type Function = (u16, u64, u64, String);
+
+fn functions(elf_file: &ElfFile, sections: &[SectionHeader]) -> Result<Vec<Function>> {
+    let mut functions = Vec::new();
+    if let Some(symtab) = elf_file.symbol_table(sections) {
+        let symbols = SymbolTable::new(elf_file, sections, symtab)?;
+        for (index, symbol) in symbols.iter().enumerate() {
+            if symbol.symbol_type() != SymbolType::FUNC || symbol.st_shndx == SHN_UNDEF {
+                continue;
+            }
+            let name = symbols.name(index)?;
+            functions.push((
+                symbol.st_shndx,
+                symbol.st_value,
+                symbol.st_size,
+                name.to_string(),
+            ));
+        }
+    }
+    Ok(functions)
+}
+
+/// Lists every relocation that refers to `name`, with where it applies and
+/// which function the patched location belongs to. The filter selects the
+/// relocation sections to look at.
+pub fn print_xref(elf_file: &ElfFile, name: &str, filter: &SectionFilter) -> Result<()> {
+    let sections = elf_file.section_headers()?;
+    let functions = functions(elf_file, &sections)?;
+    // In relocatable objects offsets and symbol values are relative to a
+    // section; everywhere else they are virtual addresses.
+    let relocatable = elf_file.elf_type() == ElfType::REL;
+    let section_name = |s: &SectionHeader| elf_file.section_name(&sections, s).unwrap_or("");
+
+    println!("References to '{}':", name);
+    println!(
+        "  {:<18} {:<24} {:<18} {:<28} Function",
+        "Section", "Type", "Offset", "Location"
+    );
+
+    let mut count = 0;
+    for (index, section) in sections.iter().enumerate() {
+        if !ElfFile::is_relocation_section(section) || !filter.selects(elf_file, &sections, index) {
+            continue;
+        }
+        let symbols = SymbolTable::linked(elf_file, &sections, section)?;
+
+        for reloc in elf_file.decoded_relocations(section)? {
+            if reloc.r_sym == 0 || symbols.get(reloc.r_sym as usize).is_none() {
+                continue;
+            }
+            let symbol_name = symbols.name(reloc.r_sym as usize)?;
+            if symbol_name != name && symbol_name.split('@').next() != Some(name) {
+                continue;
+            }
+            count += 1;
+
+            let (target, offset) = if relocatable {
+                let target = sections
+                    .get(section.sh_info as usize)
+                    .map(|s| (section.sh_info as usize, s));
+                (target, reloc.r_offset)
+            } else {
+                let target = sections.iter().enumerate().find(|(_, s)| {
+                    s.is_alloc()
+                        && reloc.r_offset >= s.sh_addr
+                        && reloc.r_offset - s.sh_addr < s.sh_size
+                });
+                let offset = target.map_or(reloc.r_offset, |(_, s)| reloc.r_offset - s.sh_addr);
+                (target, offset)
+            };
+
+            let location = match target {
+                Some((_, s)) => format!("{}+{:#x}", section_name(s), offset),
+                None => "-".to_string(),
+            };
+            let function = functions
+                .iter()
+                .find(|(shndx, start, size, _)| {
+                    let in_section =
+                        !relocatable || target.is_some_and(|(index, _)| *shndx as usize == index);
+                    in_section
+                        && reloc.r_offset >= *start
+                        && reloc.r_offset - start < (*size).max(1)
+                })
+                .map_or_else(
+                    || "-".to_string(),
+                    |(_, start, _, name)| match reloc.r_offset - start {
+                        0 => name.clone(),
+                        delta => format!("{}+{:#x}", name, delta),
+                    },
+                );
+
+            println!(
+                "  {:<18} {:<24} {:#018x} {:<28} {}",
+                section_name(section),
+                relocation::type_display(elf_file, reloc.r_type),
+                reloc.r_offset,
+                location,
+                function
+            );
+        }
+    }
+
+    println!("{} reference(s)", count);
+    Ok(())
+}